@@ -0,0 +1,256 @@
+//! Cost reports from the Anthropic Admin API, for traffic that goes to
+//! Anthropic directly instead of through Bedrock.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{Amount, CostRow, Metric, DEFAULT_TENANT};
+use serde::Deserialize;
+
+/// The `source` of the rows in the cost table.
+pub const SOURCE: &str = "anthropic";
+
+const API_VERSION: &str = "2023-06-01";
+
+/// Buckets per page; the API's maximum for daily buckets.
+const PAGE_DAYS: &str = "31";
+
+/// The `[anthropic]` config section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AnthropicConfig {
+    /// An Admin API key (`sk-ant-admin...`)
+    pub admin_key: String,
+    #[serde(default = "default_base_url")]
+    pub base_url: String,
+    /// Cost table user id per workspace id. Other workspaces are stored
+    /// under their id, and the default workspace as `default`.
+    #[serde(default)]
+    pub workspaces: HashMap<String, String>,
+}
+
+fn default_base_url() -> String {
+    "https://api.anthropic.com".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct CostReport {
+    data: Vec<Bucket>,
+    #[serde(default)]
+    has_more: bool,
+    next_page: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Bucket {
+    /// RFC 3339 start of the UTC day.
+    starting_at: String,
+    results: Vec<CostResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CostResult {
+    /// In the currency's smallest unit, e.g. cents, as a decimal string.
+    amount: String,
+    currency: String,
+    workspace_id: Option<String>,
+    model: Option<String>,
+    cost_type: Option<String>,
+}
+
+pub struct Client {
+    http: reqwest::Client,
+    config: AnthropicConfig,
+}
+
+impl Client {
+    pub fn new(config: AnthropicConfig) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    async fn page(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        page: Option<&str>,
+    ) -> Result<CostReport> {
+        let url = format!(
+            "{}/v1/organizations/cost_report",
+            self.config.base_url.trim_end_matches('/')
+        );
+        let mut query = vec![
+            ("starting_at", format!("{start}T00:00:00Z")),
+            ("ending_at", format!("{end}T00:00:00Z")),
+            ("bucket_width", "1d".to_string()),
+            ("group_by[]", "workspace_id".to_string()),
+            ("group_by[]", "description".to_string()),
+            ("limit", PAGE_DAYS.to_string()),
+        ];
+        if let Some(page) = page {
+            query.push(("page", page.to_string()));
+        }
+        let report = self
+            .http
+            .get(url)
+            .header("x-api-key", &self.config.admin_key)
+            .header("anthropic-version", API_VERSION)
+            .query(&query)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(report)
+    }
+
+    /// Daily cost per workspace and model in `[start, end)`, under every
+    /// metric since Anthropic bills a single figure, as rows of `tenant`.
+    pub async fn fetch_cost_rows(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        tenant: &str,
+    ) -> Result<Vec<CostRow>> {
+        let mut buckets = Vec::new();
+        let mut page = None;
+        loop {
+            let report = self
+                .page(start, end, page.as_deref())
+                .await
+                .context("Failed to fetch the Anthropic cost report")?;
+            buckets.extend(report.data);
+            match report.next_page {
+                Some(next) if report.has_more => page = Some(next),
+                _ => break,
+            }
+        }
+        cost_rows(&buckets, &self.config.workspaces, tenant)
+    }
+
+    /// Fetches yesterday's report to check the key.
+    pub async fn check(&self) -> Result<String> {
+        let today = Utc::now().date_naive();
+        let rows = self
+            .fetch_cost_rows(today - chrono::Duration::days(1), today, DEFAULT_TENANT)
+            .await?;
+        Ok(format!("{} rows yesterday", rows.len() / Metric::ALL.len()))
+    }
+}
+
+/// Sums the report by day, user and model, in that order; token types and
+/// context windows of the same model are separate results.
+fn cost_rows(
+    buckets: &[Bucket],
+    workspaces: &HashMap<String, String>,
+    tenant: &str,
+) -> Result<Vec<CostRow>> {
+    let mut totals: BTreeMap<(NaiveDate, String, String, String), Amount> = BTreeMap::new();
+    for bucket in buckets {
+        let date = DateTime::parse_from_rfc3339(&bucket.starting_at)
+            .with_context(|| format!("invalid bucket start {:?}", bucket.starting_at))?
+            .with_timezone(&Utc)
+            .date_naive();
+        for result in &bucket.results {
+            let user_id = match &result.workspace_id {
+                Some(id) => workspaces.get(id).unwrap_or(id).clone(),
+                None => "default".to_string(),
+            };
+            let model_id = result
+                .model
+                .as_deref()
+                .or(result.cost_type.as_deref())
+                .unwrap_or("other")
+                .to_string();
+            let key = (
+                date,
+                user_id,
+                model_id,
+                result.currency.to_ascii_uppercase(),
+            );
+            *totals.entry(key).or_default() += minor_units(&result.amount)?;
+        }
+    }
+    let rows = totals
+        .into_iter()
+        .flat_map(|((date, user_id, model_id, currency), amount)| {
+            Metric::ALL.into_iter().map(move |metric| CostRow {
+                date,
+                user_id: user_id.clone(),
+                model_id: model_id.clone(),
+                amount,
+                currency: currency.clone(),
+                metric,
+                source: SOURCE.to_string(),
+                tenant: tenant.to_string(),
+            })
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// An amount given in hundredths of the currency unit.
+fn minor_units(amount: &str) -> Result<Amount> {
+    let cents: Amount = amount.parse()?;
+    let micros = cents.micros();
+    Ok(Amount::from_micros((micros + micros.signum() * 50) / 100))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_is_summed_by_day_workspace_and_model() {
+        let report: CostReport = serde_json::from_str(
+            r#"{
+                "data": [{
+                    "starting_at": "2025-03-01T00:00:00Z",
+                    "ending_at": "2025-03-02T00:00:00Z",
+                    "results": [
+                        {"currency": "USD", "amount": "123.45", "workspace_id": "wrkspc_1",
+                         "description": "Claude Sonnet 4 Usage - Input Tokens",
+                         "cost_type": "tokens", "model": "claude-sonnet-4-20250514",
+                         "token_type": "uncached_input_tokens"},
+                        {"currency": "USD", "amount": "100", "workspace_id": "wrkspc_1",
+                         "description": "Claude Sonnet 4 Usage - Output Tokens",
+                         "cost_type": "tokens", "model": "claude-sonnet-4-20250514",
+                         "token_type": "output_tokens"},
+                        {"currency": "USD", "amount": "5", "workspace_id": null,
+                         "description": "Web Search Usage", "cost_type": "web_search",
+                         "model": null}
+                    ]
+                }],
+                "has_more": false,
+                "next_page": null
+            }"#,
+        )
+        .unwrap();
+        let workspaces = HashMap::from([("wrkspc_1".to_string(), "research".to_string())]);
+        let rows = cost_rows(&report.data, &workspaces, DEFAULT_TENANT).unwrap();
+        assert_eq!(rows.len(), 2 * Metric::ALL.len());
+        let first = &rows[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+        assert_eq!(first.user_id, "default");
+        assert_eq!(first.model_id, "web_search");
+        assert_eq!(first.amount, Amount::from_micros(50_000));
+        let sonnet = &rows[Metric::ALL.len()];
+        assert_eq!(sonnet.user_id, "research");
+        assert_eq!(sonnet.model_id, "claude-sonnet-4-20250514");
+        assert_eq!(sonnet.amount, Amount::from_micros(2_234_500));
+        assert_eq!(sonnet.currency, "USD");
+    }
+
+    #[test]
+    fn amounts_are_in_cents() {
+        assert_eq!(minor_units("1").unwrap(), Amount::from_micros(10_000));
+        assert_eq!(minor_units("0.000149").unwrap(), Amount::from_micros(1));
+        assert_eq!(
+            minor_units("-250").unwrap(),
+            Amount::from_micros(-2_500_000)
+        );
+        assert!(minor_units("ten").is_err());
+    }
+}
@@ -0,0 +1,123 @@
+//! Ingestion metrics published to CloudWatch after each batch run, so AWS
+//! alarms and dashboards can watch the batch without reading its logs.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use aws_sdk_cloudwatch::types::{Dimension, MetricDatum, StandardUnit};
+use common::Amount;
+use serde::Deserialize;
+
+/// The `[cloudwatch]` config section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CloudWatchConfig {
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Added to every metric, e.g. `{ Environment = "prod" }`.
+    #[serde(default)]
+    pub dimensions: BTreeMap<String, String>,
+}
+
+fn default_namespace() -> String {
+    "LlmProxyCost".to_string()
+}
+
+/// What one batch run reports.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunMetrics {
+    /// CE rows stored, including those of chunks stored before a failure.
+    pub rows_ingested: usize,
+    /// Yesterday's total cost once the run stored its rows in the cost table.
+    pub daily_cost: Option<Amount>,
+    pub duration: Duration,
+    pub failed: bool,
+}
+
+impl RunMetrics {
+    /// Name, value and unit of each metric.
+    fn values(&self) -> Vec<(&'static str, f64, StandardUnit)> {
+        let mut values = vec![
+            (
+                "RowsIngested",
+                self.rows_ingested as f64,
+                StandardUnit::Count,
+            ),
+            (
+                "RunDuration",
+                self.duration.as_secs_f64(),
+                StandardUnit::Seconds,
+            ),
+            (
+                "Errors",
+                f64::from(u8::from(self.failed)),
+                StandardUnit::Count,
+            ),
+        ];
+        if let Some(cost) = self.daily_cost {
+            values.push(("DailyCost", cost.to_f64(), StandardUnit::None));
+        }
+        values
+    }
+}
+
+/// Publishes `run` under the configured namespace and dimensions.
+pub async fn publish(cfg: &CloudWatchConfig, run: &RunMetrics) -> Result<()> {
+    let dimensions: Vec<Dimension> = cfg
+        .dimensions
+        .iter()
+        .map(|(name, value)| Dimension::builder().name(name).value(value).build())
+        .collect();
+    let data = run
+        .values()
+        .into_iter()
+        .map(|(name, value, unit)| {
+            MetricDatum::builder()
+                .metric_name(name)
+                .value(value)
+                .unit(unit)
+                .set_dimensions(Some(dimensions.clone()))
+                .build()
+        })
+        .collect();
+
+    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+    aws_sdk_cloudwatch::Client::new(&config)
+        .put_metric_data()
+        .namespace(&cfg.namespace)
+        .set_metric_data(Some(data))
+        .send()
+        .await
+        .context("CloudWatch rejected the batch metrics")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_run_reports_an_error_and_no_cost() {
+        let mut run = RunMetrics {
+            rows_ingested: 120,
+            daily_cost: Some(Amount::from_f64(42.5)),
+            duration: Duration::from_millis(90_500),
+            failed: false,
+        };
+        assert_eq!(
+            run.values(),
+            vec![
+                ("RowsIngested", 120.0, StandardUnit::Count),
+                ("RunDuration", 90.5, StandardUnit::Seconds),
+                ("Errors", 0.0, StandardUnit::Count),
+                ("DailyCost", 42.5, StandardUnit::None),
+            ]
+        );
+
+        run.daily_cost = None;
+        run.failed = true;
+        let values = run.values();
+        assert_eq!(values.len(), 3);
+        assert_eq!(values[2], ("Errors", 1.0, StandardUnit::Count));
+    }
+}
@@ -0,0 +1,223 @@
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono_tz::Tz;
+use tokio::sync::Mutex;
+use tokio_cron_scheduler::{Job, JobScheduler};
+
+/// How a failed scheduled run is retried.
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    /// Delay before the first retry; doubled for each further attempt.
+    pub retry_delay: Duration,
+    /// Upper bound of the random delay added before each run and retry.
+    pub max_jitter: Duration,
+}
+
+impl RetryPolicy {
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.retry_delay * 2u32.saturating_pow(attempt)
+    }
+}
+
+/// How often the daemon checks for runs requested from the server.
+const REQUEST_POLL: Duration = Duration::from_secs(30);
+
+/// The scheduler requires a seconds field; standard five-field expressions
+/// get one prepended so they fire at second zero.
+pub fn normalize_schedule(expr: &str) -> String {
+    let expr = expr.trim();
+    if expr.split_whitespace().count() == 5 {
+        format!("0 {}", expr)
+    } else {
+        expr.to_string()
+    }
+}
+
+fn jitter(max: Duration) -> Duration {
+    let max_ms = max.as_millis() as u64;
+    if max_ms == 0 {
+        return Duration::ZERO;
+    }
+    Duration::from_millis(rand::random_range(0..=max_ms))
+}
+
+async fn run_with_retries<F, Fut>(policy: &RetryPolicy, run: F) -> Result<()>
+where
+    F: Fn() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    let mut attempt = 0;
+    loop {
+        match run().await {
+            Ok(()) => return Ok(()),
+            Err(e) if attempt < policy.max_retries => {
+                let delay = policy.backoff(attempt) + jitter(policy.max_jitter);
+                attempt += 1;
+                log::warn!(
+                    "Batch run failed (attempt {}/{}): {:#}; retrying in {}s",
+                    attempt,
+                    policy.max_retries + 1,
+                    e,
+                    delay.as_secs()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Runs `run_batch` on `schedule` in `timezone` until SIGINT/SIGTERM. A tick
+/// that fires while the previous run is still going is skipped; shutdown
+/// waits for an in-flight run to finish.
+///
+/// Between ticks, `run_requested` is polled every [`REQUEST_POLL`] to serve
+/// runs requested from the server. It shares the lock of scheduled runs, so
+/// a request waits for the next poll while a run is going.
+pub async fn run<F, Fut, R, RFut>(
+    schedule: &str,
+    timezone: Tz,
+    policy: RetryPolicy,
+    run_batch: F,
+    run_requested: R,
+) -> Result<()>
+where
+    F: Fn() -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Result<()>> + Send + 'static,
+    R: Fn() -> RFut + Send + Sync + 'static,
+    RFut: Future<Output = ()> + Send + 'static,
+{
+    let schedule = normalize_schedule(schedule);
+    let running = Arc::new(Mutex::new(()));
+    let policy = Arc::new(policy);
+    let run_batch = Arc::new(run_batch);
+
+    let mut scheduler = JobScheduler::new().await?;
+    let job_running = running.clone();
+    let job = Job::new_async_tz(schedule.as_str(), timezone, move |_id, _scheduler| {
+        let running = job_running.clone();
+        let policy = policy.clone();
+        let run_batch = run_batch.clone();
+        Box::pin(async move {
+            let Ok(_guard) = running.try_lock() else {
+                log::warn!("Previous batch run is still in progress, skipping this run");
+                return;
+            };
+            tokio::time::sleep(jitter(policy.max_jitter)).await;
+            if let Err(e) = run_with_retries(&policy, || run_batch()).await {
+                log::error!("Batch run failed after {} retries: {:#}", policy.max_retries, e);
+            }
+        })
+    })?;
+    scheduler.add(job).await?;
+    let poll_running = running.clone();
+    let run_requested = Arc::new(run_requested);
+    let poll = Job::new_repeated_async(REQUEST_POLL, move |_id, _scheduler| {
+        let running = poll_running.clone();
+        let run_requested = run_requested.clone();
+        Box::pin(async move {
+            let Ok(_guard) = running.try_lock() else {
+                return;
+            };
+            run_requested().await;
+        })
+    })?;
+    scheduler.add(poll).await?;
+    scheduler.start().await?;
+    log::info!(
+        "Batch daemon started with schedule \"{}\" ({})",
+        schedule,
+        timezone
+    );
+
+    shutdown_signal().await;
+    log::info!("Shutting down batch daemon");
+    scheduler.shutdown().await?;
+    let _guard = running.lock().await;
+    Ok(())
+}
+
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to install Ctrl+C handler");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to install signal handler")
+            .recv()
+            .await;
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+
+    fn policy(max_retries: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_retries,
+            retry_delay: Duration::ZERO,
+            max_jitter: Duration::ZERO,
+        }
+    }
+
+    #[test]
+    fn normalize_schedule_adds_seconds() {
+        assert_eq!(normalize_schedule("0 3 * * *"), "0 0 3 * * *");
+        assert_eq!(normalize_schedule(" 30 0 3 * * * "), "30 0 3 * * *");
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        let policy = RetryPolicy {
+            max_retries: 3,
+            retry_delay: Duration::from_secs(10),
+            max_jitter: Duration::ZERO,
+        };
+        assert_eq!(policy.backoff(0), Duration::from_secs(10));
+        assert_eq!(policy.backoff(2), Duration::from_secs(40));
+    }
+
+    #[tokio::test]
+    async fn retries_until_success() {
+        let calls = AtomicU32::new(0);
+        let result = run_with_retries(&policy(3), || async {
+            if calls.fetch_add(1, Ordering::Relaxed) < 2 {
+                anyhow::bail!("transient");
+            }
+            Ok(())
+        })
+        .await;
+        assert!(result.is_ok());
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let calls = AtomicU32::new(0);
+        let result = run_with_retries(&policy(2), || async {
+            calls.fetch_add(1, Ordering::Relaxed);
+            anyhow::bail!("permanent")
+        })
+        .await;
+        assert!(result.is_err());
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+    }
+}
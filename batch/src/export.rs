@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use arrow::array::{ArrayRef, Date32Array, Decimal128Array, StringArray};
+use arrow::datatypes::{DataType, Field, Schema};
+use arrow::record_batch::RecordBatch;
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{Datelike, NaiveDate};
+use common::CostRow;
+use parquet::arrow::ArrowWriter;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+/// Where exports are written: a local directory or `s3://bucket/prefix`.
+#[derive(Debug, PartialEq)]
+pub enum Destination {
+    Local(PathBuf),
+    S3 { bucket: String, prefix: String },
+}
+
+impl Destination {
+    pub fn parse(dest: &str) -> Result<Self> {
+        let Some(rest) = dest.strip_prefix("s3://") else {
+            return Ok(Self::Local(PathBuf::from(dest)));
+        };
+        let (bucket, prefix) = rest.split_once('/').unwrap_or((rest, ""));
+        anyhow::ensure!(!bucket.is_empty(), "missing bucket in {dest}");
+        Ok(Self::S3 {
+            bucket: bucket.to_string(),
+            prefix: prefix.trim_matches('/').to_string(),
+        })
+    }
+}
+
+/// File format of the rows the batch writes to S3.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Jsonl,
+    Parquet,
+}
+
+impl Format {
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Jsonl => "jsonl",
+            Format::Parquet => "parquet",
+        }
+    }
+
+    fn encode(self, rows: &[&CostRow]) -> Result<Vec<u8>> {
+        match self {
+            Format::Jsonl => encode_jsonl(rows),
+            Format::Parquet => encode(rows),
+        }
+    }
+}
+
+/// Writes files under a [`Destination`], creating the S3 client on first use.
+pub struct Writer {
+    dest: Destination,
+    s3: OnceCell<aws_sdk_s3::Client>,
+}
+
+impl Writer {
+    pub fn new(dest: Destination) -> Self {
+        Self {
+            dest,
+            s3: OnceCell::new(),
+        }
+    }
+
+    pub async fn put(&self, path: &str, bytes: Vec<u8>) -> Result<()> {
+        match &self.dest {
+            Destination::Local(dir) => {
+                let file = dir.join(path);
+                if let Some(parent) = file.parent() {
+                    tokio::fs::create_dir_all(parent).await?;
+                }
+                tokio::fs::write(&file, bytes)
+                    .await
+                    .with_context(|| format!("failed to write {}", file.display()))?;
+            }
+            Destination::S3 { bucket, prefix } => {
+                let client = self.s3_client().await;
+                let key = if prefix.is_empty() {
+                    path.to_string()
+                } else {
+                    format!("{}/{}", prefix, path)
+                };
+                client
+                    .put_object()
+                    .bucket(bucket)
+                    .key(&key)
+                    .body(ByteStream::from(bytes))
+                    .send()
+                    .await
+                    .with_context(|| format!("failed to upload s3://{}/{}", bucket, key))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that an S3 destination's bucket exists and can be reached.
+    pub async fn check(&self) -> Result<String> {
+        match &self.dest {
+            Destination::Local(dir) => Ok(format!("writes under {}", dir.display())),
+            Destination::S3 { bucket, .. } => {
+                self.s3_client()
+                    .await
+                    .head_bucket()
+                    .bucket(bucket)
+                    .send()
+                    .await
+                    .with_context(|| format!("cannot reach bucket {}", bucket))?;
+                Ok(format!("bucket {} reachable", bucket))
+            }
+        }
+    }
+
+    async fn s3_client(&self) -> &aws_sdk_s3::Client {
+        self.s3
+            .get_or_init(|| async {
+                let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                aws_sdk_s3::Client::new(&config)
+            })
+            .await
+    }
+}
+
+/// Groups rows into Hive-style partitions (`year=YYYY/month=MM` or
+/// `date=YYYY-MM-DD`) so Athena can prune by date.
+fn partition(
+    rows: &[CostRow],
+    key: impl Fn(NaiveDate) -> String,
+) -> BTreeMap<String, Vec<&CostRow>> {
+    let mut parts: BTreeMap<String, Vec<&CostRow>> = BTreeMap::new();
+    for row in rows {
+        parts.entry(key(row.date)).or_default().push(row);
+    }
+    parts
+}
+
+fn by_month(date: NaiveDate) -> String {
+    format!("year={}/month={:02}", date.year(), date.month())
+}
+
+fn by_day(date: NaiveDate) -> String {
+    format!("date={}", date.format("%Y-%m-%d"))
+}
+
+fn encode_jsonl(rows: &[&CostRow]) -> Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    for r in rows {
+        let line = serde_json::json!({
+            "date": r.date.format("%Y-%m-%d").to_string(),
+            "user_id": r.user_id,
+            "model_id": r.model_id,
+            "amount": r.amount,
+            "currency": r.currency,
+            "metric": r.metric.as_str(),
+            "source": r.source,
+            "tenant": r.tenant,
+        });
+        serde_json::to_writer(&mut buf, &line)?;
+        buf.push(b'\n');
+    }
+    Ok(buf)
+}
+
+fn encode(rows: &[&CostRow]) -> Result<Vec<u8>> {
+    let schema = Arc::new(Schema::new(vec![
+        Field::new("date", DataType::Date32, false),
+        Field::new("user_id", DataType::Utf8, false),
+        Field::new("model_id", DataType::Utf8, false),
+        Field::new("amount", DataType::Decimal128(20, 6), false),
+        Field::new("currency", DataType::Utf8, false),
+        Field::new("metric", DataType::Utf8, false),
+        Field::new("source", DataType::Utf8, false),
+        Field::new("tenant", DataType::Utf8, false),
+    ]));
+    let epoch = NaiveDate::from_ymd_opt(1970, 1, 1).unwrap_or_default();
+    let dates =
+        Date32Array::from_iter_values(rows.iter().map(|r| (r.date - epoch).num_days() as i32));
+    let user_ids = StringArray::from_iter_values(rows.iter().map(|r| r.user_id.as_str()));
+    let model_ids = StringArray::from_iter_values(rows.iter().map(|r| r.model_id.as_str()));
+    // Amount is already in micro-units, which is exactly scale 6.
+    let amounts = Decimal128Array::from_iter_values(rows.iter().map(|r| r.amount.micros() as i128))
+        .with_precision_and_scale(20, 6)?;
+    let currencies = StringArray::from_iter_values(rows.iter().map(|r| r.currency.as_str()));
+    let metrics = StringArray::from_iter_values(rows.iter().map(|r| r.metric.as_str()));
+    let sources = StringArray::from_iter_values(rows.iter().map(|r| r.source.as_str()));
+    let tenants = StringArray::from_iter_values(rows.iter().map(|r| r.tenant.as_str()));
+    let batch = RecordBatch::try_new(
+        schema.clone(),
+        vec![
+            Arc::new(dates) as ArrayRef,
+            Arc::new(user_ids),
+            Arc::new(model_ids),
+            Arc::new(amounts),
+            Arc::new(currencies),
+            Arc::new(metrics),
+            Arc::new(sources),
+            Arc::new(tenants),
+        ],
+    )?;
+
+    let mut buf = Vec::new();
+    let mut writer = ArrowWriter::try_new(&mut buf, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(buf)
+}
+
+/// Writes the cost table as `cost/year=YYYY/month=MM/part-0.parquet`,
+/// replacing earlier exports. Returns the number of files written.
+pub async fn export_cost(rows: &[CostRow], writer: &Writer) -> Result<usize> {
+    let parts = partition(rows, by_month);
+    for (key, part) in &parts {
+        let path = format!("cost/{}/part-0.parquet", key);
+        writer.put(&path, encode(part)?).await?;
+        log::info!("Exported {} rows to {}", part.len(), path);
+    }
+    Ok(parts.len())
+}
+
+/// Writes fetched CE rows as one `cost/date=YYYY-MM-DD/part-0.<ext>` file per
+/// day, so re-fetching a day replaces its file. Returns the number of files.
+pub async fn write_daily(rows: &[CostRow], writer: &Writer, format: Format) -> Result<usize> {
+    let parts = partition(rows, by_day);
+    for (key, part) in &parts {
+        let path = format!("cost/{}/part-0.{}", key, format.extension());
+        writer.put(&path, format.encode(part)?).await?;
+    }
+    Ok(parts.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{Amount, Metric};
+
+    fn row(date: &str, user: &str) -> CostRow {
+        CostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            user_id: user.to_string(),
+            model_id: "m1".to_string(),
+            amount: Amount::from_micros(1_234_567),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn parse_destination() {
+        assert_eq!(
+            Destination::parse("/tmp/out").unwrap(),
+            Destination::Local(PathBuf::from("/tmp/out"))
+        );
+        assert_eq!(
+            Destination::parse("s3://bucket/warehouse/").unwrap(),
+            Destination::S3 {
+                bucket: "bucket".to_string(),
+                prefix: "warehouse".to_string(),
+            }
+        );
+        assert_eq!(
+            Destination::parse("s3://bucket").unwrap(),
+            Destination::S3 {
+                bucket: "bucket".to_string(),
+                prefix: String::new(),
+            }
+        );
+        assert!(Destination::parse("s3://").is_err());
+    }
+
+    #[test]
+    fn partition_by_month() {
+        let rows = vec![
+            row("2025-01-31", "u1"),
+            row("2025-02-01", "u1"),
+            row("2025-02-15", "u2"),
+        ];
+        let parts = partition(&rows, by_month);
+        let keys: Vec<_> = parts.keys().cloned().collect();
+        assert_eq!(keys, vec!["year=2025/month=01", "year=2025/month=02"]);
+        assert_eq!(parts["year=2025/month=02"].len(), 2);
+    }
+
+    #[test]
+    fn encode_writes_parquet() {
+        let rows = vec![row("2025-01-31", "u1")];
+        let refs: Vec<&CostRow> = rows.iter().collect();
+        let bytes = encode(&refs).unwrap();
+        assert!(bytes.starts_with(b"PAR1"));
+        assert!(bytes.ends_with(b"PAR1"));
+    }
+
+    #[test]
+    fn partition_by_day() {
+        let rows = vec![row("2025-01-31", "u1"), row("2025-01-31", "u2")];
+        let parts = partition(&rows, by_day);
+        assert_eq!(parts.keys().collect::<Vec<_>>(), vec!["date=2025-01-31"]);
+    }
+
+    #[test]
+    fn encode_jsonl_one_line_per_row() {
+        let rows = vec![row("2025-01-31", "u1"), row("2025-02-01", "u2")];
+        let refs: Vec<&CostRow> = rows.iter().collect();
+        let text = String::from_utf8(encode_jsonl(&refs).unwrap()).unwrap();
+        let lines: Vec<_> = text.lines().collect();
+        assert_eq!(lines.len(), 2);
+        let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["date"], "2025-01-31");
+        assert_eq!(first["user_id"], "u1");
+        assert_eq!(first["amount"], 1.234567);
+        assert_eq!(first["metric"], "BlendedCost");
+        assert_eq!(first["source"], "aws");
+        assert_eq!(first["tenant"], "default");
+    }
+
+    #[tokio::test]
+    async fn write_daily_to_local_dir() {
+        let dir = std::env::temp_dir().join(format!("batch-export-{}", std::process::id()));
+        let writer = Writer::new(Destination::Local(dir.clone()));
+        let rows = vec![row("2025-01-31", "u1"), row("2025-02-01", "u1")];
+        let files = write_daily(&rows, &writer, Format::Jsonl).await.unwrap();
+        assert_eq!(files, 2);
+        assert!(dir.join("cost/date=2025-02-01/part-0.jsonl").exists());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+use anyhow::Result;
+use aws_sdk_sesv2::primitives::Blob;
+use aws_sdk_sesv2::types::{Destination, EmailContent, RawMessage};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use common::{AlertPeriod, DueAlert};
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+use crate::report::Statement;
+
+/// The `[mail]` config section. `subject` and `body` may use `{email}`,
+/// `{month}` and `{total}`; `alert_subject` and `alert_body` may use
+/// `{email}`, `{period}`, `{date}`, `{cost}` and `{threshold}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct MailConfig {
+    pub from: String,
+    #[serde(default = "default_subject")]
+    pub subject: String,
+    #[serde(default = "default_body")]
+    pub body: String,
+    #[serde(default = "default_alert_subject")]
+    pub alert_subject: String,
+    #[serde(default = "default_alert_body")]
+    pub alert_body: String,
+}
+
+fn default_subject() -> String {
+    "Your LLM gateway costs for {month}".to_string()
+}
+
+fn default_body() -> String {
+    "Hello,\n\nyour LLM gateway usage in {month} cost {total}. \
+     The attached statement breaks it down by day and model.\n"
+        .to_string()
+}
+
+fn default_alert_subject() -> String {
+    "Your LLM gateway {period} cost passed {threshold}".to_string()
+}
+
+fn default_alert_body() -> String {
+    "Hello,\n\nyour LLM gateway cost for {date} is {cost}, over the {period} \
+     threshold of {threshold} you set on your alerts page.\n"
+        .to_string()
+}
+
+/// Replaces each `{key}` in `template` with its value.
+fn fill(template: &str, values: &[(&str, &str)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{key}}}"), value)
+        })
+}
+
+/// RFC 2047 encodes header values that are not plain ASCII.
+fn header_value(value: &str) -> String {
+    if value.is_ascii() {
+        value.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", STANDARD.encode(value))
+    }
+}
+
+/// A MIME message with the filled-in template as text and the statement
+/// PDF attached.
+pub fn message(cfg: &MailConfig, to: &str, statement: &Statement, pdf: &[u8]) -> Vec<u8> {
+    let month = statement.month.format("%B %Y").to_string();
+    let total = format!("{:.2} {}", statement.total, statement.currency);
+    let values = [
+        ("email", to),
+        ("month", month.as_str()),
+        ("total", total.as_str()),
+    ];
+    let subject = fill(&cfg.subject, &values);
+    let body = fill(&cfg.body, &values).replace('\n', "\r\n");
+    let file_name = format!("statement-{}.pdf", statement.month.format("%Y-%m"));
+    let boundary = format!("statement-{}", statement.user_id);
+    let attachment = STANDARD.encode(pdf);
+    let attachment: Vec<&str> = attachment
+        .as_bytes()
+        .chunks(76)
+        .map(|line| std::str::from_utf8(line).unwrap_or_default())
+        .collect();
+
+    format!(
+        "From: {from}\r\n\
+         To: {to}\r\n\
+         Subject: {subject}\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: multipart/mixed; boundary=\"{boundary}\"\r\n\
+         \r\n\
+         --{boundary}\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\
+         \r\n\
+         {body}\r\n\
+         --{boundary}\r\n\
+         Content-Type: application/pdf; name=\"{file_name}\"\r\n\
+         Content-Disposition: attachment; filename=\"{file_name}\"\r\n\
+         Content-Transfer-Encoding: base64\r\n\
+         \r\n\
+         {attachment}\r\n\
+         --{boundary}--\r\n",
+        from = cfg.from,
+        subject = header_value(&subject),
+        attachment = attachment.join("\r\n"),
+    )
+    .into_bytes()
+}
+
+/// A plain text message telling `to` that `alert` is due.
+pub fn alert_message(cfg: &MailConfig, to: &str, alert: &DueAlert) -> Vec<u8> {
+    let date = match alert.period {
+        AlertPeriod::Daily => alert.date.format("%Y-%m-%d").to_string(),
+        AlertPeriod::Monthly => alert.date.format("%B %Y so far").to_string(),
+    };
+    let cost = format!("{:.2} USD", alert.cost);
+    let threshold = format!("{:.2} USD", alert.threshold);
+    let values = [
+        ("email", to),
+        ("period", alert.period.as_str()),
+        ("date", date.as_str()),
+        ("cost", cost.as_str()),
+        ("threshold", threshold.as_str()),
+    ];
+    let subject = fill(&cfg.alert_subject, &values);
+    let body = fill(&cfg.alert_body, &values).replace('\n', "\r\n");
+
+    format!(
+        "From: {from}\r\n\
+         To: {to}\r\n\
+         Subject: {subject}\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\
+         \r\n\
+         {body}\r\n",
+        from = cfg.from,
+        subject = header_value(&subject),
+    )
+    .into_bytes()
+}
+
+/// A plain text message with an already rendered scheduled report.
+pub fn report_message(cfg: &MailConfig, to: &str, subject: &str, body: &str) -> Vec<u8> {
+    format!(
+        "From: {from}\r\n\
+         To: {to}\r\n\
+         Subject: {subject}\r\n\
+         MIME-Version: 1.0\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Transfer-Encoding: 8bit\r\n\
+         \r\n\
+         {body}\r\n",
+        from = cfg.from,
+        subject = header_value(subject),
+        body = body.replace('\n', "\r\n"),
+    )
+    .into_bytes()
+}
+
+/// Sends raw messages through SES, creating the client on first use.
+pub struct Mailer {
+    cfg: MailConfig,
+    ses: OnceCell<aws_sdk_sesv2::Client>,
+}
+
+impl Mailer {
+    pub fn new(cfg: MailConfig) -> Self {
+        Self {
+            cfg,
+            ses: OnceCell::new(),
+        }
+    }
+
+    /// Emails `statement` to `to` with `pdf` attached.
+    pub async fn send(&self, to: &str, statement: &Statement, pdf: &[u8]) -> Result<()> {
+        self.send_raw(to, message(&self.cfg, to, statement, pdf))
+            .await
+    }
+
+    /// Emails `to` that their `alert` is due.
+    pub async fn send_alert(&self, to: &str, alert: &DueAlert) -> Result<()> {
+        self.send_raw(to, alert_message(&self.cfg, to, alert)).await
+    }
+
+    /// Emails `to` a scheduled report.
+    pub async fn send_report(&self, to: &str, subject: &str, body: &str) -> Result<()> {
+        self.send_raw(to, report_message(&self.cfg, to, subject, body))
+            .await
+    }
+
+    async fn send_raw(&self, to: &str, data: Vec<u8>) -> Result<()> {
+        let client = self
+            .ses
+            .get_or_init(|| async {
+                let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+                aws_sdk_sesv2::Client::new(&config)
+            })
+            .await;
+        let raw = RawMessage::builder().data(Blob::new(data)).build()?;
+        client
+            .send_email()
+            .from_email_address(&self.cfg.from)
+            .destination(Destination::builder().to_addresses(to).build())
+            .content(EmailContent::builder().raw(raw).build())
+            .send()
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use common::Amount;
+
+    fn statement() -> Statement {
+        Statement {
+            user_id: "u1".to_string(),
+            user_label: "a@example.com".to_string(),
+            month: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            currency: "USD".to_string(),
+            total: Amount::from_f64(12.5),
+            models: vec![],
+            daily: vec![],
+        }
+    }
+
+    #[test]
+    fn fill_replaces_placeholders() {
+        assert_eq!(
+            fill(
+                "{email}: {total} in {month} {other}",
+                &[("email", "a"), ("total", "1"), ("month", "May")]
+            ),
+            "a: 1 in May {other}"
+        );
+    }
+
+    #[test]
+    fn message_has_body_and_attachment() {
+        let cfg = MailConfig {
+            from: "costs@example.com".to_string(),
+            subject: default_subject(),
+            body: default_body(),
+            alert_subject: default_alert_subject(),
+            alert_body: default_alert_body(),
+        };
+        let text =
+            String::from_utf8(message(&cfg, "a@example.com", &statement(), b"%PDF-1.3")).unwrap();
+        assert!(text.contains("To: a@example.com\r\n"));
+        assert!(text.contains("Subject: Your LLM gateway costs for May 2024\r\n"));
+        assert!(text.contains("cost 12.50 USD"));
+        assert!(text.contains("filename=\"statement-2024-05.pdf\""));
+        assert!(text.contains(&STANDARD.encode(b"%PDF-1.3")));
+        assert!(text.ends_with("--statement-u1--\r\n"));
+    }
+
+    #[test]
+    fn alert_message_fills_in_the_alert() {
+        let cfg = MailConfig {
+            from: "costs@example.com".to_string(),
+            subject: default_subject(),
+            body: default_body(),
+            alert_subject: default_alert_subject(),
+            alert_body: default_alert_body(),
+        };
+        let alert = DueAlert {
+            period: AlertPeriod::Monthly,
+            date: NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            cost: Amount::from_f64(150.0),
+            threshold: Amount::from_f64(100.0),
+        };
+        let text = String::from_utf8(alert_message(&cfg, "a@example.com", &alert)).unwrap();
+        assert!(text.contains("Subject: Your LLM gateway monthly cost passed 100.00 USD\r\n"));
+        assert!(text.contains("for May 2024 so far is 150.00 USD"));
+        assert!(text.contains("Content-Type: text/plain"));
+    }
+
+    #[test]
+    fn report_message_uses_crlf() {
+        let cfg = MailConfig {
+            from: "costs@example.com".to_string(),
+            subject: default_subject(),
+            body: default_body(),
+            alert_subject: default_alert_subject(),
+            alert_body: default_alert_body(),
+        };
+        let text = String::from_utf8(report_message(
+            &cfg,
+            "finance@example.com",
+            "LLM gateway daily summary for 2025-03-11",
+            "Daily summary\n\nTotal cost: 5.00 USD\n",
+        ))
+        .unwrap();
+        assert!(text.contains("To: finance@example.com\r\n"));
+        assert!(text.contains("Subject: LLM gateway daily summary for 2025-03-11\r\n"));
+        assert!(text.contains("Daily summary\r\n\r\nTotal cost: 5.00 USD\r\n"));
+    }
+
+    #[test]
+    fn non_ascii_subject_is_encoded() {
+        assert_eq!(header_value("Costs"), "Costs");
+        assert!(header_value("Coûts").starts_with("=?UTF-8?B?"));
+    }
+}
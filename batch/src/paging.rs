@@ -0,0 +1,283 @@
+//! Paging for critical cost spikes through incident tools, as opposed to
+//! the informational emails of [`crate::mail`].
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use common::{Amount, DEFAULT_TENANT};
+use serde::Deserialize;
+use serde_json::json;
+
+/// The `source` incidents are raised from.
+const SOURCE: &str = "llm-proxy-cost";
+
+/// The `[spike]` config section.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpikeConfig {
+    /// Day-over-day rise of the total cost, in percent, that pages.
+    pub threshold_percent: f64,
+    /// Days costing less than this never page, so a jump from a near idle
+    /// day does not wake anyone.
+    #[serde(default)]
+    pub min_cost: f64,
+    pub channels: Vec<Channel>,
+}
+
+/// Where spikes are paged, one `[[spike.channels]]` entry. Each channel
+/// raises a critical incident.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Channel {
+    /// An Events API v2 integration of a PagerDuty service.
+    PagerDuty {
+        routing_key: String,
+        #[serde(default = "default_pagerduty_url")]
+        url: String,
+    },
+    /// An Opsgenie API integration.
+    Opsgenie {
+        api_key: String,
+        #[serde(default = "default_opsgenie_url")]
+        url: String,
+    },
+}
+
+fn default_pagerduty_url() -> String {
+    "https://events.pagerduty.com/v2/enqueue".to_string()
+}
+
+fn default_opsgenie_url() -> String {
+    "https://api.opsgenie.com/v2/alerts".to_string()
+}
+
+/// A day whose total cost rose past the threshold over the day before.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spike {
+    /// The gateway whose cost rose, by tenant name.
+    pub tenant: String,
+    pub date: NaiveDate,
+    pub previous: Amount,
+    pub cost: Amount,
+    pub percent: f64,
+}
+
+impl Spike {
+    fn summary(&self) -> String {
+        let gateway = match self.tenant.as_str() {
+            DEFAULT_TENANT => "LLM gateway".to_string(),
+            tenant => format!("LLM gateway {tenant}"),
+        };
+        format!(
+            "{} cost rose {:.0}% on {} to {:.2} USD (from {:.2} USD)",
+            gateway, self.percent, self.date, self.cost, self.previous
+        )
+    }
+
+    /// Later runs for the same day update its incident instead of opening
+    /// another; each gateway's spike is its own incident.
+    fn dedup_key(&self) -> String {
+        match self.tenant.as_str() {
+            DEFAULT_TENANT => format!("{}-spike-{}", SOURCE, self.date),
+            tenant => format!("{}-{}-spike-{}", SOURCE, tenant, self.date),
+        }
+    }
+}
+
+/// The day a run at `now` checks for a spike: yesterday in UTC, the last
+/// day CE has finished, whatever time zone the batch reports in.
+pub fn spike_day(now: DateTime<Utc>) -> NaiveDate {
+    now.date_naive() - Duration::days(1)
+}
+
+/// The spike on `date` in the cost of `tenant`, if it rose more than the
+/// threshold over `previous`. A day after one without cost has nothing to
+/// compare with.
+pub fn detect(
+    cfg: &SpikeConfig,
+    tenant: &str,
+    date: NaiveDate,
+    previous: Amount,
+    cost: Amount,
+) -> Option<Spike> {
+    if previous <= Amount::ZERO || cost < Amount::from_f64(cfg.min_cost) {
+        return None;
+    }
+    let percent = (cost.to_f64() / previous.to_f64() - 1.0) * 100.0;
+    (percent > cfg.threshold_percent).then(|| Spike {
+        tenant: tenant.to_string(),
+        date,
+        previous,
+        cost,
+        percent,
+    })
+}
+
+impl Channel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::PagerDuty { .. } => "PagerDuty",
+            Self::Opsgenie { .. } => "Opsgenie",
+        }
+    }
+
+    /// The request body announcing `spike`.
+    fn body(&self, spike: &Spike) -> serde_json::Value {
+        let details = json!({
+            "tenant": spike.tenant,
+            "date": spike.date.to_string(),
+            "previous_cost": format!("{:.2}", spike.previous),
+            "cost": format!("{:.2}", spike.cost),
+            "percent": format!("{:.1}", spike.percent),
+        });
+        match self {
+            Self::PagerDuty { routing_key, .. } => json!({
+                "routing_key": routing_key,
+                "event_action": "trigger",
+                "dedup_key": spike.dedup_key(),
+                "payload": {
+                    "summary": spike.summary(),
+                    "source": SOURCE,
+                    "severity": "critical",
+                    "custom_details": details,
+                },
+            }),
+            Self::Opsgenie { .. } => json!({
+                "message": spike.summary(),
+                "alias": spike.dedup_key(),
+                "source": SOURCE,
+                "priority": "P1",
+                "details": details,
+            }),
+        }
+    }
+
+    /// Raises an incident for `spike`.
+    pub async fn trigger(&self, http: &reqwest::Client, spike: &Spike) -> Result<()> {
+        let request = match self {
+            Self::PagerDuty { url, .. } => http.post(url),
+            Self::Opsgenie { api_key, url } => http
+                .post(url)
+                .header("Authorization", format!("GenieKey {api_key}")),
+        };
+        request
+            .json(&self.body(spike))
+            .send()
+            .await?
+            .error_for_status()
+            .with_context(|| format!("{} rejected the cost spike incident", self.name()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> SpikeConfig {
+        SpikeConfig {
+            threshold_percent: 50.0,
+            min_cost: 10.0,
+            channels: vec![],
+        }
+    }
+
+    #[test]
+    fn spike_day_follows_ce_days() {
+        let date = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        // Already the 16th in Tokyo, but CE has not finished the 15th.
+        let now = "2025-03-15T23:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        let tokyo = chrono::FixedOffset::east_opt(9 * 3600).unwrap();
+        assert_eq!(now.with_timezone(&tokyo).date_naive(), date(16));
+        assert_eq!(spike_day(now), date(14));
+        let now = "2025-03-16T00:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert_eq!(spike_day(now), date(15));
+    }
+
+    fn spike() -> Spike {
+        Spike {
+            tenant: DEFAULT_TENANT.to_string(),
+            date: NaiveDate::from_ymd_opt(2025, 3, 11).unwrap(),
+            previous: Amount::from_f64(100.0),
+            cost: Amount::from_f64(180.0),
+            percent: 80.0,
+        }
+    }
+
+    #[test]
+    fn detect_needs_a_rise_past_the_threshold() {
+        let usd = Amount::from_f64;
+        let date = NaiveDate::from_ymd_opt(2025, 3, 11).unwrap();
+        assert_eq!(
+            detect(&config(), DEFAULT_TENANT, date, usd(100.0), usd(180.0)),
+            Some(spike())
+        );
+        assert_eq!(
+            detect(&config(), DEFAULT_TENANT, date, usd(100.0), usd(150.0)),
+            None
+        );
+        assert_eq!(
+            detect(&config(), DEFAULT_TENANT, date, usd(1.0), usd(9.0)),
+            None
+        );
+        assert_eq!(
+            detect(&config(), DEFAULT_TENANT, date, Amount::ZERO, usd(500.0)),
+            None
+        );
+    }
+
+    #[test]
+    fn pagerduty_event_is_a_critical_trigger() {
+        let channel = Channel::PagerDuty {
+            routing_key: "key".to_string(),
+            url: default_pagerduty_url(),
+        };
+        let body = channel.body(&spike());
+        assert_eq!(body["routing_key"], "key");
+        assert_eq!(body["event_action"], "trigger");
+        assert_eq!(body["dedup_key"], "llm-proxy-cost-spike-2025-03-11");
+        assert_eq!(body["payload"]["severity"], "critical");
+        assert_eq!(
+            body["payload"]["summary"],
+            "LLM gateway cost rose 80% on 2025-03-11 to 180.00 USD (from 100.00 USD)"
+        );
+    }
+
+    #[test]
+    fn opsgenie_alert_is_p1_with_alias() {
+        let channel = Channel::Opsgenie {
+            api_key: "key".to_string(),
+            url: default_opsgenie_url(),
+        };
+        let body = channel.body(&spike());
+        assert_eq!(body["priority"], "P1");
+        assert_eq!(body["alias"], "llm-proxy-cost-spike-2025-03-11");
+        assert_eq!(body["details"]["cost"], "180.00");
+    }
+
+    #[test]
+    fn spikes_of_other_gateways_are_separate_incidents() {
+        let spike = Spike {
+            tenant: "staging".to_string(),
+            ..spike()
+        };
+        assert_eq!(spike.dedup_key(), "llm-proxy-cost-staging-spike-2025-03-11");
+        assert_eq!(
+            spike.summary(),
+            "LLM gateway staging cost rose 80% on 2025-03-11 to 180.00 USD (from 100.00 USD)"
+        );
+    }
+
+    #[test]
+    fn channels_parse_from_config() {
+        let cfg: SpikeConfig = serde_json::from_value(json!({
+            "threshold_percent": 50.0,
+            "channels": [
+                { "type": "pagerduty", "routing_key": "abc" },
+                { "type": "opsgenie", "api_key": "def" },
+            ],
+        }))
+        .unwrap();
+        let names: Vec<&str> = cfg.channels.iter().map(Channel::name).collect();
+        assert_eq!(names, vec!["PagerDuty", "Opsgenie"]);
+        assert_eq!(cfg.min_cost, 0.0);
+    }
+}
@@ -0,0 +1,129 @@
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::extract::State;
+use axum::response::Html;
+use axum::routing::get;
+use axum::Router;
+
+/// Counters updated by the batch loop and read by the status page.
+pub struct Progress {
+    started: Instant,
+    pub chunks_total: AtomicUsize,
+    pub chunks_done: AtomicUsize,
+    pub rows_upserted: AtomicUsize,
+    pub ce_calls: AtomicUsize,
+}
+
+impl Progress {
+    pub fn new(chunks_total: usize) -> Self {
+        Self {
+            started: Instant::now(),
+            chunks_total: AtomicUsize::new(chunks_total),
+            chunks_done: AtomicUsize::new(0),
+            rows_upserted: AtomicUsize::new(0),
+            ce_calls: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Linear estimate from the average time per completed chunk.
+    pub fn eta(&self) -> Option<Duration> {
+        let done = self.chunks_done.load(Ordering::Relaxed);
+        let total = self.chunks_total.load(Ordering::Relaxed);
+        if done == 0 {
+            return None;
+        }
+        let per_chunk = self.elapsed() / done as u32;
+        Some(per_chunk * total.saturating_sub(done) as u32)
+    }
+}
+
+fn format_duration(d: Duration) -> String {
+    let secs = d.as_secs();
+    format!("{:02}:{:02}:{:02}", secs / 3600, (secs / 60) % 60, secs % 60)
+}
+
+fn render(progress: &Progress) -> String {
+    let done = progress.chunks_done.load(Ordering::Relaxed);
+    let total = progress.chunks_total.load(Ordering::Relaxed);
+    let status = if done >= total { "finished" } else { "running" };
+    let eta = progress
+        .eta()
+        .map(format_duration)
+        .unwrap_or_else(|| "-".to_string());
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta http-equiv="refresh" content="5">
+<title>Batch Progress</title>
+<style>body {{ font-family: monospace; padding: 16px; }} td {{ padding: 4px 8px; }}</style>
+</head>
+<body>
+<h1>Batch Progress</h1>
+<table>
+<tr><td>Status</td><td>{status}</td></tr>
+<tr><td>Chunks</td><td>{done} / {total}</td></tr>
+<tr><td>Rows Upserted</td><td>{rows}</td></tr>
+<tr><td>CE Calls</td><td>{calls}</td></tr>
+<tr><td>Elapsed</td><td>{elapsed}</td></tr>
+<tr><td>ETA</td><td>{eta}</td></tr>
+</table>
+</body>
+</html>"#,
+        rows = progress.rows_upserted.load(Ordering::Relaxed),
+        calls = progress.ce_calls.load(Ordering::Relaxed),
+        elapsed = format_duration(progress.elapsed()),
+    )
+}
+
+async fn status_page(State(progress): State<Arc<Progress>>) -> Html<String> {
+    Html(render(&progress))
+}
+
+/// Serves the status page on `addr` until the process exits.
+pub async fn serve(addr: SocketAddr, progress: Arc<Progress>) -> anyhow::Result<()> {
+    let app = Router::new()
+        .route("/", get(status_page))
+        .with_state(progress);
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    log::info!("Progress server listening on http://{}", addr);
+    axum::serve(listener, app).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn eta_unknown_before_first_chunk() {
+        let progress = Progress::new(4);
+        assert!(progress.eta().is_none());
+    }
+
+    #[test]
+    fn render_shows_counters() {
+        let progress = Progress::new(4);
+        progress.chunks_done.store(1, Ordering::Relaxed);
+        progress.rows_upserted.store(120, Ordering::Relaxed);
+        progress.ce_calls.store(3, Ordering::Relaxed);
+        let html = render(&progress);
+        assert!(html.contains("1 / 4"));
+        assert!(html.contains("<td>120</td>"));
+        assert!(html.contains("<td>3</td>"));
+        assert!(html.contains("running"));
+    }
+
+    #[test]
+    fn format_duration_hms() {
+        assert_eq!(format_duration(Duration::from_secs(3725)), "01:02:05");
+    }
+}
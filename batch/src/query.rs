@@ -0,0 +1,219 @@
+//! `batch query`: cost totals for a terminal, from the cost table or
+//! straight from CE.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use common::{Amount, CostByModel, CostByUser, CostRecord, CostRow, Metric};
+
+#[derive(clap::Subcommand)]
+pub enum Query {
+    /// Total cost per day
+    Daily(QueryArgs),
+    /// Total cost per user, highest first
+    ByUser(QueryArgs),
+    /// Total cost per model, highest first
+    ByModel(QueryArgs),
+}
+
+impl Query {
+    pub fn args(&self) -> &QueryArgs {
+        match self {
+            Query::Daily(args) | Query::ByUser(args) | Query::ByModel(args) => args,
+        }
+    }
+}
+
+#[derive(clap::Args)]
+pub struct QueryArgs {
+    /// First day, `YYYY-MM-DD`
+    #[arg(long)]
+    pub start: NaiveDate,
+    /// Day after the last one, `YYYY-MM-DD`
+    #[arg(long)]
+    pub end: NaiveDate,
+    /// CE cost metric; the first configured metric by default
+    #[arg(long)]
+    pub metric: Option<Metric>,
+    /// Only costs from this source, e.g. `aws` or `openai`
+    #[arg(long)]
+    pub source: Option<String>,
+    /// Ask CE instead of the cost table; AWS costs only
+    #[arg(long, conflicts_with = "source")]
+    pub live: bool,
+    /// Print JSON instead of a table
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Sums `rows` of `metric` per day, in date order.
+pub fn daily(rows: &[CostRow], metric: Metric) -> Vec<CostRecord> {
+    let mut totals: BTreeMap<NaiveDate, (Amount, &str)> = BTreeMap::new();
+    for row in rows.iter().filter(|r| r.metric == metric) {
+        totals
+            .entry(row.date)
+            .or_insert((Amount::ZERO, &row.currency))
+            .0 += row.amount;
+    }
+    totals
+        .into_iter()
+        .map(|(date, (amount, currency))| CostRecord {
+            date: date.format("%Y-%m-%d").to_string(),
+            amount,
+            currency: currency.to_string(),
+        })
+        .collect()
+}
+
+/// Sums `rows` of `metric` by `key`, highest first.
+fn totals_by<'a>(
+    rows: &'a [CostRow],
+    metric: Metric,
+    key: impl Fn(&'a CostRow) -> &'a str,
+) -> Vec<(String, Amount, String)> {
+    let mut totals: HashMap<&str, (Amount, &str)> = HashMap::new();
+    for row in rows.iter().filter(|r| r.metric == metric) {
+        totals
+            .entry(key(row))
+            .or_insert((Amount::ZERO, &row.currency))
+            .0 += row.amount;
+    }
+    let mut totals: Vec<_> = totals
+        .into_iter()
+        .map(|(id, (amount, currency))| (id.to_string(), amount, currency.to_string()))
+        .collect();
+    totals.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    totals
+}
+
+/// Sums `rows` of `metric` per user, highest first.
+pub fn by_user(rows: &[CostRow], metric: Metric) -> Vec<CostByUser> {
+    totals_by(rows, metric, |r| &r.user_id)
+        .into_iter()
+        .map(|(user_id, amount, currency)| CostByUser {
+            user_id,
+            user_email: None,
+            amount,
+            currency,
+        })
+        .collect()
+}
+
+/// Sums `rows` of `metric` per model, highest first.
+pub fn by_model(rows: &[CostRow], metric: Metric) -> Vec<CostByModel> {
+    totals_by(rows, metric, |r| &r.model_id)
+        .into_iter()
+        .map(|(model_id, amount, currency)| CostByModel {
+            model_id,
+            model_name: None,
+            amount,
+            currency,
+        })
+        .collect()
+}
+
+/// A table with the `header` columns, then amount and currency, one line
+/// per row and a total at the bottom.
+pub fn table(header: &[&str], rows: &[(Vec<String>, Amount, String)]) -> String {
+    let mut widths: Vec<usize> = header.iter().map(|h| h.len()).collect();
+    widths[0] = widths[0].max("total".len());
+    for (cells, _, _) in rows {
+        for (width, cell) in widths.iter_mut().zip(cells) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let amounts: Vec<String> = rows.iter().map(|(_, a, _)| format!("{a:.2}")).collect();
+    let total: Amount = rows.iter().map(|(_, a, _)| *a).sum();
+    let total = format!("{total:.2}");
+    let amount_width = amounts
+        .iter()
+        .map(String::len)
+        .chain([total.len(), "amount".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut out = String::new();
+    let line = |out: &mut String, cells: &[&str], amount: &str, currency: &str| {
+        for (cell, width) in cells.iter().zip(&widths) {
+            out.push_str(&format!("{cell:width$}  "));
+        }
+        out.push_str(&format!("{amount:>amount_width$}  {currency}"));
+        out.truncate(out.trim_end().len());
+        out.push('\n');
+    };
+    line(&mut out, header, "amount", "currency");
+    for ((cells, _, currency), amount) in rows.iter().zip(&amounts) {
+        let cells: Vec<&str> = cells.iter().map(String::as_str).collect();
+        line(&mut out, &cells, amount, currency);
+    }
+    let currency = rows.first().map(|(_, _, c)| c.as_str()).unwrap_or("");
+    let mut footer = vec![""; header.len()];
+    footer[0] = "total";
+    line(&mut out, &footer, &total, currency);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: &str, user: &str, model: &str, micros: i64, metric: Metric) -> CostRow {
+        CostRow {
+            date: date.parse().unwrap(),
+            user_id: user.to_string(),
+            model_id: model.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn live_rows_are_summed_for_the_metric() {
+        let rows = vec![
+            row("2025-03-02", "u1", "m1", 1_000_000, Metric::Blended),
+            row("2025-03-01", "u1", "m2", 2_000_000, Metric::Blended),
+            row("2025-03-01", "u2", "m1", 4_000_000, Metric::Blended),
+            row("2025-03-01", "u2", "m1", 8_000_000, Metric::Unblended),
+        ];
+        let days = daily(&rows, Metric::Blended);
+        assert_eq!(days.len(), 2);
+        assert_eq!(days[0].date, "2025-03-01");
+        assert_eq!(days[0].amount, Amount::from_micros(6_000_000));
+        let users = by_user(&rows, Metric::Blended);
+        assert_eq!(users[0].user_id, "u2");
+        assert_eq!(users[1].amount, Amount::from_micros(3_000_000));
+        let models = by_model(&rows, Metric::Unblended);
+        assert_eq!(models.len(), 1);
+        assert_eq!(models[0].model_id, "m1");
+    }
+
+    #[test]
+    fn table_aligns_columns_and_totals() {
+        let rows = vec![
+            (
+                vec!["u1".to_string(), "alice@example.com".to_string()],
+                Amount::from_micros(12_345_000),
+                "USD".to_string(),
+            ),
+            (
+                vec!["u22".to_string(), String::new()],
+                Amount::from_micros(500_000),
+                "USD".to_string(),
+            ),
+        ];
+        assert_eq!(
+            table(&["user_id", "email"], &rows),
+            "user_id  email              amount  currency\n\
+             u1       alice@example.com   12.35  USD\n\
+             u22                           0.50  USD\n\
+             total                        12.85  USD\n"
+        );
+        assert_eq!(
+            table(&["date"], &[]),
+            "date   amount  currency\ntotal    0.00\n"
+        );
+    }
+}
@@ -0,0 +1,319 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Result;
+use chrono::{Datelike, Months, NaiveDate};
+use common::{Amount, CostRow, Metric};
+use printpdf::{BuiltinFont, Color, Line, Mm, PdfDocument, Point, Rect, Rgb};
+
+/// Models listed individually on a statement; the rest are summed as "Other".
+const MAX_MODELS: usize = 20;
+
+/// One user's showback statement for a month.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub user_id: String,
+    /// Email when known, otherwise the user id.
+    pub user_label: String,
+    /// First day of the month.
+    pub month: NaiveDate,
+    pub currency: String,
+    pub total: Amount,
+    /// Cost per model name, highest first.
+    pub models: Vec<(String, Amount)>,
+    /// Cost per day of the month, starting on the 1st.
+    pub daily: Vec<Amount>,
+}
+
+impl Statement {
+    /// Where the statement is written, relative to the destination.
+    pub fn path(&self) -> String {
+        format!(
+            "statements/{}/{}.pdf",
+            self.month.format("%Y-%m"),
+            self.user_id
+        )
+    }
+}
+
+/// The first day of `YYYY-MM`.
+pub fn parse_month(month: &str) -> Result<NaiveDate> {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .map_err(|_| anyhow::anyhow!("invalid month {month}, expected YYYY-MM"))
+}
+
+fn days_in_month(month: NaiveDate) -> usize {
+    month
+        .checked_add_months(Months::new(1))
+        .map(|next| (next - month).num_days() as usize)
+        .unwrap_or(31)
+}
+
+/// One statement per user with cost in `month`, ordered by user label.
+/// `rows` must already be limited to the month.
+pub fn statements(
+    rows: &[CostRow],
+    month: NaiveDate,
+    metric: Metric,
+    emails: &HashMap<String, String>,
+    model_names: &HashMap<String, String>,
+) -> Vec<Statement> {
+    let days = days_in_month(month);
+    let mut by_user: BTreeMap<&str, Vec<&CostRow>> = BTreeMap::new();
+    for row in rows.iter().filter(|r| r.metric == metric) {
+        by_user.entry(&row.user_id).or_default().push(row);
+    }
+
+    let mut statements: Vec<Statement> = by_user
+        .into_iter()
+        .map(|(user_id, rows)| {
+            let mut daily = vec![Amount::ZERO; days];
+            let mut models: HashMap<&str, Amount> = HashMap::new();
+            for row in &rows {
+                if let Some(day) = daily.get_mut(row.date.day0() as usize) {
+                    *day += row.amount;
+                }
+                *models.entry(&row.model_id).or_default() += row.amount;
+            }
+            let mut models: Vec<(String, Amount)> = models
+                .into_iter()
+                .map(|(id, amount)| {
+                    let name = model_names
+                        .get(id)
+                        .cloned()
+                        .unwrap_or_else(|| id.to_string());
+                    (name, amount)
+                })
+                .collect();
+            models.sort_by_key(|(name, amount)| (Reverse(*amount), name.clone()));
+            if models.len() > MAX_MODELS {
+                let other: Amount = models.drain(MAX_MODELS - 1..).map(|(_, a)| a).sum();
+                models.push(("Other".to_string(), other));
+            }
+            Statement {
+                user_id: user_id.to_string(),
+                user_label: emails
+                    .get(user_id)
+                    .cloned()
+                    .unwrap_or_else(|| user_id.to_string()),
+                month,
+                currency: rows[0].currency.clone(),
+                total: daily.iter().copied().sum(),
+                models,
+                daily,
+            }
+        })
+        .collect();
+    statements.sort_by(|a, b| a.user_label.cmp(&b.user_label));
+    statements
+}
+
+/// Renders `statement` as a one-page A4 PDF: totals, per-model table and a
+/// bar chart of daily cost.
+pub fn render_pdf(statement: &Statement) -> Result<Vec<u8>> {
+    let month = statement.month.format("%B %Y").to_string();
+    let (doc, page, layer) = PdfDocument::new(
+        format!("Cost statement {} {}", statement.user_label, month),
+        Mm(210.0),
+        Mm(297.0),
+        "Statement",
+    );
+    let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+    let layer = doc.get_page(page).get_layer(layer);
+    let money = |amount: Amount| format!("{:.2} {}", amount, statement.currency);
+
+    layer.use_text(
+        "LLM Gateway Cost Statement",
+        18.0,
+        Mm(20.0),
+        Mm(275.0),
+        &bold,
+    );
+    layer.use_text(
+        format!("User: {}", statement.user_label),
+        11.0,
+        Mm(20.0),
+        Mm(265.0),
+        &regular,
+    );
+    layer.use_text(
+        format!("Month: {}", month),
+        11.0,
+        Mm(20.0),
+        Mm(259.0),
+        &regular,
+    );
+    layer.use_text(
+        format!("Total: {}", money(statement.total)),
+        14.0,
+        Mm(20.0),
+        Mm(249.0),
+        &bold,
+    );
+
+    // Daily trend chart.
+    let (chart_left, chart_bottom, chart_width, chart_height) = (20.0, 175.0, 170.0, 55.0);
+    layer.use_text(
+        "Daily Cost",
+        12.0,
+        Mm(chart_left),
+        Mm(chart_bottom + chart_height + 5.0),
+        &bold,
+    );
+    let max = statement.daily.iter().copied().max().unwrap_or_default();
+    let slot = chart_width / statement.daily.len().max(1) as f32;
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.26, 0.45, 0.77, None)));
+    for (i, amount) in statement.daily.iter().enumerate() {
+        if max.is_zero() || amount.is_zero() {
+            continue;
+        }
+        let height = (amount.micros() as f64 / max.micros() as f64) as f32 * chart_height;
+        let left = chart_left + slot * i as f32;
+        layer.add_rect(Rect::new(
+            Mm(left + slot * 0.15),
+            Mm(chart_bottom),
+            Mm(left + slot * 0.85),
+            Mm(chart_bottom + height),
+        ));
+    }
+    layer.set_fill_color(Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)));
+    layer.add_line(Line {
+        points: vec![
+            (Point::new(Mm(chart_left), Mm(chart_bottom)), false),
+            (
+                Point::new(Mm(chart_left + chart_width), Mm(chart_bottom)),
+                false,
+            ),
+        ],
+        is_closed: false,
+    });
+    layer.use_text(
+        money(max),
+        8.0,
+        Mm(chart_left),
+        Mm(chart_bottom + chart_height + 1.0),
+        &regular,
+    );
+    for day in [1, 10, 20, statement.daily.len()] {
+        let x = chart_left + slot * (day as f32 - 0.5) - 1.5;
+        layer.use_text(
+            day.to_string(),
+            8.0,
+            Mm(x),
+            Mm(chart_bottom - 5.0),
+            &regular,
+        );
+    }
+
+    // Per-model breakdown.
+    let mut y = 155.0;
+    layer.use_text("Cost by Model", 12.0, Mm(20.0), Mm(y), &bold);
+    y -= 8.0;
+    layer.use_text("Model", 10.0, Mm(20.0), Mm(y), &bold);
+    layer.use_text("Cost", 10.0, Mm(150.0), Mm(y), &bold);
+    for (name, amount) in &statement.models {
+        y -= 6.0;
+        layer.use_text(name.as_str(), 10.0, Mm(20.0), Mm(y), &regular);
+        layer.use_text(money(*amount), 10.0, Mm(150.0), Mm(y), &regular);
+    }
+
+    Ok(doc.save_to_bytes()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: &str, user: &str, model: &str, micros: i64) -> CostRow {
+        CostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            user_id: user.to_string(),
+            model_id: model.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn statements_group_by_user_day_and_model() {
+        let month = parse_month("2024-02").unwrap();
+        let rows = vec![
+            row("2024-02-01", "u1", "m1", 1_000_000),
+            row("2024-02-01", "u1", "m2", 3_000_000),
+            row("2024-02-29", "u1", "m1", 2_000_000),
+            row("2024-02-10", "u2", "m1", 5_000_000),
+        ];
+        let emails: HashMap<String, String> = [("u2".to_string(), "a@example.com".to_string())]
+            .into_iter()
+            .collect();
+        let names: HashMap<String, String> = [("m1".to_string(), "claude".to_string())]
+            .into_iter()
+            .collect();
+        let found = statements(&rows, month, Metric::Blended, &emails, &names);
+        assert_eq!(found.len(), 2);
+        assert_eq!(found[0].user_label, "a@example.com");
+        let u1 = &found[1];
+        assert_eq!(u1.total, Amount::from_micros(6_000_000));
+        assert_eq!(u1.daily.len(), 29);
+        assert_eq!(u1.daily[0], Amount::from_micros(4_000_000));
+        assert_eq!(u1.daily[28], Amount::from_micros(2_000_000));
+        assert_eq!(
+            u1.models,
+            vec![
+                ("claude".to_string(), Amount::from_micros(3_000_000)),
+                ("m2".to_string(), Amount::from_micros(3_000_000)),
+            ]
+        );
+        assert_eq!(u1.path(), "statements/2024-02/u1.pdf");
+    }
+
+    #[test]
+    fn statements_fold_extra_models_into_other() {
+        let month = parse_month("2024-05").unwrap();
+        let rows: Vec<CostRow> = (0..MAX_MODELS + 2)
+            .map(|i| row("2024-05-01", "u1", &format!("m{i:02}"), 1_000_000))
+            .collect();
+        let found = statements(
+            &rows,
+            month,
+            Metric::Blended,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let models = &found[0].models;
+        assert_eq!(models.len(), MAX_MODELS);
+        assert_eq!(
+            models[MAX_MODELS - 1],
+            ("Other".to_string(), Amount::from_micros(3_000_000))
+        );
+    }
+
+    #[test]
+    fn render_pdf_writes_pdf() {
+        let month = parse_month("2024-05").unwrap();
+        let rows = vec![row("2024-05-03", "u1", "m1", 1_500_000)];
+        let found = statements(
+            &rows,
+            month,
+            Metric::Blended,
+            &HashMap::new(),
+            &HashMap::new(),
+        );
+        let bytes = render_pdf(&found[0]).unwrap();
+        assert!(bytes.starts_with(b"%PDF"));
+    }
+
+    #[test]
+    fn parse_month_rejects_garbage() {
+        assert_eq!(
+            parse_month("2024-05").unwrap(),
+            NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()
+        );
+        assert!(parse_month("2024-13").is_err());
+        assert!(parse_month("May").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+use std::cmp::Reverse;
+
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use common::{Amount, ReportKind};
+
+/// Users listed by the top spenders report.
+const TOP_SPENDERS: usize = 10;
+
+/// Rows listed per breakdown of the other reports; the rest are summed as
+/// "Other".
+const MAX_ROWS: usize = 20;
+
+/// The cost of one report period. Users and models are labeled by email and
+/// name when known, otherwise by id.
+#[derive(Debug, Clone, Default)]
+pub struct ReportData {
+    pub currency: String,
+    /// Cost per day, `YYYY-MM-DD`, in date order.
+    pub daily: Vec<(String, Amount)>,
+    pub users: Vec<(String, Amount)>,
+    pub models: Vec<(String, Amount)>,
+}
+
+/// "2025-03-11", "February 2025" or "2025-03-03 to 2025-03-09" for the
+/// period `[start, end)`.
+fn period_label(start: NaiveDate, end: NaiveDate) -> String {
+    let last = end - Duration::days(1);
+    if last == start {
+        start.to_string()
+    } else if start.day() == 1 && start + Months::new(1) == end {
+        start.format("%B %Y").to_string()
+    } else {
+        format!("{} to {}", start, last)
+    }
+}
+
+/// `rows` highest first, at most `limit` of them plus an "Other" row
+/// summing the rest when `other`.
+fn ranked(rows: &[(String, Amount)], limit: usize, other: bool) -> Vec<(String, Amount)> {
+    let mut rows = rows.to_vec();
+    rows.sort_by_key(|(label, amount)| (Reverse(*amount), label.clone()));
+    if rows.len() > limit {
+        let rest: Amount = rows[limit..].iter().map(|(_, amount)| *amount).sum();
+        rows.truncate(limit);
+        if other {
+            rows.push(("Other".to_string(), rest));
+        }
+    }
+    rows
+}
+
+fn section(body: &mut String, title: &str, rows: &[(String, Amount)], currency: &str) {
+    body.push_str(&format!("\n{title}:\n"));
+    if rows.is_empty() {
+        body.push_str("  No cost in this period.\n");
+    }
+    for (label, amount) in rows {
+        let amount = format!("{amount:.2}");
+        body.push_str(&format!("  {label:<40} {amount:>12} {currency}\n"));
+    }
+}
+
+/// The subject and plain text body of a `kind` report over `[start, end)`.
+pub fn render(
+    kind: ReportKind,
+    start: NaiveDate,
+    end: NaiveDate,
+    data: &ReportData,
+) -> (String, String) {
+    let period = period_label(start, end);
+    let currency = data.currency.as_str();
+    let total: Amount = data.daily.iter().map(|(_, amount)| *amount).sum();
+    let subject = format!("LLM gateway {} for {}", kind.label().to_lowercase(), period);
+    let mut body = format!(
+        "{} for {}\n\nTotal cost: {:.2} {}\n",
+        kind.label(),
+        period,
+        total,
+        currency
+    );
+    match kind {
+        ReportKind::DailySummary => {
+            if data.daily.len() > 1 {
+                section(&mut body, "By day", &data.daily, currency);
+            }
+            section(
+                &mut body,
+                "By model",
+                &ranked(&data.models, MAX_ROWS, true),
+                currency,
+            );
+        }
+        ReportKind::TopSpenders => {
+            let title = format!("Top {} users", TOP_SPENDERS);
+            let top = ranked(&data.users, TOP_SPENDERS, false);
+            section(&mut body, &title, &top, currency);
+        }
+        ReportKind::MonthlyStatement => {
+            section(
+                &mut body,
+                "By model",
+                &ranked(&data.models, MAX_ROWS, true),
+                currency,
+            );
+            section(
+                &mut body,
+                "By user",
+                &ranked(&data.users, MAX_ROWS, true),
+                currency,
+            );
+        }
+    }
+    (subject, body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn rows(pairs: &[(&str, f64)]) -> Vec<(String, Amount)> {
+        pairs
+            .iter()
+            .map(|(label, amount)| (label.to_string(), Amount::from_f64(*amount)))
+            .collect()
+    }
+
+    #[test]
+    fn period_labels() {
+        assert_eq!(
+            period_label(date("2025-03-11"), date("2025-03-12")),
+            "2025-03-11"
+        );
+        assert_eq!(
+            period_label(date("2025-02-01"), date("2025-03-01")),
+            "February 2025"
+        );
+        assert_eq!(
+            period_label(date("2025-03-03"), date("2025-03-10")),
+            "2025-03-03 to 2025-03-09"
+        );
+    }
+
+    #[test]
+    fn top_spenders_lists_highest_users() {
+        let data = ReportData {
+            currency: "USD".to_string(),
+            daily: rows(&[("2025-03-03", 4.0), ("2025-03-04", 8.0)]),
+            users: (0..12)
+                .map(|n| (format!("u{n}@example.com"), Amount::from_f64(n as f64)))
+                .collect(),
+            models: vec![],
+        };
+        let (subject, body) = render(
+            ReportKind::TopSpenders,
+            date("2025-03-03"),
+            date("2025-03-10"),
+            &data,
+        );
+        assert_eq!(
+            subject,
+            "LLM gateway weekly top spenders for 2025-03-03 to 2025-03-09"
+        );
+        assert!(body.contains("Total cost: 12.00 USD"));
+        assert!(body.contains("Top 10 users:"));
+        assert!(body.contains("u11@example.com"));
+        assert!(body.contains("u2@example.com"));
+        assert!(!body.contains("u1@example.com"));
+        assert!(!body.contains("Other"));
+        assert!(body.find("u11@").unwrap() < body.find("u10@").unwrap());
+    }
+
+    #[test]
+    fn statement_sums_the_rest_as_other() {
+        let data = ReportData {
+            currency: "USD".to_string(),
+            daily: rows(&[("2025-02-01", 30.0)]),
+            users: rows(&[("a@example.com", 30.0)]),
+            models: (0..22)
+                .map(|n| (format!("model-{n:02}"), Amount::from_f64(1.0 + n as f64)))
+                .collect(),
+        };
+        let (subject, body) = render(
+            ReportKind::MonthlyStatement,
+            date("2025-02-01"),
+            date("2025-03-01"),
+            &data,
+        );
+        assert_eq!(subject, "LLM gateway monthly statement for February 2025");
+        assert!(body.contains("By model:"));
+        assert!(body.contains("By user:"));
+        assert!(body.contains("a@example.com"));
+        // model-00 and model-01 cost 1 and 2.
+        assert!(body.contains(&format!("  {:<40} {:>12} USD", "Other", "3.00")));
+        assert!(!body.contains("model-01"));
+    }
+
+    #[test]
+    fn daily_summary_of_one_day_skips_the_day_list() {
+        let data = ReportData {
+            currency: "USD".to_string(),
+            daily: rows(&[("2025-03-11", 5.0)]),
+            users: vec![],
+            models: vec![],
+        };
+        let (_, body) = render(
+            ReportKind::DailySummary,
+            date("2025-03-11"),
+            date("2025-03-12"),
+            &data,
+        );
+        assert!(!body.contains("By day:"));
+        assert!(body.contains("No cost in this period."));
+    }
+}
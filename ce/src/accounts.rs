@@ -0,0 +1,120 @@
+use std::sync::Arc;
+
+use aws_config::sts::AssumeRoleProvider;
+use aws_config::BehaviorVersion;
+use aws_sdk_costexplorer::config::{Credentials, Region};
+use aws_sdk_costexplorer::Client;
+use serde::Deserialize;
+use tokio::sync::OnceCell;
+
+const SESSION_NAME: &str = "llm-proxy-cost";
+
+/// A payer account whose CE data is read through an assumed role, from an
+/// `[[aws_accounts]]` config entry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct AwsAccount {
+    /// Shown in logs and errors.
+    pub name: String,
+    pub role_arn: String,
+    /// Required when the role's trust policy asks for one.
+    #[serde(default)]
+    pub external_id: Option<String>,
+}
+
+struct Slot {
+    account: Option<AwsAccount>,
+    client: OnceCell<Client>,
+}
+
+/// CE clients for every configured account, or for the ambient credentials
+/// when no accounts are configured. Each client is built on first use and
+/// kept; its assume-role provider caches the temporary credentials and
+/// refreshes them before they expire. Cheap to clone.
+#[derive(Clone)]
+pub struct Clients {
+    slots: Arc<[Slot]>,
+}
+
+impl Clients {
+    pub fn new(accounts: Vec<AwsAccount>) -> Self {
+        let slots: Vec<Slot> = if accounts.is_empty() {
+            vec![Slot {
+                account: None,
+                client: OnceCell::new(),
+            }]
+        } else {
+            accounts
+                .into_iter()
+                .map(|account| Slot {
+                    account: Some(account),
+                    client: OnceCell::new(),
+                })
+                .collect()
+        };
+        Self {
+            slots: slots.into(),
+        }
+    }
+
+    /// A single client sending every request to `endpoint_url`, such as a
+    /// local stand-in for CE, with fixed credentials instead of the ambient
+    /// ones.
+    pub fn with_endpoint(endpoint_url: &str) -> Self {
+        let config = aws_sdk_costexplorer::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .endpoint_url(endpoint_url)
+            .credentials_provider(Credentials::new("test", "test", None, None, "static"))
+            .build();
+        let slot = Slot {
+            account: None,
+            client: OnceCell::from(Client::from_conf(config)),
+        };
+        Self {
+            slots: vec![slot].into(),
+        }
+    }
+
+    /// Each account's name (`default` for the ambient credentials) and client.
+    pub async fn all(&self) -> Vec<(&str, Client)> {
+        let mut clients = Vec::with_capacity(self.slots.len());
+        for slot in self.slots.iter() {
+            let client = slot
+                .client
+                .get_or_init(|| connect(slot.account.as_ref()))
+                .await;
+            let name = slot.account.as_ref().map_or("default", |a| a.name.as_str());
+            clients.push((name, client.clone()));
+        }
+        clients
+    }
+}
+
+impl Default for Clients {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+async fn connect(account: Option<&AwsAccount>) -> Client {
+    let base = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let Some(account) = account else {
+        return Client::new(&base);
+    };
+    let mut role = AssumeRoleProvider::builder(&account.role_arn)
+        .session_name(SESSION_NAME)
+        .configure(&base);
+    if let Some(external_id) = &account.external_id {
+        role = role.external_id(external_id);
+    }
+    let config = aws_config::defaults(BehaviorVersion::latest())
+        .credentials_provider(role.build().await)
+        .load()
+        .await;
+    log::info!(
+        "Reading CE data of {} as {}",
+        account.name,
+        account.role_arn
+    );
+    Client::new(&config)
+}
@@ -0,0 +1,394 @@
+//! Saved CE responses: recorded from the API once, then replayed to run
+//! against production-shaped data without AWS access or CE charges.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use anyhow::{Context, Result};
+use aws_sdk_costexplorer::operation::get_cost_and_usage::builders::GetCostAndUsageFluentBuilder;
+use aws_sdk_costexplorer::operation::get_cost_and_usage::GetCostAndUsageOutput;
+use aws_sdk_costexplorer::types::{
+    DateInterval, Dimension, DimensionValuesWithAttributes, Expression, Group, MetricValue,
+    ResultByTime,
+};
+use serde::{Deserialize, Serialize};
+
+/// Where CE responses are saved to or answered from, one JSON file per
+/// account, query and page.
+#[derive(Debug, Clone)]
+pub enum Fixtures {
+    /// Call CE and save every response under the directory.
+    Record(PathBuf),
+    /// Answer from the responses saved under the directory without calling
+    /// CE; a query with no saved response fails.
+    Replay(PathBuf),
+}
+
+static FIXTURES: OnceLock<Fixtures> = OnceLock::new();
+
+/// Records or replays every CE query the process makes from now on. Only the
+/// first call takes effect.
+pub fn use_fixtures(fixtures: Fixtures) {
+    match &fixtures {
+        Fixtures::Record(dir) => log::info!("Recording CE responses to {}", dir.display()),
+        Fixtures::Replay(dir) => log::info!("Replaying CE responses from {}", dir.display()),
+    }
+    if FIXTURES.set(fixtures).is_err() {
+        log::warn!("CE fixtures were already set; keeping the first");
+    }
+}
+
+pub(crate) fn active() -> Option<&'static Fixtures> {
+    FIXTURES.get()
+}
+
+impl Fixtures {
+    fn dir(&self) -> &Path {
+        match self {
+            Fixtures::Record(dir) | Fixtures::Replay(dir) => dir,
+        }
+    }
+
+    /// The file holding the response to `req` for `account`.
+    pub(crate) fn path(&self, account: &str, req: &GetCostAndUsageFluentBuilder) -> PathBuf {
+        let period = req.get_time_period().as_ref();
+        let granularity = req.get_granularity().as_ref().map(|g| g.as_str());
+        let metrics = req.get_metrics().as_deref().unwrap_or_default();
+        let groups: Vec<&str> = req
+            .get_group_by()
+            .iter()
+            .flatten()
+            .filter_map(|g| g.key())
+            .collect();
+        let mut record_types = Vec::new();
+        if let Some(filter) = req.get_filter() {
+            collect_record_types(filter, &mut record_types);
+        }
+        let name = file_name(
+            period.map_or("", |p| p.start()),
+            period.map_or("", |p| p.end()),
+            granularity.unwrap_or("NONE"),
+            metrics,
+            &groups,
+            &record_types,
+            req.get_next_page_token().as_deref(),
+        );
+        self.dir().join(sanitize(account)).join(name)
+    }
+}
+
+/// The `RECORD_TYPE` values `filter` restricts a query to, which is what
+/// tells the credits query apart from the cost query over the same groups.
+fn collect_record_types<'a>(filter: &'a Expression, types: &mut Vec<&'a str>) {
+    if let Some(dimension) = filter.dimensions() {
+        if dimension.key() == Some(&Dimension::RecordType) {
+            types.extend(dimension.values().iter().map(String::as_str));
+        }
+    }
+    for inner in filter.and().iter().chain(filter.or()).chain(filter.not()) {
+        collect_record_types(inner, types);
+    }
+}
+
+/// `{start}_{end}_{granularity}_{metrics}_{groups}`, then `_{record types}`
+/// for queries restricted to some, plus a hash of the page token for pages
+/// after the first.
+fn file_name(
+    start: &str,
+    end: &str,
+    granularity: &str,
+    metrics: &[String],
+    groups: &[&str],
+    record_types: &[&str],
+    page: Option<&str>,
+) -> String {
+    let mut name = [
+        start,
+        end,
+        granularity,
+        metrics.join("+").as_str(),
+        groups.join("+").as_str(),
+    ]
+    .map(sanitize)
+    .join("_");
+    if !record_types.is_empty() {
+        name.push('_');
+        name.push_str(&sanitize(&record_types.join("+")));
+    }
+    if let Some(page) = page {
+        name.push_str(&format!("_page-{:016x}", fnv1a(page)));
+    }
+    name.push_str(".json");
+    name
+}
+
+/// Keeps letters, digits, `-`, `+` and `.` so a key is one path component.
+fn sanitize(s: &str) -> String {
+    s.chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '-' | '+' | '.' => c,
+            _ => '-',
+        })
+        .collect()
+}
+
+/// A hash that stays the same across Rust releases, unlike `DefaultHasher`.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+pub(crate) fn load(path: &Path) -> Result<GetCostAndUsageOutput> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("No recorded CE response at {}", path.display()))?;
+    let saved: Response = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid CE fixture {}", path.display()))?;
+    saved.into_output()
+}
+
+pub(crate) fn save(path: &Path, resp: &GetCostAndUsageOutput) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+    }
+    let text = serde_json::to_string_pretty(&Response::from_output(resp))?;
+    std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// The parts of a `GetCostAndUsage` response that queries read.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Response {
+    #[serde(default)]
+    next_page_token: Option<String>,
+    results_by_time: Vec<TimeResult>,
+    #[serde(default)]
+    dimension_values: Vec<DimensionValue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct TimeResult {
+    start: String,
+    end: String,
+    #[serde(default)]
+    groups: Vec<GroupResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct GroupResult {
+    keys: Vec<String>,
+    metrics: BTreeMap<String, MetricResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct MetricResult {
+    amount: Option<String>,
+    unit: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct DimensionValue {
+    value: Option<String>,
+    #[serde(default)]
+    attributes: BTreeMap<String, String>,
+}
+
+impl Response {
+    fn from_output(resp: &GetCostAndUsageOutput) -> Self {
+        let metrics = |m: Option<&HashMap<String, MetricValue>>| -> BTreeMap<_, _> {
+            m.into_iter()
+                .flatten()
+                .map(|(name, value)| {
+                    let metric = MetricResult {
+                        amount: value.amount().map(str::to_string),
+                        unit: value.unit().map(str::to_string),
+                    };
+                    (name.clone(), metric)
+                })
+                .collect()
+        };
+        Self {
+            next_page_token: resp.next_page_token().map(str::to_string),
+            results_by_time: resp
+                .results_by_time()
+                .iter()
+                .map(|r| TimeResult {
+                    start: r.time_period().map_or("", |p| p.start()).to_string(),
+                    end: r.time_period().map_or("", |p| p.end()).to_string(),
+                    groups: r
+                        .groups()
+                        .iter()
+                        .map(|g| GroupResult {
+                            keys: g.keys().to_vec(),
+                            metrics: metrics(g.metrics()),
+                        })
+                        .collect(),
+                })
+                .collect(),
+            dimension_values: resp
+                .dimension_value_attributes()
+                .iter()
+                .map(|d| DimensionValue {
+                    value: d.value().map(str::to_string),
+                    attributes: d
+                        .attributes()
+                        .into_iter()
+                        .flatten()
+                        .map(|(k, v)| (k.clone(), v.clone()))
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn into_output(self) -> Result<GetCostAndUsageOutput> {
+        let mut results = Vec::with_capacity(self.results_by_time.len());
+        for result in self.results_by_time {
+            let groups = result
+                .groups
+                .into_iter()
+                .map(|g| {
+                    let metrics = g
+                        .metrics
+                        .into_iter()
+                        .map(|(name, m)| {
+                            let value = MetricValue::builder()
+                                .set_amount(m.amount)
+                                .set_unit(m.unit)
+                                .build();
+                            (name, value)
+                        })
+                        .collect();
+                    Group::builder()
+                        .set_keys(Some(g.keys))
+                        .set_metrics(Some(metrics))
+                        .build()
+                })
+                .collect();
+            results.push(
+                ResultByTime::builder()
+                    .time_period(
+                        DateInterval::builder()
+                            .start(result.start)
+                            .end(result.end)
+                            .build()?,
+                    )
+                    .set_groups(Some(groups))
+                    .build(),
+            );
+        }
+        let dimension_values = self
+            .dimension_values
+            .into_iter()
+            .map(|d| {
+                DimensionValuesWithAttributes::builder()
+                    .set_value(d.value)
+                    .set_attributes(Some(d.attributes.into_iter().collect()))
+                    .build()
+            })
+            .collect();
+        Ok(GetCostAndUsageOutput::builder()
+            .set_next_page_token(self.next_page_token)
+            .set_results_by_time(Some(results))
+            .set_dimension_value_attributes(Some(dimension_values))
+            .build())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_costexplorer::types::{DimensionValues, GroupDefinition, GroupDefinitionType};
+
+    #[test]
+    fn file_names_follow_the_query() {
+        let client = aws_sdk_costexplorer::Client::from_conf(
+            aws_sdk_costexplorer::Config::builder()
+                .behavior_version(aws_sdk_costexplorer::config::BehaviorVersion::latest())
+                .build(),
+        );
+        let req = client
+            .get_cost_and_usage()
+            .time_period(
+                DateInterval::builder()
+                    .start("2025-03-01")
+                    .end("2025-03-08")
+                    .build()
+                    .unwrap(),
+            )
+            .granularity(aws_sdk_costexplorer::types::Granularity::Daily)
+            .metrics("BlendedCost")
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Tag)
+                    .key("GatewayUserId")
+                    .build(),
+            );
+        let fixtures = Fixtures::Replay(PathBuf::from("fixtures"));
+        assert_eq!(
+            fixtures.path("prod payer", &req),
+            Path::new(
+                "fixtures/prod-payer/2025-03-01_2025-03-08_DAILY_BlendedCost_GatewayUserId.json"
+            )
+        );
+        let credits = req.clone().filter(
+            Expression::builder()
+                .dimensions(
+                    DimensionValues::builder()
+                        .key(Dimension::RecordType)
+                        .values("Credit")
+                        .values("Refund")
+                        .build(),
+                )
+                .build(),
+        );
+        assert_eq!(
+            fixtures.path("prod payer", &credits),
+            Path::new(
+                "fixtures/prod-payer/2025-03-01_2025-03-08_DAILY_BlendedCost_GatewayUserId_Credit+Refund.json"
+            )
+        );
+        let next = fixtures.path("prod payer", &req.next_page_token("abc/="));
+        let name = next.file_name().unwrap().to_str().unwrap();
+        assert!(name.starts_with("2025-03-01_2025-03-08_DAILY_BlendedCost_GatewayUserId_page-"));
+        assert_eq!(fnv1a("abc/="), fnv1a("abc/="));
+        assert_ne!(fnv1a("abc/="), fnv1a("abc/+"));
+    }
+
+    #[test]
+    fn responses_survive_a_round_trip() {
+        let saved: Response = serde_json::from_str(
+            r#"{
+                "next_page_token": "next",
+                "results_by_time": [{
+                    "start": "2025-03-01",
+                    "end": "2025-03-02",
+                    "groups": [{
+                        "keys": ["GatewayUserId$u1", "GatewayModelId$m1"],
+                        "metrics": {"BlendedCost": {"amount": "1.25", "unit": "USD"}}
+                    }]
+                }],
+                "dimension_values": [
+                    {"value": "123456789012", "attributes": {"description": "Prod"}}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let output = saved.clone().into_output().unwrap();
+        assert_eq!(output.next_page_token(), Some("next"));
+        let group = &output.results_by_time()[0].groups()[0];
+        assert_eq!(group.keys()[1], "GatewayModelId$m1");
+        assert_eq!(
+            group.metrics().unwrap()["BlendedCost"].amount(),
+            Some("1.25")
+        );
+        assert_eq!(Response::from_output(&output), saved);
+
+        let dir = std::env::temp_dir().join(format!("ce-fixtures-{}", std::process::id()));
+        let path = dir.join("default").join("response.json");
+        save(&path, &output).unwrap();
+        assert_eq!(Response::from_output(&load(&path).unwrap()), saved);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
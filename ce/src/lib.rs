@@ -1,30 +1,205 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::sync::LazyLock;
+use std::time::Duration;
+
 use anyhow::{Context, Result};
+use aws_sdk_costexplorer::error::{DisplayErrorContext, SdkError};
+use aws_sdk_costexplorer::operation::get_cost_and_usage::builders::GetCostAndUsageFluentBuilder;
+use aws_sdk_costexplorer::operation::get_cost_and_usage::{
+    GetCostAndUsageError, GetCostAndUsageOutput,
+};
 use aws_sdk_costexplorer::types::{
-    DateInterval, Expression, Granularity, GroupDefinition, GroupDefinitionType, TagValues,
+    DateInterval, Dimension, DimensionValues, Expression, Granularity, GroupDefinition,
+    GroupDefinitionType, MatchOption, TagValues,
 };
-use aws_sdk_costexplorer::Client;
+pub use aws_sdk_costexplorer::Client;
 use chrono::NaiveDate;
-use common::CostRow;
+use common::{
+    Amount, CostByAccount, CostByApiKey, CostByRegion, CostRow, Metric, ServiceCostRow,
+    TokenUsageRow, UserTokenUsage, AWS_SOURCE, CREDIT_SOURCE, DEFAULT_TENANT,
+};
+use governor::{DefaultDirectRateLimiter, Quota, RateLimiter};
 
-pub async fn new_client() -> Client {
-    let config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
-    Client::new(&config)
-}
+mod accounts;
+mod fixtures;
+mod stats;
+
+pub use accounts::{AwsAccount, Clients};
+pub use fixtures::{use_fixtures, Fixtures};
+pub use stats::{recent_calls, CallStats};
+use stats::Outcome;
+
+/// CE allows a handful of requests per second per account; drill-down pages
+/// issue several at once, so every call in the process shares one limiter.
+const REQUESTS_PER_SECOND: NonZeroU32 = NonZeroU32::new(5).unwrap();
+const MAX_RETRIES: u32 = 5;
+const RETRY_DELAY: Duration = Duration::from_millis(500);
 
+static LIMITER: LazyLock<DefaultDirectRateLimiter> =
+    LazyLock::new(|| RateLimiter::direct(Quota::per_second(REQUESTS_PER_SECOND)));
+
+/// Daily cost per gateway user and model, one row per requested metric.
 pub async fn get_daily_cost_by_user_and_model(
-    client: &Client,
+    clients: &Clients,
     start: &str,
     end: &str,
+    metrics: &[Metric],
 ) -> Result<Vec<CostRow>> {
+    let (rows, _calls) =
+        get_daily_cost_by_user_and_model_counted(clients, start, end, metrics).await?;
+    Ok(rows)
+}
+
+/// Same as [`get_daily_cost_by_user_and_model`], also returning how many CE
+/// API calls (pages) the fetch took.
+pub async fn get_daily_cost_by_user_and_model_counted(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metrics: &[Metric],
+) -> Result<(Vec<CostRow>, usize)> {
+    let mut rows = Vec::new();
+    let mut calls = 0;
+    for (account, client) in clients.all().await {
+        let (account_rows, account_calls) = daily_cost_by_user_and_model(
+            account,
+            &client,
+            start,
+            end,
+            metrics,
+            gateway_filter(),
+            AWS_SOURCE,
+        )
+        .await
+        .with_context(|| format!("account {account}"))?;
+        rows.extend(account_rows);
+        calls += account_calls;
+    }
+    Ok((merge_cost_rows(rows), calls))
+}
+
+/// Daily credits and refunds per gateway user and model, as negative
+/// [`CREDIT_SOURCE`] rows, with the CE API calls the fetch took. CE already
+/// counts them in [`get_daily_cost_by_user_and_model`]'s figures.
+pub async fn get_daily_credits_by_user_and_model_counted(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metrics: &[Metric],
+) -> Result<(Vec<CostRow>, usize)> {
+    let mut rows = Vec::new();
+    let mut calls = 0;
+    for (account, client) in clients.all().await {
+        let (account_rows, account_calls) = daily_cost_by_user_and_model(
+            account,
+            &client,
+            start,
+            end,
+            metrics,
+            credit_filter(),
+            CREDIT_SOURCE,
+        )
+        .await
+        .with_context(|| format!("account {account}"))?;
+        rows.extend(account_rows);
+        calls += account_calls;
+    }
+    Ok((merge_cost_rows(rows), calls))
+}
+
+/// Daily cost per gateway user and model with credits and refunds split
+/// out: the [`AWS_SOURCE`] rows carry the gross cost and the
+/// [`CREDIT_SOURCE`] rows the credits, so both still sum to CE's net cost.
+pub async fn get_daily_gross_cost_and_credits_counted(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metrics: &[Metric],
+) -> Result<(Vec<CostRow>, usize)> {
+    let (net, net_calls) =
+        get_daily_cost_by_user_and_model_counted(clients, start, end, metrics).await?;
+    let (credits, credit_calls) =
+        get_daily_credits_by_user_and_model_counted(clients, start, end, metrics).await?;
+    Ok((split_credits(net, credits), net_calls + credit_calls))
+}
+
+/// Takes `credits` out of the `net` rows they were applied to, leaving the
+/// gross cost, and appends them. A credit without a net row (a day fully
+/// credited CE may leave out) gets a gross row of its own.
+pub fn split_credits(net: Vec<CostRow>, credits: Vec<CostRow>) -> Vec<CostRow> {
+    let mut rows: BTreeMap<(NaiveDate, String, String, &'static str), CostRow> = net
+        .into_iter()
+        .map(|row| {
+            let key = (
+                row.date,
+                row.user_id.clone(),
+                row.model_id.clone(),
+                row.metric.as_str(),
+            );
+            (key, row)
+        })
+        .collect();
+    for credit in &credits {
+        let key = (
+            credit.date,
+            credit.user_id.clone(),
+            credit.model_id.clone(),
+            credit.metric.as_str(),
+        );
+        let gross = rows.entry(key).or_insert_with(|| CostRow {
+            amount: Amount::ZERO,
+            source: AWS_SOURCE.to_string(),
+            ..credit.clone()
+        });
+        gross.amount -= credit.amount;
+    }
+    rows.into_values().chain(credits).collect()
+}
+
+/// Sums rows of the same day, user, model and metric, as several payer
+/// accounts can bill the same gateway user and model.
+fn merge_cost_rows(rows: Vec<CostRow>) -> Vec<CostRow> {
+    let mut merged: BTreeMap<(NaiveDate, String, String, &'static str), CostRow> = BTreeMap::new();
+    for row in rows {
+        let key = (
+            row.date,
+            row.user_id.clone(),
+            row.model_id.clone(),
+            row.metric.as_str(),
+        );
+        match merged.get_mut(&key) {
+            Some(existing) => existing.amount += row.amount,
+            None => {
+                merged.insert(key, row);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+async fn daily_cost_by_user_and_model(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    metrics: &[Metric],
+    filter: Expression,
+    source: &str,
+) -> Result<(Vec<CostRow>, usize)> {
     let mut results = Vec::new();
     let mut next_page_token: Option<String> = None;
+    let mut calls = 0usize;
 
     loop {
         let mut req = client
             .get_cost_and_usage()
             .time_period(DateInterval::builder().start(start).end(end).build()?)
             .granularity(Granularity::Daily)
-            .metrics("BlendedCost")
+            .set_metrics(Some(
+                metrics.iter().map(|m| m.as_str().to_string()).collect(),
+            ))
             .group_by(
                 GroupDefinition::builder()
                     .r#type(GroupDefinitionType::Tag)
@@ -37,48 +212,14 @@ pub async fn get_daily_cost_by_user_and_model(
                     .key("GatewayModelId")
                     .build(),
             )
-            .filter(
-                Expression::builder()
-                    .and(
-                        Expression::builder()
-                            .not(
-                                Expression::builder()
-                                    .tags(
-                                        TagValues::builder()
-                                            .key("GatewayUserId")
-                                            .match_options(
-                                                aws_sdk_costexplorer::types::MatchOption::Absent,
-                                            )
-                                            .build(),
-                                    )
-                                    .build(),
-                            )
-                            .build(),
-                    )
-                    .and(
-                        Expression::builder()
-                            .not(
-                                Expression::builder()
-                                    .tags(
-                                        TagValues::builder()
-                                            .key("GatewayModelId")
-                                            .match_options(
-                                                aws_sdk_costexplorer::types::MatchOption::Absent,
-                                            )
-                                            .build(),
-                                    )
-                                    .build(),
-                            )
-                            .build(),
-                    )
-                    .build(),
-            );
+            .filter(filter.clone());
 
         if let Some(token) = &next_page_token {
             req = req.next_page_token(token.clone());
         }
 
-        let resp = req.send().await?;
+        let resp = send(account, req).await?;
+        calls += 1;
 
         for result_by_time in resp.results_by_time() {
             let date_str = result_by_time
@@ -103,11 +244,98 @@ pub async fn get_daily_cost_by_user_and_model(
                     continue;
                 }
 
-                let (amount, currency) = extract_blended_cost(group.metrics());
-                results.push(CostRow {
+                for &metric in metrics {
+                    let (amount, currency) = extract_cost(group.metrics(), metric);
+                    results.push(CostRow {
+                        date,
+                        user_id: user_id.to_string(),
+                        model_id: model_id.to_string(),
+                        amount,
+                        currency,
+                        metric,
+                        source: source.to_string(),
+                        // Callers that serve several gateways assign the
+                        // tenant by the ids.
+                        tenant: DEFAULT_TENANT.to_string(),
+                    });
+                }
+            }
+        }
+
+        next_page_token = resp.next_page_token().map(|s| s.to_string());
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok((results, calls))
+}
+
+/// Daily cost per AWS service of everything carrying the gateway tags, so
+/// Bedrock inference can be told apart from S3/CloudWatch overhead.
+pub async fn get_daily_cost_by_service(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metric: Metric,
+) -> Result<Vec<ServiceCostRow>> {
+    let mut rows = Vec::new();
+    for (account, client) in clients.all().await {
+        rows.extend(
+            daily_cost_by_service(account, &client, start, end, metric)
+                .await
+                .with_context(|| format!("account {account}"))?,
+        );
+    }
+    Ok(rows)
+}
+
+async fn daily_cost_by_service(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    metric: Metric,
+) -> Result<Vec<ServiceCostRow>> {
+    let mut results = Vec::new();
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = client
+            .get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start).end(end).build()?)
+            .granularity(Granularity::Daily)
+            .metrics(metric.as_str())
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Dimension)
+                    .key("SERVICE")
+                    .build(),
+            )
+            .filter(gateway_filter());
+
+        if let Some(token) = &next_page_token {
+            req = req.next_page_token(token.clone());
+        }
+
+        let resp = send(account, req).await?;
+
+        for result_by_time in resp.results_by_time() {
+            let date_str = result_by_time
+                .time_period()
+                .map(|tp| tp.start().to_string())
+                .unwrap_or_default();
+            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .context("invalid date from CE API")?;
+
+            for group in result_by_time.groups() {
+                let Some(service) = group.keys().first() else {
+                    continue;
+                };
+                let (amount, currency) = extract_cost(group.metrics(), metric);
+                results.push(ServiceCostRow {
                     date,
-                    user_id: user_id.to_string(),
-                    model_id: model_id.to_string(),
+                    service: service.clone(),
                     amount,
                     currency,
                 });
@@ -123,17 +351,613 @@ pub async fn get_daily_cost_by_user_and_model(
     Ok(results)
 }
 
-fn extract_blended_cost(
+/// Cost per AWS region and gateway model over the whole range, highest
+/// first.
+pub async fn get_cost_by_region(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metric: Metric,
+) -> Result<Vec<CostByRegion>> {
+    let mut totals: BTreeMap<(String, String), (Amount, String)> = BTreeMap::new();
+    for (account, client) in clients.all().await {
+        region_totals(account, &client, start, end, metric, &mut totals)
+            .await
+            .with_context(|| format!("account {account}"))?;
+    }
+
+    let mut results: Vec<CostByRegion> = totals
+        .into_iter()
+        .map(|((region, model_id), (amount, currency))| CostByRegion {
+            region,
+            model_id,
+            model_name: None,
+            amount,
+            currency,
+        })
+        .collect();
+    results.sort_by_key(|c| Reverse(c.amount));
+    Ok(results)
+}
+
+/// Adds one account's cost per region and model to `totals`.
+async fn region_totals(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    metric: Metric,
+    totals: &mut BTreeMap<(String, String), (Amount, String)>,
+) -> Result<()> {
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = client
+            .get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start).end(end).build()?)
+            .granularity(Granularity::Monthly)
+            .metrics(metric.as_str())
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Dimension)
+                    .key("REGION")
+                    .build(),
+            )
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Tag)
+                    .key("GatewayModelId")
+                    .build(),
+            )
+            .filter(gateway_filter());
+
+        if let Some(token) = &next_page_token {
+            req = req.next_page_token(token.clone());
+        }
+
+        let resp = send(account, req).await?;
+
+        // Monthly granularity still splits ranges that cross a month.
+        for result_by_time in resp.results_by_time() {
+            for group in result_by_time.groups() {
+                let keys: Vec<&str> = group.keys().iter().map(|s| s.as_str()).collect();
+                let region = keys.first().copied().unwrap_or_default();
+                let model_id = keys
+                    .get(1)
+                    .map(|k| k.strip_prefix("GatewayModelId$").unwrap_or(k))
+                    .unwrap_or_default();
+
+                if region.is_empty() || model_id.is_empty() {
+                    continue;
+                }
+
+                let (amount, currency) = extract_cost(group.metrics(), metric);
+                totals
+                    .entry((region.to_string(), model_id.to_string()))
+                    .and_modify(|(total, _)| *total += amount)
+                    .or_insert((amount, currency));
+            }
+        }
+
+        next_page_token = resp.next_page_token().map(|s| s.to_string());
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// One gateway user's cost per API key over the whole range, highest first,
+/// read from the `GatewayApiKeyId` tag. Cost without the tag is reported
+/// under an empty key id.
+pub async fn get_cost_by_api_key(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    user_id: &str,
+    metric: Metric,
+) -> Result<Vec<CostByApiKey>> {
+    let mut totals: BTreeMap<String, (Amount, String)> = BTreeMap::new();
+    for (account, client) in clients.all().await {
+        api_key_totals(account, &client, start, end, user_id, metric, &mut totals)
+            .await
+            .with_context(|| format!("account {account}"))?;
+    }
+
+    let mut results: Vec<CostByApiKey> = totals
+        .into_iter()
+        .map(|(api_key_id, (amount, currency))| CostByApiKey {
+            api_key_id,
+            amount,
+            currency,
+        })
+        .collect();
+    results.sort_by_key(|c| Reverse(c.amount));
+    Ok(results)
+}
+
+/// Adds one account's cost per API key of `user_id` to `totals`.
+async fn api_key_totals(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    user_id: &str,
+    metric: Metric,
+    totals: &mut BTreeMap<String, (Amount, String)>,
+) -> Result<()> {
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = client
+            .get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start).end(end).build()?)
+            .granularity(Granularity::Monthly)
+            .metrics(metric.as_str())
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Tag)
+                    .key("GatewayApiKeyId")
+                    .build(),
+            )
+            .filter(user_filter(user_id));
+
+        if let Some(token) = &next_page_token {
+            req = req.next_page_token(token.clone());
+        }
+
+        let resp = send(account, req).await?;
+
+        // Monthly granularity still splits ranges that cross a month.
+        for result_by_time in resp.results_by_time() {
+            for group in result_by_time.groups() {
+                let api_key_id = group
+                    .keys()
+                    .first()
+                    .map(|k| k.strip_prefix("GatewayApiKeyId$").unwrap_or(k))
+                    .unwrap_or_default();
+
+                let (amount, currency) = extract_cost(group.metrics(), metric);
+                totals
+                    .entry(api_key_id.to_string())
+                    .and_modify(|(total, _)| *total += amount)
+                    .or_insert((amount, currency));
+            }
+        }
+
+        next_page_token = resp.next_page_token().map(|s| s.to_string());
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Cost per linked account over the whole range, highest first. Costs of
+/// the same account seen through several payer accounts are summed.
+pub async fn get_cost_by_linked_account(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+    metric: Metric,
+) -> Result<Vec<CostByAccount>> {
+    let mut totals: BTreeMap<String, CostByAccount> = BTreeMap::new();
+    for (account, client) in clients.all().await {
+        linked_account_totals(account, &client, start, end, metric, &mut totals)
+            .await
+            .with_context(|| format!("account {account}"))?;
+    }
+
+    let mut results: Vec<CostByAccount> = totals.into_values().collect();
+    results.sort_by_key(|c| Reverse(c.amount));
+    Ok(results)
+}
+
+/// Adds one payer account's cost per linked account to `totals`.
+async fn linked_account_totals(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    metric: Metric,
+    totals: &mut BTreeMap<String, CostByAccount>,
+) -> Result<()> {
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = client
+            .get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start).end(end).build()?)
+            .granularity(Granularity::Monthly)
+            .metrics(metric.as_str())
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Dimension)
+                    .key("LINKED_ACCOUNT")
+                    .build(),
+            )
+            .filter(gateway_filter());
+
+        if let Some(token) = &next_page_token {
+            req = req.next_page_token(token.clone());
+        }
+
+        let resp = send(account, req).await?;
+
+        // CE names accounts in the dimension attributes, not in the groups.
+        let names: std::collections::HashMap<&str, &str> = resp
+            .dimension_value_attributes()
+            .iter()
+            .filter_map(|attrs| {
+                let name = attrs.attributes()?.get("description")?;
+                Some((attrs.value()?, name.as_str()))
+            })
+            .collect();
+
+        // Monthly granularity still splits ranges that cross a month.
+        for result_by_time in resp.results_by_time() {
+            for group in result_by_time.groups() {
+                let Some(account_id) = group.keys().first() else {
+                    continue;
+                };
+                let (amount, currency) = extract_cost(group.metrics(), metric);
+                let entry = totals
+                    .entry(account_id.clone())
+                    .or_insert_with(|| CostByAccount {
+                        account_id: account_id.clone(),
+                        account_name: None,
+                        amount: Amount::ZERO,
+                        currency,
+                    });
+                entry.amount += amount;
+                if let Some(name) = names.get(account_id.as_str()) {
+                    entry.account_name = Some(name.to_string());
+                }
+            }
+        }
+
+        next_page_token = resp.next_page_token().map(|s| s.to_string());
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Daily input and output tokens per gateway model, from the usage quantity
+/// of Bedrock's token usage types. Cache reads and writes are left out.
+pub async fn get_daily_token_usage_by_model(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+) -> Result<Vec<TokenUsageRow>> {
+    let mut totals: BTreeMap<(NaiveDate, String), (i64, i64)> = BTreeMap::new();
+    for (account, client) in clients.all().await {
+        token_totals(account, &client, start, end, "GatewayModelId", &mut totals)
+            .await
+            .with_context(|| format!("account {account}"))?;
+    }
+
+    Ok(totals
+        .into_iter()
+        .map(|((date, model_id), (input_tokens, output_tokens))| TokenUsageRow {
+            date,
+            model_id,
+            input_tokens,
+            output_tokens,
+        })
+        .collect())
+}
+
+/// Input and output tokens per gateway user over `[start, end)`, counted
+/// the same way as [`get_daily_token_usage_by_model`].
+pub async fn get_token_usage_by_user(
+    clients: &Clients,
+    start: &str,
+    end: &str,
+) -> Result<Vec<UserTokenUsage>> {
+    let mut daily: BTreeMap<(NaiveDate, String), (i64, i64)> = BTreeMap::new();
+    for (account, client) in clients.all().await {
+        token_totals(account, &client, start, end, "GatewayUserId", &mut daily)
+            .await
+            .with_context(|| format!("account {account}"))?;
+    }
+
+    let mut totals: BTreeMap<String, (i64, i64)> = BTreeMap::new();
+    for ((_, user_id), (input_tokens, output_tokens)) in daily {
+        let entry = totals.entry(user_id).or_default();
+        entry.0 += input_tokens;
+        entry.1 += output_tokens;
+    }
+    Ok(totals
+        .into_iter()
+        .map(|(user_id, (input_tokens, output_tokens))| UserTokenUsage {
+            user_id,
+            input_tokens,
+            output_tokens,
+        })
+        .collect())
+}
+
+/// Adds one account's input and output tokens per day and value of the
+/// cost allocation tag `tag` to `totals`.
+async fn token_totals(
+    account: &str,
+    client: &Client,
+    start: &str,
+    end: &str,
+    tag: &str,
+    totals: &mut BTreeMap<(NaiveDate, String), (i64, i64)>,
+) -> Result<()> {
+    let prefix = format!("{tag}$");
+
+    let mut next_page_token: Option<String> = None;
+
+    loop {
+        let mut req = client
+            .get_cost_and_usage()
+            .time_period(DateInterval::builder().start(start).end(end).build()?)
+            .granularity(Granularity::Daily)
+            .metrics("UsageQuantity")
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Dimension)
+                    .key("USAGE_TYPE")
+                    .build(),
+            )
+            .group_by(
+                GroupDefinition::builder()
+                    .r#type(GroupDefinitionType::Tag)
+                    .key(tag)
+                    .build(),
+            )
+            .filter(gateway_filter());
+
+        if let Some(token) = &next_page_token {
+            req = req.next_page_token(token.clone());
+        }
+
+        let resp = send(account, req).await?;
+
+        for result_by_time in resp.results_by_time() {
+            let date_str = result_by_time
+                .time_period()
+                .map(|tp| tp.start().to_string())
+                .unwrap_or_default();
+            let date = NaiveDate::parse_from_str(&date_str, "%Y-%m-%d")
+                .context("invalid date from CE API")?;
+
+            for group in result_by_time.groups() {
+                let keys: Vec<&str> = group.keys().iter().map(|s| s.as_str()).collect();
+                let usage_type = keys.first().copied().unwrap_or_default();
+                let id = keys
+                    .get(1)
+                    .map(|k| k.strip_prefix(prefix.as_str()).unwrap_or(k))
+                    .unwrap_or_default();
+                let Some(kind) = token_kind(usage_type) else {
+                    continue;
+                };
+                if id.is_empty() {
+                    continue;
+                }
+
+                let Some(quantity) = group.metrics().and_then(|m| m.get("UsageQuantity")) else {
+                    continue;
+                };
+                let tokens = token_count(
+                    quantity.amount().unwrap_or("0").parse().unwrap_or(0.0),
+                    quantity.unit().unwrap_or_default(),
+                );
+                let entry = totals.entry((date, id.to_string())).or_default();
+                match kind {
+                    TokenKind::Input => entry.0 += tokens,
+                    TokenKind::Output => entry.1 += tokens,
+                }
+            }
+        }
+
+        next_page_token = resp.next_page_token().map(|s| s.to_string());
+        if next_page_token.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, PartialEq)]
+enum TokenKind {
+    Input,
+    Output,
+}
+
+/// Bedrock names token usage types either `USE1-Claude3Sonnet-input-tokens`
+/// or, for marketplace models, `USE1-MP:USE1_InputTokenCount-Units`.
+fn token_kind(usage_type: &str) -> Option<TokenKind> {
+    let normalized: String = usage_type
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if normalized.contains("cache") {
+        None
+    } else if normalized.contains("inputtoken") {
+        Some(TokenKind::Input)
+    } else if normalized.contains("outputtoken") {
+        Some(TokenKind::Output)
+    } else {
+        None
+    }
+}
+
+/// Usage quantities come in tokens or, for some models, thousands of tokens.
+fn token_count(quantity: f64, unit: &str) -> i64 {
+    let unit = unit.to_ascii_lowercase();
+    if unit.starts_with("1k") || unit.starts_with("1,000") || unit.starts_with("thousand") {
+        (quantity * 1000.0).round() as i64
+    } else {
+        quantity.round() as i64
+    }
+}
+
+/// Makes one single-day, ungrouped CE query per account to check its
+/// credentials and `ce:GetCostAndUsage` permission. Each call is billed like
+/// any other CE request.
+pub async fn check_access(clients: &Clients) -> Vec<(String, Result<()>)> {
+    let end = chrono::Utc::now().date_naive();
+    let start = end - chrono::Duration::days(1);
+    let mut results = Vec::new();
+    for (account, client) in clients.all().await {
+        let result = async {
+            let req = client
+                .get_cost_and_usage()
+                .time_period(
+                    DateInterval::builder()
+                        .start(start.format("%Y-%m-%d").to_string())
+                        .end(end.format("%Y-%m-%d").to_string())
+                        .build()?,
+                )
+                .granularity(Granularity::Daily)
+                .metrics(Metric::default().as_str());
+            send(account, req).await?;
+            anyhow::Ok(())
+        }
+        .await;
+        results.push((account.to_string(), result));
+    }
+    results
+}
+
+/// Sends `req` for `account`, or answers it from the saved response when
+/// replaying fixtures. Responses are saved when recording them.
+async fn send(account: &str, req: GetCostAndUsageFluentBuilder) -> Result<GetCostAndUsageOutput> {
+    let Some(fixtures) = fixtures::active() else {
+        return send_live(req).await;
+    };
+    let path = fixtures.path(account, &req);
+    match fixtures {
+        Fixtures::Replay(_) => fixtures::load(&path),
+        Fixtures::Record(_) => {
+            let resp = send_live(req).await?;
+            fixtures::save(&path, &resp)?;
+            Ok(resp)
+        }
+    }
+}
+
+/// Sends `req` through the shared rate limiter, retrying with exponential
+/// backoff while CE reports `LimitExceededException`.
+async fn send_live(req: GetCostAndUsageFluentBuilder) -> Result<GetCostAndUsageOutput> {
+    let mut attempt = 0;
+    loop {
+        LIMITER.until_ready().await;
+        let result = req.clone().send().await;
+        match &result {
+            Ok(_) => stats::record(Outcome::Ok, None),
+            Err(e) if is_throttled(e) => stats::record(Outcome::Throttled, None),
+            Err(e) => {
+                stats::record(Outcome::Failed, Some(DisplayErrorContext(e).to_string()))
+            }
+        }
+        match result {
+            Ok(resp) => return Ok(resp),
+            Err(e) if is_throttled(&e) && attempt < MAX_RETRIES => {
+                let delay = backoff(attempt);
+                attempt += 1;
+                log::warn!(
+                    "CE request throttled (attempt {}/{}), retrying in {}ms",
+                    attempt,
+                    MAX_RETRIES + 1,
+                    delay.as_millis()
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+fn is_throttled<R>(err: &SdkError<GetCostAndUsageError, R>) -> bool {
+    err.as_service_error()
+        .is_some_and(|e| e.is_limit_exceeded_exception())
+}
+
+fn backoff(attempt: u32) -> Duration {
+    RETRY_DELAY * 2u32.saturating_pow(attempt)
+}
+
+/// Restricts a query to costs tagged with both a gateway user and model.
+fn gateway_filter() -> Expression {
+    Expression::builder()
+        .and(tagged("GatewayUserId"))
+        .and(tagged("GatewayModelId"))
+        .build()
+}
+
+/// Restricts a query like [`gateway_filter`] to credit and refund line
+/// items.
+fn credit_filter() -> Expression {
+    Expression::builder()
+        .and(tagged("GatewayUserId"))
+        .and(tagged("GatewayModelId"))
+        .and(
+            Expression::builder()
+                .dimensions(
+                    DimensionValues::builder()
+                        .key(Dimension::RecordType)
+                        .values("Credit")
+                        .values("Refund")
+                        .build(),
+                )
+                .build(),
+        )
+        .build()
+}
+
+/// Restricts a query to one gateway user's costs that are tagged with a
+/// model.
+fn user_filter(user_id: &str) -> Expression {
+    Expression::builder()
+        .and(
+            Expression::builder()
+                .tags(TagValues::builder().key("GatewayUserId").values(user_id).build())
+                .build(),
+        )
+        .and(tagged("GatewayModelId"))
+        .build()
+}
+
+/// Costs carrying the tag `key`, with any value.
+fn tagged(key: &str) -> Expression {
+    Expression::builder()
+        .not(
+            Expression::builder()
+                .tags(
+                    TagValues::builder()
+                        .key(key)
+                        .match_options(MatchOption::Absent)
+                        .build(),
+                )
+                .build(),
+        )
+        .build()
+}
+
+fn extract_cost(
     metrics: Option<&std::collections::HashMap<String, aws_sdk_costexplorer::types::MetricValue>>,
-) -> (f64, String) {
+    metric: Metric,
+) -> (Amount, String) {
     metrics
-        .and_then(|m| m.get("BlendedCost"))
+        .and_then(|m| m.get(metric.as_str()))
         .map(|mv| {
-            let amount = mv.amount().unwrap_or("0").parse::<f64>().unwrap_or(0.0);
+            let amount = mv.amount().unwrap_or("0").parse::<Amount>().unwrap_or_default();
             let currency = mv.unit().unwrap_or("USD").to_string();
             (amount, currency)
         })
-        .unwrap_or((0.0, "USD".to_string()))
+        .unwrap_or((Amount::ZERO, "USD".to_string()))
 }
 
 #[cfg(test)]
@@ -141,30 +965,146 @@ mod tests {
     use super::*;
 
     #[test]
-    fn extract_blended_cost_none_metrics() {
-        let (amount, currency) = extract_blended_cost(None);
-        assert!((amount - 0.0).abs() < f64::EPSILON);
+    fn extract_cost_none_metrics() {
+        let (amount, currency) = extract_cost(None, Metric::Blended);
+        assert_eq!(amount, Amount::ZERO);
         assert_eq!(currency, "USD");
     }
 
     #[test]
-    fn extract_blended_cost_with_value() {
+    fn extract_cost_with_value() {
         use aws_sdk_costexplorer::types::MetricValue;
         let mut metrics = std::collections::HashMap::new();
         metrics.insert(
             "BlendedCost".to_string(),
             MetricValue::builder().amount("123.45").unit("USD").build(),
         );
-        let (amount, currency) = extract_blended_cost(Some(&metrics));
-        assert!((amount - 123.45).abs() < f64::EPSILON);
+        let (amount, currency) = extract_cost(Some(&metrics), Metric::Blended);
+        assert_eq!(amount, Amount::from_micros(123_450_000));
         assert_eq!(currency, "USD");
     }
 
     #[test]
-    fn extract_blended_cost_missing_key() {
+    fn extract_cost_picks_requested_metric() {
+        use aws_sdk_costexplorer::types::MetricValue;
+        let mut metrics = std::collections::HashMap::new();
+        metrics.insert(
+            "BlendedCost".to_string(),
+            MetricValue::builder().amount("1.00").unit("USD").build(),
+        );
+        metrics.insert(
+            "AmortizedCost".to_string(),
+            MetricValue::builder().amount("2.50").unit("USD").build(),
+        );
+        let (amount, _) = extract_cost(Some(&metrics), Metric::Amortized);
+        assert_eq!(amount, Amount::from_micros(2_500_000));
+    }
+
+    #[test]
+    fn token_usage_types() {
+        assert_eq!(token_kind("USE1-Claude3Sonnet-input-tokens"), Some(TokenKind::Input));
+        assert_eq!(token_kind("USE1-MP:USE1_OutputTokenCount-Units"), Some(TokenKind::Output));
+        assert_eq!(token_kind("USE1-MP:USE1_CacheReadInputTokenCount-Units"), None);
+        assert_eq!(token_kind("USE1-Requests-Tier1"), None);
+        assert_eq!(token_count(1.5, "1K tokens"), 1500);
+        assert_eq!(token_count(1500.0, "Tokens"), 1500);
+    }
+
+    #[test]
+    fn merge_cost_rows_sums_accounts() {
+        let row = |user: &str, micros: i64, metric: Metric| CostRow {
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            user_id: user.to_string(),
+            model_id: "m1".to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+        };
+        let merged = merge_cost_rows(vec![
+            row("u1", 1_000_000, Metric::Blended),
+            row("u2", 5_000_000, Metric::Blended),
+            row("u1", 2_000_000, Metric::Blended),
+            row("u1", 4_000_000, Metric::Unblended),
+        ]);
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged[0].user_id, "u1");
+        assert_eq!(merged[0].metric, Metric::Blended);
+        assert_eq!(merged[0].amount, Amount::from_micros(3_000_000));
+        assert_eq!(merged[2].user_id, "u2");
+    }
+
+    #[test]
+    fn split_credits_leaves_gross_rows() {
+        let row = |user: &str, micros: i64, source: &str| CostRow {
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            user_id: user.to_string(),
+            model_id: "m1".to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: source.to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+        };
+        let rows = split_credits(
+            vec![
+                row("u1", 8_000_000, AWS_SOURCE),
+                row("u2", 5_000_000, AWS_SOURCE),
+            ],
+            vec![
+                row("u1", -2_000_000, CREDIT_SOURCE),
+                row("u3", -1_000_000, CREDIT_SOURCE),
+            ],
+        );
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|r| (r.user_id.as_str(), r.source.as_str(), r.amount.micros()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("u1", AWS_SOURCE, 10_000_000),
+                ("u2", AWS_SOURCE, 5_000_000),
+                ("u3", AWS_SOURCE, 1_000_000),
+                ("u1", CREDIT_SOURCE, -2_000_000),
+                ("u3", CREDIT_SOURCE, -1_000_000),
+            ]
+        );
+        let net: Amount = rows.iter().map(|r| r.amount).sum();
+        assert_eq!(net, Amount::from_micros(13_000_000));
+    }
+
+    #[test]
+    fn backoff_doubles() {
+        assert_eq!(backoff(0), Duration::from_millis(500));
+        assert_eq!(backoff(3), Duration::from_secs(4));
+    }
+
+    #[test]
+    fn throttling_is_retried() {
+        use aws_sdk_costexplorer::types::error::{
+            InvalidNextTokenException, LimitExceededException,
+        };
+        let throttled = SdkError::<_, ()>::service_error(
+            GetCostAndUsageError::LimitExceededException(LimitExceededException::builder().build()),
+            (),
+        );
+        assert!(is_throttled(&throttled));
+        let other = SdkError::<_, ()>::service_error(
+            GetCostAndUsageError::InvalidNextTokenException(
+                InvalidNextTokenException::builder().build(),
+            ),
+            (),
+        );
+        assert!(!is_throttled(&other));
+    }
+
+    #[test]
+    fn extract_cost_missing_key() {
         let metrics = std::collections::HashMap::new();
-        let (amount, currency) = extract_blended_cost(Some(&metrics));
-        assert!((amount - 0.0).abs() < f64::EPSILON);
+        let (amount, currency) = extract_cost(Some(&metrics), Metric::Blended);
+        assert_eq!(amount, Amount::ZERO);
         assert_eq!(currency, "USD");
     }
 }
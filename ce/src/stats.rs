@@ -0,0 +1,103 @@
+//! Outcomes of the CE requests this process sent recently, for the
+//! diagnostics page.
+
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Duration, Utc};
+
+/// How far back [`recent_calls`] looks.
+const WINDOW_HOURS: i64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Outcome {
+    Ok,
+    Throttled,
+    Failed,
+}
+
+struct Calls {
+    recent: VecDeque<(DateTime<Utc>, Outcome)>,
+    last_error: Option<(DateTime<Utc>, String)>,
+}
+
+static CALLS: Mutex<Calls> = Mutex::new(Calls {
+    recent: VecDeque::new(),
+    last_error: None,
+});
+
+/// CE requests sent in the last hour, each retry counted on its own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CallStats {
+    pub calls: usize,
+    /// Requests CE turned away with `LimitExceededException`.
+    pub throttled: usize,
+    /// Requests that failed otherwise.
+    pub failed: usize,
+    /// The latest failure, however long ago.
+    pub last_error: Option<(DateTime<Utc>, String)>,
+}
+
+impl CallStats {
+    /// Share of the requests that did not succeed, from 0 to 1.
+    pub fn error_rate(&self) -> f64 {
+        if self.calls == 0 {
+            return 0.0;
+        }
+        (self.throttled + self.failed) as f64 / self.calls as f64
+    }
+}
+
+pub(crate) fn record(outcome: Outcome, error: Option<String>) {
+    record_at(Utc::now(), outcome, error);
+}
+
+fn record_at(now: DateTime<Utc>, outcome: Outcome, error: Option<String>) {
+    let mut calls = CALLS.lock().unwrap();
+    prune(&mut calls.recent, now);
+    calls.recent.push_back((now, outcome));
+    if let Some(error) = error {
+        calls.last_error = Some((now, error));
+    }
+}
+
+pub fn recent_calls() -> CallStats {
+    let mut calls = CALLS.lock().unwrap();
+    prune(&mut calls.recent, Utc::now());
+    let count = |outcome| calls.recent.iter().filter(|(_, o)| *o == outcome).count();
+    CallStats {
+        calls: calls.recent.len(),
+        throttled: count(Outcome::Throttled),
+        failed: count(Outcome::Failed),
+        last_error: calls.last_error.clone(),
+    }
+}
+
+fn prune(recent: &mut VecDeque<(DateTime<Utc>, Outcome)>, now: DateTime<Utc>) {
+    let cutoff = now - Duration::hours(WINDOW_HOURS);
+    while recent.front().is_some_and(|(at, _)| *at < cutoff) {
+        recent.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_the_last_hour() {
+        let now = Utc::now();
+        record_at(now - Duration::hours(2), Outcome::Failed, None);
+        record_at(now, Outcome::Ok, None);
+        record_at(now, Outcome::Throttled, None);
+        record_at(now, Outcome::Failed, Some("access denied".to_string()));
+
+        let stats = recent_calls();
+        assert_eq!(stats.calls, 3);
+        assert_eq!(stats.throttled, 1);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.last_error, Some((now, "access denied".to_string())));
+        assert!((stats.error_rate() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(CallStats::default().error_rate(), 0.0);
+    }
+}
@@ -0,0 +1,300 @@
+//! Queries against a stand-in CE endpoint serving canned responses, checking
+//! the requests they send and how they read the replies.
+
+use ce::Clients;
+use chrono::NaiveDate;
+use common::{Amount, Metric};
+use serde_json::{json, Value};
+use wiremock::matchers::{body_partial_json, header, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const TARGET: &str = "AWSInsightsIndexService.GetCostAndUsage";
+
+fn reply(body: Value) -> ResponseTemplate {
+    ResponseTemplate::new(200)
+        .insert_header("content-type", "application/x-amz-json-1.1")
+        .set_body_json(body)
+}
+
+fn day(start: &str, end: &str, groups: Value) -> Value {
+    json!({
+        "TimePeriod": {"Start": start, "End": end},
+        "Groups": groups,
+        "Estimated": false
+    })
+}
+
+fn cost_group(keys: [&str; 2], amount: &str) -> Value {
+    json!({
+        "Keys": keys,
+        "Metrics": {"BlendedCost": {"Amount": amount, "Unit": "USD"}}
+    })
+}
+
+/// Both gateway tags must be present.
+fn gateway_filter() -> Value {
+    let tagged = |key: &str| json!({"Not": {"Tags": {"Key": key, "MatchOptions": ["ABSENT"]}}});
+    json!({"And": [tagged("GatewayUserId"), tagged("GatewayModelId")]})
+}
+
+#[tokio::test]
+async fn daily_costs_follow_every_page() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({"NextPageToken": "page-2"})))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-02", "2025-03-03", json!([
+                cost_group(["GatewayUserId$u1", "GatewayModelId$m1"], "2.5"),
+            ]))]
+        })))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({
+            "TimePeriod": {"Start": "2025-03-01", "End": "2025-03-03"},
+            "Granularity": "DAILY",
+            "Metrics": ["BlendedCost"],
+            "GroupBy": [
+                {"Type": "TAG", "Key": "GatewayUserId"},
+                {"Type": "TAG", "Key": "GatewayModelId"}
+            ],
+            "Filter": gateway_filter()
+        })))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-01", "2025-03-02", json!([
+                cost_group(["GatewayUserId$u1", "GatewayModelId$m1"], "1.25"),
+                cost_group(["GatewayUserId$", "GatewayModelId$m1"], "9"),
+            ]))],
+            "NextPageToken": "page-2"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let (rows, calls) = ce::get_daily_cost_by_user_and_model_counted(
+        &clients,
+        "2025-03-01",
+        "2025-03-03",
+        &[Metric::Blended],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(calls, 2);
+    assert_eq!(rows.len(), 2, "untagged groups are skipped");
+    assert_eq!(rows[0].date, NaiveDate::from_ymd_opt(2025, 3, 1).unwrap());
+    assert_eq!(rows[0].user_id, "u1");
+    assert_eq!(rows[0].model_id, "m1");
+    assert_eq!(rows[0].amount, Amount::from_micros(1_250_000));
+    assert_eq!(rows[1].amount, Amount::from_micros(2_500_000));
+}
+
+#[tokio::test]
+async fn credits_are_split_out_of_the_net_cost() {
+    let server = MockServer::start().await;
+    let tagged = |key: &str| json!({"Not": {"Tags": {"Key": key, "MatchOptions": ["ABSENT"]}}});
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({
+            "Filter": {"And": [
+                tagged("GatewayUserId"),
+                tagged("GatewayModelId"),
+                {"Dimensions": {"Key": "RECORD_TYPE", "Values": ["Credit", "Refund"]}}
+            ]}
+        })))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-01", "2025-03-02", json!([
+                cost_group(["GatewayUserId$u1", "GatewayModelId$m1"], "-2"),
+            ]))]
+        })))
+        .with_priority(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({"Filter": gateway_filter()})))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-01", "2025-03-02", json!([
+                cost_group(["GatewayUserId$u1", "GatewayModelId$m1"], "8"),
+            ]))]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let (rows, calls) = ce::get_daily_gross_cost_and_credits_counted(
+        &clients,
+        "2025-03-01",
+        "2025-03-02",
+        &[Metric::Blended],
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(calls, 2);
+    let amounts: Vec<(&str, Amount)> = rows.iter().map(|r| (r.source.as_str(), r.amount)).collect();
+    assert_eq!(
+        amounts,
+        vec![
+            (common::AWS_SOURCE, Amount::from_micros(10_000_000)),
+            (common::CREDIT_SOURCE, Amount::from_micros(-2_000_000)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn region_costs_are_summed_across_months() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({
+            "Granularity": "MONTHLY",
+            "GroupBy": [
+                {"Type": "DIMENSION", "Key": "REGION"},
+                {"Type": "TAG", "Key": "GatewayModelId"}
+            ],
+            "Filter": gateway_filter()
+        })))
+        .respond_with(reply(json!({
+            "ResultsByTime": [
+                day("2025-02-15", "2025-03-01", json!([
+                    cost_group(["us-east-1", "GatewayModelId$m1"], "1"),
+                    cost_group(["us-west-2", "GatewayModelId$m1"], "5"),
+                ])),
+                day("2025-03-01", "2025-03-15", json!([
+                    cost_group(["us-east-1", "GatewayModelId$m1"], "7"),
+                ])),
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let regions = ce::get_cost_by_region(&clients, "2025-02-15", "2025-03-15", Metric::Blended)
+        .await
+        .unwrap();
+
+    let totals: Vec<(&str, Amount)> = regions
+        .iter()
+        .map(|r| (r.region.as_str(), r.amount))
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            ("us-east-1", Amount::from_micros(8_000_000)),
+            ("us-west-2", Amount::from_micros(5_000_000)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn api_key_costs_are_read_for_one_user() {
+    let server = MockServer::start().await;
+    let key_group = |key: &str, amount: &str| {
+        json!({
+            "Keys": [key],
+            "Metrics": {"BlendedCost": {"Amount": amount, "Unit": "USD"}}
+        })
+    };
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({
+            "GroupBy": [{"Type": "TAG", "Key": "GatewayApiKeyId"}],
+            "Filter": {"And": [
+                {"Tags": {"Key": "GatewayUserId", "Values": ["u1"]}},
+                {"Not": {"Tags": {"Key": "GatewayModelId", "MatchOptions": ["ABSENT"]}}}
+            ]}
+        })))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-01", "2025-03-15", json!([
+                key_group("GatewayApiKeyId$k1", "2"),
+                key_group("GatewayApiKeyId$", "0.5"),
+                key_group("GatewayApiKeyId$k2", "4"),
+            ]))]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let keys = ce::get_cost_by_api_key(&clients, "2025-03-01", "2025-03-15", "u1", Metric::Blended)
+        .await
+        .unwrap();
+
+    let totals: Vec<(&str, Amount)> = keys
+        .iter()
+        .map(|k| (k.api_key_id.as_str(), k.amount))
+        .collect();
+    assert_eq!(
+        totals,
+        vec![
+            ("k2", Amount::from_micros(4_000_000)),
+            ("k1", Amount::from_micros(2_000_000)),
+            ("", Amount::from_micros(500_000)),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn linked_accounts_take_their_names_from_the_attributes() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("x-amz-target", TARGET))
+        .and(body_partial_json(json!({
+            "GroupBy": [{"Type": "DIMENSION", "Key": "LINKED_ACCOUNT"}],
+            "Metrics": ["UnblendedCost"]
+        })))
+        .respond_with(reply(json!({
+            "ResultsByTime": [day("2025-03-01", "2025-04-01", json!([{
+                "Keys": ["123456789012"],
+                "Metrics": {"UnblendedCost": {"Amount": "3.5", "Unit": "USD"}}
+            }]))],
+            "DimensionValueAttributes": [
+                {"Value": "123456789012", "Attributes": {"description": "Research"}}
+            ]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let accounts =
+        ce::get_cost_by_linked_account(&clients, "2025-03-01", "2025-04-01", Metric::Unblended)
+            .await
+            .unwrap();
+
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].account_id, "123456789012");
+    assert_eq!(accounts[0].account_name.as_deref(), Some("Research"));
+    assert_eq!(accounts[0].amount, Amount::from_micros(3_500_000));
+}
+
+#[tokio::test]
+async fn errors_name_the_account() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(400)
+                .insert_header("content-type", "application/x-amz-json-1.1")
+                .set_body_json(json!({
+                    "__type": "DataUnavailableException",
+                    "message": "Data is not available"
+                })),
+        )
+        .mount(&server)
+        .await;
+
+    let clients = Clients::with_endpoint(&server.uri());
+    let err = ce::get_daily_cost_by_service(&clients, "2025-03-01", "2025-03-02", Metric::Blended)
+        .await
+        .unwrap_err();
+    assert!(format!("{err:#}").starts_with("account default"));
+}
@@ -0,0 +1,122 @@
+use chrono::{Datelike, NaiveDate};
+
+use crate::Amount;
+
+/// A user's own cost alert, set on their alerts page. The batch job emails
+/// the user when a day's cost, or a month's cost so far, passes its
+/// threshold, once per day or month.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostAlert {
+    pub user_id: String,
+    pub daily_threshold: Option<Amount>,
+    pub monthly_threshold: Option<Amount>,
+    /// The last day an alert was sent for.
+    pub daily_alerted: Option<NaiveDate>,
+    /// First day of the last month an alert was sent for.
+    pub monthly_alerted: Option<NaiveDate>,
+}
+
+/// Which threshold of a [`CostAlert`] was passed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertPeriod {
+    Daily,
+    Monthly,
+}
+
+impl AlertPeriod {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Monthly => "monthly",
+        }
+    }
+}
+
+/// A passed threshold not alerted on yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DueAlert {
+    pub period: AlertPeriod,
+    /// The day, or the month's first day, to record the alert under.
+    pub date: NaiveDate,
+    pub cost: Amount,
+    pub threshold: Amount,
+}
+
+impl CostAlert {
+    /// Thresholds passed by `day`'s cost and its month's cost so far that
+    /// have not been alerted on.
+    pub fn due(&self, day: NaiveDate, day_cost: Amount, month_cost: Amount) -> Vec<DueAlert> {
+        let month = day.with_day(1).unwrap_or(day);
+        let mut due = Vec::new();
+        if let Some(threshold) = self.daily_threshold {
+            if day_cost > threshold && self.daily_alerted.is_none_or(|last| last < day) {
+                due.push(DueAlert {
+                    period: AlertPeriod::Daily,
+                    date: day,
+                    cost: day_cost,
+                    threshold,
+                });
+            }
+        }
+        if let Some(threshold) = self.monthly_threshold {
+            if month_cost > threshold && self.monthly_alerted.is_none_or(|last| last < month) {
+                due.push(DueAlert {
+                    period: AlertPeriod::Monthly,
+                    date: month,
+                    cost: month_cost,
+                    threshold,
+                });
+            }
+        }
+        due
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn alerts_once_per_day_and_month() {
+        let usd = Amount::from_f64;
+        let mut alert = CostAlert {
+            user_id: "u1".to_string(),
+            daily_threshold: Some(usd(10.0)),
+            monthly_threshold: Some(usd(100.0)),
+            ..Default::default()
+        };
+        let day = date("2025-03-14");
+        assert!(alert.due(day, usd(10.0), usd(100.0)).is_empty());
+
+        let due = alert.due(day, usd(12.0), usd(150.0));
+        assert_eq!(
+            due,
+            vec![
+                DueAlert {
+                    period: AlertPeriod::Daily,
+                    date: day,
+                    cost: usd(12.0),
+                    threshold: usd(10.0),
+                },
+                DueAlert {
+                    period: AlertPeriod::Monthly,
+                    date: date("2025-03-01"),
+                    cost: usd(150.0),
+                    threshold: usd(100.0),
+                },
+            ]
+        );
+
+        alert.daily_alerted = Some(day);
+        alert.monthly_alerted = Some(date("2025-03-01"));
+        assert!(alert.due(day, usd(12.0), usd(150.0)).is_empty());
+        let next = date("2025-03-15");
+        assert_eq!(alert.due(next, usd(12.0), usd(170.0)).len(), 1);
+        let april = date("2025-04-01");
+        assert_eq!(alert.due(april, usd(12.0), usd(170.0)).len(), 2);
+    }
+}
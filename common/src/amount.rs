@@ -0,0 +1,234 @@
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
+
+use serde::{Serialize, Serializer};
+
+const SCALE_DIGITS: usize = 6;
+const SCALE: i64 = 1_000_000;
+
+/// Fixed-point monetary amount in millionths of a currency unit.
+///
+/// Sums and comparisons are exact; conversion to decimal text only happens
+/// when formatting (`{:.2}` rounds half away from zero, default is 2 places).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Amount(i64);
+
+impl Amount {
+    pub const ZERO: Amount = Amount(0);
+
+    pub const fn from_micros(micros: i64) -> Self {
+        Self(micros)
+    }
+
+    pub const fn micros(self) -> i64 {
+        self.0
+    }
+
+    /// Rounds to the nearest micro-unit. Only for sources that hand us
+    /// floats; prefer parsing the decimal string.
+    pub fn from_f64(value: f64) -> Self {
+        Self((value * SCALE as f64).round() as i64)
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.0 == 0
+    }
+}
+
+/// Divides `value` by `step`, rounding half away from zero.
+fn div_round(value: i64, step: i64) -> i64 {
+    let half = step / 2;
+    if value >= 0 {
+        (value + half) / step
+    } else {
+        (value - half) / step
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let precision = f.precision().unwrap_or(2).min(SCALE_DIGITS);
+        let step = 10i64.pow((SCALE_DIGITS - precision) as u32);
+        let rounded = div_round(self.0, step);
+        let sign = if rounded < 0 { "-" } else { "" };
+        let abs = rounded.unsigned_abs();
+        if precision == 0 {
+            return write!(f, "{sign}{abs}");
+        }
+        let unit = 10u64.pow(precision as u32);
+        write!(
+            f,
+            "{sign}{}.{:0width$}",
+            abs / unit,
+            abs % unit,
+            width = precision
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseAmountError(String);
+
+impl fmt::Display for ParseAmountError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid amount: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseAmountError {}
+
+impl FromStr for Amount {
+    type Err = ParseAmountError;
+
+    /// Parses a decimal string exactly, rounding digits past the sixth
+    /// fractional place. Exponent notation (which CE uses for tiny values)
+    /// goes through `f64`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseAmountError(s.to_string());
+        let trimmed = s.trim();
+        if trimmed.contains(['e', 'E']) {
+            let value = trimmed.parse::<f64>().map_err(|_| err())?;
+            return Ok(Self::from_f64(value));
+        }
+        let (negative, digits) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed.strip_prefix('+').unwrap_or(trimmed)),
+        };
+        let (int_part, frac_part) = digits.split_once('.').unwrap_or((digits, ""));
+        if (int_part.is_empty() && frac_part.is_empty())
+            || !int_part.bytes().all(|b| b.is_ascii_digit())
+            || !frac_part.bytes().all(|b| b.is_ascii_digit())
+        {
+            return Err(err());
+        }
+        let int: i64 = if int_part.is_empty() {
+            0
+        } else {
+            int_part.parse().map_err(|_| err())?
+        };
+        let mut frac: i64 = 0;
+        for (i, b) in frac_part.bytes().take(SCALE_DIGITS).enumerate() {
+            frac += i64::from(b - b'0') * 10i64.pow((SCALE_DIGITS - 1 - i) as u32);
+        }
+        if frac_part.as_bytes().get(SCALE_DIGITS).is_some_and(|b| *b >= b'5') {
+            frac += 1;
+        }
+        let micros = int
+            .checked_mul(SCALE)
+            .and_then(|v| v.checked_add(frac))
+            .ok_or_else(err)?;
+        Ok(Self(if negative { -micros } else { micros }))
+    }
+}
+
+impl Serialize for Amount {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+impl Add for Amount {
+    type Output = Amount;
+
+    fn add(self, rhs: Amount) -> Amount {
+        Amount(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Amount {
+    fn add_assign(&mut self, rhs: Amount) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Amount {
+    type Output = Amount;
+
+    fn sub(self, rhs: Amount) -> Amount {
+        Amount(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Amount {
+    fn sub_assign(&mut self, rhs: Amount) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Neg for Amount {
+    type Output = Amount;
+
+    fn neg(self) -> Amount {
+        Amount(-self.0)
+    }
+}
+
+impl Sum for Amount {
+    fn sum<I: Iterator<Item = Amount>>(iter: I) -> Amount {
+        iter.fold(Amount::ZERO, Add::add)
+    }
+}
+
+impl<'a> Sum<&'a Amount> for Amount {
+    fn sum<I: Iterator<Item = &'a Amount>>(iter: I) -> Amount {
+        iter.copied().sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_exact_decimal() {
+        assert_eq!("123.45".parse::<Amount>().unwrap().micros(), 123_450_000);
+        assert_eq!("-0.5".parse::<Amount>().unwrap().micros(), -500_000);
+        assert_eq!(".25".parse::<Amount>().unwrap().micros(), 250_000);
+        assert_eq!("7".parse::<Amount>().unwrap().micros(), 7_000_000);
+    }
+
+    #[test]
+    fn parse_rounds_past_micros() {
+        assert_eq!("0.0000014".parse::<Amount>().unwrap().micros(), 1);
+        assert_eq!("0.0000015".parse::<Amount>().unwrap().micros(), 2);
+        assert_eq!("1.2E-5".parse::<Amount>().unwrap().micros(), 12);
+    }
+
+    #[test]
+    fn parse_rejects_garbage() {
+        assert!("".parse::<Amount>().is_err());
+        assert!("abc".parse::<Amount>().is_err());
+        assert!("1.2.3".parse::<Amount>().is_err());
+        assert!("-".parse::<Amount>().is_err());
+    }
+
+    #[test]
+    fn sum_has_no_drift() {
+        let cent: Amount = "0.01".parse().unwrap();
+        let total: Amount = std::iter::repeat_n(cent, 100_000).sum();
+        assert_eq!(total, "1000".parse().unwrap());
+    }
+
+    #[test]
+    fn display_rounds_half_away_from_zero() {
+        assert_eq!(format!("{:.2}", Amount::from_micros(1_005_000)), "1.01");
+        assert_eq!(format!("{:.2}", Amount::from_micros(-1_005_000)), "-1.01");
+        assert_eq!(format!("{:.2}", Amount::from_micros(1_004_999)), "1.00");
+        assert_eq!(format!("{:.2}", Amount::from_micros(-4_000)), "0.00");
+        assert_eq!(format!("{}", Amount::from_micros(99_990_000)), "99.99");
+        assert_eq!(format!("{:.0}", Amount::from_micros(2_500_000)), "3");
+        assert_eq!(format!("{:.6}", Amount::from_micros(1)), "0.000001");
+    }
+
+    #[test]
+    fn f64_round_trip() {
+        assert_eq!(Amount::from_f64(123.45).micros(), 123_450_000);
+        assert!((Amount::from_micros(123_450_000).to_f64() - 123.45).abs() < f64::EPSILON);
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::{DateTime, Utc};
+
+/// An incremental batch run an admin requested from the server, run by the
+/// batch daemon when it next polls.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchRequest {
+    pub id: i64,
+    /// Email of the admin who asked for the run.
+    pub requested_by: String,
+    pub requested_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Why the run failed.
+    pub error: Option<String>,
+}
+
+impl BatchRequest {
+    pub fn status(&self) -> &'static str {
+        match (self.started_at, self.finished_at, &self.error) {
+            (None, _, _) => "Pending",
+            (Some(_), None, _) => "Running",
+            (Some(_), Some(_), None) => "Done",
+            (Some(_), Some(_), Some(_)) => "Failed",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_follows_the_run() {
+        let now = Utc::now();
+        let mut request = BatchRequest {
+            id: 1,
+            requested_by: "admin@example.com".to_string(),
+            requested_at: now,
+            started_at: None,
+            finished_at: None,
+            error: None,
+        };
+        assert_eq!(request.status(), "Pending");
+        request.started_at = Some(now);
+        assert_eq!(request.status(), "Running");
+        request.finished_at = Some(now);
+        assert_eq!(request.status(), "Done");
+        request.error = Some("CE throttled".to_string());
+        assert_eq!(request.status(), "Failed");
+    }
+}
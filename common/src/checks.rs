@@ -0,0 +1,63 @@
+use std::fmt;
+
+/// Outcome of one `--check-config` step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: String,
+    pub passed: bool,
+    /// What was reached, or why it failed.
+    pub detail: String,
+}
+
+/// Results of `--check-config`, printed as a pass/fail table.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CheckReport {
+    pub checks: Vec<Check>,
+}
+
+impl CheckReport {
+    /// Records a check; `Err` holds the failure to show.
+    pub fn record<E: fmt::Display>(&mut self, name: &str, result: Result<String, E>) {
+        let (passed, detail) = match result {
+            Ok(detail) => (true, detail),
+            Err(e) => (false, e.to_string()),
+        };
+        self.checks.push(Check {
+            name: name.to_string(),
+            passed,
+            detail,
+        });
+    }
+
+    pub fn failures(&self) -> usize {
+        self.checks.iter().filter(|c| !c.passed).count()
+    }
+}
+
+impl fmt::Display for CheckReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+        for check in &self.checks {
+            let status = if check.passed { "PASS" } else { "FAIL" };
+            writeln!(f, "{status}  {:width$}  {}", check.name, check.detail)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_aligns_names_and_counts_failures() {
+        let mut report = CheckReport::default();
+        report.record::<String>("cost db", Ok("connected".to_string()));
+        report.record("cost explorer", Err("access denied"));
+        assert_eq!(report.failures(), 1);
+        assert_eq!(
+            report.to_string(),
+            "PASS  cost db        connected\nFAIL  cost explorer  access denied\n"
+        );
+    }
+}
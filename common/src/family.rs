@@ -0,0 +1,120 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use serde::Serialize;
+
+use crate::{Amount, CostByModel};
+
+/// An admin-set rule rolling up models whose name starts with `prefix`, such
+/// as `claude-3-5-sonnet`, into `family`. Prefixes match case-insensitively
+/// and the longest matching prefix wins.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelFamily {
+    pub prefix: String,
+    pub family: String,
+}
+
+/// Cost of the models of one family.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostByFamily {
+    pub family: String,
+    /// Whether a rule matched; models no rule covers each form a family of
+    /// their own, named after the model.
+    pub matched: bool,
+    pub model_count: usize,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// The rule of `rules` that decides the family of `model_name`, if any.
+pub fn rule_for<'a>(rules: &'a [ModelFamily], model_name: &str) -> Option<&'a ModelFamily> {
+    let name = model_name.to_lowercase();
+    rules
+        .iter()
+        .filter(|r| name.starts_with(&r.prefix.to_lowercase()))
+        .max_by_key(|r| r.prefix.len())
+}
+
+/// Sums `costs` per family, highest cost first. Models are matched by name,
+/// or by id when they have none.
+pub fn cost_by_family(rules: &[ModelFamily], costs: &[CostByModel]) -> Vec<CostByFamily> {
+    let mut families: Vec<CostByFamily> = Vec::new();
+    let mut index: HashMap<(String, bool), usize> = HashMap::new();
+    for cost in costs {
+        let name = cost.model_name.as_deref().unwrap_or(&cost.model_id);
+        let (family, matched) = match rule_for(rules, name) {
+            Some(rule) => (rule.family.clone(), true),
+            None => (name.to_string(), false),
+        };
+        match index.get(&(family.clone(), matched)) {
+            Some(&i) => {
+                families[i].model_count += 1;
+                families[i].amount += cost.amount;
+            }
+            None => {
+                index.insert((family.clone(), matched), families.len());
+                families.push(CostByFamily {
+                    family,
+                    matched,
+                    model_count: 1,
+                    amount: cost.amount,
+                    currency: cost.currency.clone(),
+                });
+            }
+        }
+    }
+    families.sort_by_key(|f| Reverse(f.amount));
+    families
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(prefix: &str, family: &str) -> ModelFamily {
+        ModelFamily {
+            prefix: prefix.to_string(),
+            family: family.to_string(),
+        }
+    }
+
+    fn cost(model_id: &str, name: Option<&str>, amount: f64) -> CostByModel {
+        CostByModel {
+            model_id: model_id.to_string(),
+            model_name: name.map(str::to_string),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn rule_for_prefers_longest_prefix() {
+        let rules = vec![
+            rule("claude-3", "Claude 3"),
+            rule("claude-3-5", "Claude 3.5"),
+        ];
+        let family = |name| rule_for(&rules, name).map(|r| r.family.as_str());
+        assert_eq!(family("claude-3-opus"), Some("Claude 3"));
+        assert_eq!(family("Claude-3-5-Sonnet"), Some("Claude 3.5"));
+        assert_eq!(family("llama-3"), None);
+    }
+
+    #[test]
+    fn cost_by_family_sums_matched_models() {
+        let rules = vec![rule("claude-3", "Claude 3")];
+        let costs = vec![
+            cost("m1", Some("claude-3-opus"), 5.0),
+            cost("m2", Some("claude-3-haiku"), 1.0),
+            cost("m3", Some("llama-3"), 8.0),
+            cost("m4", None, 0.5),
+        ];
+        let families = cost_by_family(&rules, &costs);
+        assert_eq!(families.len(), 3);
+        assert_eq!(families[0].family, "llama-3");
+        assert!(!families[0].matched);
+        assert_eq!(families[1].family, "Claude 3");
+        assert_eq!(families[1].model_count, 2);
+        assert_eq!(families[1].amount, Amount::from_f64(6.0));
+        assert_eq!(families[2].family, "m4");
+    }
+}
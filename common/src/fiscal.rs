@@ -0,0 +1,171 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Deserialize;
+
+/// How a fiscal year is cut into periods.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+pub enum FiscalPattern {
+    /// Twelve periods, one per calendar month.
+    #[default]
+    #[serde(rename = "monthly")]
+    Monthly,
+    /// Quarters of 4, 4 and 5 weeks.
+    #[serde(rename = "4-4-5")]
+    FourFourFive,
+    #[serde(rename = "4-5-4")]
+    FourFiveFour,
+    #[serde(rename = "5-4-4")]
+    FiveFourFour,
+}
+
+impl FiscalPattern {
+    /// Weeks per period within a quarter, or `None` for calendar months.
+    fn quarter_weeks(self) -> Option<[i64; 3]> {
+        match self {
+            FiscalPattern::Monthly => None,
+            FiscalPattern::FourFourFive => Some([4, 4, 5]),
+            FiscalPattern::FourFiveFour => Some([4, 5, 4]),
+            FiscalPattern::FiveFourFour => Some([5, 4, 4]),
+        }
+    }
+}
+
+/// Fiscal years start on the first of `start_month` and are named after the
+/// calendar year they end in, so with `start_month = 7` FY2025 runs from
+/// July 2024 to June 2025. Week-based periods are 52 weeks long; the last
+/// period absorbs the one or two days left before the next year starts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct FiscalCalendar {
+    #[serde(default = "default_start_month")]
+    pub start_month: u32,
+    #[serde(default)]
+    pub pattern: FiscalPattern,
+}
+
+fn default_start_month() -> u32 {
+    1
+}
+
+impl Default for FiscalCalendar {
+    fn default() -> Self {
+        Self {
+            start_month: default_start_month(),
+            pattern: FiscalPattern::default(),
+        }
+    }
+}
+
+/// One fiscal period, covering `[start, end)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FiscalPeriod {
+    pub year: i32,
+    /// 1-based position within the fiscal year.
+    pub number: u32,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl FiscalPeriod {
+    pub fn label(&self) -> String {
+        format!("FY{} P{:02}", self.year, self.number)
+    }
+}
+
+impl FiscalCalendar {
+    fn start_month(&self) -> u32 {
+        self.start_month.clamp(1, 12)
+    }
+
+    pub fn year_start(&self, year: i32) -> NaiveDate {
+        let start_month = self.start_month();
+        let calendar_year = if start_month == 1 { year } else { year - 1 };
+        NaiveDate::from_ymd_opt(calendar_year, start_month, 1).unwrap_or_default()
+    }
+
+    /// The fiscal year `date` falls in.
+    pub fn year_of(&self, date: NaiveDate) -> i32 {
+        if date >= self.year_start(date.year() + 1) {
+            date.year() + 1
+        } else {
+            date.year()
+        }
+    }
+
+    /// The twelve periods of fiscal `year`, in order.
+    pub fn periods(&self, year: i32) -> Vec<FiscalPeriod> {
+        let year_start = self.year_start(year);
+        let year_end = self.year_start(year + 1);
+        let mut starts: Vec<NaiveDate> = match self.pattern.quarter_weeks() {
+            None => (0..12)
+                .map(|i| year_start + chrono::Months::new(i))
+                .collect(),
+            Some(weeks) => weeks
+                .iter()
+                .cycle()
+                .take(12)
+                .scan(year_start, |start, weeks| {
+                    let this = *start;
+                    *start += Duration::weeks(*weeks);
+                    Some(this)
+                })
+                .collect(),
+        };
+        starts.push(year_end);
+        starts
+            .windows(2)
+            .enumerate()
+            .map(|(i, w)| FiscalPeriod {
+                year,
+                number: i as u32 + 1,
+                start: w[0],
+                end: w[1],
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn monthly_calendar_year() {
+        let periods = FiscalCalendar::default().periods(2025);
+        assert_eq!(periods.len(), 12);
+        assert_eq!(periods[0].start, date("2025-01-01"));
+        assert_eq!(periods[1].start, date("2025-02-01"));
+        assert_eq!(periods[11].end, date("2026-01-01"));
+        assert_eq!(periods[0].label(), "FY2025 P01");
+    }
+
+    #[test]
+    fn year_named_after_its_end() {
+        let calendar = FiscalCalendar {
+            start_month: 7,
+            pattern: FiscalPattern::Monthly,
+        };
+        assert_eq!(calendar.year_start(2025), date("2024-07-01"));
+        assert_eq!(calendar.year_of(date("2024-06-30")), 2024);
+        assert_eq!(calendar.year_of(date("2024-07-01")), 2025);
+        assert_eq!(calendar.periods(2025)[0].start, date("2024-07-01"));
+    }
+
+    #[test]
+    fn four_four_five_weeks() {
+        let calendar = FiscalCalendar {
+            start_month: 1,
+            pattern: FiscalPattern::FourFourFive,
+        };
+        let periods = calendar.periods(2025);
+        let days: Vec<i64> = periods
+            .iter()
+            .map(|p| (p.end - p.start).num_days())
+            .collect();
+        assert_eq!(&days[..3], &[28, 28, 35]);
+        assert_eq!(days[11], 36);
+        assert_eq!(periods[11].end, date("2026-01-01"));
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::{DateTime, Duration, Utc};
+
+/// When the stored cost data was last brought up to date, from the `meta`
+/// table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DataFreshness {
+    /// End of the last batch run that stored its CE rows.
+    pub batch_run: Option<DateTime<Utc>>,
+    /// Last rebuild of the monthly rollup, by the batch or an admin.
+    pub rollup_refresh: Option<DateTime<Utc>>,
+    /// Last change made outside a batch run: on the server's pages or by an
+    /// import or prune from the command line.
+    pub edit: Option<DateTime<Utc>>,
+}
+
+impl DataFreshness {
+    /// Whether the last batch run is more than `max_age_hours` before `now`,
+    /// or never happened.
+    pub fn is_stale(&self, now: DateTime<Utc>, max_age_hours: i64) -> bool {
+        self.batch_run
+            .is_none_or(|at| now - at > Duration::hours(max_age_hours))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn stale_after_max_age_or_without_a_run() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 12, 12, 0, 0).unwrap();
+        let mut freshness = DataFreshness::default();
+        assert!(freshness.is_stale(now, 36));
+
+        freshness.batch_run = Some(now - Duration::hours(30));
+        assert!(!freshness.is_stale(now, 36));
+        assert!(freshness.is_stale(now, 24));
+    }
+}
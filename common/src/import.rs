@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use chrono::NaiveDate;
+use serde_json::Value;
+
+use crate::{Amount, CostRow, Metric, AWS_SOURCE, CREDIT_SOURCE, MANUAL_SOURCE};
+
+/// Layout of a cost file from outside AWS, such as an OpenAI invoice or an
+/// Anthropic API export converted to the columns [`parse_import`] reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    /// A header row naming the columns, then one row per cost.
+    Csv,
+    /// An array of objects with the same fields.
+    Json,
+}
+
+impl ImportFormat {
+    /// JSON for `.json` files, CSV otherwise.
+    pub fn from_file_name(name: &str) -> Self {
+        if name.to_ascii_lowercase().ends_with(".json") {
+            Self::Json
+        } else {
+            Self::Csv
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportError(String);
+
+impl fmt::Display for ImportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for ImportError {}
+
+/// Checks that `source` names a provider in lowercase letters, digits, `-`
+/// and `_`, other than the reserved `aws` and `manual`.
+pub fn check_source(source: &str) -> Result<(), ImportError> {
+    let valid = !source.is_empty()
+        && source
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if !valid {
+        return Err(ImportError(format!("invalid source name: {source:?}")));
+    }
+    if [AWS_SOURCE, CREDIT_SOURCE, MANUAL_SOURCE].contains(&source) {
+        return Err(ImportError(format!(
+            "{source:?} is reserved for Cost Explorer rows and adjustments"
+        )));
+    }
+    Ok(())
+}
+
+/// Reads cost rows of `tenant` from `source` with `date` (YYYY-MM-DD),
+/// `user_id`, `model_id`, `amount`, and optionally `currency` (default USD)
+/// and `metric`. Rows without a metric count under every metric, since
+/// other providers bill a single figure.
+pub fn parse_import(
+    text: &str,
+    format: ImportFormat,
+    source: &str,
+    tenant: &str,
+) -> Result<Vec<CostRow>, ImportError> {
+    let records = match format {
+        ImportFormat::Csv => csv_records(text)?,
+        ImportFormat::Json => json_records(text)?,
+    };
+    let mut rows = Vec::new();
+    for (at, record) in records {
+        let record_rows =
+            cost_rows(&record, source, tenant).map_err(|e| ImportError(format!("{at}: {e}")))?;
+        rows.extend(record_rows);
+    }
+    Ok(rows)
+}
+
+/// Field values by lowercase name.
+type Record = HashMap<String, String>;
+
+fn cost_rows(record: &Record, source: &str, tenant: &str) -> Result<Vec<CostRow>, String> {
+    let field = |name: &str| {
+        record
+            .get(name)
+            .map(|value| value.trim())
+            .filter(|value| !value.is_empty())
+    };
+    let required = |name: &str| field(name).ok_or_else(|| format!("missing {name}"));
+    let date = required("date")?;
+    let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+        .map_err(|_| format!("invalid date: {date:?}"))?;
+    let user_id = required("user_id")?;
+    let model_id = required("model_id")?;
+    let amount: Amount = required("amount")?.parse().map_err(|e| format!("{e}"))?;
+    let currency = field("currency").unwrap_or("USD").to_ascii_uppercase();
+    let metrics = match field("metric") {
+        Some(metric) => vec![metric.parse::<Metric>().map_err(|e| format!("{e}"))?],
+        None => Metric::ALL.to_vec(),
+    };
+    Ok(metrics
+        .into_iter()
+        .map(|metric| CostRow {
+            date,
+            user_id: user_id.to_string(),
+            model_id: model_id.to_string(),
+            amount,
+            currency: currency.clone(),
+            metric,
+            source: source.to_string(),
+            tenant: tenant.to_string(),
+        })
+        .collect())
+}
+
+/// Records of a CSV file with a header row, each with the line it starts on.
+fn csv_records(text: &str) -> Result<Vec<(String, Record)>, ImportError> {
+    let mut rows = csv_rows(text.trim_start_matches('\u{feff}'))?.into_iter();
+    let Some((_, header)) = rows.next() else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<String> = header.iter().map(|h| h.trim().to_lowercase()).collect();
+    rows.map(|(line, fields)| {
+        if fields.len() != header.len() {
+            return Err(ImportError(format!(
+                "line {line}: expected {} fields, found {}",
+                header.len(),
+                fields.len()
+            )));
+        }
+        Ok((
+            format!("line {line}"),
+            header.iter().cloned().zip(fields).collect(),
+        ))
+    })
+    .collect()
+}
+
+/// Splits CSV text into rows of fields, with RFC 4180 quoting, numbering
+/// each row by the line it starts on. Blank lines are skipped.
+fn csv_rows(text: &str) -> Result<Vec<(usize, Vec<String>)>, ImportError> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            '"' if in_quotes => in_quotes = false,
+            '"' if field.is_empty() => in_quotes = true,
+            ',' if !in_quotes => fields.push(std::mem::take(&mut field)),
+            '\r' if !in_quotes => {}
+            '\n' if !in_quotes => {
+                fields.push(std::mem::take(&mut field));
+                if fields.len() > 1 || !fields[0].trim().is_empty() {
+                    rows.push((row_line, std::mem::take(&mut fields)));
+                }
+                fields.clear();
+                line += 1;
+                row_line = line;
+            }
+            c => {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+        }
+    }
+    if in_quotes {
+        return Err(ImportError(format!(
+            "line {row_line}: unterminated quoted field"
+        )));
+    }
+    fields.push(field);
+    if fields.len() > 1 || !fields[0].trim().is_empty() {
+        rows.push((row_line, fields));
+    }
+    Ok(rows)
+}
+
+/// Records of a JSON array of objects, numbered from 1.
+fn json_records(text: &str) -> Result<Vec<(String, Record)>, ImportError> {
+    let objects: Vec<serde_json::Map<String, Value>> =
+        serde_json::from_str(text).map_err(|e| ImportError(format!("invalid JSON: {e}")))?;
+    objects
+        .into_iter()
+        .enumerate()
+        .map(|(i, object)| {
+            let at = format!("record {}", i + 1);
+            let mut record = Record::new();
+            for (name, value) in object {
+                let value = match value {
+                    Value::String(s) => s,
+                    Value::Number(n) => n.to_string(),
+                    Value::Null => continue,
+                    other => {
+                        return Err(ImportError(format!(
+                            "{at}: {name} must be a string or number, not {other}"
+                        )))
+                    }
+                };
+                record.insert(name.to_lowercase(), value);
+            }
+            Ok((at, record))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DEFAULT_TENANT;
+
+    fn date(s: &str) -> NaiveDate {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn csv_rows_without_a_metric_count_under_every_metric() {
+        let text = "\u{feff}Date,User_ID,Model_ID,Amount,Currency\r\n\
+                    2025-03-01,alice@example.com,\"gpt-4o, batch\",12.345,usd\r\n\
+                    \r\n";
+        let rows = parse_import(text, ImportFormat::Csv, "openai", DEFAULT_TENANT).unwrap();
+        assert_eq!(rows.len(), Metric::ALL.len());
+        assert!(rows.iter().all(|r| r.date == date("2025-03-01")
+            && r.user_id == "alice@example.com"
+            && r.model_id == "gpt-4o, batch"
+            && r.amount == Amount::from_micros(12_345_000)
+            && r.currency == "USD"
+            && r.source == "openai"
+            && r.tenant == DEFAULT_TENANT));
+        let metrics: Vec<Metric> = rows.iter().map(|r| r.metric).collect();
+        assert_eq!(metrics, Metric::ALL.to_vec());
+    }
+
+    #[test]
+    fn csv_fields_may_span_lines_and_escape_quotes() {
+        let text = "date,user_id,model_id,amount,metric\n\
+                    2025-03-02,u1,\"claude \"\"opus\"\"\nlatest\",1.5,UnblendedCost\n\
+                    2025-03-02,u2,m2,oops,UnblendedCost\n";
+        let err = parse_import(text, ImportFormat::Csv, "openai", DEFAULT_TENANT).unwrap_err();
+        assert_eq!(err.to_string(), "line 4: invalid amount: \"oops\"");
+        let rows = parse_import(
+            &text[..text.rfind("2025").unwrap()],
+            ImportFormat::Csv,
+            "openai",
+            DEFAULT_TENANT,
+        )
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].model_id, "claude \"opus\"\nlatest");
+        assert_eq!(rows[0].metric, Metric::Unblended);
+        assert!(parse_import(
+            "date,user_id\n\"2025",
+            ImportFormat::Csv,
+            "openai",
+            DEFAULT_TENANT
+        )
+        .is_err());
+        assert_eq!(
+            parse_import(
+                "date,user_id\n2025-03-01",
+                ImportFormat::Csv,
+                "openai",
+                DEFAULT_TENANT
+            )
+            .unwrap_err()
+            .to_string(),
+            "line 2: expected 2 fields, found 1"
+        );
+    }
+
+    #[test]
+    fn json_records_take_numbers_or_strings() {
+        let text = r#"[
+            {"date": "2025-03-01", "user_id": "u1", "model_id": "m1", "amount": 0.25,
+             "metric": "BlendedCost", "note": null},
+            {"date": "2025-03-02", "user_id": "u1", "model_id": "m1", "amount": "1"}
+        ]"#;
+        let rows = parse_import(text, ImportFormat::Json, "openai", DEFAULT_TENANT).unwrap();
+        assert_eq!(rows.len(), 1 + Metric::ALL.len());
+        assert_eq!(rows[0].amount, Amount::from_micros(250_000));
+        assert_eq!(rows[0].metric, Metric::Blended);
+        let err = parse_import(
+            r#"[{"date": "2025-03-01"}]"#,
+            ImportFormat::Json,
+            "openai",
+            DEFAULT_TENANT,
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "record 1: missing user_id");
+        assert_eq!(
+            ImportFormat::from_file_name("Invoice.JSON"),
+            ImportFormat::Json
+        );
+        assert_eq!(
+            ImportFormat::from_file_name("invoice.csv"),
+            ImportFormat::Csv
+        );
+    }
+
+    #[test]
+    fn sources_are_checked() {
+        assert!(check_source("openai").is_ok());
+        assert!(check_source("anthropic-api_2").is_ok());
+        assert!(check_source("aws").is_err());
+        assert!(check_source("aws-credit").is_err());
+        assert!(check_source("manual").is_err());
+        assert!(check_source("OpenAI").is_err());
+        assert!(check_source("").is_err());
+    }
+}
@@ -0,0 +1,147 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashSet};
+
+use serde::Serialize;
+
+use crate::Amount;
+
+/// What a [`Label`] is attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LabelTarget {
+    User,
+    Model,
+}
+
+impl LabelTarget {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Model => "model",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "user" => Some(Self::User),
+            "model" => Some(Self::Model),
+            _ => None,
+        }
+    }
+}
+
+/// An admin-set reporting label, such as `production`, on a gateway user or
+/// model. A user or model may carry several labels.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Label {
+    pub target: LabelTarget,
+    pub target_id: String,
+    pub label: String,
+}
+
+/// Cost of the users or models carrying one label. `label` is `None` for
+/// those without any.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CostByLabel {
+    pub label: Option<String>,
+    pub members: usize,
+    pub amount: Amount,
+}
+
+/// The ids of `target`s carrying `label`.
+pub fn labeled_ids(labels: &[Label], target: LabelTarget, label: &str) -> HashSet<String> {
+    labels
+        .iter()
+        .filter(|l| l.target == target && l.label == label)
+        .map(|l| l.target_id.clone())
+        .collect()
+}
+
+/// Sums `costs`, `(id, amount)` pairs of `target`s, per label, highest cost
+/// first with the unlabeled last. An id with several labels counts towards
+/// each, so the rows may add up to more than the total.
+pub fn cost_by_label<'a>(
+    labels: &[Label],
+    target: LabelTarget,
+    costs: impl IntoIterator<Item = (&'a str, Amount)>,
+) -> Vec<CostByLabel> {
+    let mut by_id: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for l in labels.iter().filter(|l| l.target == target) {
+        by_id.entry(&l.target_id).or_default().push(&l.label);
+    }
+    let mut totals: BTreeMap<Option<&str>, (usize, Amount)> = BTreeMap::new();
+    for (id, amount) in costs {
+        let keys: Vec<Option<&str>> = match by_id.get(id) {
+            Some(names) => names.iter().map(|n| Some(*n)).collect(),
+            None => vec![None],
+        };
+        for key in keys {
+            let entry = totals.entry(key).or_insert((0, Amount::ZERO));
+            entry.0 += 1;
+            entry.1 += amount;
+        }
+    }
+    let mut rows: Vec<CostByLabel> = totals
+        .into_iter()
+        .map(|(label, (members, amount))| CostByLabel {
+            label: label.map(str::to_string),
+            members,
+            amount,
+        })
+        .collect();
+    rows.sort_by_key(|r| (r.label.is_none(), Reverse(r.amount)));
+    rows
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(target: LabelTarget, id: &str, name: &str) -> Label {
+        Label {
+            target,
+            target_id: id.to_string(),
+            label: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn target_round_trips() {
+        for target in [LabelTarget::User, LabelTarget::Model] {
+            assert_eq!(LabelTarget::parse(target.as_str()), Some(target));
+        }
+        assert_eq!(LabelTarget::parse("team"), None);
+    }
+
+    #[test]
+    fn cost_by_label_counts_each_label() {
+        let labels = vec![
+            label(LabelTarget::User, "u1", "production"),
+            label(LabelTarget::User, "u1", "external"),
+            label(LabelTarget::User, "u2", "research"),
+            label(LabelTarget::Model, "u3", "production"),
+        ];
+        let costs = [
+            ("u1", Amount::from_f64(5.0)),
+            ("u2", Amount::from_f64(7.0)),
+            ("u3", Amount::from_f64(1.0)),
+        ];
+        let rows = cost_by_label(&labels, LabelTarget::User, costs);
+        let summary: Vec<(Option<&str>, usize, Amount)> = rows
+            .iter()
+            .map(|r| (r.label.as_deref(), r.members, r.amount))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (Some("research"), 1, Amount::from_f64(7.0)),
+                (Some("external"), 1, Amount::from_f64(5.0)),
+                (Some("production"), 1, Amount::from_f64(5.0)),
+                (None, 1, Amount::from_f64(1.0)),
+            ]
+        );
+        assert_eq!(
+            labeled_ids(&labels, LabelTarget::User, "production"),
+            HashSet::from(["u1".to_string()])
+        );
+    }
+}
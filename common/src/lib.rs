@@ -1,20 +1,70 @@
+mod alert;
+mod amount;
+mod batch_request;
+mod checks;
+mod family;
+mod fiscal;
+mod freshness;
+mod import;
+mod label;
+mod markup;
+mod matrix;
+mod metric;
+mod pricing;
+mod subscription;
+mod tenant;
+
 use chrono::NaiveDate;
 use serde::Serialize;
 
+pub use alert::{AlertPeriod, CostAlert, DueAlert};
+pub use amount::{Amount, ParseAmountError};
+pub use batch_request::BatchRequest;
+pub use checks::{Check, CheckReport};
+pub use family::{cost_by_family, rule_for, CostByFamily, ModelFamily};
+pub use fiscal::{FiscalCalendar, FiscalPattern, FiscalPeriod};
+pub use freshness::DataFreshness;
+pub use import::{check_source, parse_import, ImportError, ImportFormat};
+pub use label::{cost_by_label, labeled_ids, CostByLabel, Label, LabelTarget};
+pub use markup::Markup;
+pub use matrix::CostMatrix;
+pub use metric::{Metric, ParseMetricError};
+pub use pricing::{
+    cost_per_million_tokens, estimate_costs, price_on, ModelPrice, TokenUsageRow, UserTokenUsage,
+};
+pub use subscription::{ReportKind, ReportSchedule, ReportSubscription};
+pub use tenant::{check_tenant, DEFAULT_TENANT};
+
+/// Source of the rows the batch job fetches from Cost Explorer.
+pub const AWS_SOURCE: &str = "aws";
+
+/// Source of the credits and refunds CE applies to [`AWS_SOURCE`] rows,
+/// stored as negative amounts so the rows of both sum to the net cost.
+pub const CREDIT_SOURCE: &str = "aws-credit";
+
+/// Source of manual cost adjustments.
+pub const MANUAL_SOURCE: &str = "manual";
+
 #[derive(Debug, Clone)]
 pub struct CostRow {
     pub date: NaiveDate,
     pub user_id: String,
     pub model_id: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub currency: String,
+    pub metric: Metric,
+    /// Where the cost comes from: [`AWS_SOURCE`], [`CREDIT_SOURCE`],
+    /// [`MANUAL_SOURCE`] or an imported provider such as `openai`.
+    pub source: String,
+    /// The gateway whose user and model the row is for, by tenant name.
+    pub tenant: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct CostByUser {
     pub user_id: String,
     pub user_email: Option<String>,
-    pub amount: f64,
+    pub amount: Amount,
     pub currency: String,
 }
 
@@ -22,14 +72,73 @@ pub struct CostByUser {
 pub struct CostByModel {
     pub model_id: String,
     pub model_name: Option<String>,
-    pub amount: f64,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// Daily cost of one AWS service within the gateway tag filter.
+#[derive(Debug, Clone)]
+pub struct ServiceCostRow {
+    pub date: NaiveDate,
+    pub service: String,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// Daily cost of one gateway model.
+#[derive(Debug, Clone)]
+pub struct ModelCostRow {
+    pub date: NaiveDate,
+    pub model_id: String,
+    pub model_name: Option<String>,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// Daily cost of one gateway user.
+#[derive(Debug, Clone)]
+pub struct UserCostRow {
+    pub date: NaiveDate,
+    pub user_id: String,
+    pub user_email: Option<String>,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// Cost of one gateway model in one AWS region.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostByRegion {
+    pub region: String,
+    pub model_id: String,
+    pub model_name: Option<String>,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// A gateway user's cost made through one of their API keys.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostByApiKey {
+    /// Empty for cost CE could not tie to a key, e.g. from before the
+    /// gateway tagged keys.
+    pub api_key_id: String,
+    pub amount: Amount,
+    pub currency: String,
+}
+
+/// Gateway cost billed to one linked (member) account of the organization.
+#[derive(Debug, Clone, Serialize)]
+pub struct CostByAccount {
+    pub account_id: String,
+    /// The account name CE reports, if any.
+    pub account_name: Option<String>,
+    pub amount: Amount,
     pub currency: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
 pub struct CostRecord {
     pub date: String,
-    pub amount: f64,
+    pub amount: Amount,
     pub currency: String,
 }
 
@@ -69,3 +178,75 @@ pub struct InferenceProfileInfo {
     pub user_email: Option<String>,
     pub created_at: String,
 }
+
+/// A user's monthly spending cap. The batch job sets `limit_exceeded` once
+/// the month's cost goes over `monthly_limit`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SpendLimit {
+    pub user_id: String,
+    pub user_email: Option<String>,
+    pub monthly_limit: Amount,
+    pub limit_exceeded: bool,
+}
+
+impl SpendLimit {
+    pub fn is_exceeded_by(&self, spent: Amount) -> bool {
+        spent > self.monthly_limit
+    }
+}
+
+/// Row count and date span of one stored cost table, for the diagnostics
+/// page. Dates are `None` when the table is empty.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TableStats {
+    pub table: String,
+    pub rows: i64,
+    pub oldest: Option<String>,
+    pub newest: Option<String>,
+}
+
+/// Connections of a database pool: open ones, idle ones among them, and the
+/// most it opens.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct PoolStatus {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32,
+}
+
+impl PoolStatus {
+    pub fn in_use(&self) -> usize {
+        (self.size as usize).saturating_sub(self.idle)
+    }
+}
+
+/// A manual row in the cost table: a credit, a share of a cost CE does not
+/// tag, or a correction. Reports add it to the CE rows of the same day, user
+/// and model; at most one adjustment exists per such key and metric.
+#[derive(Debug, Clone)]
+pub struct CostAdjustment {
+    pub date: NaiveDate,
+    pub user_id: String,
+    pub user_email: Option<String>,
+    pub model_id: String,
+    pub model_name: Option<String>,
+    pub metric: Metric,
+    pub amount: Amount,
+    pub currency: String,
+    pub note: String,
+}
+
+impl CostAdjustment {
+    pub fn to_row(&self, tenant: &str) -> CostRow {
+        CostRow {
+            date: self.date,
+            user_id: self.user_id.clone(),
+            model_id: self.model_id.clone(),
+            amount: self.amount,
+            currency: self.currency.clone(),
+            metric: self.metric,
+            source: MANUAL_SOURCE.to_string(),
+            tenant: tenant.to_string(),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::{Amount, CostMatrix};
+
+/// Platform fee added to the raw cloud cost when charging internal teams
+/// back, as a percentage, optionally overridden per model.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Markup {
+    /// Percent added to the cost of models without an override.
+    pub percent: f64,
+    /// Percent by model id.
+    pub models: BTreeMap<String, f64>,
+}
+
+impl Markup {
+    /// Whether no model is marked up, so raw and charged cost are the same.
+    pub fn is_none(&self) -> bool {
+        self.percent == 0.0 && self.models.values().all(|&p| p == 0.0)
+    }
+
+    pub fn percent_for(&self, model_id: &str) -> f64 {
+        self.models.get(model_id).copied().unwrap_or(self.percent)
+    }
+
+    /// `raw` cost of `model_id` with its markup added, to the micro-unit.
+    pub fn charged(&self, model_id: &str, raw: Amount) -> Amount {
+        // Percent in millionths, so the product stays in integers.
+        let percent = Amount::from_f64(self.percent_for(model_id)).micros() as i128;
+        let scaled = raw.micros() as i128 * percent;
+        let denominator = 100_000_000i128;
+        let half = scaled.signum() * denominator / 2;
+        raw + Amount::from_micros(((scaled + half) / denominator) as i64)
+    }
+
+    /// Charged cost per user of `matrix`, adding each model's markup to the
+    /// user's cost of that model.
+    pub fn charge_users(&self, matrix: &CostMatrix) -> HashMap<String, Amount> {
+        let mut charged = HashMap::new();
+        for ((user_id, _), row) in matrix.users.iter().zip(&matrix.cells) {
+            let amount = matrix
+                .models
+                .iter()
+                .zip(row)
+                .map(|((model_id, _), raw)| self.charged(model_id, *raw))
+                .sum();
+            charged.insert(user_id.clone(), amount);
+        }
+        charged
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn charged_adds_the_model_markup() {
+        let markup = Markup {
+            percent: 10.0,
+            models: [("m2".to_string(), 2.5)].into_iter().collect(),
+        };
+        assert!(!markup.is_none());
+        assert!(Markup::default().is_none());
+        assert_eq!(
+            markup.charged("m1", Amount::from_f64(100.0)),
+            Amount::from_f64(110.0)
+        );
+        assert_eq!(
+            markup.charged("m2", Amount::from_f64(100.0)),
+            Amount::from_f64(102.5)
+        );
+        assert_eq!(
+            markup.charged("m1", Amount::from_micros(-15)),
+            Amount::from_micros(-17)
+        );
+        assert_eq!(
+            Markup::default().charged("m1", Amount::from_f64(1.23)),
+            Amount::from_f64(1.23)
+        );
+    }
+
+    #[test]
+    fn charge_users_follows_each_users_model_mix() {
+        let usd = "USD".to_string();
+        let entry = |user: &str, model: &str, amount: f64| {
+            let amount = Amount::from_f64(amount);
+            (user.to_string(), model.to_string(), amount, usd.clone())
+        };
+        let matrix = CostMatrix::from_entries([
+            entry("u1", "m1", 100.0),
+            entry("u1", "m2", 100.0),
+            entry("u2", "m2", 40.0),
+        ]);
+        let markup = Markup {
+            percent: 10.0,
+            models: [("m2".to_string(), 0.0)].into_iter().collect(),
+        };
+        let charged = markup.charge_users(&matrix);
+        assert_eq!(charged["u1"], Amount::from_f64(210.0));
+        assert_eq!(charged["u2"], Amount::from_f64(40.0));
+    }
+}
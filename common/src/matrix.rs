@@ -0,0 +1,133 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use crate::Amount;
+
+/// Cost per user (rows) and model (columns) over a period.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CostMatrix {
+    /// `(user_id, email)`, highest total first.
+    pub users: Vec<(String, Option<String>)>,
+    /// `(model_id, name)`, highest total first.
+    pub models: Vec<(String, Option<String>)>,
+    /// `cells[user][model]`, aligned with `users` and `models`.
+    pub cells: Vec<Vec<Amount>>,
+    pub currency: String,
+}
+
+impl CostMatrix {
+    /// Builds the grid from `(user_id, model_id, amount, currency)` entries,
+    /// summing repeated pairs.
+    pub fn from_entries<I>(entries: I) -> Self
+    where
+        I: IntoIterator<Item = (String, String, Amount, String)>,
+    {
+        let mut sums: HashMap<(String, String), Amount> = HashMap::new();
+        let mut currency = None;
+        for (user_id, model_id, amount, cur) in entries {
+            currency.get_or_insert(cur);
+            *sums.entry((user_id, model_id)).or_default() += amount;
+        }
+        let mut user_totals: HashMap<&str, Amount> = HashMap::new();
+        let mut model_totals: HashMap<&str, Amount> = HashMap::new();
+        for ((user_id, model_id), amount) in &sums {
+            *user_totals.entry(user_id).or_default() += *amount;
+            *model_totals.entry(model_id).or_default() += *amount;
+        }
+        let users = ranked(user_totals);
+        let models = ranked(model_totals);
+        let user_index: HashMap<&str, usize> = users
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+        let model_index: HashMap<&str, usize> = models
+            .iter()
+            .enumerate()
+            .map(|(i, id)| (id.as_str(), i))
+            .collect();
+        let mut cells = vec![vec![Amount::ZERO; models.len()]; users.len()];
+        for ((user_id, model_id), amount) in &sums {
+            cells[user_index[user_id.as_str()]][model_index[model_id.as_str()]] = *amount;
+        }
+        Self {
+            users: users.into_iter().map(|id| (id, None)).collect(),
+            models: models.into_iter().map(|id| (id, None)).collect(),
+            cells,
+            currency: currency.unwrap_or_else(|| "USD".to_string()),
+        }
+    }
+
+    pub fn user_totals(&self) -> Vec<Amount> {
+        self.cells.iter().map(|row| row.iter().sum()).collect()
+    }
+
+    pub fn model_totals(&self) -> Vec<Amount> {
+        (0..self.models.len())
+            .map(|j| self.cells.iter().map(|row| row[j]).sum())
+            .collect()
+    }
+
+    pub fn total(&self) -> Amount {
+        self.cells.iter().flatten().sum()
+    }
+}
+
+/// Ids by total, highest first, ties broken by id.
+fn ranked(totals: HashMap<&str, Amount>) -> Vec<String> {
+    let mut ranked: Vec<_> = totals.into_iter().collect();
+    ranked.sort_by_key(|(id, amount)| (Reverse(*amount), *id));
+    ranked.into_iter().map(|(id, _)| id.to_string()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(user: &str, model: &str, micros: i64) -> (String, String, Amount, String) {
+        (
+            user.to_string(),
+            model.to_string(),
+            Amount::from_micros(micros),
+            "USD".to_string(),
+        )
+    }
+
+    #[test]
+    fn from_entries_ranks_and_sums() {
+        let matrix = CostMatrix::from_entries(vec![
+            entry("u1", "m1", 1),
+            entry("u2", "m1", 5),
+            entry("u2", "m2", 1),
+            entry("u1", "m2", 3),
+            entry("u1", "m2", 3),
+        ]);
+        assert_eq!(matrix.users[0].0, "u1");
+        assert_eq!(matrix.models[0].0, "m2");
+        assert_eq!(
+            matrix.cells[0],
+            vec![Amount::from_micros(6), Amount::from_micros(1)]
+        );
+        assert_eq!(
+            matrix.cells[1],
+            vec![Amount::from_micros(1), Amount::from_micros(5)]
+        );
+        assert_eq!(
+            matrix.user_totals(),
+            vec![Amount::from_micros(7), Amount::from_micros(6)]
+        );
+        assert_eq!(
+            matrix.model_totals(),
+            vec![Amount::from_micros(7), Amount::from_micros(6)]
+        );
+        assert_eq!(matrix.total(), Amount::from_micros(13));
+        assert_eq!(matrix.currency, "USD");
+    }
+
+    #[test]
+    fn from_entries_empty() {
+        let matrix = CostMatrix::from_entries(Vec::new());
+        assert!(matrix.users.is_empty());
+        assert_eq!(matrix.total(), Amount::ZERO);
+    }
+}
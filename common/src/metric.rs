@@ -0,0 +1,86 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Cost Explorer cost metric. Serialized as the CE metric name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Metric {
+    #[default]
+    #[serde(rename = "BlendedCost")]
+    Blended,
+    #[serde(rename = "UnblendedCost")]
+    Unblended,
+    #[serde(rename = "AmortizedCost")]
+    Amortized,
+    #[serde(rename = "NetUnblendedCost")]
+    NetUnblended,
+}
+
+impl Metric {
+    pub const ALL: [Metric; 4] = [
+        Metric::Blended,
+        Metric::Unblended,
+        Metric::Amortized,
+        Metric::NetUnblended,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Metric::Blended => "BlendedCost",
+            Metric::Unblended => "UnblendedCost",
+            Metric::Amortized => "AmortizedCost",
+            Metric::NetUnblended => "NetUnblendedCost",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Metric::Blended => "Blended",
+            Metric::Unblended => "Unblended",
+            Metric::Amortized => "Amortized",
+            Metric::NetUnblended => "Net Unblended",
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseMetricError(String);
+
+impl fmt::Display for ParseMetricError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown cost metric: {:?}", self.0)
+    }
+}
+
+impl std::error::Error for ParseMetricError {}
+
+impl FromStr for Metric {
+    type Err = ParseMetricError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Metric::ALL
+            .into_iter()
+            .find(|m| m.as_str() == s)
+            .ok_or_else(|| ParseMetricError(s.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_round_trip() {
+        for metric in Metric::ALL {
+            assert_eq!(metric.as_str().parse::<Metric>().unwrap(), metric);
+        }
+        assert!("blended".parse::<Metric>().is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::Amount;
+
+/// List price of a gateway model per 1K tokens, in effect from
+/// `effective_from` until the model's next price starts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelPrice {
+    pub model_id: String,
+    pub model_name: Option<String>,
+    pub effective_from: NaiveDate,
+    pub input_per_1k: Amount,
+    pub output_per_1k: Amount,
+}
+
+impl ModelPrice {
+    /// What `input_tokens` and `output_tokens` cost at this price.
+    pub fn cost(&self, input_tokens: i64, output_tokens: i64) -> Amount {
+        let micros = self.input_per_1k.micros() as i128 * input_tokens as i128
+            + self.output_per_1k.micros() as i128 * output_tokens as i128;
+        Amount::from_micros((micros / 1000) as i64)
+    }
+}
+
+/// Tokens one gateway model used on one day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TokenUsageRow {
+    pub date: NaiveDate,
+    pub model_id: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// Tokens one gateway user used over a period.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UserTokenUsage {
+    pub user_id: String,
+    pub input_tokens: i64,
+    pub output_tokens: i64,
+}
+
+/// What one million tokens cost when `tokens` cost `amount`, or `None`
+/// without any tokens.
+pub fn cost_per_million_tokens(amount: Amount, tokens: i64) -> Option<Amount> {
+    (tokens > 0)
+        .then(|| Amount::from_micros((amount.micros() as i128 * 1_000_000 / tokens as i128) as i64))
+}
+
+/// The price of `model_id` in effect on `date`, if any.
+pub fn price_on<'a>(
+    prices: &'a [ModelPrice],
+    model_id: &str,
+    date: NaiveDate,
+) -> Option<&'a ModelPrice> {
+    prices
+        .iter()
+        .filter(|p| p.model_id == model_id && p.effective_from <= date)
+        .max_by_key(|p| p.effective_from)
+}
+
+/// Estimated cost per model id. Days without a price in effect are left out.
+pub fn estimate_costs(prices: &[ModelPrice], usage: &[TokenUsageRow]) -> HashMap<String, Amount> {
+    let mut estimates = HashMap::new();
+    for row in usage {
+        let Some(price) = price_on(prices, &row.model_id, row.date) else {
+            continue;
+        };
+        *estimates
+            .entry(row.model_id.clone())
+            .or_insert(Amount::ZERO) += price.cost(row.input_tokens, row.output_tokens);
+    }
+    estimates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn price(from: &str, input: f64, output: f64) -> ModelPrice {
+        ModelPrice {
+            model_id: "m1".to_string(),
+            model_name: None,
+            effective_from: date(from),
+            input_per_1k: Amount::from_f64(input),
+            output_per_1k: Amount::from_f64(output),
+        }
+    }
+
+    fn usage(day: &str, input: i64, output: i64) -> TokenUsageRow {
+        TokenUsageRow {
+            date: date(day),
+            model_id: "m1".to_string(),
+            input_tokens: input,
+            output_tokens: output,
+        }
+    }
+
+    #[test]
+    fn cost_is_per_thousand_tokens() {
+        let p = price("2025-01-01", 0.003, 0.015);
+        assert_eq!(p.cost(2_000, 1_000), Amount::from_f64(0.021));
+    }
+
+    #[test]
+    fn cost_per_million_scales_to_a_million_tokens() {
+        assert_eq!(
+            cost_per_million_tokens(Amount::from_f64(0.75), 250_000),
+            Some(Amount::from_f64(3.0))
+        );
+        assert_eq!(cost_per_million_tokens(Amount::from_f64(1.0), 0), None);
+    }
+
+    #[test]
+    fn estimate_uses_price_in_effect() {
+        let prices = vec![
+            price("2025-01-01", 0.001, 0.0),
+            price("2025-02-01", 0.002, 0.0),
+        ];
+        let rows = vec![
+            usage("2024-12-31", 1_000, 0),
+            usage("2025-01-31", 1_000, 0),
+            usage("2025-02-01", 1_000, 0),
+        ];
+        let estimates = estimate_costs(&prices, &rows);
+        assert_eq!(estimates["m1"], Amount::from_f64(0.003));
+        assert!(price_on(&prices, "m2", date("2025-03-01")).is_none());
+    }
+}
@@ -0,0 +1,173 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+/// What a [`ReportSubscription`] sends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportKind {
+    /// Total cost with its per-day and per-model breakdown.
+    DailySummary,
+    /// The users with the highest cost.
+    TopSpenders,
+    /// Total cost by model and by user.
+    MonthlyStatement,
+}
+
+impl ReportKind {
+    pub const ALL: [Self; 3] = [
+        Self::DailySummary,
+        Self::TopSpenders,
+        Self::MonthlyStatement,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::DailySummary => "daily_summary",
+            Self::TopSpenders => "top_spenders",
+            Self::MonthlyStatement => "monthly_statement",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::DailySummary => "Daily summary",
+            Self::TopSpenders => "Weekly top spenders",
+            Self::MonthlyStatement => "Monthly statement",
+        }
+    }
+}
+
+/// How often a [`ReportSubscription`] is sent. Each send covers the last
+/// whole day, ISO week or month.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportSchedule {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+impl ReportSchedule {
+    pub const ALL: [Self; 3] = [Self::Daily, Self::Weekly, Self::Monthly];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|schedule| schedule.as_str() == s)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Daily => "Every day",
+            Self::Weekly => "Every Monday",
+            Self::Monthly => "On the 1st of each month",
+        }
+    }
+
+    /// The last whole period before `today`, as `[start, end)`.
+    pub fn last_period(self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let end = match self {
+            Self::Daily => today,
+            Self::Weekly => {
+                today - Duration::days(i64::from(today.weekday().num_days_from_monday()))
+            }
+            Self::Monthly => today.with_day(1).unwrap_or(today),
+        };
+        let start = match self {
+            Self::Daily => end - Duration::days(1),
+            Self::Weekly => end - Duration::days(7),
+            Self::Monthly => end - Months::new(1),
+        };
+        (start, end)
+    }
+}
+
+/// An admin-set scheduled report, emailed by the batch daemon.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReportSubscription {
+    pub id: i64,
+    pub recipient: String,
+    pub kind: ReportKind,
+    pub schedule: ReportSchedule,
+    /// Start of the last period sent.
+    pub last_sent: Option<NaiveDate>,
+}
+
+impl ReportSubscription {
+    /// The period to send on `today`, unless it was sent already.
+    pub fn due(&self, today: NaiveDate) -> Option<(NaiveDate, NaiveDate)> {
+        let (start, end) = self.schedule.last_period(today);
+        self.last_sent
+            .is_none_or(|last| last < start)
+            .then_some((start, end))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn names_round_trip() {
+        for kind in ReportKind::ALL {
+            assert_eq!(ReportKind::parse(kind.as_str()), Some(kind));
+        }
+        for schedule in ReportSchedule::ALL {
+            assert_eq!(ReportSchedule::parse(schedule.as_str()), Some(schedule));
+        }
+        assert_eq!(ReportKind::parse("yearly"), None);
+    }
+
+    #[test]
+    fn last_period_is_the_last_whole_one() {
+        // A Wednesday.
+        let today = date("2025-03-12");
+        assert_eq!(
+            ReportSchedule::Daily.last_period(today),
+            (date("2025-03-11"), today)
+        );
+        assert_eq!(
+            ReportSchedule::Weekly.last_period(today),
+            (date("2025-03-03"), date("2025-03-10"))
+        );
+        assert_eq!(
+            ReportSchedule::Monthly.last_period(today),
+            (date("2025-02-01"), date("2025-03-01"))
+        );
+    }
+
+    #[test]
+    fn due_once_per_period() {
+        let mut subscription = ReportSubscription {
+            id: 1,
+            recipient: "finance@example.com".to_string(),
+            kind: ReportKind::TopSpenders,
+            schedule: ReportSchedule::Weekly,
+            last_sent: None,
+        };
+        let today = date("2025-03-12");
+        let period = (date("2025-03-03"), date("2025-03-10"));
+        assert_eq!(subscription.due(today), Some(period));
+
+        subscription.last_sent = Some(period.0);
+        assert_eq!(subscription.due(today), None);
+        assert_eq!(subscription.due(date("2025-03-16")), None);
+        assert_eq!(
+            subscription.due(date("2025-03-17")),
+            Some((date("2025-03-10"), date("2025-03-17")))
+        );
+    }
+}
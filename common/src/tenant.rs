@@ -0,0 +1,31 @@
+/// Tenant of the gateway in `database_url_gateway_ro`, and of the cost rows
+/// stored before there were several.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Checks that `tenant` names a gateway in lowercase letters, digits, `-`
+/// and `_`, since it appears in URLs.
+pub fn check_tenant(tenant: &str) -> Result<(), String> {
+    let valid = !tenant.is_empty()
+        && tenant
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-' || b == b'_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid tenant name: {tenant:?}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tenant_names_are_url_safe() {
+        assert!(check_tenant(DEFAULT_TENANT).is_ok());
+        assert!(check_tenant("prod-eu_2").is_ok());
+        assert!(check_tenant("").is_err());
+        assert!(check_tenant("Prod").is_err());
+        assert!(check_tenant("dev/1").is_err());
+    }
+}
@@ -0,0 +1,5 @@
+// Rebuild when migrations change, as `sqlx::migrate!` embeds them.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+    println!("cargo:rerun-if-changed=migrations-sqlite");
+}
@@ -0,0 +1,49 @@
+-- The Postgres schema in `migrations`, for the SQLite backend. Amounts are
+-- integer micro-units, dates `YYYY-MM-DD` text and timestamps UTC text.
+CREATE TABLE cost (
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    metric TEXT NOT NULL DEFAULT 'BlendedCost',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    adjustment BOOLEAN NOT NULL DEFAULT FALSE,
+    note TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (date, user_id, model_id, metric, adjustment)
+);
+
+CREATE TABLE roles (
+    user_email TEXT PRIMARY KEY,
+    role TEXT NOT NULL CHECK (role IN ('admin', 'finance', 'viewer', 'self_only')),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE spend_limits (
+    user_id TEXT PRIMARY KEY,
+    monthly_limit INTEGER NOT NULL CHECK (monthly_limit >= 0),
+    limit_exceeded BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE model_prices (
+    model_id TEXT NOT NULL,
+    effective_from TEXT NOT NULL,
+    input_per_1k INTEGER NOT NULL CHECK (input_per_1k >= 0),
+    output_per_1k INTEGER NOT NULL CHECK (output_per_1k >= 0),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (model_id, effective_from)
+);
+
+-- Monthly statements already emailed, so each is sent once.
+CREATE TABLE statement_sends (
+    user_id TEXT NOT NULL,
+    month TEXT NOT NULL,
+    email TEXT NOT NULL,
+    sent_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id, month)
+);
@@ -0,0 +1,26 @@
+-- Daily totals per user and per model; see the Postgres migration.
+CREATE TABLE cost_daily_user (
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, user_id, metric)
+);
+
+CREATE TABLE cost_daily_model (
+    date TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, model_id, metric)
+);
+
+INSERT INTO cost_daily_user (date, user_id, metric, amount, currency)
+SELECT date, user_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, user_id, metric;
+
+INSERT INTO cost_daily_model (date, model_id, metric, amount, currency)
+SELECT date, model_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, model_id, metric;
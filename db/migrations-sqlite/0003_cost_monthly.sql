@@ -0,0 +1,14 @@
+-- Monthly rollup of the cost table; see the Postgres migration.
+CREATE TABLE cost_monthly (
+    month TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (month, user_id, model_id, metric)
+);
+
+INSERT INTO cost_monthly (month, user_id, model_id, metric, amount, currency)
+SELECT strftime('%Y-%m-01', date), user_id, model_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY strftime('%Y-%m-01', date), user_id, model_id, metric;
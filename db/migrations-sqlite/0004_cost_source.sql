@@ -0,0 +1,25 @@
+-- The source of each cost row; see the Postgres migration. SQLite cannot
+-- change a primary key, so the table is rebuilt.
+CREATE TABLE cost_new (
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    metric TEXT NOT NULL DEFAULT 'BlendedCost',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    adjustment BOOLEAN NOT NULL DEFAULT FALSE,
+    note TEXT NOT NULL DEFAULT '',
+    source TEXT NOT NULL DEFAULT 'aws',
+    PRIMARY KEY (date, user_id, model_id, metric, adjustment, source)
+);
+
+INSERT INTO cost_new (date, user_id, model_id, amount, currency, metric,
+                      created_at, updated_at, adjustment, note)
+SELECT date, user_id, model_id, amount, currency, metric,
+       created_at, updated_at, adjustment, note
+FROM cost;
+
+DROP TABLE cost;
+ALTER TABLE cost_new RENAME TO cost;
@@ -0,0 +1,50 @@
+-- Sources in the aggregates; see the Postgres migration. The tables only
+-- hold derived totals, so they are recreated.
+UPDATE cost SET source = 'manual' WHERE adjustment;
+
+DROP TABLE cost_daily_user;
+DROP TABLE cost_daily_model;
+DROP TABLE cost_monthly;
+
+CREATE TABLE cost_daily_user (
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, user_id, metric, source)
+);
+
+CREATE TABLE cost_daily_model (
+    date TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, model_id, metric, source)
+);
+
+CREATE TABLE cost_monthly (
+    month TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (month, user_id, model_id, metric, source)
+);
+
+INSERT INTO cost_daily_user (date, user_id, metric, source, amount, currency)
+SELECT date, user_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, user_id, metric, source;
+
+INSERT INTO cost_daily_model (date, model_id, metric, source, amount, currency)
+SELECT date, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, model_id, metric, source;
+
+INSERT INTO cost_monthly (month, user_id, model_id, metric, source, amount, currency)
+SELECT strftime('%Y-%m-01', date), user_id, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY strftime('%Y-%m-01', date), user_id, model_id, metric, source;
@@ -0,0 +1,10 @@
+-- Users' own cost alerts; see the Postgres migration.
+CREATE TABLE cost_alerts (
+    user_id TEXT PRIMARY KEY,
+    daily_threshold INTEGER CHECK (daily_threshold >= 0),
+    monthly_threshold INTEGER CHECK (monthly_threshold >= 0),
+    daily_alerted TEXT,
+    monthly_alerted TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,7 @@
+-- Model family rules; see the Postgres migration.
+CREATE TABLE model_families (
+    prefix TEXT PRIMARY KEY CHECK (prefix <> ''),
+    family TEXT NOT NULL CHECK (family <> ''),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,8 @@
+-- Reporting labels; see the Postgres migration.
+CREATE TABLE labels (
+    target TEXT NOT NULL CHECK (target IN ('user', 'model')),
+    target_id TEXT NOT NULL,
+    label TEXT NOT NULL CHECK (label <> ''),
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (target, target_id, label)
+);
@@ -0,0 +1,10 @@
+-- Scheduled reports; see the Postgres migration.
+CREATE TABLE report_subscriptions (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recipient TEXT NOT NULL CHECK (recipient <> ''),
+    report TEXT NOT NULL
+        CHECK (report IN ('daily_summary', 'top_spenders', 'monthly_statement')),
+    schedule TEXT NOT NULL CHECK (schedule IN ('daily', 'weekly', 'monthly')),
+    last_sent TEXT,
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,5 @@
+-- Data freshness timestamps; see the Postgres migration.
+CREATE TABLE meta (
+    key TEXT PRIMARY KEY,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+-- Requested batch runs; see the Postgres migration.
+CREATE TABLE batch_requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    requested_by TEXT NOT NULL,
+    requested_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    started_at TEXT,
+    finished_at TEXT,
+    error TEXT
+);
@@ -0,0 +1,6 @@
+-- Ingestion high-watermarks; see the Postgres migration.
+CREATE TABLE ingest_watermarks (
+    source TEXT PRIMARY KEY,
+    through TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
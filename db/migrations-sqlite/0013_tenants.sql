@@ -0,0 +1,90 @@
+-- The tenant of each cost row; see the Postgres migration. SQLite cannot
+-- change a primary key, so the tables are rebuilt.
+CREATE TABLE cost_new (
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    metric TEXT NOT NULL DEFAULT 'BlendedCost',
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    adjustment BOOLEAN NOT NULL DEFAULT FALSE,
+    note TEXT NOT NULL DEFAULT '',
+    source TEXT NOT NULL DEFAULT 'aws',
+    tenant TEXT NOT NULL DEFAULT 'default',
+    PRIMARY KEY (tenant, date, user_id, model_id, metric, adjustment, source)
+);
+
+INSERT INTO cost_new (date, user_id, model_id, amount, currency, metric,
+                      created_at, updated_at, adjustment, note, source)
+SELECT date, user_id, model_id, amount, currency, metric,
+       created_at, updated_at, adjustment, note, source
+FROM cost;
+
+DROP TABLE cost;
+ALTER TABLE cost_new RENAME TO cost;
+
+DROP TABLE cost_daily_user;
+DROP TABLE cost_daily_model;
+DROP TABLE cost_monthly;
+
+CREATE TABLE cost_daily_user (
+    tenant TEXT NOT NULL,
+    date TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (tenant, date, user_id, metric, source)
+);
+
+CREATE TABLE cost_daily_model (
+    tenant TEXT NOT NULL,
+    date TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (tenant, date, model_id, metric, source)
+);
+
+CREATE TABLE cost_monthly (
+    tenant TEXT NOT NULL,
+    month TEXT NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    source TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (tenant, month, user_id, model_id, metric, source)
+);
+
+INSERT INTO cost_daily_user (tenant, date, user_id, metric, source, amount, currency)
+SELECT tenant, date, user_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY tenant, date, user_id, metric, source;
+
+INSERT INTO cost_daily_model (tenant, date, model_id, metric, source, amount, currency)
+SELECT tenant, date, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY tenant, date, model_id, metric, source;
+
+INSERT INTO cost_monthly (tenant, month, user_id, model_id, metric, source, amount, currency)
+SELECT tenant, strftime('%Y-%m-01', date), user_id, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY tenant, strftime('%Y-%m-01', date), user_id, model_id, metric, source;
+
+CREATE TABLE ingest_watermarks_new (
+    tenant TEXT NOT NULL DEFAULT 'default',
+    source TEXT NOT NULL,
+    through TEXT NOT NULL,
+    updated_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (tenant, source)
+);
+
+INSERT INTO ingest_watermarks_new (source, through, updated_at)
+SELECT source, through, updated_at FROM ingest_watermarks;
+
+DROP TABLE ingest_watermarks;
+ALTER TABLE ingest_watermarks_new RENAME TO ingest_watermarks;
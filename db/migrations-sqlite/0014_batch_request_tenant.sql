@@ -0,0 +1,2 @@
+-- The tenant of each requested batch run; see the Postgres migration.
+ALTER TABLE batch_requests ADD COLUMN tenant TEXT NOT NULL DEFAULT 'default';
@@ -0,0 +1,46 @@
+-- Daily cost per gateway user, model and CE metric, plus manual adjustments.
+-- Databases set up before migrations existed may already hold an older
+-- version of the table, so it is created if missing and then upgraded.
+CREATE TABLE IF NOT EXISTS cost (
+    date DATE NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    amount NUMERIC(20, 6) NOT NULL,
+    currency TEXT NOT NULL DEFAULT 'USD',
+    metric TEXT NOT NULL DEFAULT 'BlendedCost',
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    adjustment BOOLEAN NOT NULL DEFAULT FALSE,
+    note TEXT NOT NULL DEFAULT '',
+    PRIMARY KEY (date, user_id, model_id, metric, adjustment)
+);
+
+-- Tables created before amounts were fixed-point stored them as floats.
+DO $$ BEGIN
+    IF EXISTS (
+        SELECT 1 FROM information_schema.columns
+        WHERE table_name = 'cost' AND column_name = 'amount'
+          AND data_type = 'double precision'
+    ) THEN
+        ALTER TABLE cost ALTER COLUMN amount TYPE NUMERIC(20, 6);
+    END IF;
+END $$;
+
+-- Tables created before metrics were stored held BlendedCost only.
+ALTER TABLE cost ADD COLUMN IF NOT EXISTS metric TEXT NOT NULL DEFAULT 'BlendedCost';
+
+-- Manual adjustments sit next to the CE row with the same key.
+ALTER TABLE cost
+    ADD COLUMN IF NOT EXISTS adjustment BOOLEAN NOT NULL DEFAULT FALSE,
+    ADD COLUMN IF NOT EXISTS note TEXT NOT NULL DEFAULT '';
+
+DO $$ BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM information_schema.key_column_usage
+        WHERE table_name = 'cost' AND constraint_name = 'cost_pkey'
+          AND column_name = 'adjustment'
+    ) THEN
+        ALTER TABLE cost DROP CONSTRAINT cost_pkey,
+            ADD PRIMARY KEY (date, user_id, model_id, metric, adjustment);
+    END IF;
+END $$;
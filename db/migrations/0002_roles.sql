@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS roles (
+    user_email TEXT PRIMARY KEY,
+    role TEXT NOT NULL CHECK (role IN ('admin', 'finance', 'viewer', 'self_only')),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,7 @@
+CREATE TABLE IF NOT EXISTS spend_limits (
+    user_id TEXT PRIMARY KEY,
+    monthly_limit NUMERIC(20, 6) NOT NULL CHECK (monthly_limit >= 0),
+    limit_exceeded BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
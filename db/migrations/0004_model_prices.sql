@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS model_prices (
+    model_id TEXT NOT NULL,
+    effective_from DATE NOT NULL,
+    input_per_1k NUMERIC(20, 6) NOT NULL CHECK (input_per_1k >= 0),
+    output_per_1k NUMERIC(20, 6) NOT NULL CHECK (output_per_1k >= 0),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (model_id, effective_from)
+);
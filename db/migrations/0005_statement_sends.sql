@@ -0,0 +1,8 @@
+-- Monthly statements already emailed, so each is sent once.
+CREATE TABLE IF NOT EXISTS statement_sends (
+    user_id TEXT NOT NULL,
+    month DATE NOT NULL,
+    email TEXT NOT NULL,
+    sent_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (user_id, month)
+);
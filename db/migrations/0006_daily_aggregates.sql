@@ -0,0 +1,29 @@
+-- Daily totals per user and per model, kept in step with the cost table by
+-- every write to it, so drill-down pages avoid grouping the full table.
+CREATE TABLE IF NOT EXISTS cost_daily_user (
+    date DATE NOT NULL,
+    user_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount NUMERIC(20, 6) NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, user_id, metric)
+);
+
+CREATE TABLE IF NOT EXISTS cost_daily_model (
+    date DATE NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount NUMERIC(20, 6) NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (date, model_id, metric)
+);
+
+INSERT INTO cost_daily_user (date, user_id, metric, amount, currency)
+SELECT date, user_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, user_id, metric
+ON CONFLICT DO NOTHING;
+
+INSERT INTO cost_daily_model (date, model_id, metric, amount, currency)
+SELECT date, model_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, model_id, metric
+ON CONFLICT DO NOTHING;
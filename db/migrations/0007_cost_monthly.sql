@@ -0,0 +1,17 @@
+-- Monthly rollup of the cost table, rebuilt by the batch job after each run
+-- and on demand from the admin pages, so monthly views read one row per
+-- month instead of aggregating every day.
+CREATE TABLE IF NOT EXISTS cost_monthly (
+    month DATE NOT NULL,
+    user_id TEXT NOT NULL,
+    model_id TEXT NOT NULL,
+    metric TEXT NOT NULL,
+    amount NUMERIC(20, 6) NOT NULL,
+    currency TEXT NOT NULL,
+    PRIMARY KEY (month, user_id, model_id, metric)
+);
+
+INSERT INTO cost_monthly (month, user_id, model_id, metric, amount, currency)
+SELECT DATE_TRUNC('month', date)::DATE, user_id, model_id, metric, SUM(amount), MIN(currency)
+FROM cost GROUP BY DATE_TRUNC('month', date), user_id, model_id, metric
+ON CONFLICT DO NOTHING;
@@ -0,0 +1,7 @@
+-- Where each cost row came from: `aws` for rows the batch job fetches from
+-- Cost Explorer, or the provider named when importing a cost file, so
+-- imported rows sit next to CE rows with the same key.
+ALTER TABLE cost ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'aws';
+
+ALTER TABLE cost DROP CONSTRAINT cost_pkey,
+    ADD PRIMARY KEY (date, user_id, model_id, metric, adjustment, source);
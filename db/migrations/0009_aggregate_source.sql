@@ -0,0 +1,29 @@
+-- Manual adjustments get a source of their own, and the daily aggregates
+-- and monthly rollup keep sources apart so pages can narrow them to one.
+UPDATE cost SET source = 'manual' WHERE adjustment;
+
+ALTER TABLE cost_daily_user ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'aws';
+ALTER TABLE cost_daily_user DROP CONSTRAINT cost_daily_user_pkey,
+    ADD PRIMARY KEY (date, user_id, metric, source);
+
+ALTER TABLE cost_daily_model ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'aws';
+ALTER TABLE cost_daily_model DROP CONSTRAINT cost_daily_model_pkey,
+    ADD PRIMARY KEY (date, model_id, metric, source);
+
+ALTER TABLE cost_monthly ADD COLUMN IF NOT EXISTS source TEXT NOT NULL DEFAULT 'aws';
+ALTER TABLE cost_monthly DROP CONSTRAINT cost_monthly_pkey,
+    ADD PRIMARY KEY (month, user_id, model_id, metric, source);
+
+TRUNCATE cost_daily_user, cost_daily_model, cost_monthly;
+
+INSERT INTO cost_daily_user (date, user_id, metric, source, amount, currency)
+SELECT date, user_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, user_id, metric, source;
+
+INSERT INTO cost_daily_model (date, model_id, metric, source, amount, currency)
+SELECT date, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY date, model_id, metric, source;
+
+INSERT INTO cost_monthly (month, user_id, model_id, metric, source, amount, currency)
+SELECT DATE_TRUNC('month', date)::DATE, user_id, model_id, metric, source, SUM(amount), MIN(currency)
+FROM cost GROUP BY DATE_TRUNC('month', date), user_id, model_id, metric, source;
@@ -0,0 +1,10 @@
+-- Users' own cost alert thresholds, and the last day and month alerted on.
+CREATE TABLE IF NOT EXISTS cost_alerts (
+    user_id TEXT PRIMARY KEY,
+    daily_threshold NUMERIC(20, 6) CHECK (daily_threshold >= 0),
+    monthly_threshold NUMERIC(20, 6) CHECK (monthly_threshold >= 0),
+    daily_alerted DATE,
+    monthly_alerted DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
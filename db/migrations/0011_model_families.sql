@@ -0,0 +1,7 @@
+-- Admin-set rules rolling models up into families by name prefix.
+CREATE TABLE IF NOT EXISTS model_families (
+    prefix TEXT PRIMARY KEY CHECK (prefix <> ''),
+    family TEXT NOT NULL CHECK (family <> ''),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
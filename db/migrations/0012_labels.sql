@@ -0,0 +1,8 @@
+-- Admin-set reporting labels on gateway users and models.
+CREATE TABLE IF NOT EXISTS labels (
+    target TEXT NOT NULL CHECK (target IN ('user', 'model')),
+    target_id TEXT NOT NULL,
+    label TEXT NOT NULL CHECK (label <> ''),
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (target, target_id, label)
+);
@@ -0,0 +1,11 @@
+-- Admin-set scheduled reports the batch daemon emails, and the start of
+-- the last period each was sent for.
+CREATE TABLE IF NOT EXISTS report_subscriptions (
+    id BIGSERIAL PRIMARY KEY,
+    recipient TEXT NOT NULL CHECK (recipient <> ''),
+    report TEXT NOT NULL
+        CHECK (report IN ('daily_summary', 'top_spenders', 'monthly_statement')),
+    schedule TEXT NOT NULL CHECK (schedule IN ('daily', 'weekly', 'monthly')),
+    last_sent DATE,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,6 @@
+-- When the stored data was last brought up to date, by key: the end of the
+-- last successful batch run and the last monthly rollup rebuild.
+CREATE TABLE IF NOT EXISTS meta (
+    key TEXT PRIMARY KEY,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
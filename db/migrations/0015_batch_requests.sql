@@ -0,0 +1,10 @@
+-- Batch runs requested by admins from the server, consumed by the batch
+-- daemon; one run answers every request pending when it starts.
+CREATE TABLE IF NOT EXISTS batch_requests (
+    id BIGSERIAL PRIMARY KEY,
+    requested_by TEXT NOT NULL,
+    requested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMPTZ,
+    finished_at TIMESTAMPTZ,
+    error TEXT
+);
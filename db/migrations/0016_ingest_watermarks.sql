@@ -0,0 +1,7 @@
+-- Last day fully ingested per cost source. Incremental batch runs fetch
+-- from a settlement window before it, so missed days are caught up.
+CREATE TABLE IF NOT EXISTS ingest_watermarks (
+    source TEXT PRIMARY KEY,
+    through DATE NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
@@ -0,0 +1,22 @@
+-- The gateway each cost row belongs to, so one cost database can serve
+-- several gateways (tenants). Rows stored before tenants existed belong to
+-- the gateway in `database_url_gateway_ro`, the `default` tenant.
+ALTER TABLE cost ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE cost DROP CONSTRAINT cost_pkey,
+    ADD PRIMARY KEY (tenant, date, user_id, model_id, metric, adjustment, source);
+
+ALTER TABLE cost_daily_user ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE cost_daily_user DROP CONSTRAINT cost_daily_user_pkey,
+    ADD PRIMARY KEY (tenant, date, user_id, metric, source);
+
+ALTER TABLE cost_daily_model ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE cost_daily_model DROP CONSTRAINT cost_daily_model_pkey,
+    ADD PRIMARY KEY (tenant, date, model_id, metric, source);
+
+ALTER TABLE cost_monthly ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE cost_monthly DROP CONSTRAINT cost_monthly_pkey,
+    ADD PRIMARY KEY (tenant, month, user_id, model_id, metric, source);
+
+ALTER TABLE ingest_watermarks ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
+ALTER TABLE ingest_watermarks DROP CONSTRAINT ingest_watermarks_pkey,
+    ADD PRIMARY KEY (tenant, source);
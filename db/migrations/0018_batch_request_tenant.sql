@@ -0,0 +1,4 @@
+-- The gateway (tenant) a batch run was requested for, so each tenant's
+-- daemon claims only its own requests. Requests queued before tenants
+-- existed belong to the `default` tenant.
+ALTER TABLE batch_requests ADD COLUMN IF NOT EXISTS tenant TEXT NOT NULL DEFAULT 'default';
@@ -1,12 +1,18 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 
 use anyhow::Result;
-use chrono::NaiveDate;
-use common::{ApiKeyInfo, CostByModel, CostByUser, CostRecord, CostRow, InferenceProfileInfo, ModelInfo, UserInfo};
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+use common::{AlertPeriod, Amount, ApiKeyInfo, BatchRequest, CostAdjustment, CostAlert, CostByModel, CostByUser, CostRecord, CostRow, DataFreshness, InferenceProfileInfo, Label, LabelTarget, Metric, ModelCostRow, ModelFamily, ModelInfo, ModelPrice, PoolStatus, ReportKind, ReportSchedule, ReportSubscription, SpendLimit, TableStats, UserCostRow, UserInfo, AWS_SOURCE, CREDIT_SOURCE, MANUAL_SOURCE};
 use sqlx::postgres::PgPoolOptions;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod store;
+
+pub use store::{CostDb, CostStore};
+
 pub async fn init_pool(database_url: &str) -> Result<PgPool> {
     let pool = PgPoolOptions::new()
         .max_connections(5)
@@ -22,6 +28,22 @@ pub fn init_pool_lazy(database_url: &str) -> Result<PgPool> {
     Ok(pool)
 }
 
+/// Connections `pool` holds and may open.
+pub fn pool_status<DB: sqlx::Database>(pool: &sqlx::Pool<DB>) -> PoolStatus {
+    PoolStatus {
+        size: pool.size(),
+        idle: pool.num_idle(),
+        max: pool.options().get_max_connections(),
+    }
+}
+
+/// Applies pending migrations from `db/migrations` to the cost database.
+/// The first ones also upgrade tables created before migrations existed.
+pub async fn migrate(pool: &PgPool) -> Result<()> {
+    sqlx::migrate!().run(pool).await?;
+    Ok(())
+}
+
 pub async fn get_user_email(pool: &PgPool, user_id: Uuid) -> Option<String> {
     sqlx::query_scalar::<_, String>("select user_email from users where user_id = $1::uuid")
         .bind(user_id.to_string().to_lowercase())
@@ -31,6 +53,19 @@ pub async fn get_user_email(pool: &PgPool, user_id: Uuid) -> Option<String> {
         .flatten()
 }
 
+pub async fn get_user_emails(pool: &PgPool, user_ids: &[Uuid]) -> Result<HashMap<Uuid, String>> {
+    if user_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        "select user_id, user_email from users where user_id = any($1)",
+    )
+    .bind(user_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
 pub async fn get_user_id_by_email(pool: &PgPool, email: &str) -> Option<Uuid> {
     sqlx::query_scalar::<_, Uuid>("select user_id from users where user_email = $1")
         .bind(email)
@@ -49,6 +84,19 @@ pub async fn get_model_name(pool: &PgPool, model_id: Uuid) -> Option<String> {
         .flatten()
 }
 
+pub async fn get_model_names(pool: &PgPool, model_ids: &[Uuid]) -> Result<HashMap<Uuid, String>> {
+    if model_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        "select model_id, model_name from models where model_id = any($1)",
+    )
+    .bind(model_ids)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().collect())
+}
+
 pub async fn list_users(pool: &PgPool) -> Result<Vec<(Uuid, String)>> {
     let rows = sqlx::query_as::<_, (Uuid, String)>(
         "select user_id, user_email from users order by user_email",
@@ -67,6 +115,43 @@ pub async fn list_models(pool: &PgPool) -> Result<Vec<(Uuid, String)>> {
     Ok(rows)
 }
 
+/// `%query%` with LIKE wildcards in `query` matched literally.
+fn contains_pattern(query: &str) -> String {
+    let escaped = query
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// Users whose email or id contains `query`, case-insensitively.
+pub async fn search_users(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<(Uuid, String)>> {
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        "select user_id, user_email from users \
+         where user_email ilike $1 or user_id::text ilike $1 \
+         order by user_email limit $2",
+    )
+    .bind(contains_pattern(query))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
+/// Models whose name or id contains `query`, case-insensitively.
+pub async fn search_models(pool: &PgPool, query: &str, limit: i64) -> Result<Vec<(Uuid, String)>> {
+    let rows = sqlx::query_as::<_, (Uuid, String)>(
+        "select model_id, model_name from models \
+         where model_name ilike $1 or model_id::text ilike $1 \
+         order by model_name limit $2",
+    )
+    .bind(contains_pattern(query))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows)
+}
+
 pub async fn list_user_ids(pool: &PgPool) -> Result<HashSet<String>> {
     let rows = sqlx::query_scalar::<_, Uuid>("SELECT user_id FROM users")
         .fetch_all(pool)
@@ -274,91 +359,1118 @@ pub async fn list_profiles_for_user(
 
 // --- Cost table functions ---
 
-pub async fn create_cost_table(pool: &PgPool) -> Result<()> {
+pub async fn get_role(pool: &PgPool, email: &str) -> Result<Option<String>> {
+    let role = sqlx::query_scalar::<_, String>("SELECT role FROM roles WHERE user_email = $1")
+        .bind(email)
+        .fetch_optional(pool)
+        .await?;
+    Ok(role)
+}
+
+fn spend_limit((user_id, monthly_limit, limit_exceeded): (String, i64, bool)) -> SpendLimit {
+    SpendLimit {
+        user_id,
+        user_email: None,
+        monthly_limit: Amount::from_micros(monthly_limit),
+        limit_exceeded,
+    }
+}
+
+pub async fn list_spend_limits(pool: &PgPool) -> Result<Vec<SpendLimit>> {
+    let rows = sqlx::query_as::<_, (String, i64, bool)>(
+        r#"SELECT user_id, (monthly_limit * 1000000)::BIGINT, limit_exceeded
+           FROM spend_limits ORDER BY user_id"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(spend_limit).collect())
+}
+
+pub async fn get_spend_limit(pool: &PgPool, user_id: &str) -> Result<Option<SpendLimit>> {
+    let row = sqlx::query_as::<_, (String, i64, bool)>(
+        r#"SELECT user_id, (monthly_limit * 1000000)::BIGINT, limit_exceeded
+           FROM spend_limits WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(spend_limit))
+}
+
+/// Sets or changes a user's cap. The exceeded flag is left for the next
+/// batch run to re-evaluate.
+pub async fn set_spend_limit(pool: &PgPool, user_id: &str, monthly_limit: Amount) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO spend_limits (user_id, monthly_limit)
+           VALUES ($1, $2::NUMERIC / 1000000)
+           ON CONFLICT (user_id)
+           DO UPDATE SET monthly_limit=EXCLUDED.monthly_limit, updated_at=NOW()"#,
+    )
+    .bind(user_id)
+    .bind(monthly_limit.micros())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_spend_limit(pool: &PgPool, user_id: &str) -> Result<()> {
+    sqlx::query("DELETE FROM spend_limits WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn set_limit_exceeded(pool: &PgPool, user_id: &str, exceeded: bool) -> Result<()> {
+    sqlx::query(
+        "UPDATE spend_limits SET limit_exceeded = $2, updated_at = NOW() WHERE user_id = $1",
+    )
+    .bind(user_id)
+    .bind(exceeded)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+type CostAlertRow = (String, Option<i64>, Option<i64>, Option<NaiveDate>, Option<NaiveDate>);
+
+fn cost_alert(
+    (user_id, daily, monthly, daily_alerted, monthly_alerted): CostAlertRow,
+) -> CostAlert {
+    CostAlert {
+        user_id,
+        daily_threshold: daily.map(Amount::from_micros),
+        monthly_threshold: monthly.map(Amount::from_micros),
+        daily_alerted,
+        monthly_alerted,
+    }
+}
+
+pub async fn list_cost_alerts(pool: &PgPool) -> Result<Vec<CostAlert>> {
+    let rows = sqlx::query_as::<_, CostAlertRow>(
+        r#"SELECT user_id, (daily_threshold * 1000000)::BIGINT,
+                  (monthly_threshold * 1000000)::BIGINT, daily_alerted, monthly_alerted
+           FROM cost_alerts ORDER BY user_id"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(cost_alert).collect())
+}
+
+pub async fn get_cost_alert(pool: &PgPool, user_id: &str) -> Result<Option<CostAlert>> {
+    let row = sqlx::query_as::<_, CostAlertRow>(
+        r#"SELECT user_id, (daily_threshold * 1000000)::BIGINT,
+                  (monthly_threshold * 1000000)::BIGINT, daily_alerted, monthly_alerted
+           FROM cost_alerts WHERE user_id = $1"#,
+    )
+    .bind(user_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(cost_alert))
+}
+
+/// Sets a user's thresholds, keeping the record of alerts already sent;
+/// removes the alert when both are `None`.
+pub async fn set_cost_alert(
+    pool: &PgPool,
+    user_id: &str,
+    daily: Option<Amount>,
+    monthly: Option<Amount>,
+) -> Result<()> {
+    if daily.is_none() && monthly.is_none() {
+        sqlx::query("DELETE FROM cost_alerts WHERE user_id = $1")
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+        return Ok(());
+    }
+    sqlx::query(
+        r#"INSERT INTO cost_alerts (user_id, daily_threshold, monthly_threshold)
+           VALUES ($1, $2::NUMERIC / 1000000, $3::NUMERIC / 1000000)
+           ON CONFLICT (user_id)
+           DO UPDATE SET daily_threshold=EXCLUDED.daily_threshold,
+                         monthly_threshold=EXCLUDED.monthly_threshold, updated_at=NOW()"#,
+    )
+    .bind(user_id)
+    .bind(daily.map(Amount::micros))
+    .bind(monthly.map(Amount::micros))
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records that the user was alerted for `date`: a day, or a month's
+/// first day.
+pub async fn record_cost_alert(
+    pool: &PgPool,
+    user_id: &str,
+    period: AlertPeriod,
+    date: NaiveDate,
+) -> Result<()> {
+    let column = match period {
+        AlertPeriod::Daily => "daily_alerted",
+        AlertPeriod::Monthly => "monthly_alerted",
+    };
+    sqlx::query(&format!(
+        "UPDATE cost_alerts SET {column} = $2, updated_at = NOW() WHERE user_id = $1"
+    ))
+    .bind(user_id)
+    .bind(date)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn list_model_prices(pool: &PgPool) -> Result<Vec<ModelPrice>> {
+    let rows = sqlx::query_as::<_, (String, NaiveDate, i64, i64)>(
+        r#"SELECT model_id, effective_from,
+                  (input_per_1k * 1000000)::BIGINT, (output_per_1k * 1000000)::BIGINT
+           FROM model_prices ORDER BY model_id, effective_from"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(model_id, effective_from, input, output)| ModelPrice {
+            model_id,
+            model_name: None,
+            effective_from,
+            input_per_1k: Amount::from_micros(input),
+            output_per_1k: Amount::from_micros(output),
+        })
+        .collect())
+}
+
+/// Adds a price, or replaces the one the model already has from the same date.
+pub async fn set_model_price(pool: &PgPool, price: &ModelPrice) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO model_prices (model_id, effective_from, input_per_1k, output_per_1k)
+           VALUES ($1, $2, $3::NUMERIC / 1000000, $4::NUMERIC / 1000000)
+           ON CONFLICT (model_id, effective_from)
+           DO UPDATE SET input_per_1k=EXCLUDED.input_per_1k,
+                         output_per_1k=EXCLUDED.output_per_1k, updated_at=NOW()"#,
+    )
+    .bind(&price.model_id)
+    .bind(price.effective_from)
+    .bind(price.input_per_1k.micros())
+    .bind(price.output_per_1k.micros())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_model_price(
+    pool: &PgPool,
+    model_id: &str,
+    effective_from: NaiveDate,
+) -> Result<()> {
+    sqlx::query("DELETE FROM model_prices WHERE model_id = $1 AND effective_from = $2")
+        .bind(model_id)
+        .bind(effective_from)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn list_model_families(pool: &PgPool) -> Result<Vec<ModelFamily>> {
+    let rows = sqlx::query_as::<_, (String, String)>(
+        "SELECT prefix, family FROM model_families ORDER BY family, prefix",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(prefix, family)| ModelFamily { prefix, family })
+        .collect())
+}
+
+/// Adds a family rule, or moves an existing prefix to another family.
+pub async fn set_model_family(pool: &PgPool, rule: &ModelFamily) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO model_families (prefix, family) VALUES ($1, $2)
+           ON CONFLICT (prefix) DO UPDATE SET family=EXCLUDED.family, updated_at=NOW()"#,
+    )
+    .bind(&rule.prefix)
+    .bind(&rule.family)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn delete_model_family(pool: &PgPool, prefix: &str) -> Result<()> {
+    sqlx::query("DELETE FROM model_families WHERE prefix = $1")
+        .bind(prefix)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+fn label((target, target_id, label): (String, String, String)) -> Option<Label> {
+    Some(Label {
+        target: LabelTarget::parse(&target)?,
+        target_id,
+        label,
+    })
+}
+
+pub async fn list_labels(pool: &PgPool) -> Result<Vec<Label>> {
+    let rows = sqlx::query_as::<_, (String, String, String)>(
+        "SELECT target, target_id, label FROM labels ORDER BY label, target, target_id",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().filter_map(label).collect())
+}
+
+/// Attaches a label; attaching one twice is a no-op.
+pub async fn add_label(pool: &PgPool, label: &Label) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO labels (target, target_id, label) VALUES ($1, $2, $3)
+           ON CONFLICT DO NOTHING"#,
+    )
+    .bind(label.target.as_str())
+    .bind(&label.target_id)
+    .bind(&label.label)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn remove_label(pool: &PgPool, label: &Label) -> Result<()> {
+    sqlx::query("DELETE FROM labels WHERE target = $1 AND target_id = $2 AND label = $3")
+        .bind(label.target.as_str())
+        .bind(&label.target_id)
+        .bind(&label.label)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+type ReportSubscriptionRow = (i64, String, String, String, Option<NaiveDate>);
+
+fn report_subscription(
+    (id, recipient, report, schedule, last_sent): ReportSubscriptionRow,
+) -> Option<ReportSubscription> {
+    Some(ReportSubscription {
+        id,
+        recipient,
+        kind: ReportKind::parse(&report)?,
+        schedule: ReportSchedule::parse(&schedule)?,
+        last_sent,
+    })
+}
+
+pub async fn list_report_subscriptions(pool: &PgPool) -> Result<Vec<ReportSubscription>> {
+    let rows = sqlx::query_as::<_, ReportSubscriptionRow>(
+        r#"SELECT id, recipient, report, schedule, last_sent
+           FROM report_subscriptions ORDER BY recipient, id"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().filter_map(report_subscription).collect())
+}
+
+pub async fn add_report_subscription(
+    pool: &PgPool,
+    recipient: &str,
+    kind: ReportKind,
+    schedule: ReportSchedule,
+) -> Result<()> {
     sqlx::query(
-        r#"CREATE TABLE IF NOT EXISTS cost (
-            date DATE NOT NULL,
-            user_id TEXT NOT NULL,
-            model_id TEXT NOT NULL,
-            amount DOUBLE PRECISION NOT NULL,
-            currency TEXT NOT NULL DEFAULT 'USD',
-            created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
-            PRIMARY KEY (date, user_id, model_id)
-        )"#,
+        "INSERT INTO report_subscriptions (recipient, report, schedule) VALUES ($1, $2, $3)",
     )
+    .bind(recipient)
+    .bind(kind.as_str())
+    .bind(schedule.as_str())
     .execute(pool)
     .await?;
     Ok(())
 }
 
+pub async fn delete_report_subscription(pool: &PgPool, id: i64) -> Result<()> {
+    sqlx::query("DELETE FROM report_subscriptions WHERE id = $1")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Records that the subscription was sent for the period starting `start`.
+pub async fn record_report_sent(pool: &PgPool, id: i64, start: NaiveDate) -> Result<()> {
+    sqlx::query("UPDATE report_subscriptions SET last_sent = $2 WHERE id = $1")
+        .bind(id)
+        .bind(start)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Users whose statement for `month` (its first day) was already emailed.
+pub async fn list_statement_sends(pool: &PgPool, month: NaiveDate) -> Result<HashSet<String>> {
+    let rows =
+        sqlx::query_scalar::<_, String>("SELECT user_id FROM statement_sends WHERE month = $1")
+            .bind(month)
+            .fetch_all(pool)
+            .await?;
+    Ok(rows.into_iter().collect())
+}
+
+pub async fn record_statement_send(
+    pool: &PgPool,
+    user_id: &str,
+    month: NaiveDate,
+    email: &str,
+) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO statement_sends (user_id, month, email) VALUES ($1, $2, $3)
+           ON CONFLICT (user_id, month) DO NOTHING"#,
+    )
+    .bind(user_id)
+    .bind(month)
+    .bind(email)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Disables every active API key of a user in the gateway DB. Needs a
+/// connection with write access; returns how many keys were disabled.
+pub async fn disable_api_keys_for_user(pool: &PgPool, user_id: Uuid) -> Result<u64> {
+    let result = sqlx::query(
+        "UPDATE api_keys SET is_disabled = TRUE WHERE user_id = $1::uuid AND NOT is_disabled",
+    )
+    .bind(user_id.to_string().to_lowercase())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected())
+}
+
+/// Upserts CE rows and refreshes the daily aggregates of their dates.
 pub async fn upsert_cost_rows(pool: &PgPool, rows: &[CostRow]) -> Result<()> {
+    let Some((start, end)) = date_range(rows) else {
+        return Ok(());
+    };
+    let mut tx = pool.begin().await?;
+    upsert_rows(&mut tx, rows).await?;
+    refresh_daily_aggregates(&mut tx, start, end).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Upserts rows from a provider other than AWS, then refreshes the daily
+/// aggregates and monthly rollup of their dates.
+pub async fn import_cost_rows(pool: &PgPool, rows: &[CostRow]) -> Result<()> {
+    let Some((start, end)) = date_range(rows) else {
+        return Ok(());
+    };
+    let mut tx = pool.begin().await?;
+    upsert_rows(&mut tx, rows).await?;
+    refresh_daily_aggregates(&mut tx, start, end).await?;
+    refresh_monthly(&mut tx, Some((start, end))).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// `[first, last + 1 day)` of the rows' dates.
+fn date_range(rows: &[CostRow]) -> Option<(NaiveDate, NaiveDate)> {
+    let start = rows.iter().map(|r| r.date).min()?;
+    let end = rows.iter().map(|r| r.date).max()?;
+    Some((start, end + chrono::Duration::days(1)))
+}
+
+async fn upsert_rows(conn: &mut sqlx::PgConnection, rows: &[CostRow]) -> Result<()> {
     for row in rows {
         sqlx::query(
-            r#"INSERT INTO cost (date, user_id, model_id, amount, currency)
-               VALUES ($1, $2, $3, $4, $5)
-               ON CONFLICT (date, user_id, model_id)
+            r#"INSERT INTO cost (date, user_id, model_id, amount, currency, metric, source, tenant)
+               VALUES ($1, $2, $3, $4::NUMERIC / 1000000, $5, $6, $7, $8)
+               ON CONFLICT (tenant, date, user_id, model_id, metric, adjustment, source)
                DO UPDATE SET amount=EXCLUDED.amount, currency=EXCLUDED.currency, updated_at=NOW()"#,
         )
-        .bind(&row.date)
+        .bind(row.date)
         .bind(&row.user_id)
         .bind(&row.model_id)
-        .bind(row.amount)
+        .bind(row.amount.micros())
         .bind(&row.currency)
+        .bind(row.metric.as_str())
+        .bind(&row.source)
+        .bind(&row.tenant)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Recomputes `cost_daily_user` and `cost_daily_model` for `[start, end)`
+/// from the cost table, per tenant and source.
+async fn refresh_daily_aggregates(
+    conn: &mut sqlx::PgConnection,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<()> {
+    for (table, column) in [("cost_daily_user", "user_id"), ("cost_daily_model", "model_id")] {
+        sqlx::query(&format!("DELETE FROM {table} WHERE date >= $1 AND date < $2"))
+            .bind(start)
+            .bind(end)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(&format!(
+            r#"INSERT INTO {table} (tenant, date, {column}, metric, source, amount, currency)
+               SELECT tenant, date, {column}, metric, source, SUM(amount), MIN(currency)
+               FROM cost WHERE date >= $1 AND date < $2
+               GROUP BY tenant, date, {column}, metric, source"#
+        ))
+        .bind(start)
+        .bind(end)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+type CostTableRow = (
+    NaiveDate,
+    String,
+    String,
+    i64,
+    String,
+    String,
+    String,
+    String,
+);
+
+fn cost_rows(rows: Vec<CostTableRow>) -> Result<Vec<CostRow>> {
+    rows.into_iter()
+        .map(
+            |(date, user_id, model_id, amount, currency, metric, source, tenant)| {
+                Ok(CostRow {
+                    date,
+                    user_id,
+                    model_id,
+                    amount: Amount::from_micros(amount),
+                    currency,
+                    metric: metric.parse()?,
+                    source,
+                    tenant,
+                })
+            },
+        )
+        .collect()
+}
+
+/// Every row of the cost table, oldest first.
+pub async fn list_cost_rows(pool: &PgPool) -> Result<Vec<CostRow>> {
+    let rows = sqlx::query_as::<_, CostTableRow>(
+        r#"SELECT date, user_id, model_id, (amount * 1000000)::BIGINT, currency, metric, source,
+                  tenant
+           FROM cost ORDER BY tenant, date, user_id, model_id, metric, source"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    cost_rows(rows)
+}
+
+/// `meta` keys of the [`DataFreshness`] timestamps.
+const META_BATCH_RUN: &str = "batch_run";
+const META_ROLLUP_REFRESH: &str = "rollup_refresh";
+const META_EDIT: &str = "edit";
+
+async fn touch_meta(conn: &mut sqlx::PgConnection, key: &str) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO meta (key, updated_at) VALUES ($1, NOW())
+           ON CONFLICT (key) DO UPDATE SET updated_at=EXCLUDED.updated_at"#,
+    )
+    .bind(key)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+fn data_freshness_rows(rows: Vec<(String, DateTime<Utc>)>) -> DataFreshness {
+    let mut freshness = DataFreshness::default();
+    for (key, updated_at) in rows {
+        match key.as_str() {
+            META_BATCH_RUN => freshness.batch_run = Some(updated_at),
+            META_ROLLUP_REFRESH => freshness.rollup_refresh = Some(updated_at),
+            META_EDIT => freshness.edit = Some(updated_at),
+            _ => {}
+        }
+    }
+    freshness
+}
+
+/// Records that a batch run stored its rows just now.
+pub async fn record_batch_run(pool: &PgPool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    touch_meta(&mut conn, META_BATCH_RUN).await
+}
+
+/// Records that cost data or settings were changed outside a batch run just
+/// now.
+pub async fn record_edit(pool: &PgPool) -> Result<()> {
+    let mut conn = pool.acquire().await?;
+    touch_meta(&mut conn, META_EDIT).await
+}
+
+pub async fn data_freshness(pool: &PgPool) -> Result<DataFreshness> {
+    let rows = sqlx::query_as::<_, (String, DateTime<Utc>)>("SELECT key, updated_at FROM meta")
+        .fetch_all(pool)
+        .await?;
+    Ok(data_freshness_rows(rows))
+}
+
+type BatchRequestRow = (
+    i64,
+    String,
+    DateTime<Utc>,
+    Option<DateTime<Utc>>,
+    Option<DateTime<Utc>>,
+    Option<String>,
+);
+
+fn batch_request(
+    (id, requested_by, requested_at, started_at, finished_at, error): BatchRequestRow,
+) -> BatchRequest {
+    BatchRequest {
+        id,
+        requested_by,
+        requested_at,
+        started_at,
+        finished_at,
+        error,
+    }
+}
+
+/// Last day of `source` fully ingested for `tenant`, if any run stored it
+/// yet.
+pub async fn ingest_watermark(
+    pool: &PgPool,
+    tenant: &str,
+    source: &str,
+) -> Result<Option<NaiveDate>> {
+    let through = sqlx::query_scalar::<_, NaiveDate>(
+        "SELECT through FROM ingest_watermarks WHERE tenant = $1 AND source = $2",
+    )
+    .bind(tenant)
+    .bind(source)
+    .fetch_optional(pool)
+    .await?;
+    Ok(through)
+}
+
+/// Moves the watermark of `source` for `tenant` up to `through`; never
+/// moves it back.
+pub async fn advance_ingest_watermark(
+    pool: &PgPool,
+    tenant: &str,
+    source: &str,
+    through: NaiveDate,
+) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO ingest_watermarks (tenant, source, through, updated_at)
+           VALUES ($1, $2, $3, NOW())
+           ON CONFLICT (tenant, source) DO UPDATE
+           SET through = GREATEST(ingest_watermarks.through, EXCLUDED.through),
+               updated_at = EXCLUDED.updated_at"#,
+    )
+    .bind(tenant)
+    .bind(source)
+    .bind(through)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Queues a batch run of `tenant` for its daemon.
+pub async fn request_batch_run(pool: &PgPool, tenant: &str, requested_by: &str) -> Result<()> {
+    sqlx::query("INSERT INTO batch_requests (tenant, requested_by) VALUES ($1, $2)")
+        .bind(tenant)
+        .bind(requested_by)
         .execute(pool)
         .await?;
+    Ok(())
+}
+
+/// The `limit` latest requested runs of `tenant`, newest first.
+pub async fn list_batch_requests(
+    pool: &PgPool,
+    tenant: &str,
+    limit: i64,
+) -> Result<Vec<BatchRequest>> {
+    let rows = sqlx::query_as::<_, BatchRequestRow>(
+        r#"SELECT id, requested_by, requested_at, started_at, finished_at, error
+           FROM batch_requests WHERE tenant = $1 ORDER BY id DESC LIMIT $2"#,
+    )
+    .bind(tenant)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(batch_request).collect())
+}
+
+/// Marks every pending request of `tenant` as started; returns how many
+/// there were.
+pub async fn claim_batch_requests(pool: &PgPool, tenant: &str) -> Result<u64> {
+    let claimed = sqlx::query(
+        "UPDATE batch_requests SET started_at = NOW() WHERE tenant = $1 AND started_at IS NULL",
+    )
+    .bind(tenant)
+    .execute(pool)
+    .await?
+    .rows_affected();
+    Ok(claimed)
+}
+
+/// Marks every started request of `tenant` as finished, failed with `error`
+/// if given.
+pub async fn finish_batch_requests(pool: &PgPool, tenant: &str, error: Option<&str>) -> Result<()> {
+    sqlx::query(
+        r#"UPDATE batch_requests SET finished_at = NOW(), error = $2
+           WHERE tenant = $1 AND started_at IS NOT NULL AND finished_at IS NULL"#,
+    )
+    .bind(tenant)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Row counts and date spans of the cost table and the aggregates kept
+/// from it.
+pub async fn table_stats(pool: &PgPool) -> Result<Vec<TableStats>> {
+    let rows = sqlx::query_as::<_, TableStatsRow>(
+        r#"SELECT 'cost', COUNT(*), MIN(date)::text, MAX(date)::text FROM cost
+           UNION ALL
+           SELECT 'cost_daily_user', COUNT(*), MIN(date)::text, MAX(date)::text
+           FROM cost_daily_user
+           UNION ALL
+           SELECT 'cost_daily_model', COUNT(*), MIN(date)::text, MAX(date)::text
+           FROM cost_daily_model
+           UNION ALL
+           SELECT 'cost_monthly', COUNT(*), MIN(month)::text, MAX(month)::text
+           FROM cost_monthly"#,
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(table_stats_rows(rows))
+}
+
+/// Table, row count, oldest and newest date.
+type TableStatsRow = (String, i64, Option<String>, Option<String>);
+
+fn table_stats_rows(rows: Vec<TableStatsRow>) -> Vec<TableStats> {
+    rows.into_iter()
+        .map(|(table, rows, oldest, newest)| TableStats {
+            table,
+            rows,
+            oldest,
+            newest,
+        })
+        .collect()
+}
+
+/// Earliest day in `[start, end)` with a row of `tenant` last written less
+/// than `settlement_hours` after the day ended (UTC).
+pub async fn first_unsettled_date(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    settlement_hours: i64,
+) -> Result<Option<NaiveDate>> {
+    let date = sqlx::query_scalar::<_, Option<NaiveDate>>(
+        r#"SELECT MIN(date) FROM cost
+           WHERE date >= $1 AND date < $2 AND source = 'aws' AND tenant = $4
+             AND updated_at < ((date + 1)::TIMESTAMP AT TIME ZONE 'UTC')
+                 + make_interval(hours => $3::INT)"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(settlement_hours)
+    .bind(tenant)
+    .fetch_one(pool)
+    .await?;
+    Ok(date)
+}
+
+/// Rows of `tenant` fetched from CE dated in `[start, end)`, credits
+/// included, oldest first. Manual adjustments and imported rows are left
+/// out.
+pub async fn list_cost_rows_between(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<CostRow>> {
+    let rows = sqlx::query_as::<_, CostTableRow>(
+        r#"SELECT date, user_id, model_id, (amount * 1000000)::BIGINT, currency, metric, source,
+                  tenant
+           FROM cost WHERE date >= $1 AND date < $2 AND source IN ($3, $4) AND tenant = $5
+           ORDER BY date, user_id, model_id, metric"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(AWS_SOURCE)
+    .bind(CREDIT_SOURCE)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    cost_rows(rows)
+}
+
+/// Manual adjustments of `tenant` dated in `[start, end)`, oldest first.
+pub async fn list_adjustments(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<Vec<CostAdjustment>> {
+    let rows = sqlx::query_as::<_, (NaiveDate, String, String, String, i64, String, String)>(
+        r#"SELECT date, user_id, model_id, metric, (amount * 1000000)::BIGINT, currency, note
+           FROM cost WHERE date >= $1 AND date < $2 AND adjustment AND tenant = $3
+           ORDER BY date, user_id, model_id, metric"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    rows.into_iter()
+        .map(|(date, user_id, model_id, metric, amount, currency, note)| {
+            Ok(CostAdjustment {
+                date,
+                user_id,
+                user_email: None,
+                model_id,
+                model_name: None,
+                metric: metric.parse()?,
+                amount: Amount::from_micros(amount),
+                currency,
+                note,
+            })
+        })
+        .collect()
+}
+
+/// Inserts an adjustment of `tenant`, or replaces the one with the same
+/// date, user, model and metric.
+pub async fn set_adjustment(
+    pool: &PgPool,
+    tenant: &str,
+    adjustment: &CostAdjustment,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"INSERT INTO cost (date, user_id, model_id, amount, currency, metric, adjustment, note, source, tenant)
+           VALUES ($1, $2, $3, $4::NUMERIC / 1000000, $5, $6, TRUE, $7, $8, $9)
+           ON CONFLICT (tenant, date, user_id, model_id, metric, adjustment, source)
+           DO UPDATE SET amount=EXCLUDED.amount, currency=EXCLUDED.currency,
+                         note=EXCLUDED.note, updated_at=NOW()"#,
+    )
+    .bind(adjustment.date)
+    .bind(&adjustment.user_id)
+    .bind(&adjustment.model_id)
+    .bind(adjustment.amount.micros())
+    .bind(&adjustment.currency)
+    .bind(adjustment.metric.as_str())
+    .bind(&adjustment.note)
+    .bind(MANUAL_SOURCE)
+    .bind(tenant)
+    .execute(&mut *tx)
+    .await?;
+    let next_day = adjustment.date + chrono::Duration::days(1);
+    refresh_daily_aggregates(&mut tx, adjustment.date, next_day).await?;
+    refresh_monthly(&mut tx, Some((adjustment.date, next_day))).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn delete_adjustment(
+    pool: &PgPool,
+    tenant: &str,
+    date: NaiveDate,
+    user_id: &str,
+    model_id: &str,
+    metric: Metric,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"DELETE FROM cost
+           WHERE date = $1 AND user_id = $2 AND model_id = $3 AND metric = $4 AND adjustment
+             AND tenant = $5"#,
+    )
+    .bind(date)
+    .bind(user_id)
+    .bind(model_id)
+    .bind(metric.as_str())
+    .bind(tenant)
+    .execute(&mut *tx)
+    .await?;
+    let next_day = date + chrono::Duration::days(1);
+    refresh_daily_aggregates(&mut tx, date, next_day).await?;
+    refresh_monthly(&mut tx, Some((date, next_day))).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// The whole months inside `[start, end)`, as `[first, last)`; an empty
+/// range at `end` when there are none.
+pub(crate) fn whole_months(start: NaiveDate, end: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let first = match start.day() {
+        1 => start,
+        _ => month_start(start) + Months::new(1),
+    };
+    let last = month_start(end);
+    if first < last {
+        (first, last)
+    } else {
+        (end, end)
     }
+}
+
+/// The months overlapping `[start, end)`, as `[first, last)`.
+pub(crate) fn covering_months(start: NaiveDate, end: NaiveDate) -> (NaiveDate, NaiveDate) {
+    let last = end.pred_opt().map_or(end, |d| month_start(d) + Months::new(1));
+    (month_start(start), last)
+}
+
+fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).expect("every month has a first day")
+}
+
+/// Monthly totals of `tenant` in `[start, end)`, narrowed by `filters`
+/// (column and value pairs) and `source`. Whole months come from
+/// `cost_monthly`, the days around them from the cost table.
+async fn monthly_cost(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    filters: &[(&str, &str)],
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostRecord>> {
+    let (first, last) = whole_months(start, end);
+    let filter: String = filters
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| format!(" AND {column} = ${}", i + 8))
+        .chain([" AND ($6::TEXT IS NULL OR source = $6) AND tenant = $7".to_string()])
+        .collect();
+    let sql = format!(
+        r#"SELECT to_char(month, 'YYYY-MM-DD'), (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM (
+               SELECT month, amount, currency FROM cost_monthly
+               WHERE month >= $4 AND month < $5 AND metric = $3{filter}
+               UNION ALL
+               SELECT DATE_TRUNC('month', date)::DATE, amount, currency FROM cost
+               WHERE ((date >= $1 AND date < $4) OR (date >= $5 AND date < $2))
+                 AND metric = $3{filter}
+           ) AS months
+           GROUP BY month ORDER BY month"#
+    );
+    let mut query = sqlx::query_as::<_, (String, i64, String)>(&sql)
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str())
+        .bind(first)
+        .bind(last)
+        .bind(source)
+        .bind(tenant);
+    for (_, value) in filters {
+        query = query.bind(*value);
+    }
+    let rows = query.fetch_all(pool).await?;
+    Ok(rows
+        .into_iter()
+        .map(|(date, amount, currency)| CostRecord {
+            date,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect())
+}
+
+/// Rebuilds `cost_monthly` for the months overlapping `[start, end)`, or
+/// for every month when `range` is `None`.
+pub async fn refresh_monthly_rollup(
+    pool: &PgPool,
+    range: Option<(NaiveDate, NaiveDate)>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    refresh_monthly(&mut tx, range).await?;
+    touch_meta(&mut tx, META_ROLLUP_REFRESH).await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+async fn refresh_monthly(
+    conn: &mut sqlx::PgConnection,
+    range: Option<(NaiveDate, NaiveDate)>,
+) -> Result<()> {
+    let (first, last) = match range {
+        Some((start, end)) => {
+            let (first, last) = covering_months(start, end);
+            (Some(first), Some(last))
+        }
+        None => (None, None),
+    };
+    sqlx::query(
+        r#"DELETE FROM cost_monthly
+           WHERE ($1::DATE IS NULL OR month >= $1) AND ($2::DATE IS NULL OR month < $2)"#,
+    )
+    .bind(first)
+    .bind(last)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(
+        r#"INSERT INTO cost_monthly (tenant, month, user_id, model_id, metric, source, amount, currency)
+           SELECT tenant, DATE_TRUNC('month', date)::DATE, user_id, model_id, metric, source, SUM(amount), MIN(currency)
+           FROM cost
+           WHERE ($1::DATE IS NULL OR date >= $1) AND ($2::DATE IS NULL OR date < $2)
+           GROUP BY tenant, DATE_TRUNC('month', date), user_id, model_id, metric, source"#,
+    )
+    .bind(first)
+    .bind(last)
+    .execute(&mut *conn)
+    .await?;
     Ok(())
 }
 
-pub async fn get_daily_cost(pool: &PgPool, start: NaiveDate, end: NaiveDate) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT date::text, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2
+/// Deletes cost rows, adjustments, aggregates and statement sends dated
+/// before `cutoff`, then vacuums the tables. Returns the number of cost rows
+/// deleted.
+pub async fn prune_before(pool: &PgPool, cutoff: NaiveDate) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+    let deleted = sqlx::query("DELETE FROM cost WHERE date < $1")
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await?
+        .rows_affected();
+    for sql in [
+        "DELETE FROM cost_daily_user WHERE date < $1",
+        "DELETE FROM cost_daily_model WHERE date < $1",
+        "DELETE FROM cost_monthly WHERE month < $1",
+        "DELETE FROM statement_sends WHERE month < $1",
+    ] {
+        sqlx::query(sql).bind(cutoff).execute(&mut *tx).await?;
+    }
+    // A cutoff inside a month leaves that month's rollup partly pruned.
+    refresh_monthly(&mut tx, Some((cutoff, cutoff + chrono::Duration::days(1)))).await?;
+    tx.commit().await?;
+
+    // VACUUM cannot run inside a transaction.
+    sqlx::query(
+        "VACUUM (ANALYZE) cost, cost_daily_user, cost_daily_model, cost_monthly, statement_sends",
+    )
+    .execute(pool)
+    .await?;
+    Ok(deleted)
+}
+
+/// Sources with cost of `tenant` in the monthly rollup, alphabetically.
+pub async fn list_sources(pool: &PgPool, tenant: &str) -> Result<Vec<String>> {
+    let sources = sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT source FROM cost_monthly WHERE tenant = $1 ORDER BY source",
+    )
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    Ok(sources)
+}
+
+pub async fn get_daily_cost(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostRecord>> {
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT date::text, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
            GROUP BY date ORDER BY date"#,
     )
     .bind(start)
     .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
 }
 
-pub async fn get_monthly_cost(pool: &PgPool, start: NaiveDate, end: NaiveDate) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT to_char(DATE_TRUNC('month', date), 'YYYY-MM-DD'), SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2
-           GROUP BY DATE_TRUNC('month', date) ORDER BY DATE_TRUNC('month', date)"#,
+pub async fn get_monthly_cost(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostRecord>> {
+    monthly_cost(pool, tenant, start, end, &[], metric, source).await
+}
+
+/// One record per ISO week, dated by its Monday.
+pub async fn get_weekly_cost(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostRecord>> {
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT to_char(DATE_TRUNC('week', date), 'YYYY-MM-DD'), (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
+           GROUP BY DATE_TRUNC('week', date) ORDER BY DATE_TRUNC('week', date)"#,
     )
     .bind(start)
     .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
 }
 
-pub async fn get_cost_by_user(pool: &PgPool, start: NaiveDate, end: NaiveDate) -> Result<Vec<CostByUser>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT user_id, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2
+pub async fn get_cost_by_user(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostByUser>> {
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT user_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost_daily_user WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
            GROUP BY user_id ORDER BY SUM(amount) DESC"#,
     )
     .bind(start)
     .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
@@ -366,7 +1478,7 @@ pub async fn get_cost_by_user(pool: &PgPool, start: NaiveDate, end: NaiveDate) -
         .map(|(user_id, amount, currency)| CostByUser {
             user_id,
             user_email: None,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -374,16 +1486,23 @@ pub async fn get_cost_by_user(pool: &PgPool, start: NaiveDate, end: NaiveDate) -
 
 pub async fn get_cost_by_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostByModel>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT model_id, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT model_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost_daily_model WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
            GROUP BY model_id ORDER BY SUM(amount) DESC"#,
     )
     .bind(start)
     .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
@@ -391,26 +1510,129 @@ pub async fn get_cost_by_model(
         .map(|(model_id, amount, currency)| CostByModel {
             model_id,
             model_name: None,
-            amount,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect())
+}
+
+pub async fn get_daily_cost_by_model(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<ModelCostRow>> {
+    let rows = sqlx::query_as::<_, (NaiveDate, String, i64, String)>(
+        r#"SELECT date, model_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
+           GROUP BY date, model_id ORDER BY date, SUM(amount) DESC"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(date, model_id, amount, currency)| ModelCostRow {
+            date,
+            model_id,
+            model_name: None,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect())
+}
+
+pub async fn get_daily_cost_by_user(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<UserCostRow>> {
+    let rows = sqlx::query_as::<_, (NaiveDate, String, i64, String)>(
+        r#"SELECT date, user_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
+           GROUP BY date, user_id ORDER BY date, SUM(amount) DESC"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(date, user_id, amount, currency)| UserCostRow {
+            date,
+            user_id,
+            user_email: None,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
 }
 
+/// `(user_id, model_id, amount, currency)` for every pair with cost.
+pub async fn get_cost_by_user_and_model(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<(String, String, Amount, String)>> {
+    let rows = sqlx::query_as::<_, (String, String, i64, String)>(
+        r#"SELECT user_id, model_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND metric = $3
+             AND ($4::TEXT IS NULL OR source = $4) AND tenant = $5
+           GROUP BY user_id, model_id"#,
+    )
+    .bind(start)
+    .bind(end)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|(user_id, model_id, amount, currency)| {
+            (user_id, model_id, Amount::from_micros(amount), currency)
+        })
+        .collect())
+}
+
 pub async fn get_cost_by_model_for_user(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     user_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostByModel>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT model_id, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT model_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3 AND metric = $4
+             AND ($5::TEXT IS NULL OR source = $5) AND tenant = $6
            GROUP BY model_id ORDER BY SUM(amount) DESC"#,
     )
     .bind(start)
     .bind(end)
     .bind(user_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
@@ -418,7 +1640,7 @@ pub async fn get_cost_by_model_for_user(
         .map(|(model_id, amount, currency)| CostByModel {
             model_id,
             model_name: None,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -426,18 +1648,25 @@ pub async fn get_cost_by_model_for_user(
 
 pub async fn get_cost_by_user_for_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     model_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostByUser>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT user_id, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND model_id = $3
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT user_id, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND model_id = $3 AND metric = $4
+             AND ($5::TEXT IS NULL OR source = $5) AND tenant = $6
            GROUP BY user_id ORDER BY SUM(amount) DESC"#,
     )
     .bind(start)
     .bind(end)
     .bind(model_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
@@ -445,7 +1674,7 @@ pub async fn get_cost_by_user_for_model(
         .map(|(user_id, amount, currency)| CostByUser {
             user_id,
             user_email: None,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -453,25 +1682,32 @@ pub async fn get_cost_by_user_for_model(
 
 pub async fn get_daily_cost_for_user(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     user_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT date::text, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT date::text, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost_daily_user WHERE date >= $1 AND date < $2 AND user_id = $3 AND metric = $4
+             AND ($5::TEXT IS NULL OR source = $5) AND tenant = $6
            GROUP BY date ORDER BY date"#,
     )
     .bind(start)
     .bind(end)
     .bind(user_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -479,25 +1715,44 @@ pub async fn get_daily_cost_for_user(
 
 pub async fn get_monthly_cost_for_user(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     user_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT to_char(DATE_TRUNC('month', date), 'YYYY-MM-DD'), SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3
-           GROUP BY DATE_TRUNC('month', date) ORDER BY DATE_TRUNC('month', date)"#,
+    monthly_cost(pool, tenant, start, end, &[("user_id", user_id)], metric, source).await
+}
+
+pub async fn get_weekly_cost_for_user(
+    pool: &PgPool,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    user_id: &str,
+    metric: Metric,
+    source: Option<&str>,
+) -> Result<Vec<CostRecord>> {
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT to_char(DATE_TRUNC('week', date), 'YYYY-MM-DD'), (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost_daily_user WHERE date >= $1 AND date < $2 AND user_id = $3 AND metric = $4
+             AND ($5::TEXT IS NULL OR source = $5) AND tenant = $6
+           GROUP BY DATE_TRUNC('week', date) ORDER BY DATE_TRUNC('week', date)"#,
     )
     .bind(start)
     .bind(end)
     .bind(user_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -505,25 +1760,32 @@ pub async fn get_monthly_cost_for_user(
 
 pub async fn get_daily_cost_for_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     model_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT date::text, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND model_id = $3
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT date::text, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost_daily_model WHERE date >= $1 AND date < $2 AND model_id = $3 AND metric = $4
+             AND ($5::TEXT IS NULL OR source = $5) AND tenant = $6
            GROUP BY date ORDER BY date"#,
     )
     .bind(start)
     .bind(end)
     .bind(model_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
@@ -531,84 +1793,65 @@ pub async fn get_daily_cost_for_model(
 
 pub async fn get_monthly_cost_for_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     model_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT to_char(DATE_TRUNC('month', date), 'YYYY-MM-DD'), SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND model_id = $3
-           GROUP BY DATE_TRUNC('month', date) ORDER BY DATE_TRUNC('month', date)"#,
-    )
-    .bind(start)
-    .bind(end)
-    .bind(model_id)
-    .fetch_all(pool)
-    .await?;
-    Ok(rows
-        .into_iter()
-        .map(|(date, amount, currency)| CostRecord {
-            date,
-            amount,
-            currency,
-        })
-        .collect())
+    monthly_cost(pool, tenant, start, end, &[("model_id", model_id)], metric, source).await
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_daily_cost_for_user_and_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     user_id: &str,
     model_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT date::text, SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3 AND model_id = $4
+    let rows = sqlx::query_as::<_, (String, i64, String)>(
+        r#"SELECT date::text, (SUM(amount) * 1000000)::BIGINT, MIN(currency)
+           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3 AND model_id = $4 AND metric = $5
+             AND ($6::TEXT IS NULL OR source = $6) AND tenant = $7
            GROUP BY date ORDER BY date"#,
     )
     .bind(start)
     .bind(end)
     .bind(user_id)
     .bind(model_id)
+    .bind(metric.as_str())
+    .bind(source)
+    .bind(tenant)
     .fetch_all(pool)
     .await?;
     Ok(rows
         .into_iter()
         .map(|(date, amount, currency)| CostRecord {
             date,
-            amount,
+            amount: Amount::from_micros(amount),
             currency,
         })
         .collect())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub async fn get_monthly_cost_for_user_and_model(
     pool: &PgPool,
+    tenant: &str,
     start: NaiveDate,
     end: NaiveDate,
     user_id: &str,
     model_id: &str,
+    metric: Metric,
+    source: Option<&str>,
 ) -> Result<Vec<CostRecord>> {
-    let rows = sqlx::query_as::<_, (String, f64, String)>(
-        r#"SELECT to_char(DATE_TRUNC('month', date), 'YYYY-MM-DD'), SUM(amount), MIN(currency)
-           FROM cost WHERE date >= $1 AND date < $2 AND user_id = $3 AND model_id = $4
-           GROUP BY DATE_TRUNC('month', date) ORDER BY DATE_TRUNC('month', date)"#,
-    )
-    .bind(start)
-    .bind(end)
-    .bind(user_id)
-    .bind(model_id)
-    .fetch_all(pool)
-    .await?;
-    Ok(rows
-        .into_iter()
-        .map(|(date, amount, currency)| CostRecord {
-            date,
-            amount,
-            currency,
-        })
-        .collect())
+    let filters = [("user_id", user_id), ("model_id", model_id)];
+    monthly_cost(pool, tenant, start, end, &filters, metric, source).await
 }
 
 pub async fn list_profiles_for_model(
@@ -0,0 +1,1238 @@
+//! SQLite cost database for small deployments and local development.
+//! Amounts are stored as integer micro-units and dates as `YYYY-MM-DD`
+//! text; queries mirror the Postgres ones in `lib.rs`.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    AlertPeriod, Amount, BatchRequest, CostAdjustment, CostAlert, CostByModel, CostByUser,
+    CostRecord, CostRow, DataFreshness, Label, Metric, ModelCostRow, ModelFamily, ModelPrice,
+    PoolStatus, ReportKind, ReportSchedule, ReportSubscription, SpendLimit, TableStats,
+    UserCostRow, AWS_SOURCE, CREDIT_SOURCE, MANUAL_SOURCE,
+};
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+use sqlx::SqlitePool;
+
+use crate::store::CostStore;
+use crate::{
+    batch_request, cost_alert, cost_rows, covering_months, data_freshness_rows, date_range, label,
+    report_subscription, spend_limit, table_stats_rows, whole_months, BatchRequestRow,
+    CostAlertRow, CostTableRow, ReportSubscriptionRow, TableStatsRow, META_BATCH_RUN, META_EDIT,
+    META_ROLLUP_REFRESH,
+};
+
+pub async fn init_pool(database_url: &str) -> Result<SqlitePool> {
+    let options = SqliteConnectOptions::from_str(database_url)?.create_if_missing(true);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect_with(options)
+        .await?;
+    Ok(pool)
+}
+
+fn records(rows: Vec<(String, i64, String)>) -> Vec<CostRecord> {
+    rows.into_iter()
+        .map(|(date, amount, currency)| CostRecord {
+            date,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect()
+}
+
+fn by_user(rows: Vec<(String, i64, String)>) -> Vec<CostByUser> {
+    rows.into_iter()
+        .map(|(user_id, amount, currency)| CostByUser {
+            user_id,
+            user_email: None,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect()
+}
+
+fn by_model(rows: Vec<(String, i64, String)>) -> Vec<CostByModel> {
+    rows.into_iter()
+        .map(|(model_id, amount, currency)| CostByModel {
+            model_id,
+            model_name: None,
+            amount: Amount::from_micros(amount),
+            currency,
+        })
+        .collect()
+}
+
+/// Buckets `date` by day, week (its Monday) or month (its first day).
+const DAY: &str = "date";
+const WEEK: &str = "date(date, 'weekday 0', '-6 days')";
+const MONTH: &str = "strftime('%Y-%m-01', date)";
+
+/// Total per `bucket` of `table` in `[start, end)`, narrowed by `filters` (column and
+/// value pairs).
+async fn bucketed(
+    pool: &SqlitePool,
+    table: &str,
+    bucket: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    filters: &[(&str, &str)],
+    metric: Metric,
+) -> Result<Vec<CostRecord>> {
+    let sql = format!(
+        "SELECT {bucket} AS bucket, SUM(amount), MIN(currency) FROM {table}
+         WHERE date >= ?1 AND date < ?2 AND metric = ?3{}
+         GROUP BY bucket ORDER BY bucket",
+        filter_clause(filters)
+    );
+    let mut query = sqlx::query_as::<_, (String, i64, String)>(&sql)
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str());
+    for (_, value) in filters {
+        query = query.bind(*value);
+    }
+    Ok(records(query.fetch_all(pool).await?))
+}
+
+/// Total per `column` of `table` in `[start, end)`, highest first, narrowed by `filters`.
+async fn grouped(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+    filters: &[(&str, &str)],
+    metric: Metric,
+) -> Result<Vec<(String, i64, String)>> {
+    let sql = format!(
+        "SELECT {column}, SUM(amount), MIN(currency) FROM {table}
+         WHERE date >= ?1 AND date < ?2 AND metric = ?3{}
+         GROUP BY {column} ORDER BY SUM(amount) DESC",
+        filter_clause(filters)
+    );
+    let mut query = sqlx::query_as::<_, (String, i64, String)>(&sql)
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str());
+    for (_, value) in filters {
+        query = query.bind(*value);
+    }
+    Ok(query.fetch_all(pool).await?)
+}
+
+/// Monthly totals in `[start, end)`, narrowed by `filters`. Whole months
+/// come from `cost_monthly`, the days around them from the cost table.
+async fn monthly_cost(
+    pool: &SqlitePool,
+    start: NaiveDate,
+    end: NaiveDate,
+    filters: &[(&str, &str)],
+    metric: Metric,
+) -> Result<Vec<CostRecord>> {
+    let (first, last) = whole_months(start, end);
+    let filter: String = filters
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| format!(" AND {column} = ?{}", i + 6))
+        .collect();
+    let sql = format!(
+        r#"SELECT month, SUM(amount), MIN(currency)
+           FROM (
+               SELECT month, amount, currency FROM cost_monthly
+               WHERE month >= ?4 AND month < ?5 AND metric = ?3{filter}
+               UNION ALL
+               SELECT {MONTH}, amount, currency FROM cost
+               WHERE ((date >= ?1 AND date < ?4) OR (date >= ?5 AND date < ?2))
+                 AND metric = ?3{filter}
+           )
+           GROUP BY month ORDER BY month"#
+    );
+    let mut query = sqlx::query_as::<_, (String, i64, String)>(&sql)
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str())
+        .bind(first)
+        .bind(last);
+    for (_, value) in filters {
+        query = query.bind(*value);
+    }
+    Ok(records(query.fetch_all(pool).await?))
+}
+
+async fn refresh_monthly(
+    conn: &mut sqlx::SqliteConnection,
+    range: Option<(NaiveDate, NaiveDate)>,
+) -> Result<()> {
+    let (first, last) = match range {
+        Some((start, end)) => {
+            let (first, last) = covering_months(start, end);
+            (Some(first), Some(last))
+        }
+        None => (None, None),
+    };
+    sqlx::query(
+        r#"DELETE FROM cost_monthly
+           WHERE (?1 IS NULL OR month >= ?1) AND (?2 IS NULL OR month < ?2)"#,
+    )
+    .bind(first)
+    .bind(last)
+    .execute(&mut *conn)
+    .await?;
+    sqlx::query(&format!(
+        r#"INSERT INTO cost_monthly (tenant, month, user_id, model_id, metric, source, amount, currency)
+           SELECT tenant, {MONTH}, user_id, model_id, metric, source, SUM(amount), MIN(currency)
+           FROM cost
+           WHERE (?1 IS NULL OR date >= ?1) AND (?2 IS NULL OR date < ?2)
+           GROUP BY tenant, {MONTH}, user_id, model_id, metric, source"#
+    ))
+    .bind(first)
+    .bind(last)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+async fn touch_meta(conn: &mut sqlx::SqliteConnection, key: &str) -> Result<()> {
+    sqlx::query(
+        r#"INSERT INTO meta (key, updated_at) VALUES (?1, CURRENT_TIMESTAMP)
+           ON CONFLICT (key) DO UPDATE SET updated_at=excluded.updated_at"#,
+    )
+    .bind(key)
+    .execute(&mut *conn)
+    .await?;
+    Ok(())
+}
+
+/// Recomputes the daily aggregates for `[start, end)` from the cost table.
+async fn refresh_daily_aggregates(
+    conn: &mut sqlx::SqliteConnection,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<()> {
+    for (table, column) in [
+        ("cost_daily_user", "user_id"),
+        ("cost_daily_model", "model_id"),
+    ] {
+        sqlx::query(&format!(
+            "DELETE FROM {table} WHERE date >= ?1 AND date < ?2"
+        ))
+        .bind(start)
+        .bind(end)
+        .execute(&mut *conn)
+        .await?;
+        sqlx::query(&format!(
+            r#"INSERT INTO {table} (tenant, date, {column}, metric, source, amount, currency)
+               SELECT tenant, date, {column}, metric, source, SUM(amount), MIN(currency)
+               FROM cost WHERE date >= ?1 AND date < ?2
+               GROUP BY tenant, date, {column}, metric, source"#
+        ))
+        .bind(start)
+        .bind(end)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn upsert_rows(conn: &mut sqlx::SqliteConnection, rows: &[CostRow]) -> Result<()> {
+    for row in rows {
+        sqlx::query(
+            r#"INSERT INTO cost (date, user_id, model_id, amount, currency, metric, source, tenant)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+               ON CONFLICT (tenant, date, user_id, model_id, metric, adjustment, source)
+               DO UPDATE SET amount=excluded.amount, currency=excluded.currency,
+                             updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(row.date)
+        .bind(&row.user_id)
+        .bind(&row.model_id)
+        .bind(row.amount.micros())
+        .bind(&row.currency)
+        .bind(row.metric.as_str())
+        .bind(&row.source)
+        .bind(&row.tenant)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// `filters` plus the `tenant` column, and the `source` column when
+/// narrowing to one source.
+fn scoped<'a>(
+    filters: &[(&'a str, &'a str)],
+    tenant: &'a str,
+    source: Option<&'a str>,
+) -> Vec<(&'a str, &'a str)> {
+    let mut filters = filters.to_vec();
+    filters.push(("tenant", tenant));
+    filters.extend(source.map(|source| ("source", source)));
+    filters
+}
+
+/// `AND column = ?n` for each filter, numbered after the three shared binds.
+fn filter_clause(filters: &[(&str, &str)]) -> String {
+    filters
+        .iter()
+        .enumerate()
+        .map(|(i, (column, _))| format!(" AND {column} = ?{}", i + 4))
+        .collect()
+}
+
+#[async_trait]
+impl CostStore for SqlitePool {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query_scalar::<_, i32>("SELECT 1")
+            .fetch_one(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        sqlx::migrate!("./migrations-sqlite").run(self).await?;
+        Ok(())
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        crate::pool_status(self)
+    }
+
+    async fn table_stats(&self) -> Result<Vec<TableStats>> {
+        let rows = sqlx::query_as::<_, TableStatsRow>(
+            r#"SELECT 'cost', COUNT(*), MIN(date), MAX(date) FROM cost
+               UNION ALL
+               SELECT 'cost_daily_user', COUNT(*), MIN(date), MAX(date) FROM cost_daily_user
+               UNION ALL
+               SELECT 'cost_daily_model', COUNT(*), MIN(date), MAX(date) FROM cost_daily_model
+               UNION ALL
+               SELECT 'cost_monthly', COUNT(*), MIN(month), MAX(month) FROM cost_monthly"#,
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(table_stats_rows(rows))
+    }
+
+    async fn get_role(&self, email: &str) -> Result<Option<String>> {
+        let role = sqlx::query_scalar::<_, String>("SELECT role FROM roles WHERE user_email = ?1")
+            .bind(email)
+            .fetch_optional(self)
+            .await?;
+        Ok(role)
+    }
+
+    async fn list_spend_limits(&self) -> Result<Vec<SpendLimit>> {
+        let rows = sqlx::query_as::<_, (String, i64, bool)>(
+            "SELECT user_id, monthly_limit, limit_exceeded FROM spend_limits ORDER BY user_id",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows.into_iter().map(spend_limit).collect())
+    }
+
+    async fn get_spend_limit(&self, user_id: &str) -> Result<Option<SpendLimit>> {
+        let row = sqlx::query_as::<_, (String, i64, bool)>(
+            "SELECT user_id, monthly_limit, limit_exceeded FROM spend_limits WHERE user_id = ?1",
+        )
+        .bind(user_id)
+        .fetch_optional(self)
+        .await?;
+        Ok(row.map(spend_limit))
+    }
+
+    async fn set_spend_limit(&self, user_id: &str, monthly_limit: Amount) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO spend_limits (user_id, monthly_limit) VALUES (?1, ?2)
+               ON CONFLICT (user_id)
+               DO UPDATE SET monthly_limit=excluded.monthly_limit, updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(user_id)
+        .bind(monthly_limit.micros())
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_spend_limit(&self, user_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM spend_limits WHERE user_id = ?1")
+            .bind(user_id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn set_limit_exceeded(&self, user_id: &str, exceeded: bool) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE spend_limits SET limit_exceeded = ?2, updated_at = CURRENT_TIMESTAMP
+               WHERE user_id = ?1"#,
+        )
+        .bind(user_id)
+        .bind(exceeded)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_cost_alerts(&self) -> Result<Vec<CostAlert>> {
+        let rows = sqlx::query_as::<_, CostAlertRow>(
+            r#"SELECT user_id, daily_threshold, monthly_threshold, daily_alerted, monthly_alerted
+               FROM cost_alerts ORDER BY user_id"#,
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows.into_iter().map(cost_alert).collect())
+    }
+
+    async fn get_cost_alert(&self, user_id: &str) -> Result<Option<CostAlert>> {
+        let row = sqlx::query_as::<_, CostAlertRow>(
+            r#"SELECT user_id, daily_threshold, monthly_threshold, daily_alerted, monthly_alerted
+               FROM cost_alerts WHERE user_id = ?1"#,
+        )
+        .bind(user_id)
+        .fetch_optional(self)
+        .await?;
+        Ok(row.map(cost_alert))
+    }
+
+    async fn set_cost_alert(
+        &self,
+        user_id: &str,
+        daily: Option<Amount>,
+        monthly: Option<Amount>,
+    ) -> Result<()> {
+        if daily.is_none() && monthly.is_none() {
+            sqlx::query("DELETE FROM cost_alerts WHERE user_id = ?1")
+                .bind(user_id)
+                .execute(self)
+                .await?;
+            return Ok(());
+        }
+        sqlx::query(
+            r#"INSERT INTO cost_alerts (user_id, daily_threshold, monthly_threshold)
+               VALUES (?1, ?2, ?3)
+               ON CONFLICT (user_id)
+               DO UPDATE SET daily_threshold=excluded.daily_threshold,
+                             monthly_threshold=excluded.monthly_threshold,
+                             updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(user_id)
+        .bind(daily.map(Amount::micros))
+        .bind(monthly.map(Amount::micros))
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn record_cost_alert(
+        &self,
+        user_id: &str,
+        period: AlertPeriod,
+        date: NaiveDate,
+    ) -> Result<()> {
+        let column = match period {
+            AlertPeriod::Daily => "daily_alerted",
+            AlertPeriod::Monthly => "monthly_alerted",
+        };
+        sqlx::query(&format!(
+            "UPDATE cost_alerts SET {column} = ?2, updated_at = CURRENT_TIMESTAMP WHERE user_id = ?1"
+        ))
+        .bind(user_id)
+        .bind(date)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn list_model_prices(&self) -> Result<Vec<ModelPrice>> {
+        let rows = sqlx::query_as::<_, (String, NaiveDate, i64, i64)>(
+            r#"SELECT model_id, effective_from, input_per_1k, output_per_1k
+               FROM model_prices ORDER BY model_id, effective_from"#,
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(model_id, effective_from, input, output)| ModelPrice {
+                model_id,
+                model_name: None,
+                effective_from,
+                input_per_1k: Amount::from_micros(input),
+                output_per_1k: Amount::from_micros(output),
+            })
+            .collect())
+    }
+
+    async fn set_model_price(&self, price: &ModelPrice) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO model_prices (model_id, effective_from, input_per_1k, output_per_1k)
+               VALUES (?1, ?2, ?3, ?4)
+               ON CONFLICT (model_id, effective_from)
+               DO UPDATE SET input_per_1k=excluded.input_per_1k,
+                             output_per_1k=excluded.output_per_1k, updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(&price.model_id)
+        .bind(price.effective_from)
+        .bind(price.input_per_1k.micros())
+        .bind(price.output_per_1k.micros())
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_model_price(&self, model_id: &str, effective_from: NaiveDate) -> Result<()> {
+        sqlx::query("DELETE FROM model_prices WHERE model_id = ?1 AND effective_from = ?2")
+            .bind(model_id)
+            .bind(effective_from)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_model_families(&self) -> Result<Vec<ModelFamily>> {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT prefix, family FROM model_families ORDER BY family, prefix",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(prefix, family)| ModelFamily { prefix, family })
+            .collect())
+    }
+
+    async fn set_model_family(&self, rule: &ModelFamily) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO model_families (prefix, family) VALUES (?1, ?2)
+               ON CONFLICT (prefix)
+               DO UPDATE SET family=excluded.family, updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(&rule.prefix)
+        .bind(&rule.family)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_model_family(&self, prefix: &str) -> Result<()> {
+        sqlx::query("DELETE FROM model_families WHERE prefix = ?1")
+            .bind(prefix)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>> {
+        let rows = sqlx::query_as::<_, (String, String, String)>(
+            "SELECT target, target_id, label FROM labels ORDER BY label, target, target_id",
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows.into_iter().filter_map(label).collect())
+    }
+
+    async fn add_label(&self, label: &Label) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO labels (target, target_id, label) VALUES (?1, ?2, ?3)
+               ON CONFLICT DO NOTHING"#,
+        )
+        .bind(label.target.as_str())
+        .bind(&label.target_id)
+        .bind(&label.label)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn remove_label(&self, label: &Label) -> Result<()> {
+        sqlx::query("DELETE FROM labels WHERE target = ?1 AND target_id = ?2 AND label = ?3")
+            .bind(label.target.as_str())
+            .bind(&label.target_id)
+            .bind(&label.label)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_report_subscriptions(&self) -> Result<Vec<ReportSubscription>> {
+        let rows = sqlx::query_as::<_, ReportSubscriptionRow>(
+            r#"SELECT id, recipient, report, schedule, last_sent
+               FROM report_subscriptions ORDER BY recipient, id"#,
+        )
+        .fetch_all(self)
+        .await?;
+        Ok(rows.into_iter().filter_map(report_subscription).collect())
+    }
+
+    async fn add_report_subscription(
+        &self,
+        recipient: &str,
+        kind: ReportKind,
+        schedule: ReportSchedule,
+    ) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO report_subscriptions (recipient, report, schedule) VALUES (?1, ?2, ?3)",
+        )
+        .bind(recipient)
+        .bind(kind.as_str())
+        .bind(schedule.as_str())
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn delete_report_subscription(&self, id: i64) -> Result<()> {
+        sqlx::query("DELETE FROM report_subscriptions WHERE id = ?1")
+            .bind(id)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn record_report_sent(&self, id: i64, start: NaiveDate) -> Result<()> {
+        sqlx::query("UPDATE report_subscriptions SET last_sent = ?2 WHERE id = ?1")
+            .bind(id)
+            .bind(start)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_statement_sends(&self, month: NaiveDate) -> Result<HashSet<String>> {
+        let rows =
+            sqlx::query_scalar::<_, String>("SELECT user_id FROM statement_sends WHERE month = ?1")
+                .bind(month)
+                .fetch_all(self)
+                .await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    async fn record_statement_send(
+        &self,
+        user_id: &str,
+        month: NaiveDate,
+        email: &str,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO statement_sends (user_id, month, email) VALUES (?1, ?2, ?3)
+               ON CONFLICT (user_id, month) DO NOTHING"#,
+        )
+        .bind(user_id)
+        .bind(month)
+        .bind(email)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn upsert_cost_rows(&self, rows: &[CostRow]) -> Result<()> {
+        let Some((start, end)) = date_range(rows) else {
+            return Ok(());
+        };
+        let mut tx = self.begin().await?;
+        upsert_rows(&mut tx, rows).await?;
+        refresh_daily_aggregates(&mut tx, start, end).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn import_cost_rows(&self, rows: &[CostRow]) -> Result<()> {
+        let Some((start, end)) = date_range(rows) else {
+            return Ok(());
+        };
+        let mut tx = self.begin().await?;
+        upsert_rows(&mut tx, rows).await?;
+        refresh_daily_aggregates(&mut tx, start, end).await?;
+        refresh_monthly(&mut tx, Some((start, end))).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn refresh_monthly_rollup(&self, range: Option<(NaiveDate, NaiveDate)>) -> Result<()> {
+        let mut tx = self.begin().await?;
+        refresh_monthly(&mut tx, range).await?;
+        touch_meta(&mut tx, META_ROLLUP_REFRESH).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn record_batch_run(&self) -> Result<()> {
+        let mut conn = self.acquire().await?;
+        touch_meta(&mut conn, META_BATCH_RUN).await
+    }
+
+    async fn record_edit(&self) -> Result<()> {
+        let mut conn = self.acquire().await?;
+        touch_meta(&mut conn, META_EDIT).await
+    }
+
+    async fn data_freshness(&self) -> Result<DataFreshness> {
+        let rows = sqlx::query_as::<_, (String, DateTime<Utc>)>("SELECT key, updated_at FROM meta")
+            .fetch_all(self)
+            .await?;
+        Ok(data_freshness_rows(rows))
+    }
+
+    async fn ingest_watermark(&self, tenant: &str, source: &str) -> Result<Option<NaiveDate>> {
+        let through = sqlx::query_scalar::<_, NaiveDate>(
+            "SELECT through FROM ingest_watermarks WHERE tenant = ?1 AND source = ?2",
+        )
+        .bind(tenant)
+        .bind(source)
+        .fetch_optional(self)
+        .await?;
+        Ok(through)
+    }
+
+    async fn advance_ingest_watermark(
+        &self,
+        tenant: &str,
+        source: &str,
+        through: NaiveDate,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"INSERT INTO ingest_watermarks (tenant, source, through, updated_at)
+               VALUES (?1, ?2, ?3, CURRENT_TIMESTAMP)
+               ON CONFLICT (tenant, source) DO UPDATE
+               SET through = MAX(through, excluded.through),
+                   updated_at = excluded.updated_at"#,
+        )
+        .bind(tenant)
+        .bind(source)
+        .bind(through)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn request_batch_run(&self, tenant: &str, requested_by: &str) -> Result<()> {
+        sqlx::query("INSERT INTO batch_requests (tenant, requested_by) VALUES (?1, ?2)")
+            .bind(tenant)
+            .bind(requested_by)
+            .execute(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn list_batch_requests(&self, tenant: &str, limit: i64) -> Result<Vec<BatchRequest>> {
+        let rows = sqlx::query_as::<_, BatchRequestRow>(
+            r#"SELECT id, requested_by, requested_at, started_at, finished_at, error
+               FROM batch_requests WHERE tenant = ?1 ORDER BY id DESC LIMIT ?2"#,
+        )
+        .bind(tenant)
+        .bind(limit)
+        .fetch_all(self)
+        .await?;
+        Ok(rows.into_iter().map(batch_request).collect())
+    }
+
+    async fn claim_batch_requests(&self, tenant: &str) -> Result<u64> {
+        let claimed = sqlx::query(
+            r#"UPDATE batch_requests SET started_at = CURRENT_TIMESTAMP
+               WHERE tenant = ?1 AND started_at IS NULL"#,
+        )
+        .bind(tenant)
+        .execute(self)
+        .await?
+        .rows_affected();
+        Ok(claimed)
+    }
+
+    async fn finish_batch_requests(&self, tenant: &str, error: Option<&str>) -> Result<()> {
+        sqlx::query(
+            r#"UPDATE batch_requests SET finished_at = CURRENT_TIMESTAMP, error = ?2
+               WHERE tenant = ?1 AND started_at IS NOT NULL AND finished_at IS NULL"#,
+        )
+        .bind(tenant)
+        .bind(error)
+        .execute(self)
+        .await?;
+        Ok(())
+    }
+
+    async fn prune_before(&self, cutoff: NaiveDate) -> Result<u64> {
+        let mut tx = self.begin().await?;
+        let deleted = sqlx::query("DELETE FROM cost WHERE date < ?1")
+            .bind(cutoff)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+        for sql in [
+            "DELETE FROM cost_daily_user WHERE date < ?1",
+            "DELETE FROM cost_daily_model WHERE date < ?1",
+            "DELETE FROM cost_monthly WHERE month < ?1",
+            "DELETE FROM statement_sends WHERE month < ?1",
+        ] {
+            sqlx::query(sql).bind(cutoff).execute(&mut *tx).await?;
+        }
+        refresh_monthly(&mut tx, Some((cutoff, cutoff + chrono::Duration::days(1)))).await?;
+        tx.commit().await?;
+
+        sqlx::query("VACUUM").execute(self).await?;
+        Ok(deleted)
+    }
+
+    async fn list_cost_rows(&self) -> Result<Vec<CostRow>> {
+        let rows = sqlx::query_as::<_, CostTableRow>(
+            r#"SELECT date, user_id, model_id, amount, currency, metric, source, tenant
+               FROM cost ORDER BY tenant, date, user_id, model_id, metric"#,
+        )
+        .fetch_all(self)
+        .await?;
+        cost_rows(rows)
+    }
+
+    async fn first_unsettled_date(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        settlement_hours: i64,
+    ) -> Result<Option<NaiveDate>> {
+        let date = sqlx::query_scalar::<_, Option<NaiveDate>>(
+            r#"SELECT MIN(date) FROM cost
+               WHERE date >= ?1 AND date < ?2 AND source = 'aws' AND tenant = ?4
+                 AND updated_at < datetime(date, '+1 day', printf('%+d hours', ?3))"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(settlement_hours)
+        .bind(tenant)
+        .fetch_one(self)
+        .await?;
+        Ok(date)
+    }
+
+    async fn list_cost_rows_between(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostRow>> {
+        let rows = sqlx::query_as::<_, CostTableRow>(
+            r#"SELECT date, user_id, model_id, amount, currency, metric, source, tenant
+               FROM cost WHERE date >= ?1 AND date < ?2 AND source IN (?3, ?4) AND tenant = ?5
+               ORDER BY date, user_id, model_id, metric"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(AWS_SOURCE)
+        .bind(CREDIT_SOURCE)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        cost_rows(rows)
+    }
+
+    async fn list_adjustments(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostAdjustment>> {
+        let rows = sqlx::query_as::<_, (NaiveDate, String, String, String, i64, String, String)>(
+            r#"SELECT date, user_id, model_id, metric, amount, currency, note
+               FROM cost WHERE date >= ?1 AND date < ?2 AND adjustment AND tenant = ?3
+               ORDER BY date, user_id, model_id, metric"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        rows.into_iter()
+            .map(
+                |(date, user_id, model_id, metric, amount, currency, note)| {
+                    Ok(CostAdjustment {
+                        date,
+                        user_id,
+                        user_email: None,
+                        model_id,
+                        model_name: None,
+                        metric: metric.parse()?,
+                        amount: Amount::from_micros(amount),
+                        currency,
+                        note,
+                    })
+                },
+            )
+            .collect()
+    }
+
+    async fn set_adjustment(&self, tenant: &str, adjustment: &CostAdjustment) -> Result<()> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r#"INSERT INTO cost (date, user_id, model_id, amount, currency, metric, adjustment, note, source, tenant)
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1, ?7, ?8, ?9)
+               ON CONFLICT (tenant, date, user_id, model_id, metric, adjustment, source)
+               DO UPDATE SET amount=excluded.amount, currency=excluded.currency,
+                             note=excluded.note, updated_at=CURRENT_TIMESTAMP"#,
+        )
+        .bind(adjustment.date)
+        .bind(&adjustment.user_id)
+        .bind(&adjustment.model_id)
+        .bind(adjustment.amount.micros())
+        .bind(&adjustment.currency)
+        .bind(adjustment.metric.as_str())
+        .bind(&adjustment.note)
+        .bind(MANUAL_SOURCE)
+        .bind(tenant)
+        .execute(&mut *tx)
+        .await?;
+        let next_day = adjustment.date + chrono::Duration::days(1);
+        refresh_daily_aggregates(&mut tx, adjustment.date, next_day).await?;
+        refresh_monthly(&mut tx, Some((adjustment.date, next_day))).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn delete_adjustment(
+        &self,
+        tenant: &str,
+        date: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+    ) -> Result<()> {
+        let mut tx = self.begin().await?;
+        sqlx::query(
+            r#"DELETE FROM cost
+               WHERE date = ?1 AND user_id = ?2 AND model_id = ?3 AND metric = ?4 AND adjustment
+                 AND tenant = ?5"#,
+        )
+        .bind(date)
+        .bind(user_id)
+        .bind(model_id)
+        .bind(metric.as_str())
+        .bind(tenant)
+        .execute(&mut *tx)
+        .await?;
+        let next_day = date + chrono::Duration::days(1);
+        refresh_daily_aggregates(&mut tx, date, next_day).await?;
+        refresh_monthly(&mut tx, Some((date, next_day))).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    async fn list_sources(&self, tenant: &str) -> Result<Vec<String>> {
+        let sources = sqlx::query_scalar::<_, String>(
+            "SELECT DISTINCT source FROM cost_monthly WHERE tenant = ?1 ORDER BY source",
+        )
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(sources)
+    }
+
+    async fn get_daily_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[], tenant, source);
+        bucketed(self, "cost", DAY, start, end, &filters, metric).await
+    }
+
+    async fn get_monthly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[], tenant, source);
+        monthly_cost(self, start, end, &filters, metric).await
+    }
+
+    async fn get_weekly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[], tenant, source);
+        bucketed(self, "cost", WEEK, start, end, &filters, metric).await
+    }
+
+    async fn get_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>> {
+        let filters = scoped(&[], tenant, source);
+        Ok(by_user(
+            grouped(
+                self,
+                "cost_daily_user",
+                "user_id",
+                start,
+                end,
+                &filters,
+                metric,
+            )
+            .await?,
+        ))
+    }
+
+    async fn get_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>> {
+        let filters = scoped(&[], tenant, source);
+        Ok(by_model(
+            grouped(
+                self,
+                "cost_daily_model",
+                "model_id",
+                start,
+                end,
+                &filters,
+                metric,
+            )
+            .await?,
+        ))
+    }
+
+    async fn get_daily_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<ModelCostRow>> {
+        let rows = sqlx::query_as::<_, (NaiveDate, String, i64, String)>(
+            r#"SELECT date, model_id, SUM(amount), MIN(currency)
+               FROM cost WHERE date >= ?1 AND date < ?2 AND metric = ?3
+                 AND (?4 IS NULL OR source = ?4) AND tenant = ?5
+               GROUP BY date, model_id ORDER BY date, SUM(amount) DESC"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str())
+        .bind(source)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(date, model_id, amount, currency)| ModelCostRow {
+                date,
+                model_id,
+                model_name: None,
+                amount: Amount::from_micros(amount),
+                currency,
+            })
+            .collect())
+    }
+
+    async fn get_daily_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<UserCostRow>> {
+        let rows = sqlx::query_as::<_, (NaiveDate, String, i64, String)>(
+            r#"SELECT date, user_id, SUM(amount), MIN(currency)
+               FROM cost WHERE date >= ?1 AND date < ?2 AND metric = ?3
+                 AND (?4 IS NULL OR source = ?4) AND tenant = ?5
+               GROUP BY date, user_id ORDER BY date, SUM(amount) DESC"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str())
+        .bind(source)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(date, user_id, amount, currency)| UserCostRow {
+                date,
+                user_id,
+                user_email: None,
+                amount: Amount::from_micros(amount),
+                currency,
+            })
+            .collect())
+    }
+
+    async fn get_cost_by_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<(String, String, Amount, String)>> {
+        let rows = sqlx::query_as::<_, (String, String, i64, String)>(
+            r#"SELECT user_id, model_id, SUM(amount), MIN(currency)
+               FROM cost WHERE date >= ?1 AND date < ?2 AND metric = ?3
+                 AND (?4 IS NULL OR source = ?4) AND tenant = ?5
+               GROUP BY user_id, model_id"#,
+        )
+        .bind(start)
+        .bind(end)
+        .bind(metric.as_str())
+        .bind(source)
+        .bind(tenant)
+        .fetch_all(self)
+        .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(user_id, model_id, amount, currency)| {
+                (user_id, model_id, Amount::from_micros(amount), currency)
+            })
+            .collect())
+    }
+
+    async fn get_cost_by_model_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>> {
+        let filters = scoped(&[("user_id", user_id)], tenant, source);
+        Ok(by_model(
+            grouped(self, "cost", "model_id", start, end, &filters, metric).await?,
+        ))
+    }
+
+    async fn get_cost_by_user_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>> {
+        let filters = scoped(&[("model_id", model_id)], tenant, source);
+        Ok(by_user(
+            grouped(self, "cost", "user_id", start, end, &filters, metric).await?,
+        ))
+    }
+
+    async fn get_daily_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[("user_id", user_id)], tenant, source);
+        bucketed(self, "cost_daily_user", DAY, start, end, &filters, metric).await
+    }
+
+    async fn get_monthly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[("user_id", user_id)], tenant, source);
+        monthly_cost(self, start, end, &filters, metric).await
+    }
+
+    async fn get_weekly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[("user_id", user_id)], tenant, source);
+        bucketed(self, "cost_daily_user", WEEK, start, end, &filters, metric).await
+    }
+
+    async fn get_daily_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[("model_id", model_id)], tenant, source);
+        bucketed(self, "cost_daily_model", DAY, start, end, &filters, metric).await
+    }
+
+    async fn get_monthly_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(&[("model_id", model_id)], tenant, source);
+        monthly_cost(self, start, end, &filters, metric).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_daily_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(
+            &[("user_id", user_id), ("model_id", model_id)],
+            tenant,
+            source,
+        );
+        bucketed(self, "cost", DAY, start, end, &filters, metric).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_monthly_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        let filters = scoped(
+            &[("user_id", user_id), ("model_id", model_id)],
+            tenant,
+            source,
+        );
+        monthly_cost(self, start, end, &filters, metric).await
+    }
+}
@@ -0,0 +1,787 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use common::{
+    AlertPeriod, Amount, BatchRequest, CostAdjustment, CostAlert, CostByModel, CostByUser,
+    CostRecord, CostRow, DataFreshness, Label, Metric, ModelCostRow, ModelFamily, ModelPrice,
+    PoolStatus, ReportKind, ReportSchedule, ReportSubscription, SpendLimit, TableStats,
+    UserCostRow,
+};
+use sqlx::PgPool;
+
+/// The cost database, whichever engine holds it. The gateway database is
+/// always Postgres and stays with the free functions. Cost rows belong to
+/// a tenant, the gateway their ids come from; the tables keyed by user or
+/// model id are shared, since gateway ids are UUIDs.
+#[async_trait]
+pub trait CostStore: Send + Sync {
+    /// Fails when the database is unreachable.
+    async fn ping(&self) -> Result<()>;
+    /// Applies pending migrations.
+    async fn migrate(&self) -> Result<()>;
+    fn pool_status(&self) -> PoolStatus;
+    /// Row counts and date spans of the stored cost tables.
+    async fn table_stats(&self) -> Result<Vec<TableStats>>;
+    async fn get_role(&self, email: &str) -> Result<Option<String>>;
+    async fn list_spend_limits(&self) -> Result<Vec<SpendLimit>>;
+    async fn get_spend_limit(&self, user_id: &str) -> Result<Option<SpendLimit>>;
+    async fn set_spend_limit(&self, user_id: &str, monthly_limit: Amount) -> Result<()>;
+    async fn delete_spend_limit(&self, user_id: &str) -> Result<()>;
+    async fn set_limit_exceeded(&self, user_id: &str, exceeded: bool) -> Result<()>;
+    async fn list_cost_alerts(&self) -> Result<Vec<CostAlert>>;
+    async fn get_cost_alert(&self, user_id: &str) -> Result<Option<CostAlert>>;
+    /// Removes the alert when both thresholds are `None`.
+    async fn set_cost_alert(
+        &self,
+        user_id: &str,
+        daily: Option<Amount>,
+        monthly: Option<Amount>,
+    ) -> Result<()>;
+    async fn record_cost_alert(
+        &self,
+        user_id: &str,
+        period: AlertPeriod,
+        date: NaiveDate,
+    ) -> Result<()>;
+    async fn list_model_prices(&self) -> Result<Vec<ModelPrice>>;
+    async fn set_model_price(&self, price: &ModelPrice) -> Result<()>;
+    async fn delete_model_price(&self, model_id: &str, effective_from: NaiveDate) -> Result<()>;
+    async fn list_model_families(&self) -> Result<Vec<ModelFamily>>;
+    async fn set_model_family(&self, rule: &ModelFamily) -> Result<()>;
+    async fn delete_model_family(&self, prefix: &str) -> Result<()>;
+    async fn list_labels(&self) -> Result<Vec<Label>>;
+    async fn add_label(&self, label: &Label) -> Result<()>;
+    async fn remove_label(&self, label: &Label) -> Result<()>;
+    async fn list_report_subscriptions(&self) -> Result<Vec<ReportSubscription>>;
+    async fn add_report_subscription(
+        &self,
+        recipient: &str,
+        kind: ReportKind,
+        schedule: ReportSchedule,
+    ) -> Result<()>;
+    async fn delete_report_subscription(&self, id: i64) -> Result<()>;
+    /// Records that the subscription was sent for the period starting `start`.
+    async fn record_report_sent(&self, id: i64, start: NaiveDate) -> Result<()>;
+    async fn list_statement_sends(&self, month: NaiveDate) -> Result<HashSet<String>>;
+    async fn record_statement_send(
+        &self,
+        user_id: &str,
+        month: NaiveDate,
+        email: &str,
+    ) -> Result<()>;
+    /// Also refreshes the daily aggregates of the rows' dates, but not the
+    /// monthly rollup; see [`CostStore::refresh_monthly_rollup`].
+    async fn upsert_cost_rows(&self, rows: &[CostRow]) -> Result<()>;
+    /// Upserts rows from another provider, refreshing the daily aggregates
+    /// and monthly rollup of their dates.
+    async fn import_cost_rows(&self, rows: &[CostRow]) -> Result<()>;
+    /// Rebuilds the monthly rollup for the months overlapping the range, or
+    /// for every month when `None`.
+    async fn refresh_monthly_rollup(&self, range: Option<(NaiveDate, NaiveDate)>) -> Result<()>;
+    /// Records that a batch run stored its rows just now.
+    async fn record_batch_run(&self) -> Result<()>;
+    /// Records that cost data or settings were changed outside a batch run
+    /// just now.
+    async fn record_edit(&self) -> Result<()>;
+    /// When the batch last ran and the monthly rollup was last rebuilt.
+    async fn data_freshness(&self) -> Result<DataFreshness>;
+    /// Last day of `source` fully ingested for `tenant`, if any run stored
+    /// it yet.
+    async fn ingest_watermark(&self, tenant: &str, source: &str) -> Result<Option<NaiveDate>>;
+    /// Moves the watermark of `source` for `tenant` up to `through`; never
+    /// moves it back.
+    async fn advance_ingest_watermark(
+        &self,
+        tenant: &str,
+        source: &str,
+        through: NaiveDate,
+    ) -> Result<()>;
+    /// Queues a batch run of `tenant` for its daemon.
+    async fn request_batch_run(&self, tenant: &str, requested_by: &str) -> Result<()>;
+    /// The `limit` latest requested runs of `tenant`, newest first.
+    async fn list_batch_requests(&self, tenant: &str, limit: i64) -> Result<Vec<BatchRequest>>;
+    /// Marks every pending request of `tenant` as started; returns how many
+    /// there were.
+    async fn claim_batch_requests(&self, tenant: &str) -> Result<u64>;
+    /// Marks every started request of `tenant` as finished, failed with
+    /// `error` if given.
+    async fn finish_batch_requests(&self, tenant: &str, error: Option<&str>) -> Result<()>;
+    /// Deletes cost data dated before `cutoff` and reclaims its space;
+    /// returns the number of cost rows deleted.
+    async fn prune_before(&self, cutoff: NaiveDate) -> Result<u64>;
+    async fn list_cost_rows(&self) -> Result<Vec<CostRow>>;
+    async fn first_unsettled_date(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        settlement_hours: i64,
+    ) -> Result<Option<NaiveDate>>;
+    async fn list_cost_rows_between(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostRow>>;
+    async fn list_adjustments(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostAdjustment>>;
+    async fn set_adjustment(&self, tenant: &str, adjustment: &CostAdjustment) -> Result<()>;
+    async fn delete_adjustment(
+        &self,
+        tenant: &str,
+        date: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+    ) -> Result<()>;
+    /// Sources with cost of `tenant` in the monthly rollup, for the source
+    /// filter.
+    async fn list_sources(&self, tenant: &str) -> Result<Vec<String>>;
+    /// The read functions below cover the cost of `tenant`, narrowed to
+    /// `source` when given.
+    async fn get_daily_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_monthly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_weekly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>>;
+    async fn get_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>>;
+    async fn get_daily_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<ModelCostRow>>;
+    /// One row per day and user, by date then highest cost first.
+    async fn get_daily_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<UserCostRow>>;
+    async fn get_cost_by_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<(String, String, Amount, String)>>;
+    async fn get_cost_by_model_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>>;
+    async fn get_cost_by_user_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>>;
+    async fn get_daily_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_monthly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_weekly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_daily_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_monthly_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    #[allow(clippy::too_many_arguments)]
+    async fn get_daily_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+    #[allow(clippy::too_many_arguments)]
+    async fn get_monthly_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>>;
+}
+
+#[async_trait]
+impl CostStore for PgPool {
+    async fn ping(&self) -> Result<()> {
+        sqlx::query_scalar::<_, i32>("SELECT 1")
+            .fetch_one(self)
+            .await?;
+        Ok(())
+    }
+
+    async fn migrate(&self) -> Result<()> {
+        crate::migrate(self).await
+    }
+
+    fn pool_status(&self) -> PoolStatus {
+        crate::pool_status(self)
+    }
+
+    async fn table_stats(&self) -> Result<Vec<TableStats>> {
+        crate::table_stats(self).await
+    }
+
+    async fn get_role(&self, email: &str) -> Result<Option<String>> {
+        crate::get_role(self, email).await
+    }
+
+    async fn list_spend_limits(&self) -> Result<Vec<SpendLimit>> {
+        crate::list_spend_limits(self).await
+    }
+
+    async fn get_spend_limit(&self, user_id: &str) -> Result<Option<SpendLimit>> {
+        crate::get_spend_limit(self, user_id).await
+    }
+
+    async fn set_spend_limit(&self, user_id: &str, monthly_limit: Amount) -> Result<()> {
+        crate::set_spend_limit(self, user_id, monthly_limit).await
+    }
+
+    async fn delete_spend_limit(&self, user_id: &str) -> Result<()> {
+        crate::delete_spend_limit(self, user_id).await
+    }
+
+    async fn set_limit_exceeded(&self, user_id: &str, exceeded: bool) -> Result<()> {
+        crate::set_limit_exceeded(self, user_id, exceeded).await
+    }
+
+    async fn list_cost_alerts(&self) -> Result<Vec<CostAlert>> {
+        crate::list_cost_alerts(self).await
+    }
+
+    async fn get_cost_alert(&self, user_id: &str) -> Result<Option<CostAlert>> {
+        crate::get_cost_alert(self, user_id).await
+    }
+
+    async fn set_cost_alert(
+        &self,
+        user_id: &str,
+        daily: Option<Amount>,
+        monthly: Option<Amount>,
+    ) -> Result<()> {
+        crate::set_cost_alert(self, user_id, daily, monthly).await
+    }
+
+    async fn record_cost_alert(
+        &self,
+        user_id: &str,
+        period: AlertPeriod,
+        date: NaiveDate,
+    ) -> Result<()> {
+        crate::record_cost_alert(self, user_id, period, date).await
+    }
+
+    async fn list_model_prices(&self) -> Result<Vec<ModelPrice>> {
+        crate::list_model_prices(self).await
+    }
+
+    async fn set_model_price(&self, price: &ModelPrice) -> Result<()> {
+        crate::set_model_price(self, price).await
+    }
+
+    async fn delete_model_price(&self, model_id: &str, effective_from: NaiveDate) -> Result<()> {
+        crate::delete_model_price(self, model_id, effective_from).await
+    }
+
+    async fn list_model_families(&self) -> Result<Vec<ModelFamily>> {
+        crate::list_model_families(self).await
+    }
+
+    async fn set_model_family(&self, rule: &ModelFamily) -> Result<()> {
+        crate::set_model_family(self, rule).await
+    }
+
+    async fn delete_model_family(&self, prefix: &str) -> Result<()> {
+        crate::delete_model_family(self, prefix).await
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>> {
+        crate::list_labels(self).await
+    }
+
+    async fn add_label(&self, label: &Label) -> Result<()> {
+        crate::add_label(self, label).await
+    }
+
+    async fn remove_label(&self, label: &Label) -> Result<()> {
+        crate::remove_label(self, label).await
+    }
+
+    async fn list_report_subscriptions(&self) -> Result<Vec<ReportSubscription>> {
+        crate::list_report_subscriptions(self).await
+    }
+
+    async fn add_report_subscription(
+        &self,
+        recipient: &str,
+        kind: ReportKind,
+        schedule: ReportSchedule,
+    ) -> Result<()> {
+        crate::add_report_subscription(self, recipient, kind, schedule).await
+    }
+
+    async fn delete_report_subscription(&self, id: i64) -> Result<()> {
+        crate::delete_report_subscription(self, id).await
+    }
+
+    async fn record_report_sent(&self, id: i64, start: NaiveDate) -> Result<()> {
+        crate::record_report_sent(self, id, start).await
+    }
+
+    async fn list_statement_sends(&self, month: NaiveDate) -> Result<HashSet<String>> {
+        crate::list_statement_sends(self, month).await
+    }
+
+    async fn record_statement_send(
+        &self,
+        user_id: &str,
+        month: NaiveDate,
+        email: &str,
+    ) -> Result<()> {
+        crate::record_statement_send(self, user_id, month, email).await
+    }
+
+    async fn upsert_cost_rows(&self, rows: &[CostRow]) -> Result<()> {
+        crate::upsert_cost_rows(self, rows).await
+    }
+
+    async fn import_cost_rows(&self, rows: &[CostRow]) -> Result<()> {
+        crate::import_cost_rows(self, rows).await
+    }
+
+    async fn refresh_monthly_rollup(&self, range: Option<(NaiveDate, NaiveDate)>) -> Result<()> {
+        crate::refresh_monthly_rollup(self, range).await
+    }
+
+    async fn record_batch_run(&self) -> Result<()> {
+        crate::record_batch_run(self).await
+    }
+
+    async fn record_edit(&self) -> Result<()> {
+        crate::record_edit(self).await
+    }
+
+    async fn data_freshness(&self) -> Result<DataFreshness> {
+        crate::data_freshness(self).await
+    }
+
+    async fn ingest_watermark(&self, tenant: &str, source: &str) -> Result<Option<NaiveDate>> {
+        crate::ingest_watermark(self, tenant, source).await
+    }
+
+    async fn advance_ingest_watermark(
+        &self,
+        tenant: &str,
+        source: &str,
+        through: NaiveDate,
+    ) -> Result<()> {
+        crate::advance_ingest_watermark(self, tenant, source, through).await
+    }
+
+    async fn request_batch_run(&self, tenant: &str, requested_by: &str) -> Result<()> {
+        crate::request_batch_run(self, tenant, requested_by).await
+    }
+
+    async fn list_batch_requests(&self, tenant: &str, limit: i64) -> Result<Vec<BatchRequest>> {
+        crate::list_batch_requests(self, tenant, limit).await
+    }
+
+    async fn claim_batch_requests(&self, tenant: &str) -> Result<u64> {
+        crate::claim_batch_requests(self, tenant).await
+    }
+
+    async fn finish_batch_requests(&self, tenant: &str, error: Option<&str>) -> Result<()> {
+        crate::finish_batch_requests(self, tenant, error).await
+    }
+
+    async fn prune_before(&self, cutoff: NaiveDate) -> Result<u64> {
+        crate::prune_before(self, cutoff).await
+    }
+
+    async fn list_cost_rows(&self) -> Result<Vec<CostRow>> {
+        crate::list_cost_rows(self).await
+    }
+
+    async fn first_unsettled_date(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        settlement_hours: i64,
+    ) -> Result<Option<NaiveDate>> {
+        crate::first_unsettled_date(self, tenant, start, end, settlement_hours).await
+    }
+
+    async fn list_cost_rows_between(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostRow>> {
+        crate::list_cost_rows_between(self, tenant, start, end).await
+    }
+
+    async fn list_adjustments(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<CostAdjustment>> {
+        crate::list_adjustments(self, tenant, start, end).await
+    }
+
+    async fn set_adjustment(&self, tenant: &str, adjustment: &CostAdjustment) -> Result<()> {
+        crate::set_adjustment(self, tenant, adjustment).await
+    }
+
+    async fn delete_adjustment(
+        &self,
+        tenant: &str,
+        date: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+    ) -> Result<()> {
+        crate::delete_adjustment(self, tenant, date, user_id, model_id, metric).await
+    }
+
+    async fn list_sources(&self, tenant: &str) -> Result<Vec<String>> {
+        crate::list_sources(self, tenant).await
+    }
+
+    async fn get_daily_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_daily_cost(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_monthly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_monthly_cost(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_weekly_cost(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_weekly_cost(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>> {
+        crate::get_cost_by_user(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>> {
+        crate::get_cost_by_model(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_daily_cost_by_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<ModelCostRow>> {
+        crate::get_daily_cost_by_model(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_daily_cost_by_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<UserCostRow>> {
+        crate::get_daily_cost_by_user(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_cost_by_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<(String, String, Amount, String)>> {
+        crate::get_cost_by_user_and_model(self, tenant, start, end, metric, source).await
+    }
+
+    async fn get_cost_by_model_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByModel>> {
+        crate::get_cost_by_model_for_user(self, tenant, start, end, user_id, metric, source).await
+    }
+
+    async fn get_cost_by_user_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostByUser>> {
+        crate::get_cost_by_user_for_model(self, tenant, start, end, model_id, metric, source).await
+    }
+
+    async fn get_daily_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_daily_cost_for_user(self, tenant, start, end, user_id, metric, source).await
+    }
+
+    async fn get_monthly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_monthly_cost_for_user(self, tenant, start, end, user_id, metric, source).await
+    }
+
+    async fn get_weekly_cost_for_user(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_weekly_cost_for_user(self, tenant, start, end, user_id, metric, source).await
+    }
+
+    async fn get_daily_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_daily_cost_for_model(self, tenant, start, end, model_id, metric, source).await
+    }
+
+    async fn get_monthly_cost_for_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_monthly_cost_for_model(self, tenant, start, end, model_id, metric, source).await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_daily_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_daily_cost_for_user_and_model(
+            self, tenant, start, end, user_id, model_id, metric, source,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn get_monthly_cost_for_user_and_model(
+        &self,
+        tenant: &str,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+        source: Option<&str>,
+    ) -> Result<Vec<CostRecord>> {
+        crate::get_monthly_cost_for_user_and_model(
+            self, tenant, start, end, user_id, model_id, metric, source,
+        )
+        .await
+    }
+}
+
+/// A connection pool to the cost database, Postgres or SQLite.
+#[derive(Clone)]
+pub enum CostDb {
+    Postgres(PgPool),
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlx::SqlitePool),
+}
+
+impl CostDb {
+    /// Connects to Postgres, or with the `sqlite` feature to the SQLite file
+    /// of a `sqlite:` URL, creating it if missing.
+    pub async fn connect(database_url: &str) -> Result<Self> {
+        #[cfg(feature = "sqlite")]
+        if database_url.starts_with("sqlite:") {
+            return Ok(Self::Sqlite(crate::sqlite::init_pool(database_url).await?));
+        }
+        anyhow::ensure!(
+            !database_url.starts_with("sqlite:"),
+            "SQLite cost databases need the `sqlite` feature"
+        );
+        Ok(Self::Postgres(crate::init_pool(database_url).await?))
+    }
+
+    pub fn store(&self) -> Arc<dyn CostStore> {
+        match self {
+            Self::Postgres(pool) => Arc::new(pool.clone()),
+            #[cfg(feature = "sqlite")]
+            Self::Sqlite(pool) => Arc::new(pool.clone()),
+        }
+    }
+}
@@ -35,11 +35,6 @@
     cargoBuildFlags = ["--bin" "server"];
   };
 
-  adminPackage = mkPackage {
-    pname = "cost-admin";
-    cargoBuildFlags = ["--bin" "server" "--features" "admin"];
-  };
-
   batchPackage = mkPackage {
     pname = "cost-batch";
     cargoBuildFlags = ["--bin" "batch"];
@@ -50,11 +45,6 @@ in {
     package = package;
     entrypoint = "server";
   };
-  admin = mkImage {
-    name = "cost-admin";
-    package = adminPackage;
-    entrypoint = "server";
-  };
   batch = mkImage {
     name = "cost-batch";
     package = batchPackage;
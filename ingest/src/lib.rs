@@ -0,0 +1,352 @@
+//! Bringing CE cost rows into the cost table: the chunked CE fetch, keeping
+//! the rows of users and models the gateway knows, the upsert, and the
+//! reconciliation of stored rows. Shared by the batch job and the server's
+//! on-demand refresh.
+
+pub mod reconcile;
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use common::{CostRow, Metric, AWS_SOURCE};
+use db::CostStore;
+use sqlx::PgPool;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+/// Days an incremental run refetches before its watermark, since CE
+/// restates recent days; the whole lookback before any run stored one.
+pub const DEFAULT_INCREMENTAL_DAYS: i64 = 3;
+
+/// Days fetched per CE query.
+pub const DEFAULT_CHUNK_DAYS: i64 = 30;
+
+/// Chunks fetched at once. The shared CE rate limiter still paces the calls
+/// themselves, so this only overlaps their latency.
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 4;
+
+/// Splits `[start, end)` into consecutive ranges of at most `chunk_days`.
+pub fn split_range(
+    start: NaiveDate,
+    end: NaiveDate,
+    chunk_days: i64,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let chunk = chunk_days.max(1);
+    let mut chunks = Vec::new();
+    let mut cursor = start;
+    while cursor < end {
+        let next = (cursor + chrono::Duration::days(chunk)).min(end);
+        chunks.push((cursor, next));
+        cursor = next;
+    }
+    chunks
+}
+
+/// The `[start, end)` an incremental run fetches: the `settlement_days`
+/// ending at `watermark`, the last day fully ingested, and every day since
+/// through yesterday. Without a watermark, the last `settlement_days`.
+pub fn incremental_range(
+    watermark: Option<NaiveDate>,
+    today: NaiveDate,
+    settlement_days: i64,
+) -> (NaiveDate, NaiveDate) {
+    let next = watermark.map_or(today, |through| {
+        (through + chrono::Duration::days(1)).min(today)
+    });
+    (next - chrono::Duration::days(settlement_days.max(1)), today)
+}
+
+/// User and model ids in the gateway DB of `tenant`. CE tags rows with
+/// whatever ids the requests carried, so rows of other ids are not stored.
+#[derive(Debug, Clone, Default)]
+pub struct KnownIds {
+    pub tenant: String,
+    pub users: HashSet<String>,
+    pub models: HashSet<String>,
+}
+
+impl KnownIds {
+    pub async fn load(gateway: &PgPool, tenant: &str) -> Result<Self> {
+        let (users, models) =
+            tokio::try_join!(db::list_user_ids(gateway), db::list_model_ids(gateway))?;
+        log::info!(
+            "Gateway DB of tenant {}: {} known users, {} known models",
+            tenant,
+            users.len(),
+            models.len()
+        );
+        Ok(Self {
+            tenant: tenant.to_string(),
+            users,
+            models,
+        })
+    }
+
+    /// Keeps only rows whose user and model are known, as rows of the
+    /// tenant, logging a sample of the unknown ids that were dropped.
+    pub fn filter(&self, rows: &[CostRow]) -> Vec<CostRow> {
+        let mut filtered_rows = Vec::new();
+        let mut unknown_user_ids = HashSet::new();
+        let mut unknown_model_ids = HashSet::new();
+        let mut skipped_count = 0usize;
+
+        for row in rows {
+            let user_known = self.users.contains(&row.user_id);
+            let model_known = self.models.contains(&row.model_id);
+            if user_known && model_known {
+                filtered_rows.push(CostRow {
+                    tenant: self.tenant.clone(),
+                    ..row.clone()
+                });
+            } else {
+                skipped_count += 1;
+                if !user_known {
+                    unknown_user_ids.insert(row.user_id.clone());
+                }
+                if !model_known {
+                    unknown_model_ids.insert(row.model_id.clone());
+                }
+            }
+        }
+
+        if skipped_count > 0 {
+            let sample_users: Vec<_> = unknown_user_ids.iter().take(5).cloned().collect();
+            let sample_models: Vec<_> = unknown_model_ids.iter().take(5).cloned().collect();
+            log::warn!(
+                "Skipped {} rows with unknown entities ({} unknown user_ids, {} unknown model_ids). \
+                 Sample unknown user_ids: {:?}, sample unknown model_ids: {:?}",
+                skipped_count,
+                unknown_user_ids.len(),
+                unknown_model_ids.len(),
+                sample_users,
+                sample_models,
+            );
+        }
+
+        filtered_rows
+    }
+}
+
+/// The rows of one chunk's CE queries over known users and models.
+#[derive(Debug, Clone, Default)]
+pub struct Chunk {
+    pub rows: Vec<CostRow>,
+    /// Rows CE returned, before unknown ids were dropped.
+    pub fetched: usize,
+    /// CE API calls (pages) the query took.
+    pub calls: usize,
+}
+
+/// What [`Source::ingest`] stored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Ingested {
+    pub rows: usize,
+    pub calls: usize,
+}
+
+/// CE cost rows in `metrics` of the users and models in `known`, as rows
+/// of its tenant.
+#[derive(Clone)]
+pub struct Source {
+    pub clients: ce::Clients,
+    pub known: Arc<KnownIds>,
+    pub metrics: Vec<Metric>,
+}
+
+impl Source {
+    /// Reads the known ids from the gateway DB of `tenant`.
+    pub async fn load(
+        gateway: &PgPool,
+        tenant: &str,
+        clients: ce::Clients,
+        metrics: Vec<Metric>,
+    ) -> Result<Self> {
+        Ok(Self {
+            clients,
+            known: Arc::new(KnownIds::load(gateway, tenant).await?),
+            metrics,
+        })
+    }
+
+    pub fn tenant(&self) -> &str {
+        &self.known.tenant
+    }
+
+    /// Fetches `[start, end)` in one CE query for the cost and one for the
+    /// credits, which are split out of it.
+    pub async fn fetch(&self, start: NaiveDate, end: NaiveDate) -> Result<Chunk> {
+        let (rows, calls) = ce::get_daily_gross_cost_and_credits_counted(
+            &self.clients,
+            &start.format("%Y-%m-%d").to_string(),
+            &end.format("%Y-%m-%d").to_string(),
+            &self.metrics,
+        )
+        .await?;
+        log::info!(
+            "Fetched {} cost rows from CE for {} to {}",
+            rows.len(),
+            start,
+            end
+        );
+        let filtered_rows = self.known.filter(&rows);
+        log::info!(
+            "Filtered {} CE rows down to {} rows with known users/models",
+            rows.len(),
+            filtered_rows.len()
+        );
+        Ok(Chunk {
+            fetched: rows.len(),
+            rows: filtered_rows,
+            calls,
+        })
+    }
+
+    /// Fetches `[start, end)` in chunks of `chunk_days`, with at most
+    /// `concurrency` CE queries going at once. Chunks come out of the set as
+    /// they finish, in any order; dropping the set cancels the rest.
+    pub fn fetch_chunks(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        chunk_days: i64,
+        concurrency: usize,
+    ) -> JoinSet<Result<Chunk>> {
+        let permits = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut fetches = JoinSet::new();
+        for (chunk_start, chunk_end) in split_range(start, end, chunk_days) {
+            let source = self.clone();
+            let permits = permits.clone();
+            fetches.spawn(async move {
+                let _permit = permits.acquire_owned().await?;
+                source.fetch(chunk_start, chunk_end).await
+            });
+        }
+        fetches
+    }
+
+    /// Fetches `[start, end)` like [`Source::fetch_chunks`] and upserts the
+    /// rows into `store`, then finishes the tenant's run with [`finish`].
+    pub async fn ingest(
+        &self,
+        store: &dyn CostStore,
+        start: NaiveDate,
+        end: NaiveDate,
+        chunk_days: i64,
+        concurrency: usize,
+    ) -> Result<Ingested> {
+        let mut ingested = Ingested::default();
+        let mut fetches = self.fetch_chunks(start, end, chunk_days, concurrency);
+        while let Some(chunk) = fetches.join_next().await {
+            let chunk = chunk??;
+            store.upsert_cost_rows(&chunk.rows).await?;
+            log::info!("Upserted {} rows into cost table", chunk.rows.len());
+            ingested.rows += chunk.rows.len();
+            ingested.calls += chunk.calls;
+        }
+        finish(store, self.tenant(), start, end).await?;
+        Ok(ingested)
+    }
+}
+
+/// Refreshes the monthly rollup of `[start, end)` once its rows are
+/// upserted, moves the CE watermark of `tenant` up to the last day of the
+/// range and records the run for the data freshness footer.
+pub async fn finish(
+    store: &dyn CostStore,
+    tenant: &str,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> Result<()> {
+    store.refresh_monthly_rollup(Some((start, end))).await?;
+    log::info!("Refreshed monthly rollup for {} to {}", start, end);
+    if let Some(through) = end.pred_opt().filter(|through| *through >= start) {
+        store
+            .advance_ingest_watermark(tenant, AWS_SOURCE, through)
+            .await?;
+    }
+    store.record_batch_run().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::Amount;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn split_range_exact_chunks() {
+        let chunks = split_range(date("2025-01-01"), date("2025-01-07"), 3);
+        assert_eq!(
+            chunks,
+            vec![
+                (date("2025-01-01"), date("2025-01-04")),
+                (date("2025-01-04"), date("2025-01-07")),
+            ]
+        );
+    }
+
+    #[test]
+    fn split_range_last_chunk_is_short() {
+        let chunks = split_range(date("2025-01-01"), date("2025-01-05"), 3);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[1], (date("2025-01-04"), date("2025-01-05")));
+    }
+
+    #[test]
+    fn split_range_empty() {
+        assert!(split_range(date("2025-01-05"), date("2025-01-05"), 3).is_empty());
+    }
+
+    #[test]
+    fn incremental_range_resumes_from_watermark() {
+        let today = date("2025-03-10");
+        assert_eq!(
+            incremental_range(None, today, 3),
+            (date("2025-03-07"), today)
+        );
+        // A daily run refetches the same days as without a watermark.
+        assert_eq!(
+            incremental_range(Some(date("2025-03-09")), today, 3),
+            (date("2025-03-07"), today)
+        );
+        // Missed runs are caught up.
+        assert_eq!(
+            incremental_range(Some(date("2025-03-01")), today, 3),
+            (date("2025-02-27"), today)
+        );
+        assert_eq!(
+            incremental_range(Some(date("2025-03-12")), today, 3),
+            (date("2025-03-07"), today)
+        );
+    }
+
+    #[test]
+    fn filter_drops_unknown_ids() {
+        let row = |user: &str, model: &str| CostRow {
+            date: date("2025-01-01"),
+            user_id: user.to_string(),
+            model_id: model.to_string(),
+            amount: Amount::from_f64(1.0),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        };
+        let rows = vec![row("u1", "m1"), row("u2", "m1"), row("u1", "m2")];
+        let known = KnownIds {
+            tenant: "dev".to_string(),
+            users: ["u1".to_string()].into_iter().collect(),
+            models: ["m1".to_string()].into_iter().collect(),
+        };
+        let filtered = known.filter(&rows);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].user_id, "u1");
+        assert_eq!(filtered[0].model_id, "m1");
+        assert_eq!(filtered[0].tenant, "dev");
+    }
+}
@@ -0,0 +1,321 @@
+//! Checking stored rows against a fresh CE query, since CE restates recent
+//! figures after a run stored them.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::NaiveDate;
+use common::{Amount, CostRow, Metric};
+use db::CostStore;
+
+use crate::Source;
+
+/// Days CE may still restate; reconciling stops before them.
+pub const RESTATEMENT_DAYS: i64 = 2;
+
+type Key = (String, NaiveDate, String, String, Metric, String);
+
+/// A cost table row that no longer matches what CE reports. A row missing
+/// on either side counts as zero.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discrepancy {
+    pub tenant: String,
+    pub date: NaiveDate,
+    pub user_id: String,
+    pub model_id: String,
+    pub metric: Metric,
+    /// The CE source of the row, gross cost or credits.
+    pub source: String,
+    pub stored: Amount,
+    pub fresh: Amount,
+    pub currency: String,
+}
+
+impl Discrepancy {
+    pub fn delta(&self) -> Amount {
+        self.fresh - self.stored
+    }
+
+    /// The row to upsert so the cost table matches CE.
+    pub fn repaired_row(&self) -> CostRow {
+        CostRow {
+            date: self.date,
+            user_id: self.user_id.clone(),
+            model_id: self.model_id.clone(),
+            amount: self.fresh,
+            currency: self.currency.clone(),
+            metric: self.metric,
+            source: self.source.clone(),
+            tenant: self.tenant.clone(),
+        }
+    }
+}
+
+fn keyed(rows: &[CostRow]) -> HashMap<Key, (Amount, &str)> {
+    let mut keyed = HashMap::new();
+    for row in rows {
+        let key = (
+            row.tenant.clone(),
+            row.date,
+            row.user_id.clone(),
+            row.model_id.clone(),
+            row.metric,
+            row.source.clone(),
+        );
+        let entry = keyed
+            .entry(key)
+            .or_insert((Amount::ZERO, row.currency.as_str()));
+        entry.0 += row.amount;
+    }
+    keyed
+}
+
+/// `key` in a sortable form, metrics by name.
+fn order(key: &Key) -> (&str, NaiveDate, &str, &str, &str, &str) {
+    (&key.0, key.1, &key.2, &key.3, key.4.as_str(), &key.5)
+}
+
+/// Rows whose stored and fresh amounts differ by more than `threshold`,
+/// by tenant, date, user, model, metric and source.
+pub fn diff(stored: &[CostRow], fresh: &[CostRow], threshold: Amount) -> Vec<Discrepancy> {
+    let stored = keyed(stored);
+    let fresh = keyed(fresh);
+    let mut keys: Vec<&Key> = stored.keys().chain(fresh.keys()).collect();
+    keys.sort_by(|a, b| order(a).cmp(&order(b)));
+    keys.dedup();
+
+    keys.into_iter()
+        .filter_map(|key| {
+            let (stored_amount, stored_currency) = stored.get(key).copied().unwrap_or_default();
+            let (fresh_amount, fresh_currency) = fresh.get(key).copied().unwrap_or_default();
+            let delta = fresh_amount - stored_amount;
+            if delta.max(-delta) <= threshold {
+                return None;
+            }
+            let currency = match fresh_currency {
+                "" => stored_currency,
+                c => c,
+            };
+            let (tenant, date, user_id, model_id, metric, source) = key.clone();
+            Some(Discrepancy {
+                tenant,
+                date,
+                user_id,
+                model_id,
+                metric,
+                source,
+                stored: stored_amount,
+                fresh: fresh_amount,
+                currency: currency.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Rows with their model and source dropped, so [`diff`] compares each
+/// day's net cost per user.
+fn per_user(rows: impl IntoIterator<Item = CostRow>) -> Vec<CostRow> {
+    rows.into_iter()
+        .map(|row| CostRow {
+            model_id: String::new(),
+            source: String::new(),
+            ..row
+        })
+        .collect()
+}
+
+/// How upserting `fresh` over `stored` would change each day's cost per user
+/// and metric. Stored rows CE no longer returns are kept, as the upsert
+/// keeps them; `model_id` and `source` of the results are empty.
+pub fn upsert_diff(stored: &[CostRow], fresh: &[CostRow], threshold: Amount) -> Vec<Discrepancy> {
+    let fresh_keys = keyed(fresh);
+    let kept = stored.iter().filter(|row| {
+        let key = (
+            row.tenant.clone(),
+            row.date,
+            row.user_id.clone(),
+            row.model_id.clone(),
+            row.metric,
+            row.source.clone(),
+        );
+        !fresh_keys.contains_key(&key)
+    });
+    let after = per_user(kept.chain(fresh).cloned());
+    diff(&per_user(stored.iter().cloned()), &after, threshold)
+}
+
+/// What [`run`] found.
+#[derive(Debug, Clone, Default)]
+pub struct Reconciliation {
+    pub discrepancies: Vec<Discrepancy>,
+    /// Rows of known users and models CE returned.
+    pub fresh_rows: usize,
+}
+
+/// Compares the stored rows of the source's tenant in `[start, end)` and
+/// metrics with a fresh CE query. With `repair`, the CE figures of differing rows are
+/// upserted and the monthly rollup refreshed.
+pub async fn run(
+    source: &Source,
+    store: &dyn CostStore,
+    start: NaiveDate,
+    end: NaiveDate,
+    threshold: Amount,
+    repair: bool,
+) -> Result<Reconciliation> {
+    let stored: Vec<CostRow> = store
+        .list_cost_rows_between(source.tenant(), start, end)
+        .await?
+        .into_iter()
+        .filter(|row| source.metrics.contains(&row.metric))
+        .collect();
+    let fresh = source.fetch(start, end).await?.rows;
+
+    let discrepancies = diff(&stored, &fresh, threshold);
+    if repair && !discrepancies.is_empty() {
+        let rows: Vec<CostRow> = discrepancies.iter().map(|d| d.repaired_row()).collect();
+        store.upsert_cost_rows(&rows).await?;
+        store.refresh_monthly_rollup(Some((start, end))).await?;
+        log::info!("Repaired {} rows in the cost table", rows.len());
+    }
+    Ok(Reconciliation {
+        discrepancies,
+        fresh_rows: fresh.len(),
+    })
+}
+
+/// Fetches `[start, end)` like a batch run and reports how storing the rows
+/// would change the cost table, per day and user, without writing.
+pub async fn preview(
+    source: &Source,
+    store: &dyn CostStore,
+    start: NaiveDate,
+    end: NaiveDate,
+    chunk_days: i64,
+    concurrency: usize,
+    threshold: Amount,
+) -> Result<Reconciliation> {
+    let stored: Vec<CostRow> = store
+        .list_cost_rows_between(source.tenant(), start, end)
+        .await?
+        .into_iter()
+        .filter(|row| source.metrics.contains(&row.metric))
+        .collect();
+    let mut fresh = Vec::new();
+    let mut fetches = source.fetch_chunks(start, end, chunk_days, concurrency);
+    while let Some(chunk) = fetches.join_next().await {
+        fresh.extend(chunk??.rows);
+    }
+    Ok(Reconciliation {
+        discrepancies: upsert_diff(&stored, &fresh, threshold),
+        fresh_rows: fresh.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: &str, user: &str, micros: i64) -> CostRow {
+        CostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            user_id: user.to_string(),
+            model_id: "m1".to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn diff_reports_changes_above_threshold() {
+        let stored = vec![
+            row("2025-01-01", "u1", 1_000_000),
+            row("2025-01-01", "u2", 1_000_000),
+            row("2025-01-02", "u1", 500_000),
+        ];
+        let fresh = vec![
+            row("2025-01-01", "u1", 1_005_000),
+            row("2025-01-01", "u2", 1_200_000),
+            row("2025-01-02", "u3", 300_000),
+        ];
+        let found = diff(&stored, &fresh, Amount::from_f64(0.01));
+        let summary: Vec<_> = found
+            .iter()
+            .map(|d| (d.user_id.as_str(), d.delta().micros()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![("u2", 200_000), ("u1", -500_000), ("u3", 300_000)]
+        );
+        assert_eq!(found[1].repaired_row().amount, Amount::ZERO);
+        assert_eq!(found[1].currency, "USD");
+    }
+
+    #[test]
+    fn upsert_diff_sums_models_and_keeps_missing_rows() {
+        let mut other_model = row("2025-01-01", "u1", 2_000_000);
+        other_model.model_id = "m2".to_string();
+        let stored = vec![
+            row("2025-01-01", "u1", 1_000_000),
+            other_model.clone(),
+            row("2025-01-02", "u2", 500_000),
+        ];
+        let mut fresh_other_model = other_model;
+        fresh_other_model.amount = Amount::from_micros(2_500_000);
+        let fresh = vec![fresh_other_model, row("2025-01-03", "u3", 300_000)];
+
+        let found = upsert_diff(&stored, &fresh, Amount::from_f64(0.01));
+        let summary: Vec<_> = found
+            .iter()
+            .map(|d| {
+                (
+                    d.date.to_string(),
+                    d.user_id.as_str(),
+                    d.stored.micros(),
+                    d.fresh.micros(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("2025-01-01".to_string(), "u1", 3_000_000, 3_500_000),
+                ("2025-01-03".to_string(), "u3", 0, 300_000),
+            ]
+        );
+        assert!(found.iter().all(|d| d.model_id.is_empty()));
+    }
+
+    #[test]
+    fn diff_keeps_credits_apart() {
+        let stored = vec![row("2025-01-01", "u1", 8_000_000)];
+        let mut credit = row("2025-01-01", "u1", -2_000_000);
+        credit.source = common::CREDIT_SOURCE.to_string();
+        let fresh = vec![row("2025-01-01", "u1", 10_000_000), credit];
+
+        let found = diff(&stored, &fresh, Amount::ZERO);
+        let repaired: Vec<_> = found
+            .iter()
+            .map(|d| d.repaired_row())
+            .map(|r| (r.source, r.amount.micros()))
+            .collect();
+        assert_eq!(
+            repaired,
+            vec![
+                (common::AWS_SOURCE.to_string(), 10_000_000),
+                (common::CREDIT_SOURCE.to_string(), -2_000_000),
+            ]
+        );
+        // The net cost per user is unchanged.
+        assert!(upsert_diff(&stored, &fresh, Amount::ZERO).is_empty());
+    }
+
+    #[test]
+    fn diff_matching_rows_is_empty() {
+        let rows = vec![row("2025-01-01", "u1", 1_000_000)];
+        assert!(diff(&rows, &rows, Amount::ZERO).is_empty());
+    }
+}
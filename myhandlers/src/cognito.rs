@@ -0,0 +1,37 @@
+use crate::oidc::{Discovery, OidcProvider};
+
+/// AWS Cognito hosted UI settings. Cognito is an OIDC provider whose
+/// endpoints follow from the domain, and it lists groups in
+/// `cognito:groups`.
+#[derive(Clone)]
+pub struct CognitoProvider {
+    pub client_id: String,
+    pub client_secret: String,
+    pub domain: String,
+    pub redirect_uri: String,
+    pub region: String,
+    pub user_pool_id: String,
+}
+
+impl From<CognitoProvider> for OidcProvider {
+    fn from(cognito: CognitoProvider) -> Self {
+        let domain = cognito.domain.trim_start_matches("https://");
+        let base = format!("https://{}", domain.trim_end_matches('/'));
+        OidcProvider::new(
+            &format!(
+                "https://cognito-idp.{}.amazonaws.com/{}",
+                cognito.region, cognito.user_pool_id
+            ),
+            &cognito.client_id,
+            &cognito.client_secret,
+            &cognito.redirect_uri,
+            ["openid", "email"].map(String::from).to_vec(),
+        )
+        .with_groups_claim("cognito:groups")
+        .with_endpoints(Discovery {
+            authorization_endpoint: format!("{base}/oauth2/authorize"),
+            token_endpoint: format!("{base}/oauth2/token"),
+            userinfo_endpoint: format!("{base}/oauth2/userInfo"),
+        })
+    }
+}
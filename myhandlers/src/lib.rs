@@ -1,19 +1,47 @@
+mod cognito;
+mod oidc;
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
 use axum::{
-    extract::{Query, State},
+    extract::State,
+    http::Uri,
     response::{IntoResponse, Redirect, Response},
+    Extension,
 };
-use handlers::CallbackQuery;
+pub use cognito::CognitoProvider;
 use myerrors::AppError;
+pub use oidc::{OidcProvider, GROUPS_KEY};
 use tower_sessions::Session;
 
+/// `scheme://host` the client reached the server at, as a request extension.
+/// Set by the server behind trusted proxies; redirect URIs configured as a
+/// path are resolved against it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Origin(pub String);
+
+/// Sign-in flow of an identity provider. A successful callback stores the
+/// user's address under the `email` session key.
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Redirects to the provider's sign-in page.
+    async fn login(&self, session: Session, origin: Option<&Origin>) -> Result<Response, AppError>;
+    /// Handles the provider's redirect back, whose query is in `uri`.
+    async fn callback(
+        &self,
+        uri: &Uri,
+        session: Session,
+        origin: Option<&Origin>,
+    ) -> Result<Response, AppError>;
+    /// Checks that the provider is reachable with the configured settings,
+    /// without signing anyone in; describes what was reached.
+    async fn check(&self) -> anyhow::Result<String>;
+}
+
 #[derive(Clone)]
 pub struct AppState {
-    pub cognito_client_id: String,
-    pub cognito_client_secret: String,
-    pub cognito_domain: String,
-    pub cognito_redirect_uri: String,
-    pub cognito_region: String,
-    pub cognito_user_pool_id: String,
+    pub provider: Arc<dyn AuthProvider>,
 }
 
 pub async fn logout(session: Session) -> Result<Response, AppError> {
@@ -21,30 +49,24 @@ pub async fn logout(session: Session) -> Result<Response, AppError> {
     Ok(Redirect::to("/").into_response())
 }
 
-pub async fn login(session: Session, state: State<AppState>) -> Result<Response, AppError> {
-    let state = State(handlers::AppState {
-        client_id: state.cognito_client_id.clone(),
-        client_secret: state.cognito_client_secret.clone(),
-        domain: state.cognito_domain.clone(),
-        redirect_uri: state.cognito_redirect_uri.clone(),
-        region: state.cognito_region.clone(),
-        user_pool_id: state.cognito_user_pool_id.clone(),
-    });
-    Ok(handlers::login(session, state).await?)
+pub async fn login(
+    session: Session,
+    origin: Option<Extension<Origin>>,
+    state: State<AppState>,
+) -> Result<Response, AppError> {
+    let origin = origin.map(|Extension(origin)| origin);
+    state.provider.login(session, origin.as_ref()).await
 }
 
 pub async fn callback(
-    query: Query<CallbackQuery>,
+    uri: Uri,
     session: Session,
+    origin: Option<Extension<Origin>>,
     state: State<AppState>,
 ) -> Result<Response, AppError> {
-    let state = State(handlers::AppState {
-        client_id: state.cognito_client_id.clone(),
-        client_secret: state.cognito_client_secret.clone(),
-        domain: state.cognito_domain.clone(),
-        redirect_uri: state.cognito_redirect_uri.clone(),
-        region: state.cognito_region.clone(),
-        user_pool_id: state.cognito_user_pool_id.clone(),
-    });
-    Ok(handlers::callback(query, session, state).await?)
+    let origin = origin.map(|Extension(origin)| origin);
+    state
+        .provider
+        .callback(&uri, session, origin.as_ref())
+        .await
 }
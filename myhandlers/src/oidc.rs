@@ -0,0 +1,365 @@
+use anyhow::{bail, Context};
+use async_trait::async_trait;
+use axum::{
+    extract::Query,
+    http::Uri,
+    response::{IntoResponse, Redirect, Response},
+};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use myerrors::AppError;
+use reqwest::Url;
+use serde::de::Unexpected;
+use serde::{Deserialize, Deserializer};
+use serde_json::{Map, Value};
+use tokio::sync::OnceCell;
+use tower_sessions::Session;
+use uuid::Uuid;
+
+use crate::{AuthProvider, Origin};
+
+/// Session key holding the `state` sent with the last login redirect.
+const STATE_KEY: &str = "oidc_state";
+
+/// Session key holding the signed-in user's groups from the ID token.
+pub const GROUPS_KEY: &str = "groups";
+
+/// Endpoints from the issuer's discovery document.
+#[derive(Deserialize)]
+pub(crate) struct Discovery {
+    pub(crate) authorization_endpoint: String,
+    pub(crate) token_endpoint: String,
+    pub(crate) userinfo_endpoint: String,
+}
+
+#[derive(Deserialize)]
+struct OidcCallback {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct UserInfo {
+    email: Option<String>,
+    #[serde(default, deserialize_with = "bool_or_string")]
+    email_verified: Option<bool>,
+    /// Azure AD puts the sign-in address here when `email` is unset.
+    preferred_username: Option<String>,
+}
+
+/// Reads a claim that is a boolean, or a `"true"`/`"false"` string as
+/// Cognito's userInfo endpoint sends it.
+fn bool_or_string<'de, D>(deserializer: D) -> Result<Option<bool>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Claim {
+        Bool(bool),
+        String(String),
+    }
+    match Option::<Claim>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(Claim::Bool(value)) => Ok(Some(value)),
+        Some(Claim::String(value)) => match value.as_str() {
+            "true" => Ok(Some(true)),
+            "false" => Ok(Some(false)),
+            _ => Err(serde::de::Error::invalid_value(
+                Unexpected::Str(&value),
+                &"true or false",
+            )),
+        },
+    }
+}
+
+/// Any OpenID Connect provider with a discovery document, e.g. Okta, Azure
+/// AD or Keycloak, using the authorization code flow.
+pub struct OidcProvider {
+    issuer_url: String,
+    client_id: String,
+    client_secret: String,
+    redirect_uri: String,
+    scopes: Vec<String>,
+    /// ID token claim listing the user's groups.
+    groups_claim: String,
+    http: reqwest::Client,
+    /// Fetched on first use so startup does not depend on the provider.
+    discovery: OnceCell<Discovery>,
+}
+
+/// Who signed in, and which groups the ID token put them in.
+struct SignIn {
+    email: String,
+    groups: Vec<String>,
+}
+
+impl OidcProvider {
+    pub fn new(
+        issuer_url: &str,
+        client_id: &str,
+        client_secret: &str,
+        redirect_uri: &str,
+        scopes: Vec<String>,
+    ) -> Self {
+        Self {
+            issuer_url: issuer_url.trim_end_matches('/').to_string(),
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            redirect_uri: redirect_uri.to_string(),
+            scopes,
+            groups_claim: "groups".to_string(),
+            http: reqwest::Client::new(),
+            discovery: OnceCell::new(),
+        }
+    }
+
+    pub fn with_groups_claim(mut self, claim: &str) -> Self {
+        self.groups_claim = claim.to_string();
+        self
+    }
+
+    /// Uses fixed endpoints instead of fetching the discovery document.
+    pub(crate) fn with_endpoints(mut self, discovery: Discovery) -> Self {
+        self.discovery = OnceCell::from(discovery);
+        self
+    }
+
+    async fn discovery(&self) -> anyhow::Result<&Discovery> {
+        self.discovery
+            .get_or_try_init(|| self.fetch_discovery())
+            .await
+    }
+
+    async fn fetch_discovery(&self) -> anyhow::Result<Discovery> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer_url);
+        self.http
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Discovery>()
+            .await
+            .with_context(|| format!("Failed to read OIDC discovery document {url}"))
+    }
+
+    /// The configured redirect URI if absolute; otherwise that path, or
+    /// `/callback`, on the client's origin.
+    fn redirect_uri(&self, origin: Option<&Origin>) -> String {
+        if self.redirect_uri.starts_with("https://") || self.redirect_uri.starts_with("http://") {
+            return self.redirect_uri.clone();
+        }
+        let path = match self.redirect_uri.as_str() {
+            "" => "/callback",
+            path => path,
+        };
+        match origin {
+            Some(Origin(origin)) => format!("{origin}{path}"),
+            None => path.to_string(),
+        }
+    }
+
+    async fn authorization_url(&self, state: &str, redirect_uri: &str) -> anyhow::Result<Url> {
+        let discovery = self.discovery().await?;
+        let mut url = Url::parse(&discovery.authorization_endpoint)?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", redirect_uri)
+            .append_pair("scope", &self.scopes.join(" "))
+            .append_pair("state", state);
+        Ok(url)
+    }
+
+    /// Exchanges the callback's code for the user's address and groups.
+    async fn sign_in(
+        &self,
+        uri: &Uri,
+        session: &Session,
+        redirect_uri: &str,
+    ) -> anyhow::Result<SignIn> {
+        let Query(query) = Query::<OidcCallback>::try_from_uri(uri)?;
+        if let Some(error) = query.error {
+            bail!(
+                "Sign-in failed: {error} {}",
+                query.error_description.unwrap_or_default()
+            );
+        }
+        let expected = session.remove::<String>(STATE_KEY).await?;
+        if expected.is_none() || query.state != expected {
+            bail!("Sign-in state mismatch; please log in again");
+        }
+        let code = query.code.context("Missing authorization code")?;
+
+        let discovery = self.discovery().await?;
+        let token: TokenResponse = self
+            .http
+            .post(&discovery.token_endpoint)
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()
+            .context("Token request failed")?
+            .json()
+            .await?;
+        let info: UserInfo = self
+            .http
+            .get(&discovery.userinfo_endpoint)
+            .bearer_auth(&token.access_token)
+            .send()
+            .await?
+            .error_for_status()
+            .context("Userinfo request failed")?
+            .json()
+            .await?;
+
+        if info.email_verified == Some(false) {
+            bail!("Email address is not verified");
+        }
+        let email = info
+            .email
+            .or(info.preferred_username.filter(|name| name.contains('@')))
+            .context("Provider returned no email address; request the `email` scope")?;
+        let groups = match &token.id_token {
+            Some(id_token) => {
+                let claims = id_token_claims(id_token)?;
+                check_claims(&claims, &self.issuer_url, &self.client_id)?;
+                claim_groups(&claims, &self.groups_claim)
+            }
+            None => Vec::new(),
+        };
+        Ok(SignIn { email, groups })
+    }
+}
+
+/// Reads the ID token's claims without checking its signature, which OIDC
+/// Core 3.1.3.7 allows for tokens received directly from the token endpoint
+/// over TLS.
+fn id_token_claims(id_token: &str) -> anyhow::Result<Map<String, Value>> {
+    let payload = id_token.split('.').nth(1).context("Malformed ID token")?;
+    let json = URL_SAFE_NO_PAD
+        .decode(payload.trim_end_matches('='))
+        .context("Malformed ID token")?;
+    Ok(serde_json::from_slice(&json)?)
+}
+
+/// The token must come from our issuer and be meant for this client.
+fn check_claims(claims: &Map<String, Value>, issuer: &str, client_id: &str) -> anyhow::Result<()> {
+    let iss = claims
+        .get("iss")
+        .and_then(Value::as_str)
+        .unwrap_or_default();
+    if iss.trim_end_matches('/') != issuer {
+        bail!("ID token issuer {iss:?} does not match {issuer:?}");
+    }
+    let aud_ok = match claims.get("aud") {
+        Some(Value::String(aud)) => aud == client_id,
+        Some(Value::Array(auds)) => auds.iter().any(|aud| aud.as_str() == Some(client_id)),
+        _ => false,
+    };
+    if !aud_ok {
+        bail!("ID token was not issued for this client");
+    }
+    Ok(())
+}
+
+/// Group names under `claim`, given either as an array or a single string.
+fn claim_groups(claims: &Map<String, Value>, claim: &str) -> Vec<String> {
+    match claims.get(claim) {
+        Some(Value::Array(groups)) => groups
+            .iter()
+            .filter_map(|g| g.as_str().map(str::to_string))
+            .collect(),
+        Some(Value::String(group)) => vec![group.clone()],
+        _ => Vec::new(),
+    }
+}
+
+#[async_trait]
+impl AuthProvider for OidcProvider {
+    async fn login(&self, session: Session, origin: Option<&Origin>) -> Result<Response, AppError> {
+        let state = Uuid::new_v4().to_string();
+        session.insert(STATE_KEY, &state).await?;
+        let url = self
+            .authorization_url(&state, &self.redirect_uri(origin))
+            .await?;
+        Ok(Redirect::to(url.as_str()).into_response())
+    }
+
+    async fn callback(
+        &self,
+        uri: &Uri,
+        session: Session,
+        origin: Option<&Origin>,
+    ) -> Result<Response, AppError> {
+        let sign_in = self
+            .sign_in(uri, &session, &self.redirect_uri(origin))
+            .await?;
+        session.cycle_id().await?;
+        session.insert("email", sign_in.email).await?;
+        session.insert(GROUPS_KEY, sign_in.groups).await?;
+        Ok(Redirect::to("/").into_response())
+    }
+
+    async fn check(&self) -> anyhow::Result<String> {
+        // Always fetched, so Cognito's region and user pool are checked
+        // even though its endpoints are fixed.
+        self.fetch_discovery().await?;
+        let endpoint = &self.discovery().await?.authorization_endpoint;
+        self.http
+            .get(endpoint)
+            .send()
+            .await
+            .with_context(|| format!("Cannot reach sign-in endpoint {endpoint}"))?;
+        Ok(format!(
+            "issuer {}, sign-in at {}",
+            self.issuer_url, endpoint
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn user_info_takes_cognitos_string_booleans() {
+        let cognito = r#"{
+            "sub": "8f1c2d3e-4b5a-6978-8a9b-0c1d2e3f4a5b",
+            "email_verified": "true",
+            "email": "alice@example.com",
+            "username": "alice"
+        }"#;
+        let info: UserInfo = serde_json::from_str(cognito).unwrap();
+        assert_eq!(info.email.as_deref(), Some("alice@example.com"));
+        assert_eq!(info.email_verified, Some(true));
+
+        let unverified = r#"{"email": "bob@example.com", "email_verified": "false"}"#;
+        let info: UserInfo = serde_json::from_str(unverified).unwrap();
+        assert_eq!(info.email_verified, Some(false));
+
+        let okta = r#"{"email": "carol@example.com", "email_verified": true}"#;
+        let info: UserInfo = serde_json::from_str(okta).unwrap();
+        assert_eq!(info.email_verified, Some(true));
+
+        let azure = r#"{"preferred_username": "dave@example.com"}"#;
+        let info: UserInfo = serde_json::from_str(azure).unwrap();
+        assert_eq!(info.email_verified, None);
+
+        assert!(serde_json::from_str::<UserInfo>(r#"{"email_verified": "yes"}"#).is_err());
+    }
+}
@@ -0,0 +1,141 @@
+//! JSON listings for scripts, paginated like the pages they mirror. Each
+//! body carries the total and the neighbouring pages' URLs, which are also
+//! sent as RFC 8288 `Link` headers.
+
+use axum::http::{header, HeaderValue};
+use axum::response::{IntoResponse, Response};
+use axum::Json;
+use common::Amount;
+use serde::Serialize;
+
+use crate::pages::{users, with_query};
+
+/// Largest `?page_size=` honoured.
+pub const MAX_PAGE_SIZE: usize = 1000;
+
+/// One page of `items`, which are the rows of `page` (from 1) when the full
+/// listing of `total` rows is cut into pages of `page_size`.
+#[derive(Serialize)]
+pub struct Paged<T> {
+    pub total: usize,
+    pub page: usize,
+    pub page_size: usize,
+    pub next: Option<String>,
+    pub prev: Option<String>,
+    pub items: Vec<T>,
+}
+
+impl<T: Serialize> Paged<T> {
+    /// Cuts `page` out of `rows`; `path` is the listing's URL with every
+    /// query parameter but `page`. Pages past the end are empty but keep a
+    /// `prev` link back to the last one.
+    pub fn new(path: &str, rows: Vec<T>, page: usize, page_size: usize) -> Self {
+        let total = rows.len();
+        let page = page.max(1);
+        let page_size = page_size.clamp(1, MAX_PAGE_SIZE);
+        let last = total.div_ceil(page_size).max(1);
+        let link = |page: usize| with_query(path, "page", &page.to_string());
+        Self {
+            total,
+            page,
+            page_size,
+            next: (page < last).then(|| link(page + 1)),
+            prev: (page > 1).then(|| link((page - 1).min(last))),
+            items: rows
+                .into_iter()
+                .skip((page - 1).saturating_mul(page_size))
+                .take(page_size)
+                .collect(),
+        }
+    }
+
+    /// `Link: <...>; rel="next", <...>; rel="prev"`, or `None` on the only page.
+    fn link_header(&self) -> Option<HeaderValue> {
+        let links: Vec<String> = [("next", &self.next), ("prev", &self.prev)]
+            .into_iter()
+            .filter_map(|(rel, url)| url.as_ref().map(|url| format!("<{url}>; rel=\"{rel}\"")))
+            .collect();
+        if links.is_empty() {
+            return None;
+        }
+        HeaderValue::from_str(&links.join(", ")).ok()
+    }
+}
+
+impl<T: Serialize> IntoResponse for Paged<T> {
+    fn into_response(self) -> Response {
+        let link = self.link_header();
+        let mut response = Json(self).into_response();
+        if let Some(link) = link {
+            response.headers_mut().insert(header::LINK, link);
+        }
+        response
+    }
+}
+
+/// A row of `/users.json`; the previous period's cost is only present when
+/// comparing.
+#[derive(Serialize)]
+pub struct User {
+    pub user_id: String,
+    pub email: String,
+    pub cost: Amount,
+    pub currency: String,
+    pub api_keys: String,
+    pub profiles: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous: Option<Amount>,
+    /// Cost with the markup added, when one is configured.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub charged: Option<Amount>,
+}
+
+impl User {
+    pub fn new(row: users::IndexRow, compare: bool) -> Self {
+        Self {
+            user_id: row.user_id,
+            email: row.display,
+            cost: row.cost,
+            currency: row.currency,
+            api_keys: row.api_keys,
+            profiles: row.profiles,
+            previous: compare.then_some(row.previous),
+            charged: row.charged,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_link_their_neighbours() {
+        let rows: Vec<usize> = (0..120).collect();
+        let first = Paged::new("/users.json?period=7d", rows.clone(), 1, 50);
+        assert_eq!((first.total, first.page, first.items.len()), (120, 1, 50));
+        assert_eq!(first.next.as_deref(), Some("/users.json?period=7d&page=2"));
+        assert_eq!(first.prev, None);
+        assert_eq!(
+            first.link_header().unwrap(),
+            "</users.json?period=7d&page=2>; rel=\"next\""
+        );
+
+        let last = Paged::new("/users.json", rows.clone(), 3, 50);
+        assert_eq!(last.items, (100..120).collect::<Vec<_>>());
+        assert_eq!(last.next, None);
+        assert_eq!(last.prev.as_deref(), Some("/users.json?page=2"));
+
+        let past = Paged::new("/users.json", rows, 9, 50);
+        assert!(past.items.is_empty());
+        assert_eq!(past.prev.as_deref(), Some("/users.json?page=3"));
+    }
+
+    #[test]
+    fn single_pages_have_no_links() {
+        let page = Paged::new("/users.json", vec![1, 2], 1, 0);
+        assert_eq!(page.page_size, 1);
+        let page = Paged::new("/users.json", vec![1, 2], 1, 50);
+        assert!(page.link_header().is_none());
+    }
+}
@@ -0,0 +1,72 @@
+use common::CheckReport;
+
+use crate::config::AppConfig;
+
+/// Checks the settings `server` depends on and prints a pass/fail table;
+/// fails when any check does.
+pub async fn run(config: &AppConfig) -> anyhow::Result<()> {
+    let mut report = CheckReport::default();
+
+    let gateway = |url: &str| {
+        let url = url.to_string();
+        async move {
+            let pool = db::init_pool(&url).await?;
+            let users = db::list_user_ids(&pool).await?;
+            anyhow::Ok(format!("{} users", users.len()))
+        }
+    };
+    report.record(
+        "gateway db",
+        gateway(&config.database_url_gateway_ro)
+            .await
+            .map_err(|e| format!("{e:#}")),
+    );
+    for tenant in &config.tenants {
+        report.record(
+            &format!("gateway db ({})", tenant.name),
+            gateway(&tenant.database_url_gateway_ro)
+                .await
+                .map_err(|e| format!("{e:#}")),
+        );
+    }
+
+    let cost = async {
+        let store = db::CostDb::connect(&config.database_url_cost)
+            .await?
+            .store();
+        store.ping().await?;
+        anyhow::Ok("connected".to_string())
+    };
+    report.record("cost db", cost.await.map_err(|e| format!("{e:#}")));
+
+    let clients = ce::Clients::new(config.aws_accounts.clone());
+    for (account, result) in ce::check_access(&clients).await {
+        report.record(
+            &format!("cost explorer ({account})"),
+            result
+                .map(|()| "GetCostAndUsage allowed".to_string())
+                .map_err(|e| format!("{e:#}")),
+        );
+    }
+
+    let missing = config.missing_auth_settings();
+    let auth = if missing.is_empty() {
+        config
+            .auth_provider()
+            .check()
+            .await
+            .map_err(|e| format!("{e:#}"))
+    } else {
+        Err(format!("missing {}", missing.join(", ")))
+    };
+    report.record(&format!("{:?} auth", config.auth_provider), auth);
+
+    print!("{report}");
+    anyhow::ensure!(
+        report.failures() == 0,
+        "{} of {} checks failed",
+        report.failures(),
+        report.checks.len()
+    );
+    Ok(())
+}
@@ -0,0 +1,262 @@
+use std::sync::LazyLock;
+
+use axum::extract::{Query, Request, State};
+use axum::http::{header, HeaderMap, HeaderValue, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Duration, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+use common::Metric;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tower_sessions::Session;
+
+use crate::handlers::{resolve_period, AppState};
+use crate::preferences::Preferences;
+use crate::reload;
+use crate::roles::Role;
+use crate::service::DataSource;
+
+/// Pages are per user, so only the browser may keep them, and it must ask
+/// before reusing one.
+const CACHE_CONTROL: &str = "private, no-cache";
+
+/// Stands in for the build and the config a process started with: tags of
+/// an earlier process are never taken for current ones.
+static STARTED: LazyLock<DateTime<Utc>> = LazyLock::new(Utc::now);
+
+#[derive(Deserialize)]
+struct PeriodQuery {
+    period: Option<String>,
+}
+
+/// The validators of a page, known before it is rendered.
+struct Validator {
+    tag: HeaderValue,
+    last_modified: DateTime<Utc>,
+}
+
+/// Answers a request for a page over a finalized range whose
+/// `If-None-Match` names the current tag with an empty `304`, without
+/// rendering the page. The tag hashes what the page is rendered from: its
+/// URL, the user and their preferences, today's date, when the stored data
+/// last changed and the running config. Changes made through the pages are
+/// recorded, so tags move on after them.
+pub async fn etag(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    if !req.method().is_safe() {
+        let records_edit = !req.uri().path().ends_with("/graphql");
+        let response = next.run(req).await;
+        let status = response.status();
+        if records_edit && (status.is_success() || status.is_redirection()) {
+            if let Err(e) = state.service.record_edit().await {
+                log::warn!("{e:#}");
+            }
+        }
+        return response;
+    }
+    if req.method() != Method::GET {
+        return next.run(req).await;
+    }
+    let Some(validator) = validator(&state, &req).await else {
+        return next.run(req).await;
+    };
+
+    let not_modified = req
+        .headers()
+        .get(header::IF_NONE_MATCH)
+        .is_some_and(|value| matches(value, &validator.tag));
+    let mut response = if not_modified {
+        StatusCode::NOT_MODIFIED.into_response()
+    } else {
+        next.run(req).await
+    };
+    if response.status() == StatusCode::NOT_MODIFIED
+        || (response.status() == StatusCode::OK && is_html(response.headers()))
+    {
+        let headers = response.headers_mut();
+        headers.insert(header::ETAG, validator.tag);
+        headers.insert(header::LAST_MODIFIED, http_date(validator.last_modified));
+        headers.insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static(CACHE_CONTROL),
+        );
+    }
+    response
+}
+
+/// The validators of the page `req` asks for, or `None` when it is not
+/// cached: pages read live from CE, which carry the time they were fetched,
+/// pages [`covered_end`] does not know and ranges not yet finalized.
+async fn validator(state: &AppState, req: &Request) -> Option<Validator> {
+    let config = state.config.load();
+    if config.data_source != DataSource::Db {
+        return None;
+    }
+    let session = req.extensions().get::<Session>()?;
+    let email = session.get::<String>("email").await.ok().flatten()?;
+    let role = session.get::<Role>("role").await.ok().flatten()?;
+    let prefs = Preferences::load(session).await;
+    let metric = session.get::<Metric>("metric").await.ok().flatten();
+    let source = session.get::<String>("source").await.ok().flatten();
+
+    let Query(query) = Query::<PeriodQuery>::try_from_uri(req.uri()).ok()?;
+    let period = query
+        .period
+        .or_else(|| prefs.period.clone())
+        .unwrap_or_else(|| config.default_period.clone());
+    let timezone = prefs
+        .timezone
+        .as_deref()
+        .and_then(|tz| tz.parse::<Tz>().ok());
+    let today = match timezone {
+        Some(tz) => Utc::now().with_timezone(&tz).date_naive(),
+        None => state.service.today(),
+    };
+    let (_, period_end) = resolve_period(&period, today);
+    let end = covered_end(req.uri().path(), period_end)?;
+    let now = Utc::now();
+    if !finalized(end, config.settlement_hours, now) {
+        return None;
+    }
+
+    let freshness = match state.service.data_freshness().await {
+        Ok(freshness) => freshness,
+        Err(e) => {
+            log::warn!("{e:#}");
+            return None;
+        }
+    };
+    let changes = [
+        freshness.batch_run,
+        freshness.rollup_refresh,
+        freshness.edit,
+        reload::last_reload().map(|reload| reload.at),
+        Some(*STARTED),
+    ];
+    let last_modified = changes.into_iter().flatten().max()?;
+    // The footer warns once the data is stale.
+    let stale = freshness.is_stale(now, config.stale_data_hours);
+    let inputs = format!(
+        "{}\n{}\n{email}\n{role:?}\n{prefs:?}\n{metric:?}\n{source:?}\n{today}\n{changes:?}\n{stale}",
+        state.service.tenant(),
+        req.uri(),
+    );
+    Some(Validator {
+        tag: tag(inputs.as_bytes()),
+        last_modified,
+    })
+}
+
+/// The end, exclusive, of the days shown by the page at `path`, for the
+/// pages that show only the cost of their period, day or month; `None` for
+/// the others.
+fn covered_end(path: &str, period_end: NaiveDate) -> Option<NaiveDate> {
+    let segments: Vec<&str> = path.trim_matches('/').split('/').collect();
+    match segments.as_slice() {
+        ["costs", "daily" | "weekly" | "monthly" | "matrix"] | ["costs", "daily", "stacked"] => {
+            Some(period_end)
+        }
+        ["costs", "daily", date, ..] => date.parse::<NaiveDate>().ok()?.succ_opt(),
+        ["costs", "monthly", month, ..] => {
+            NaiveDate::parse_from_str(&format!("{month}-01"), "%Y-%m-%d")
+                .ok()?
+                .checked_add_months(Months::new(1))
+        }
+        _ => None,
+    }
+}
+
+/// Whether the days before `end` are over and `settlement_hours` have
+/// passed since, so CE no longer restates them.
+fn finalized(end: NaiveDate, settlement_hours: i64, now: DateTime<Utc>) -> bool {
+    end.and_time(chrono::NaiveTime::MIN).and_utc() + Duration::hours(settlement_hours) <= now
+}
+
+fn http_date(at: DateTime<Utc>) -> HeaderValue {
+    HeaderValue::from_str(&at.format("%a, %d %b %Y %H:%M:%S GMT").to_string())
+        .expect("a formatted date is a valid header value")
+}
+
+pub(crate) fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/html"))
+}
+
+/// A strong tag from the first 128 bits of the SHA-256 of `inputs`.
+fn tag(inputs: &[u8]) -> HeaderValue {
+    let digest = Sha256::digest(inputs);
+    let tag = format!("\"{}\"", URL_SAFE_NO_PAD.encode(&digest[..16]));
+    HeaderValue::from_str(&tag).expect("base64 is a valid header value")
+}
+
+/// Whether an `If-None-Match` list names `tag`; weak tags compare by their
+/// opaque part, as `If-None-Match` uses the weak comparison.
+fn matches(if_none_match: &HeaderValue, tag: &HeaderValue) -> bool {
+    let Ok(list) = if_none_match.to_str() else {
+        return false;
+    };
+    let Ok(tag) = tag.to_str() else {
+        return false;
+    };
+    list.split(',').map(str::trim).any(|candidate| {
+        candidate == "*" || candidate.strip_prefix("W/").unwrap_or(candidate) == tag
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_pages_of_known_ranges_are_cached() {
+        let day = |s: &str| s.parse::<NaiveDate>().unwrap();
+        let end = day("2025-03-15");
+        assert_eq!(covered_end("/costs/daily", end), Some(end));
+        assert_eq!(covered_end("/costs/daily/stacked", end), Some(end));
+        assert_eq!(
+            covered_end("/costs/daily/2025-01-31/users", end),
+            Some(day("2025-02-01"))
+        );
+        assert_eq!(
+            covered_end("/costs/monthly/2024-12/models", end),
+            Some(day("2025-01-01"))
+        );
+        assert_eq!(covered_end("/costs/daily/today", end), None);
+        assert_eq!(covered_end("/users", end), None);
+        assert_eq!(covered_end("/", end), None);
+    }
+
+    #[test]
+    fn ranges_are_final_once_settled() {
+        let end = "2025-03-15".parse::<NaiveDate>().unwrap();
+        let at = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        assert!(!finalized(end, 72, at("2025-03-17T23:59:59Z")));
+        assert!(finalized(end, 72, at("2025-03-18T00:00:00Z")));
+        assert_eq!(
+            http_date(at("2025-03-18T06:05:04Z")),
+            "Tue, 18 Mar 2025 06:05:04 GMT"
+        );
+    }
+
+    #[test]
+    fn tags_follow_their_inputs() {
+        assert_eq!(tag(b"<p>1</p>"), tag(b"<p>1</p>"));
+        assert_ne!(tag(b"<p>1</p>"), tag(b"<p>2</p>"));
+        assert!(tag(b"").to_str().unwrap().starts_with('"'));
+    }
+
+    #[test]
+    fn if_none_match_lists() {
+        let tag = tag(b"page");
+        let current = tag.to_str().unwrap();
+        let header = |s: &str| HeaderValue::from_str(s).unwrap();
+        assert!(matches(&header(current), &tag));
+        assert!(matches(&header(&format!("\"old\", W/{current}")), &tag));
+        assert!(matches(&header("*"), &tag));
+        assert!(!matches(&header("\"old\""), &tag));
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+use common::{Amount, CostByModel, CostByUser, CostMatrix, CostRecord, Markup};
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+
+use crate::pages::{change_percent, models, users};
+
+/// Builds the period workbook with Daily, By User and By Model sheets.
+/// `charged` is the configured markup and the charged cost by user it
+/// gives, which add a charged cost column to the By User and By Model
+/// sheets.
+pub fn cost_workbook(
+    daily: &[CostRecord],
+    by_user: &[CostByUser],
+    by_model: &[CostByModel],
+    charged: Option<(&Markup, &HashMap<String, Amount>)>,
+) -> Result<Vec<u8>, XlsxError> {
+    let header = Format::new().set_bold();
+    let money = Format::new().set_num_format("#,##0.00");
+    let date = Format::new().set_num_format("yyyy-mm-dd");
+    let mut workbook = Workbook::new();
+
+    let sheet = workbook.add_worksheet().set_name("Daily")?;
+    write_header(sheet, &["Date", "Cost", "Currency"], &header)?;
+    for (i, r) in daily.iter().enumerate() {
+        let row = i as u32 + 1;
+        match NaiveDate::parse_from_str(&r.date, "%Y-%m-%d") {
+            Ok(d) => sheet.write_date_with_format(row, 0, &d, &date)?,
+            Err(_) => sheet.write_string(row, 0, &r.date)?,
+        };
+        sheet.write_number_with_format(row, 1, r.amount.to_f64(), &money)?;
+        sheet.write_string(row, 2, &r.currency)?;
+    }
+    sheet.set_column_width(0, 12)?;
+
+    let sheet = workbook.add_worksheet().set_name("By User")?;
+    write_header(
+        sheet,
+        &cost_header(&["User ID", "Email"], charged.is_some()),
+        &header,
+    )?;
+    for (i, c) in by_user.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &c.user_id)?;
+        sheet.write_string(row, 1, c.user_email.as_deref().unwrap_or(""))?;
+        sheet.write_number_with_format(row, 2, c.amount.to_f64(), &money)?;
+        let mut col = 3;
+        if let Some((_, users)) = charged {
+            let amount = users.get(&c.user_id).copied().unwrap_or_default();
+            sheet.write_number_with_format(row, col, amount.to_f64(), &money)?;
+            col += 1;
+        }
+        sheet.write_string(row, col, &c.currency)?;
+    }
+    sheet.set_column_width(0, 38)?;
+    sheet.set_column_width(1, 30)?;
+
+    let sheet = workbook.add_worksheet().set_name("By Model")?;
+    write_header(
+        sheet,
+        &cost_header(&["Model ID", "Model"], charged.is_some()),
+        &header,
+    )?;
+    for (i, c) in by_model.iter().enumerate() {
+        let row = i as u32 + 1;
+        sheet.write_string(row, 0, &c.model_id)?;
+        sheet.write_string(row, 1, c.model_name.as_deref().unwrap_or(""))?;
+        sheet.write_number_with_format(row, 2, c.amount.to_f64(), &money)?;
+        let mut col = 3;
+        if let Some((markup, _)) = charged {
+            let amount = markup.charged(&c.model_id, c.amount);
+            sheet.write_number_with_format(row, col, amount.to_f64(), &money)?;
+            col += 1;
+        }
+        sheet.write_string(row, col, &c.currency)?;
+    }
+    sheet.set_column_width(0, 38)?;
+    sheet.set_column_width(1, 30)?;
+
+    workbook.save_to_buffer()
+}
+
+/// User × model grid as CSV, with a total column and a total row.
+pub fn matrix_csv(matrix: &CostMatrix) -> String {
+    let mut header = vec!["User ID".to_string(), "User".to_string()];
+    header.extend(
+        matrix
+            .models
+            .iter()
+            .map(|(id, name)| name.clone().unwrap_or_else(|| id.clone())),
+    );
+    header.push("Total".to_string());
+    let mut lines = vec![csv_line(&header)];
+
+    for ((user_id, email), (row, total)) in matrix
+        .users
+        .iter()
+        .zip(matrix.cells.iter().zip(matrix.user_totals()))
+    {
+        let mut fields = vec![user_id.clone(), email.clone().unwrap_or_default()];
+        fields.extend(row.iter().map(|a| format!("{:.2}", a)));
+        fields.push(format!("{:.2}", total));
+        lines.push(csv_line(&fields));
+    }
+
+    let mut fields = vec!["Total".to_string(), String::new()];
+    fields.extend(matrix.model_totals().iter().map(|a| format!("{:.2}", a)));
+    fields.push(format!("{:.2}", matrix.total()));
+    lines.push(csv_line(&fields));
+    lines.join("\r\n") + "\r\n"
+}
+
+/// The users index as CSV, one line per row across all pages; `compare`
+/// adds the previous-period columns and `charged` the charged cost.
+pub fn users_csv(rows: &[users::IndexRow], compare: bool, charged: bool) -> String {
+    let mut header: Vec<String> = cost_header(&["User ID", "Email"], charged)
+        .into_iter()
+        .chain(["API Keys", "Profiles"])
+        .map(String::from)
+        .collect();
+    if compare {
+        header.extend(COMPARE_HEADER.map(String::from));
+    }
+    let mut lines = vec![csv_line(&header)];
+    for r in rows {
+        let mut fields = vec![r.user_id.clone(), r.display.clone()];
+        fields.extend(cost_fields(
+            r.cost,
+            r.charged.filter(|_| charged),
+            &r.currency,
+        ));
+        fields.extend([r.api_keys.clone(), r.profiles.to_string()]);
+        if compare {
+            fields.extend(change_fields(r.cost, r.previous));
+        }
+        lines.push(csv_line(&fields));
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+/// The models index as CSV, one line per row across all pages; `compare`
+/// adds the previous-period columns and `charged` the charged cost.
+pub fn models_csv(rows: &[models::IndexRow], compare: bool, charged: bool) -> String {
+    let mut header: Vec<String> = cost_header(&["Model ID", "Name"], charged)
+        .into_iter()
+        .chain(["Status", "Protected", "Users"])
+        .map(String::from)
+        .collect();
+    if compare {
+        header.extend(COMPARE_HEADER.map(String::from));
+    }
+    let mut lines = vec![csv_line(&header)];
+    for r in rows {
+        let mut fields = vec![r.model_id.clone(), r.display.clone()];
+        fields.extend(cost_fields(
+            r.cost,
+            r.charged.filter(|_| charged),
+            &r.currency,
+        ));
+        fields.extend([
+            r.status.clone(),
+            r.protected.to_string(),
+            r.user_count.to_string(),
+        ]);
+        if compare {
+            fields.extend(change_fields(r.cost, r.previous));
+        }
+        lines.push(csv_line(&fields));
+    }
+    lines.join("\r\n") + "\r\n"
+}
+
+const COMPARE_HEADER: [&str; 3] = ["Previous", "Change", "Change %"];
+
+/// `leading` columns, then the cost and currency columns; `charged` splits
+/// the cost into raw and charged.
+fn cost_header<'a>(leading: &[&'a str], charged: bool) -> Vec<&'a str> {
+    let cost: &[&str] = if charged {
+        &["Raw Cost", "Charged Cost", "Currency"]
+    } else {
+        &["Cost", "Currency"]
+    };
+    leading.iter().chain(cost).copied().collect()
+}
+
+/// Cost, charged cost if any and currency, matching [`cost_header`].
+fn cost_fields(cost: Amount, charged: Option<Amount>, currency: &str) -> Vec<String> {
+    let mut fields = vec![format!("{:.2}", cost)];
+    fields.extend(charged.map(|c| format!("{:.2}", c)));
+    fields.push(currency.to_string());
+    fields
+}
+
+/// Previous, change and change % as plain numbers; change % is empty when
+/// the previous period had no cost.
+fn change_fields(current: Amount, previous: Amount) -> [String; 3] {
+    [
+        format!("{:.2}", previous),
+        format!("{:.2}", current - previous),
+        change_percent(current, previous)
+            .map(|p| format!("{:.1}", p))
+            .unwrap_or_default(),
+    ]
+}
+
+/// Quotes fields containing separators, quotes or line breaks.
+fn csv_line(fields: &[String]) -> String {
+    fields
+        .iter()
+        .map(|f| {
+            if f.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", f.replace('"', "\"\""))
+            } else {
+                f.clone()
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn write_header(sheet: &mut Worksheet, titles: &[&str], format: &Format) -> Result<(), XlsxError> {
+    for (col, title) in titles.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, *title, format)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cost_workbook_is_xlsx() {
+        let daily = vec![CostRecord {
+            date: "2024-01-15".to_string(),
+            amount: Amount::from_f64(12.34),
+            currency: "USD".to_string(),
+        }];
+        let by_user = vec![CostByUser {
+            user_id: "abc-123".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            amount: Amount::from_f64(12.34),
+            currency: "USD".to_string(),
+        }];
+        let bytes = cost_workbook(&daily, &by_user, &[], None).unwrap();
+        // xlsx files are zip archives
+        assert!(bytes.starts_with(b"PK"));
+
+        let charged = [("abc-123".to_string(), Amount::from_f64(13.57))]
+            .into_iter()
+            .collect();
+        let markup = Markup {
+            percent: 10.0,
+            ..Markup::default()
+        };
+        let bytes = cost_workbook(&daily, &by_user, &[], Some((&markup, &charged))).unwrap();
+        assert!(bytes.starts_with(b"PK"));
+    }
+
+    #[test]
+    fn matrix_csv_has_totals_and_quotes() {
+        let mut matrix = CostMatrix::from_entries(vec![
+            ("u1".to_string(), "m1".to_string(), Amount::from_f64(1.5), "USD".to_string()),
+            ("u1".to_string(), "m2".to_string(), Amount::from_f64(2.0), "USD".to_string()),
+        ]);
+        matrix.users[0].1 = Some("alice@example.com".to_string());
+        matrix.models[0].1 = Some("claude, sonnet".to_string());
+        let csv = matrix_csv(&matrix);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "User ID,User,\"claude, sonnet\",m1,Total");
+        assert_eq!(lines[1], "u1,alice@example.com,2.00,1.50,3.50");
+        assert_eq!(lines[2], "Total,,2.00,1.50,3.50");
+    }
+
+    #[test]
+    fn users_csv_has_every_filtered_row_in_order() {
+        let costs: Vec<CostByUser> = (1..=60)
+            .map(|i| CostByUser {
+                user_id: format!("u{i}"),
+                user_email: Some(format!("user{i}@example.com")),
+                amount: Amount::from_f64(i as f64),
+                currency: "USD".to_string(),
+            })
+            .collect();
+        let filter = crate::pages::IndexFilter::parse(Some("10"), None, None);
+        let rows = users::index_rows(&[], &costs, None, Some(1), "desc", &filter, None);
+        let csv = users_csv(&rows, false, false);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "User ID,Email,Cost,Currency,API Keys,Profiles");
+        assert_eq!(lines[1], "u60,user60@example.com,60.00,USD,-,0");
+        assert_eq!(lines.last(), Some(&"u10,user10@example.com,10.00,USD,-,0"));
+        assert_eq!(lines.len(), 52);
+    }
+
+    #[test]
+    fn models_csv_adds_comparison_columns() {
+        let costs = vec![CostByModel {
+            model_id: "m1".to_string(),
+            model_name: Some("Claude".to_string()),
+            amount: Amount::from_f64(3.0),
+            currency: "USD".to_string(),
+        }];
+        let previous = vec![CostByModel {
+            amount: Amount::from_f64(2.0),
+            ..costs[0].clone()
+        }];
+        let filter = crate::pages::IndexFilter::default();
+        let rows = models::index_rows(&[], &costs, Some(&previous), None, "asc", &filter, None);
+        let csv = models_csv(&rows, true, false);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].ends_with(",Previous,Change,Change %"));
+        assert_eq!(lines[1], "m1,Claude,3.00,USD,-,false,0,2.00,1.00,50.0");
+    }
+
+    #[test]
+    fn index_csvs_split_raw_and_charged_cost() {
+        let costs = vec![CostByModel {
+            model_id: "m1".to_string(),
+            model_name: Some("Claude".to_string()),
+            amount: Amount::from_f64(3.0),
+            currency: "USD".to_string(),
+        }];
+        let markup = Markup {
+            percent: 10.0,
+            ..Markup::default()
+        };
+        let filter = crate::pages::IndexFilter::default();
+        let rows = models::index_rows(&[], &costs, None, None, "asc", &filter, Some(&markup));
+        let csv = models_csv(&rows, false, true);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "Model ID,Name,Raw Cost,Charged Cost,Currency,Status,Protected,Users"
+        );
+        assert_eq!(lines[1], "m1,Claude,3.00,3.30,USD,-,false,0");
+
+        let costs = vec![CostByUser {
+            user_id: "u1".to_string(),
+            user_email: Some("user1@example.com".to_string()),
+            amount: Amount::from_f64(3.0),
+            currency: "USD".to_string(),
+        }];
+        let charged = [("u1".to_string(), Amount::from_f64(3.45))]
+            .into_iter()
+            .collect();
+        let rows = users::index_rows(&[], &costs, None, None, "asc", &filter, Some(&charged));
+        let csv = users_csv(&rows, false, true);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "User ID,Email,Raw Cost,Charged Cost,Currency,API Keys,Profiles"
+        );
+        assert_eq!(lines[1], "u1,user1@example.com,3.00,3.45,USD,-,0");
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::{header, HeaderMap};
+use axum::middleware::Next;
+use axum::response::Response;
+use myhandlers::Origin;
+use serde::Deserialize;
+
+use crate::handlers::AppState;
+
+/// An address or CIDR block, e.g. `10.0.0.0/8`, from `trusted_proxies`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct IpRange {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IpRange {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.addr, ip.to_canonical()) {
+            (IpAddr::V4(net), IpAddr::V4(ip)) => {
+                masked(net.to_bits().into(), self.prefix + 96)
+                    == masked(ip.to_bits().into(), self.prefix + 96)
+            }
+            (IpAddr::V6(net), IpAddr::V6(ip)) => {
+                masked(net.to_bits(), self.prefix) == masked(ip.to_bits(), self.prefix)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Keeps the top `prefix` bits of a 128-bit address.
+fn masked(bits: u128, prefix: u8) -> u128 {
+    match prefix {
+        0 => 0,
+        p => bits & (u128::MAX << (128 - u32::from(p))),
+    }
+}
+
+impl FromStr for IpRange {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr: IpAddr = addr
+            .trim()
+            .parse()
+            .map_err(|_| format!("invalid proxy address {s:?}"))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix
+                .trim()
+                .parse::<u8>()
+                .ok()
+                .filter(|p| *p <= max)
+                .ok_or_else(|| format!("invalid prefix length in {s:?}"))?,
+            None => max,
+        };
+        Ok(Self { addr, prefix })
+    }
+}
+
+impl TryFrom<String> for IpRange {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Who sent a request and how they reached the server, trusting
+/// `X-Forwarded-*` headers only when the connection comes from a trusted
+/// proxy.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClientInfo {
+    pub ip: Option<IpAddr>,
+    /// `http` or `https`.
+    pub scheme: String,
+    pub host: Option<String>,
+}
+
+impl ClientInfo {
+    pub fn resolve(headers: &HeaderMap, peer: Option<IpAddr>, trusted: &[IpRange]) -> Self {
+        let is_trusted = |ip: IpAddr| trusted.iter().any(|range| range.contains(ip));
+        let host = header_value(headers, header::HOST.as_str());
+        if !peer.is_some_and(is_trusted) {
+            return Self {
+                ip: peer,
+                scheme: "http".to_string(),
+                host,
+            };
+        }
+
+        // Each proxy appends the address it received the request from, so
+        // the client is the rightmost address not belonging to a proxy.
+        let chain: Vec<IpAddr> = headers
+            .get_all("x-forwarded-for")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map_while(|addr| addr.trim().parse().ok())
+            .collect();
+        let ip = chain
+            .iter()
+            .rev()
+            .find(|ip| !is_trusted(**ip))
+            .or(chain.first())
+            .copied()
+            .or(peer);
+        let scheme = match header_value(headers, "x-forwarded-proto")
+            .map(|proto| proto.to_ascii_lowercase())
+            .as_deref()
+        {
+            Some("https") => "https",
+            _ => "http",
+        };
+        Self {
+            ip,
+            scheme: scheme.to_string(),
+            host: header_value(headers, "x-forwarded-host").or(host),
+        }
+    }
+
+    /// `scheme://host`, when the request named its host.
+    pub fn origin(&self) -> Option<Origin> {
+        let host = self.host.as_ref()?;
+        Some(Origin(format!("{}://{}", self.scheme, host)))
+    }
+}
+
+impl fmt::Display for ClientInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.ip {
+            Some(ip) => write!(f, "{ip}"),
+            None => f.write_str("unknown address"),
+        }
+    }
+}
+
+/// First comma-separated value of a header.
+fn header_value(headers: &HeaderMap, name: &str) -> Option<String> {
+    let value = headers.get(name)?.to_str().ok()?;
+    let first = value.split(',').next()?.trim();
+    (!first.is_empty()).then(|| first.to_string())
+}
+
+/// Adds the request's [`ClientInfo`] and [`Origin`] to its extensions.
+pub async fn client_info(State(state): State<AppState>, mut req: Request, next: Next) -> Response {
+    let peer = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    let info = ClientInfo::resolve(req.headers(), peer, &state.config.load().trusted_proxies);
+    if let Some(origin) = info.origin() {
+        req.extensions_mut().insert(origin);
+    }
+    req.extensions_mut().insert(info);
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn ranges_match_addresses() {
+        let range: IpRange = "10.0.0.0/8".parse().unwrap();
+        assert!(range.contains(ip("10.1.2.3")));
+        assert!(range.contains(ip("::ffff:10.1.2.3")));
+        assert!(!range.contains(ip("11.0.0.1")));
+        let single: IpRange = "192.168.1.10".parse().unwrap();
+        assert!(single.contains(ip("192.168.1.10")));
+        assert!(!single.contains(ip("192.168.1.11")));
+        let v6: IpRange = "fd00::/8".parse().unwrap();
+        assert!(v6.contains(ip("fd12::1")));
+        assert!("10.0.0.0/33".parse::<IpRange>().is_err());
+        assert!("proxy".parse::<IpRange>().is_err());
+    }
+
+    #[test]
+    fn untrusted_peers_forwarded_headers_are_ignored() {
+        let headers = headers(&[
+            ("host", "cost.internal:8080"),
+            ("x-forwarded-for", "203.0.113.7"),
+            ("x-forwarded-proto", "https"),
+        ]);
+        let info = ClientInfo::resolve(&headers, Some(ip("198.51.100.1")), &[]);
+        assert_eq!(info.ip, Some(ip("198.51.100.1")));
+        assert_eq!(
+            info.origin(),
+            Some(Origin("http://cost.internal:8080".to_string()))
+        );
+    }
+
+    #[test]
+    fn trusted_proxies_forwarded_headers_are_used() {
+        let trusted = ["10.0.0.0/8".parse().unwrap()];
+        let headers = headers(&[
+            ("host", "cost.internal:8080"),
+            ("x-forwarded-for", "1.2.3.4, 203.0.113.7, 10.0.0.5"),
+            ("x-forwarded-proto", "HTTPS"),
+            ("x-forwarded-host", "costs.example.com"),
+        ]);
+        let info = ClientInfo::resolve(&headers, Some(ip("10.0.0.9")), &trusted);
+        assert_eq!(info.ip, Some(ip("203.0.113.7")));
+        assert_eq!(
+            info.origin(),
+            Some(Origin("https://costs.example.com".to_string()))
+        );
+        assert_eq!(info.to_string(), "203.0.113.7");
+    }
+}
@@ -0,0 +1,96 @@
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use common::DataFreshness;
+
+use crate::etag::is_html;
+use crate::handlers::AppState;
+use crate::service::DataSource;
+
+fn format_time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// The footer text, e.g. "2025-03-11 06:00 UTC (rollup refreshed
+/// 2025-03-11 09:30 UTC)".
+fn updated(freshness: &DataFreshness) -> String {
+    let batch_run = freshness
+        .batch_run
+        .map_or_else(|| "never".to_string(), format_time);
+    match freshness.rollup_refresh {
+        Some(at) => format!("{} (rollup refreshed {})", batch_run, format_time(at)),
+        None => batch_run,
+    }
+}
+
+fn stale_warning(freshness: &DataFreshness, max_age_hours: i64) -> String {
+    match freshness.batch_run {
+        Some(_) => format!(
+            "Cost data has not been updated for over {} hours; the batch job may be failing.",
+            max_age_hours
+        ),
+        None => "No batch run has stored cost data yet.".to_string(),
+    }
+}
+
+/// Adds when the stored cost data was last updated to every rendered page,
+/// with a banner once it is older than `stale_data_hours`. Pages read live
+/// from CE have nothing stored to date.
+pub async fn freshness(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let response = next.run(req).await;
+    let config = state.config.load();
+    if response.status() != StatusCode::OK
+        || !is_html(response.headers())
+        || config.data_source == DataSource::Ce
+    {
+        return response;
+    }
+    let freshness = match state.service.data_freshness().await {
+        Ok(freshness) => freshness,
+        Err(e) => {
+            log::warn!("{e:#}");
+            return response;
+        }
+    };
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::error!("Failed to read a rendered page: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Ok(page) = std::str::from_utf8(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let stale = freshness
+        .is_stale(Utc::now(), config.stale_data_hours)
+        .then(|| stale_warning(&freshness, config.stale_data_hours));
+    let page = templates::add_freshness(page, &updated(&freshness), stale.as_deref());
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn updated_names_both_refreshes() {
+        let mut freshness = DataFreshness::default();
+        assert_eq!(updated(&freshness), "never");
+        freshness.batch_run = Some(Utc.with_ymd_and_hms(2025, 3, 11, 6, 0, 0).unwrap());
+        assert_eq!(updated(&freshness), "2025-03-11 06:00 UTC");
+        freshness.rollup_refresh = Some(Utc.with_ymd_and_hms(2025, 3, 11, 9, 30, 0).unwrap());
+        assert_eq!(
+            updated(&freshness),
+            "2025-03-11 06:00 UTC (rollup refreshed 2025-03-11 09:30 UTC)"
+        );
+        assert!(stale_warning(&freshness, 36).contains("over 36 hours"));
+    }
+}
@@ -0,0 +1,399 @@
+//! GraphQL schema for reporting queries the pages do not answer, served at
+//! `/graphql` behind the same login and share links as the pages. Date
+//! ranges are `[start, end)`.
+
+use std::sync::LazyLock;
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Error, Object, Result, Schema, SimpleObject,
+};
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use chrono::NaiveDate;
+use common::{CostRecord, Metric};
+use tower_sessions::Session;
+
+use crate::handlers::{require_login, AppState, CurrentUser};
+
+/// Deepest nesting a query may use, so one request cannot fan out into a
+/// cost query per user per model per user.
+const MAX_DEPTH: usize = 6;
+
+pub type CostSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+static SCHEMA: LazyLock<CostSchema> = LazyLock::new(|| {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .finish()
+});
+
+/// Who is asking, and the state to answer from.
+struct Viewer {
+    user: CurrentUser,
+    /// The viewer's gateway user id, for roles that only see their own costs.
+    user_id: Option<String>,
+    state: AppState,
+}
+
+impl Viewer {
+    fn require_all_costs(&self) -> Result<()> {
+        if self.user.role.sees_all_costs() {
+            Ok(())
+        } else {
+            Err(forbidden())
+        }
+    }
+
+    fn require_user(&self, user_id: &str) -> Result<()> {
+        if self.user.role.sees_all_users() || self.user_id.as_deref() == Some(user_id) {
+            Ok(())
+        } else {
+            Err(forbidden())
+        }
+    }
+
+    /// `metric` by its CE name, or the configured default.
+    fn metric(&self, metric: Option<String>) -> Result<Metric> {
+        match metric {
+            Some(metric) => Ok(metric.parse::<Metric>()?),
+            None => Ok(self.state.config.load().metric),
+        }
+    }
+}
+
+fn forbidden() -> Error {
+    Error::new("forbidden for your role")
+}
+
+fn viewer<'a>(ctx: &Context<'a>) -> &'a Viewer {
+    ctx.data_unchecked::<Viewer>()
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+enum Granularity {
+    Daily,
+    /// Weeks starting on Monday.
+    Weekly,
+    Monthly,
+}
+
+/// Total cost of one day, week or month, dated by its first day.
+#[derive(SimpleObject)]
+struct Cost {
+    date: String,
+    amount: f64,
+    currency: String,
+}
+
+fn costs(records: Vec<CostRecord>) -> Vec<Cost> {
+    records
+        .into_iter()
+        .map(|r| Cost {
+            date: r.date,
+            amount: r.amount.to_f64(),
+            currency: r.currency,
+        })
+        .collect()
+}
+
+/// Tokens one model used on one day, from CE.
+#[derive(SimpleObject)]
+struct TokenUsage {
+    date: NaiveDate,
+    model_id: String,
+    input_tokens: i64,
+    output_tokens: i64,
+}
+
+/// A gateway user.
+struct User {
+    id: String,
+    email: Option<String>,
+}
+
+#[Object]
+impl User {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn email(&self) -> Option<&str> {
+        self.email.as_deref()
+    }
+
+    async fn costs(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        #[graphql(default_with = "Granularity::Daily")] granularity: Granularity,
+        metric: Option<String>,
+    ) -> Result<Vec<Cost>> {
+        let viewer = viewer(ctx);
+        let metric = viewer.metric(metric)?;
+        let service = &viewer.state.service;
+        let records = match granularity {
+            Granularity::Daily => {
+                service
+                    .get_daily_cost_for_user(start, end, &self.id, metric)
+                    .await?
+            }
+            Granularity::Weekly => {
+                service
+                    .get_weekly_cost_for_user(start, end, &self.id, metric)
+                    .await?
+            }
+            Granularity::Monthly => {
+                service
+                    .get_monthly_cost_for_user(start, end, &self.id, metric)
+                    .await?
+            }
+        };
+        Ok(costs(records))
+    }
+
+    /// The user's cost per model, highest first.
+    async fn models(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Option<String>,
+    ) -> Result<Vec<ModelCost>> {
+        let viewer = viewer(ctx);
+        let metric = viewer.metric(metric)?;
+        let costs = viewer
+            .state
+            .service
+            .get_cost_by_model_for_user(start, end, &self.id, metric)
+            .await?;
+        Ok(costs
+            .into_iter()
+            .map(|c| ModelCost {
+                model: Model {
+                    id: c.model_id,
+                    name: c.model_name,
+                },
+                amount: c.amount.to_f64(),
+                currency: c.currency,
+            })
+            .collect())
+    }
+}
+
+/// A gateway model.
+struct Model {
+    id: String,
+    name: Option<String>,
+}
+
+#[Object]
+impl Model {
+    async fn id(&self) -> &str {
+        &self.id
+    }
+
+    async fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Daily or monthly cost across all users; there is no weekly
+    /// breakdown per model.
+    async fn costs(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        #[graphql(default_with = "Granularity::Daily")] granularity: Granularity,
+        metric: Option<String>,
+    ) -> Result<Vec<Cost>> {
+        let viewer = viewer(ctx);
+        viewer.require_all_costs()?;
+        let metric = viewer.metric(metric)?;
+        let service = &viewer.state.service;
+        let records = match granularity {
+            Granularity::Daily => {
+                service
+                    .get_daily_cost_for_model(start, end, &self.id, metric)
+                    .await?
+            }
+            Granularity::Weekly => {
+                return Err(Error::new("weekly costs are not available per model"))
+            }
+            Granularity::Monthly => {
+                service
+                    .get_monthly_cost_for_model(start, end, &self.id, metric)
+                    .await?
+            }
+        };
+        Ok(costs(records))
+    }
+
+    /// The model's cost per user, highest first.
+    async fn users(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Option<String>,
+    ) -> Result<Vec<UserCost>> {
+        let viewer = viewer(ctx);
+        if !viewer.user.role.sees_all_users() {
+            return Err(forbidden());
+        }
+        let metric = viewer.metric(metric)?;
+        let costs = viewer
+            .state
+            .service
+            .get_cost_by_user_for_model(start, end, &self.id, metric)
+            .await?;
+        Ok(costs
+            .into_iter()
+            .map(|c| UserCost {
+                user: User {
+                    id: c.user_id,
+                    email: c.user_email,
+                },
+                amount: c.amount.to_f64(),
+                currency: c.currency,
+            })
+            .collect())
+    }
+}
+
+#[derive(SimpleObject)]
+struct ModelCost {
+    model: Model,
+    amount: f64,
+    currency: String,
+}
+
+#[derive(SimpleObject)]
+struct UserCost {
+    user: User,
+    amount: f64,
+    currency: String,
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The logged-in user, when they have a gateway account.
+    async fn me(&self, ctx: &Context<'_>) -> Option<User> {
+        let viewer = viewer(ctx);
+        viewer.user_id.clone().map(|id| User {
+            id,
+            email: Some(viewer.user.email.clone()),
+        })
+    }
+
+    async fn users(&self, ctx: &Context<'_>) -> Result<Vec<User>> {
+        let viewer = viewer(ctx);
+        if !viewer.user.role.sees_all_users() {
+            return Err(forbidden());
+        }
+        let users = viewer.state.service.list_users().await?;
+        Ok(users
+            .into_iter()
+            .map(|(id, email)| User {
+                id,
+                email: Some(email),
+            })
+            .collect())
+    }
+
+    async fn user(&self, ctx: &Context<'_>, id: String) -> Result<User> {
+        let viewer = viewer(ctx);
+        viewer.require_user(&id)?;
+        let email = viewer.state.service.get_user_email(&id).await;
+        Ok(User { id, email })
+    }
+
+    async fn models(&self, ctx: &Context<'_>) -> Result<Vec<Model>> {
+        let viewer = viewer(ctx);
+        viewer.require_all_costs()?;
+        let models = viewer.state.service.list_models().await?;
+        Ok(models
+            .into_iter()
+            .map(|(id, name)| Model {
+                id,
+                name: Some(name),
+            })
+            .collect())
+    }
+
+    async fn model(&self, ctx: &Context<'_>, id: String) -> Result<Model> {
+        let viewer = viewer(ctx);
+        viewer.require_all_costs()?;
+        let name = viewer.state.service.get_model_name(&id).await;
+        Ok(Model { id, name })
+    }
+
+    /// Total cost across users and models.
+    async fn costs(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+        #[graphql(default_with = "Granularity::Daily")] granularity: Granularity,
+        metric: Option<String>,
+    ) -> Result<Vec<Cost>> {
+        let viewer = viewer(ctx);
+        viewer.require_all_costs()?;
+        let metric = viewer.metric(metric)?;
+        let service = &viewer.state.service;
+        let records = match granularity {
+            Granularity::Daily => service.get_daily_cost(start, end, metric).await?,
+            Granularity::Weekly => service.get_weekly_cost(start, end, metric).await?,
+            Granularity::Monthly => service.get_monthly_cost(start, end, metric).await?,
+        };
+        Ok(costs(records))
+    }
+
+    /// Daily token usage per model.
+    async fn usage(
+        &self,
+        ctx: &Context<'_>,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<TokenUsage>> {
+        let viewer = viewer(ctx);
+        viewer.require_all_costs()?;
+        let usage = viewer
+            .state
+            .service
+            .get_token_usage_by_model(start, end)
+            .await?;
+        Ok(usage
+            .into_iter()
+            .map(|u| TokenUsage {
+                date: u.date,
+                model_id: u.model_id,
+                input_tokens: u.input_tokens,
+                output_tokens: u.output_tokens,
+            })
+            .collect())
+    }
+}
+
+/// Answers a GraphQL query sent by GET or POST; 401 without a login.
+pub async fn graphql(
+    session: Session,
+    State(state): State<AppState>,
+    request: GraphQLRequest,
+) -> Response {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(_) => return StatusCode::UNAUTHORIZED.into_response(),
+    };
+    let user_id = state.service.get_user_id_by_email(&user.email).await;
+    let viewer = Viewer {
+        user,
+        user_id,
+        state,
+    };
+    GraphQLResponse::from(SCHEMA.execute(request.into_inner().data(viewer)).await).into_response()
+}
@@ -1,17 +1,36 @@
-#[cfg(not(feature = "admin"))]
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
-use axum::extract::{Path, Query, State};
-#[cfg(not(feature = "admin"))]
-use axum::http::StatusCode;
+use arc_swap::ArcSwap;
+use axum::extract::{Form, Multipart, Path, Query, State};
+use axum::http::{header, HeaderMap, StatusCode, Uri};
+use axum::response::sse::{Event, KeepAlive, Sse};
 use axum::response::{Html, IntoResponse, Redirect, Response};
-use chrono::{Datelike, NaiveDate, Utc};
-use serde::Deserialize;
+use axum::Extension;
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use chrono_tz::Tz;
+use common::{
+    cost_by_family, cost_by_label, estimate_costs, labeled_ids, Amount, CostAdjustment,
+    CostByModel, CostByUser, CostRecord, Label, LabelTarget, Markup, Metric, ModelFamily,
+    ModelInfo, ModelPrice, ReportKind, ReportSchedule, UserInfo, AWS_SOURCE, CREDIT_SOURCE,
+};
+use myerrors::AppError;
+use myhandlers::{AuthProvider, GROUPS_KEY};
+use serde::{Deserialize, Serialize};
 use tower_sessions::Session;
+use uuid::Uuid;
 
+use crate::api;
+use crate::config::AppConfig;
+use crate::export;
+use crate::forwarded::ClientInfo;
 use crate::pages;
-use crate::service::CostService;
+use crate::preferences::{Preferences, PreferencesForm};
+use crate::reload;
+use crate::request_id;
+use crate::roles::Role;
+use crate::service::{moving_average, CostService, MOVING_AVERAGE_DAYS};
+use crate::share;
 
 pub async fn health_check(State(state): State<AppState>) -> Response {
     match state.service.health_check().await {
@@ -23,28 +42,118 @@ pub async fn health_check(State(state): State<AppState>) -> Response {
     }
 }
 
+/// Serves a built-in stylesheet or script. Its name changes with its
+/// content, so browsers may keep it indefinitely.
+pub async fn asset(Path(name): Path<String>) -> Response {
+    match templates::asset(&name) {
+        Some(asset) => (
+            [
+                (header::CONTENT_TYPE, asset.content_type),
+                (header::CACHE_CONTROL, "public, max-age=31536000, immutable"),
+            ],
+            asset.body,
+        )
+            .into_response(),
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
 #[derive(Clone)]
 pub struct AppState {
     pub service: Arc<dyn CostService>,
     pub base_path: String,
-    pub cognito_client_id: String,
-    pub cognito_client_secret: String,
-    pub cognito_domain: String,
-    pub cognito_redirect_uri: String,
-    pub cognito_region: String,
-    pub cognito_user_pool_id: String,
+    pub auth_provider: Arc<dyn AuthProvider>,
+    /// Swapped on reload; read the settings that may change while running
+    /// (default metric and period, time zone, fiscal calendar, admin group)
+    /// from here.
+    pub config: Arc<ArcSwap<AppConfig>>,
+    /// Services of the tenants besides the one `service` reads, by name;
+    /// their pages are served under [`crate::tenants::base_path`].
+    pub tenants: Arc<BTreeMap<String, Arc<dyn CostService>>>,
+}
+
+impl AppState {
+    /// This state with the service's cost queries narrowed to `source`.
+    pub fn with_source(&self, source: Option<&str>) -> Self {
+        Self {
+            service: self.service.with_source(source),
+            ..self.clone()
+        }
+    }
+
+    pub fn auth_state(&self) -> myhandlers::AppState {
+        myhandlers::AppState {
+            provider: self.auth_provider.clone(),
+        }
+    }
+}
+
+pub struct CurrentUser {
+    pub email: String,
+    pub role: Role,
+}
+
+/// Why a page could not be rendered: a malformed path parameter (400) or a
+/// failed upstream query (502).
+pub enum PageError {
+    BadRequest(String),
+    Upstream(anyhow::Error),
+}
+
+impl PageError {
+    fn invalid(what: &str, value: &str) -> Self {
+        Self::BadRequest(format!("Invalid {what}: {value:?}"))
+    }
+}
+
+impl IntoResponse for PageError {
+    fn into_response(self) -> Response {
+        let (status, message) = match self {
+            Self::BadRequest(message) => (StatusCode::BAD_REQUEST, message),
+            Self::Upstream(e) => {
+                log::error!("Page query failed: {:#}", e);
+                (StatusCode::BAD_GATEWAY, format!("{:#}", e))
+            }
+        };
+        (status, Html(pages::error::render(&message))).into_response()
+    }
+}
+
+impl<E> From<E> for PageError
+where
+    E: Into<anyhow::Error>,
+{
+    fn from(err: E) -> Self {
+        Self::Upstream(err.into())
+    }
 }
 
 #[derive(Deserialize)]
 pub struct PeriodParams {
     pub period: Option<String>,
     pub page: Option<usize>,
-    pub sort: Option<usize>,
+    pub sort: Option<String>,
     pub order: Option<String>,
+    /// Alias of `order`.
+    pub dir: Option<String>,
+    pub from: Option<String>,
+    pub compare: Option<String>,
+    pub metric: Option<String>,
+    /// Narrows cost figures to one source; empty for all sources.
+    pub source: Option<String>,
+    /// Sources with stored cost, for the source filter; never from the
+    /// query string.
+    #[serde(skip)]
+    pub sources: Vec<String>,
+    /// Filled from the user's preferences, never from the query string.
+    #[serde(skip)]
+    pub page_size: Option<usize>,
+    /// Filled from the user's preferences, never from the query string.
+    #[serde(skip)]
+    pub timezone: Option<Tz>,
 }
 
-fn resolve_period(period: &str) -> (NaiveDate, NaiveDate) {
-    let today = Utc::now().date_naive();
+pub(crate) fn resolve_period(period: &str, today: NaiveDate) -> (NaiveDate, NaiveDate) {
     match period {
         "7d" => {
             let start = today - chrono::Duration::days(6);
@@ -88,300 +197,1403 @@ fn snap_to_month_start(date: NaiveDate) -> NaiveDate {
     NaiveDate::from_ymd_opt(date.year(), date.month(), 1).unwrap_or(date)
 }
 
+fn snap_to_week_start(date: NaiveDate) -> NaiveDate {
+    date - chrono::Duration::days(date.weekday().num_days_from_monday() as i64)
+}
+
+/// The window of the same length immediately before `start..end`.
+fn previous_period(start: NaiveDate, end: NaiveDate) -> (NaiveDate, NaiveDate) {
+    (start - (end - start), start)
+}
+
+/// Month-aligned `previous_period` for the monthly view: as many whole months
+/// as `start..end` touches, ending where `start`'s month begins. Also returns
+/// the shift that lines the previous months up with the current ones.
+fn previous_months(start: NaiveDate, end: NaiveDate) -> (NaiveDate, NaiveDate, Months) {
+    let start = snap_to_month_start(start);
+    let last = end.pred_opt().unwrap_or(end).max(start);
+    let count = (last.year() - start.year()) * 12 + last.month() as i32 - start.month() as i32 + 1;
+    let months = Months::new(count as u32);
+    (start.checked_sub_months(months).unwrap_or(start), start, months)
+}
+
+/// Moves previous-period records onto the dates they are compared against,
+/// so pages can match rows by date.
+fn shift_records(
+    records: Vec<CostRecord>,
+    shift: impl Fn(NaiveDate) -> Option<NaiveDate>,
+) -> Vec<CostRecord> {
+    records
+        .into_iter()
+        .filter_map(|mut r| {
+            let date = NaiveDate::parse_from_str(&r.date, "%Y-%m-%d").ok()?;
+            r.date = shift(date)?.format("%Y-%m-%d").to_string();
+            Some(r)
+        })
+        .collect()
+}
+
+fn get_compare(params: &PeriodParams) -> bool {
+    params.compare.as_deref() == Some("prev")
+}
+
+/// A `?metric=` choice sticks for the rest of the session; otherwise the
+/// configured default applies.
+async fn get_metric(session: &Session, params: &PeriodParams, state: &AppState) -> Metric {
+    if let Some(metric) = params.metric.as_deref().and_then(|m| m.parse::<Metric>().ok()) {
+        if let Err(e) = session.insert("metric", metric).await {
+            log::warn!("Failed to store metric in session: {e}");
+        }
+        return metric;
+    }
+    match session.get::<Metric>("metric").await {
+        Ok(Some(metric)) => metric,
+        _ => state.config.load().metric,
+    }
+}
+
+/// Like the metric, a `?source=` choice sticks for the rest of the session;
+/// an empty one goes back to all sources.
+async fn get_source(session: &Session, source: Option<&str>) -> Option<String> {
+    if let Some(source) = source.map(str::trim) {
+        let result = if source.is_empty() {
+            session.remove::<String>("source").await.map(|_| ())
+        } else {
+            session.insert("source", source).await
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to store source in session: {e}");
+        }
+        return (!source.is_empty()).then(|| source.to_string());
+    }
+    session.get::<String>("source").await.ok().flatten()
+}
+
+/// Fills in the user's preferred period, or else the configured default,
+/// and page size where the request leaves them open, and the chosen source.
+async fn apply_preferences(
+    session: &Session,
+    state: &AppState,
+    mut params: PeriodParams,
+) -> PeriodParams {
+    let prefs = Preferences::load(session).await;
+    if params.period.is_none() {
+        params.period = prefs
+            .period
+            .or_else(|| Some(state.config.load().default_period.clone()));
+    }
+    params.page_size = prefs.page_size;
+    params.timezone = prefs.timezone.as_deref().and_then(|tz| tz.parse().ok());
+    params.source = get_source(session, params.source.as_deref()).await;
+    params.sources = state.service.list_sources().await.unwrap_or_else(|e| {
+        log::error!("Failed to list cost sources: {e:#}");
+        Vec::new()
+    });
+    params
+}
+
+/// The current date in the user's preferred time zone, or else the
+/// configured one, so periods end on the finance team's "today".
+fn today(params: &PeriodParams, state: &AppState) -> NaiveDate {
+    match params.timezone {
+        Some(tz) => Utc::now().with_timezone(&tz).date_naive(),
+        None => state.service.today(),
+    }
+}
+
 fn get_period(params: &PeriodParams) -> String {
     params.period.as_deref().unwrap_or("30d").to_string()
 }
 
+fn get_nav(params: &PeriodParams) -> pages::NavContext {
+    pages::NavContext::new(&get_period(params), params.from.as_deref())
+        .with_page_size(params.page_size.unwrap_or(pages::PAGE_SIZE))
+        .with_sort(params.sort.as_deref(), params.dir.as_deref().or(params.order.as_deref()))
+        .with_sources(params.source.as_deref(), &params.sources)
+}
+
 fn get_page(params: &PeriodParams) -> usize {
     params.page.unwrap_or(1).max(1)
 }
 
-fn get_sort(params: &PeriodParams) -> Option<usize> {
-    params.sort
+/// Filters of the users and models indexes.
+#[derive(Deserialize)]
+pub struct FilterParams {
+    pub min_cost: Option<String>,
+    pub status: Option<String>,
+    pub has_cost: Option<String>,
+    pub label: Option<String>,
+}
+
+fn get_filter(params: &FilterParams) -> pages::IndexFilter {
+    pages::IndexFilter::parse(
+        params.min_cost.as_deref(),
+        params.status.as_deref(),
+        params.has_cost.as_deref(),
+    )
+    .with_label(params.label.as_deref())
+}
+
+/// `?sort=` is a column index or one of the table's `columns` names.
+fn get_sort(params: &PeriodParams, columns: &[&str]) -> Option<usize> {
+    let sort = params.sort.as_deref()?.trim();
+    sort.parse()
+        .ok()
+        .or_else(|| columns.iter().position(|c| c.eq_ignore_ascii_case(sort)))
 }
 
 fn get_order(params: &PeriodParams) -> String {
     params
-        .order
+        .dir
         .as_deref()
+        .or(params.order.as_deref())
         .unwrap_or("asc")
         .to_string()
 }
 
-fn parse_month_range(month: &str) -> (NaiveDate, NaiveDate) {
-    let start_str = format!("{}-01", month);
-    let start =
-        NaiveDate::parse_from_str(&start_str, "%Y-%m-%d").unwrap_or_else(|_| Utc::now().date_naive());
-    let (y, m) = if start.month() == 12 {
-        (start.year() + 1, 1)
-    } else {
-        (start.year(), start.month() + 1)
+fn parse_month_range(month: &str) -> Option<(NaiveDate, NaiveDate)> {
+    let start = NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d").ok()?;
+    let next = start.checked_add_months(Months::new(1))?;
+    Some((start, next - chrono::Duration::days(1)))
+}
+
+/// `{month}` path segments are `YYYY-MM`.
+fn parse_month(month: &str) -> Result<(NaiveDate, NaiveDate), PageError> {
+    parse_month_range(month).ok_or_else(|| PageError::invalid("month", month))
+}
+
+fn parse_week_start(week: &str) -> Option<NaiveDate> {
+    let (year, week) = week.split_once("-W")?;
+    NaiveDate::from_isoywd_opt(year.parse().ok()?, week.parse().ok()?, chrono::Weekday::Mon)
+}
+
+/// `{week}` path segments are ISO weeks, `YYYY-Www`; returns Monday and Sunday.
+fn parse_week(week: &str) -> Result<(NaiveDate, NaiveDate), PageError> {
+    parse_week_start(week)
+        .map(|monday| (monday, monday + chrono::Duration::days(6)))
+        .ok_or_else(|| PageError::invalid("week", week))
+}
+
+/// `{date}` path segments are `YYYY-MM-DD`.
+fn parse_date(date: &str) -> Result<NaiveDate, PageError> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").map_err(|_| PageError::invalid("date", date))
+}
+
+/// User and model ids are gateway UUIDs; anything else would only reach CE
+/// filters and database lookups as garbage.
+fn check_id(what: &str, id: &str) -> Result<(), PageError> {
+    Uuid::parse_str(id)
+        .map(|_| ())
+        .map_err(|_| PageError::invalid(what, id))
+}
+
+async fn load_role(service: &dyn CostService, email: &str) -> Role {
+    match service.get_role(email).await {
+        Some(role) => role.parse().unwrap_or_else(|e| {
+            log::warn!("Ignoring role for {email}: {e}");
+            Role::default()
+        }),
+        None => Role::default(),
+    }
+}
+
+/// Members of the configured admin group are admins; everyone else gets
+/// their `roles` table entry.
+pub(crate) async fn login_role(session: &Session, state: &AppState, email: &str) -> Role {
+    if let Some(admin_group) = &state.config.load().admin_group {
+        let groups = session
+            .get::<Vec<String>>(GROUPS_KEY)
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or_default();
+        if groups.iter().any(|g| g == admin_group) {
+            return Role::Admin;
+        }
+    }
+    load_role(state.service.as_ref(), email).await
+}
+
+pub(crate) async fn require_login(
+    session: &Session,
+    state: &AppState,
+) -> Result<CurrentUser, Response> {
+    let email = match session.get::<String>("email").await {
+        Ok(Some(email)) => email,
+        _ => return Err(Redirect::to("/login").into_response()),
+    };
+    request_id::record_email(&email);
+    // Sessions created before roles were introduced have no role yet.
+    let role = match session.get::<Role>("role").await {
+        Ok(Some(role)) => role,
+        _ => {
+            let role = login_role(session, state, &email).await;
+            if let Err(e) = session.insert("role", role).await {
+                log::warn!("Failed to store role in session: {e}");
+            }
+            role
+        }
     };
-    let end = NaiveDate::from_ymd_opt(y, m, 1)
-        .unwrap_or(start) - chrono::Duration::days(1);
-    (start, end)
+    Ok(CurrentUser { email, role })
 }
 
-async fn require_login(session: &Session) -> Result<String, Response> {
-    match session.get::<String>("email").await {
-        Ok(Some(email)) => Ok(email),
-        _ => Err(Redirect::to("/login").into_response()),
+pub async fn callback(
+    uri: Uri,
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+) -> Result<Response, AppError> {
+    let origin = client.origin().map(Extension);
+    let response =
+        myhandlers::callback(uri, session.clone(), origin, State(state.auth_state())).await?;
+    if let Some(email) = session.get::<String>("email").await? {
+        let role = login_role(&session, &state, &email).await;
+        log::info!("{email} ({client}) logged in with role {}", role.as_str());
+        session.insert("role", role).await?;
     }
+    Ok(response)
 }
 
-#[cfg(not(feature = "admin"))]
 async fn resolve_current_user_id(service: &dyn CostService, email: &str) -> Option<String> {
     service.get_user_id_by_email(email).await
 }
 
+/// The CE credits and refunds of `[start, end)` for a summary page's gross
+/// and net totals, if it has any and shows every source.
+async fn period_credits(
+    state: &AppState,
+    source: Option<&str>,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+) -> Result<Option<Amount>, PageError> {
+    if source.is_some() {
+        return Ok(None);
+    }
+    let credits: Amount = state
+        .service
+        .with_source(Some(CREDIT_SOURCE))
+        .get_daily_cost(start, end, metric)
+        .await?
+        .iter()
+        .map(|r| r.amount)
+        .sum();
+    Ok((credits != Amount::ZERO).then_some(credits))
+}
+
 pub async fn render_home(
     session: Session,
     State(state): State<AppState>,
+    uri: Uri,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if !user.role.sees_all_costs() {
+        let path = pages::make_path(&state.base_path, "/me");
+        let location = match uri.query() {
+            Some(query) => format!("{path}?{query}"),
+            None => path,
+        };
+        return Ok(Redirect::to(&location).into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let (start, end) = resolve_period(&period);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let tz = params.timezone.unwrap_or(state.config.load().timezone);
+    let data_as_of = state
+        .service
+        .data_as_of(start, end, metric)
+        .await
+        .map(|at| at.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string());
 
-    #[cfg(feature = "admin")]
-    {
-        let daily_cost = state.service.get_daily_cost(start, end).await;
-        let monthly_cost = state.service.get_monthly_cost(snap_to_month_start(start), end).await;
-        let users = state.service.list_users().await;
-        let models = state.service.list_models().await;
+    let daily_cost = state.service.get_daily_cost(start, end, metric).await?;
+    let monthly_cost = state.service.get_monthly_cost(snap_to_month_start(start), end, metric).await?;
+    let users = state.service.list_users().await?;
+    let models = state.service.list_models().await?;
 
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
-        let currency = daily_cost
-            .first()
-            .map(|r| r.currency.as_str())
-            .unwrap_or("USD");
+    let credits = period_credits(&state, params.source.as_deref(), start, end, metric).await?;
 
-        Html(pages::home::render(
-            &state.base_path,
-            &period,
-            total_cost,
-            currency,
-            daily_cost.len(),
-            monthly_cost.len(),
-            users.len(),
-            models.len(),
-        ))
-        .into_response()
-    }
+    let total_cost: Amount = daily_cost.iter().map(|r| r.amount).sum();
+    let currency = daily_cost
+        .first()
+        .map(|r| r.currency.as_str())
+        .unwrap_or("USD");
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let daily_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_daily_cost_for_user(start, end, uid).await
-        } else {
-            vec![]
-        };
-        let monthly_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_monthly_cost_for_user(snap_to_month_start(start), end, uid).await
-        } else {
-            vec![]
-        };
-        let model_count = if let Some(ref uid) = current_user_id {
-            let costs = state
+    Ok(Html(pages::home::render(
+        &state.base_path,
+        &nav,
+        total_cost,
+        currency,
+        credits,
+        daily_cost.len(),
+        monthly_cost.len(),
+        users.len(),
+        models.len(),
+        user.role.sees_all_users(),
+        true,
+        metric,
+        data_as_of.as_deref(),
+        state.config.load().live_refresh_minutes > 0,
+    ))
+    .into_response())
+}
+
+/// The logged-in user's own costs: their daily trend, model mix and API
+/// keys. Users who only see their own costs land here instead of the home
+/// page.
+pub async fn render_my_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let tz = params.timezone.unwrap_or(state.config.load().timezone);
+    let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+    let (daily_cost, models, keys, data_as_of) = match &current_user_id {
+        Some(uid) => {
+            let daily_cost = state
                 .service
-                .get_cost_by_model_for_user(start, end, uid)
-                .await;
-            costs.len()
+                .get_daily_cost_for_user(start, end, uid, metric)
+                .await?;
+            let mut models = state
+                .service
+                .get_cost_by_model_for_user(start, end, uid, metric)
+                .await?;
+            models.sort_by(|a, b| b.amount.cmp(&a.amount));
+            let keys = state.service.list_api_keys_for_user(uid).await?;
+            let data_as_of = state
+                .service
+                .data_as_of(start, end, metric)
+                .await
+                .map(|at| at.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string());
+            (daily_cost, models, keys, data_as_of)
+        }
+        None => (vec![], vec![], vec![], None),
+    };
+
+    Ok(Html(pages::me::render(
+        &state.base_path,
+        &nav,
+        &user.email,
+        current_user_id.as_deref(),
+        &daily_cost,
+        &models,
+        &keys,
+        metric,
+        data_as_of.as_deref(),
+    ))
+    .into_response())
+}
+
+/// Totals on the home page that [`home_events`] keeps current.
+#[derive(Serialize, PartialEq)]
+struct LiveTotals {
+    total: String,
+    data_as_of: Option<String>,
+}
+
+/// One viewer's home page, recomputed on every tick of `interval`.
+struct HomeEvents {
+    state: AppState,
+    user: CurrentUser,
+    params: PeriodParams,
+    period: String,
+    metric: Metric,
+    interval: tokio::time::Interval,
+    last: Option<LiveTotals>,
+}
+
+impl HomeEvents {
+    async fn totals(&self) -> anyhow::Result<LiveTotals> {
+        let (start, end) = resolve_period(&self.period, today(&self.params, &self.state));
+        let service = self.state.service.as_ref();
+        let daily_cost = if self.user.role.sees_all_costs() {
+            service.get_daily_cost(start, end, self.metric).await?
         } else {
-            0
+            match resolve_current_user_id(service, &self.user.email).await {
+                Some(uid) => service.get_daily_cost_for_user(start, end, &uid, self.metric).await?,
+                None => vec![],
+            }
         };
-
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
+        let total: Amount = daily_cost.iter().map(|r| r.amount).sum();
         let currency = daily_cost
             .first()
             .map(|r| r.currency.as_str())
             .unwrap_or("USD");
+        let tz = self.params.timezone.unwrap_or(self.state.config.load().timezone);
+        let data_as_of = service
+            .data_as_of(start, end, self.metric)
+            .await
+            .map(|at| at.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string());
+        Ok(LiveTotals {
+            total: format!("{:.2} {}", total, currency),
+            data_as_of,
+        })
+    }
 
-        Html(pages::home::render(
-            &state.base_path,
-            &period,
-            total_cost,
-            currency,
-            daily_cost.len(),
-            monthly_cost.len(),
-            1,
-            model_count,
-        ))
-        .into_response()
+    /// Waits for totals that differ from the last ones sent.
+    async fn next(mut self) -> Option<(Result<Event, axum::Error>, Self)> {
+        loop {
+            self.interval.tick().await;
+            match self.totals().await {
+                Ok(totals) if self.last.as_ref() != Some(&totals) => {
+                    let event = Event::default().json_data(&totals);
+                    self.last = Some(totals);
+                    return Some((event, self));
+                }
+                Ok(_) => {}
+                Err(e) => log::warn!("Failed to refresh home page totals: {:#}", e),
+            }
+        }
     }
 }
 
-pub async fn render_daily_costs(
+/// Server-sent events with the home page's totals, checked every
+/// `live_refresh_minutes` and sent when they change, so a page left open
+/// stays current.
+pub async fn home_events(
     session: Session,
     State(state): State<AppState>,
     Query(params): Query<PeriodParams>,
 ) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
         Err(redirect) => return redirect,
     };
+    let minutes = state.config.load().live_refresh_minutes;
+    if minutes == 0 {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+    let period = get_period(&params);
+    let every = std::time::Duration::from_secs(minutes * 60);
+    let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + every, every);
+    interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+    let events = HomeEvents {
+        state,
+        user,
+        params,
+        period,
+        metric,
+        interval,
+        last: None,
+    };
+    Sse::new(futures_util::stream::unfold(events, HomeEvents::next))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+pub async fn export_xlsx(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, AppError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+    if !user.role.sees_all_users() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let period = get_period(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let daily_cost = state.service.get_daily_cost(start, end, metric).await?;
+    let by_user = state.service.get_cost_by_user(start, end, metric).await?;
+    let by_model = state.service.get_cost_by_model(start, end, metric).await?;
+    let markup = markup(&state);
+    let charged = charged_by_user(&state, start, end, metric).await?;
+    let bytes = export::cost_workbook(
+        &daily_cost,
+        &by_user,
+        &by_model,
+        markup.as_ref().zip(charged.as_ref()),
+    )?;
+
+    let disposition = format!("attachment; filename=\"cost_{}_{}.xlsx\"", start, end);
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet".to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        bytes,
+    )
+        .into_response())
+}
+
+pub async fn render_daily_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::DATE_SORT);
     let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
-
-    #[cfg(feature = "admin")]
-    {
-        let daily_cost = state.service.get_daily_cost(start, end).await;
-        let daily_cost = pages::sort_records(daily_cost, sort, &order);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+
+    let previous_range = get_compare(&params).then(|| previous_period(start, end));
+    let shift = |d: NaiveDate| Some(d + (end - start));
+
+    // The average reaches back before `start` so the first days are
+    // smoothed too.
+    let history_start = start - chrono::Duration::days(MOVING_AVERAGE_DAYS - 1);
+    let from_start = |d: NaiveDate| (d >= start).then_some(d);
+
+    if user.role.sees_all_costs() {
+        let history = state.service.get_daily_cost(history_start, end, metric).await?;
+        let average = moving_average(&history, start, MOVING_AVERAGE_DAYS);
+        let daily_cost = pages::sort_records(shift_records(history, from_start), sort, &order);
+        let previous = match previous_range {
+            Some((prev_start, prev_end)) => Some(shift_records(
+                state.service.get_daily_cost(prev_start, prev_end, metric).await?,
+                shift,
+            )),
+            None => None,
+        };
 
-        Html(pages::costs::render(
+        Ok(Html(pages::costs::render(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &daily_cost,
+            &average,
+            previous.as_deref(),
         ))
-        .into_response()
-    }
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let daily_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_daily_cost_for_user(start, end, uid).await
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let history = if let Some(ref uid) = current_user_id {
+            state.service.get_daily_cost_for_user(history_start, end, uid, metric).await?
         } else {
             vec![]
         };
-        let daily_cost = pages::sort_records(daily_cost, sort, &order);
+        let average = moving_average(&history, start, MOVING_AVERAGE_DAYS);
+        let daily_cost = pages::sort_records(shift_records(history, from_start), sort, &order);
+        let previous = match (previous_range, &current_user_id) {
+            (Some((prev_start, prev_end)), Some(uid)) => Some(shift_records(
+                state
+                    .service
+                    .get_daily_cost_for_user(prev_start, prev_end, uid, metric)
+                    .await?,
+                shift,
+            )),
+            (Some(_), None) => Some(vec![]),
+            (None, _) => None,
+        };
 
-        Html(pages::costs::render(
+        Ok(Html(pages::costs::render(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &daily_cost,
+            &average,
+            previous.as_deref(),
         ))
-        .into_response()
+        .into_response())
     }
 }
 
-pub async fn render_users(
+pub async fn render_daily_costs_by_model(
     session: Session,
     State(state): State<AppState>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let rows = state.service.get_daily_cost_by_model(start, end, metric).await?;
 
-    #[cfg(feature = "admin")]
-    {
-        let users_enriched = state.service.list_users_enriched().await;
-        let costs = state.service.get_cost_by_user(start, end).await;
+    Ok(Html(pages::stacked::render(&state.base_path, &nav, &rows)).into_response())
+}
 
-        Html(pages::users::render_index(
-            &state.base_path,
-            &period,
-            page,
-            &users_enriched,
-            &costs,
-            sort,
-            &order,
-        ))
-        .into_response()
+/// Histogram and percentiles of daily per-user cost; only users who see
+/// every user get the largest user-days named.
+pub async fn render_distribution(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
     }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let costs = state.service.get_cost_by_user(start, end).await;
-        let costs: Vec<_> = if let Some(ref uid) = current_user_id {
-            costs.into_iter().filter(|c| c.user_id == *uid).collect()
-        } else {
-            costs
-        };
-        let users_enriched = state.service.list_users_enriched().await;
-        let users_enriched: Vec<_> = if let Some(ref uid) = current_user_id {
-            users_enriched
-                .into_iter()
-                .filter(|u| u.user_id == *uid)
-                .collect()
-        } else {
-            users_enriched
-        };
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let rows = state.service.get_daily_cost_by_user(start, end, metric).await?;
 
-        Html(pages::users::render_index(
-            &state.base_path,
-            &period,
-            page,
-            &users_enriched,
-            &costs,
-            sort,
-            &order,
-        ))
-        .into_response()
-    }
+    Ok(Html(pages::distribution::render(
+        &state.base_path,
+        &nav,
+        &rows,
+        user.role.sees_all_users(),
+    ))
+    .into_response())
 }
 
-pub async fn render_models(
+pub async fn render_families(
     session: Session,
     State(state): State<AppState>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let costs = if user.role.sees_all_costs() {
+        state.service.get_cost_by_model(start, end, metric).await?
+    } else {
+        match resolve_current_user_id(state.service.as_ref(), &user.email).await {
+            Some(uid) => {
+                state
+                    .service
+                    .get_cost_by_model_for_user(start, end, &uid, metric)
+                    .await?
+            }
+            None => vec![],
+        }
+    };
+    let rules = state.service.list_model_families().await?;
 
-    #[cfg(feature = "admin")]
-    {
-        let models_enriched = state.service.list_models_enriched().await;
-        let costs = state.service.get_cost_by_model(start, end).await;
+    Ok(Html(pages::families::render(
+        &state.base_path,
+        &nav,
+        &cost_by_family(&rules, &costs),
+        user.role == Role::Admin,
+    ))
+    .into_response())
+}
 
-        Html(pages::models::render_index(
-            &state.base_path,
-            &period,
-            page,
-            &models_enriched,
-            &costs,
-            sort,
-            &order,
-        ))
-        .into_response()
+pub async fn render_label_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
     }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let costs = if let Some(ref uid) = current_user_id {
-            state
-                .service
-                .get_cost_by_model_for_user(start, end, uid)
-                .await
-        } else {
-            vec![]
-        };
-        // Filter models to only those the user has cost data for
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let users = state.service.get_cost_by_user(start, end, metric).await?;
+    let models = state.service.get_cost_by_model(start, end, metric).await?;
+    let labels = state.service.list_labels().await?;
+    let total: Amount = users.iter().map(|u| u.amount).sum();
+    let currency = users
+        .first()
+        .map(|u| u.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+
+    Ok(Html(pages::labels::render(
+        &state.base_path,
+        &nav,
+        &cost_by_label(
+            &labels,
+            LabelTarget::User,
+            users.iter().map(|u| (u.user_id.as_str(), u.amount)),
+        ),
+        &cost_by_label(
+            &labels,
+            LabelTarget::Model,
+            models.iter().map(|m| (m.model_id.as_str(), m.amount)),
+        ),
+        total,
+        &currency,
+        user.role == Role::Admin,
+    ))
+    .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct IdleParams {
+    pub days: Option<i64>,
+}
+
+pub async fn render_idle(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(idle): Query<IdleParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_users() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+
+    let days = idle.days.unwrap_or(pages::idle::DEFAULT_IDLE_DAYS);
+    if !(1..=366).contains(&days) {
+        return Err(PageError::invalid("days", &days.to_string()));
+    }
+    let end = today(&params, &state);
+    let start = end - chrono::Duration::days(days - 1);
+    let users = state.service.list_users_enriched().await?;
+    let models = state.service.list_models_enriched().await?;
+    let user_costs = state.service.get_cost_by_user(start, end, metric).await?;
+    let model_costs = state.service.get_cost_by_model(start, end, metric).await?;
+
+    Ok(Html(pages::idle::render(
+        &state.base_path,
+        days,
+        &pages::idle::idle_users(&users, &user_costs),
+        &pages::idle::idle_models(&models, &model_costs),
+    ))
+    .into_response())
+}
+
+pub async fn render_cost_matrix(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_users() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let matrix = state.service.get_cost_matrix(start, end, metric).await?;
+
+    Ok(Html(pages::matrix::render(&state.base_path, &nav, &matrix)).into_response())
+}
+
+pub async fn export_cost_matrix_csv(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, AppError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+    if !user.role.sees_all_users() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let period = get_period(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let matrix = state.service.get_cost_matrix(start, end, metric).await?;
+
+    let filename = format!("cost_matrix_{}_{}.csv", start, end);
+    Ok(csv_response(&filename, export::matrix_csv(&matrix)))
+}
+
+/// A CSV download named `filename`.
+fn csv_response(filename: &str, csv: String) -> Response {
+    let disposition = format!("attachment; filename=\"{}\"", filename);
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        csv,
+    )
+        .into_response()
+}
+
+pub async fn render_calendar(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+
+    let daily_cost = if user.role.sees_all_costs() {
+        state.service.get_daily_cost(start, end, metric).await?
+    } else {
+        match resolve_current_user_id(state.service.as_ref(), &user.email).await {
+            Some(uid) => state.service.get_daily_cost_for_user(start, end, &uid, metric).await?,
+            None => vec![],
+        }
+    };
+
+    Ok(Html(pages::calendar::render(&state.base_path, &nav, start, end, &daily_cost))
+        .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct FiscalParams {
+    pub year: Option<i32>,
+}
+
+pub async fn render_fiscal(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(fiscal): Query<FiscalParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let calendar = state.config.load().fiscal;
+    let year = fiscal
+        .year
+        .unwrap_or_else(|| calendar.year_of(today(&params, &state)));
+    let (start, end) = (calendar.year_start(year), calendar.year_start(year + 1));
+
+    let daily_cost = if user.role.sees_all_costs() {
+        state.service.get_daily_cost(start, end, metric).await?
+    } else {
+        match resolve_current_user_id(state.service.as_ref(), &user.email).await {
+            Some(uid) => state.service.get_daily_cost_for_user(start, end, &uid, metric).await?,
+            None => vec![],
+        }
+    };
+
+    Ok(Html(pages::fiscal::render(&state.base_path, &nav, &calendar, year, &daily_cost))
+        .into_response())
+}
+
+pub async fn render_users(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(filter): Query<FilterParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::users::INDEX_SORT);
+    let order = get_order(&params);
+    let filter = get_filter(&filter);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let index = users_index(
+        &state,
+        &user,
+        start,
+        end,
+        get_compare(&params),
+        metric,
+        filter.label.as_deref(),
+    )
+    .await?;
+
+    let charged = charged_by_user(&state, start, end, metric).await?;
+
+    Ok(Html(pages::users::render_index(
+        &state.base_path,
+        &nav,
+        page,
+        &index.items,
+        &index.costs,
+        index.previous.as_deref(),
+        sort,
+        &order,
+        &filter,
+        charged.as_ref(),
+    ))
+    .into_response())
+}
+
+/// The users index as CSV, with the page's filter, sort and comparison but
+/// every page of rows.
+pub async fn export_users_csv(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(filter): Query<FilterParams>,
+) -> Result<Response, AppError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let sort = get_sort(&params, pages::users::INDEX_SORT);
+    let order = get_order(&params);
+    let filter = get_filter(&filter);
+    let compare = get_compare(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let index = users_index(
+        &state,
+        &user,
+        start,
+        end,
+        compare,
+        metric,
+        filter.label.as_deref(),
+    )
+    .await?;
+    let charged = charged_by_user(&state, start, end, metric).await?;
+    let rows = pages::users::index_rows(
+        &index.items,
+        &index.costs,
+        index.previous.as_deref(),
+        sort,
+        &order,
+        &filter,
+        charged.as_ref(),
+    );
+
+    let filename = format!("cost_by_user_{}_{}.csv", start, end);
+    Ok(csv_response(
+        &filename,
+        export::users_csv(&rows, compare, charged.is_some()),
+    ))
+}
+
+/// `?page_size=` of the JSON listings; pages take theirs from preferences.
+#[derive(Deserialize)]
+pub struct PageSizeParams {
+    pub page_size: Option<usize>,
+}
+
+/// The users index as JSON, one page at a time, with the same filter, sort
+/// and comparison parameters as the page.
+pub async fn users_json(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(filter): Query<FilterParams>,
+    Query(size): Query<PageSizeParams>,
+) -> Result<Response, AppError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let page = get_page(&params);
+    let page_size = size
+        .page_size
+        .or(params.page_size)
+        .unwrap_or(pages::PAGE_SIZE)
+        .clamp(1, api::MAX_PAGE_SIZE);
+    let sort = get_sort(&params, pages::users::INDEX_SORT);
+    let order = get_order(&params);
+    let filter = get_filter(&filter);
+    let compare = get_compare(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let index = users_index(
+        &state,
+        &user,
+        start,
+        end,
+        compare,
+        metric,
+        filter.label.as_deref(),
+    )
+    .await?;
+    let charged = charged_by_user(&state, start, end, metric).await?;
+    let rows = pages::users::index_rows(
+        &index.items,
+        &index.costs,
+        index.previous.as_deref(),
+        sort,
+        &order,
+        &filter,
+        charged.as_ref(),
+    );
+
+    let path = pages::with_query(
+        &pages::with_sort(
+            &filter.apply_to(&pages::with_compare(
+                &pages::with_period(&pages::make_path(&state.base_path, "/users.json"), &period),
+                compare,
+            )),
+            sort,
+            &order,
+        ),
+        "page_size",
+        &page_size.to_string(),
+    );
+    let rows = rows.into_iter().map(|row| api::User::new(row, compare)).collect();
+    Ok(api::Paged::new(&path, rows, page, page_size).into_response())
+}
+
+/// Accounts, costs and, when comparing, previous-period costs of an index
+/// page, narrowed to what the signed-in user may see.
+struct IndexData<I, C> {
+    items: Vec<I>,
+    costs: Vec<C>,
+    previous: Option<Vec<C>>,
+}
+
+impl<I, C> IndexData<I, C> {
+    /// Keeps the accounts whose id is in `ids`, and their costs; all of them
+    /// when `ids` is `None`.
+    fn narrowed(
+        mut self,
+        ids: Option<&HashSet<String>>,
+        item_id: impl Fn(&I) -> &str,
+        cost_id: impl Fn(&C) -> &str,
+    ) -> Self {
+        let Some(ids) = ids else {
+            return self;
+        };
+        self.items.retain(|i| ids.contains(item_id(i)));
+        self.costs.retain(|c| ids.contains(cost_id(c)));
+        if let Some(previous) = &mut self.previous {
+            previous.retain(|c| ids.contains(cost_id(c)));
+        }
+        self
+    }
+}
+
+/// Ids of the `target`s carrying `label`, or `None` when no label is asked
+/// for.
+async fn label_members(
+    state: &AppState,
+    target: LabelTarget,
+    label: Option<&str>,
+) -> anyhow::Result<Option<HashSet<String>>> {
+    match label {
+        Some(label) => {
+            let labels = state.service.list_labels().await?;
+            Ok(Some(labeled_ids(&labels, target, label)))
+        }
+        None => Ok(None),
+    }
+}
+
+/// The configured markup, unless it adds nothing.
+fn markup(state: &AppState) -> Option<Markup> {
+    Some(state.config.load().markup()).filter(|markup| !markup.is_none())
+}
+
+/// Charged cost by user id over `[start, end)`, when a markup is configured.
+/// A user's markup depends on the models they used, so this takes the user
+/// by model costs.
+async fn charged_by_user(
+    state: &AppState,
+    start: NaiveDate,
+    end: NaiveDate,
+    metric: Metric,
+) -> anyhow::Result<Option<HashMap<String, Amount>>> {
+    match markup(state) {
+        Some(markup) => {
+            let matrix = state.service.get_cost_matrix(start, end, metric).await?;
+            Ok(Some(markup.charge_users(&matrix)))
+        }
+        None => Ok(None),
+    }
+}
+
+async fn users_index(
+    state: &AppState,
+    user: &CurrentUser,
+    start: NaiveDate,
+    end: NaiveDate,
+    compare: bool,
+    metric: Metric,
+    label: Option<&str>,
+) -> anyhow::Result<IndexData<UserInfo, CostByUser>> {
+    let members = label_members(state, LabelTarget::User, label).await?;
+    let previous_range = compare.then(|| previous_period(start, end));
+    let previous = match previous_range {
+        Some((prev_start, prev_end)) => {
+            Some(state.service.get_cost_by_user(prev_start, prev_end, metric).await?)
+        }
+        None => None,
+    };
+
+    if user.role.sees_all_users() {
+        let users_enriched = state.service.list_users_enriched().await?;
+        let costs = state.service.get_cost_by_user(start, end, metric).await?;
+
+        Ok(IndexData {
+            items: users_enriched,
+            costs,
+            previous,
+        }
+        .narrowed(members.as_ref(), |u| u.user_id.as_str(), |c| c.user_id.as_str()))
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let costs = state.service.get_cost_by_user(start, end, metric).await?;
+        let costs: Vec<_> = if let Some(ref uid) = current_user_id {
+            costs.into_iter().filter(|c| c.user_id == *uid).collect()
+        } else {
+            costs
+        };
+        let previous = previous.map(|previous| match current_user_id {
+            Some(ref uid) => previous.into_iter().filter(|c| c.user_id == *uid).collect(),
+            None => previous,
+        });
+        let users_enriched = state.service.list_users_enriched().await?;
+        let users_enriched: Vec<_> = if let Some(ref uid) = current_user_id {
+            users_enriched
+                .into_iter()
+                .filter(|u| u.user_id == *uid)
+                .collect()
+        } else {
+            users_enriched
+        };
+
+        Ok(IndexData {
+            items: users_enriched,
+            costs,
+            previous,
+        }
+        .narrowed(members.as_ref(), |u| u.user_id.as_str(), |c| c.user_id.as_str()))
+    }
+}
+
+pub async fn render_models(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(filter): Query<FilterParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::models::INDEX_SORT);
+    let order = get_order(&params);
+    let filter = get_filter(&filter);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let index = models_index(
+        &state,
+        &user,
+        start,
+        end,
+        get_compare(&params),
+        metric,
+        filter.label.as_deref(),
+    )
+    .await?;
+
+    Ok(Html(pages::models::render_index(
+        &state.base_path,
+        &nav,
+        page,
+        &index.items,
+        &index.costs,
+        index.previous.as_deref(),
+        sort,
+        &order,
+        &filter,
+        markup(&state).as_ref(),
+    ))
+    .into_response())
+}
+
+/// The models index as CSV, with the page's filter, sort and comparison but
+/// every page of rows.
+pub async fn export_models_csv(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+    Query(filter): Query<FilterParams>,
+) -> Result<Response, AppError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let sort = get_sort(&params, pages::models::INDEX_SORT);
+    let order = get_order(&params);
+    let filter = get_filter(&filter);
+    let compare = get_compare(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let index = models_index(
+        &state,
+        &user,
+        start,
+        end,
+        compare,
+        metric,
+        filter.label.as_deref(),
+    )
+    .await?;
+    let markup = markup(&state);
+    let rows = pages::models::index_rows(
+        &index.items,
+        &index.costs,
+        index.previous.as_deref(),
+        sort,
+        &order,
+        &filter,
+        markup.as_ref(),
+    );
+
+    let filename = format!("cost_by_model_{}_{}.csv", start, end);
+    Ok(csv_response(
+        &filename,
+        export::models_csv(&rows, compare, markup.is_some()),
+    ))
+}
+
+async fn models_index(
+    state: &AppState,
+    user: &CurrentUser,
+    start: NaiveDate,
+    end: NaiveDate,
+    compare: bool,
+    metric: Metric,
+    label: Option<&str>,
+) -> anyhow::Result<IndexData<ModelInfo, CostByModel>> {
+    let members = label_members(state, LabelTarget::Model, label).await?;
+    let previous_range = compare.then(|| previous_period(start, end));
+
+    if user.role.sees_all_costs() {
+        let models_enriched = state.service.list_models_enriched().await?;
+        let costs = state.service.get_cost_by_model(start, end, metric).await?;
+        let previous = match previous_range {
+            Some((prev_start, prev_end)) => {
+                Some(state.service.get_cost_by_model(prev_start, prev_end, metric).await?)
+            }
+            None => None,
+        };
+
+        Ok(IndexData {
+            items: models_enriched,
+            costs,
+            previous,
+        }
+        .narrowed(members.as_ref(), |m| m.model_id.as_str(), |c| c.model_id.as_str()))
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let costs = if let Some(ref uid) = current_user_id {
+            state
+                .service
+                .get_cost_by_model_for_user(start, end, uid, metric)
+                .await?
+        } else {
+            vec![]
+        };
+        let previous = match (previous_range, &current_user_id) {
+            (Some((prev_start, prev_end)), Some(uid)) => Some(
+                state
+                    .service
+                    .get_cost_by_model_for_user(prev_start, prev_end, uid, metric)
+                    .await?,
+            ),
+            (Some(_), None) => Some(vec![]),
+            (None, _) => None,
+        };
+        // Filter models to only those the user has cost data for
         let cost_model_ids: HashSet<String> =
             costs.iter().map(|c| c.model_id.clone()).collect();
         let models_enriched: Vec<_> = state
             .service
             .list_models_enriched()
-            .await
+            .await?
             .into_iter()
             .filter(|m| cost_model_ids.contains(&m.model_id))
             .map(|mut m| {
@@ -390,16 +1602,12 @@ pub async fn render_models(
             })
             .collect();
 
-        Html(pages::models::render_index(
-            &state.base_path,
-            &period,
-            page,
-            &models_enriched,
-            &costs,
-            sort,
-            &order,
-        ))
-        .into_response()
+        Ok(IndexData {
+            items: models_enriched,
+            costs,
+            previous,
+        }
+        .narrowed(members.as_ref(), |m| m.model_id.as_str(), |c| c.model_id.as_str()))
     }
 }
 
@@ -408,26 +1616,44 @@ pub async fn render_user_hub(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if current_user_id.as_deref() != Some(user_id.as_str()) {
-            return StatusCode::FORBIDDEN.into_response();
+            return Ok(StatusCode::FORBIDDEN.into_response());
         }
     }
 
-    let period = get_period(&params);
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+    let nav = get_nav(&params);
+    let limit = state.service.get_spend_limit(&user_id).await?;
+    let edit_limits = user.role == Role::Admin;
+    let (start, end) = resolve_period(&get_period(&params), today(&params, &state));
+    let keys = state.service.list_api_keys_for_user(&user_id).await?;
+    let key_costs = state
+        .service
+        .get_cost_by_api_key_for_user(start, end, &user_id, metric)
+        .await?;
     let user_info = state.service.get_user_info(&user_id).await;
     match user_info {
-        Some(info) => {
-            Html(pages::users::render_hub(&state.base_path, &period, &info)).into_response()
-        }
+        Some(info) => Ok(Html(pages::users::render_hub(
+            &state.base_path,
+            &nav,
+            &info,
+            limit.as_ref(),
+            edit_limits,
+            &keys,
+            &key_costs,
+        ))
+        .into_response()),
         None => {
             // Fallback: construct minimal UserInfo from email lookup
             let user_email = state
@@ -443,7 +1669,16 @@ pub async fn render_user_hub(
                 active_api_key_count: 0,
                 inference_profile_count: 0,
             };
-            Html(pages::users::render_hub(&state.base_path, &period, &info)).into_response()
+            Ok(Html(pages::users::render_hub(
+                &state.base_path,
+                &nav,
+                &info,
+                limit.as_ref(),
+                edit_limits,
+                &keys,
+                &key_costs,
+            ))
+            .into_response())
         }
     }
 }
@@ -453,25 +1688,29 @@ pub async fn render_user_daily_costs(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if current_user_id.as_deref() != Some(user_id.as_str()) {
-            return StatusCode::FORBIDDEN.into_response();
+            return Ok(StatusCode::FORBIDDEN.into_response());
         }
     }
 
     let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::DATE_SORT);
     let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let (start, end) = resolve_period(&period, today(&params, &state));
     let user_email = state
         .service
         .get_user_email(&user_id)
@@ -479,19 +1718,19 @@ pub async fn render_user_daily_costs(
         .unwrap_or_else(|| "unknown".to_string());
     let costs = state
         .service
-        .get_daily_cost_for_user(start, end, &user_id)
-        .await;
+        .get_daily_cost_for_user(start, end, &user_id, metric)
+        .await?;
     let costs = pages::sort_records(costs, sort, &order);
 
-    Html(pages::users::render_daily_costs(
+    Ok(Html(pages::users::render_daily_costs(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &user_id,
         &user_email,
         &costs,
     ))
-    .into_response()
+    .into_response())
 }
 
 pub async fn render_user_monthly_costs(
@@ -499,25 +1738,29 @@ pub async fn render_user_monthly_costs(
     State(state): State<AppState>,
     Path(user_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if current_user_id.as_deref() != Some(user_id.as_str()) {
-            return StatusCode::FORBIDDEN.into_response();
+            return Ok(StatusCode::FORBIDDEN.into_response());
         }
     }
 
     let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::MONTH_SORT);
     let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let (start, end) = resolve_period(&period, today(&params, &state));
     let user_email = state
         .service
         .get_user_email(&user_id)
@@ -525,115 +1768,274 @@ pub async fn render_user_monthly_costs(
         .unwrap_or_else(|| "unknown".to_string());
     let costs = state
         .service
-        .get_monthly_cost_for_user(snap_to_month_start(start), end, &user_id)
-        .await;
+        .get_monthly_cost_for_user(snap_to_month_start(start), end, &user_id, metric)
+        .await?;
     let costs = pages::sort_records(costs, sort, &order);
 
-    Html(pages::users::render_monthly_costs(
+    Ok(Html(pages::users::render_monthly_costs(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &user_id,
         &user_email,
         &costs,
     ))
-    .into_response()
+    .into_response())
 }
 
-pub async fn render_model_hub(
+pub async fn render_user_keys(
     session: Session,
     State(state): State<AppState>,
-    Path(model_id): Path<String>,
+    Path(user_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
 
-    let period = get_period(&params);
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let has_access = if let Some(ref uid) = current_user_id {
-            let (start, end) = resolve_period("12m");
-            let costs = state
-                .service
-                .get_cost_by_model_for_user(start, end, uid)
-                .await;
-            costs.iter().any(|c| c.model_id == model_id)
-        } else {
-            false
-        };
-        if !has_access {
-            return StatusCode::FORBIDDEN.into_response();
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        if current_user_id.as_deref() != Some(user_id.as_str()) {
+            return Ok(StatusCode::FORBIDDEN.into_response());
         }
     }
 
-    let model_info = state.service.get_model_info(&model_id).await;
-    match model_info {
-        Some(mut info) => {
-            #[cfg(not(feature = "admin"))]
-            {
-                info.user_count = 1;
-            }
-            Html(pages::models::render_hub(&state.base_path, &period, &info)).into_response()
-        }
-        None => {
-            let model_name = state
-                .service
-                .get_model_name(&model_id)
-                .await
-                .unwrap_or_else(|| "unknown".to_string());
-            let info = common::ModelInfo {
-                model_id: model_id.clone(),
-                model_name,
-                is_disabled: false,
-                protected: false,
-                user_count: 1,
-            };
-            Html(pages::models::render_hub(&state.base_path, &period, &info)).into_response()
-        }
-    }
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::API_KEY_SORT);
+    let order = get_order(&params);
+    let user_email = state
+        .service
+        .get_user_email(&user_id)
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
+    let keys = state.service.list_api_keys_for_user(&user_id).await?;
+    let keys = pages::sort_api_keys(keys, sort, &order);
+
+    Ok(Html(pages::users::render_keys(
+        &state.base_path,
+        &nav,
+        page,
+        &user_id,
+        &user_email,
+        &keys,
+    ))
+    .into_response())
 }
 
-pub async fn render_model_daily_costs(
+pub async fn render_user_profiles(
+    session: Session,
+    State(state): State<AppState>,
+    Path(user_id): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
+
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        if current_user_id.as_deref() != Some(user_id.as_str()) {
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+    }
+
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::PROFILE_SORT);
+    let order = get_order(&params);
+    let user_email = state
+        .service
+        .get_user_email(&user_id)
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
+    let profiles = state.service.list_profiles_for_user(&user_id).await?;
+    let profiles = pages::sort_profiles(profiles, sort, &order);
+
+    Ok(Html(pages::users::render_profiles(
+        &state.base_path,
+        &nav,
+        page,
+        &user_id,
+        &user_email,
+        &profiles,
+    ))
+    .into_response())
+}
+
+pub async fn render_model_hub(
     session: Session,
     State(state): State<AppState>,
     Path(model_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+
+    if !user.role.sees_all_costs() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let has_access = if let Some(ref uid) = current_user_id {
+            let (start, end) = resolve_period("12m", today(&params, &state));
+            let costs = state
+                .service
+                .get_cost_by_model_for_user(start, end, uid, metric)
+                .await?;
+            costs.iter().any(|c| c.model_id == model_id)
+        } else {
+            false
+        };
+        if !has_access {
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+    }
+
+    // Region costs come from CE for all users, so only full-cost roles see them.
+    let regions = if user.role.sees_all_costs() {
+        let (start, end) = resolve_period(&get_period(&params), today(&params, &state));
+        let mut regions = state.service.get_cost_by_region(start, end, metric).await?;
+        regions.retain(|c| c.model_id == model_id);
+        regions
+    } else {
+        vec![]
+    };
+
+    let model_info = state.service.get_model_info(&model_id).await;
+    match model_info {
+        Some(mut info) => {
+            if !user.role.sees_all_costs() {
+                info.user_count = 1;
+            }
+            Ok(Html(pages::models::render_hub(&state.base_path, &nav, &info, &regions)).into_response())
+        }
+        None => {
+            let model_name = state
+                .service
+                .get_model_name(&model_id)
+                .await
+                .unwrap_or_else(|| "unknown".to_string());
+            let info = common::ModelInfo {
+                model_id: model_id.clone(),
+                model_name,
+                is_disabled: false,
+                protected: false,
+                user_count: 1,
+            };
+            Ok(Html(pages::models::render_hub(&state.base_path, &nav, &info, &regions)).into_response())
+        }
+    }
+}
+
+pub async fn render_model_users(
+    session: Session,
+    State(state): State<AppState>,
+    Path(model_id): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::models::USERS_SORT);
     let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let (start, end) = resolve_period(&period, today(&params, &state));
     let model_name = state
         .service
         .get_model_name(&model_id)
         .await
         .unwrap_or_else(|| "unknown".to_string());
-
-    #[cfg(feature = "admin")]
+    let profiles = state.service.list_profiles_for_model(&model_id).await?;
     let costs = state
         .service
-        .get_daily_cost_for_model(start, end, &model_id)
-        .await;
+        .get_cost_by_user_for_model(start, end, &model_id, metric)
+        .await?;
+
+    // Users who cannot see everyone only get their own profiles.
+    let (profiles, costs) = if user.role.sees_all_users() {
+        (profiles, costs)
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let is_current = |id: &str| current_user_id.as_deref() == Some(id);
+        (
+            profiles.into_iter().filter(|p| is_current(&p.user_id)).collect(),
+            costs.into_iter().filter(|c| is_current(&c.user_id)).collect(),
+        )
+    };
+
+    Ok(Html(pages::models::render_users(
+        &state.base_path,
+        &nav,
+        page,
+        &model_id,
+        &model_name,
+        &profiles,
+        &costs,
+        sort,
+        &order,
+    ))
+    .into_response())
+}
+
+pub async fn render_model_daily_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Path(model_id): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::DATE_SORT);
+    let order = get_order(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let model_name = state
+        .service
+        .get_model_name(&model_id)
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
 
-    #[cfg(not(feature = "admin"))]
-    let costs = {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    let costs = if user.role.sees_all_costs() {
+        state
+            .service
+            .get_daily_cost_for_model(start, end, &model_id, metric)
+            .await?
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if let Some(ref uid) = current_user_id {
             state
                 .service
-                .get_daily_cost_for_user_and_model(start, end, uid, &model_id)
-                .await
+                .get_daily_cost_for_user_and_model(start, end, uid, &model_id, metric)
+                .await?
         } else {
             vec![]
         }
@@ -641,15 +2043,15 @@ pub async fn render_model_daily_costs(
 
     let costs = pages::sort_records(costs, sort, &order);
 
-    Html(pages::models::render_daily_costs(
+    Ok(Html(pages::models::render_daily_costs(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &model_id,
         &model_name,
         &costs,
     ))
-    .into_response()
+    .into_response())
 }
 
 pub async fn render_model_monthly_costs(
@@ -657,37 +2059,40 @@ pub async fn render_model_monthly_costs(
     State(state): State<AppState>,
     Path(model_id): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::MONTH_SORT);
     let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let (start, end) = resolve_period(&period, today(&params, &state));
     let model_name = state
         .service
         .get_model_name(&model_id)
         .await
         .unwrap_or_else(|| "unknown".to_string());
 
-    #[cfg(feature = "admin")]
-    let costs = state
-        .service
-        .get_monthly_cost_for_model(snap_to_month_start(start), end, &model_id)
-        .await;
-
-    #[cfg(not(feature = "admin"))]
-    let costs = {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    let costs = if user.role.sees_all_costs() {
+        state
+            .service
+            .get_monthly_cost_for_model(snap_to_month_start(start), end, &model_id, metric)
+            .await?
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if let Some(ref uid) = current_user_id {
             state
                 .service
-                .get_monthly_cost_for_user_and_model(snap_to_month_start(start), end, uid, &model_id)
-                .await
+                .get_monthly_cost_for_user_and_model(snap_to_month_start(start), end, uid, &model_id, metric)
+                .await?
         } else {
             vec![]
         }
@@ -695,73 +2100,173 @@ pub async fn render_model_monthly_costs(
 
     let costs = pages::sort_records(costs, sort, &order);
 
-    Html(pages::models::render_monthly_costs(
+    Ok(Html(pages::models::render_monthly_costs(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &model_id,
         &model_name,
         &costs,
     ))
-    .into_response()
+    .into_response())
 }
 
 // --- Daily cost drill-down handlers ---
 
-pub async fn render_date_hub(
+pub async fn render_services(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    // Service overhead is not attributed to users, so only full-cost roles
+    // see it.
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let rows = state.service.get_daily_cost_by_service(start, end, metric).await?;
+
+    Ok(Html(pages::services::render_index(&state.base_path, &nav, &rows)).into_response())
+}
+
+pub async fn render_service_date(
     session: Session,
     State(state): State<AppState>,
     Path(date): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let date_nd = parse_date(&date)?;
+    let next_day = date_nd + chrono::Duration::days(1);
+    let rows = state
+        .service
+        .get_daily_cost_by_service(date_nd, next_day, metric)
+        .await?;
+
+    Ok(Html(pages::services::render_date(&state.base_path, &nav, &date, &rows)).into_response())
+}
+
+pub async fn render_regions(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let costs = state.service.get_cost_by_region(start, end, metric).await?;
+
+    Ok(Html(pages::regions::render(&state.base_path, &nav, &costs)).into_response())
+}
+
+pub async fn render_accounts(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let date_nd = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .unwrap_or_else(|_| Utc::now().date_naive());
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let costs = state.service.get_cost_by_account(start, end, metric).await?;
+
+    Ok(Html(pages::accounts::render(&state.base_path, &nav, &costs)).into_response())
+}
+
+pub async fn render_date_hub(
+    session: Session,
+    State(state): State<AppState>,
+    Path(date): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let date_nd = parse_date(&date)?;
     let next_day = date_nd + chrono::Duration::days(1);
 
-    #[cfg(feature = "admin")]
-    {
-        let daily_cost = state.service.get_daily_cost(date_nd, next_day).await;
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
+    if user.role.sees_all_costs() {
+        let daily_cost = state.service.get_daily_cost(date_nd, next_day, metric).await?;
+        let total_cost: Amount = daily_cost.iter().map(|r| r.amount).sum();
         let currency = daily_cost
             .first()
             .map(|r| r.currency.as_str())
             .unwrap_or("USD");
-        let users = state.service.get_cost_by_user(date_nd, next_day).await;
-        let models = state.service.get_cost_by_model(date_nd, next_day).await;
+        let users = state.service.get_cost_by_user(date_nd, next_day, metric).await?;
+        let models = state.service.get_cost_by_model(date_nd, next_day, metric).await?;
 
-        Html(pages::costs::render_hub(
+        Ok(Html(pages::costs::render_hub(
             &state.base_path,
-            &period,
+            &nav,
             &date,
             total_cost,
             currency,
             users.len(),
             models.len(),
         ))
-        .into_response()
-    }
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         let daily_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_daily_cost_for_user(date_nd, next_day, uid).await
+            state.service.get_daily_cost_for_user(date_nd, next_day, uid, metric).await?
         } else {
             vec![]
         };
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
+        let total_cost: Amount = daily_cost.iter().map(|r| r.amount).sum();
         let currency = daily_cost
             .first()
             .map(|r| r.currency.as_str())
             .unwrap_or("USD");
         let users = if let Some(ref uid) = current_user_id {
-            let all = state.service.get_cost_by_user(date_nd, next_day).await;
+            let all = state.service.get_cost_by_user(date_nd, next_day, metric).await?;
             all.into_iter()
                 .filter(|c| c.user_id == *uid)
                 .collect::<Vec<_>>()
@@ -771,22 +2276,22 @@ pub async fn render_date_hub(
         let models = if let Some(ref uid) = current_user_id {
             state
                 .service
-                .get_cost_by_model_for_user(date_nd, next_day, uid)
-                .await
+                .get_cost_by_model_for_user(date_nd, next_day, uid, metric)
+                .await?
         } else {
             vec![]
         };
 
-        Html(pages::costs::render_hub(
+        Ok(Html(pages::costs::render_hub(
             &state.base_path,
-            &period,
+            &nav,
             &date,
             total_cost,
             currency,
             users.len(),
             models.len(),
         ))
-        .into_response()
+        .into_response())
     }
 }
 
@@ -795,39 +2300,37 @@ pub async fn render_date_users(
     State(state): State<AppState>,
     Path(date): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::USER_SORT);
     let order = get_order(&params);
-    let date_nd = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .unwrap_or_else(|_| Utc::now().date_naive());
+    let date_nd = parse_date(&date)?;
     let next_day = date_nd + chrono::Duration::days(1);
 
-    #[cfg(feature = "admin")]
-    {
-        let costs = state.service.get_cost_by_user(date_nd, next_day).await;
+    if user.role.sees_all_users() {
+        let costs = state.service.get_cost_by_user(date_nd, next_day, metric).await?;
         let costs = pages::sort_by_user(costs, sort, &order);
 
-        Html(pages::costs::render_users(
+        Ok(Html(pages::costs::render_users(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &date,
             &costs,
         ))
-        .into_response()
-    }
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let costs = state.service.get_cost_by_user(date_nd, next_day).await;
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let costs = state.service.get_cost_by_user(date_nd, next_day, metric).await?;
         let costs: Vec<_> = if let Some(ref uid) = current_user_id {
             costs.into_iter().filter(|c| c.user_id == *uid).collect()
         } else {
@@ -835,14 +2338,14 @@ pub async fn render_date_users(
         };
         let costs = pages::sort_by_user(costs, sort, &order);
 
-        Html(pages::costs::render_users(
+        Ok(Html(pages::costs::render_users(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &date,
             &costs,
         ))
-        .into_response()
+        .into_response())
     }
 }
 
@@ -851,56 +2354,54 @@ pub async fn render_date_models(
     State(state): State<AppState>,
     Path(date): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::MODEL_SORT);
     let order = get_order(&params);
-    let date_nd = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .unwrap_or_else(|_| Utc::now().date_naive());
+    let date_nd = parse_date(&date)?;
     let next_day = date_nd + chrono::Duration::days(1);
 
-    #[cfg(feature = "admin")]
-    {
-        let costs = state.service.get_cost_by_model(date_nd, next_day).await;
+    if user.role.sees_all_costs() {
+        let costs = state.service.get_cost_by_model(date_nd, next_day, metric).await?;
         let costs = pages::sort_by_model(costs, sort, &order);
 
-        Html(pages::costs::render_models(
+        Ok(Html(pages::costs::render_models(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &date,
             &costs,
         ))
-        .into_response()
-    }
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         let costs = if let Some(ref uid) = current_user_id {
             state
                 .service
-                .get_cost_by_model_for_user(date_nd, next_day, uid)
-                .await
+                .get_cost_by_model_for_user(date_nd, next_day, uid, metric)
+                .await?
         } else {
             vec![]
         };
         let costs = pages::sort_by_model(costs, sort, &order);
 
-        Html(pages::costs::render_models(
+        Ok(Html(pages::costs::render_models(
             &state.base_path,
-            &period,
+            &nav,
             page,
             &date,
             &costs,
         ))
-        .into_response()
+        .into_response())
     }
 }
 
@@ -909,26 +2410,28 @@ pub async fn render_date_models_for_user(
     State(state): State<AppState>,
     Path((date, user_id)): Path<(String, String)>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         if current_user_id.as_deref() != Some(user_id.as_str()) {
-            return StatusCode::FORBIDDEN.into_response();
+            return Ok(StatusCode::FORBIDDEN.into_response());
         }
     }
 
-    let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::MODEL_SORT);
     let order = get_order(&params);
-    let date_nd = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .unwrap_or_else(|_| Utc::now().date_naive());
+    let date_nd = parse_date(&date)?;
     let next_day = date_nd + chrono::Duration::days(1);
     let user_email = state
         .service
@@ -937,19 +2440,19 @@ pub async fn render_date_models_for_user(
         .unwrap_or_else(|| "unknown".to_string());
     let costs = state
         .service
-        .get_cost_by_model_for_user(date_nd, next_day, &user_id)
-        .await;
+        .get_cost_by_model_for_user(date_nd, next_day, &user_id, metric)
+        .await?;
     let costs = pages::sort_by_model(costs, sort, &order);
 
-    Html(pages::costs::render_user_models(
+    Ok(Html(pages::costs::render_user_models(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &date,
         &user_email,
         &costs,
     ))
-    .into_response()
+    .into_response())
 }
 
 pub async fn render_date_users_for_model(
@@ -957,18 +2460,21 @@ pub async fn render_date_users_for_model(
     State(state): State<AppState>,
     Path((date, model_id)): Path<(String, String)>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
-    let period = get_period(&params);
+    let nav = get_nav(&params);
     let page = get_page(&params);
-    let sort = get_sort(&params);
+    let sort = get_sort(&params, pages::USER_SORT);
     let order = get_order(&params);
-    let date_nd = NaiveDate::parse_from_str(&date, "%Y-%m-%d")
-        .unwrap_or_else(|_| Utc::now().date_naive());
+    let date_nd = parse_date(&date)?;
     let next_day = date_nd + chrono::Duration::days(1);
     let model_name = state
         .service
@@ -976,19 +2482,17 @@ pub async fn render_date_users_for_model(
         .await
         .unwrap_or_else(|| "unknown".to_string());
 
-    #[cfg(feature = "admin")]
-    let costs = state
-        .service
-        .get_cost_by_user_for_model(date_nd, next_day, &model_id)
-        .await;
-
-    #[cfg(not(feature = "admin"))]
-    let costs = {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
+    let costs = if user.role.sees_all_users() {
+        state
+            .service
+            .get_cost_by_user_for_model(date_nd, next_day, &model_id, metric)
+            .await?
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
         let all = state
             .service
-            .get_cost_by_user_for_model(date_nd, next_day, &model_id)
-            .await;
+            .get_cost_by_user_for_model(date_nd, next_day, &model_id, metric)
+            .await?;
         if let Some(ref uid) = current_user_id {
             all.into_iter().filter(|c| c.user_id == *uid).collect()
         } else {
@@ -998,386 +2502,1754 @@ pub async fn render_date_users_for_model(
 
     let costs = pages::sort_by_user(costs, sort, &order);
 
-    Html(pages::costs::render_model_users(
+    Ok(Html(pages::costs::render_model_users(
         &state.base_path,
-        &period,
+        &nav,
         page,
         &date,
         &model_name,
         &costs,
     ))
-    .into_response()
+    .into_response())
+}
+
+// --- Monthly cost handlers ---
+
+pub async fn render_monthly_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::MONTH_SORT);
+    let order = get_order(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+
+    let previous_range = get_compare(&params).then(|| previous_months(start, end));
+
+    if user.role.sees_all_costs() {
+        let monthly_cost = state.service.get_monthly_cost(snap_to_month_start(start), end, metric).await?;
+        let monthly_cost = pages::sort_records(monthly_cost, sort, &order);
+        let previous = match previous_range {
+            Some((prev_start, prev_end, months)) => Some(shift_records(
+                state.service.get_monthly_cost(prev_start, prev_end, metric).await?,
+                |d| d.checked_add_months(months),
+            )),
+            None => None,
+        };
+        let credits = period_credits(
+            &state,
+            params.source.as_deref(),
+            snap_to_month_start(start),
+            end,
+            metric,
+        )
+        .await?;
+
+        Ok(Html(pages::monthly::render(
+            &state.base_path,
+            &nav,
+            page,
+            &monthly_cost,
+            previous.as_deref(),
+            credits,
+        ))
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let monthly_cost = if let Some(ref uid) = current_user_id {
+            state.service.get_monthly_cost_for_user(snap_to_month_start(start), end, uid, metric).await?
+        } else {
+            vec![]
+        };
+        let monthly_cost = pages::sort_records(monthly_cost, sort, &order);
+        let previous = match (previous_range, &current_user_id) {
+            (Some((prev_start, prev_end, months)), Some(uid)) => Some(shift_records(
+                state
+                    .service
+                    .get_monthly_cost_for_user(prev_start, prev_end, uid, metric)
+                    .await?,
+                |d| d.checked_add_months(months),
+            )),
+            (Some(_), None) => Some(vec![]),
+            (None, _) => None,
+        };
+
+        Ok(Html(pages::monthly::render(
+            &state.base_path,
+            &nav,
+            page,
+            &monthly_cost,
+            previous.as_deref(),
+            None,
+        ))
+        .into_response())
+    }
 }
 
-// --- Monthly cost handlers ---
+pub async fn render_weekly_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+    let start = snap_to_week_start(start);
+
+    let weekly_cost = if user.role.sees_all_costs() {
+        state.service.get_weekly_cost(start, end, metric).await?
+    } else {
+        match resolve_current_user_id(state.service.as_ref(), &user.email).await {
+            Some(uid) => {
+                state
+                    .service
+                    .get_weekly_cost_for_user(start, end, &uid, metric)
+                    .await?
+            }
+            None => vec![],
+        }
+    };
+
+    Ok(Html(pages::weekly::render(&state.base_path, &nav, page, &weekly_cost)).into_response())
+}
+
+pub async fn render_week(
+    session: Session,
+    State(state): State<AppState>,
+    Path(week): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let (start, end) = parse_week(&week)?;
+
+    let daily_cost = if user.role.sees_all_costs() {
+        state.service.get_daily_cost(start, end, metric).await?
+    } else {
+        match resolve_current_user_id(state.service.as_ref(), &user.email).await {
+            Some(uid) => {
+                state
+                    .service
+                    .get_daily_cost_for_user(start, end, &uid, metric)
+                    .await?
+            }
+            None => vec![],
+        }
+    };
+
+    Ok(Html(pages::weekly::render_week(&state.base_path, &nav, start, &daily_cost)).into_response())
+}
+
+pub async fn render_month_hub(
+    session: Session,
+    State(state): State<AppState>,
+    Path(month): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let (start, end) = parse_month(&month)?;
+    let (prev_start, prev_end) = (start - Months::new(1), start - chrono::Duration::days(1));
+
+    if user.role.sees_all_costs() {
+        let daily_cost = state.service.get_daily_cost(start, end, metric).await?;
+        let total_cost: Amount = daily_cost.iter().map(|r| r.amount).sum();
+        let currency = daily_cost
+            .first()
+            .map(|r| r.currency.as_str())
+            .unwrap_or("USD");
+        let users = state.service.get_cost_by_user(start, end, metric).await?;
+        let models = state.service.get_cost_by_model(start, end, metric).await?;
+        let previous = state.service.get_daily_cost(prev_start, prev_end, metric).await?;
+
+        Ok(Html(pages::monthly::render_hub(
+            &state.base_path,
+            &nav,
+            &month,
+            total_cost,
+            currency,
+            users.len(),
+            models.len(),
+            &daily_cost,
+            &previous,
+            None,
+        ))
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let daily_cost = if let Some(ref uid) = current_user_id {
+            state.service.get_daily_cost_for_user(start, end, uid, metric).await?
+        } else {
+            vec![]
+        };
+        let total_cost: Amount = daily_cost.iter().map(|r| r.amount).sum();
+        let currency = daily_cost
+            .first()
+            .map(|r| r.currency.as_str())
+            .unwrap_or("USD");
+        let users = if let Some(ref uid) = current_user_id {
+            let all = state.service.get_cost_by_user(start, end, metric).await?;
+            all.into_iter()
+                .filter(|c| c.user_id == *uid)
+                .collect::<Vec<_>>()
+        } else {
+            vec![]
+        };
+        let models = if let Some(ref uid) = current_user_id {
+            state
+                .service
+                .get_cost_by_model_for_user(start, end, uid, metric)
+                .await?
+        } else {
+            vec![]
+        };
+        // A user's spend limit is the only budget there is.
+        let (previous, budget) = if let Some(ref uid) = current_user_id {
+            let previous = state
+                .service
+                .get_daily_cost_for_user(prev_start, prev_end, uid, metric)
+                .await?;
+            let limit = state.service.get_spend_limit(uid).await?;
+            (previous, limit.map(|l| l.monthly_limit))
+        } else {
+            (vec![], None)
+        };
+
+        Ok(Html(pages::monthly::render_hub(
+            &state.base_path,
+            &nav,
+            &month,
+            total_cost,
+            currency,
+            users.len(),
+            models.len(),
+            &daily_cost,
+            &previous,
+            budget,
+        ))
+        .into_response())
+    }
+}
+
+pub async fn render_month_users(
+    session: Session,
+    State(state): State<AppState>,
+    Path(month): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::USER_SORT);
+    let order = get_order(&params);
+    let (start, end) = parse_month(&month)?;
+
+    if user.role.sees_all_users() {
+        let costs = state.service.get_cost_by_user(start, end, metric).await?;
+        let costs = pages::sort_by_user(costs, sort, &order);
+
+        Ok(Html(pages::monthly::render_users(
+            &state.base_path,
+            &nav,
+            page,
+            &month,
+            &costs,
+            user.role == Role::Admin,
+        ))
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let costs = state.service.get_cost_by_user(start, end, metric).await?;
+        let costs: Vec<_> = if let Some(ref uid) = current_user_id {
+            costs.into_iter().filter(|c| c.user_id == *uid).collect()
+        } else {
+            costs
+        };
+        let costs = pages::sort_by_user(costs, sort, &order);
+
+        Ok(Html(pages::monthly::render_users(
+            &state.base_path,
+            &nav,
+            page,
+            &month,
+            &costs,
+            false,
+        ))
+        .into_response())
+    }
+}
+
+pub async fn render_month_models(
+    session: Session,
+    State(state): State<AppState>,
+    Path(month): Path<String>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::MODEL_SORT);
+    let order = get_order(&params);
+    let (start, end) = parse_month(&month)?;
+
+    if user.role.sees_all_costs() {
+        let costs = state.service.get_cost_by_model(start, end, metric).await?;
+        let costs = pages::sort_by_model(costs, sort, &order);
+
+        Ok(Html(pages::monthly::render_models(
+            &state.base_path,
+            &nav,
+            page,
+            &month,
+            &costs,
+        ))
+        .into_response())
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let costs = if let Some(ref uid) = current_user_id {
+            state
+                .service
+                .get_cost_by_model_for_user(start, end, uid, metric)
+                .await?
+        } else {
+            vec![]
+        };
+        let costs = pages::sort_by_model(costs, sort, &order);
+
+        Ok(Html(pages::monthly::render_models(
+            &state.base_path,
+            &nav,
+            page,
+            &month,
+            &costs,
+        ))
+        .into_response())
+    }
+}
+
+pub async fn render_month_models_for_user(
+    session: Session,
+    State(state): State<AppState>,
+    Path((month, user_id)): Path<(String, String)>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("user id", &user_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    if !user.role.sees_all_users() {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        if current_user_id.as_deref() != Some(user_id.as_str()) {
+            return Ok(StatusCode::FORBIDDEN.into_response());
+        }
+    }
+
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::MODEL_SORT);
+    let order = get_order(&params);
+    let (start, end) = parse_month(&month)?;
+    let user_email = state
+        .service
+        .get_user_email(&user_id)
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
+    let costs = state
+        .service
+        .get_cost_by_model_for_user(start, end, &user_id, metric)
+        .await?;
+    let costs = pages::sort_by_model(costs, sort, &order);
+
+    Ok(Html(pages::monthly::render_user_models(
+        &state.base_path,
+        &nav,
+        page,
+        &month,
+        &user_email,
+        &costs,
+    ))
+    .into_response())
+}
+
+pub async fn render_month_users_for_model(
+    session: Session,
+    State(state): State<AppState>,
+    Path((month, model_id)): Path<(String, String)>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+    check_id("model id", &model_id)?;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let nav = get_nav(&params);
+    let page = get_page(&params);
+    let sort = get_sort(&params, pages::USER_SORT);
+    let order = get_order(&params);
+    let (start, end) = parse_month(&month)?;
+    let model_name = state
+        .service
+        .get_model_name(&model_id)
+        .await
+        .unwrap_or_else(|| "unknown".to_string());
+
+    let costs = if user.role.sees_all_users() {
+        state
+            .service
+            .get_cost_by_user_for_model(start, end, &model_id, metric)
+            .await?
+    } else {
+        let current_user_id = resolve_current_user_id(state.service.as_ref(), &user.email).await;
+        let all = state
+            .service
+            .get_cost_by_user_for_model(start, end, &model_id, metric)
+            .await?;
+        if let Some(ref uid) = current_user_id {
+            all.into_iter().filter(|c| c.user_id == *uid).collect()
+        } else {
+            vec![]
+        }
+    };
+
+    let costs = pages::sort_by_user(costs, sort, &order);
+
+    Ok(Html(pages::monthly::render_model_users(
+        &state.base_path,
+        &nav,
+        page,
+        &month,
+        &model_name,
+        &costs,
+    ))
+    .into_response())
+}
+
+#[derive(Deserialize)]
+pub struct SearchParams {
+    pub q: Option<String>,
+}
+
+pub async fn render_search(
+    session: Session,
+    State(state): State<AppState>,
+    Query(search): Query<SearchParams>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let params = apply_preferences(&session, &state, params).await;
+
+    let nav = get_nav(&params);
+    let query = search.q.as_deref().unwrap_or("").trim();
+    let users = if !user.role.sees_all_users() {
+        None
+    } else if query.is_empty() {
+        Some(Vec::new())
+    } else {
+        Some(state.service.search_users(query).await?)
+    };
+    let models = if !user.role.sees_all_costs() {
+        None
+    } else if query.is_empty() {
+        Some(Vec::new())
+    } else {
+        Some(state.service.search_models(query).await?)
+    };
+
+    Ok(Html(pages::search::render(
+        &state.base_path,
+        &nav,
+        query,
+        users.as_deref(),
+        models.as_deref(),
+    ))
+    .into_response())
+}
+
+pub async fn render_preferences(session: Session, State(state): State<AppState>) -> Response {
+    if let Err(redirect) = require_login(&session, &state).await {
+        return redirect;
+    }
+    let prefs = Preferences::load(&session).await;
+    Html(pages::preferences::render(&state.base_path, &prefs)).into_response()
+}
+
+pub async fn save_preferences(
+    session: Session,
+    State(state): State<AppState>,
+    Form(form): Form<PreferencesForm>,
+) -> Result<Response, AppError> {
+    if let Err(redirect) = require_login(&session, &state).await {
+        return Ok(redirect);
+    }
+    Preferences::from_form(&form).save(&session).await?;
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "")).into_response())
+}
+
+/// The submitted spend limit form; an empty limit removes the user's limit.
+#[derive(Deserialize)]
+pub struct SpendLimitForm {
+    pub user_id: String,
+    #[serde(default)]
+    pub monthly_limit: String,
+}
+
+pub async fn render_spend_limits(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let config = state.config.load();
+    let today = Utc::now().with_timezone(&config.timezone).date_naive();
+    let (start, end) = resolve_period("month", today);
+    let limits = state.service.list_spend_limits().await?;
+    let spent = state
+        .service
+        .get_cost_by_user(start, end + chrono::Duration::days(1), config.metric)
+        .await?
+        .into_iter()
+        .map(|c| (c.user_id, c.amount))
+        .collect();
+    let users = state.service.list_users().await?;
+
+    Ok(Html(pages::limits::render(&state.base_path, &limits, &spent, &users)).into_response())
+}
+
+pub async fn save_spend_limit(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<SpendLimitForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    check_id("user id", &form.user_id)?;
+    let monthly_limit = match form.monthly_limit.trim() {
+        "" => None,
+        value => match value.parse::<Amount>() {
+            Ok(limit) if limit >= Amount::ZERO => Some(limit),
+            _ => return Err(PageError::invalid("monthly limit", value)),
+        },
+    };
+
+    state
+        .service
+        .set_spend_limit(&form.user_id, monthly_limit)
+        .await?;
+    log::info!(
+        "{} ({}) set the monthly spend limit of {} to {:?}",
+        user.email,
+        client,
+        form.user_id,
+        monthly_limit.map(|l| l.to_string())
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/limits")).into_response())
+}
+
+/// The submitted cost alert form; an empty threshold turns that alert off.
+#[derive(Deserialize)]
+pub struct CostAlertForm {
+    #[serde(default)]
+    pub daily_threshold: String,
+    #[serde(default)]
+    pub monthly_threshold: String,
+}
+
+/// Parses an optional non-negative amount from a form field.
+fn parse_threshold(what: &str, value: &str) -> Result<Option<Amount>, PageError> {
+    match value.trim() {
+        "" => Ok(None),
+        value => match value.parse::<Amount>() {
+            Ok(amount) if amount >= Amount::ZERO => Ok(Some(amount)),
+            _ => Err(PageError::invalid(what, value)),
+        },
+    }
+}
+
+/// The logged-in user's own cost alert, whatever their role. Users the
+/// gateway does not know get a page saying so.
+pub async fn render_cost_alert(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let Some(user_id) = resolve_current_user_id(state.service.as_ref(), &user.email).await else {
+        return Ok(Html(pages::alerts::render(&state.base_path, None, None, Amount::ZERO))
+            .into_response());
+    };
+
+    let config = state.config.load();
+    let today = Utc::now().with_timezone(&config.timezone).date_naive();
+    let (start, end) = resolve_period("month", today);
+    let alert = state.service.get_cost_alert(&user_id).await?;
+    let spent = state
+        .service
+        .get_cost_by_model_for_user(start, end + chrono::Duration::days(1), &user_id, config.metric)
+        .await?
+        .iter()
+        .map(|c| c.amount)
+        .sum();
+
+    Ok(Html(pages::alerts::render(
+        &state.base_path,
+        Some(&user.email),
+        alert.as_ref(),
+        spent,
+    ))
+    .into_response())
+}
+
+pub async fn save_cost_alert(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<CostAlertForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    let Some(user_id) = resolve_current_user_id(state.service.as_ref(), &user.email).await else {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    };
+    let daily = parse_threshold("daily threshold", &form.daily_threshold)?;
+    let monthly = parse_threshold("monthly threshold", &form.monthly_threshold)?;
+
+    state.service.set_cost_alert(&user_id, daily, monthly).await?;
+    log::info!(
+        "{} ({}) set their cost alert to {:?} daily, {:?} monthly",
+        user.email,
+        client,
+        daily.map(|a| a.to_string()),
+        monthly.map(|a| a.to_string())
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/me/alerts")).into_response())
+}
+
+/// The submitted model price form.
+#[derive(Deserialize)]
+pub struct ModelPriceForm {
+    pub model_id: String,
+    pub effective_from: String,
+    pub input_per_1k: String,
+    pub output_per_1k: String,
+}
+
+/// Identifies the price to delete.
+#[derive(Deserialize)]
+pub struct DeleteModelPriceForm {
+    pub model_id: String,
+    pub effective_from: String,
+}
+
+fn parse_price(what: &str, value: &str) -> Result<Amount, PageError> {
+    match value.trim().parse::<Amount>() {
+        Ok(price) if price >= Amount::ZERO => Ok(price),
+        _ => Err(PageError::invalid(what, value)),
+    }
+}
+
+pub async fn render_model_prices(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let prices = state.service.list_model_prices().await?;
+    let models = state.service.list_models().await?;
+
+    Ok(Html(pages::prices::render(&state.base_path, &prices, &models)).into_response())
+}
+
+pub async fn save_model_price(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<ModelPriceForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    check_id("model id", &form.model_id)?;
+    let price = ModelPrice {
+        model_id: form.model_id,
+        model_name: None,
+        effective_from: parse_date(&form.effective_from)?,
+        input_per_1k: parse_price("input price", &form.input_per_1k)?,
+        output_per_1k: parse_price("output price", &form.output_per_1k)?,
+    };
+
+    state.service.set_model_price(&price).await?;
+    log::info!(
+        "{} ({}) set the price of {} from {} to {} input / {} output per 1K tokens",
+        user.email,
+        client,
+        price.model_id,
+        price.effective_from,
+        price.input_per_1k,
+        price.output_per_1k
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/prices")).into_response())
+}
+
+pub async fn delete_model_price(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<DeleteModelPriceForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    check_id("model id", &form.model_id)?;
+    let effective_from = parse_date(&form.effective_from)?;
+
+    state
+        .service
+        .delete_model_price(&form.model_id, effective_from)
+        .await?;
+    log::info!(
+        "{} ({}) deleted the price of {} from {}",
+        user.email,
+        client,
+        form.model_id,
+        effective_from
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/prices")).into_response())
+}
+
+/// The submitted model family rule.
+#[derive(Deserialize)]
+pub struct ModelFamilyForm {
+    pub prefix: String,
+    pub family: String,
+}
+
+/// Identifies the family rule to delete.
+#[derive(Deserialize)]
+pub struct DeleteModelFamilyForm {
+    pub prefix: String,
+}
+
+pub async fn render_model_families(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let rules = state.service.list_model_families().await?;
+    let models = state.service.list_models().await?;
+
+    Ok(Html(pages::families::render_rules(&state.base_path, &rules, &models)).into_response())
+}
+
+pub async fn save_model_family(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<ModelFamilyForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let rule = ModelFamily {
+        prefix: form.prefix.trim().to_string(),
+        family: form.family.trim().to_string(),
+    };
+    if rule.prefix.is_empty() {
+        return Err(PageError::invalid("prefix", &form.prefix));
+    }
+    if rule.family.is_empty() {
+        return Err(PageError::invalid("family", &form.family));
+    }
+
+    state.service.set_model_family(&rule).await?;
+    log::info!(
+        "{} ({}) rolled models starting with {:?} up into {:?}",
+        user.email,
+        client,
+        rule.prefix,
+        rule.family
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/families")).into_response())
+}
+
+pub async fn delete_model_family(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<DeleteModelFamilyForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    state.service.delete_model_family(&form.prefix).await?;
+    log::info!(
+        "{} ({}) deleted the model family rule for {:?}",
+        user.email,
+        client,
+        form.prefix
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/families")).into_response())
+}
+
+/// A label to attach or remove. `target` is `user:<id>` or `model:<id>`.
+#[derive(Deserialize)]
+pub struct LabelForm {
+    pub target: String,
+    pub label: String,
+}
+
+impl LabelForm {
+    fn to_label(&self) -> Result<Label, PageError> {
+        let (target, target_id) = self
+            .target
+            .split_once(':')
+            .and_then(|(kind, id)| Some((LabelTarget::parse(kind)?, id)))
+            .filter(|(_, id)| !id.is_empty())
+            .ok_or_else(|| PageError::invalid("target", &self.target))?;
+        let label = self.label.trim();
+        if label.is_empty() {
+            return Err(PageError::invalid("label", &self.label));
+        }
+        Ok(Label {
+            target,
+            target_id: target_id.to_string(),
+            label: label.to_string(),
+        })
+    }
+}
+
+pub async fn render_labels(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let labels = state.service.list_labels().await?;
+    let users = state.service.list_users().await?;
+    let models = state.service.list_models().await?;
+
+    Ok(Html(pages::labels::render_editor(
+        &state.base_path,
+        &labels,
+        &users,
+        &models,
+    ))
+    .into_response())
+}
+
+pub async fn save_label(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<LabelForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let label = form.to_label()?;
+
+    state.service.add_label(&label).await?;
+    log::info!(
+        "{} ({}) labeled {} {} {:?}",
+        user.email,
+        client,
+        label.target.as_str(),
+        label.target_id,
+        label.label
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/labels")).into_response())
+}
+
+pub async fn delete_label(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<LabelForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let label = form.to_label()?;
+
+    state.service.remove_label(&label).await?;
+    log::info!(
+        "{} ({}) removed label {:?} from {} {}",
+        user.email,
+        client,
+        label.label,
+        label.target.as_str(),
+        label.target_id
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/labels")).into_response())
+}
+
+/// The submitted report subscription.
+#[derive(Deserialize)]
+pub struct SubscriptionForm {
+    pub recipient: String,
+    pub report: String,
+    pub schedule: String,
+}
+
+/// Identifies the report subscription to delete.
+#[derive(Deserialize)]
+pub struct DeleteSubscriptionForm {
+    pub id: i64,
+}
+
+pub async fn render_subscriptions(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let subscriptions = state.service.list_report_subscriptions().await?;
+
+    Ok(Html(pages::subscriptions::render(
+        &state.base_path,
+        &subscriptions,
+    ))
+    .into_response())
+}
+
+pub async fn save_subscription(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<SubscriptionForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let recipient = form.recipient.trim();
+    if !recipient.contains('@') {
+        return Err(PageError::invalid("recipient", &form.recipient));
+    }
+    let kind = ReportKind::parse(&form.report)
+        .ok_or_else(|| PageError::invalid("report", &form.report))?;
+    let schedule = ReportSchedule::parse(&form.schedule)
+        .ok_or_else(|| PageError::invalid("schedule", &form.schedule))?;
+
+    state
+        .service
+        .add_report_subscription(recipient, kind, schedule)
+        .await?;
+    log::info!(
+        "{} ({}) subscribed {} to the {} report, sent {}",
+        user.email,
+        client,
+        recipient,
+        kind.as_str(),
+        schedule.as_str()
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/subscriptions")).into_response())
+}
+
+pub async fn delete_subscription(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Form(form): Form<DeleteSubscriptionForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    state.service.delete_report_subscription(form.id).await?;
+    log::info!(
+        "{} ({}) deleted report subscription {}",
+        user.email,
+        client,
+        form.id
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/subscriptions")).into_response())
+}
+
+/// The submitted adjustment form, for both new and edited rows.
+#[derive(Deserialize)]
+pub struct AdjustmentForm {
+    pub date: String,
+    pub user_id: String,
+    pub model_id: String,
+    pub metric: String,
+    pub amount: String,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Identifies the adjustment to delete.
+#[derive(Deserialize)]
+pub struct DeleteAdjustmentForm {
+    pub date: String,
+    pub user_id: String,
+    pub model_id: String,
+    pub metric: String,
+}
+
+fn parse_metric(metric: &str) -> Result<Metric, PageError> {
+    metric
+        .parse()
+        .map_err(|_| PageError::invalid("metric", metric))
+}
+
+pub async fn render_adjustments(
+    session: Session,
+    State(state): State<AppState>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+
+    let period = get_period(&params);
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+
+    // Include adjustments dated today.
+    let end = end.succ_opt().unwrap_or(end);
+    let adjustments = state.service.list_adjustments(start, end).await?;
+    let users = state.service.list_users().await?;
+    let models = state.service.list_models().await?;
+
+    Ok(Html(pages::adjustments::render(
+        &state.base_path,
+        &nav,
+        &adjustments,
+        &users,
+        &models,
+        metric,
+    ))
+    .into_response())
+}
+
+pub async fn save_adjustment(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Query(params): Query<PeriodParams>,
+    Form(form): Form<AdjustmentForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    check_id("user id", &form.user_id)?;
+    check_id("model id", &form.model_id)?;
+    let adjustment = CostAdjustment {
+        date: parse_date(&form.date)?,
+        user_id: form.user_id,
+        user_email: None,
+        model_id: form.model_id,
+        model_name: None,
+        metric: parse_metric(&form.metric)?,
+        amount: form
+            .amount
+            .trim()
+            .parse()
+            .map_err(|_| PageError::invalid("amount", &form.amount))?,
+        currency: "USD".to_string(),
+        note: form.note.trim().to_string(),
+    };
+
+    state.service.set_adjustment(&adjustment).await?;
+    log::info!(
+        "{} ({}) set the {} adjustment of {} on {} for {} to {} ({})",
+        user.email,
+        client,
+        adjustment.metric,
+        adjustment.user_id,
+        adjustment.date,
+        adjustment.model_id,
+        adjustment.amount,
+        adjustment.note
+    );
+    let path = pages::make_path(&state.base_path, "/adjustments");
+    Ok(Redirect::to(&pages::with_period(&path, &get_period(&params))).into_response())
+}
+
+pub async fn delete_adjustment(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Query(params): Query<PeriodParams>,
+    Form(form): Form<DeleteAdjustmentForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    check_id("user id", &form.user_id)?;
+    check_id("model id", &form.model_id)?;
+    let date = parse_date(&form.date)?;
+    let metric = parse_metric(&form.metric)?;
+
+    state
+        .service
+        .delete_adjustment(date, &form.user_id, &form.model_id, metric)
+        .await?;
+    log::info!(
+        "{} ({}) deleted the {} adjustment of {} on {} for {}",
+        user.email,
+        client,
+        metric,
+        form.user_id,
+        date,
+        form.model_id
+    );
+    let path = pages::make_path(&state.base_path, "/adjustments");
+    Ok(Redirect::to(&pages::with_period(&path, &get_period(&params))).into_response())
+}
+
+pub async fn refresh_rollup(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Query(params): Query<PeriodParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    state.service.refresh_monthly_rollup().await?;
+    log::info!("{} ({}) rebuilt the monthly rollup", user.email, client);
+    let path = pages::make_path(&state.base_path, "/adjustments");
+    Ok(Redirect::to(&pages::with_period(&path, &get_period(&params))).into_response())
+}
+
+/// What the server holds and how CE is answering, for debugging slow or
+/// empty pages.
+pub async fn render_diagnostics(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let diagnostics = state.service.diagnostics().await;
+    let reload = reload::last_reload();
+    Ok(Html(pages::diagnostics::render(
+        &state.base_path,
+        &diagnostics,
+        reload.as_ref(),
+    ))
+    .into_response())
+}
+
+/// The latest requested batch runs, with a button queueing another.
+pub async fn render_batch_runs(
+    session: Session,
+    State(state): State<AppState>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let requests = state
+        .service
+        .list_batch_requests(pages::batch::SHOWN_REQUESTS)
+        .await?;
+    Ok(Html(pages::batch::render(&state.base_path, &requests)).into_response())
+}
+
+/// Queues an incremental batch run for the batch daemon, so admins need no
+/// shell access for a manual refresh.
+pub async fn request_batch_run(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    state.service.request_batch_run(&user.email).await?;
+    log::info!("{} ({}) requested a batch run", user.email, client);
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/admin/batch")).into_response())
+}
+
+/// Fetches the last few days of the default metric from CE into the cost
+/// table within the request, without the alerts and reports of a batch run.
+pub async fn refresh_costs(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+
+    let metric = state.config.load().metric;
+    let rows = state.service.refresh_costs(metric).await?;
+    log::info!(
+        "{} ({}) refreshed costs from CE, {} rows stored",
+        user.email,
+        client,
+        rows
+    );
+    Ok(Redirect::to(&pages::make_path(&state.base_path, "/admin/batch")).into_response())
+}
 
-pub async fn render_monthly_costs(
+/// Loads an uploaded cost file from another provider into the cost table.
+pub async fn import_costs(
     session: Session,
     State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+    mut multipart: Multipart,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
 
-    let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = resolve_period(&period);
+    let bad_upload = |e: axum::extract::multipart::MultipartError| {
+        PageError::BadRequest(format!("Invalid upload: {}", e.body_text()))
+    };
+    let mut source = String::new();
+    let mut file = None;
+    while let Some(field) = multipart.next_field().await.map_err(bad_upload)? {
+        match field.name() {
+            Some("source") => {
+                source = field.text().await.map_err(bad_upload)?.trim().to_string();
+            }
+            Some("file") => {
+                let name = field.file_name().unwrap_or_default().to_string();
+                file = Some((name, field.text().await.map_err(bad_upload)?));
+            }
+            _ => {}
+        }
+    }
+    common::check_source(&source).map_err(|e| PageError::BadRequest(e.to_string()))?;
+    let Some((name, text)) = file else {
+        return Err(PageError::BadRequest("No file uploaded".to_string()));
+    };
+    let format = common::ImportFormat::from_file_name(&name);
+    let rows = common::parse_import(&text, format, &source, state.service.tenant())
+        .map_err(|e| PageError::BadRequest(format!("Invalid cost file {name:?}: {e}")))?;
+
+    state.service.import_costs(&rows).await?;
+    log::info!(
+        "{} ({}) imported {} cost rows from {:?} as {}",
+        user.email,
+        client,
+        rows.len(),
+        name,
+        source
+    );
+    let path = pages::make_path(&state.base_path, "/adjustments");
+    Ok(Redirect::to(&pages::with_period(&path, &get_period(&params))).into_response())
+}
 
-    #[cfg(feature = "admin")]
-    {
-        let monthly_cost = state.service.get_monthly_cost(snap_to_month_start(start), end).await;
-        let monthly_cost = pages::sort_records(monthly_cost, sort, &order);
+#[derive(Deserialize)]
+pub struct SourceParams {
+    pub source: Option<String>,
+}
 
-        Html(pages::monthly::render(
-            &state.base_path,
-            &period,
-            page,
-            &monthly_cost,
-        ))
-        .into_response()
+/// Stores the header filter's source for the session and returns to the
+/// page the filter was used on.
+pub async fn choose_source(
+    session: Session,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<SourceParams>,
+) -> Response {
+    if let Err(redirect) = require_login(&session, &state).await {
+        return redirect;
     }
+    get_source(&session, Some(params.source.as_deref().unwrap_or(""))).await;
+    let back = headers
+        .get(header::REFERER)
+        .and_then(|referer| referer.to_str().ok()?.parse::<Uri>().ok())
+        .and_then(|uri| uri.path_and_query().map(|p| p.to_string()))
+        .filter(|path| pages::is_local_path(path))
+        .unwrap_or_else(|| pages::make_path(&state.base_path, ""));
+    Redirect::to(&back).into_response()
+}
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let monthly_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_monthly_cost_for_user(snap_to_month_start(start), end, uid).await
-        } else {
-            vec![]
-        };
-        let monthly_cost = pages::sort_records(monthly_cost, sort, &order);
+#[derive(Deserialize)]
+pub struct ShareParams {
+    pub path: Option<String>,
+}
 
-        Html(pages::monthly::render(
-            &state.base_path,
-            &period,
-            page,
-            &monthly_cost,
-        ))
-        .into_response()
-    }
+#[derive(Deserialize)]
+pub struct ShareForm {
+    pub path: String,
+    pub days: String,
 }
 
-pub async fn render_month_hub(
+pub async fn render_share(
     session: Session,
     State(state): State<AppState>,
-    Path(month): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+    Query(share): Query<ShareParams>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let nav = get_nav(&params);
+    let config = state.config.load();
 
-    let period = get_period(&params);
-    let (start, end) = parse_month_range(&month);
+    Ok(Html(pages::share::render(
+        &state.base_path,
+        &nav,
+        share.path.as_deref().unwrap_or_default(),
+        config.share_max_days,
+        !config.share_secret.is_empty(),
+        None,
+    ))
+    .into_response())
+}
 
-    #[cfg(feature = "admin")]
-    {
-        let daily_cost = state.service.get_daily_cost(start, end).await;
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
-        let currency = daily_cost
-            .first()
-            .map(|r| r.currency.as_str())
-            .unwrap_or("USD");
-        let users = state.service.get_cost_by_user(start, end).await;
-        let models = state.service.get_cost_by_model(start, end).await;
+/// Signs `form.path`, pinned to the admin's current metric unless it names
+/// one, for `form.days` days.
+pub async fn create_share_link(
+    session: Session,
+    State(state): State<AppState>,
+    Extension(client): Extension<ClientInfo>,
+    Query(params): Query<PeriodParams>,
+    Form(form): Form<ShareForm>,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
+    };
+    if user.role != Role::Admin {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
+    let nav = get_nav(&params);
+    let config = state.config.load();
+    if config.share_secret.is_empty() {
+        return Err(PageError::BadRequest(
+            "Share links need share_secret to be set".to_string(),
+        ));
+    }
 
-        Html(pages::monthly::render_hub(
-            &state.base_path,
-            &period,
-            &month,
-            total_cost,
-            currency,
-            users.len(),
-            models.len(),
-        ))
-        .into_response()
+    let path = form.path.trim();
+    let query = path.split_once('?').map(|(_, query)| query).unwrap_or_default();
+    let has_param = |name: &str| {
+        query
+            .split('&')
+            .any(|pair| pair.split('=').next() == Some(name))
+    };
+    if !path.starts_with('/') || path.starts_with("//") || has_param(share::SHARE_PARAM) {
+        return Err(PageError::invalid("report path", path));
     }
+    let days = form
+        .days
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .filter(|days| (1..=config.share_max_days).contains(days))
+        .ok_or_else(|| PageError::invalid("number of days", &form.days))?;
+    let target = if has_param("metric") {
+        path.to_string()
+    } else {
+        pages::with_query(path, "metric", metric.as_str())
+    };
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let daily_cost = if let Some(ref uid) = current_user_id {
-            state.service.get_daily_cost_for_user(start, end, uid).await
-        } else {
-            vec![]
-        };
-        let total_cost: f64 = daily_cost.iter().map(|r| r.amount).sum();
-        let currency = daily_cost
-            .first()
-            .map(|r| r.currency.as_str())
-            .unwrap_or("USD");
-        let users = if let Some(ref uid) = current_user_id {
-            let all = state.service.get_cost_by_user(start, end).await;
-            all.into_iter()
-                .filter(|c| c.user_id == *uid)
-                .collect::<Vec<_>>()
-        } else {
-            vec![]
-        };
-        let models = if let Some(ref uid) = current_user_id {
-            state
-                .service
-                .get_cost_by_model_for_user(start, end, uid)
-                .await
-        } else {
-            vec![]
-        };
+    let expires = Utc::now() + chrono::Duration::days(days);
+    let link = share::sign(&config.share_secret, &target, expires);
+    let url = match client.origin() {
+        Some(myhandlers::Origin(origin)) => format!("{origin}{link}"),
+        None => link,
+    };
+    log::info!(
+        "{} ({}) shared {} until {}",
+        user.email,
+        client,
+        target,
+        expires.format("%Y-%m-%d %H:%M UTC")
+    );
+    let tz = params.timezone.unwrap_or(config.timezone);
+    let link = pages::share::ShareLink {
+        url,
+        expires: expires.with_timezone(&tz).format("%Y-%m-%d %H:%M %Z").to_string(),
+    };
 
-        Html(pages::monthly::render_hub(
-            &state.base_path,
-            &period,
-            &month,
-            total_cost,
-            currency,
-            users.len(),
-            models.len(),
-        ))
-        .into_response()
-    }
+    Ok(Html(pages::share::render(
+        &state.base_path,
+        &nav,
+        &target,
+        config.share_max_days,
+        true,
+        Some(link),
+    ))
+    .into_response())
 }
 
-pub async fn render_month_users(
+pub async fn render_cost_estimates(
     session: Session,
     State(state): State<AppState>,
-    Path(month): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = parse_month_range(&month);
-
-    #[cfg(feature = "admin")]
-    {
-        let costs = state.service.get_cost_by_user(start, end).await;
-        let costs = pages::sort_by_user(costs, sort, &order);
-
-        Html(pages::monthly::render_users(
-            &state.base_path,
-            &period,
-            page,
-            &month,
-            &costs,
-        ))
-        .into_response()
-    }
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let costs = state.service.get_cost_by_user(start, end).await;
-        let costs: Vec<_> = if let Some(ref uid) = current_user_id {
-            costs.into_iter().filter(|c| c.user_id == *uid).collect()
-        } else {
-            costs
-        };
-        let costs = pages::sort_by_user(costs, sort, &order);
+    let actual = state.service.get_cost_by_model(start, end, metric).await?;
+    let prices = state.service.list_model_prices().await?;
+    let usage = state.service.get_token_usage_by_model(start, end).await?;
+    let estimates = estimate_costs(&prices, &usage);
 
-        Html(pages::monthly::render_users(
-            &state.base_path,
-            &period,
-            page,
-            &month,
-            &costs,
-        ))
-        .into_response()
-    }
+    Ok(Html(pages::prices::render_variance(
+        &state.base_path,
+        &nav,
+        &actual,
+        &estimates,
+        user.role == Role::Admin,
+    ))
+    .into_response())
 }
 
-pub async fn render_month_models(
+/// Models and users ranked by cost per 1M tokens over the period.
+pub async fn render_efficiency(
     session: Session,
     State(state): State<AppState>,
-    Path(month): Path<String>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = parse_month_range(&month);
-
-    #[cfg(feature = "admin")]
-    {
-        let costs = state.service.get_cost_by_model(start, end).await;
-        let costs = pages::sort_by_model(costs, sort, &order);
-
-        Html(pages::monthly::render_models(
-            &state.base_path,
-            &period,
-            page,
-            &month,
-            &costs,
-        ))
-        .into_response()
-    }
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
 
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let costs = if let Some(ref uid) = current_user_id {
-            state
-                .service
-                .get_cost_by_model_for_user(start, end, uid)
-                .await
-        } else {
-            vec![]
-        };
-        let costs = pages::sort_by_model(costs, sort, &order);
+    let models = state.service.get_cost_by_model(start, end, metric).await?;
+    let users = state.service.get_cost_by_user(start, end, metric).await?;
+    let model_tokens = state.service.get_token_usage_by_model(start, end).await?;
+    let user_tokens = state.service.get_token_usage_by_user(start, end).await?;
 
-        Html(pages::monthly::render_models(
-            &state.base_path,
-            &period,
-            page,
-            &month,
-            &costs,
-        ))
-        .into_response()
-    }
+    Ok(Html(pages::efficiency::render(
+        &state.base_path,
+        &nav,
+        &models,
+        &users,
+        &model_tokens,
+        &user_tokens,
+    ))
+    .into_response())
 }
 
-pub async fn render_month_models_for_user(
+/// Per-model on-demand against amortized cost. The metric selection does
+/// not apply; the page always compares the two.
+pub async fn render_amortization(
     session: Session,
     State(state): State<AppState>,
-    Path((month, user_id)): Path<(String, String)>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
-
-    #[cfg(not(feature = "admin"))]
-    {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        if current_user_id.as_deref() != Some(user_id.as_str()) {
-            return StatusCode::FORBIDDEN.into_response();
-        }
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
     }
+    let params = apply_preferences(&session, &state, params).await;
+    let state = state.with_source(params.source.as_deref());
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = parse_month_range(&month);
-    let user_email = state
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
+
+    let on_demand = state
         .service
-        .get_user_email(&user_id)
-        .await
-        .unwrap_or_else(|| "unknown".to_string());
-    let costs = state
+        .get_cost_by_model(start, end, Metric::Unblended)
+        .await?;
+    let amortized = state
         .service
-        .get_cost_by_model_for_user(start, end, &user_id)
-        .await;
-    let costs = pages::sort_by_model(costs, sort, &order);
+        .get_cost_by_model(start, end, Metric::Amortized)
+        .await?;
 
-    Html(pages::monthly::render_user_models(
+    Ok(Html(pages::amortization::render(
         &state.base_path,
-        &period,
-        page,
-        &month,
-        &user_email,
-        &costs,
+        &nav,
+        &on_demand,
+        &amortized,
     ))
-    .into_response()
+    .into_response())
 }
 
-pub async fn render_month_users_for_model(
+/// CE credits and refunds of the period by day, user and model, with the
+/// gross CE cost they were taken off.
+pub async fn render_credits(
     session: Session,
     State(state): State<AppState>,
-    Path((month, model_id)): Path<(String, String)>,
     Query(params): Query<PeriodParams>,
-) -> Response {
-    let _email = match require_login(&session).await {
-        Ok(email) => email,
-        Err(redirect) => return redirect,
+) -> Result<Response, PageError> {
+    let user = match require_login(&session, &state).await {
+        Ok(user) => user,
+        Err(redirect) => return Ok(redirect),
     };
+    if !user.role.sees_all_costs() {
+        return Ok(StatusCode::FORBIDDEN.into_response());
+    }
+    let params = apply_preferences(&session, &state, params).await;
+    let metric = get_metric(&session, &params, &state).await;
 
     let period = get_period(&params);
-    let page = get_page(&params);
-    let sort = get_sort(&params);
-    let order = get_order(&params);
-    let (start, end) = parse_month_range(&month);
-    let model_name = state
-        .service
-        .get_model_name(&model_id)
-        .await
-        .unwrap_or_else(|| "unknown".to_string());
+    let nav = get_nav(&params);
+    let (start, end) = resolve_period(&period, today(&params, &state));
 
-    #[cfg(feature = "admin")]
-    let costs = state
+    let gross: Amount = state
         .service
-        .get_cost_by_user_for_model(start, end, &model_id)
-        .await;
-
-    #[cfg(not(feature = "admin"))]
-    let costs = {
-        let current_user_id = resolve_current_user_id(state.service.as_ref(), &_email).await;
-        let all = state
-            .service
-            .get_cost_by_user_for_model(start, end, &model_id)
-            .await;
-        if let Some(ref uid) = current_user_id {
-            all.into_iter().filter(|c| c.user_id == *uid).collect()
-        } else {
-            vec![]
-        }
-    };
-
-    let costs = pages::sort_by_user(costs, sort, &order);
-
-    Html(pages::monthly::render_model_users(
+        .with_source(Some(AWS_SOURCE))
+        .get_daily_cost(start, end, metric)
+        .await?
+        .iter()
+        .map(|r| r.amount)
+        .sum();
+    let credits = state.service.with_source(Some(CREDIT_SOURCE));
+    let daily = credits.get_daily_cost(start, end, metric).await?;
+    let users = credits.get_cost_by_user(start, end, metric).await?;
+    let models = credits.get_cost_by_model(start, end, metric).await?;
+
+    Ok(Html(pages::credits::render(
         &state.base_path,
-        &period,
-        page,
-        &month,
-        &model_name,
-        &costs,
+        &nav,
+        gross,
+        &daily,
+        &users,
+        &models,
     ))
-    .into_response()
+    .into_response())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, 15).unwrap()
+    }
+
     #[test]
     fn resolve_period_7d() {
-        let (start, end) = resolve_period("7d");
+        let (start, end) = resolve_period("7d", today());
         assert_eq!((end - start).num_days(), 6);
     }
 
+    #[test]
+    fn resolve_period_ends_today() {
+        assert_eq!(resolve_period("7d", today()).1, today());
+        assert_eq!(
+            resolve_period("month", today()).0,
+            NaiveDate::from_ymd_opt(2025, 3, 1).unwrap()
+        );
+    }
+
     #[test]
     fn resolve_period_30d() {
-        let (start, end) = resolve_period("30d");
+        let (start, end) = resolve_period("30d", today());
         assert_eq!((end - start).num_days(), 29);
     }
 
     #[test]
     fn resolve_period_month() {
-        let (start, end) = resolve_period("month");
+        let (start, end) = resolve_period("month", today());
         assert_eq!(start.day(), 1);
         assert_eq!(start.month(), end.month());
     }
 
     #[test]
     fn resolve_period_last_month() {
-        let (start, end) = resolve_period("last_month");
+        let (start, end) = resolve_period("last_month", today());
         assert_eq!(start.day(), 1);
         assert_eq!(start.month(), end.month());
         // end should be last day of that month
@@ -1393,28 +4265,58 @@ mod tests {
 
     #[test]
     fn resolve_period_3m() {
-        let (start, end) = resolve_period("3m");
+        let (start, end) = resolve_period("3m", today());
         assert_eq!((end - start).num_days(), 90);
     }
 
     #[test]
     fn resolve_period_6m() {
-        let (start, end) = resolve_period("6m");
+        let (start, end) = resolve_period("6m", today());
         assert_eq!((end - start).num_days(), 180);
     }
 
     #[test]
     fn resolve_period_12m() {
-        let (start, end) = resolve_period("12m");
+        let (start, end) = resolve_period("12m", today());
         assert_eq!((end - start).num_days(), 365);
     }
 
     #[test]
     fn resolve_period_default() {
-        let (start, end) = resolve_period("unknown");
+        let (start, end) = resolve_period("unknown", today());
         assert_eq!((end - start).num_days(), 29);
     }
 
+    #[test]
+    fn previous_period_is_adjacent_and_equal_length() {
+        let start = NaiveDate::from_ymd_opt(2024, 3, 1).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 3, 31).unwrap();
+        let (prev_start, prev_end) = previous_period(start, end);
+        assert_eq!(prev_end, start);
+        assert_eq!(prev_start.to_string(), "2024-01-31");
+    }
+
+    #[test]
+    fn previous_months_covers_whole_months() {
+        let start = NaiveDate::from_ymd_opt(2024, 3, 17).unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 4, 16).unwrap();
+        let (prev_start, prev_end, months) = previous_months(start, end);
+        assert_eq!(prev_start.to_string(), "2024-01-01");
+        assert_eq!(prev_end.to_string(), "2024-03-01");
+        assert_eq!(months, Months::new(2));
+    }
+
+    #[test]
+    fn shift_records_moves_dates() {
+        let records = vec![CostRecord {
+            date: "2024-01-01".to_string(),
+            amount: Amount::from_f64(1.0),
+            currency: "USD".to_string(),
+        }];
+        let shifted = shift_records(records, |d| d.checked_add_months(Months::new(2)));
+        assert_eq!(shifted[0].date, "2024-03-01");
+    }
+
     #[test]
     fn get_period_default() {
         let params = PeriodParams {
@@ -1422,6 +4324,14 @@ mod tests {
             page: None,
             sort: None,
             order: None,
+            dir: None,
+            from: None,
+            compare: None,
+            metric: None,
+            source: None,
+            sources: Vec::new(),
+            page_size: None,
+            timezone: None,
         };
         assert_eq!(get_period(&params), "30d");
     }
@@ -1433,35 +4343,103 @@ mod tests {
             page: None,
             sort: None,
             order: None,
+            dir: None,
+            from: None,
+            compare: None,
+            metric: None,
+            source: None,
+            sources: Vec::new(),
+            page_size: None,
+            timezone: None,
         };
         assert_eq!(get_period(&params), "7d");
     }
 
     #[test]
     fn parse_month_range_january() {
-        let (start, end) = parse_month_range("2024-01");
+        let (start, end) = parse_month_range("2024-01").unwrap();
         assert_eq!(start.to_string(), "2024-01-01");
         assert_eq!(end.to_string(), "2024-01-31");
     }
 
     #[test]
     fn parse_month_range_february_leap() {
-        let (start, end) = parse_month_range("2024-02");
+        let (start, end) = parse_month_range("2024-02").unwrap();
         assert_eq!(start.to_string(), "2024-02-01");
         assert_eq!(end.to_string(), "2024-02-29");
     }
 
     #[test]
     fn parse_month_range_february_non_leap() {
-        let (start, end) = parse_month_range("2023-02");
+        let (start, end) = parse_month_range("2023-02").unwrap();
         assert_eq!(start.to_string(), "2023-02-01");
         assert_eq!(end.to_string(), "2023-02-28");
     }
 
     #[test]
     fn parse_month_range_december() {
-        let (start, end) = parse_month_range("2024-12");
+        let (start, end) = parse_month_range("2024-12").unwrap();
         assert_eq!(start.to_string(), "2024-12-01");
         assert_eq!(end.to_string(), "2024-12-31");
     }
+
+    #[test]
+    fn parse_week_is_monday_to_sunday() {
+        let (start, end) = parse_week("2025-W01").unwrap();
+        assert_eq!(start.to_string(), "2024-12-30");
+        assert_eq!(end.to_string(), "2025-01-05");
+        assert!(parse_week("2025-W54").is_err());
+        assert!(parse_week("2025-01").is_err());
+        assert_eq!(
+            snap_to_week_start(NaiveDate::from_ymd_opt(2025, 1, 5).unwrap()).to_string(),
+            "2024-12-30"
+        );
+    }
+
+    #[test]
+    fn page_error_is_bad_gateway() {
+        let err = anyhow::anyhow!("timed out").context("Failed to query daily cost");
+        let response = PageError::from(err).into_response();
+        assert_eq!(response.status(), StatusCode::BAD_GATEWAY);
+    }
+
+    #[test]
+    fn parse_month_range_rejects_garbage() {
+        assert!(parse_month_range("2024-13").is_none());
+        assert!(parse_month_range("2024-01-15").is_none());
+        assert!(parse_month_range("latest").is_none());
+    }
+
+    #[test]
+    fn invalid_path_params_are_bad_request() {
+        assert!(parse_date("2024-02-30").is_err());
+        assert!(parse_date("2024-02-29").is_ok());
+        assert!(check_id("user id", "not-a-uuid").is_err());
+        assert!(check_id("user id", "6f1c2c4e-8d4b-4d0e-9a52-3b1f0f4a9c11").is_ok());
+        let response = PageError::invalid("date", "yesterday").into_response();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn get_sort_accepts_index_or_name() {
+        let params = |sort: &str, dir: Option<&str>| PeriodParams {
+            period: None,
+            page: None,
+            sort: Some(sort.to_string()),
+            order: Some("asc".to_string()),
+            dir: dir.map(str::to_string),
+            from: None,
+            compare: None,
+            metric: None,
+            source: None,
+            sources: Vec::new(),
+            page_size: None,
+            timezone: None,
+        };
+        assert_eq!(get_sort(&params("1", None), pages::DATE_SORT), Some(1));
+        assert_eq!(get_sort(&params("Cost", None), pages::DATE_SORT), Some(1));
+        assert_eq!(get_sort(&params("bogus", None), pages::DATE_SORT), None);
+        assert_eq!(get_order(&params("cost", None)), "asc");
+        assert_eq!(get_order(&params("cost", Some("desc"))), "desc");
+    }
 }
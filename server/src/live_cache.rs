@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+
+use anyhow::Result;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use common::{CostRow, Metric};
+
+/// Entries not refreshed for this long are dropped on the next store.
+const EVICT_AFTER_HOURS: i64 = 24;
+
+/// A tenant's `[start, end)` date range of live CE rows in one metric, for
+/// all its users and models; per-user and per-model pages filter the shared
+/// rows.
+pub type LiveKey = (String, NaiveDate, NaiveDate, Metric);
+
+/// One day's rows, cached per day so overlapping ranges share them.
+struct Entry {
+    rows: Vec<CostRow>,
+    fetched_at: DateTime<Utc>,
+    refreshing: bool,
+}
+
+/// What the cache holds for a range.
+pub struct Lookup {
+    /// Rows of every cached day, fresh or not.
+    pub rows: Vec<CostRow>,
+    /// Runs of uncached days, to fetch before serving the range.
+    pub missing: Vec<LiveKey>,
+    /// Runs of days older than the cache's max age, now marked refreshing.
+    /// The caller should serve their rows and refresh them in the background.
+    pub stale: Vec<LiveKey>,
+}
+
+/// What the cache holds across all ranges and metrics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CacheStats {
+    /// Cached (tenant, day, metric) entries.
+    pub entries: usize,
+    pub rows: usize,
+    pub oldest_day: Option<NaiveDate>,
+    pub newest_day: Option<NaiveDate>,
+    /// When the least recently fetched entry was fetched.
+    pub oldest_fetch: Option<DateTime<Utc>>,
+    /// Entries being refreshed in the background.
+    pub refreshing: usize,
+    /// Ranges being fetched for the first time.
+    pub in_flight: usize,
+}
+
+/// Live CE results served stale-while-revalidate, so pages only wait on CE
+/// for days that were never fetched.
+pub struct LiveCache {
+    max_age: Duration,
+    entries: Mutex<HashMap<(String, NaiveDate, Metric), Entry>>,
+    /// Ranges being fetched for the first time; concurrent misses wait on
+    /// the same lock instead of each querying CE.
+    in_flight: Mutex<HashMap<LiveKey, Arc<tokio::sync::Mutex<()>>>>,
+}
+
+impl LiveCache {
+    pub fn new(max_age_seconds: i64) -> Self {
+        Self {
+            max_age: Duration::seconds(max_age_seconds),
+            entries: Mutex::new(HashMap::new()),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn lookup(&self, key: &LiveKey, now: DateTime<Utc>) -> Lookup {
+        let (tenant, start, end, metric) = key;
+        let mut entries = self.entries.lock().unwrap();
+        let mut rows = Vec::new();
+        let mut missing = Vec::new();
+        let mut stale = Vec::new();
+        for day in days(*start, *end) {
+            match entries.get_mut(&(tenant.clone(), day, *metric)) {
+                Some(entry) => {
+                    rows.extend(entry.rows.iter().cloned());
+                    if now - entry.fetched_at >= self.max_age && !entry.refreshing {
+                        entry.refreshing = true;
+                        stale.push(day);
+                    }
+                }
+                None => missing.push(day),
+            }
+        }
+        Lookup {
+            rows,
+            missing: runs(tenant, &missing, *metric),
+            stale: runs(tenant, &stale, *metric),
+        }
+    }
+
+    /// Caches `rows` as the whole of `key`'s range: days without a row had
+    /// no cost.
+    pub fn store(&self, key: LiveKey, rows: Vec<CostRow>, now: DateTime<Utc>) {
+        let (tenant, start, end, metric) = key;
+        let mut by_day: HashMap<NaiveDate, Vec<CostRow>> = HashMap::new();
+        for row in rows {
+            by_day.entry(row.date).or_default().push(row);
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.retain(|_, e| now - e.fetched_at < Duration::hours(EVICT_AFTER_HOURS));
+        for day in days(start, end) {
+            entries.insert(
+                (tenant.clone(), day, metric),
+                Entry {
+                    rows: by_day.remove(&day).unwrap_or_default(),
+                    fetched_at: now,
+                    refreshing: false,
+                },
+            );
+        }
+    }
+
+    /// Lets the next lookup retry a refresh that failed.
+    pub fn refresh_failed(&self, key: &LiveKey) {
+        let (tenant, start, end, metric) = key;
+        let mut entries = self.entries.lock().unwrap();
+        for day in days(*start, *end) {
+            if let Some(entry) = entries.get_mut(&(tenant.clone(), day, *metric)) {
+                entry.refreshing = false;
+            }
+        }
+    }
+
+    /// When the oldest day of the range was fetched; `None` unless every day
+    /// is cached.
+    pub fn fetched_at(&self, key: &LiveKey) -> Option<DateTime<Utc>> {
+        let (tenant, start, end, metric) = key;
+        let entries = self.entries.lock().unwrap();
+        let mut oldest: Option<DateTime<Utc>> = None;
+        for day in days(*start, *end) {
+            let at = entries.get(&(tenant.clone(), day, *metric))?.fetched_at;
+            oldest = Some(oldest.map_or(at, |o| o.min(at)));
+        }
+        oldest
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let entries = self.entries.lock().unwrap();
+        let mut stats = CacheStats {
+            entries: entries.len(),
+            in_flight: self.in_flight.lock().unwrap().len(),
+            ..CacheStats::default()
+        };
+        for (&(_, day, _), entry) in entries.iter() {
+            stats.rows += entry.rows.len();
+            stats.oldest_day = Some(stats.oldest_day.map_or(day, |d| d.min(day)));
+            stats.newest_day = Some(stats.newest_day.map_or(day, |d| d.max(day)));
+            let at = entry.fetched_at;
+            stats.oldest_fetch = Some(stats.oldest_fetch.map_or(at, |o| o.min(at)));
+            if entry.refreshing {
+                stats.refreshing += 1;
+            }
+        }
+        stats
+    }
+
+    /// The rows of `key` if every day of it is cached.
+    fn cached(&self, key: &LiveKey) -> Option<Vec<CostRow>> {
+        let (tenant, start, end, metric) = key;
+        let entries = self.entries.lock().unwrap();
+        let mut rows = Vec::new();
+        for day in days(*start, *end) {
+            let entry = entries.get(&(tenant.clone(), day, *metric))?;
+            rows.extend(entry.rows.iter().cloned());
+        }
+        Some(rows)
+    }
+
+    /// Runs `fetch` for a missing range and caches its rows. Callers that
+    /// miss the same range meanwhile wait for it and get the cached rows.
+    pub async fn fetch_once<F, Fut>(&self, key: LiveKey, fetch: F) -> Result<Vec<CostRow>>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Vec<CostRow>>>,
+    {
+        let lock = self
+            .in_flight
+            .lock()
+            .unwrap()
+            .entry(key.clone())
+            .or_default()
+            .clone();
+        let _guard = lock.lock().await;
+        if let Some(rows) = self.cached(&key) {
+            return Ok(rows);
+        }
+        let fetched = fetch().await;
+        if let Ok(rows) = &fetched {
+            self.store(key.clone(), rows.clone(), Utc::now());
+        }
+        // Only once the rows are cached, so a miss in between waits on this
+        // fetch instead of starting another.
+        self.in_flight.lock().unwrap().remove(&key);
+        fetched
+    }
+}
+
+fn days(start: NaiveDate, end: NaiveDate) -> impl Iterator<Item = NaiveDate> {
+    start.iter_days().take_while(move |day| *day < end)
+}
+
+/// Groups ascending days into ranges of consecutive ones.
+fn runs(tenant: &str, days: &[NaiveDate], metric: Metric) -> Vec<LiveKey> {
+    let mut runs: Vec<LiveKey> = Vec::new();
+    for &day in days {
+        let next = day + Duration::days(1);
+        match runs.last_mut() {
+            Some((_, _, end, _)) if *end == day => *end = next,
+            _ => runs.push((tenant.to_string(), day, next, metric)),
+        }
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::DEFAULT_TENANT;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2025, 3, day).unwrap()
+    }
+
+    fn key() -> LiveKey {
+        range(1, 2, Metric::Blended)
+    }
+
+    fn range(start: u32, end: u32, metric: Metric) -> LiveKey {
+        (DEFAULT_TENANT.to_string(), date(start), date(end), metric)
+    }
+
+    fn row(day: u32) -> CostRow {
+        CostRow {
+            date: date(day),
+            user_id: "u1".to_string(),
+            model_id: "m1".to_string(),
+            amount: common::Amount::from_micros(1),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: common::AWS_SOURCE.to_string(),
+            tenant: DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn stale_entries_are_refreshed_once() {
+        let cache = LiveCache::new(300);
+        let now = Utc::now();
+        assert_eq!(cache.lookup(&key(), now).missing, vec![key()]);
+
+        cache.store(key(), Vec::new(), now);
+        let lookup = cache.lookup(&key(), now);
+        assert!(lookup.missing.is_empty() && lookup.stale.is_empty());
+
+        let later = now + Duration::seconds(301);
+        assert_eq!(cache.lookup(&key(), later).stale, vec![key()]);
+        assert!(cache.lookup(&key(), later).stale.is_empty());
+
+        cache.refresh_failed(&key());
+        assert_eq!(cache.lookup(&key(), later).stale, vec![key()]);
+
+        cache.store(key(), Vec::new(), later);
+        assert!(cache.lookup(&key(), later).stale.is_empty());
+        assert_eq!(cache.fetched_at(&key()), Some(later));
+    }
+
+    #[test]
+    fn lookup_returns_cached_days_and_gaps() {
+        let cache = LiveCache::new(300);
+        let now = Utc::now();
+        cache.store(range(3, 5, Metric::Blended), vec![row(3), row(4)], now);
+        cache.store(range(7, 8, Metric::Blended), vec![row(7)], now);
+
+        let lookup = cache.lookup(&range(1, 10, Metric::Blended), now);
+        assert_eq!(lookup.rows.len(), 3);
+        assert_eq!(
+            lookup.missing,
+            vec![
+                range(1, 3, Metric::Blended),
+                range(5, 7, Metric::Blended),
+                range(8, 10, Metric::Blended),
+            ]
+        );
+        assert!(cache.fetched_at(&range(3, 5, Metric::Blended)).is_some());
+        assert!(cache.fetched_at(&range(3, 6, Metric::Blended)).is_none());
+
+        let other = cache.lookup(&range(3, 5, Metric::Unblended), now);
+        assert!(other.rows.is_empty());
+        assert_eq!(other.missing.len(), 1);
+    }
+
+    #[test]
+    fn tenants_are_cached_apart() {
+        let cache = LiveCache::new(300);
+        let now = Utc::now();
+        cache.store(key(), vec![row(1)], now);
+        let staging = ("staging".to_string(), date(1), date(2), Metric::Blended);
+        let lookup = cache.lookup(&staging, now);
+        assert!(lookup.rows.is_empty());
+        assert_eq!(lookup.missing, vec![staging.clone()]);
+        cache.store(staging.clone(), Vec::new(), now);
+        assert_eq!(cache.lookup(&key(), now).rows.len(), 1);
+        assert!(cache.lookup(&staging, now).rows.is_empty());
+        assert_eq!(cache.stats().entries, 2);
+    }
+
+    #[tokio::test]
+    async fn concurrent_misses_fetch_once() {
+        let cache = LiveCache::new(300);
+        let calls = AtomicUsize::new(0);
+        let fetch = || async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+            Ok(vec![row(1)])
+        };
+        let (a, b) = tokio::join!(
+            cache.fetch_once(key(), fetch),
+            cache.fetch_once(key(), fetch)
+        );
+        assert_eq!(a.unwrap().len(), 1);
+        assert_eq!(b.unwrap().len(), 1);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+        assert!(cache.lookup(&key(), Utc::now()).missing.is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn fetch_stays_in_flight_until_its_rows_are_cached() {
+        let cache = Arc::new(LiveCache::new(300));
+        let (done, fetched) = tokio::sync::oneshot::channel::<()>();
+        let first = tokio::spawn({
+            let cache = cache.clone();
+            async move {
+                let fetch = || async move {
+                    fetched.await?;
+                    Ok(vec![row(1)])
+                };
+                cache.fetch_once(key(), fetch).await
+            }
+        });
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        {
+            // Holding the entries keeps the finished fetch from storing.
+            let entries = cache.entries.lock().unwrap();
+            done.send(()).unwrap();
+            std::thread::sleep(std::time::Duration::from_millis(50));
+            // A miss now waits for that fetch instead of querying CE again.
+            assert_eq!(cache.in_flight.lock().unwrap().len(), 1);
+            drop(entries);
+        }
+        assert_eq!(first.await.unwrap().unwrap().len(), 1);
+        let miss = cache
+            .fetch_once(key(), || async { anyhow::bail!("fetched again") })
+            .await;
+        assert_eq!(miss.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn failed_fetch_is_retried() {
+        let cache = LiveCache::new(300);
+        let failed = cache
+            .fetch_once(key(), || async { anyhow::bail!("CE unavailable") })
+            .await;
+        assert!(failed.is_err());
+        assert!(cache.fetched_at(&key()).is_none());
+        let rows = cache.fetch_once(key(), || async { Ok(Vec::new()) }).await;
+        assert!(rows.is_ok());
+        assert!(cache.fetched_at(&key()).is_some());
+    }
+
+    #[test]
+    fn stats_cover_every_entry() {
+        let cache = LiveCache::new(300);
+        assert_eq!(cache.stats(), CacheStats::default());
+
+        let now = Utc::now();
+        cache.store(
+            range(3, 5, Metric::Blended),
+            vec![row(3), row(3), row(4)],
+            now,
+        );
+        cache.store(
+            range(1, 2, Metric::Unblended),
+            Vec::new(),
+            now + Duration::seconds(1),
+        );
+        cache.lookup(&range(3, 4, Metric::Blended), now + Duration::seconds(300));
+
+        let stats = cache.stats();
+        assert_eq!(stats.entries, 3);
+        assert_eq!(stats.rows, 3);
+        assert_eq!(stats.oldest_day, Some(date(1)));
+        assert_eq!(stats.newest_day, Some(date(4)));
+        assert_eq!(stats.oldest_fetch, Some(now));
+        assert_eq!(stats.refreshing, 1);
+        assert_eq!(stats.in_flight, 0);
+    }
+
+    #[test]
+    fn store_evicts_old_entries() {
+        let cache = LiveCache::new(300);
+        let now = Utc::now();
+        cache.store(key(), Vec::new(), now);
+        let other = range(1, 2, Metric::Unblended);
+        cache.store(other.clone(), Vec::new(), now + Duration::hours(25));
+        assert!(cache.fetched_at(&key()).is_none());
+        assert!(cache.fetched_at(&other).is_some());
+    }
+}
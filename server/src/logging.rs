@@ -0,0 +1,44 @@
+use std::sync::OnceLock;
+
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{reload, EnvFilter, Registry};
+
+/// Swaps the filter of the subscriber installed by [`init`].
+static FILTER: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// `RUST_LOG` (default `server=info`) with `filter` applied on top.
+fn build(filter: Option<&str>) -> EnvFilter {
+    let mut directives = std::env::var("RUST_LOG").unwrap_or_else(|_| "server=info".to_string());
+    if let Some(filter) = filter {
+        directives.push(',');
+        directives.push_str(filter);
+    }
+    EnvFilter::builder().parse_lossy(directives)
+}
+
+/// Installs a subscriber writing one JSON object per line, with the fields
+/// of the current request's span (its ID and signed-in email) on each, and
+/// routes the `log` macros through it.
+pub fn init() {
+    let (filter, handle) = reload::Layer::new(build(None));
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(
+            tracing_subscriber::fmt::layer()
+                .json()
+                .with_current_span(true)
+                .with_span_list(false),
+        )
+        .init();
+    let _ = FILTER.set(handle);
+}
+
+/// Replaces the filter of the subscriber installed by [`init`].
+pub fn set_filter(filter: Option<&str>) {
+    if let Some(handle) = FILTER.get() {
+        if let Err(e) = handle.reload(build(filter)) {
+            log::warn!("Failed to apply the log filter: {e}");
+        }
+    }
+}
@@ -1,48 +1,116 @@
+mod api;
+mod check;
 mod config;
+mod etag;
+mod export;
+mod forwarded;
+mod freshness;
+mod graphql;
 mod handlers;
+mod live_cache;
+mod logging;
 mod pages;
+mod preferences;
+mod rate_limit;
+mod reload;
+mod request_id;
+mod roles;
 pub mod service;
+mod share;
+mod tenants;
 
 #[cfg(test)]
 mod tests;
 
-use axum::routing::get;
+use arc_swap::ArcSwap;
+use axum::routing::{get, post};
 use axum::Router;
 use clap::Parser;
 use handlers::AppState;
-use myhandlers::{callback, login, logout};
+use myhandlers::{login, logout};
 use service::RealCostService;
+use std::net::SocketAddr;
 use std::sync::Arc;
-use tower_sessions::{ExpiredDeletion, Expiry, SessionManagerLayer};
+use tokio::task::JoinHandle;
+use tower_http::services::ServeDir;
+use tower_sessions::{ExpiredDeletion, Expiry, MemoryStore, SessionManagerLayer, SessionStore};
 
-use crate::config::load_config;
+use crate::config::{load_config, SessionStoreKind};
 
 #[derive(Parser)]
 #[command(name = "cost-explorer")]
 struct Args {
     #[arg(long, default_value = "config")]
     config_file: String,
+    /// Check database, Cost Explorer and sign-in settings, print the
+    /// results, then exit
+    #[arg(long)]
+    check_config: bool,
+    /// Save every CE response under this directory while serving
+    #[arg(long, value_name = "DIR", conflicts_with = "replay_fixtures")]
+    record_fixtures: Option<std::path::PathBuf>,
+    /// Answer CE queries from responses saved with `--record-fixtures`
+    /// instead of calling CE
+    #[arg(long, value_name = "DIR")]
+    replay_fixtures: Option<std::path::PathBuf>,
 }
 
 pub fn build_router(state: AppState) -> Router {
     let base = state.base_path.clone();
 
-    let auth_state = myhandlers::AppState {
-        cognito_client_id: state.cognito_client_id.clone(),
-        cognito_client_secret: state.cognito_client_secret.clone(),
-        cognito_domain: state.cognito_domain.clone(),
-        cognito_redirect_uri: state.cognito_redirect_uri.clone(),
-        cognito_region: state.cognito_region.clone(),
-        cognito_user_pool_id: state.cognito_user_pool_id.clone(),
-    };
+    let auth_state = state.auth_state();
+    let client_info = axum::middleware::from_fn_with_state(state.clone(), forwarded::client_info);
+    let shared_access = axum::middleware::from_fn_with_state(state.clone(), share::shared_access);
 
     let health_route = Router::new()
         .route("/health", get(handlers::health_check))
+        .route("/callback", get(handlers::callback))
         .with_state(state.clone());
 
-    let cost_routes = Router::new()
-        .route("/", get(handlers::render_home))
-        .route("/costs/daily", get(handlers::render_daily_costs))
+    let links = Arc::new(tenants::links(
+        &base,
+        state.tenants.keys().map(String::as_str),
+    ));
+    let mut tenant_routes = Router::new();
+    for (name, service) in state.tenants.iter() {
+        let tenant_state = AppState {
+            service: service.clone(),
+            base_path: tenants::base_path(&base, name),
+            ..state.clone()
+        };
+        let admins_only =
+            axum::middleware::from_fn_with_state(tenant_state.clone(), tenants::admins_only);
+        let routes = cost_routes(tenant_state.clone(), &links).layer(admins_only);
+        tenant_routes = tenant_routes.nest(&tenant_state.base_path, routes);
+    }
+
+    let cost_routes = cost_routes(state, &links);
+    let cost_routes = if base == "/" {
+        cost_routes
+    } else {
+        Router::new().nest(&base, cost_routes)
+    };
+
+    Router::new()
+        .route("/login", get(login))
+        .route("/logout", get(logout))
+        .with_state(auth_state)
+        .merge(health_route)
+        .merge(cost_routes)
+        .merge(tenant_routes)
+        .layer(shared_access)
+        .layer(client_info)
+        .layer(axum::middleware::from_fn(request_id::request_id))
+}
+
+/// The pages of `state`'s tenant, relative to its base path. With more than
+/// one tenant in `links`, admins get a switcher between them.
+fn cost_routes(state: AppState, links: &Arc<Vec<(String, String)>>) -> Router {
+    let freshness = axum::middleware::from_fn_with_state(state.clone(), freshness::freshness);
+
+    // Drill-downs fan out into per-day, per-user and per-model queries, so
+    // each client's rate of them is limited.
+    let drill_down_routes = Router::new()
         .route("/costs/daily/{date}", get(handlers::render_date_hub))
         .route("/costs/daily/{date}/users", get(handlers::render_date_users))
         .route(
@@ -57,7 +125,8 @@ pub fn build_router(state: AppState) -> Router {
             "/costs/daily/{date}/models/{model_id}",
             get(handlers::render_date_users_for_model),
         )
-        .route("/costs/monthly", get(handlers::render_monthly_costs))
+        .route("/costs/services/{date}", get(handlers::render_service_date))
+        .route("/costs/weekly/{week}", get(handlers::render_week))
         .route("/costs/monthly/{month}", get(handlers::render_month_hub))
         .route(
             "/costs/monthly/{month}/users",
@@ -75,109 +144,303 @@ pub fn build_router(state: AppState) -> Router {
             "/costs/monthly/{month}/models/{model_id}",
             get(handlers::render_month_users_for_model),
         )
-        .route("/users", get(handlers::render_users))
-        .route("/models", get(handlers::render_models))
         .route("/users/{id}", get(handlers::render_user_hub))
         .route("/models/{id}", get(handlers::render_model_hub))
         .route("/users/{id}/daily", get(handlers::render_user_daily_costs))
         .route("/users/{id}/monthly", get(handlers::render_user_monthly_costs))
+        .route("/users/{id}/keys", get(handlers::render_user_keys))
+        .route("/users/{id}/profiles", get(handlers::render_user_profiles))
         .route("/models/{id}/daily", get(handlers::render_model_daily_costs))
         .route("/models/{id}/monthly", get(handlers::render_model_monthly_costs))
-        .with_state(state);
+        .route("/models/{id}/users", get(handlers::render_model_users));
+    let drill_down_routes = rate_limit::limit(drill_down_routes, &state.config.load().rate_limit);
 
-    let cost_routes = if base == "/" {
-        cost_routes
+    let cost_routes = Router::new()
+        .route("/", get(handlers::render_home))
+        .route("/events/home", get(handlers::home_events))
+        .route("/me", get(handlers::render_my_costs))
+        .route("/export.xlsx", get(handlers::export_xlsx))
+        .route("/costs/daily", get(handlers::render_daily_costs))
+        .route("/costs/calendar", get(handlers::render_calendar))
+        .route("/costs/fiscal", get(handlers::render_fiscal))
+        .route("/costs/matrix", get(handlers::render_cost_matrix))
+        .route("/costs/matrix.csv", get(handlers::export_cost_matrix_csv))
+        .route("/costs/daily/stacked", get(handlers::render_daily_costs_by_model))
+        .route("/costs/distribution", get(handlers::render_distribution))
+        .route("/costs/families", get(handlers::render_families))
+        .route("/costs/labels", get(handlers::render_label_costs))
+        .route("/reports/idle", get(handlers::render_idle))
+        .route("/costs/services", get(handlers::render_services))
+        .route("/costs/regions", get(handlers::render_regions))
+        .route("/costs/accounts", get(handlers::render_accounts))
+        .route("/costs/estimates", get(handlers::render_cost_estimates))
+        .route("/costs/amortization", get(handlers::render_amortization))
+        .route("/efficiency", get(handlers::render_efficiency))
+        .route("/costs/credits", get(handlers::render_credits))
+        .route("/costs/weekly", get(handlers::render_weekly_costs))
+        .route("/costs/monthly", get(handlers::render_monthly_costs))
+        .route("/users", get(handlers::render_users))
+        .route("/users.csv", get(handlers::export_users_csv))
+        .route("/users.json", get(handlers::users_json))
+        .route("/models", get(handlers::render_models))
+        .route("/models.csv", get(handlers::export_models_csv))
+        .route("/search", get(handlers::render_search))
+        .route("/source", get(handlers::choose_source))
+        .route("/graphql", get(graphql::graphql).post(graphql::graphql))
+        .route(
+            "/preferences",
+            get(handlers::render_preferences).post(handlers::save_preferences),
+        )
+        .route(
+            "/limits",
+            get(handlers::render_spend_limits).post(handlers::save_spend_limit),
+        )
+        .route(
+            "/me/alerts",
+            get(handlers::render_cost_alert).post(handlers::save_cost_alert),
+        )
+        .route(
+            "/prices",
+            get(handlers::render_model_prices).post(handlers::save_model_price),
+        )
+        .route("/prices/delete", post(handlers::delete_model_price))
+        .route(
+            "/families",
+            get(handlers::render_model_families).post(handlers::save_model_family),
+        )
+        .route("/families/delete", post(handlers::delete_model_family))
+        .route(
+            "/labels",
+            get(handlers::render_labels).post(handlers::save_label),
+        )
+        .route("/labels/delete", post(handlers::delete_label))
+        .route(
+            "/subscriptions",
+            get(handlers::render_subscriptions).post(handlers::save_subscription),
+        )
+        .route("/subscriptions/delete", post(handlers::delete_subscription))
+        .route(
+            "/adjustments",
+            get(handlers::render_adjustments).post(handlers::save_adjustment),
+        )
+        .route("/adjustments/delete", post(handlers::delete_adjustment))
+        .route("/rollup/refresh", post(handlers::refresh_rollup))
+        .route("/import", post(handlers::import_costs))
+        .route("/admin/diagnostics", get(handlers::render_diagnostics))
+        .route("/admin/batch", get(handlers::render_batch_runs))
+        .route("/admin/batch/run", post(handlers::request_batch_run))
+        .route("/admin/refresh", post(handlers::refresh_costs))
+        .route(
+            "/share",
+            get(handlers::render_share).post(handlers::create_share_link),
+        )
+        .merge(drill_down_routes)
+        .layer(freshness);
+    let cost_routes = if links.len() > 1 {
+        let switcher = tenants::Switcher {
+            state: state.clone(),
+            links: links.clone(),
+        };
+        cost_routes.layer(axum::middleware::from_fn_with_state(
+            switcher,
+            tenants::switcher,
+        ))
     } else {
-        Router::new().nest(&base, cost_routes)
+        cost_routes
     };
-
-    Router::new()
-        .route("/callback", get(callback))
-        .route("/login", get(login))
-        .route("/logout", get(logout))
-        .with_state(auth_state)
-        .merge(health_route)
-        .merge(cost_routes)
+    let cost_routes = cost_routes
+        .layer(axum::middleware::from_fn_with_state(
+            state.clone(),
+            etag::etag,
+        ))
+        .route("/assets/{name}", get(handlers::asset));
+    match &state.config.load().static_dir {
+        Some(dir) => cost_routes.nest_service("/static", ServeDir::new(dir)),
+        None => cost_routes,
+    }
+    .with_state(state)
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-    env_logger::init_from_env(env_logger::Env::default().default_filter_or("server=info"));
+    logging::init();
 
     let args = Args::parse();
 
-    if cfg!(feature = "admin") {
-        log::info!("Running in ADMIN mode (all users visible)");
-    } else {
-        log::info!("Running in NORMAL mode (per-user filtering)");
-    }
-
     let app_config = load_config(&args.config_file).await?;
+    templates::set_assets_path(&pages::make_path(&app_config.base_path, "/assets"));
+    reload::apply(&app_config);
+    if args.check_config {
+        return check::run(&app_config).await;
+    }
 
-    if app_config.cognito_client_id.is_empty()
-        || app_config.cognito_client_secret.is_empty()
-        || app_config.cognito_domain.is_empty()
-    {
+    let missing = app_config.missing_auth_settings();
+    if !missing.is_empty() {
         log::error!(
-            "Missing required Cognito configuration. Check config file or environment variables."
+            "Missing {:?} auth configuration ({}). Check config file or environment variables.",
+            app_config.auth_provider,
+            missing.join(", ")
         );
     }
 
     let gateway_pool = db::init_pool_lazy(&app_config.database_url_gateway_ro)?;
     log::info!("Gateway DB pool initialized");
-    let cost_pool = db::init_pool(&app_config.database_url_cost).await?;
+    let mut tenant_pools = Vec::new();
+    for tenant in &app_config.tenants {
+        tenant_pools.push((
+            tenant.name.clone(),
+            db::init_pool_lazy(&tenant.database_url_gateway_ro)?,
+        ));
+    }
+    if !tenant_pools.is_empty() {
+        log::info!(
+            "Gateway DB pools of {} more tenants initialized",
+            tenant_pools.len()
+        );
+    }
+    let cost_db = db::CostDb::connect(&app_config.database_url_cost).await?;
     log::info!("Cost DB connected successfully");
 
-    db::create_cost_table(&cost_pool).await?;
-
-    let session_store = tower_sessions_sqlx_store::PostgresStore::new(cost_pool.clone());
-    session_store.migrate().await?;
+    let cost_store = cost_db.store();
+    cost_store.migrate().await?;
 
-    let deletion_task = tokio::task::spawn(
-        session_store
-            .clone()
-            .continuously_delete_expired(tokio::time::Duration::from_secs(3600)),
-    );
-
-    let session_layer = SessionManagerLayer::new(session_store)
-        .with_expiry(Expiry::OnInactivity(time::Duration::seconds(86400)))
-        .with_same_site(tower_sessions::cookie::SameSite::Lax);
+    if let Some(dir) = args.record_fixtures {
+        ce::use_fixtures(ce::Fixtures::Record(dir));
+    } else if let Some(dir) = args.replay_fixtures {
+        ce::use_fixtures(ce::Fixtures::Replay(dir));
+    }
+    let ce_clients = ce::Clients::new(app_config.aws_accounts.clone());
+    log::info!("Serving cost data from {:?}", app_config.data_source);
 
+    let host = app_config.host.clone();
+    let port = app_config.port;
+    let session_store = app_config.session_store;
+    #[cfg(feature = "redis")]
+    let redis_url = app_config.redis_url.clone();
+    let config = Arc::new(ArcSwap::from_pointee(app_config));
+    reload::spawn(args.config_file, config.clone());
+    let clock_config = config.clone();
     let service = RealCostService {
         pool: gateway_pool,
-        cost_pool,
+        tenant: common::DEFAULT_TENANT.to_string(),
+        filter_live: !tenant_pools.is_empty(),
+        cost_db: cost_store,
+        data_source: config.load().data_source,
+        settlement_hours: config.load().settlement_hours,
+        ce_clients,
+        live_cache: Arc::new(live_cache::LiveCache::new(config.load().live_cache_seconds)),
+        clock: Arc::new(move || {
+            let timezone = clock_config.load().timezone;
+            chrono::Utc::now().with_timezone(&timezone).date_naive()
+        }),
+        source: None,
     };
+    let tenants = tenant_pools
+        .into_iter()
+        .map(|(name, pool)| {
+            let tenant_service: Arc<dyn service::CostService> = Arc::new(RealCostService {
+                pool,
+                tenant: name.clone(),
+                ..service.clone()
+            });
+            (name, tenant_service)
+        })
+        .collect();
     let state = AppState {
         service: Arc::new(service),
-        base_path: app_config.base_path,
-        cognito_client_id: app_config.cognito_client_id,
-        cognito_client_secret: app_config.cognito_client_secret,
-        cognito_domain: app_config.cognito_domain,
-        cognito_redirect_uri: app_config.cognito_redirect_uri,
-        cognito_region: app_config.cognito_region,
-        cognito_user_pool_id: app_config.cognito_user_pool_id,
+        auth_provider: config.load().auth_provider(),
+        base_path: config.load().base_path.clone(),
+        config,
+        tenants: Arc::new(tenants),
     };
 
-    let app = build_router(state).layer(session_layer);
+    let app = build_router(state);
+    let listener = tokio::net::TcpListener::bind(format!("{}:{}", host, port)).await?;
+    log::info!("Listening on http://{}:{}", host, port);
+
+    match session_store {
+        // The cost database, whichever engine that is.
+        SessionStoreKind::Postgres => match cost_db {
+            db::CostDb::Postgres(pool) => {
+                let session_store = tower_sessions_sqlx_store::PostgresStore::new(pool);
+                session_store.migrate().await?;
+                let deletion_task = delete_expired(&session_store);
+                serve(app, listener, session_store, Some(deletion_task)).await
+            }
+            #[cfg(feature = "sqlite")]
+            db::CostDb::Sqlite(pool) => {
+                let session_store = tower_sessions_sqlx_store::SqliteStore::new(pool);
+                session_store.migrate().await?;
+                let deletion_task = delete_expired(&session_store);
+                serve(app, listener, session_store, Some(deletion_task)).await
+            }
+        },
+        // Redis expires sessions itself.
+        #[cfg(feature = "redis")]
+        SessionStoreKind::Redis => {
+            use tower_sessions_redis_store::fred::prelude::{ClientLike, Config, Pool};
+
+            let pool = Pool::new(Config::from_url(&redis_url)?, None, None, None, 6)?;
+            let _connection = pool.init().await?;
+            log::info!("Keeping sessions in Redis");
+            let session_store = tower_sessions_redis_store::RedisStore::new(pool);
+            serve(app, listener, session_store, None).await
+        }
+        #[cfg(not(feature = "redis"))]
+        SessionStoreKind::Redis => {
+            anyhow::bail!("session_store = \"redis\" needs a server built with --features redis")
+        }
+        SessionStoreKind::Memory => {
+            log::warn!("Keeping sessions in memory; they are lost on restart");
+            serve(app, listener, MemoryStore::default(), None).await
+        }
+    }
+}
+
+/// Deletes `session_store`'s expired sessions hourly.
+fn delete_expired<S>(session_store: &S) -> JoinHandle<tower_sessions::session_store::Result<()>>
+where
+    S: ExpiredDeletion + Clone,
+{
+    tokio::task::spawn(
+        session_store
+            .clone()
+            .continuously_delete_expired(tokio::time::Duration::from_secs(3600)),
+    )
+}
 
-    let listener =
-        tokio::net::TcpListener::bind(format!("{}:{}", app_config.host, app_config.port)).await?;
-    log::info!(
-        "Listening on http://{}:{}",
-        app_config.host,
-        app_config.port
-    );
+/// Serves `app` with sessions kept in `session_store`, stopping
+/// `deletion_task` on shutdown.
+async fn serve<S>(
+    app: Router,
+    listener: tokio::net::TcpListener,
+    session_store: S,
+    deletion_task: Option<JoinHandle<tower_sessions::session_store::Result<()>>>,
+) -> anyhow::Result<()>
+where
+    S: SessionStore + Clone,
+{
+    let session_layer = SessionManagerLayer::new(session_store)
+        .with_expiry(Expiry::OnInactivity(time::Duration::seconds(86400)))
+        .with_same_site(tower_sessions::cookie::SameSite::Lax);
 
+    let app = app
+        .layer(session_layer)
+        .into_make_service_with_connect_info::<SocketAddr>();
     axum::serve(listener, app)
-        .with_graceful_shutdown(shutdown_signal(deletion_task.abort_handle()))
+        .with_graceful_shutdown(shutdown_signal(
+            deletion_task.as_ref().map(JoinHandle::abort_handle),
+        ))
         .await?;
 
-    deletion_task.await??;
+    if let Some(deletion_task) = deletion_task {
+        deletion_task.await??;
+    }
 
     Ok(())
 }
 
-async fn shutdown_signal(deletion_task_abort_handle: tokio::task::AbortHandle) {
+async fn shutdown_signal(deletion_task_abort_handle: Option<tokio::task::AbortHandle>) {
     let ctrl_c = async {
         tokio::signal::ctrl_c()
             .await
@@ -196,7 +459,10 @@ async fn shutdown_signal(deletion_task_abort_handle: tokio::task::AbortHandle) {
     let terminate = std::future::pending::<()>();
 
     tokio::select! {
-        _ = ctrl_c => { deletion_task_abort_handle.abort() },
-        _ = terminate => { deletion_task_abort_handle.abort() },
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    if let Some(handle) = deletion_task_abort_handle {
+        handle.abort();
     }
 }
@@ -0,0 +1,112 @@
+use super::{make_path, share, with_period, NavContext};
+use common::{Amount, CostByAccount};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// Gateway cost per linked account, highest first, for organizations on
+/// consolidated billing.
+pub fn render(base: &str, nav: &NavContext, costs: &[CostByAccount]) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let rows: Vec<_> = costs
+        .iter()
+        .map(|c| {
+            (
+                c.account_name.clone().unwrap_or_default(),
+                c.account_id.clone(),
+                format!("{:.2} {}", c.amount, c.currency),
+                share(c.amount, total),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Cost by Linked Account"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_by_account">
+                    <tr>
+                        <th>"Account"</th>
+                        <th>"Account ID"</th>
+                        <th>"Cost"</th>
+                        <th>"Share"</th>
+                    </tr>
+                    {rows.into_iter().map(|(name, id, cost, share)| view! {
+                        <tr>
+                            <td>{name}</td>
+                            <td>{id}</td>
+                            <td>{cost}</td>
+                            <td>{share}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Accounts".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Accounts"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/accounts"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+            InfoRow::new("Accounts", &costs.len().to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(id: &str, name: Option<&str>, micros: i64) -> CostByAccount {
+        CostByAccount {
+            account_id: id.to_string(),
+            account_name: name.map(str::to_string),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_lists_accounts() {
+        let costs = vec![
+            cost("111111111111", Some("research"), 3_000_000),
+            cost("222222222222", None, 1_000_000),
+        ];
+        let html = render("/", &"30d".into(), &costs);
+        assert!(html.contains("<title>Cost Explorer - Accounts</title>"));
+        assert!(html.contains("research"));
+        assert!(html.contains("222222222222"));
+        assert!(html.contains("75.0%"));
+        assert!(html.contains("4.00 USD"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), &[]);
+        assert!(html.contains("No cost data found for this period."));
+    }
+}
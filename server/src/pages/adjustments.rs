@@ -0,0 +1,245 @@
+use super::{make_path, with_period, NavContext};
+use common::{Amount, CostAdjustment, Metric};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// Admin page listing manual cost adjustments in the period, each editable
+/// in place, with a form to add one. `metric` preselects the new row's metric.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    adjustments: &[CostAdjustment],
+    users: &[(String, String)],
+    models: &[(String, String)],
+    metric: Metric,
+) -> String {
+    let period = nav.period.as_str();
+    let action = with_period(&make_path(base, "/adjustments"), period);
+    let delete_action = with_period(&make_path(base, "/adjustments/delete"), period);
+    let rollup_action = with_period(&make_path(base, "/rollup/refresh"), period);
+    let import_action = with_period(&make_path(base, "/import"), period);
+    let currency = adjustments
+        .first()
+        .map(|a| a.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let total: Amount = adjustments.iter().map(|a| a.amount).sum();
+    let rows: Vec<_> = adjustments
+        .iter()
+        .map(|a| {
+            (
+                a.date.format("%Y-%m-%d").to_string(),
+                a.user_id.clone(),
+                make_path(base, &format!("/users/{}", a.user_id)),
+                a.user_email.clone().unwrap_or_else(|| a.user_id.clone()),
+                a.model_id.clone(),
+                make_path(base, &format!("/models/{}", a.model_id)),
+                a.model_name.clone().unwrap_or_else(|| a.model_id.clone()),
+                a.metric,
+                format!("{:.6}", a.amount),
+                a.note.clone(),
+            )
+        })
+        .collect();
+    let user_options: Vec<_> = users.to_vec();
+    let model_options: Vec<_> = models.to_vec();
+
+    let content = view! {
+        <h2>"Cost Adjustments"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No adjustments in this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_adjustments">
+                    <tr>
+                        <th>"Date"</th>
+                        <th>"User"</th>
+                        <th>"Model"</th>
+                        <th>"Metric"</th>
+                        <th>"Amount"</th>
+                        <th>"Note"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(date, user_id, user_href, user_label, model_id, model_href, model_label, metric, amount, note)| {
+                        let form_id = format!("adjustment-{}-{}-{}-{}", date, user_id, model_id, metric.as_str());
+                        view! {
+                            <tr>
+                                <td>{date.clone()}</td>
+                                <td><a href={user_href}>{user_label}</a></td>
+                                <td><a href={model_href}>{model_label}</a></td>
+                                <td>{metric.label()}</td>
+                                <td>
+                                    <input form={form_id.clone()} type="number" name="amount" step="0.000001" value={amount} required/>
+                                </td>
+                                <td>
+                                    <input form={form_id.clone()} type="text" name="note" value={note}/>
+                                </td>
+                                <td>
+                                    <form id={form_id} method="post" action={action.clone()}>
+                                        <input type="hidden" name="date" value={date.clone()}/>
+                                        <input type="hidden" name="user_id" value={user_id.clone()}/>
+                                        <input type="hidden" name="model_id" value={model_id.clone()}/>
+                                        <input type="hidden" name="metric" value={metric.as_str()}/>
+                                        <button type="submit">"Save"</button>
+                                    </form>
+                                    <form method="post" action={delete_action.clone()}>
+                                        <input type="hidden" name="date" value={date}/>
+                                        <input type="hidden" name="user_id" value={user_id}/>
+                                        <input type="hidden" name="model_id" value={model_id}/>
+                                        <input type="hidden" name="metric" value={metric.as_str()}/>
+                                        <button type="submit">"Delete"</button>
+                                    </form>
+                                </td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Add Adjustment"</h3>
+        <p>"Negative amounts are credits. An adjustment adds to the imported cost of the same day, user, model and metric."</p>
+        <form method="post" action={action.clone()}>
+            <p>
+                <label>"Date "
+                    <input type="date" name="date" required/>
+                </label>
+            </p>
+            <p>
+                <label>"User "
+                    <select name="user_id">
+                        {user_options.into_iter().map(|(id, email)| view! {
+                            <option value={id}>{email}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Model "
+                    <select name="model_id">
+                        {model_options.into_iter().map(|(id, name)| view! {
+                            <option value={id}>{name}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Metric "
+                    <select name="metric">
+                        {Metric::ALL.into_iter().map(|m| view! {
+                            <option value={m.as_str()} selected={m == metric}>{m.label()}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Amount (USD) "
+                    <input type="number" name="amount" step="0.000001" required/>
+                </label>
+            </p>
+            <p>
+                <label>"Note "
+                    <input type="text" name="note"/>
+                </label>
+            </p>
+            <button type="submit">"Save"</button>
+        </form>
+        <h3>"Import Costs"</h3>
+        <p>"Load costs from another provider: a CSV file, or a JSON array of objects, with date, user_id, model_id and amount, and optionally currency and metric. Rows without a metric count under every metric. Importing the same source again replaces matching rows. Imported costs show on pages read from the cost table, not on live CE data."</p>
+        <form method="post" action={import_action} enctype="multipart/form-data">
+            <p>
+                <label>"Source "
+                    <input type="text" name="source" pattern="[a-z0-9_-]+" placeholder="openai" required/>
+                </label>
+            </p>
+            <p>
+                <label>"File "
+                    <input type="file" name="file" accept=".csv,.json" required/>
+                </label>
+            </p>
+            <button type="submit">"Import"</button>
+        </form>
+        <h3>"Monthly Rollup"</h3>
+        <p>"Monthly pages read whole months from a rollup the batch job refreshes after each run. Rebuild it after changing the cost table by hand."</p>
+        <form method="post" action={rollup_action}>
+            <button type="submit">"Rebuild"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Cost Adjustments".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Cost Adjustments"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/adjustments"), period),
+            ),
+            InfoRow::new("Adjustments", &adjustments.len().to_string()),
+            InfoRow::new("Net Adjustment", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn adjustment(amount: f64, note: &str) -> CostAdjustment {
+        CostAdjustment {
+            date: NaiveDate::from_ymd_opt(2025, 3, 1).unwrap(),
+            user_id: "u1".to_string(),
+            user_email: Some("a@example.com".to_string()),
+            model_id: "m1".to_string(),
+            model_name: None,
+            metric: Metric::Blended,
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+            note: note.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_lists_adjustments() {
+        let adjustments = vec![adjustment(-2.5, "refund"), adjustment(1.0, "shared")];
+        let users = vec![("u2".to_string(), "b@example.com".to_string())];
+        let models = vec![("m2".to_string(), "llama".to_string())];
+        let html = render(
+            "/_dashboard",
+            &"month".into(),
+            &adjustments,
+            &users,
+            &models,
+            Metric::Blended,
+        );
+        assert!(html.contains("<title>Cost Explorer - Cost Adjustments</title>"));
+        assert!(html.contains(r#"href="/_dashboard/users/u1""#));
+        assert!(html.contains("a@example.com"));
+        assert!(html.contains(r#"value="-2.500000""#));
+        assert!(html.contains(r#"value="refund""#));
+        assert!(html.contains("-1.50 USD"));
+        assert!(html.contains(r#"action="/_dashboard/adjustments/delete?period=month""#));
+        assert!(html.contains(r#"action="/_dashboard/rollup/refresh?period=month""#));
+        assert!(html.contains(r#"action="/_dashboard/import?period=month""#));
+        assert!(html.contains(r#"<option value="u2">b@example.com</option>"#));
+        assert!(html.contains(r#"<option value="m2">llama</option>"#));
+    }
+
+    #[test]
+    fn render_without_adjustments() {
+        let html = render("/", &"30d".into(), &[], &[], &[], Metric::Blended);
+        assert!(html.contains("No adjustments in this period."));
+        assert!(html.contains("0.00 USD"));
+    }
+}
@@ -0,0 +1,109 @@
+use super::make_path;
+use common::{Amount, CostAlert};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+fn threshold(amount: Option<Amount>) -> String {
+    amount.map(|a| format!("{:.2}", a)).unwrap_or_default()
+}
+
+/// The user's own daily and monthly cost alert. `email` is `None` for users
+/// without a gateway account, who have no costs to alert on.
+pub fn render(base: &str, email: Option<&str>, alert: Option<&CostAlert>, spent: Amount) -> String {
+    let action = make_path(base, "/me/alerts");
+    let daily = threshold(alert.and_then(|a| a.daily_threshold));
+    let monthly = threshold(alert.and_then(|a| a.monthly_threshold));
+    let mut info_rows = vec![];
+    if email.is_some() {
+        info_rows.push(InfoRow::new("Spent This Month", &format!("{:.2}", spent)));
+        for (label, date) in [
+            ("Last Daily Alert", alert.and_then(|a| a.daily_alerted)),
+            ("Last Monthly Alert", alert.and_then(|a| a.monthly_alerted)),
+        ] {
+            if let Some(date) = date {
+                info_rows.push(InfoRow::new(label, &date.to_string()));
+            }
+        }
+    }
+
+    let content = view! {
+        <h2>"My Cost Alerts"</h2>
+        {match email {
+            None => Either::Left(view! {
+                <p>"Your account has no gateway user, so there are no costs to alert on."</p>
+            }),
+            Some(email) => Either::Right(view! {
+                <p>
+                    "The nightly batch job emails " {email.to_string()}
+                    " when your cost for a day or for the month so far goes over a threshold. "
+                    "Leave a threshold empty to turn that alert off."
+                </p>
+                <form method="post" action={action}>
+                    <p>
+                        <label>"Daily threshold (USD) "
+                            <input type="number" name="daily_threshold" min="0" step="0.01" value={daily}/>
+                        </label>
+                    </p>
+                    <p>
+                        <label>"Monthly threshold (USD) "
+                            <input type="number" name="monthly_threshold" min="0" step="0.01" value={monthly}/>
+                        </label>
+                    </p>
+                    <button type="submit">"Save"</button>
+                </form>
+            }),
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - My Alerts".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("My Alerts"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn render_fills_in_thresholds() {
+        let alert = CostAlert {
+            user_id: "u1".to_string(),
+            daily_threshold: Some(Amount::from_f64(5.0)),
+            daily_alerted: NaiveDate::from_ymd_opt(2025, 3, 4),
+            ..Default::default()
+        };
+        let html = render(
+            "/_dashboard",
+            Some("alice@example.com"),
+            Some(&alert),
+            Amount::from_f64(12.5),
+        );
+        assert!(html.contains("<title>Cost Explorer - My Alerts</title>"));
+        assert!(html.contains(r#"action="/_dashboard/me/alerts""#));
+        assert!(html.contains(r#"name="daily_threshold" min="0" step="0.01" value="5.00""#));
+        assert!(html.contains(r#"name="monthly_threshold" min="0" step="0.01" value="""#));
+        assert!(html.contains("12.50"));
+        assert!(html.contains("2025-03-04"));
+    }
+
+    #[test]
+    fn render_without_gateway_user() {
+        let html = render("/", None, None, Amount::ZERO);
+        assert!(html.contains("no gateway user"));
+        assert!(!html.contains("daily_threshold"));
+    }
+}
@@ -0,0 +1,180 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use super::{change_cells, make_path, with_period, NavContext};
+use common::{Amount, CostByModel};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// Per-model on-demand (unblended) cost next to the amortized cost, which
+/// spreads Savings Plans, reservations and provisioned throughput
+/// commitments over the days they cover. Models are ordered by the size of
+/// the difference.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    on_demand: &[CostByModel],
+    amortized: &[CostByModel],
+) -> String {
+    let period = nav.period.as_str();
+    let currency = on_demand
+        .iter()
+        .chain(amortized)
+        .next()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let mut models: BTreeMap<&str, (Option<&str>, Amount, Amount)> = BTreeMap::new();
+    for c in on_demand {
+        let model = models
+            .entry(&c.model_id)
+            .or_insert((None, Amount::ZERO, Amount::ZERO));
+        model.0 = model.0.or(c.model_name.as_deref());
+        model.1 += c.amount;
+    }
+    for c in amortized {
+        let model = models
+            .entry(&c.model_id)
+            .or_insert((None, Amount::ZERO, Amount::ZERO));
+        model.0 = model.0.or(c.model_name.as_deref());
+        model.2 += c.amount;
+    }
+    let mut rows: Vec<_> = models.into_iter().collect();
+    rows.sort_by_key(|(_, (_, on_demand, amortized))| {
+        Reverse((*amortized - *on_demand).max(*on_demand - *amortized))
+    });
+    let total_on_demand: Amount = on_demand.iter().map(|c| c.amount).sum();
+    let total_amortized: Amount = amortized.iter().map(|c| c.amount).sum();
+    // Stored data has only the metrics the batch fetches.
+    let missing_metric = on_demand.is_empty() != amortized.is_empty();
+    let empty = rows.is_empty();
+    let origin = nav.here(
+        &with_period(&make_path(base, "/costs/amortization"), period),
+        1,
+    );
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|(model_id, (model_name, on_demand, amortized))| {
+            let href = nav.drill(
+                &make_path(base, &format!("/models/{}", model_id)),
+                origin.as_deref(),
+            );
+            let label = model_name.unwrap_or(model_id).to_string();
+            let cells = change_cells(amortized, on_demand, &currency);
+            (href, label, format!("{:.2} {}", amortized, currency), cells)
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Amortized vs On-Demand Cost"</h2>
+        <p>
+            "On-demand is CE's unblended cost, charged as usage happens. Amortized cost spreads "
+            "Savings Plans, reservations and provisioned throughput commitments over the days "
+            "they cover, which is what a model's usage costs once commitments are paid for."
+        </p>
+        {missing_metric.then(|| view! {
+            <p>
+                "Only one of the two metrics has data for this period. Add both UnblendedCost and "
+                "AmortizedCost to the batch job's metrics to compare them."
+            </p>
+        })}
+        {if empty {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="amortized_cost">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"On-Demand"</th>
+                        <th>"Amortized"</th>
+                        <th>"Difference"</th>
+                        <th>"Difference %"</th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, amortized, [on_demand, difference, percent])| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{on_demand}</td>
+                            <td>{amortized}</td>
+                            <td>{difference}</td>
+                            <td>{percent}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    let [_, difference, percent] = change_cells(total_amortized, total_on_demand, &currency);
+    let info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/amortization"), period),
+        ),
+        InfoRow::new(
+            "On-Demand Cost",
+            &format!("{:.2} {}", total_on_demand, currency),
+        ),
+        InfoRow::new(
+            "Amortized Cost",
+            &format!("{:.2} {}", total_amortized, currency),
+        ),
+        InfoRow::new("Difference", &format!("{} ({})", difference, percent)),
+    ];
+
+    Page {
+        title: "Cost Explorer - Amortized vs On-Demand".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Amortized vs On-Demand"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(model_id: &str, name: Option<&str>, amount: f64) -> CostByModel {
+        CostByModel {
+            model_id: model_id.to_string(),
+            model_name: name.map(str::to_string),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_orders_by_difference() {
+        let on_demand = vec![
+            cost("m1", Some("claude"), 100.0),
+            cost("m2", Some("llama"), 10.0),
+        ];
+        let amortized = vec![
+            cost("m1", Some("claude"), 60.0),
+            cost("m2", Some("llama"), 10.0),
+            cost("m3", None, 5.0),
+        ];
+        let html = render("/_dashboard", &"30d".into(), &on_demand, &amortized);
+        assert!(html.contains("<title>Cost Explorer - Amortized vs On-Demand</title>"));
+        let claude = html.find(">claude</a>").unwrap();
+        let unnamed = html.find(">m3</a>").unwrap();
+        assert!(claude < unnamed && unnamed < html.find(">llama</a>").unwrap());
+        assert!(html.contains("-40.00 USD"));
+        assert!(html.contains("-40.0%"));
+        assert!(html.contains(r#"href="/_dashboard/models/m1""#));
+        assert!(!html.contains("Only one of the two metrics"));
+
+        let html = render("/", &"30d".into(), &on_demand, &[]);
+        assert!(html.contains("Only one of the two metrics"));
+    }
+}
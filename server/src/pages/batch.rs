@@ -0,0 +1,144 @@
+use super::make_path;
+use chrono::{DateTime, Utc};
+use common::BatchRequest;
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+/// Requested runs listed on the page.
+pub const SHOWN_REQUESTS: i64 = 20;
+
+fn time(at: Option<DateTime<Utc>>) -> String {
+    at.map_or_else(
+        || "-".to_string(),
+        |at| at.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+    )
+}
+
+/// Admin page queueing an incremental batch run, with the latest requested
+/// runs and how they went.
+pub fn render(base: &str, requests: &[BatchRequest]) -> String {
+    let action = make_path(base, "/admin/batch/run");
+    let refresh_action = make_path(base, "/admin/refresh");
+    let pending = requests.iter().any(|r| r.started_at.is_none());
+    let rows: Vec<_> = requests
+        .iter()
+        .map(|r| {
+            (
+                time(Some(r.requested_at)),
+                r.requested_by.clone(),
+                r.status(),
+                time(r.started_at),
+                time(r.finished_at),
+                r.error.clone().unwrap_or_default(),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Batch Runs"</h2>
+        <p>
+            "The batch daemon starts a requested run within 30 seconds, fetching its usual "
+            "incremental lookback from CE. Requests wait while a scheduled run is going or the "
+            "daemon is not running."
+        </p>
+        <form method="post" action={action}>
+            <button type="submit">"Run batch now"</button>
+        </form>
+        {pending.then(|| view! { <p>"A requested run is waiting for the daemon."</p> })}
+        <p>
+            "Refreshing fetches the same days of the default metric into the cost table "
+            "within the request instead, without the alerts, reports and exports of a batch run."
+        </p>
+        <form method="post" action={refresh_action}>
+            <button type="submit">"Refresh costs now"</button>
+        </form>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No batch runs requested yet."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="batch_requests">
+                    <tr>
+                        <th>"Requested"</th>
+                        <th>"By"</th>
+                        <th>"Status"</th>
+                        <th>"Started"</th>
+                        <th>"Finished"</th>
+                        <th>"Error"</th>
+                    </tr>
+                    {rows.into_iter().map(|(requested, by, status, started, finished, error)| view! {
+                        <tr>
+                            <td>{requested}</td>
+                            <td>{by}</td>
+                            <td>{status}</td>
+                            <td>{started}</td>
+                            <td>{finished}</td>
+                            <td>{error}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Batch Runs".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::link("Diagnostics", make_path(base, "/admin/diagnostics")),
+            Breadcrumb::current("Batch Runs"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![InfoRow::new("Latest Requests", &requests.len().to_string())],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn render_lists_requests_and_run_form() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        let requests = vec![
+            BatchRequest {
+                id: 2,
+                requested_by: "admin@example.com".to_string(),
+                requested_at: at,
+                started_at: None,
+                finished_at: None,
+                error: None,
+            },
+            BatchRequest {
+                id: 1,
+                requested_by: "ops@example.com".to_string(),
+                requested_at: at,
+                started_at: Some(at),
+                finished_at: Some(at),
+                error: Some("CE throttled".to_string()),
+            },
+        ];
+        let html = render("/_dashboard", &requests);
+        assert!(html.contains("<title>Cost Explorer - Batch Runs</title>"));
+        assert!(html.contains(r#"action="/_dashboard/admin/batch/run""#));
+        assert!(html.contains(r#"action="/_dashboard/admin/refresh""#));
+        assert!(html.contains("A requested run is waiting for the daemon."));
+        assert!(html.contains("<td>Pending</td>"));
+        assert!(html.contains("<td>Failed</td>"));
+        assert!(html.contains("<td>CE throttled</td>"));
+        assert!(html.contains("<td>2025-03-01 09:30:00 UTC</td>"));
+
+        let html = render("/", &[]);
+        assert!(html.contains("No batch runs requested yet."));
+        assert!(!html.contains("waiting for the daemon"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+
+use super::{make_path, with_period, NavContext};
+use chrono::{Datelike, NaiveDate};
+use common::{Amount, CostRecord};
+use leptos::prelude::*;
+use templates::{calendar_heatmap, period_links, Breadcrumb, HeatmapCell, InfoRow, Page};
+
+/// One cell per day from `start` to `end`, weeks starting on Monday, shaded
+/// relative to the most expensive day.
+fn heatmap_cells(
+    base: &str,
+    nav: &NavContext,
+    origin: Option<&str>,
+    start: NaiveDate,
+    end: NaiveDate,
+    totals: &BTreeMap<NaiveDate, Amount>,
+    currency: &str,
+) -> Vec<HeatmapCell> {
+    let max = totals.values().copied().max().unwrap_or_default();
+    let first_monday =
+        start - chrono::Duration::days(start.weekday().num_days_from_monday() as i64);
+    start
+        .iter_days()
+        .take_while(|d| *d <= end)
+        .map(|day| {
+            let amount = totals.get(&day).copied().unwrap_or_default();
+            let intensity = if max > Amount::ZERO {
+                amount.to_f64() / max.to_f64()
+            } else {
+                0.0
+            };
+            let date = day.format("%Y-%m-%d").to_string();
+            HeatmapCell {
+                week: ((day - first_monday).num_days() / 7) as usize,
+                weekday: day.weekday().num_days_from_monday() as usize,
+                intensity,
+                title: format!("{}: {:.2} {}", date, amount, currency),
+                href: nav.drill(&make_path(base, &format!("/costs/daily/{}", date)), origin),
+            }
+        })
+        .collect()
+}
+
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    start: NaiveDate,
+    end: NaiveDate,
+    daily_cost: &[CostRecord],
+) -> String {
+    let period = nav.period.as_str();
+    let currency = daily_cost
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let mut totals: BTreeMap<NaiveDate, Amount> = BTreeMap::new();
+    for r in daily_cost {
+        if let Ok(date) = NaiveDate::parse_from_str(&r.date, "%Y-%m-%d") {
+            *totals.entry(date).or_default() += r.amount;
+        }
+    }
+    let total: Amount = totals.values().sum();
+    let busiest = totals
+        .iter()
+        .max_by_key(|(_, amount)| **amount)
+        .filter(|(_, amount)| !amount.is_zero())
+        .map(|(date, amount)| format!("{} ({:.2} {})", date.format("%Y-%m-%d"), amount, currency));
+    let self_path = with_period(&make_path(base, "/costs/calendar"), period);
+    let origin = nav.here(&self_path, 1);
+    let svg = calendar_heatmap(&heatmap_cells(
+        base,
+        nav,
+        origin.as_deref(),
+        start,
+        end,
+        &totals,
+        &currency,
+    ));
+
+    let content = view! {
+        <h2>"Cost Calendar"</h2>
+        <div inner_html={svg}></div>
+    };
+
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/calendar"), period),
+        ),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+    ];
+    if let Some(busiest) = busiest {
+        info_rows.push(InfoRow::new("Busiest Day", &busiest));
+    }
+
+    Page {
+        title: "Cost Explorer - Calendar".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Calendar"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, micros: i64) -> CostRecord {
+        CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn heatmap_cells_cover_every_day() {
+        let totals = BTreeMap::from([
+            (date("2024-01-03"), Amount::from_micros(1_000_000)),
+            (date("2024-01-08"), Amount::from_micros(4_000_000)),
+        ]);
+        let nav: NavContext = "30d".into();
+        let cells = heatmap_cells(
+            "/",
+            &nav,
+            None,
+            date("2024-01-03"),
+            date("2024-01-09"),
+            &totals,
+            "USD",
+        );
+        assert_eq!(cells.len(), 7);
+        // 2024-01-03 is a Wednesday in the first week.
+        assert_eq!((cells[0].week, cells[0].weekday), (0, 2));
+        assert!((cells[0].intensity - 0.25).abs() < 1e-9);
+        // 2024-01-08 is the following Monday.
+        assert_eq!((cells[5].week, cells[5].weekday), (1, 0));
+        assert!((cells[5].intensity - 1.0).abs() < 1e-9);
+        assert_eq!(cells[1].intensity, 0.0);
+        assert_eq!(cells[5].href, "/costs/daily/2024-01-08");
+    }
+
+    #[test]
+    fn render_shows_heatmap_and_busiest_day() {
+        let daily = vec![
+            record("2024-01-03", 1_000_000),
+            record("2024-01-04", 3_000_000),
+        ];
+        let html = render(
+            "/",
+            &"7d".into(),
+            date("2024-01-01"),
+            date("2024-01-07"),
+            &daily,
+        );
+        assert!(html.contains("<title>Cost Explorer - Calendar</title>"));
+        assert!(html.contains("<svg class=\"heatmap\""));
+        assert!(html.contains("/costs/daily/2024-01-04?period=7d"));
+        assert!(html.contains("2024-01-04 (3.00 USD)"));
+        assert!(html.contains("4.00 USD"));
+    }
+}
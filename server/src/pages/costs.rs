@@ -1,12 +1,51 @@
-use super::{make_path, paginate, with_period, PAGE_SIZE};
-use common::{CostByModel, CostByUser, CostRecord};
+use super::{
+    change_cells, compare_info_rows, compare_links, make_path, paginate, with_compare, with_period,
+    NavContext,
+};
+use chrono::{Duration, NaiveDate};
+use common::{Amount, CostByModel, CostByUser, CostRecord};
 use leptos::either::Either;
 use leptos::prelude::*;
-use templates::{pagination_nav, period_links, Breadcrumb, InfoRow, NavLink, Page, Subpage};
+use std::collections::HashMap;
+use templates::{
+    pagination_nav, period_links, stacked_bar_chart_with_line, Breadcrumb, ChartLine, InfoRow,
+    Page, StackedBar, Subpage,
+};
+
+use crate::service::MOVING_AVERAGE_DAYS;
+
+/// Change of `date`'s cost from the cost `days` earlier, or `None` when
+/// either day is missing from `costs`.
+fn delta(costs: &HashMap<String, Amount>, date: &str, days: i64) -> Option<Amount> {
+    let day = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+    let earlier = (day - Duration::days(days)).format("%Y-%m-%d").to_string();
+    Some(*costs.get(date)? - *costs.get(&earlier)?)
+}
+
+/// A delta cell: the signed change with an up or down arrow and its CSS
+/// class, or "-" with no class when there is nothing to compare.
+fn delta_cell(delta: Option<Amount>, currency: &str) -> (String, Option<&'static str>) {
+    match delta {
+        Some(d) if d > Amount::ZERO => (format!("▲ +{:.2} {}", d, currency), Some("delta-up")),
+        Some(d) if d < Amount::ZERO => (format!("▼ {:.2} {}", d, currency), Some("delta-down")),
+        Some(d) => (format!("{:.2} {}", d, currency), None),
+        None => ("-".to_string(), None),
+    }
+}
 
-pub fn render(base: &str, period: &str, page: usize, daily_cost: &[CostRecord]) -> String {
+/// Daily cost table and chart. `average` holds each day's moving average,
+/// drawn over the chart and shown as a column.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    daily_cost: &[CostRecord],
+    average: &[CostRecord],
+    previous: Option<&[CostRecord]>,
+) -> String {
+    let period = nav.period.as_str();
     let daily_cost = daily_cost.to_vec();
-    let total: f64 = daily_cost.iter().map(|r| r.amount).sum();
+    let total: Amount = daily_cost.iter().map(|r| r.amount).sum();
     let currency = daily_cost
         .first()
         .map(|r| r.currency.clone())
@@ -17,9 +56,44 @@ pub fn render(base: &str, period: &str, page: usize, daily_cost: &[CostRecord])
     let start_owned = start.to_string();
     let end_owned = end.to_string();
     let base_owned = base.to_string();
-    let (page_items, page) = paginate(&daily_cost, page);
-    let self_path = with_period(&make_path(base, "/costs/daily"), period);
-    let pagination_html = pagination_nav(&self_path, page, daily_cost.len(), PAGE_SIZE);
+    // Previous-period records arrive already moved onto the dates they are
+    // compared against.
+    let compare = previous.is_some();
+    let previous_map: HashMap<String, Amount> = previous
+        .unwrap_or_default()
+        .iter()
+        .map(|r| (r.date.clone(), r.amount))
+        .collect();
+    let previous_total = previous.map(|p| p.iter().map(|r| r.amount).sum::<Amount>());
+    // Looked up by date, as the rows may be sorted by cost.
+    let cost_map: HashMap<String, Amount> =
+        daily_cost.iter().map(|r| (r.date.clone(), r.amount)).collect();
+    let average_map: HashMap<String, Amount> =
+        average.iter().map(|r| (r.date.clone(), r.amount)).collect();
+    let mut by_date: Vec<&CostRecord> = daily_cost.iter().collect();
+    by_date.sort_by(|a, b| a.date.cmp(&b.date));
+    let bars: Vec<StackedBar> = by_date
+        .iter()
+        .map(|r| StackedBar {
+            label: r.date.clone(),
+            href: nav.drill(&make_path(base, &format!("/costs/daily/{}", r.date)), None),
+            values: vec![r.amount.to_f64()],
+        })
+        .collect();
+    let line = ChartLine {
+        label: format!("{}-day average", MOVING_AVERAGE_DAYS),
+        values: by_date
+            .iter()
+            .map(|r| average_map.get(&r.date).map_or(0.0, |a| a.to_f64()))
+            .collect(),
+    };
+    let chart = stacked_bar_chart_with_line(&["Cost".to_string()], &bars, Some(&line));
+    let (page_items, page) = paginate(&daily_cost, page, nav.page_size);
+    let period_path = with_period(&make_path(base, "/costs/daily"), period);
+    let self_path = with_compare(&period_path, compare);
+    let origin = nav.here(&self_path, page);
+    let pagination_html =
+        pagination_nav(&nav.with_from(&self_path), page, daily_cost.len(), nav.page_size);
 
     let content = view! {
         <h2>"Daily Cost Breakdown"</h2>
@@ -29,19 +103,45 @@ pub fn render(base: &str, period: &str, page: usize, daily_cost: &[CostRecord])
             })
         } else {
             Either::Right(view! {
+                <div inner_html={chart}></div>
                 <table class="data-table" data-export-name="daily_cost" data-start={start_owned} data-end={end_owned}>
                     <tr>
                         <th>"Date"</th>
                         <th>"Cost"</th>
+                        <th>{format!("{}-Day Avg", MOVING_AVERAGE_DAYS)}</th>
+                        <th>"vs Prev Day"</th>
+                        <th>"vs Last Week"</th>
+                        {compare.then(|| view! {
+                            <th>"Previous"</th>
+                            <th>"Change"</th>
+                            <th>"Change %"</th>
+                        })}
                     </tr>
                     {page_items.iter().map(|r| {
-                        let date_href = make_path(&base_owned, &format!("/costs/daily/{}", r.date));
+                        let date_href = nav.drill(&make_path(&base_owned, &format!("/costs/daily/{}", r.date)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", r.amount, r.currency);
+                        let average_str = average_map
+                            .get(&r.date)
+                            .map_or_else(|| "-".to_string(), |a| format!("{:.2} {}", a, r.currency));
+                        let (day_delta, day_class) = delta_cell(delta(&cost_map, &r.date, 1), &r.currency);
+                        let (week_delta, week_class) = delta_cell(delta(&cost_map, &r.date, 7), &r.currency);
+                        let change = compare.then(|| {
+                            let previous = previous_map.get(&r.date).copied().unwrap_or_default();
+                            change_cells(r.amount, previous, &r.currency)
+                        });
                         let date = r.date.clone();
                         view! {
                             <tr>
                                 <td><a href={date_href}>{date}</a></td>
                                 <td>{cost_str}</td>
+                                <td>{average_str}</td>
+                                <td class={day_class}>{day_delta}</td>
+                                <td class={week_class}>{week_delta}</td>
+                                {change.map(|[previous, change, percent]| view! {
+                                    <td>{previous}</td>
+                                    <td>{change}</td>
+                                    <td>{percent}</td>
+                                })}
                             </tr>
                         }
                     }).collect::<Vec<_>>()}
@@ -51,20 +151,27 @@ pub fn render(base: &str, period: &str, page: usize, daily_cost: &[CostRecord])
         }}
     };
 
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&with_compare(&make_path(base, "/costs/daily"), compare), period),
+        ),
+        InfoRow::raw("Compare", compare_links(&period_path, compare)),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+    ];
+    info_rows.extend(compare_info_rows(total, previous_total, &currency));
+
     Page {
         title: "Cost Explorer - Daily Cost".to_string(),
         breadcrumbs: vec![
             Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
             Breadcrumb::current("Daily Cost"),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::raw(
-                "Period",
-                period_links(&make_path(base, "/costs/daily"), period),
-            ),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
-        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
         content,
         subpages: vec![],
     }
@@ -73,13 +180,18 @@ pub fn render(base: &str, period: &str, page: usize, daily_cost: &[CostRecord])
 
 pub fn render_hub(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     date: &str,
-    total_cost: f64,
+    total_cost: Amount,
     currency: &str,
     user_count: usize,
     model_count: usize,
 ) -> String {
+    let period = nav.period.as_str();
+    let origin = nav.here(
+        &with_period(&make_path(base, &format!("/costs/daily/{}", date)), period),
+        1,
+    );
     Page {
         title: format!("Cost Explorer - {}", date),
         breadcrumbs: vec![
@@ -90,7 +202,10 @@ pub fn render_hub(
             ),
             Breadcrumb::current(date),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Date", date),
             InfoRow::new("Total Cost", &format!("{:.2} {}", total_cost, currency)),
@@ -99,12 +214,18 @@ pub fn render_hub(
         subpages: vec![
             Subpage::new(
                 "By User",
-                make_path(base, &format!("/costs/daily/{}/users", date)),
+                nav.drill(
+                    &make_path(base, &format!("/costs/daily/{}/users", date)),
+                    origin.as_deref(),
+                ),
                 user_count,
             ),
             Subpage::new(
                 "By Model",
-                make_path(base, &format!("/costs/daily/{}/models", date)),
+                nav.drill(
+                    &make_path(base, &format!("/costs/daily/{}/models", date)),
+                    origin.as_deref(),
+                ),
                 model_count,
             ),
         ],
@@ -114,23 +235,28 @@ pub fn render_hub(
 
 pub fn render_users(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     date: &str,
     costs: &[CostByUser],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
     let date_owned = date.to_string();
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(base, &format!("/costs/daily/{}/users", date));
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/daily/{}/users", date)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Cost by User"</h2>
@@ -148,7 +274,7 @@ pub fn render_users(
                     {page_items.iter().map(|c| {
                         let display = c.user_email.clone()
                             .unwrap_or_else(|| c.user_id.clone());
-                        let href = make_path(&base_owned, &format!("/costs/daily/{}/users/{}", date_owned, c.user_id));
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/daily/{}/users/{}", date_owned, c.user_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         view! {
                             <tr>
@@ -171,10 +297,16 @@ pub fn render_users(
                 "Daily Cost",
                 with_period(&make_path(base, "/costs/daily"), period),
             ),
-            Breadcrumb::link(date, make_path(base, &format!("/costs/daily/{}", date))),
+            Breadcrumb::link(
+                date,
+                with_period(&make_path(base, &format!("/costs/daily/{}", date)), period),
+            ),
             Breadcrumb::current("By User"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Date", date),
             InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
@@ -187,23 +319,28 @@ pub fn render_users(
 
 pub fn render_models(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     date: &str,
     costs: &[CostByModel],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
     let date_owned = date.to_string();
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(base, &format!("/costs/daily/{}/models", date));
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/daily/{}/models", date)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Cost by Model"</h2>
@@ -221,7 +358,7 @@ pub fn render_models(
                     {page_items.iter().map(|c| {
                         let display = c.model_name.clone()
                             .unwrap_or_else(|| c.model_id.clone());
-                        let href = make_path(&base_owned, &format!("/costs/daily/{}/models/{}", date_owned, c.model_id));
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/daily/{}/models/{}", date_owned, c.model_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         view! {
                             <tr>
@@ -244,10 +381,16 @@ pub fn render_models(
                 "Daily Cost",
                 with_period(&make_path(base, "/costs/daily"), period),
             ),
-            Breadcrumb::link(date, make_path(base, &format!("/costs/daily/{}", date))),
+            Breadcrumb::link(
+                date,
+                with_period(&make_path(base, &format!("/costs/daily/{}", date)), period),
+            ),
             Breadcrumb::current("By Model"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Date", date),
             InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
@@ -260,22 +403,26 @@ pub fn render_models(
 
 pub fn render_user_models(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     date: &str,
     user_email: &str,
     costs: &[CostByModel],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(base, &format!("/costs/daily/{}/users/{}", date, user_email));
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/daily/{}/users/{}", date, user_email)),
+        period,
+    );
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Models for "{user_email}</h2>
@@ -315,14 +462,23 @@ pub fn render_user_models(
                 "Daily Cost",
                 with_period(&make_path(base, "/costs/daily"), period),
             ),
-            Breadcrumb::link(date, make_path(base, &format!("/costs/daily/{}", date))),
+            Breadcrumb::link(
+                date,
+                with_period(&make_path(base, &format!("/costs/daily/{}", date)), period),
+            ),
             Breadcrumb::link(
                 "By User",
-                make_path(base, &format!("/costs/daily/{}/users", date)),
+                with_period(
+                    &make_path(base, &format!("/costs/daily/{}/users", date)),
+                    period,
+                ),
             ),
             Breadcrumb::current(user_email),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Date", date),
             InfoRow::new("User", user_email),
@@ -336,25 +492,26 @@ pub fn render_user_models(
 
 pub fn render_model_users(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     date: &str,
     model_name: &str,
     costs: &[CostByUser],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(
-        base,
-        &format!("/costs/daily/{}/models/{}", date, model_name),
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/daily/{}/models/{}", date, model_name)),
+        period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Users for "{model_name}</h2>
@@ -394,14 +551,23 @@ pub fn render_model_users(
                 "Daily Cost",
                 with_period(&make_path(base, "/costs/daily"), period),
             ),
-            Breadcrumb::link(date, make_path(base, &format!("/costs/daily/{}", date))),
+            Breadcrumb::link(
+                date,
+                with_period(&make_path(base, &format!("/costs/daily/{}", date)), period),
+            ),
             Breadcrumb::link(
                 "By Model",
-                make_path(base, &format!("/costs/daily/{}/models", date)),
+                with_period(
+                    &make_path(base, &format!("/costs/daily/{}/models", date)),
+                    period,
+                ),
             ),
             Breadcrumb::current(model_name),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Date", date),
             InfoRow::new("Model", model_name),
@@ -421,23 +587,23 @@ mod tests {
     fn render_contains_title() {
         let daily = vec![CostRecord {
             date: "2024-01-15".to_string(),
-            amount: 123.45,
+            amount: Amount::from_f64(123.45),
             currency: "USD".to_string(),
         }];
-        let html = render("/", "30d", 1, &daily);
+        let html = render("/", &"30d".into(), 1, &daily, &[], None);
         assert!(html.contains("<title>Cost Explorer - Daily Cost</title>"));
     }
 
     #[test]
     fn render_contains_breadcrumbs() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], &[], None);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
     }
 
     #[test]
     fn render_contains_period_links() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], &[], None);
         assert!(html.contains("<b>Past 30 Days</b>"));
         assert!(html.contains("?period=7d"));
     }
@@ -446,43 +612,119 @@ mod tests {
     fn render_contains_total_cost() {
         let daily = vec![CostRecord {
             date: "2024-01-15".to_string(),
-            amount: 99.99,
+            amount: Amount::from_f64(99.99),
             currency: "USD".to_string(),
         }];
-        let html = render("/", "30d", 1, &daily);
+        let html = render("/", &"30d".into(), 1, &daily, &[], None);
         assert!(html.contains("99.99 USD"));
     }
 
+    #[test]
+    fn render_compare_shows_change_columns() {
+        let daily = vec![CostRecord {
+            date: "2024-01-15".to_string(),
+            amount: Amount::from_f64(120.0),
+            currency: "USD".to_string(),
+        }];
+        let previous = vec![CostRecord {
+            date: "2024-01-15".to_string(),
+            amount: Amount::from_f64(100.0),
+            currency: "USD".to_string(),
+        }];
+        let html = render("/", &"7d".into(), 1, &daily, &[], Some(&previous));
+        assert!(html.contains("<th>Change %</th>"));
+        assert!(html.contains("+20.00 USD"));
+        assert!(html.contains("+20.0%"));
+        assert!(html.contains("/costs/daily?compare=prev&period=30d"));
+    }
+
     #[test]
     fn render_contains_daily_table() {
         let daily = vec![
             CostRecord {
                 date: "2024-01-15".to_string(),
-                amount: 50.0,
+                amount: Amount::from_f64(50.0),
                 currency: "USD".to_string(),
             },
             CostRecord {
                 date: "2024-01-16".to_string(),
-                amount: 75.0,
+                amount: Amount::from_f64(75.0),
                 currency: "USD".to_string(),
             },
         ];
-        let html = render("/", "30d", 1, &daily);
+        let html = render("/", &"30d".into(), 1, &daily, &[], None);
         assert!(html.contains("2024-01-15"));
         assert!(html.contains("2024-01-16"));
         assert!(html.contains("50.00 USD"));
         assert!(html.contains("75.00 USD"));
     }
 
+    #[test]
+    fn delta_compares_with_earlier_dates() {
+        let costs: HashMap<String, Amount> = [
+            ("2024-01-08", 10.0),
+            ("2024-01-14", 30.0),
+            ("2024-01-15", 25.0),
+        ]
+        .into_iter()
+        .map(|(d, a)| (d.to_string(), Amount::from_f64(a)))
+        .collect();
+        assert_eq!(delta(&costs, "2024-01-15", 1), Some(Amount::from_f64(-5.0)));
+        assert_eq!(delta(&costs, "2024-01-15", 7), Some(Amount::from_f64(15.0)));
+        assert_eq!(delta(&costs, "2024-01-14", 1), None);
+        assert_eq!(delta(&costs, "2024-01-16", 1), None);
+    }
+
+    #[test]
+    fn render_shows_day_and_week_deltas() {
+        let daily: Vec<CostRecord> = [
+            ("2024-01-08", 10.0),
+            ("2024-01-14", 30.0),
+            ("2024-01-15", 25.0),
+        ]
+        .into_iter()
+        .map(|(date, amount)| CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        })
+        .collect();
+        let html = render("/", &"30d".into(), 1, &daily, &[], None);
+        assert!(html.contains("<th>vs Prev Day</th>"));
+        assert!(html.contains("<th>vs Last Week</th>"));
+        assert!(html.contains(r#"<td class="delta-down">▼ -5.00 USD</td>"#));
+        assert!(html.contains(r#"<td class="delta-up">▲ +15.00 USD</td>"#));
+        assert!(html.contains("<td>-</td>"));
+    }
+
+    #[test]
+    fn render_shows_moving_average() {
+        let daily = vec![CostRecord {
+            date: "2024-01-15".to_string(),
+            amount: Amount::from_f64(50.0),
+            currency: "USD".to_string(),
+        }];
+        let average = vec![CostRecord {
+            date: "2024-01-15".to_string(),
+            amount: Amount::from_f64(42.5),
+            currency: "USD".to_string(),
+        }];
+        let html = render("/", &"30d".into(), 1, &daily, &average, None);
+        assert!(html.contains("<th>7-Day Avg</th>"));
+        assert!(html.contains("<td>42.50 USD</td>"));
+        assert!(html.contains(r#"<polyline class="chart-line""#));
+        assert!(html.contains("7-day average"));
+    }
+
     #[test]
     fn render_empty_daily_cost() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], &[], None);
         assert!(html.contains("No cost data found for this period."));
     }
 
     #[test]
     fn render_uses_custom_base_path() {
-        let html = render("/_dashboard", "30d", 1, &[]);
+        let html = render("/_dashboard", &"30d".into(), 1, &[], &[], None);
         assert!(html.contains("/_dashboard/costs/daily"));
     }
 
@@ -491,16 +733,16 @@ mod tests {
         let daily = vec![
             CostRecord {
                 date: "2024-01-15".to_string(),
-                amount: 50.0,
+                amount: Amount::from_f64(50.0),
                 currency: "USD".to_string(),
             },
             CostRecord {
                 date: "2024-01-16".to_string(),
-                amount: 75.0,
+                amount: Amount::from_f64(75.0),
                 currency: "USD".to_string(),
             },
         ];
-        let html = render("/", "30d", 1, &daily);
+        let html = render("/", &"30d".into(), 1, &daily, &[], None);
         assert!(html.contains("/costs/daily/2024-01-15"));
         assert!(html.contains("/costs/daily/2024-01-16"));
         assert!(html.contains("<a href=\"/costs/daily/2024-01-15\">"));
@@ -511,22 +753,22 @@ mod tests {
     fn render_dates_are_links_custom_base() {
         let daily = vec![CostRecord {
             date: "2024-01-15".to_string(),
-            amount: 50.0,
+            amount: Amount::from_f64(50.0),
             currency: "USD".to_string(),
         }];
-        let html = render("/_dashboard", "30d", 1, &daily);
+        let html = render("/_dashboard", &"30d".into(), 1, &daily, &[], None);
         assert!(html.contains("/_dashboard/costs/daily/2024-01-15"));
     }
 
     #[test]
     fn render_hub_contains_title() {
-        let html = render_hub("/", "30d", "2024-01-15", 123.45, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01-15", Amount::from_f64(123.45), "USD", 3, 2);
         assert!(html.contains("<title>Cost Explorer - 2024-01-15</title>"));
     }
 
     #[test]
     fn render_hub_contains_breadcrumbs() {
-        let html = render_hub("/", "30d", "2024-01-15", 123.45, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01-15", Amount::from_f64(123.45), "USD", 3, 2);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("2024-01-15"));
@@ -534,14 +776,14 @@ mod tests {
 
     #[test]
     fn render_hub_contains_info_rows() {
-        let html = render_hub("/", "30d", "2024-01-15", 123.45, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01-15", Amount::from_f64(123.45), "USD", 3, 2);
         assert!(html.contains("2024-01-15"));
         assert!(html.contains("123.45 USD"));
     }
 
     #[test]
     fn render_hub_contains_subpage_links() {
-        let html = render_hub("/", "30d", "2024-01-15", 123.45, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01-15", Amount::from_f64(123.45), "USD", 3, 2);
         assert!(html.contains("By User"));
         assert!(html.contains("By Model"));
         assert!(html.contains("/costs/daily/2024-01-15/users"));
@@ -550,14 +792,14 @@ mod tests {
 
     #[test]
     fn render_hub_custom_base() {
-        let html = render_hub("/_dashboard", "30d", "2024-01-15", 50.0, "USD", 1, 1);
+        let html = render_hub("/_dashboard", &"30d".into(), "2024-01-15", Amount::from_f64(50.0), "USD", 1, 1);
         assert!(html.contains("/_dashboard/costs/daily/2024-01-15/users"));
         assert!(html.contains("/_dashboard/costs/daily/2024-01-15/models"));
     }
 
     #[test]
     fn render_users_empty() {
-        let html = render_users("/", "30d", 1, "2024-01-15", &[]);
+        let html = render_users("/", &"30d".into(), 1, "2024-01-15", &[]);
         assert!(html.contains("No cost data found for this date."));
     }
 
@@ -566,10 +808,10 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "user-1".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 42.0,
+            amount: Amount::from_f64(42.0),
             currency: "USD".to_string(),
         }];
-        let html = render_users("/", "30d", 1, "2024-01-15", &costs);
+        let html = render_users("/", &"30d".into(), 1, "2024-01-15", &costs);
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("42.00 USD"));
         assert!(html.contains("/costs/daily/2024-01-15/users/user-1"));
@@ -577,7 +819,7 @@ mod tests {
 
     #[test]
     fn render_users_breadcrumbs() {
-        let html = render_users("/", "30d", 1, "2024-01-15", &[]);
+        let html = render_users("/", &"30d".into(), 1, "2024-01-15", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("2024-01-15"));
@@ -589,16 +831,16 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "user-1".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 10.0,
+            amount: Amount::from_f64(10.0),
             currency: "USD".to_string(),
         }];
-        let html = render_users("/", "30d", 1, "2024-01-15", &costs);
+        let html = render_users("/", &"30d".into(), 1, "2024-01-15", &costs);
         assert!(html.contains("<a href=\"/costs/daily/2024-01-15/users/user-1\">"));
     }
 
     #[test]
     fn render_models_empty() {
-        let html = render_models("/", "30d", 1, "2024-01-15", &[]);
+        let html = render_models("/", &"30d".into(), 1, "2024-01-15", &[]);
         assert!(html.contains("No cost data found for this date."));
     }
 
@@ -607,10 +849,10 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 55.0,
+            amount: Amount::from_f64(55.0),
             currency: "USD".to_string(),
         }];
-        let html = render_models("/", "30d", 1, "2024-01-15", &costs);
+        let html = render_models("/", &"30d".into(), 1, "2024-01-15", &costs);
         assert!(html.contains("claude-3"));
         assert!(html.contains("55.00 USD"));
         assert!(html.contains("/costs/daily/2024-01-15/models/model-1"));
@@ -618,7 +860,7 @@ mod tests {
 
     #[test]
     fn render_models_breadcrumbs() {
-        let html = render_models("/", "30d", 1, "2024-01-15", &[]);
+        let html = render_models("/", &"30d".into(), 1, "2024-01-15", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("2024-01-15"));
@@ -630,16 +872,16 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 10.0,
+            amount: Amount::from_f64(10.0),
             currency: "USD".to_string(),
         }];
-        let html = render_models("/", "30d", 1, "2024-01-15", &costs);
+        let html = render_models("/", &"30d".into(), 1, "2024-01-15", &costs);
         assert!(html.contains("<a href=\"/costs/daily/2024-01-15/models/model-1\">"));
     }
 
     #[test]
     fn render_user_models_empty() {
-        let html = render_user_models("/", "30d", 1, "2024-01-15", "alice@example.com", &[]);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01-15", "alice@example.com", &[]);
         assert!(html.contains("No cost data found."));
     }
 
@@ -648,10 +890,10 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 30.0,
+            amount: Amount::from_f64(30.0),
             currency: "USD".to_string(),
         }];
-        let html = render_user_models("/", "30d", 1, "2024-01-15", "alice@example.com", &costs);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01-15", "alice@example.com", &costs);
         assert!(html.contains("claude-3"));
         assert!(html.contains("30.00 USD"));
         // Leaf page: model names are plain text, not links
@@ -660,7 +902,7 @@ mod tests {
 
     #[test]
     fn render_user_models_breadcrumbs() {
-        let html = render_user_models("/", "30d", 1, "2024-01-15", "alice@example.com", &[]);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01-15", "alice@example.com", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("2024-01-15"));
@@ -670,7 +912,7 @@ mod tests {
 
     #[test]
     fn render_model_users_empty() {
-        let html = render_model_users("/", "30d", 1, "2024-01-15", "claude-3", &[]);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01-15", "claude-3", &[]);
         assert!(html.contains("No cost data found."));
     }
 
@@ -679,10 +921,10 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "user-1".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 25.0,
+            amount: Amount::from_f64(25.0),
             currency: "USD".to_string(),
         }];
-        let html = render_model_users("/", "30d", 1, "2024-01-15", "claude-3", &costs);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01-15", "claude-3", &costs);
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("25.00 USD"));
         // Leaf page: user emails are plain text, not links
@@ -691,7 +933,7 @@ mod tests {
 
     #[test]
     fn render_model_users_breadcrumbs() {
-        let html = render_model_users("/", "30d", 1, "2024-01-15", "claude-3", &[]);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01-15", "claude-3", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("2024-01-15"));
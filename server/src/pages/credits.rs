@@ -0,0 +1,211 @@
+use super::{make_path, with_period, NavContext};
+use common::{Amount, CostByModel, CostByUser, CostRecord};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// The CE credits and refunds of the period by day, user and model, next to
+/// the gross CE cost they were taken off. Credits are negative, so the
+/// largest come first.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    gross: Amount,
+    daily: &[CostRecord],
+    users: &[CostByUser],
+    models: &[CostByModel],
+) -> String {
+    let period = nav.period.as_str();
+    let currency = daily
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let credits: Amount = daily.iter().map(|r| r.amount).sum();
+    let empty = daily.iter().all(|r| r.amount == Amount::ZERO);
+    let origin = nav.here(&with_period(&make_path(base, "/costs/credits"), period), 1);
+
+    let days: Vec<_> = daily
+        .iter()
+        .filter(|r| r.amount != Amount::ZERO)
+        .map(|r| {
+            let href = nav.drill(
+                &make_path(base, &format!("/costs/daily/{}", r.date)),
+                origin.as_deref(),
+            );
+            (
+                href,
+                r.date.clone(),
+                format!("{:.2} {}", r.amount, r.currency),
+            )
+        })
+        .collect();
+    let mut users: Vec<_> = users.iter().filter(|c| c.amount != Amount::ZERO).collect();
+    users.sort_by_key(|c| c.amount);
+    let users: Vec<_> = users
+        .into_iter()
+        .map(|c| {
+            let href = nav.drill(
+                &make_path(base, &format!("/users/{}", c.user_id)),
+                origin.as_deref(),
+            );
+            let label = c.user_email.clone().unwrap_or_else(|| c.user_id.clone());
+            (href, label, format!("{:.2} {}", c.amount, c.currency))
+        })
+        .collect();
+    let mut models: Vec<_> = models.iter().filter(|c| c.amount != Amount::ZERO).collect();
+    models.sort_by_key(|c| c.amount);
+    let models: Vec<_> = models
+        .into_iter()
+        .map(|c| {
+            let href = nav.drill(
+                &make_path(base, &format!("/models/{}", c.model_id)),
+                origin.as_deref(),
+            );
+            let label = c.model_name.clone().unwrap_or_else(|| c.model_id.clone());
+            (href, label, format!("{:.2} {}", c.amount, c.currency))
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Credits and Refunds"</h2>
+        <p>
+            "CE reports credits and refunds as negative line items. They are kept apart from "
+            "the gross cost of the usage they were applied to; totals elsewhere are net of them."
+        </p>
+        {if empty {
+            Either::Left(view! {
+                <p>"No credits or refunds found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <h3>"By Day"</h3>
+                <table class="data-table" data-export-name="credits_by_day">
+                    <tr>
+                        <th>"Date"</th>
+                        <th>"Credits"</th>
+                    </tr>
+                    {days.into_iter().map(|(href, date, amount)| view! {
+                        <tr>
+                            <td><a href={href}>{date}</a></td>
+                            <td>{amount}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+                <h3>"By User"</h3>
+                <table class="data-table" data-export-name="credits_by_user">
+                    <tr>
+                        <th>"User"</th>
+                        <th>"Credits"</th>
+                    </tr>
+                    {users.into_iter().map(|(href, label, amount)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{amount}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+                <h3>"By Model"</h3>
+                <table class="data-table" data-export-name="credits_by_model">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Credits"</th>
+                    </tr>
+                    {models.into_iter().map(|(href, label, amount)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{amount}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    let info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/credits"), period),
+        ),
+        InfoRow::new("Gross Cost", &format!("{:.2} {}", gross, currency)),
+        InfoRow::new("Credits", &format!("{:.2} {}", credits, currency)),
+        InfoRow::new("Net Cost", &format!("{:.2} {}", gross + credits, currency)),
+    ];
+
+    Page {
+        title: "Cost Explorer - Credits".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Credits"),
+        ],
+        search: Some(nav.search(base)),
+        // Always the CE gross cost and credits, whatever the source filter.
+        source_filter: None,
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(date: &str, amount: f64) -> CostRecord {
+        CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_lists_credits_largest_first() {
+        let daily = vec![
+            day("2025-03-01", -5.0),
+            day("2025-03-02", 0.0),
+            day("2025-03-03", -15.0),
+        ];
+        let users = vec![
+            CostByUser {
+                user_id: "u1".to_string(),
+                user_email: Some("alice@example.com".to_string()),
+                amount: Amount::from_f64(-5.0),
+                currency: "USD".to_string(),
+            },
+            CostByUser {
+                user_id: "u2".to_string(),
+                user_email: None,
+                amount: Amount::from_f64(-15.0),
+                currency: "USD".to_string(),
+            },
+        ];
+        let models = vec![CostByModel {
+            model_id: "m1".to_string(),
+            model_name: Some("claude".to_string()),
+            amount: Amount::from_f64(-20.0),
+            currency: "USD".to_string(),
+        }];
+        let html = render(
+            "/_dashboard",
+            &"30d".into(),
+            Amount::from_f64(120.0),
+            &daily,
+            &users,
+            &models,
+        );
+        assert!(html.contains("<title>Cost Explorer - Credits</title>"));
+        assert!(html.contains("120.00 USD"));
+        assert!(html.contains("-20.00 USD"));
+        assert!(html.contains("100.00 USD"));
+        assert!(html.contains(r#"href="/_dashboard/costs/daily/2025-03-03""#));
+        assert!(!html.contains(">2025-03-02</a>"));
+        assert!(html.find(">u2</a>").unwrap() < html.find(">alice@example.com</a>").unwrap());
+        assert!(html.contains(r#"href="/_dashboard/models/m1""#));
+
+        let html = render("/", &"30d".into(), Amount::from_f64(120.0), &[], &[], &[]);
+        assert!(html.contains("No credits or refunds found for this period."));
+    }
+}
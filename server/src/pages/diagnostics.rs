@@ -0,0 +1,264 @@
+use super::make_path;
+use chrono::{DateTime, Utc};
+use common::PoolStatus;
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+use crate::reload::Reload;
+use crate::service::Diagnostics;
+
+fn time(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+}
+
+fn or_none(value: Option<String>) -> String {
+    value.unwrap_or_else(|| "-".to_string())
+}
+
+/// Rows of a two-column table of figures.
+fn figures(rows: Vec<(&'static str, String)>) -> impl IntoView {
+    view! {
+        <table class="data-table">
+            {rows.into_iter().map(|(label, value)| view! {
+                <tr>
+                    <th>{label}</th>
+                    <td>{value}</td>
+                </tr>
+            }).collect::<Vec<_>>()}
+        </table>
+    }
+}
+
+/// Admin page of what the server holds and how CE is answering, to tell a
+/// slow or empty page from missing data: stored table sizes, the live
+/// cache, recent CE calls, database pools and background tasks.
+pub fn render(base: &str, diagnostics: &Diagnostics, reload: Option<&Reload>) -> String {
+    let tables: Vec<_> = diagnostics
+        .tables
+        .iter()
+        .map(|t| {
+            (
+                t.table.clone(),
+                t.rows.to_string(),
+                or_none(t.oldest.clone()),
+                or_none(t.newest.clone()),
+            )
+        })
+        .collect();
+
+    let cache = &diagnostics.live_cache;
+    let cache_rows = vec![
+        ("Day entries", cache.entries.to_string()),
+        ("Rows", cache.rows.to_string()),
+        (
+            "Oldest day",
+            or_none(cache.oldest_day.map(|d| d.to_string())),
+        ),
+        (
+            "Newest day",
+            or_none(cache.newest_day.map(|d| d.to_string())),
+        ),
+        ("Oldest fetch", or_none(cache.oldest_fetch.map(time))),
+    ];
+
+    let calls = &diagnostics.ce_calls;
+    let error_rate = format!("{:.1}%", calls.error_rate() * 100.0);
+    let ce_rows = vec![
+        ("Calls", calls.calls.to_string()),
+        ("Throttled", calls.throttled.to_string()),
+        ("Failed", calls.failed.to_string()),
+        ("Error rate", error_rate.clone()),
+        (
+            "Last error",
+            or_none(
+                calls
+                    .last_error
+                    .as_ref()
+                    .map(|(at, error)| format!("{} - {}", time(*at), error)),
+            ),
+        ),
+    ];
+
+    let pool = |name: &'static str, status: PoolStatus| {
+        (
+            name,
+            status.size.to_string(),
+            status.in_use().to_string(),
+            status.idle.to_string(),
+            status.max.to_string(),
+        )
+    };
+    let pools = vec![
+        pool("Cost", diagnostics.cost_pool),
+        pool("Gateway", diagnostics.gateway_pool),
+    ];
+
+    let reload_status = match reload {
+        None => "Watching; not reloaded since start".to_string(),
+        Some(Reload { at, error: None }) => format!("Watching; reloaded at {}", time(*at)),
+        Some(Reload {
+            at,
+            error: Some(error),
+        }) => format!("Watching; reload at {} failed: {}", time(*at), error),
+    };
+    let task_rows = vec![
+        ("Config reload", reload_status),
+        (
+            "Live cache refresh",
+            format!(
+                "{} day entries refreshing, {} first fetches in flight",
+                cache.refreshing, cache.in_flight
+            ),
+        ),
+    ];
+
+    let content = view! {
+        <h2>"Diagnostics"</h2>
+        <h3>"Stored Cost"</h3>
+        {if tables.is_empty() {
+            Either::Left(view! {
+                <p>"No table figures available."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table">
+                    <tr>
+                        <th>"Table"</th>
+                        <th>"Rows"</th>
+                        <th>"Oldest"</th>
+                        <th>"Newest"</th>
+                    </tr>
+                    {tables.into_iter().map(|(table, rows, oldest, newest)| view! {
+                        <tr>
+                            <td>{table}</td>
+                            <td>{rows}</td>
+                            <td>{oldest}</td>
+                            <td>{newest}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Live CE Cache"</h3>
+        {figures(cache_rows)}
+        <h3>"Cost Explorer Calls (Last Hour)"</h3>
+        {figures(ce_rows)}
+        <h3>"Database Pools"</h3>
+        <table class="data-table">
+            <tr>
+                <th>"Pool"</th>
+                <th>"Open"</th>
+                <th>"In Use"</th>
+                <th>"Idle"</th>
+                <th>"Max"</th>
+            </tr>
+            {pools.into_iter().map(|(name, size, in_use, idle, max)| view! {
+                <tr>
+                    <td>{name}</td>
+                    <td>{size}</td>
+                    <td>{in_use}</td>
+                    <td>{idle}</td>
+                    <td>{max}</td>
+                </tr>
+            }).collect::<Vec<_>>()}
+        </table>
+        <h3>"Background Tasks"</h3>
+        {figures(task_rows)}
+    };
+
+    Page {
+        title: "Cost Explorer - Diagnostics".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Diagnostics"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: diagnostics
+            .tables_error
+            .as_ref()
+            .map(|e| format!("Failed to read the cost tables: {e}")),
+        nav_links: vec![
+            NavLink::new("Batch Runs", make_path(base, "/admin/batch")),
+            NavLink::back(),
+        ],
+        info_rows: vec![
+            InfoRow::new(
+                "Data Source",
+                &format!("{:?}", diagnostics.data_source).to_lowercase(),
+            ),
+            InfoRow::new("CE Calls (Last Hour)", &calls.calls.to_string()),
+            InfoRow::new("CE Error Rate", &error_rate),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use common::TableStats;
+
+    #[test]
+    fn render_shows_every_section() {
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 9, 30, 0).unwrap();
+        let mut diagnostics = Diagnostics {
+            tables: vec![
+                TableStats {
+                    table: "cost".to_string(),
+                    rows: 1200,
+                    oldest: Some("2025-01-01".to_string()),
+                    newest: Some("2025-02-28".to_string()),
+                },
+                TableStats {
+                    table: "cost_monthly".to_string(),
+                    rows: 0,
+                    oldest: None,
+                    newest: None,
+                },
+            ],
+            cost_pool: PoolStatus {
+                size: 3,
+                idle: 1,
+                max: 5,
+            },
+            ..Diagnostics::default()
+        };
+        diagnostics.ce_calls.calls = 4;
+        diagnostics.ce_calls.failed = 1;
+        diagnostics.ce_calls.last_error = Some((at, "AccessDenied".to_string()));
+        diagnostics.live_cache.refreshing = 2;
+        let reload = Reload {
+            at,
+            error: Some("missing field".to_string()),
+        };
+
+        let html = render("/_dashboard", &diagnostics, Some(&reload));
+        assert!(html.contains("<title>Cost Explorer - Diagnostics</title>"));
+        assert!(html.contains("<td>1200</td>"));
+        assert!(html.contains("<td>2025-02-28</td>"));
+        assert!(html.contains("<td>cost_monthly</td>"));
+        assert!(html.contains("<td>-</td>"));
+        assert!(html.contains("<td>Gateway</td>"));
+        assert!(html.contains("25.0%"));
+        assert!(html.contains("2025-03-01 09:30:00 UTC - AccessDenied"));
+        assert!(html.contains("reload at 2025-03-01 09:30:00 UTC failed: missing field"));
+        assert!(html.contains("2 day entries refreshing"));
+    }
+
+    #[test]
+    fn render_shows_table_errors() {
+        let diagnostics = Diagnostics {
+            tables_error: Some("connection refused".to_string()),
+            ..Diagnostics::default()
+        };
+        let html = render("/", &diagnostics, None);
+        assert!(html.contains("Failed to read the cost tables: connection refused"));
+        assert!(html.contains("No table figures available."));
+        assert!(html.contains("not reloaded since start"));
+    }
+}
@@ -0,0 +1,314 @@
+//! How daily per-user spend is spread: a histogram over decades of cost and
+//! its percentiles, with the largest user-days named.
+
+use super::{make_path, with_period, NavContext};
+use common::{Amount, UserCostRow};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// User-days listed under "Largest User-Days".
+const TOP_USER_DAYS: usize = 10;
+
+/// Lower edge of the first bounded bucket, 0.01; each next edge is ten
+/// times the last.
+const FIRST_EDGE_MICROS: i64 = 10_000;
+
+/// User-days whose cost falls in `[low, high)`; `low` is `None` for the
+/// bucket under the first edge and `high` for the last bucket.
+#[derive(Debug, PartialEq)]
+pub struct Bucket {
+    pub low: Option<Amount>,
+    pub high: Option<Amount>,
+    pub count: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Distribution {
+    pub count: usize,
+    pub p50: Amount,
+    pub p90: Amount,
+    pub p99: Amount,
+    pub mean: Amount,
+    pub max: Amount,
+    pub buckets: Vec<Bucket>,
+}
+
+/// Nearest-rank percentile of `sorted`, which must not be empty.
+fn percentile(sorted: &[Amount], p: usize) -> Amount {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+/// Index of the bucket holding `amount`: 0 under the first edge, then one
+/// per decade.
+fn bucket_index(amount: Amount) -> usize {
+    let mut edge = FIRST_EDGE_MICROS;
+    let mut index = 0;
+    while amount.micros() >= edge {
+        index += 1;
+        match edge.checked_mul(10) {
+            Some(next) => edge = next,
+            None => break,
+        }
+    }
+    index
+}
+
+fn bucket_edge(index: usize) -> Amount {
+    Amount::from_micros(FIRST_EDGE_MICROS * 10_i64.pow(index as u32))
+}
+
+/// Statistics of the positive amounts; credits and empty days are left out.
+/// `None` when no amount is positive.
+pub fn distribution(amounts: impl IntoIterator<Item = Amount>) -> Option<Distribution> {
+    let mut sorted: Vec<Amount> = amounts.into_iter().filter(|a| *a > Amount::ZERO).collect();
+    if sorted.is_empty() {
+        return None;
+    }
+    sorted.sort();
+    let count = sorted.len();
+    let total: Amount = sorted.iter().sum();
+    let first = bucket_index(sorted[0]);
+    let last = bucket_index(sorted[count - 1]);
+    let mut buckets: Vec<Bucket> = (first..=last)
+        .map(|index| Bucket {
+            low: (index > 0).then(|| bucket_edge(index - 1)),
+            high: Some(bucket_edge(index)),
+            count: 0,
+        })
+        .collect();
+    if let Some(top) = buckets.last_mut() {
+        top.high = None;
+    }
+    for amount in &sorted {
+        buckets[bucket_index(*amount) - first].count += 1;
+    }
+    Some(Distribution {
+        count,
+        p50: percentile(&sorted, 50),
+        p90: percentile(&sorted, 90),
+        p99: percentile(&sorted, 99),
+        mean: Amount::from_micros(total.micros() / count as i64),
+        max: sorted[count - 1],
+        buckets,
+    })
+}
+
+fn bucket_label(bucket: &Bucket) -> String {
+    match (bucket.low, bucket.high) {
+        (None, Some(high)) => format!("< {:.2}", high),
+        (Some(low), Some(high)) => format!("{:.2} – {:.2}", low, high),
+        (Some(low), None) => format!("≥ {:.2}", low),
+        (None, None) => "All".to_string(),
+    }
+}
+
+/// Histogram and percentiles of `rows`' daily per-user cost. The largest
+/// user-days are only named when `show_users`.
+pub fn render(base: &str, nav: &NavContext, rows: &[UserCostRow], show_users: bool) -> String {
+    let period = nav.period.as_str();
+    let self_path = with_period(&make_path(base, "/costs/distribution"), period);
+    let origin = nav.here(&self_path, 1);
+    let currency = rows
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let stats = distribution(rows.iter().map(|r| r.amount));
+    let money = |amount: Amount| format!("{:.2} {}", amount, currency);
+
+    let mut info_rows = vec![InfoRow::raw(
+        "Period",
+        period_links(&make_path(base, "/costs/distribution"), period),
+    )];
+    if let Some(stats) = &stats {
+        info_rows.push(InfoRow::new("User-Days", &stats.count.to_string()));
+        info_rows.push(InfoRow::new("Median (p50)", &money(stats.p50)));
+        info_rows.push(InfoRow::new("p90", &money(stats.p90)));
+        info_rows.push(InfoRow::new("p99", &money(stats.p99)));
+        info_rows.push(InfoRow::new("Mean", &money(stats.mean)));
+        info_rows.push(InfoRow::new("Max", &money(stats.max)));
+    }
+
+    let content = match stats {
+        None => Either::Left(view! {
+            <h2>"Daily Spend per User"</h2>
+            <p>"No cost data found for this period."</p>
+        }),
+        Some(stats) => {
+            let widest = stats
+                .buckets
+                .iter()
+                .map(|b| b.count)
+                .max()
+                .unwrap_or(1)
+                .max(1);
+            let bucket_rows: Vec<_> = stats
+                .buckets
+                .iter()
+                .map(|b| {
+                    (
+                        bucket_label(b),
+                        b.count,
+                        format!("{:.1}%", b.count as f64 / stats.count as f64 * 100.0),
+                        format!("width: {}px", b.count * 200 / widest),
+                    )
+                })
+                .collect();
+            let mut top: Vec<&UserCostRow> =
+                rows.iter().filter(|r| r.amount > Amount::ZERO).collect();
+            top.sort_by(|a, b| b.amount.cmp(&a.amount));
+            let top_rows: Vec<_> = top
+                .into_iter()
+                .take(if show_users { TOP_USER_DAYS } else { 0 })
+                .map(|r| {
+                    let date = r.date.to_string();
+                    (
+                        nav.drill(
+                            &make_path(base, &format!("/costs/daily/{}/users/{}", date, r.user_id)),
+                            origin.as_deref(),
+                        ),
+                        date,
+                        nav.drill(
+                            &make_path(base, &format!("/users/{}", r.user_id)),
+                            origin.as_deref(),
+                        ),
+                        r.user_email.clone().unwrap_or_else(|| r.user_id.clone()),
+                        money(r.amount),
+                        format!("{:.1}×", r.amount.to_f64() / stats.p50.to_f64()),
+                    )
+                })
+                .collect();
+            Either::Right(view! {
+                <h2>"Daily Spend per User"</h2>
+                <table class="data-table" data-export-name="cost_distribution">
+                    <tr>
+                        <th>"Daily Cost"</th>
+                        <th>"User-Days"</th>
+                        <th>"Share"</th>
+                        <th></th>
+                    </tr>
+                    {bucket_rows.into_iter().map(|(label, count, share, width)| view! {
+                        <tr>
+                            <td>{label}</td>
+                            <td>{count}</td>
+                            <td>{share}</td>
+                            <td><span class="hist-bar" style={width}></span></td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+                {(!top_rows.is_empty()).then(|| view! {
+                    <h2>"Largest User-Days"</h2>
+                    <table class="data-table" data-export-name="largest_user_days">
+                        <tr>
+                            <th>"Date"</th>
+                            <th>"User"</th>
+                            <th>"Cost"</th>
+                            <th>"vs Median"</th>
+                        </tr>
+                        {top_rows.into_iter().map(|(date_href, date, user_href, user, cost, ratio)| view! {
+                            <tr>
+                                <td><a href={date_href}>{date}</a></td>
+                                <td><a href={user_href}>{user}</a></td>
+                                <td>{cost}</td>
+                                <td>{ratio}</td>
+                            </tr>
+                        }).collect::<Vec<_>>()}
+                    </table>
+                })}
+            })
+        }
+    };
+
+    Page {
+        title: "Cost Explorer - Cost Distribution".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Cost Distribution"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn usd(amount: f64) -> Amount {
+        Amount::from_f64(amount)
+    }
+
+    fn row(date: &str, user_id: &str, amount: f64) -> UserCostRow {
+        UserCostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            user_id: user_id.to_string(),
+            user_email: Some(format!("{user_id}@example.com")),
+            amount: usd(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn percentiles_use_nearest_rank() {
+        let stats = distribution((1..=100).map(|n| usd(n as f64))).unwrap();
+        assert_eq!(stats.count, 100);
+        assert_eq!(stats.p50, usd(50.0));
+        assert_eq!(stats.p90, usd(90.0));
+        assert_eq!(stats.p99, usd(99.0));
+        assert_eq!(stats.max, usd(100.0));
+        assert_eq!(stats.mean, usd(50.5));
+
+        let single = distribution([usd(3.0)]).unwrap();
+        assert_eq!((single.p50, single.p99), (usd(3.0), usd(3.0)));
+        assert!(distribution([Amount::ZERO, usd(-1.0)]).is_none());
+    }
+
+    #[test]
+    fn buckets_span_decades_between_min_and_max() {
+        let stats = distribution([usd(0.005), usd(0.5), usd(0.7), usd(250.0)]).unwrap();
+        let counts: Vec<usize> = stats.buckets.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![1, 0, 2, 0, 0, 1]);
+        assert_eq!(stats.buckets[0].low, None);
+        assert_eq!(stats.buckets[2].low, Some(usd(0.1)));
+        assert_eq!(stats.buckets[2].high, Some(usd(1.0)));
+        assert_eq!(stats.buckets[5].high, None);
+        assert_eq!(bucket_label(&stats.buckets[5]), "≥ 100.00");
+    }
+
+    #[test]
+    fn render_shows_histogram_and_largest_user_days() {
+        let rows = vec![
+            row("2025-03-01", "u1", 1.0),
+            row("2025-03-01", "u2", 2.0),
+            row("2025-03-02", "u3", 40.0),
+        ];
+        let html = render("/_dashboard", &"7d".into(), &rows, true);
+        assert!(html.contains("<title>Cost Explorer - Cost Distribution</title>"));
+        assert!(html.contains("Median (p50)"));
+        assert!(html.contains("2.00 USD"));
+        assert!(html.contains("1.00 – 10.00"));
+        assert!(html.contains("≥ 10.00"));
+        assert!(html.contains("Largest User-Days"));
+        assert!(html.contains("/_dashboard/costs/daily/2025-03-02/users/u3?period=7d"));
+        assert!(html.contains("u3@example.com"));
+        assert!(html.contains("20.0×"));
+
+        let html = render("/_dashboard", &"7d".into(), &rows, false);
+        assert!(!html.contains("Largest User-Days"));
+        assert!(!html.contains("u3@example.com"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), &[], true);
+        assert!(html.contains("No cost data found for this period."));
+    }
+}
@@ -0,0 +1,273 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::{make_path, with_period, NavContext};
+use common::{
+    cost_per_million_tokens, Amount, CostByModel, CostByUser, TokenUsageRow, UserTokenUsage,
+};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// One model's or user's cost over the period next to the tokens it used.
+struct Ranked<'a> {
+    id: &'a str,
+    label: &'a str,
+    cost: Amount,
+    input_tokens: i64,
+    output_tokens: i64,
+    per_million: Option<Amount>,
+}
+
+/// Ranks `costs`, given as id, label and amount, by cost per million input
+/// and output tokens, most expensive first. Those without tokens go last.
+fn rank<'a>(
+    costs: impl Iterator<Item = (&'a str, &'a str, Amount)>,
+    tokens: &HashMap<&str, (i64, i64)>,
+) -> Vec<Ranked<'a>> {
+    let mut rows: Vec<_> = costs
+        .map(|(id, label, cost)| {
+            let (input_tokens, output_tokens) = tokens.get(id).copied().unwrap_or_default();
+            Ranked {
+                id,
+                label,
+                cost,
+                input_tokens,
+                output_tokens,
+                per_million: cost_per_million_tokens(cost, input_tokens + output_tokens),
+            }
+        })
+        .collect();
+    rows.sort_by_key(|r| Reverse((r.per_million, r.cost)));
+    rows
+}
+
+fn table(
+    rows: Vec<Ranked<'_>>,
+    heading: &'static str,
+    export_name: &'static str,
+    href: impl Fn(&str) -> String,
+    currency: &str,
+) -> impl IntoView {
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|r| {
+            (
+                href(r.id),
+                r.label.to_string(),
+                format!("{:.2} {}", r.cost, currency),
+                r.input_tokens.to_string(),
+                r.output_tokens.to_string(),
+                r.per_million
+                    .map_or_else(|| "-".to_string(), |a| format!("{:.2} {}", a, currency)),
+            )
+        })
+        .collect();
+    view! {
+        <table class="data-table" data-export-name={export_name}>
+            <tr>
+                <th>{heading}</th>
+                <th>"Cost"</th>
+                <th>"Input Tokens"</th>
+                <th>"Output Tokens"</th>
+                <th>"Per 1M Tokens"</th>
+            </tr>
+            {rows.into_iter().map(|(href, label, cost, input, output, per_million)| view! {
+                <tr>
+                    <td><a href={href}>{label}</a></td>
+                    <td>{cost}</td>
+                    <td>{input}</td>
+                    <td>{output}</td>
+                    <td>{per_million}</td>
+                </tr>
+            }).collect::<Vec<_>>()}
+        </table>
+    }
+}
+
+/// Models and users ranked by what a million tokens cost them over the
+/// period. Token counts always come from CE; cost per request is not shown
+/// because nothing this dashboard reads counts requests.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    models: &[CostByModel],
+    users: &[CostByUser],
+    model_tokens: &[TokenUsageRow],
+    user_tokens: &[UserTokenUsage],
+) -> String {
+    let period = nav.period.as_str();
+    let currency = models
+        .first()
+        .map(|c| c.currency.clone())
+        .or_else(|| users.first().map(|c| c.currency.clone()))
+        .unwrap_or_else(|| "USD".to_string());
+
+    let mut per_model: HashMap<&str, (i64, i64)> = HashMap::new();
+    for row in model_tokens {
+        let entry = per_model.entry(&row.model_id).or_default();
+        entry.0 += row.input_tokens;
+        entry.1 += row.output_tokens;
+    }
+    let per_user: HashMap<&str, (i64, i64)> = user_tokens
+        .iter()
+        .map(|u| (u.user_id.as_str(), (u.input_tokens, u.output_tokens)))
+        .collect();
+
+    let total_cost: Amount = models.iter().map(|c| c.amount).sum();
+    let total_tokens: i64 = per_model
+        .values()
+        .map(|(input, output)| input + output)
+        .sum();
+    let empty = models.is_empty() && users.is_empty();
+
+    let model_rows = rank(
+        models.iter().map(|c| {
+            let label = c.model_name.as_deref().unwrap_or(&c.model_id);
+            (c.model_id.as_str(), label, c.amount)
+        }),
+        &per_model,
+    );
+    let user_rows = rank(
+        users.iter().map(|c| {
+            let label = c.user_email.as_deref().unwrap_or(&c.user_id);
+            (c.user_id.as_str(), label, c.amount)
+        }),
+        &per_user,
+    );
+
+    let origin = nav.here(&with_period(&make_path(base, "/efficiency"), period), 1);
+    let model_href = |id: &str| {
+        nav.drill(
+            &make_path(base, &format!("/models/{}", id)),
+            origin.as_deref(),
+        )
+    };
+    let user_href = |id: &str| {
+        nav.drill(
+            &make_path(base, &format!("/users/{}", id)),
+            origin.as_deref(),
+        )
+    };
+
+    let content = view! {
+        <h2>"Cost per 1M Tokens"</h2>
+        <p>
+            "Cost over the period divided by the input and output tokens Cost Explorer reports "
+            "for Bedrock. Cache reads and writes are not counted as tokens. Cost per request is "
+            "not shown: neither Cost Explorer nor the gateway tables record request counts."
+        </p>
+        {if empty {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <h3>"By Model"</h3>
+                {table(model_rows, "Model", "cost_per_token_by_model", model_href, &currency)}
+                <h3>"By User"</h3>
+                {table(user_rows, "User", "cost_per_token_by_user", user_href, &currency)}
+            })
+        }}
+    };
+
+    let info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/efficiency"), period),
+        ),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total_cost, currency)),
+        InfoRow::new("Total Tokens", &total_tokens.to_string()),
+        InfoRow::new(
+            "Per 1M Tokens",
+            &cost_per_million_tokens(total_cost, total_tokens)
+                .map_or_else(|| "-".to_string(), |a| format!("{:.2} {}", a, currency)),
+        ),
+    ];
+
+    Page {
+        title: "Cost Explorer - Cost Efficiency".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Cost Efficiency"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn cost(model_id: &str, name: Option<&str>, amount: f64) -> CostByModel {
+        CostByModel {
+            model_id: model_id.to_string(),
+            model_name: name.map(str::to_string),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    fn usage(day: &str, model_id: &str, input: i64, output: i64) -> TokenUsageRow {
+        TokenUsageRow {
+            date: NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap(),
+            model_id: model_id.to_string(),
+            input_tokens: input,
+            output_tokens: output,
+        }
+    }
+
+    #[test]
+    fn render_ranks_by_cost_per_million_tokens() {
+        let models = vec![
+            cost("m1", Some("claude"), 30.0),
+            cost("m2", Some("llama"), 1.0),
+            cost("m3", None, 5.0),
+        ];
+        let users = vec![CostByUser {
+            user_id: "u1".to_string(),
+            user_email: Some("a@example.com".to_string()),
+            amount: Amount::from_f64(36.0),
+            currency: "USD".to_string(),
+        }];
+        let model_tokens = vec![
+            usage("2025-03-01", "m1", 1_000_000, 500_000),
+            usage("2025-03-02", "m1", 400_000, 100_000),
+            usage("2025-03-01", "m2", 4_000_000, 1_000_000),
+        ];
+        let user_tokens = vec![UserTokenUsage {
+            user_id: "u1".to_string(),
+            input_tokens: 3_000_000,
+            output_tokens: 3_000_000,
+        }];
+        let html = render(
+            "/_dashboard",
+            &"30d".into(),
+            &models,
+            &users,
+            &model_tokens,
+            &user_tokens,
+        );
+        assert!(html.contains("<title>Cost Explorer - Cost Efficiency</title>"));
+        let claude = html.find(">claude</a>").unwrap();
+        let llama = html.find(">llama</a>").unwrap();
+        assert!(claude < llama && llama < html.find(">m3</a>").unwrap());
+        assert!(html.contains("15.00 USD"));
+        assert!(html.contains("0.20 USD"));
+        assert!(html.contains(">6.00 USD<"));
+        assert!(html.contains(r#"href="/_dashboard/models/m1""#));
+        assert!(html.contains(r#"href="/_dashboard/users/u1""#));
+        assert!(html.contains("Cost per request is not shown"));
+
+        let html = render("/", &"30d".into(), &[], &[], &[], &[]);
+        assert!(html.contains("No cost data found"));
+    }
+}
@@ -0,0 +1,30 @@
+use templates::{Breadcrumb, NavLink, Page};
+
+pub fn render(message: &str) -> String {
+    Page {
+        title: "Cost Explorer - Error".to_string(),
+        breadcrumbs: vec![Breadcrumb::current("Error")],
+        search: None,
+        source_filter: None,
+        error: Some(message.to_string()),
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content: (),
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_shows_message() {
+        let html = render("Failed to query daily cost: pool timed out");
+        assert!(html.contains("<title>Cost Explorer - Error</title>"));
+        assert!(html.contains("error-banner"));
+        assert!(html.contains("Failed to query daily cost: pool timed out"));
+        assert!(html.contains("history.back()"));
+    }
+}
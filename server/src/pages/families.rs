@@ -0,0 +1,261 @@
+use super::{group_links, make_path, share, with_period, NavContext};
+use common::{rule_for, Amount, CostByFamily, ModelFamily};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{html_escape, period_links, Breadcrumb, InfoRow, NavLink, Page};
+
+/// Cost rolled up into model families. `edit_rules` links to the admin
+/// family editor.
+pub fn render(base: &str, nav: &NavContext, costs: &[CostByFamily], edit_rules: bool) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let ungrouped = costs.iter().filter(|c| !c.matched).count();
+    let rows: Vec<_> = costs
+        .iter()
+        .map(|c| {
+            (
+                if c.matched {
+                    c.family.clone()
+                } else {
+                    format!("{} (no family)", c.family)
+                },
+                c.model_count,
+                format!("{:.2} {}", c.amount, c.currency),
+                share(c.amount, total),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Cost by Model Family"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_by_family">
+                    <tr>
+                        <th>"Family"</th>
+                        <th>"Models"</th>
+                        <th>"Cost"</th>
+                        <th>"Share"</th>
+                    </tr>
+                    {rows.into_iter().map(|(family, models, cost, share)| view! {
+                        <tr>
+                            <td>{family}</td>
+                            <td>{models}</td>
+                            <td>{cost}</td>
+                            <td>{share}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/families"), period),
+        ),
+        InfoRow::raw("Group By", group_links(base, period, true)),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        InfoRow::new("Models Without Family", &ungrouped.to_string()),
+    ];
+    if edit_rules {
+        info_rows.push(InfoRow::raw(
+            "Families",
+            format!(
+                r#"<a href="{}">Edit model families</a>"#,
+                html_escape(&make_path(base, "/families"))
+            ),
+        ));
+    }
+
+    Page {
+        title: "Cost Explorer - Model Families".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Model Families"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// Admin page listing model family rules, the models each covers, and forms
+/// to add and delete rules. `models` are `(id, name)` pairs.
+pub fn render_rules(base: &str, rules: &[ModelFamily], models: &[(String, String)]) -> String {
+    let action = make_path(base, "/families");
+    let delete_action = make_path(base, "/families/delete");
+    let covered = |rule: &ModelFamily| -> String {
+        let names: Vec<&str> = models
+            .iter()
+            .map(|(_, name)| name.as_str())
+            .filter(|name| rule_for(rules, name) == Some(rule))
+            .collect();
+        if names.is_empty() {
+            "-".to_string()
+        } else {
+            names.join(", ")
+        }
+    };
+    let rows: Vec<_> = rules
+        .iter()
+        .map(|r| (r.prefix.clone(), r.family.clone(), covered(r)))
+        .collect();
+    let unmatched: Vec<String> = models
+        .iter()
+        .filter(|(_, name)| rule_for(rules, name).is_none())
+        .map(|(_, name)| name.clone())
+        .collect();
+    let unmatched_count = unmatched.len();
+
+    let content = view! {
+        <h2>"Model Families"</h2>
+        <p>
+            "Models whose name starts with a rule's prefix, ignoring case, roll up into its family. "
+            "The longest matching prefix wins."
+        </p>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No model families set."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="model_families">
+                    <tr>
+                        <th>"Prefix"</th>
+                        <th>"Family"</th>
+                        <th>"Models"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(prefix, family, covered)| view! {
+                        <tr>
+                            <td><code>{prefix.clone()}</code></td>
+                            <td>{family}</td>
+                            <td>{covered}</td>
+                            <td>
+                                <form method="post" action={delete_action.clone()}>
+                                    <input type="hidden" name="prefix" value={prefix}/>
+                                    <button type="submit">"Delete"</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        {(!unmatched.is_empty()).then(|| view! {
+            <p>{format!("Models without a family: {}", unmatched.join(", "))}</p>
+        })}
+        <h3>"Set Family"</h3>
+        <form method="post" action={action}>
+            <p>
+                <label>"Name prefix "
+                    <input type="text" name="prefix" placeholder="claude-3-5" required/>
+                </label>
+            </p>
+            <p>
+                <label>"Family "
+                    <input type="text" name="family" placeholder="Claude 3.5" required/>
+                </label>
+            </p>
+            <button type="submit">"Save"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Model Families".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Model Families"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::new("Rules", &rules.len().to_string()),
+            InfoRow::new("Models Without Family", &unmatched_count.to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(prefix: &str, family: &str) -> ModelFamily {
+        ModelFamily {
+            prefix: prefix.to_string(),
+            family: family.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_lists_families_with_share() {
+        let costs = vec![
+            CostByFamily {
+                family: "Claude 3".to_string(),
+                matched: true,
+                model_count: 2,
+                amount: Amount::from_f64(6.0),
+                currency: "USD".to_string(),
+            },
+            CostByFamily {
+                family: "llama-3".to_string(),
+                matched: false,
+                model_count: 1,
+                amount: Amount::from_f64(2.0),
+                currency: "USD".to_string(),
+            },
+        ];
+        let html = render("/_dashboard", &"7d".into(), &costs, true);
+        assert!(html.contains("<title>Cost Explorer - Model Families</title>"));
+        assert!(html.contains("75.0%"));
+        assert!(html.contains("llama-3 (no family)"));
+        assert!(html.contains("8.00 USD"));
+        assert!(
+            html.contains(r#"<a href="/_dashboard/models?period=7d">Model</a> | <b>Family</b>"#)
+        );
+        assert!(html.contains(r#"href="/_dashboard/families""#));
+
+        let html = render("/", &"7d".into(), &[], false);
+        assert!(html.contains("No cost data found for this period."));
+        assert!(!html.contains("Edit model families"));
+    }
+
+    #[test]
+    fn render_rules_shows_covered_and_unmatched_models() {
+        let rules = vec![
+            rule("claude-3", "Claude 3"),
+            rule("claude-3-5", "Claude 3.5"),
+        ];
+        let models = vec![
+            ("m1".to_string(), "claude-3-opus".to_string()),
+            ("m2".to_string(), "claude-3-5-sonnet".to_string()),
+            ("m3".to_string(), "llama-3".to_string()),
+        ];
+        let html = render_rules("/_dashboard", &rules, &models);
+        assert!(html.contains(r#"action="/_dashboard/families""#));
+        assert!(html.contains(r#"action="/_dashboard/families/delete""#));
+        assert!(html.contains("<td>claude-3-opus</td>"));
+        assert!(html.contains("<td>claude-3-5-sonnet</td>"));
+        assert!(html.contains("Models without a family: llama-3"));
+    }
+}
@@ -0,0 +1,143 @@
+use super::{make_path, with_period, with_query, NavContext};
+use chrono::NaiveDate;
+use common::{Amount, CostRecord, FiscalCalendar, FiscalPattern};
+use leptos::prelude::*;
+use templates::{html_escape, Breadcrumb, InfoRow, Page};
+
+fn pattern_name(pattern: FiscalPattern) -> &'static str {
+    match pattern {
+        FiscalPattern::Monthly => "Calendar months",
+        FiscalPattern::FourFourFive => "4-4-5 weeks",
+        FiscalPattern::FourFiveFour => "4-5-4 weeks",
+        FiscalPattern::FiveFourFour => "5-4-4 weeks",
+    }
+}
+
+/// Costs of fiscal `year`, one row per fiscal period.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    calendar: &FiscalCalendar,
+    year: i32,
+    daily_cost: &[CostRecord],
+) -> String {
+    let period = nav.period.as_str();
+    let currency = daily_cost
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let periods = calendar.periods(year);
+    let mut totals = vec![Amount::ZERO; periods.len()];
+    for r in daily_cost {
+        let Ok(date) = NaiveDate::parse_from_str(&r.date, "%Y-%m-%d") else {
+            continue;
+        };
+        if let Some(i) = periods.iter().position(|p| p.start <= date && date < p.end) {
+            totals[i] += r.amount;
+        }
+    }
+    let total: Amount = totals.iter().sum();
+    let rows: Vec<_> = periods
+        .iter()
+        .zip(&totals)
+        .map(|(p, amount)| {
+            let last = p.end.pred_opt().unwrap_or(p.end);
+            (
+                p.label(),
+                p.start.format("%Y-%m-%d").to_string(),
+                last.format("%Y-%m-%d").to_string(),
+                format!("{:.2}", amount),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>{format!("Fiscal Year {}", year)}</h2>
+        <table class="data-table" data-export-name={format!("fiscal_{}", year)}>
+            <tr>
+                <th>"Period"</th>
+                <th>"Start"</th>
+                <th>"End"</th>
+                <th>"Cost"</th>
+            </tr>
+            {rows.into_iter().map(|(label, start, end, amount)| view! {
+                <tr>
+                    <td>{label}</td>
+                    <td>{start}</td>
+                    <td>{end}</td>
+                    <td>{amount}</td>
+                </tr>
+            }).collect::<Vec<_>>()}
+        </table>
+    };
+
+    let year_link = |year: i32| {
+        let href = with_query(
+            &with_period(&make_path(base, "/costs/fiscal"), period),
+            "year",
+            &year.to_string(),
+        );
+        format!(r#"<a href="{}">FY{}</a>"#, html_escape(&href), year)
+    };
+    Page {
+        title: format!("Cost Explorer - FY{}", year),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current(format!("FY{}", year)),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Fiscal Year",
+                format!(
+                    "{} | <b>FY{}</b> | {}",
+                    year_link(year - 1),
+                    year,
+                    year_link(year + 1)
+                ),
+            ),
+            InfoRow::new("Periods", pattern_name(calendar.pattern)),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, micros: i64) -> CostRecord {
+        CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_groups_by_fiscal_period() {
+        let calendar = FiscalCalendar {
+            start_month: 1,
+            pattern: FiscalPattern::FourFourFive,
+        };
+        let costs = vec![
+            record("2025-01-01", 1_000_000),
+            record("2025-01-28", 2_000_000),
+            record("2025-01-29", 4_000_000),
+        ];
+        let html = render("/", &"30d".into(), &calendar, 2025, &costs);
+        assert!(html.contains("<title>Cost Explorer - FY2025</title>"));
+        assert!(html.contains("FY2025 P01"));
+        assert!(html.contains("<td>3.00</td>"));
+        assert!(html.contains("<td>4.00</td>"));
+        assert!(html.contains("7.00 USD"));
+        assert!(html.contains("4-4-5 weeks"));
+        assert!(html.contains(r#"href="/costs/fiscal?year=2024""#));
+    }
+}
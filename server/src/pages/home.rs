@@ -1,25 +1,96 @@
-use super::{make_path, with_period};
-use templates::{period_links, Breadcrumb, InfoRow, Page, Subpage};
+use super::{credit_info_rows, make_path, metric_links, with_period, NavContext};
+use common::{Amount, Metric};
+use templates::{html_escape, period_links, Breadcrumb, InfoRow, NavLink, Page, Subpage};
 
 #[allow(clippy::too_many_arguments)]
 pub fn render(
     base: &str,
-    period: &str,
-    total_cost: f64,
+    nav: &NavContext,
+    total_cost: Amount,
     currency: &str,
+    credits: Option<Amount>,
     cost_count: usize,
     monthly_count: usize,
     user_count: usize,
     model_count: usize,
+    can_export: bool,
+    show_breakdowns: bool,
+    metric: Metric,
+    data_as_of: Option<&str>,
+    live: bool,
 ) -> String {
+    let period = nav.period.as_str();
+    // With `live`, the layout script keeps these values current from the
+    // page's event stream.
+    let live_src = live.then(|| with_period(&make_path(base, "/events/home"), period));
+    let live_value = |field: &str, value: &str| match &live_src {
+        Some(src) => format!(
+            r#"<span data-live="{}" data-live-field="{}">{}</span>"#,
+            html_escape(src),
+            field,
+            html_escape(value)
+        ),
+        None => html_escape(value),
+    };
+    let total_label = match credits {
+        Some(_) => "Net Cost",
+        None => "Total Cost",
+    };
+    let mut info_rows = vec![InfoRow::raw(
+        "Period",
+        period_links(&make_path(base, ""), period),
+    )];
+    info_rows.extend(credit_info_rows(
+        base, period, total_cost, credits, currency,
+    ));
+    info_rows.push(InfoRow::raw(
+        total_label,
+        live_value("total", &format!("{:.2} {}", total_cost, currency)),
+    ));
+    info_rows.push(InfoRow::raw(
+        "Metric",
+        metric_links(&with_period(&make_path(base, ""), period), metric),
+    ));
+    if let Some(as_of) = data_as_of {
+        info_rows.push(InfoRow::raw("Data As Of", live_value("data_as_of", as_of)));
+    }
+    if show_breakdowns {
+        info_rows.push(InfoRow::raw(
+            "Breakdown",
+            format!(
+                r#"<a href="{}">By AWS Service</a> | <a href="{}">By Region</a> | <a href="{}">By Account</a> | <a href="{}">By Model per Day</a> | <a href="{}">Estimated vs Actual</a> | <a href="{}">Amortized vs On-Demand</a> | <a href="{}">Cost per Token</a> | <a href="{}">Distribution</a>"#,
+                html_escape(&with_period(&make_path(base, "/costs/services"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/regions"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/accounts"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/daily/stacked"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/estimates"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/amortization"), period)),
+                html_escape(&with_period(&make_path(base, "/efficiency"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/distribution"), period))
+            ),
+        ));
+    }
+    if can_export {
+        info_rows.push(InfoRow::raw(
+            "Export",
+            format!(
+                r#"<a href="{}">Excel (.xlsx)</a> | <a href="{}">User × Model Matrix</a>"#,
+                html_escape(&with_period(&make_path(base, "/export.xlsx"), period)),
+                html_escape(&with_period(&make_path(base, "/costs/matrix"), period))
+            ),
+        ));
+    }
     Page {
         title: "Cost Explorer - Home".to_string(),
         breadcrumbs: vec![Breadcrumb::current("Cost Explorer")],
-        nav_links: vec![],
-        info_rows: vec![
-            InfoRow::raw("Period", period_links(&make_path(base, ""), period)),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total_cost, currency)),
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![
+            NavLink::new("Preferences", make_path(base, "/preferences")),
+            NavLink::new("My Alerts", make_path(base, "/me/alerts")),
         ],
+        info_rows,
         content: (),
         subpages: vec![
             Subpage::new(
@@ -27,11 +98,26 @@ pub fn render(
                 with_period(&make_path(base, "/costs/daily"), period),
                 cost_count,
             ),
+            Subpage::new(
+                "Cost Calendar",
+                with_period(&make_path(base, "/costs/calendar"), period),
+                cost_count,
+            ),
+            Subpage::new(
+                "Weekly Cost",
+                with_period(&make_path(base, "/costs/weekly"), period),
+                "-",
+            ),
             Subpage::new(
                 "Monthly Cost",
                 with_period(&make_path(base, "/costs/monthly"), period),
                 monthly_count,
             ),
+            Subpage::new(
+                "Fiscal Periods",
+                with_period(&make_path(base, "/costs/fiscal"), period),
+                "-",
+            ),
             Subpage::new(
                 "Users",
                 with_period(&make_path(base, "/users"), period),
@@ -53,27 +139,88 @@ mod tests {
 
     #[test]
     fn render_contains_title() {
-        let html = render("/", "30d", 123.45, "USD", 1, 6, 5, 3);
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::from_f64(123.45),
+            "USD",
+            None,
+            1,
+            6,
+            5,
+            3,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("<title>Cost Explorer - Home</title>"));
     }
 
     #[test]
     fn render_contains_period_links() {
-        let html = render("/", "30d", 0.0, "USD", 0, 0, 0, 0);
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("<b>Past 30 Days</b>"));
         assert!(html.contains("?period=7d"));
     }
 
     #[test]
     fn render_contains_total_cost() {
-        let html = render("/", "30d", 99.99, "USD", 0, 0, 0, 0);
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::from_f64(99.99),
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("99.99 USD"));
     }
 
     #[test]
     fn render_contains_subpage_links() {
-        let html = render("/", "30d", 0.0, "USD", 0, 0, 5, 3);
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            5,
+            3,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("/costs/daily"));
+        assert!(html.contains("/costs/calendar"));
         assert!(html.contains("/costs/monthly"));
         assert!(html.contains("/users"));
         assert!(html.contains("/models"));
@@ -85,17 +232,233 @@ mod tests {
 
     #[test]
     fn render_contains_counts() {
-        let html = render("/", "30d", 0.0, "USD", 2, 6, 12, 7);
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            2,
+            6,
+            12,
+            7,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("12"));
         assert!(html.contains("7"));
     }
 
     #[test]
     fn render_uses_custom_base_path() {
-        let html = render("/_dashboard", "30d", 0.0, "USD", 0, 0, 1, 1);
+        let html = render(
+            "/_dashboard",
+            &"30d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            1,
+            1,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
         assert!(html.contains("/_dashboard/costs/daily"));
         assert!(html.contains("/_dashboard/costs/monthly"));
         assert!(html.contains("/_dashboard/users"));
         assert!(html.contains("/_dashboard/models"));
     }
+
+    #[test]
+    fn render_export_link_only_when_allowed() {
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            true,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
+        assert!(html.contains("/export.xlsx?period=7d"));
+        assert!(html.contains("/costs/matrix?period=7d"));
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
+        assert!(!html.contains("/export.xlsx"));
+        assert!(!html.contains("/costs/matrix"));
+    }
+
+    #[test]
+    fn render_contains_metric_links() {
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Amortized,
+            None,
+            false,
+        );
+        assert!(html.contains("<b>Amortized</b>"));
+        assert!(html.contains("?period=7d&amp;metric=UnblendedCost"));
+    }
+
+    #[test]
+    fn render_breakdown_links_only_when_allowed() {
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            true,
+            Metric::Blended,
+            None,
+            false,
+        );
+        assert!(html.contains("/costs/services?period=7d"));
+        assert!(html.contains("/costs/regions?period=7d"));
+        assert!(html.contains("/costs/accounts?period=7d"));
+        assert!(html.contains("/costs/daily/stacked?period=7d"));
+        assert!(html.contains("/costs/amortization?period=7d"));
+        assert!(html.contains("/efficiency?period=7d"));
+        assert!(html.contains("/costs/distribution?period=7d"));
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            None,
+            false,
+        );
+        assert!(!html.contains("/costs/services"));
+        assert!(!html.contains("/costs/regions"));
+        assert!(!html.contains("/costs/accounts"));
+    }
+
+    #[test]
+    fn render_shows_data_as_of() {
+        let html = render(
+            "/",
+            &"30d".into(),
+            Amount::ZERO,
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            Some("2025-03-01 09:30 UTC"),
+            false,
+        );
+        assert!(html.contains("Data As Of"));
+        assert!(html.contains("2025-03-01 09:30 UTC"));
+    }
+
+    #[test]
+    fn render_live_marks_updated_values() {
+        let html = render(
+            "/",
+            &"7d".into(),
+            Amount::from_f64(12.5),
+            "USD",
+            None,
+            0,
+            0,
+            0,
+            0,
+            false,
+            false,
+            Metric::Blended,
+            Some("2025-03-01 09:30 UTC"),
+            true,
+        );
+        assert!(html.contains(
+            r#"<span data-live="/events/home?period=7d" data-live-field="total">12.50 USD</span>"#
+        ));
+        assert!(html.contains(r#"data-live-field="data_as_of">2025-03-01 09:30 UTC"#));
+    }
+
+    #[test]
+    fn render_splits_out_credits() {
+        let render_with = |credits| {
+            render(
+                "/_dashboard",
+                &"30d".into(),
+                Amount::from_f64(80.0),
+                "USD",
+                credits,
+                0,
+                0,
+                0,
+                0,
+                false,
+                false,
+                Metric::Blended,
+                None,
+                false,
+            )
+        };
+        let html = render_with(Some(Amount::from_f64(-20.0)));
+        assert!(html.contains("Gross Cost"));
+        assert!(html.contains("100.00 USD"));
+        assert!(html.contains(r#"<a href="/_dashboard/costs/credits">-20.00 USD</a>"#));
+        assert!(html.contains("Net Cost"));
+        assert!(!html.contains("Total Cost"));
+
+        let html = render_with(None);
+        assert!(html.contains("Total Cost"));
+        assert!(!html.contains("Gross Cost"));
+    }
 }
@@ -0,0 +1,251 @@
+//! Users holding active API keys and models with inference profiles that
+//! had no spend in the last N days, as candidates for key cleanup and model
+//! deprecation.
+
+use std::collections::HashSet;
+
+use super::make_path;
+use common::{Amount, CostByModel, CostByUser, ModelInfo, UserInfo};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{html_escape, Breadcrumb, InfoRow, NavLink, Page};
+
+/// Lookback offered when `?days=` is not given.
+pub const DEFAULT_IDLE_DAYS: i64 = 30;
+
+/// Lookbacks linked from the page.
+const IDLE_DAYS_CHOICES: [i64; 3] = [7, 30, 90];
+
+/// Users with an active API key and no positive cost in `costs`.
+pub fn idle_users<'a>(users: &'a [UserInfo], costs: &[CostByUser]) -> Vec<&'a UserInfo> {
+    let spending: HashSet<&str> = costs
+        .iter()
+        .filter(|c| c.amount > Amount::ZERO)
+        .map(|c| c.user_id.as_str())
+        .collect();
+    users
+        .iter()
+        .filter(|u| u.active_api_key_count > 0 && !spending.contains(u.user_id.as_str()))
+        .collect()
+}
+
+/// Models some user holds an inference profile for, with no positive cost
+/// in `costs`.
+pub fn idle_models<'a>(models: &'a [ModelInfo], costs: &[CostByModel]) -> Vec<&'a ModelInfo> {
+    let used: HashSet<&str> = costs
+        .iter()
+        .filter(|c| c.amount > Amount::ZERO)
+        .map(|c| c.model_id.as_str())
+        .collect();
+    models
+        .iter()
+        .filter(|m| m.user_count > 0 && !used.contains(m.model_id.as_str()))
+        .collect()
+}
+
+fn days_links(path: &str, days: i64) -> String {
+    IDLE_DAYS_CHOICES
+        .iter()
+        .map(|&choice| {
+            if choice == days {
+                format!("<b>{} days</b>", choice)
+            } else {
+                format!(
+                    r#"<a href="{}?days={}">{} days</a>"#,
+                    html_escape(path),
+                    choice,
+                    choice
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// The idle report over the last `days` days; `users` and `models` are the
+/// already selected idle ones.
+pub fn render(base: &str, days: i64, users: &[&UserInfo], models: &[&ModelInfo]) -> String {
+    let user_rows: Vec<_> = users
+        .iter()
+        .map(|u| {
+            (
+                make_path(base, &format!("/users/{}", u.user_id)),
+                u.user_email.clone(),
+                make_path(base, &format!("/users/{}/keys", u.user_id)),
+                u.active_api_key_count,
+                u.created_at.clone(),
+            )
+        })
+        .collect();
+    let model_rows: Vec<_> = models
+        .iter()
+        .map(|m| {
+            (
+                make_path(base, &format!("/models/{}", m.model_id)),
+                m.model_name.clone(),
+                if m.is_disabled { "Disabled" } else { "Enabled" },
+                m.user_count,
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Idle Users"</h2>
+        {if user_rows.is_empty() {
+            Either::Left(view! {
+                <p>"Every user with an active API key has spend in this window."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="idle_users">
+                    <tr>
+                        <th>"User"</th>
+                        <th>"Active API Keys"</th>
+                        <th>"Created"</th>
+                    </tr>
+                    {user_rows.into_iter().map(|(href, email, keys_href, keys, created)| view! {
+                        <tr>
+                            <td><a href={href}>{email}</a></td>
+                            <td><a href={keys_href}>{keys}</a></td>
+                            <td>{created}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h2>"Idle Models"</h2>
+        {if model_rows.is_empty() {
+            Either::Left(view! {
+                <p>"Every model with inference profiles has usage in this window."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="idle_models">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Status"</th>
+                        <th>"Users With Profiles"</th>
+                    </tr>
+                    {model_rows.into_iter().map(|(href, name, status, profile_users)| view! {
+                        <tr>
+                            <td><a href={href}>{name}</a></td>
+                            <td>{status}</td>
+                            <td>{profile_users}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Idle Users and Models".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Idle Users and Models"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Without Spend For",
+                days_links(&make_path(base, "/reports/idle"), days),
+            ),
+            InfoRow::new("Idle Users", &users.len().to_string()),
+            InfoRow::new("Idle Models", &models.len().to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(id: &str, active_keys: i64) -> UserInfo {
+        UserInfo {
+            user_id: id.to_string(),
+            user_email: format!("{id}@example.com"),
+            created_at: "2025-01-01".to_string(),
+            api_key_count: active_keys,
+            active_api_key_count: active_keys,
+            inference_profile_count: 0,
+        }
+    }
+
+    fn model(id: &str, user_count: i64) -> ModelInfo {
+        ModelInfo {
+            model_id: id.to_string(),
+            model_name: format!("{id}-name"),
+            is_disabled: false,
+            protected: false,
+            user_count,
+        }
+    }
+
+    fn user_cost(id: &str, amount: f64) -> CostByUser {
+        CostByUser {
+            user_id: id.to_string(),
+            user_email: None,
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    fn model_cost(id: &str, amount: f64) -> CostByModel {
+        CostByModel {
+            model_id: id.to_string(),
+            model_name: None,
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn idle_users_need_an_active_key_and_no_spend() {
+        let users = vec![user("u1", 1), user("u2", 2), user("u3", 0), user("u4", 1)];
+        let costs = vec![user_cost("u1", 3.0), user_cost("u4", 0.0)];
+        let ids: Vec<&str> = idle_users(&users, &costs)
+            .iter()
+            .map(|u| u.user_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["u2", "u4"]);
+    }
+
+    #[test]
+    fn idle_models_need_profiles_and_no_spend() {
+        let models = vec![model("m1", 2), model("m2", 1), model("m3", 0)];
+        let costs = vec![model_cost("m1", 0.5)];
+        let ids: Vec<&str> = idle_models(&models, &costs)
+            .iter()
+            .map(|m| m.model_id.as_str())
+            .collect();
+        assert_eq!(ids, vec!["m2"]);
+    }
+
+    #[test]
+    fn render_lists_idle_users_and_models() {
+        let users = vec![user("u2", 2)];
+        let models = vec![model("m2", 1)];
+        let html = render(
+            "/_dashboard",
+            30,
+            &users.iter().collect::<Vec<_>>(),
+            &models.iter().collect::<Vec<_>>(),
+        );
+        assert!(html.contains("<title>Cost Explorer - Idle Users and Models</title>"));
+        assert!(html.contains(r#"<a href="/_dashboard/users/u2">u2@example.com</a>"#));
+        assert!(html.contains(r#"<a href="/_dashboard/users/u2/keys">2</a>"#));
+        assert!(html.contains(r#"<a href="/_dashboard/models/m2">m2-name</a>"#));
+        assert!(html.contains("<b>30 days</b>"));
+        assert!(html.contains(r#"<a href="/_dashboard/reports/idle?days=90">90 days</a>"#));
+
+        let html = render("/", 7, &[], &[]);
+        assert!(html.contains("Every user with an active API key has spend in this window."));
+        assert!(html.contains("Every model with inference profiles has usage in this window."));
+    }
+}
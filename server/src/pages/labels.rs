@@ -0,0 +1,334 @@
+use std::collections::{BTreeSet, HashMap};
+
+use super::{make_path, share, with_period, with_query, NavContext};
+use common::{Amount, CostByLabel, Label, LabelTarget};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{html_escape, period_links, Breadcrumb, InfoRow, NavLink, Page};
+
+/// `(label, href, members, cost, share)` rows of one label table; the
+/// unlabeled row has no link.
+fn label_rows(
+    costs: &[CostByLabel],
+    index_path: &str,
+    total: Amount,
+    currency: &str,
+) -> Vec<(String, Option<String>, usize, String, String)> {
+    costs
+        .iter()
+        .map(|c| {
+            let (label, href) = match &c.label {
+                Some(label) => (label.clone(), Some(with_query(index_path, "label", label))),
+                None => ("(unlabeled)".to_string(), None),
+            };
+            (
+                label,
+                href,
+                c.members,
+                format!("{:.2} {}", c.amount, currency),
+                share(c.amount, total),
+            )
+        })
+        .collect()
+}
+
+fn label_table(
+    title: &'static str,
+    export_name: &'static str,
+    members: &'static str,
+    rows: Vec<(String, Option<String>, usize, String, String)>,
+) -> impl IntoView {
+    view! {
+        <h2>{title}</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name={export_name}>
+                    <tr>
+                        <th>"Label"</th>
+                        <th>{members}</th>
+                        <th>"Cost"</th>
+                        <th>"Share"</th>
+                    </tr>
+                    {rows.into_iter().map(|(label, href, count, cost, share)| view! {
+                        <tr>
+                            <td>{match href {
+                                Some(href) => Either::Left(view! { <a href={href}>{label}</a> }),
+                                None => Either::Right(label),
+                            }}</td>
+                            <td>{count}</td>
+                            <td>{cost}</td>
+                            <td>{share}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    }
+}
+
+/// Cost grouped by the labels on users and on models. Each label links to
+/// the users or models index filtered to it. Shares are of the period's
+/// total, so a user or model with several labels counts more than once.
+/// `edit_labels` links to the admin label editor.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    by_user: &[CostByLabel],
+    by_model: &[CostByLabel],
+    total: Amount,
+    currency: &str,
+    edit_labels: bool,
+) -> String {
+    let period = nav.period.as_str();
+    let user_rows = label_rows(
+        by_user,
+        &with_period(&make_path(base, "/users"), period),
+        total,
+        currency,
+    );
+    let model_rows = label_rows(
+        by_model,
+        &with_period(&make_path(base, "/models"), period),
+        total,
+        currency,
+    );
+    let content = view! {
+        {label_table("Cost by User Label", "cost_by_user_label", "Users", user_rows)}
+        {label_table("Cost by Model Label", "cost_by_model_label", "Models", model_rows)}
+    };
+
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/labels"), period),
+        ),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+    ];
+    if edit_labels {
+        info_rows.push(InfoRow::raw(
+            "Labels",
+            format!(
+                r#"<a href="{}">Edit labels</a>"#,
+                html_escape(&make_path(base, "/labels"))
+            ),
+        ));
+    }
+
+    Page {
+        title: "Cost Explorer - Cost by Label".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Cost by Label"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// Admin page listing labels, with forms to attach and remove them.
+/// `users` and `models` are `(id, name)` pairs.
+pub fn render_editor(
+    base: &str,
+    labels: &[Label],
+    users: &[(String, String)],
+    models: &[(String, String)],
+) -> String {
+    let action = make_path(base, "/labels");
+    let delete_action = make_path(base, "/labels/delete");
+    let user_names: HashMap<&str, &str> = users
+        .iter()
+        .map(|(id, name)| (id.as_str(), name.as_str()))
+        .collect();
+    let model_names: HashMap<&str, &str> = models
+        .iter()
+        .map(|(id, name)| (id.as_str(), name.as_str()))
+        .collect();
+    let names: BTreeSet<&str> = labels.iter().map(|l| l.label.as_str()).collect();
+    let label_count = names.len();
+    let rows: Vec<_> = labels
+        .iter()
+        .map(|l| {
+            let (kind, path, names) = match l.target {
+                LabelTarget::User => ("User", "/users", &user_names),
+                LabelTarget::Model => ("Model", "/models", &model_names),
+            };
+            (
+                l.label.clone(),
+                kind,
+                make_path(base, &format!("{}/{}", path, l.target_id)),
+                names
+                    .get(l.target_id.as_str())
+                    .map_or_else(|| l.target_id.clone(), |n| n.to_string()),
+                format!("{}:{}", l.target.as_str(), l.target_id),
+            )
+        })
+        .collect();
+    let options = |target: LabelTarget, pairs: &[(String, String)]| {
+        pairs
+            .iter()
+            .map(|(id, name)| (format!("{}:{}", target.as_str(), id), name.clone()))
+            .collect::<Vec<_>>()
+    };
+    let user_options = options(LabelTarget::User, users);
+    let model_options = options(LabelTarget::Model, models);
+    let known: Vec<String> = names.into_iter().map(str::to_string).collect();
+
+    let content = view! {
+        <h2>"Labels"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No labels set."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="labels">
+                    <tr>
+                        <th>"Label"</th>
+                        <th>"Type"</th>
+                        <th>"Name"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(label, kind, href, name, target)| view! {
+                        <tr>
+                            <td>{label.clone()}</td>
+                            <td>{kind}</td>
+                            <td><a href={href}>{name}</a></td>
+                            <td>
+                                <form method="post" action={delete_action.clone()}>
+                                    <input type="hidden" name="target" value={target}/>
+                                    <input type="hidden" name="label" value={label}/>
+                                    <button type="submit">"Remove"</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Add Label"</h3>
+        <form method="post" action={action}>
+            <p>
+                <label>"User or model "
+                    <select name="target">
+                        <optgroup label="Users">
+                            {user_options.into_iter().map(|(value, name)| view! {
+                                <option value={value}>{name}</option>
+                            }).collect::<Vec<_>>()}
+                        </optgroup>
+                        <optgroup label="Models">
+                            {model_options.into_iter().map(|(value, name)| view! {
+                                <option value={value}>{name}</option>
+                            }).collect::<Vec<_>>()}
+                        </optgroup>
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Label "
+                    <input type="text" name="label" list="known-labels" placeholder="production" required/>
+                </label>
+                <datalist id="known-labels">
+                    {known.into_iter().map(|label| view! {
+                        <option value={label}></option>
+                    }).collect::<Vec<_>>()}
+                </datalist>
+            </p>
+            <button type="submit">"Add"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Labels".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Labels"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::new("Labels", &label_count.to_string()),
+            InfoRow::raw(
+                "Report",
+                format!(
+                    r#"<a href="{}">Cost by label</a>"#,
+                    html_escape(&make_path(base, "/costs/labels"))
+                ),
+            ),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn label(target: LabelTarget, id: &str, name: &str) -> Label {
+        Label {
+            target,
+            target_id: id.to_string(),
+            label: name.to_string(),
+        }
+    }
+
+    #[test]
+    fn render_links_labels_to_filtered_indexes() {
+        let by_user = vec![
+            CostByLabel {
+                label: Some("production".to_string()),
+                members: 2,
+                amount: Amount::from_f64(6.0),
+            },
+            CostByLabel {
+                label: None,
+                members: 1,
+                amount: Amount::from_f64(2.0),
+            },
+        ];
+        let html = render(
+            "/_dashboard",
+            &"7d".into(),
+            &by_user,
+            &[],
+            Amount::from_f64(8.0),
+            "USD",
+            true,
+        );
+        assert!(html.contains("<title>Cost Explorer - Cost by Label</title>"));
+        assert!(html.contains(r#"href="/_dashboard/users?period=7d&amp;label=production""#));
+        assert!(html.contains("(unlabeled)"));
+        assert!(html.contains("75.0%"));
+        assert!(html.contains("Cost by Model Label"));
+        assert!(html.contains(r#"href="/_dashboard/labels""#));
+    }
+
+    #[test]
+    fn render_editor_lists_labels_and_targets() {
+        let labels = vec![
+            label(LabelTarget::User, "u1", "research"),
+            label(LabelTarget::Model, "m1", "external"),
+        ];
+        let users = vec![("u1".to_string(), "alice@example.com".to_string())];
+        let models = vec![("m1".to_string(), "claude".to_string())];
+        let html = render_editor("/", &labels, &users, &models);
+        assert!(html.contains(r#"<a href="/users/u1">alice@example.com</a>"#));
+        assert!(html.contains(r#"<a href="/models/m1">claude</a>"#));
+        assert!(html.contains(r#"<option value="model:m1">claude</option>"#));
+        assert!(html.contains(r#"<input type="hidden" name="target" value="user:u1""#));
+        assert!(html.contains(r#"<option value="research">"#));
+    }
+}
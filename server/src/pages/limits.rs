@@ -0,0 +1,158 @@
+use std::collections::HashMap;
+
+use super::make_path;
+use common::{Amount, SpendLimit};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+/// Admin page listing monthly spend limits with this month's spend, and a
+/// form to set or remove one.
+pub fn render(
+    base: &str,
+    limits: &[SpendLimit],
+    spent: &HashMap<String, Amount>,
+    users: &[(String, String)],
+) -> String {
+    let action = make_path(base, "/limits");
+    let exceeded_count = limits.iter().filter(|l| l.limit_exceeded).count();
+    let rows: Vec<_> = limits
+        .iter()
+        .map(|limit| {
+            let href = make_path(base, &format!("/users/{}", limit.user_id));
+            let label = limit
+                .user_email
+                .clone()
+                .unwrap_or_else(|| limit.user_id.clone());
+            let spent = spent.get(&limit.user_id).copied().unwrap_or_default();
+            let status = if limit.limit_exceeded {
+                "Exceeded"
+            } else {
+                "OK"
+            };
+            (
+                href,
+                label,
+                limit.user_id.clone(),
+                format!("{:.2}", limit.monthly_limit),
+                format!("{:.2}", spent),
+                status,
+            )
+        })
+        .collect();
+    let options: Vec<_> = users.to_vec();
+
+    let content = view! {
+        <h2>"Monthly Spend Limits"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No spend limits set."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="spend_limits">
+                    <tr>
+                        <th>"User"</th>
+                        <th>"Monthly Limit"</th>
+                        <th>"Spent This Month"</th>
+                        <th>"Status"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, user_id, limit, spent, status)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{limit}</td>
+                            <td>{spent}</td>
+                            <td>{status}</td>
+                            <td>
+                                <form method="post" action={action.clone()}>
+                                    <input type="hidden" name="user_id" value={user_id}/>
+                                    <input type="hidden" name="monthly_limit" value=""/>
+                                    <button type="submit">"Remove"</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Set Limit"</h3>
+        <form method="post" action={action}>
+            <p>
+                <label>"User "
+                    <select name="user_id">
+                        {options.into_iter().map(|(id, email)| view! {
+                            <option value={id}>{email}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Monthly limit (USD) "
+                    <input type="number" name="monthly_limit" min="0" step="0.01" required/>
+                </label>
+            </p>
+            <button type="submit">"Save"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Spend Limits".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Spend Limits"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::new("Limits", &limits.len().to_string()),
+            InfoRow::new("Exceeded", &exceeded_count.to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limit(user_id: &str, email: Option<&str>, micros: i64, exceeded: bool) -> SpendLimit {
+        SpendLimit {
+            user_id: user_id.to_string(),
+            user_email: email.map(str::to_string),
+            monthly_limit: Amount::from_micros(micros),
+            limit_exceeded: exceeded,
+        }
+    }
+
+    #[test]
+    fn render_lists_limits_and_spend() {
+        let limits = vec![
+            limit("u1", Some("alice@example.com"), 100_000_000, true),
+            limit("u2", None, 50_000_000, false),
+        ];
+        let spent: HashMap<String, Amount> = [("u1".to_string(), Amount::from_f64(120.5))]
+            .into_iter()
+            .collect();
+        let users = vec![("u3".to_string(), "carol@example.com".to_string())];
+        let html = render("/_dashboard", &limits, &spent, &users);
+        assert!(html.contains("<title>Cost Explorer - Spend Limits</title>"));
+        assert!(html.contains(r#"href="/_dashboard/users/u1""#));
+        assert!(html.contains("alice@example.com"));
+        assert!(html.contains("100.00"));
+        assert!(html.contains("120.50"));
+        assert!(html.contains("Exceeded"));
+        assert!(html.contains(r#"action="/_dashboard/limits""#));
+        assert!(html.contains(r#"<option value="u3">carol@example.com</option>"#));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &[], &HashMap::new(), &[]);
+        assert!(html.contains("No spend limits set."));
+    }
+}
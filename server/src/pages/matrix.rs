@@ -0,0 +1,163 @@
+use super::{make_path, with_period, NavContext};
+use common::CostMatrix;
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{html_escape, period_links, Breadcrumb, InfoRow, Page};
+
+pub fn render(base: &str, nav: &NavContext, matrix: &CostMatrix) -> String {
+    let period = nav.period.as_str();
+    let currency = match matrix.currency.as_str() {
+        "" => "USD",
+        c => c,
+    };
+    let self_path = with_period(&make_path(base, "/costs/matrix"), period);
+    let origin = nav.here(&self_path, 1);
+
+    let model_headers: Vec<(String, String)> = matrix
+        .models
+        .iter()
+        .map(|(id, name)| {
+            let href = nav.drill(
+                &make_path(base, &format!("/models/{}", id)),
+                origin.as_deref(),
+            );
+            (href, name.clone().unwrap_or_else(|| id.clone()))
+        })
+        .collect();
+    let rows: Vec<_> = matrix
+        .users
+        .iter()
+        .zip(matrix.cells.iter().zip(matrix.user_totals()))
+        .map(|((id, email), (cells, total))| {
+            let href = nav.drill(
+                &make_path(base, &format!("/users/{}", id)),
+                origin.as_deref(),
+            );
+            let label = email.clone().unwrap_or_else(|| id.clone());
+            let cells: Vec<String> = cells
+                .iter()
+                .map(|a| {
+                    if a.is_zero() {
+                        String::new()
+                    } else {
+                        format!("{:.2}", a)
+                    }
+                })
+                .collect();
+            (href, label, cells, format!("{:.2}", total))
+        })
+        .collect();
+    let model_totals: Vec<String> = matrix
+        .model_totals()
+        .iter()
+        .map(|a| format!("{:.2}", a))
+        .collect();
+    let grand_total = format!("{:.2}", matrix.total());
+
+    let content = view! {
+        <h2>"User × Model Cost"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_matrix">
+                    <tr>
+                        <th>"User"</th>
+                        {model_headers.into_iter().map(|(href, label)| view! {
+                            <th><a href={href}>{label}</a></th>
+                        }).collect::<Vec<_>>()}
+                        <th>"Total"</th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, cells, total)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            {cells.into_iter().map(|c| view! { <td>{c}</td> }).collect::<Vec<_>>()}
+                            <td><strong>{total}</strong></td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                    <tr>
+                        <td><strong>"Total"</strong></td>
+                        {model_totals.into_iter().map(|t| view! {
+                            <td><strong>{t}</strong></td>
+                        }).collect::<Vec<_>>()}
+                        <td><strong>{grand_total}</strong></td>
+                    </tr>
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Cost Matrix".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Cost Matrix"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/matrix"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", matrix.total(), currency)),
+            InfoRow::raw(
+                "Export",
+                format!(
+                    r#"<a href="{}">CSV</a>"#,
+                    html_escape(&with_period(&make_path(base, "/costs/matrix.csv"), period))
+                ),
+            ),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::Amount;
+
+    fn matrix() -> CostMatrix {
+        let mut matrix = CostMatrix::from_entries(vec![
+            (
+                "u1".to_string(),
+                "m1".to_string(),
+                Amount::from_f64(1.5),
+                "USD".to_string(),
+            ),
+            (
+                "u2".to_string(),
+                "m2".to_string(),
+                Amount::from_f64(2.0),
+                "USD".to_string(),
+            ),
+        ]);
+        matrix.users[0].1 = Some("bob@example.com".to_string());
+        matrix
+    }
+
+    #[test]
+    fn render_has_totals_and_links() {
+        let html = render("/", &"7d".into(), &matrix());
+        assert!(html.contains("<title>Cost Explorer - Cost Matrix</title>"));
+        assert!(html.contains(r#"href="/users/u2?period=7d""#));
+        assert!(html.contains(r#"href="/models/m1?period=7d""#));
+        assert!(html.contains("bob@example.com"));
+        assert!(html.contains("<strong>3.50</strong>"));
+        assert!(html.contains("/costs/matrix.csv?period=7d"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), &CostMatrix::default());
+        assert!(html.contains("No cost data found for this period."));
+        assert!(html.contains("0.00 USD"));
+    }
+}
@@ -0,0 +1,227 @@
+use super::{make_path, metric_links, share, with_period, NavContext};
+use common::{Amount, ApiKeyInfo, CostByModel, CostRecord, Metric};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{
+    period_links, stacked_bar_chart, Breadcrumb, InfoRow, NavLink, Page, StackedBar, Subpage,
+};
+
+/// The logged-in user's own costs. `user_id` is `None` for users the gateway
+/// does not know, who get a page saying so.
+#[allow(clippy::too_many_arguments)]
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    email: &str,
+    user_id: Option<&str>,
+    daily: &[CostRecord],
+    models: &[CostByModel],
+    keys: &[ApiKeyInfo],
+    metric: Metric,
+    data_as_of: Option<&str>,
+) -> String {
+    let period = nav.period.as_str();
+    let self_path = make_path(base, "/me");
+    let total: Amount = daily.iter().map(|r| r.amount).sum();
+    let currency = daily
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let mut info_rows = vec![
+        InfoRow::new("User", email),
+        InfoRow::raw("Period", period_links(&self_path, period)),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        InfoRow::raw(
+            "Metric",
+            metric_links(&with_period(&self_path, period), metric),
+        ),
+    ];
+    if let Some(as_of) = data_as_of {
+        info_rows.push(InfoRow::new("Data As Of", as_of));
+    }
+
+    let bars: Vec<StackedBar> = daily
+        .iter()
+        .map(|r| StackedBar {
+            label: r.date.clone(),
+            href: nav.drill(&make_path(base, &format!("/costs/daily/{}", r.date)), None),
+            values: vec![r.amount.to_f64()],
+        })
+        .collect();
+    let chart = (!bars.is_empty()).then(|| stacked_bar_chart(&["Cost".to_string()], &bars));
+    let model_total: Amount = models.iter().map(|m| m.amount).sum();
+    let model_rows: Vec<_> = models
+        .iter()
+        .map(|m| {
+            (
+                m.model_name.clone().unwrap_or_else(|| m.model_id.clone()),
+                format!("{:.2} {}", m.amount, m.currency),
+                share(m.amount, model_total),
+            )
+        })
+        .collect();
+    let key_rows: Vec<_> = keys
+        .iter()
+        .map(|k| {
+            (
+                k.api_key_preview.clone(),
+                if k.is_disabled { "Disabled" } else { "Active" },
+                k.created_at.clone(),
+            )
+        })
+        .collect();
+
+    let content = match user_id {
+        None => Either::Left(view! {
+            <p>"Your account has no gateway user, so there are no costs to show."</p>
+        }),
+        Some(_) => Either::Right(view! {
+            <h2>"Daily Cost"</h2>
+            {match chart {
+                Some(chart) => Either::Left(view! { <div inner_html={chart}></div> }),
+                None => Either::Right(view! { <p>"No cost in this period."</p> }),
+            }}
+            <h2>"Cost by Model"</h2>
+            <table class="data-table" data-export-name="my_cost_by_model">
+                <tr>
+                    <th>"Model"</th>
+                    <th>"Cost"</th>
+                    <th>"Share"</th>
+                </tr>
+                {model_rows.into_iter().map(|(model, cost, share)| view! {
+                    <tr>
+                        <td>{model}</td>
+                        <td>{cost}</td>
+                        <td>{share}</td>
+                    </tr>
+                }).collect::<Vec<_>>()}
+            </table>
+            <h2>"API Keys"</h2>
+            <table class="data-table" data-export-name="my_api_keys">
+                <tr>
+                    <th>"Key"</th>
+                    <th>"Status"</th>
+                    <th>"Created"</th>
+                </tr>
+                {key_rows.into_iter().map(|(key, status, created)| view! {
+                    <tr>
+                        <td><code>{key}</code></td>
+                        <td>{status}</td>
+                        <td>{created}</td>
+                    </tr>
+                }).collect::<Vec<_>>()}
+            </table>
+        }),
+    };
+    let subpages = match user_id {
+        Some(id) => vec![
+            Subpage::new(
+                "Daily Cost",
+                with_period(&make_path(base, &format!("/users/{id}/daily")), period),
+                daily.len(),
+            ),
+            Subpage::new(
+                "Monthly Cost",
+                with_period(&make_path(base, &format!("/users/{id}/monthly")), period),
+                "-",
+            ),
+            Subpage::new(
+                "Details",
+                with_period(&make_path(base, &format!("/users/{id}")), period),
+                "-",
+            ),
+        ],
+        None => vec![],
+    };
+
+    Page {
+        title: "Cost Explorer - My Costs".to_string(),
+        breadcrumbs: vec![Breadcrumb::current("My Costs")],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![
+            NavLink::new("Preferences", make_path(base, "/preferences")),
+            NavLink::new("My Alerts", make_path(base, "/me/alerts")),
+        ],
+        info_rows,
+        content,
+        subpages,
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(preview: &str, disabled: bool) -> ApiKeyInfo {
+        ApiKeyInfo {
+            api_key_id: preview.to_string(),
+            api_key_preview: preview.to_string(),
+            is_disabled: disabled,
+            created_at: "2025-01-02".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_shows_trend_models_and_keys() {
+        let daily = vec![
+            CostRecord {
+                date: "2025-03-01".to_string(),
+                amount: Amount::from_f64(2.0),
+                currency: "USD".to_string(),
+            },
+            CostRecord {
+                date: "2025-03-02".to_string(),
+                amount: Amount::from_f64(6.0),
+                currency: "USD".to_string(),
+            },
+        ];
+        let models = vec![CostByModel {
+            model_id: "m1".to_string(),
+            model_name: Some("Claude".to_string()),
+            amount: Amount::from_f64(8.0),
+            currency: "USD".to_string(),
+        }];
+        let keys = vec![key("sk-abc…", false), key("sk-old…", true)];
+        let html = render(
+            "/_dashboard",
+            &"7d".into(),
+            "alice@example.com",
+            Some("u1"),
+            &daily,
+            &models,
+            &keys,
+            Metric::Blended,
+            None,
+        );
+        assert!(html.contains("<title>Cost Explorer - My Costs</title>"));
+        assert!(html.contains("8.00 USD"));
+        assert!(html.contains(r#"<svg class="chart""#));
+        assert!(html.contains("/_dashboard/costs/daily/2025-03-02"));
+        assert!(html.contains("Claude"));
+        assert!(html.contains("100.0%"));
+        assert!(html.contains("sk-old…"));
+        assert!(html.contains("Disabled"));
+        assert!(html.contains("/_dashboard/users/u1/daily?period=7d"));
+        assert!(html.contains("/_dashboard/me?period=30d"));
+    }
+
+    #[test]
+    fn render_without_gateway_user() {
+        let html = render(
+            "/",
+            &"30d".into(),
+            "bob@example.com",
+            None,
+            &[],
+            &[],
+            &[],
+            Metric::Blended,
+            None,
+        );
+        assert!(html.contains("no gateway user"));
+        assert!(!html.contains("/users/"));
+    }
+}
@@ -1,12 +1,177 @@
+pub mod accounts;
+pub mod amortization;
+pub mod adjustments;
+pub mod alerts;
+pub mod batch;
+pub mod calendar;
 pub mod costs;
+pub mod credits;
+pub mod diagnostics;
+pub mod distribution;
+pub mod efficiency;
+pub mod error;
+pub mod families;
+pub mod fiscal;
 pub mod home;
+pub mod idle;
+pub mod labels;
+pub mod limits;
+pub mod matrix;
+pub mod me;
 pub mod models;
 pub mod monthly;
+pub mod preferences;
+pub mod prices;
+pub mod regions;
+pub mod search;
+pub mod services;
+pub mod share;
+pub mod stacked;
+pub mod subscriptions;
 pub mod users;
+pub mod weekly;
 
+/// Default rows per table page.
 pub const PAGE_SIZE: usize = 50;
 
-use common::{CostByModel, CostByUser, CostRecord};
+use common::{Amount, ApiKeyInfo, CostByModel, CostByUser, CostRecord, InferenceProfileInfo, Metric};
+use templates::{html_escape, InfoRow, NavLink, SourceFilter};
+
+/// Navigation state carried across drill-downs in the query string. `from` is
+/// the full URL of the page a drill-down started on, so "Back" can return to
+/// it exactly (period, page, sorting and its own `from`) instead of relying on
+/// history.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NavContext {
+    pub period: String,
+    pub from: Option<String>,
+    /// The page's own `?sort=` and `?order=`, as given.
+    pub sort: Option<String>,
+    pub order: Option<String>,
+    /// Rows per table page; comes from the user's preferences.
+    pub page_size: usize,
+    /// The source cost figures are narrowed to; `None` for all.
+    pub source: Option<String>,
+    /// Sources with stored cost, offered by the source filter.
+    pub sources: Vec<String>,
+}
+
+impl NavContext {
+    pub fn new(period: &str, from: Option<&str>) -> Self {
+        Self {
+            period: period.to_string(),
+            from: from.filter(|f| is_local_path(f)).map(|f| f.to_string()),
+            sort: None,
+            order: None,
+            page_size: PAGE_SIZE,
+            source: None,
+            sources: Vec::new(),
+        }
+    }
+
+    pub fn with_page_size(mut self, page_size: usize) -> Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    pub fn with_sort(mut self, sort: Option<&str>, order: Option<&str>) -> Self {
+        self.sort = sort.map(|s| s.to_string());
+        self.order = order.map(|o| o.to_string());
+        self
+    }
+
+    pub fn with_sources(mut self, source: Option<&str>, sources: &[String]) -> Self {
+        self.source = source.map(|s| s.to_string());
+        self.sources = sources.to_vec();
+        self
+    }
+
+    /// The header source filter; only shown when there is a choice to make
+    /// or one was made.
+    pub fn source_filter(&self, base: &str) -> Option<SourceFilter> {
+        if self.sources.len() < 2 && self.source.is_none() {
+            return None;
+        }
+        Some(SourceFilter {
+            action: make_path(base, "/source"),
+            sources: self.sources.clone(),
+            current: self.source.clone(),
+        })
+    }
+
+    /// Action of the header search box, which keeps the period.
+    pub fn search(&self, base: &str) -> String {
+        with_period(&make_path(base, "/search"), &self.period)
+    }
+
+    /// Appends this page's own `from` to `path`, so links that stay on the
+    /// same page (pagination) keep the way back.
+    pub fn with_from(&self, path: &str) -> String {
+        match &self.from {
+            Some(from) => with_query(path, "from", from),
+            None => path.to_string(),
+        }
+    }
+
+    /// URL of the current page to use as the `from` of links rendered on it.
+    /// `None` when the plain path already reproduces the page exactly.
+    pub fn here(&self, self_path: &str, page: usize) -> Option<String> {
+        if page <= 1 && self.from.is_none() && self.sort.is_none() {
+            return None;
+        }
+        let mut path = self_path.to_string();
+        if page > 1 {
+            path = with_query(&path, "page", &page.to_string());
+        }
+        if let Some(sort) = &self.sort {
+            path = with_query(&path, "sort", sort);
+            if let Some(order) = &self.order {
+                path = with_query(&path, "order", order);
+            }
+        }
+        Some(self.with_from(&path))
+    }
+
+    /// Link to `path` that returns to `origin` on "Back".
+    pub fn drill(&self, path: &str, origin: Option<&str>) -> String {
+        let path = with_period(path, &self.period);
+        match origin {
+            Some(origin) => with_query(&path, "from", origin),
+            None => path,
+        }
+    }
+
+    pub fn back(&self) -> NavLink {
+        match &self.from {
+            Some(from) => NavLink::new("Back", from),
+            None => NavLink::back(),
+        }
+    }
+}
+
+impl From<&str> for NavContext {
+    fn from(period: &str) -> Self {
+        Self::new(period, None)
+    }
+}
+
+// Only same-origin absolute paths are accepted so `from` cannot be used as
+// an open redirect. Browsers drop tabs and newlines from URLs, so "/\t/host"
+// would become "//host": whitespace and control characters are refused too.
+pub fn is_local_path(path: &str) -> bool {
+    path.starts_with('/')
+        && !path.starts_with("//")
+        && !path.contains('\\')
+        && !path.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+// `?sort=` names of each sortable table's columns, in column order.
+pub const DATE_SORT: &[&str] = &["date", "cost"];
+pub const MONTH_SORT: &[&str] = &["month", "cost"];
+pub const USER_SORT: &[&str] = &["email", "cost"];
+pub const MODEL_SORT: &[&str] = &["model", "cost"];
+pub const API_KEY_SORT: &[&str] = &["key", "status", "created"];
+pub const PROFILE_SORT: &[&str] = &["model", "profile", "created"];
 
 pub fn sort_records(mut records: Vec<CostRecord>, sort: Option<usize>, order: &str) -> Vec<CostRecord> {
     let Some(col) = sort else { return records };
@@ -14,7 +179,7 @@ pub fn sort_records(mut records: Vec<CostRecord>, sort: Option<usize>, order: &s
     records.sort_by(|a, b| {
         let cmp = match col {
             0 => a.date.cmp(&b.date),
-            1 => a.amount.partial_cmp(&b.amount).unwrap_or(std::cmp::Ordering::Equal),
+            1 => a.amount.cmp(&b.amount),
             _ => std::cmp::Ordering::Equal,
         };
         if desc { cmp.reverse() } else { cmp }
@@ -32,7 +197,7 @@ pub fn sort_by_user(mut costs: Vec<CostByUser>, sort: Option<usize>, order: &str
                 let be = b.user_email.as_deref().unwrap_or(&b.user_id);
                 ae.cmp(be)
             }
-            1 => a.amount.partial_cmp(&b.amount).unwrap_or(std::cmp::Ordering::Equal),
+            1 => a.amount.cmp(&b.amount),
             _ => std::cmp::Ordering::Equal,
         };
         if desc { cmp.reverse() } else { cmp }
@@ -50,7 +215,7 @@ pub fn sort_by_model(mut costs: Vec<CostByModel>, sort: Option<usize>, order: &s
                 let bn = b.model_name.as_deref().unwrap_or(&b.model_id);
                 an.cmp(bn)
             }
-            1 => a.amount.partial_cmp(&b.amount).unwrap_or(std::cmp::Ordering::Equal),
+            1 => a.amount.cmp(&b.amount),
             _ => std::cmp::Ordering::Equal,
         };
         if desc { cmp.reverse() } else { cmp }
@@ -58,6 +223,44 @@ pub fn sort_by_model(mut costs: Vec<CostByModel>, sort: Option<usize>, order: &s
     costs
 }
 
+pub fn sort_api_keys(mut keys: Vec<ApiKeyInfo>, sort: Option<usize>, order: &str) -> Vec<ApiKeyInfo> {
+    let Some(col) = sort else { return keys };
+    let desc = order == "desc";
+    keys.sort_by(|a, b| {
+        let cmp = match col {
+            0 => a.api_key_preview.cmp(&b.api_key_preview),
+            1 => a.is_disabled.cmp(&b.is_disabled),
+            2 => a.created_at.cmp(&b.created_at),
+            _ => std::cmp::Ordering::Equal,
+        };
+        if desc { cmp.reverse() } else { cmp }
+    });
+    keys
+}
+
+pub fn sort_profiles(
+    mut profiles: Vec<InferenceProfileInfo>,
+    sort: Option<usize>,
+    order: &str,
+) -> Vec<InferenceProfileInfo> {
+    let Some(col) = sort else { return profiles };
+    let desc = order == "desc";
+    profiles.sort_by(|a, b| {
+        let cmp = match col {
+            0 => {
+                let an = a.model_name.as_deref().unwrap_or(&a.model_id);
+                let bn = b.model_name.as_deref().unwrap_or(&b.model_id);
+                an.cmp(bn)
+            }
+            1 => a.inference_profile_id.cmp(&b.inference_profile_id),
+            2 => a.created_at.cmp(&b.created_at),
+            _ => std::cmp::Ordering::Equal,
+        };
+        if desc { cmp.reverse() } else { cmp }
+    });
+    profiles
+}
+
 pub fn with_period(path: &str, period: &str) -> String {
     if period == "30d" {
         path.to_string()
@@ -66,6 +269,267 @@ pub fn with_period(path: &str, period: &str) -> String {
     }
 }
 
+/// Keeps the table's `?sort=` and `?order=`, if it is sorted.
+pub fn with_sort(path: &str, sort: Option<usize>, order: &str) -> String {
+    match sort {
+        Some(col) => with_query(&with_query(path, "sort", &col.to_string()), "order", order),
+        None => path.to_string(),
+    }
+}
+
+/// Keeps `?compare=prev` on links that stay on a comparison page.
+pub fn with_compare(path: &str, compare: bool) -> String {
+    if compare {
+        with_query(path, "compare", "prev")
+    } else {
+        path.to_string()
+    }
+}
+
+/// Toggle between the plain view and the previous-period comparison of
+/// `path`, styled like `period_links`.
+pub fn compare_links(path: &str, compare: bool) -> String {
+    if compare {
+        format!(
+            r#"<b>Previous Period</b> | <a href="{}">Off</a>"#,
+            html_escape(path)
+        )
+    } else {
+        format!(
+            r#"<a href="{}">Previous Period</a> | <b>Off</b>"#,
+            html_escape(&with_compare(path, true))
+        )
+    }
+}
+
+/// Toggle between the models index and its rollup into model families,
+/// styled like `period_links`.
+pub fn group_links(base: &str, period: &str, families: bool) -> String {
+    let models = with_period(&make_path(base, "/models"), period);
+    let families_path = with_period(&make_path(base, "/costs/families"), period);
+    if families {
+        format!(
+            r#"<a href="{}">Model</a> | <b>Family</b>"#,
+            html_escape(&models)
+        )
+    } else {
+        format!(
+            r#"<b>Model</b> | <a href="{}">Family</a>"#,
+            html_escape(&families_path)
+        )
+    }
+}
+
+/// Server-side filters for the users and models indexes, from `?min_cost=`,
+/// `?status=active|disabled` and `?has_cost=true`. Unparseable values are
+/// ignored.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct IndexFilter {
+    pub min_cost: Option<Amount>,
+    /// `Some(true)` keeps active rows only, `Some(false)` disabled ones.
+    pub active: Option<bool>,
+    pub has_cost: bool,
+    /// `?label=`; the handler narrows the rows to users or models carrying
+    /// it, as only it knows the labels.
+    pub label: Option<String>,
+}
+
+impl IndexFilter {
+    pub fn parse(min_cost: Option<&str>, status: Option<&str>, has_cost: Option<&str>) -> Self {
+        Self {
+            min_cost: min_cost.and_then(|v| v.parse().ok()),
+            active: match status {
+                Some("active") => Some(true),
+                Some("disabled") => Some(false),
+                _ => None,
+            },
+            has_cost: has_cost == Some("true"),
+            label: None,
+        }
+    }
+
+    pub fn with_label(mut self, label: Option<&str>) -> Self {
+        self.label = label.map(str::trim).filter(|l| !l.is_empty()).map(str::to_string);
+        self
+    }
+
+    /// Rows with an unknown status (`None`) only pass when no status is
+    /// asked for.
+    pub fn matches(&self, cost: Amount, active: Option<bool>) -> bool {
+        self.min_cost.is_none_or(|min| cost >= min)
+            && (!self.has_cost || !cost.is_zero())
+            && self.active.is_none_or(|want| active == Some(want))
+    }
+
+    /// `path` with this filter's query parameters, so pagination and the
+    /// period links keep it.
+    pub fn apply_to(&self, path: &str) -> String {
+        let mut path = path.to_string();
+        if let Some(min) = self.min_cost {
+            path = with_query(&path, "min_cost", &format!("{:.2}", min));
+        }
+        if let Some(active) = self.active {
+            path = with_query(&path, "status", if active { "active" } else { "disabled" });
+        }
+        if self.has_cost {
+            path = with_query(&path, "has_cost", "true");
+        }
+        if let Some(label) = &self.label {
+            path = with_query(&path, "label", label);
+        }
+        path
+    }
+}
+
+/// Status and zero-cost toggles for an index at `path`, styled like
+/// `period_links`.
+pub fn filter_links(path: &str, filter: &IndexFilter) -> String {
+    let link = |label: &str, f: IndexFilter, current: bool| {
+        if current {
+            format!("<b>{}</b>", label)
+        } else {
+            format!(r#"<a href="{}">{}</a>"#, html_escape(&f.apply_to(path)), label)
+        }
+    };
+    let status = [(None, "All"), (Some(true), "Active"), (Some(false), "Disabled")]
+        .into_iter()
+        .map(|(active, label)| {
+            let f = IndexFilter { active, ..filter.clone() };
+            link(label, f, filter.active == active)
+        })
+        .collect::<Vec<_>>()
+        .join(" | ");
+    let with_cost = IndexFilter { has_cost: true, ..filter.clone() };
+    let all_rows = IndexFilter { has_cost: false, ..filter.clone() };
+    let mut links = format!(
+        "{} &middot; {} | {}",
+        status,
+        link("With Cost", with_cost, filter.has_cost),
+        link("Including Zero Cost", all_rows, !filter.has_cost)
+    );
+    if let Some(label) = &filter.label {
+        let unlabeled = IndexFilter { label: None, ..filter.clone() };
+        links.push_str(&format!(
+            r#" &middot; Label <b>{}</b> (<a href="{}">clear</a>)"#,
+            html_escape(label),
+            html_escape(&unlabeled.apply_to(path))
+        ));
+    }
+    links
+}
+
+/// Links switching between CE cost metrics, with `current` in bold.
+pub fn metric_links(path: &str, current: Metric) -> String {
+    Metric::ALL
+        .into_iter()
+        .map(|m| {
+            if m == current {
+                format!("<b>{}</b>", m.label())
+            } else {
+                format!(
+                    r#"<a href="{}">{}</a>"#,
+                    html_escape(&with_query(path, "metric", m.as_str())),
+                    m.label()
+                )
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+/// Percentage change from `previous` to `current`; `None` when there is
+/// nothing to compare against.
+pub fn change_percent(current: Amount, previous: Amount) -> Option<f64> {
+    if previous.is_zero() {
+        return None;
+    }
+    Some((current - previous).micros() as f64 / previous.micros().abs() as f64 * 100.0)
+}
+
+/// `amount` as a percentage of `total`, or "-" when the total is zero.
+pub fn share(amount: Amount, total: Amount) -> String {
+    if total.is_zero() {
+        return "-".to_string();
+    }
+    format!("{:.1}%", amount.micros() as f64 / total.micros() as f64 * 100.0)
+}
+
+/// The "Previous", "Change" and "Change %" cells of a comparison row.
+pub fn change_cells(current: Amount, previous: Amount, currency: &str) -> [String; 3] {
+    let delta = current - previous;
+    let sign = if delta > Amount::ZERO { "+" } else { "" };
+    let percent = match change_percent(current, previous) {
+        Some(p) => format!("{}{:.1}%", sign, p),
+        None => "-".to_string(),
+    };
+    [
+        format!("{:.2} {}", previous, currency),
+        format!("{}{:.2} {}", sign, delta, currency),
+        percent,
+    ]
+}
+
+/// Info rows comparing `total` with the previous period's total, if any.
+pub fn compare_info_rows(total: Amount, previous: Option<Amount>, currency: &str) -> Vec<InfoRow> {
+    let Some(previous) = previous else {
+        return vec![];
+    };
+    let [previous_str, change, percent] = change_cells(total, previous, currency);
+    vec![
+        InfoRow::new("Previous Period", &previous_str),
+        InfoRow::new("Change", &format!("{} ({})", change, percent)),
+    ]
+}
+
+/// The "Charged Cost" info row of an index, when a markup is configured.
+pub fn charged_info_row(charged: Option<Amount>, currency: &str) -> Option<InfoRow> {
+    charged.map(|charged| InfoRow::new("Charged Cost", &format!("{:.2} {}", charged, currency)))
+}
+
+/// "Gross Cost" and "Credits" info rows for a page whose `net` total has
+/// the period's CE `credits` and refunds taken off, if it had any. The
+/// credits link to their page.
+pub fn credit_info_rows(
+    base: &str,
+    period: &str,
+    net: Amount,
+    credits: Option<Amount>,
+    currency: &str,
+) -> Vec<InfoRow> {
+    let Some(credits) = credits else {
+        return vec![];
+    };
+    vec![
+        InfoRow::new("Gross Cost", &format!("{:.2} {}", net - credits, currency)),
+        InfoRow::raw(
+            "Credits",
+            format!(
+                r#"<a href="{}">{}</a>"#,
+                html_escape(&with_period(&make_path(base, "/costs/credits"), period)),
+                html_escape(&format!("{:.2} {}", credits, currency))
+            ),
+        ),
+    ]
+}
+
+pub fn with_query(path: &str, key: &str, value: &str) -> String {
+    let sep = if path.contains('?') { '&' } else { '?' };
+    format!("{}{}{}={}", path, sep, key, encode_query_value(value))
+}
+
+pub fn encode_query_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for b in value.bytes() {
+        match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                out.push(b as char)
+            }
+            _ => out.push_str(&format!("%{:02X}", b)),
+        }
+    }
+    out
+}
+
 pub fn make_path(base: &str, suffix: &str) -> String {
     if suffix.is_empty() {
         return base.to_string();
@@ -74,15 +538,15 @@ pub fn make_path(base: &str, suffix: &str) -> String {
     format!("{}{}", base, suffix)
 }
 
-pub fn paginate<T>(items: &[T], page: usize) -> (&[T], usize) {
+pub fn paginate<T>(items: &[T], page: usize, page_size: usize) -> (&[T], usize) {
     let total = items.len();
     if total == 0 {
         return (items, 1);
     }
-    let total_pages = total.div_ceil(PAGE_SIZE);
+    let total_pages = total.div_ceil(page_size);
     let page = page.clamp(1, total_pages);
-    let start = (page - 1) * PAGE_SIZE;
-    let end = (start + PAGE_SIZE).min(total);
+    let start = (page - 1) * page_size;
+    let end = (start + page_size).min(total);
     (&items[start..end], page)
 }
 
@@ -122,4 +586,182 @@ mod tests {
         assert_eq!(with_period("/users", "7d"), "/users?period=7d");
         assert_eq!(with_period("/models", "3m"), "/models?period=3m");
     }
+
+    #[test]
+    fn index_filter_matches() {
+        let filter = IndexFilter::parse(Some("1.5"), Some("active"), Some("true"));
+        assert!(filter.matches(Amount::from_micros(2_000_000), Some(true)));
+        assert!(!filter.matches(Amount::from_micros(1_000_000), Some(true)));
+        assert!(!filter.matches(Amount::from_micros(2_000_000), Some(false)));
+        assert!(!filter.matches(Amount::from_micros(2_000_000), None));
+
+        let has_cost = IndexFilter::parse(None, Some("bogus"), Some("true"));
+        assert_eq!(has_cost.active, None);
+        assert!(!has_cost.matches(Amount::ZERO, Some(true)));
+        assert!(IndexFilter::default().matches(Amount::ZERO, None));
+    }
+
+    #[test]
+    fn index_filter_apply_to_round_trips() {
+        let filter = IndexFilter::parse(Some("10"), Some("disabled"), Some("true"));
+        assert_eq!(
+            filter.apply_to("/users?period=7d"),
+            "/users?period=7d&min_cost=10.00&status=disabled&has_cost=true"
+        );
+        assert_eq!(IndexFilter::default().apply_to("/users"), "/users");
+    }
+
+    #[test]
+    fn index_filter_keeps_label() {
+        let filter = IndexFilter::default().with_label(Some(" production "));
+        assert_eq!(filter.apply_to("/users"), "/users?label=production");
+        assert_eq!(IndexFilter::default().with_label(Some("")).label, None);
+        let links = filter_links("/users", &filter);
+        assert!(links.contains("Label <b>production</b>"));
+        assert!(links.contains(r#"href="/users?status=active&amp;label=production""#));
+        assert!(links.contains(r#"(<a href="/users">clear</a>)"#));
+    }
+
+    #[test]
+    fn with_compare_appends_flag() {
+        assert_eq!(with_compare("/users?period=7d", true), "/users?period=7d&compare=prev");
+        assert_eq!(with_compare("/users", false), "/users");
+    }
+
+    #[test]
+    fn group_links_toggle_between_models_and_families() {
+        assert_eq!(
+            group_links("/", "7d", false),
+            r#"<b>Model</b> | <a href="/costs/families?period=7d">Family</a>"#
+        );
+        assert_eq!(
+            group_links("/_dashboard", "30d", true),
+            r#"<a href="/_dashboard/models">Model</a> | <b>Family</b>"#
+        );
+    }
+
+    #[test]
+    fn change_cells_formats_delta() {
+        let [previous, change, percent] =
+            change_cells(Amount::from_f64(150.0), Amount::from_f64(100.0), "USD");
+        assert_eq!(previous, "100.00 USD");
+        assert_eq!(change, "+50.00 USD");
+        assert_eq!(percent, "+50.0%");
+
+        let [_, change, percent] =
+            change_cells(Amount::from_f64(75.0), Amount::from_f64(100.0), "USD");
+        assert_eq!(change, "-25.00 USD");
+        assert_eq!(percent, "-25.0%");
+    }
+
+    #[test]
+    fn change_cells_without_previous_cost() {
+        let [_, change, percent] = change_cells(Amount::from_f64(10.0), Amount::ZERO, "USD");
+        assert_eq!(change, "+10.00 USD");
+        assert_eq!(percent, "-");
+    }
+
+    #[test]
+    fn encode_query_value_escapes_reserved() {
+        assert_eq!(
+            encode_query_value("/users?period=7d&page=2"),
+            "/users%3Fperiod%3D7d%26page%3D2"
+        );
+    }
+
+    #[test]
+    fn nav_context_rejects_external_from() {
+        assert_eq!(NavContext::new("30d", Some("https://evil.example")).from, None);
+        assert_eq!(NavContext::new("30d", Some("//evil.example")).from, None);
+        assert_eq!(
+            NavContext::new("30d", Some("/users?period=7d")).from.as_deref(),
+            Some("/users?period=7d")
+        );
+    }
+
+    #[test]
+    fn local_path_refuses_what_browsers_turn_into_another_host() {
+        assert!(is_local_path("/users?period=7d&page=2"));
+        for path in ["/\t/evil.example", "/\n/evil.example", "/\r/evil.example", "/ /evil.example"] {
+            assert!(!is_local_path(path), "{path:?}");
+        }
+        assert_eq!(NavContext::new("30d", Some("/\t/evil.example")).from, None);
+    }
+
+    #[test]
+    fn nav_context_drill_carries_origin() {
+        let nav = NavContext::from("7d");
+        let origin = nav.here("/users?period=7d", 2);
+        assert_eq!(origin.as_deref(), Some("/users?period=7d&page=2"));
+        assert_eq!(
+            nav.drill("/users/abc", origin.as_deref()),
+            "/users/abc?period=7d&from=/users%3Fperiod%3D7d%26page%3D2"
+        );
+    }
+
+    #[test]
+    fn nav_context_drill_without_state_is_plain() {
+        let nav = NavContext::from("30d");
+        assert_eq!(nav.here("/users", 1), None);
+        assert_eq!(nav.drill("/users/abc", None), "/users/abc");
+    }
+
+    #[test]
+    fn nav_context_here_keeps_own_from() {
+        let nav = NavContext::new("30d", Some("/users"));
+        assert_eq!(
+            nav.here("/users/abc", 1).as_deref(),
+            Some("/users/abc?from=/users")
+        );
+    }
+
+    #[test]
+    fn nav_context_here_keeps_sorting() {
+        let nav = NavContext::from("7d").with_sort(Some("cost"), Some("desc"));
+        let origin = nav.here("/users?period=7d", 2);
+        assert_eq!(
+            origin.as_deref(),
+            Some("/users?period=7d&page=2&sort=cost&order=desc")
+        );
+        assert_eq!(
+            nav.drill("/users/abc", origin.as_deref()),
+            "/users/abc?period=7d&from=/users%3Fperiod%3D7d%26page%3D2%26sort%3Dcost%26order%3Ddesc"
+        );
+        assert_eq!(
+            NavContext::from("30d").with_sort(None, Some("desc")).here("/users", 1),
+            None
+        );
+    }
+
+    #[test]
+    fn nav_context_back_uses_from() {
+        assert_eq!(NavContext::new("30d", Some("/users")).back().href, "/users");
+        assert_eq!(NavContext::from("30d").back().href, "javascript:history.back()");
+    }
+
+    #[test]
+    fn nav_context_search_keeps_period() {
+        assert_eq!(NavContext::from("7d").search("/_dashboard"), "/_dashboard/search?period=7d");
+        assert_eq!(NavContext::from("30d").search("/"), "/search");
+    }
+
+    #[test]
+    fn nav_context_source_filter_needs_a_choice() {
+        let sources = vec!["aws".to_string()];
+        assert!(NavContext::from("30d").with_sources(None, &sources).source_filter("/").is_none());
+        let sources = vec!["aws".to_string(), "openai".to_string()];
+        let filter = NavContext::from("30d")
+            .with_sources(Some("openai"), &sources)
+            .source_filter("/costs/")
+            .unwrap();
+        assert_eq!(filter.action, "/costs/source");
+        assert_eq!(filter.sources, sources);
+        assert_eq!(filter.current.as_deref(), Some("openai"));
+    }
+
+    #[test]
+    fn share_of_total() {
+        assert_eq!(share(Amount::from_micros(1), Amount::from_micros(4)), "25.0%");
+        assert_eq!(share(Amount::from_micros(1), Amount::ZERO), "-");
+    }
 }
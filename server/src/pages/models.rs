@@ -1,53 +1,79 @@
-use super::{make_path, paginate, with_period, PAGE_SIZE};
-use common::{CostByModel, CostRecord, ModelInfo};
+use super::{
+    change_cells, change_percent, charged_info_row, compare_info_rows, compare_links, filter_links,
+    group_links, make_path, paginate, share, with_compare, with_period, with_sort, IndexFilter,
+    NavContext,
+};
+use super::regions::totals_by_region;
+use common::{
+    Amount, CostByModel, CostByRegion, CostByUser, CostRecord, InferenceProfileInfo, Markup,
+    ModelInfo,
+};
 use leptos::either::Either;
 use leptos::prelude::*;
-use templates::{pagination_nav, period_links, Breadcrumb, InfoRow, NavLink, Page, Subpage};
+use templates::{html_escape, pagination_nav, period_links, Breadcrumb, InfoRow, Page, Subpage};
 
-pub fn render_index(
-    base: &str,
-    period: &str,
-    page: usize,
+/// `?sort=` names for [`render_index`] columns.
+pub const INDEX_SORT: &[&str] = &[
+    "name", "cost", "status", "protected", "users", "previous", "change", "change_pct", "charged",
+];
+
+/// `?sort=` names for [`render_users`] columns.
+pub const USERS_SORT: &[&str] = &["email", "profiles", "since", "cost"];
+
+/// A row of the models index.
+pub struct IndexRow {
+    pub model_id: String,
+    pub display: String,
+    pub cost: Amount,
+    pub previous: Amount,
+    pub currency: String,
+    /// `None` for cost entries without a matching account.
+    active: Option<bool>,
+    pub status: String,
+    pub protected: bool,
+    pub user_count: i64,
+    /// Cost with the markup added, when one is configured.
+    pub charged: Option<Amount>,
+}
+
+/// Every row of the models index that passes `filter`, in `sort` order.
+/// `markup` adds the charged cost, when one is configured.
+pub fn index_rows(
     models: &[ModelInfo],
     costs: &[CostByModel],
+    previous: Option<&[CostByModel]>,
     sort: Option<usize>,
     order: &str,
-) -> String {
-    let models = models.to_vec();
-    let costs = costs.to_vec();
-    let empty = models.is_empty() && costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    filter: &IndexFilter,
+    markup: Option<&Markup>,
+) -> Vec<IndexRow> {
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let base_owned = base.to_string();
 
     // Build a cost lookup by model_id
     let cost_map: std::collections::HashMap<String, &CostByModel> =
         costs.iter().map(|c| (c.model_id.clone(), c)).collect();
+    let previous_map: std::collections::HashMap<String, Amount> = previous
+        .unwrap_or_default()
+        .iter()
+        .map(|c| (c.model_id.clone(), c.amount))
+        .collect();
 
-    struct Row {
-        model_id: String,
-        display: String,
-        cost: f64,
-        currency: String,
-        status: String,
-        protected: bool,
-        user_count: i64,
-    }
-
-    let mut rows: Vec<Row> = models
+    let mut rows: Vec<IndexRow> = models
         .iter()
         .map(|m| {
             let cost_entry = cost_map.get(&m.model_id);
-            Row {
+            IndexRow {
                 model_id: m.model_id.clone(),
                 display: m.model_name.clone(),
-                cost: cost_entry.map(|c| c.amount).unwrap_or(0.0),
+                cost: cost_entry.map(|c| c.amount).unwrap_or_default(),
+                previous: previous_map.get(&m.model_id).copied().unwrap_or_default(),
                 currency: cost_entry
                     .map(|c| c.currency.clone())
                     .unwrap_or_else(|| currency.clone()),
+                active: Some(!m.is_disabled),
                 status: if m.is_disabled {
                     "Disabled".to_string()
                 } else {
@@ -55,6 +81,7 @@ pub fn render_index(
                 },
                 protected: m.protected,
                 user_count: m.user_count,
+                charged: None,
             }
         })
         .collect();
@@ -62,45 +89,105 @@ pub fn render_index(
     // Also add any cost entries for models not in the enriched list
     let model_ids: std::collections::HashSet<String> =
         models.iter().map(|m| m.model_id.clone()).collect();
-    for c in &costs {
+    for c in costs {
         if !model_ids.contains(&c.model_id) {
-            rows.push(Row {
+            rows.push(IndexRow {
                 model_id: c.model_id.clone(),
                 display: c.model_name.clone().unwrap_or_else(|| c.model_id.clone()),
                 cost: c.amount,
+                previous: previous_map.get(&c.model_id).copied().unwrap_or_default(),
                 currency: c.currency.clone(),
+                active: None,
                 status: "-".to_string(),
                 protected: false,
                 user_count: 0,
+                charged: None,
             });
         }
     }
+    if let Some(markup) = markup {
+        for r in &mut rows {
+            r.charged = Some(markup.charged(&r.model_id, r.cost));
+        }
+    }
 
-    let total_rows = rows.len();
-    // Sort rows before paginating
+    rows.retain(|r| filter.matches(r.cost, r.active));
     if let Some(col) = sort {
         let desc = order == "desc";
         rows.sort_by(|a, b| {
             let cmp = match col {
                 0 => a.display.cmp(&b.display),
-                1 => a.cost.partial_cmp(&b.cost).unwrap_or(std::cmp::Ordering::Equal),
+                1 => a.cost.cmp(&b.cost),
                 2 => a.status.cmp(&b.status),
                 3 => a.protected.cmp(&b.protected),
                 4 => a.user_count.cmp(&b.user_count),
+                5 => a.previous.cmp(&b.previous),
+                6 => (a.cost - a.previous).cmp(&(b.cost - b.previous)),
+                7 => change_percent(a.cost, a.previous)
+                    .partial_cmp(&change_percent(b.cost, b.previous))
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                8 => a.charged.cmp(&b.charged),
                 _ => std::cmp::Ordering::Equal,
             };
             if desc { cmp.reverse() } else { cmp }
         });
     }
+    rows
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_index(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    models: &[ModelInfo],
+    costs: &[CostByModel],
+    previous: Option<&[CostByModel]>,
+    sort: Option<usize>,
+    order: &str,
+    filter: &IndexFilter,
+    markup: Option<&Markup>,
+) -> String {
+    let period = nav.period.as_str();
+    let empty = models.is_empty() && costs.is_empty();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let base_owned = base.to_string();
+    let compare = previous.is_some();
+    let previous_total = previous.map(|p| p.iter().map(|c| c.amount).sum::<Amount>());
+
+    let charged_total = markup.map(|markup| {
+        costs
+            .iter()
+            .map(|c| markup.charged(&c.model_id, c.amount))
+            .sum::<Amount>()
+    });
+
+    let rows = index_rows(models, costs, previous, sort, order, filter, markup);
+    let total_rows = rows.len();
     let total_pages = if total_rows == 0 {
         1
     } else {
-        total_rows.div_ceil(PAGE_SIZE)
+        total_rows.div_ceil(nav.page_size)
     };
     let page = page.clamp(1, total_pages);
-    let skip = (page - 1) * PAGE_SIZE;
-    let self_path = with_period(&make_path(base, "/models"), period);
-    let pagination_html = pagination_nav(&self_path, page, total_rows, PAGE_SIZE);
+    let skip = (page - 1) * nav.page_size;
+    let period_path = with_period(&make_path(base, "/models"), period);
+    let unfiltered_path = with_compare(&period_path, compare);
+    let self_path = filter.apply_to(&unfiltered_path);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, total_rows, nav.page_size);
+    let export_path = with_sort(
+        &filter.apply_to(&with_compare(
+            &with_period(&make_path(base, "/models.csv"), period),
+            compare,
+        )),
+        sort,
+        order,
+    );
 
     let content = view! {
         <h2>"Models"</h2>
@@ -110,26 +197,46 @@ pub fn render_index(
             })
         } else {
             Either::Right(view! {
-                <table class="data-table" data-export-name="cost_by_model">
+                <table class="data-table" data-export-name="cost_by_model" data-export-href={export_path.clone()}>
                     <tr>
                         <th>"Name"</th>
-                        <th>"Cost"</th>
+                        {if markup.is_some() {
+                            Either::Left(view! {
+                                <th>"Raw Cost"</th>
+                                <th>"Charged Cost"</th>
+                            })
+                        } else {
+                            Either::Right(view! { <th>"Cost"</th> })
+                        }}
                         <th>"Status"</th>
                         <th>"Protected"</th>
                         <th>"Users"</th>
+                        {compare.then(|| view! {
+                            <th>"Previous"</th>
+                            <th>"Change"</th>
+                            <th>"Change %"</th>
+                        })}
                     </tr>
-                    {rows.into_iter().skip(skip).take(PAGE_SIZE).map(|r| {
-                        let href = with_period(&make_path(&base_owned, &format!("/models/{}", r.model_id)), period);
+                    {rows.into_iter().skip(skip).take(nav.page_size).map(|r| {
+                        let href = nav.drill(&make_path(&base_owned, &format!("/models/{}", r.model_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", r.cost, r.currency);
+                        let charged_str = r.charged.map(|c| format!("{:.2} {}", c, r.currency));
+                        let change = compare.then(|| change_cells(r.cost, r.previous, &r.currency));
                         let protected_str = if r.protected { "Yes" } else { "No" };
                         let user_count_str = r.user_count.to_string();
                         view! {
                             <tr>
                                 <td><a href={href}>{r.display}</a></td>
                                 <td>{cost_str}</td>
+                                {charged_str.map(|c| view! { <td>{c}</td> })}
                                 <td>{r.status}</td>
                                 <td>{protected_str}</td>
                                 <td>{user_count_str}</td>
+                                {change.map(|[previous, change, percent]| view! {
+                                    <td>{previous}</td>
+                                    <td>{change}</td>
+                                    <td>{percent}</td>
+                                })}
                             </tr>
                         }
                     }).collect::<Vec<_>>()}
@@ -139,30 +246,92 @@ pub fn render_index(
         }}
     };
 
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(
+                &filter.apply_to(&with_compare(&make_path(base, "/models"), compare)),
+                period,
+            ),
+        ),
+        InfoRow::raw(
+            "Compare",
+            compare_links(&filter.apply_to(&period_path), compare),
+        ),
+        InfoRow::raw("Filter", filter_links(&unfiltered_path, filter)),
+        InfoRow::raw("Group By", group_links(base, period, false)),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+    ];
+    info_rows.extend(charged_info_row(charged_total, &currency));
+    info_rows.push(InfoRow::raw(
+        "Export",
+        format!(r#"<a href="{}">CSV</a>"#, html_escape(&export_path)),
+    ));
+    info_rows.extend(compare_info_rows(total, previous_total, &currency));
+
     Page {
         title: "Cost Explorer - Models".to_string(),
         breadcrumbs: vec![
             Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
             Breadcrumb::current("Models"),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::raw("Period", period_links(&make_path(base, "/models"), period)),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
-        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
         content,
         subpages: vec![],
     }
     .render()
 }
 
-pub fn render_hub(base: &str, period: &str, model: &ModelInfo) -> String {
+pub fn render_hub(
+    base: &str,
+    nav: &NavContext,
+    model: &ModelInfo,
+    regions: &[CostByRegion],
+) -> String {
+    let period = nav.period.as_str();
+    let origin = nav.here(
+        &with_period(&make_path(base, &format!("/models/{}", model.model_id)), period),
+        1,
+    );
     let status = if model.is_disabled {
         "Disabled"
     } else {
         "Active"
     };
     let protected = if model.protected { "Yes" } else { "No" };
+    let region_totals = totals_by_region(regions);
+    let region_total: Amount = region_totals.iter().map(|(_, amount)| *amount).sum();
+    let region_currency = regions
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let content = (!region_totals.is_empty()).then(|| {
+        view! {
+            <h2>"Cost by Region"</h2>
+            <table class="data-table" data-export-name="model_cost_by_region">
+                <tr>
+                    <th>"Region"</th>
+                    <th>"Cost"</th>
+                    <th>"Share"</th>
+                </tr>
+                {region_totals.into_iter().map(|(region, amount)| {
+                    let cost_str = format!("{:.2} {}", amount, region_currency);
+                    let share_str = share(amount, region_total);
+                    view! {
+                        <tr>
+                            <td>{region}</td>
+                            <td>{cost_str}</td>
+                            <td>{share_str}</td>
+                        </tr>
+                    }
+                }).collect::<Vec<_>>()}
+            </table>
+        }
+    });
 
     Page {
         title: format!("Cost Explorer - {}", model.model_name),
@@ -171,7 +340,10 @@ pub fn render_hub(base: &str, period: &str, model: &ModelInfo) -> String {
             Breadcrumb::link("Models", with_period(&make_path(base, "/models"), period)),
             Breadcrumb::current(&model.model_name),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Model ID", &model.model_id),
             InfoRow::new("Model Name", &model.model_name),
@@ -179,52 +351,210 @@ pub fn render_hub(base: &str, period: &str, model: &ModelInfo) -> String {
             InfoRow::new("Protected", protected),
             InfoRow::new("Users with Access", &model.user_count.to_string()),
         ],
-        content: (),
+        content,
         subpages: vec![
             Subpage::new(
                 "Daily Cost",
-                with_period(
+                nav.drill(
                     &make_path(base, &format!("/models/{}/daily", model.model_id)),
-                    period,
+                    origin.as_deref(),
                 ),
                 "-",
             ),
             Subpage::new(
                 "Monthly Cost",
-                with_period(
+                nav.drill(
                     &make_path(base, &format!("/models/{}/monthly", model.model_id)),
-                    period,
+                    origin.as_deref(),
                 ),
                 "-",
             ),
+            Subpage::new(
+                "Users",
+                nav.drill(
+                    &make_path(base, &format!("/models/{}/users", model.model_id)),
+                    origin.as_deref(),
+                ),
+                model.user_count,
+            ),
+        ],
+    }
+    .render()
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_users(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    model_id: &str,
+    model_name: &str,
+    profiles: &[InferenceProfileInfo],
+    costs: &[CostByUser],
+    sort: Option<usize>,
+    order: &str,
+) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let cost_map: std::collections::HashMap<&str, &CostByUser> =
+        costs.iter().map(|c| (c.user_id.as_str(), c)).collect();
+
+    struct Row {
+        user_id: String,
+        display: String,
+        profiles: usize,
+        since: String,
+        cost: Amount,
+        currency: String,
+    }
+
+    let mut rows: Vec<Row> = Vec::new();
+    for p in profiles {
+        if let Some(row) = rows.iter_mut().find(|r| r.user_id == p.user_id) {
+            row.profiles += 1;
+            if !p.created_at.is_empty() && (row.since.is_empty() || p.created_at < row.since) {
+                row.since = p.created_at.clone();
+            }
+            continue;
+        }
+        let cost_entry = cost_map.get(p.user_id.as_str());
+        rows.push(Row {
+            user_id: p.user_id.clone(),
+            display: p.user_email.clone().unwrap_or_else(|| p.user_id.clone()),
+            profiles: 1,
+            since: p.created_at.clone(),
+            cost: cost_entry.map(|c| c.amount).unwrap_or_default(),
+            currency: cost_entry
+                .map(|c| c.currency.clone())
+                .unwrap_or_else(|| currency.clone()),
+        });
+    }
+    let empty = rows.is_empty();
+
+    let total_rows = rows.len();
+    if let Some(col) = sort {
+        let desc = order == "desc";
+        rows.sort_by(|a, b| {
+            let cmp = match col {
+                0 => a.display.cmp(&b.display),
+                1 => a.profiles.cmp(&b.profiles),
+                2 => a.since.cmp(&b.since),
+                3 => a.cost.cmp(&b.cost),
+                _ => std::cmp::Ordering::Equal,
+            };
+            if desc { cmp.reverse() } else { cmp }
+        });
+    }
+    let total_pages = if total_rows == 0 {
+        1
+    } else {
+        total_rows.div_ceil(nav.page_size)
+    };
+    let page = page.clamp(1, total_pages);
+    let skip = (page - 1) * nav.page_size;
+    let self_path = with_period(
+        &make_path(base, &format!("/models/{}/users", model_id)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, total_rows, nav.page_size);
+    let base_owned = base.to_string();
+
+    let content = view! {
+        <h2>"Users with Access"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No users have an inference profile for this model."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="model_users">
+                    <tr>
+                        <th>"Email"</th>
+                        <th>"Profiles"</th>
+                        <th>"Since"</th>
+                        <th>"Cost"</th>
+                    </tr>
+                    {rows.into_iter().skip(skip).take(nav.page_size).map(|r| {
+                        let href = nav.drill(&make_path(&base_owned, &format!("/users/{}", r.user_id)), origin.as_deref());
+                        let profiles_str = r.profiles.to_string();
+                        let cost_str = format!("{:.2} {}", r.cost, r.currency);
+                        view! {
+                            <tr>
+                                <td><a href={href}>{r.display}</a></td>
+                                <td>{profiles_str}</td>
+                                <td>{r.since}</td>
+                                <td>{cost_str}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+                <div inner_html={pagination_html}></div>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Cost Explorer - {} - Users", model_name),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link("Models", with_period(&make_path(base, "/models"), period)),
+            Breadcrumb::link(
+                model_name,
+                with_period(&make_path(base, &format!("/models/{}", model_id)), period),
+            ),
+            Breadcrumb::current("Users"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(
+                    &make_path(base, &format!("/models/{}/users", model_id)),
+                    period,
+                ),
+            ),
+            InfoRow::new("Users", &total_rows.to_string()),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
         ],
+        content,
+        subpages: vec![],
     }
     .render()
 }
 
 pub fn render_daily_costs(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     model_id: &str,
     model_name: &str,
     costs: &[CostRecord],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
 
-    let (page_items, page) = paginate(&costs, page);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
     let self_path = with_period(
         &make_path(base, &format!("/models/{}/daily", model_id)),
         period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Daily Cost"</h2>
@@ -240,7 +570,7 @@ pub fn render_daily_costs(
                         <th>"Cost"</th>
                     </tr>
                     {page_items.iter().map(|c| {
-                        let href = with_period(&make_path(&base_owned, &format!("/costs/daily/{}/models/{}", c.date, model_id)), period);
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/daily/{}/models/{}", c.date, model_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         let date = c.date.clone();
                         view! {
@@ -267,7 +597,10 @@ pub fn render_daily_costs(
             ),
             Breadcrumb::current("Daily Cost"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::raw(
                 "Period",
@@ -286,27 +619,29 @@ pub fn render_daily_costs(
 
 pub fn render_monthly_costs(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     model_id: &str,
     model_name: &str,
     costs: &[CostRecord],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
 
-    let (page_items, page) = paginate(&costs, page);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
     let self_path = with_period(
         &make_path(base, &format!("/models/{}/monthly", model_id)),
         period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Monthly Cost"</h2>
@@ -323,7 +658,7 @@ pub fn render_monthly_costs(
                     </tr>
                     {page_items.iter().map(|c| {
                         let month = if c.date.len() >= 7 { &c.date[..7] } else { &c.date };
-                        let href = with_period(&make_path(&base_owned, &format!("/costs/monthly/{}/models/{}", month, model_id)), period);
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/monthly/{}/models/{}", month, model_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         let month_display = month.to_string();
                         view! {
@@ -350,7 +685,10 @@ pub fn render_monthly_costs(
             ),
             Breadcrumb::current("Monthly Cost"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::raw(
                 "Period",
@@ -373,7 +711,18 @@ mod tests {
 
     #[test]
     fn render_index_empty() {
-        let html = render_index("/", "30d", 1, &[], &[], None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("No models found."));
         assert!(html.contains("Cost Explorer - Models"));
     }
@@ -390,20 +739,43 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 100.0,
+            amount: Amount::from_f64(100.0),
             currency: "USD".to_string(),
         }];
-        let html = render_index("/", "30d", 1, &models, &costs, None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &models,
+            &costs,
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("claude-3"));
         assert!(html.contains("100.00 USD"));
         assert!(html.contains("Active"));
         assert!(html.contains("Yes")); // protected
         assert!(html.contains("/models/model-1"));
+        assert!(html.contains(r#"<b>Model</b> | <a href="/costs/families">Family</a>"#));
     }
 
     #[test]
     fn render_index_period_links() {
-        let html = render_index("/", "30d", 1, &[], &[], None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("<b>Past 30 Days</b>"));
         assert!(html.contains("?period=7d"));
     }
@@ -417,10 +789,51 @@ mod tests {
             protected: false,
             user_count: 1,
         }];
-        let html = render_index("/_dashboard", "30d", 1, &models, &[], None, "asc");
+        let html = render_index(
+            "/_dashboard",
+            &"30d".into(),
+            1,
+            &models,
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("/_dashboard/models/model-1"));
     }
 
+    #[test]
+    fn render_index_shows_charged_cost() {
+        let costs = vec![CostByModel {
+            model_id: "model-1".to_string(),
+            model_name: Some("claude-3".to_string()),
+            amount: Amount::from_f64(100.0),
+            currency: "USD".to_string(),
+        }];
+        let markup = Markup {
+            percent: 10.0,
+            models: [("model-1".to_string(), 15.0)].into_iter().collect(),
+        };
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &costs,
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            Some(&markup),
+        );
+        assert!(html.contains("<th>Raw Cost</th>"));
+        assert!(html.contains("<th>Charged Cost</th>"));
+        assert!(html.contains("<td>115.00 USD</td>"));
+        assert!(html.contains("<td>Charged Cost</td>"));
+    }
+
     #[test]
     fn render_hub_contains_info() {
         let model = ModelInfo {
@@ -430,18 +843,42 @@ mod tests {
             protected: true,
             user_count: 5,
         };
-        let html = render_hub("/", "30d", &model);
+        let html = render_hub("/", &"30d".into(), &model, &[]);
         assert!(html.contains("claude-3"));
         assert!(html.contains("model-1"));
         assert!(html.contains("Active"));
         assert!(html.contains("Yes")); // protected
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("Monthly Cost"));
+        assert!(html.contains("/models/model-1/users"));
+        assert!(!html.contains("Cost by Region"));
+    }
+
+    #[test]
+    fn render_hub_contains_regions() {
+        let model = ModelInfo {
+            model_id: "model-1".to_string(),
+            model_name: "claude-3".to_string(),
+            is_disabled: false,
+            protected: false,
+            user_count: 1,
+        };
+        let regions = vec![CostByRegion {
+            region: "eu-central-1".to_string(),
+            model_id: "model-1".to_string(),
+            model_name: None,
+            amount: Amount::from_f64(2.5),
+            currency: "USD".to_string(),
+        }];
+        let html = render_hub("/", &"30d".into(), &model, &regions);
+        assert!(html.contains("Cost by Region"));
+        assert!(html.contains("eu-central-1"));
+        assert!(html.contains("2.50 USD"));
     }
 
     #[test]
     fn render_daily_costs_empty() {
-        let html = render_daily_costs("/", "30d", 1, "model-1", "claude-3", &[]);
+        let html = render_daily_costs("/", &"30d".into(), 1, "model-1", "claude-3", &[]);
         assert!(html.contains("No cost data found for this model"));
     }
 
@@ -449,10 +886,10 @@ mod tests {
     fn render_daily_costs_with_data() {
         let costs = vec![CostRecord {
             date: "2024-01-15".to_string(),
-            amount: 75.0,
+            amount: Amount::from_f64(75.0),
             currency: "USD".to_string(),
         }];
-        let html = render_daily_costs("/", "30d", 1, "model-1", "claude-3", &costs);
+        let html = render_daily_costs("/", &"30d".into(), 1, "model-1", "claude-3", &costs);
         assert!(html.contains("2024-01-15"));
         assert!(html.contains("75.00 USD"));
         assert!(html.contains("/costs/daily/2024-01-15/models/model-1"));
@@ -460,7 +897,7 @@ mod tests {
 
     #[test]
     fn render_monthly_costs_empty() {
-        let html = render_monthly_costs("/", "30d", 1, "model-1", "claude-3", &[]);
+        let html = render_monthly_costs("/", &"30d".into(), 1, "model-1", "claude-3", &[]);
         assert!(html.contains("No cost data found for this model"));
     }
 
@@ -468,12 +905,57 @@ mod tests {
     fn render_monthly_costs_with_data() {
         let costs = vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 500.0,
+            amount: Amount::from_f64(500.0),
             currency: "USD".to_string(),
         }];
-        let html = render_monthly_costs("/", "30d", 1, "model-1", "claude-3", &costs);
+        let html = render_monthly_costs("/", &"30d".into(), 1, "model-1", "claude-3", &costs);
         assert!(html.contains("2024-01"));
         assert!(html.contains("500.00 USD"));
         assert!(html.contains("/costs/monthly/2024-01/models/model-1"));
     }
+
+    #[test]
+    fn render_users_groups_profiles_by_user() {
+        let profile = |id: &str, user: &str, created: &str| InferenceProfileInfo {
+            inference_profile_id: id.to_string(),
+            model_id: "model-1".to_string(),
+            model_name: Some("claude-3-sonnet".to_string()),
+            user_id: user.to_string(),
+            user_email: Some(format!("{}@example.com", user)),
+            created_at: created.to_string(),
+        };
+        let profiles = vec![
+            profile("p1", "alice", "2024-02-01"),
+            profile("p2", "alice", "2024-01-01"),
+            profile("p3", "bob", "2024-03-01"),
+        ];
+        let costs = vec![CostByUser {
+            user_id: "alice".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            amount: Amount::from_f64(12.5),
+            currency: "USD".to_string(),
+        }];
+        let html = render_users(
+            "/",
+            &"30d".into(),
+            1,
+            "model-1",
+            "claude-3-sonnet",
+            &profiles,
+            &costs,
+            None,
+            "asc",
+        );
+        assert!(html.contains("<a href=\"/users/alice\">alice@example.com</a>"));
+        assert!(html.contains("bob@example.com"));
+        assert!(html.contains("2024-01-01"));
+        assert!(!html.contains("2024-02-01"));
+        assert!(html.contains("12.50 USD"));
+    }
+
+    #[test]
+    fn render_users_empty() {
+        let html = render_users("/", &"30d".into(), 1, "model-1", "m", &[], &[], None, "asc");
+        assert!(html.contains("No users have an inference profile for this model."));
+    }
 }
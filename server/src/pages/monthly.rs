@@ -1,12 +1,58 @@
-use super::{make_path, paginate, with_period, PAGE_SIZE};
-use common::{CostByModel, CostByUser, CostRecord};
+use super::{
+    change_cells, compare_info_rows, compare_links, credit_info_rows, make_path, paginate,
+    with_compare, with_period, with_query, NavContext,
+};
+use chrono::{Datelike, Duration, Months, NaiveDate};
+use common::{Amount, CostByModel, CostByUser, CostRecord};
 use leptos::either::Either;
 use leptos::prelude::*;
-use templates::{pagination_nav, period_links, Breadcrumb, InfoRow, NavLink, Page, Subpage};
+use templates::{
+    line_chart, pagination_nav, period_links, Breadcrumb, ChartLine, InfoRow, NavLink, Page,
+    Subpage,
+};
+
+/// Running total of `daily` by day of the month, through the last day with a
+/// record.
+fn cumulative(daily: &[CostRecord]) -> Vec<f64> {
+    let mut by_day = vec![Amount::ZERO; 31];
+    let mut days = 0;
+    for r in daily {
+        if let Ok(date) = NaiveDate::parse_from_str(&r.date, "%Y-%m-%d") {
+            let day = date.day0() as usize;
+            by_day[day] += r.amount;
+            days = days.max(day + 1);
+        }
+    }
+    by_day.truncate(days);
+    let mut total = Amount::ZERO;
+    by_day
+        .into_iter()
+        .map(|amount| {
+            total += amount;
+            total.to_f64()
+        })
+        .collect()
+}
+
+/// Days in `month` (`YYYY-MM`), or 31 if it does not parse.
+fn days_in_month(month: &str) -> u32 {
+    NaiveDate::parse_from_str(&format!("{}-01", month), "%Y-%m-%d")
+        .ok()
+        .and_then(|start| start.checked_add_months(Months::new(1)))
+        .map_or(31, |next| (next - Duration::days(1)).day())
+}
 
-pub fn render(base: &str, period: &str, page: usize, monthly_cost: &[CostRecord]) -> String {
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    monthly_cost: &[CostRecord],
+    previous: Option<&[CostRecord]>,
+    credits: Option<Amount>,
+) -> String {
+    let period = nav.period.as_str();
     let monthly_cost = monthly_cost.to_vec();
-    let total: f64 = monthly_cost.iter().map(|r| r.amount).sum();
+    let total: Amount = monthly_cost.iter().map(|r| r.amount).sum();
     let currency = monthly_cost
         .first()
         .map(|r| r.currency.clone())
@@ -17,9 +63,21 @@ pub fn render(base: &str, period: &str, page: usize, monthly_cost: &[CostRecord]
     let start_owned = start.to_string();
     let end_owned = end.to_string();
     let base_owned = base.to_string();
-    let (page_items, page) = paginate(&monthly_cost, page);
-    let self_path = with_period(&make_path(base, "/costs/monthly"), period);
-    let pagination_html = pagination_nav(&self_path, page, monthly_cost.len(), PAGE_SIZE);
+    // Previous-period records arrive already moved onto the dates they are
+    // compared against.
+    let compare = previous.is_some();
+    let previous_map: std::collections::HashMap<String, Amount> = previous
+        .unwrap_or_default()
+        .iter()
+        .map(|r| (r.date.clone(), r.amount))
+        .collect();
+    let previous_total = previous.map(|p| p.iter().map(|r| r.amount).sum::<Amount>());
+    let (page_items, page) = paginate(&monthly_cost, page, nav.page_size);
+    let period_path = with_period(&make_path(base, "/costs/monthly"), period);
+    let self_path = with_compare(&period_path, compare);
+    let origin = nav.here(&self_path, page);
+    let pagination_html =
+        pagination_nav(&nav.with_from(&self_path), page, monthly_cost.len(), nav.page_size);
 
     let content = view! {
         <h2>"Monthly Cost Breakdown"</h2>
@@ -33,16 +91,30 @@ pub fn render(base: &str, period: &str, page: usize, monthly_cost: &[CostRecord]
                     <tr>
                         <th>"Month"</th>
                         <th>"Cost"</th>
+                        {compare.then(|| view! {
+                            <th>"Previous"</th>
+                            <th>"Change"</th>
+                            <th>"Change %"</th>
+                        })}
                     </tr>
                     {page_items.iter().map(|r| {
                         let month = r.date.strip_suffix("-01").unwrap_or(&r.date).to_string();
-                        let month_href = make_path(&base_owned, &format!("/costs/monthly/{}", month));
+                        let month_href = nav.drill(&make_path(&base_owned, &format!("/costs/monthly/{}", month)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", r.amount, r.currency);
+                        let change = compare.then(|| {
+                            let previous = previous_map.get(&r.date).copied().unwrap_or_default();
+                            change_cells(r.amount, previous, &r.currency)
+                        });
                         let month_display = month.clone();
                         view! {
                             <tr>
                                 <td><a href={month_href}>{month_display}</a></td>
                                 <td>{cost_str}</td>
+                                {change.map(|[previous, change, percent]| view! {
+                                    <td>{previous}</td>
+                                    <td>{change}</td>
+                                    <td>{percent}</td>
+                                })}
                             </tr>
                         }
                     }).collect::<Vec<_>>()}
@@ -52,35 +124,94 @@ pub fn render(base: &str, period: &str, page: usize, monthly_cost: &[CostRecord]
         }}
     };
 
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&with_compare(&make_path(base, "/costs/monthly"), compare), period),
+        ),
+        InfoRow::raw("Compare", compare_links(&period_path, compare)),
+    ];
+    info_rows.extend(credit_info_rows(base, period, total, credits, &currency));
+    let total_label = match credits {
+        Some(_) => "Net Cost",
+        None => "Total Cost",
+    };
+    info_rows.push(InfoRow::new(
+        total_label,
+        &format!("{:.2} {}", total, currency),
+    ));
+    info_rows.extend(compare_info_rows(total, previous_total, &currency));
+
     Page {
         title: "Cost Explorer - Monthly Cost".to_string(),
         breadcrumbs: vec![
             Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
             Breadcrumb::current("Monthly Cost"),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::raw(
-                "Period",
-                period_links(&make_path(base, "/costs/monthly"), period),
-            ),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
-        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
         content,
         subpages: vec![],
     }
     .render()
 }
 
+/// Month hub with the month's cumulative cost against the previous month's
+/// and, when set, the monthly `budget`.
+#[allow(clippy::too_many_arguments)]
 pub fn render_hub(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     month: &str,
-    total_cost: f64,
+    total_cost: Amount,
     currency: &str,
     user_count: usize,
     model_count: usize,
+    daily: &[CostRecord],
+    previous: &[CostRecord],
+    budget: Option<Amount>,
 ) -> String {
+    let period = nav.period.as_str();
+    let origin = nav.here(
+        &with_period(&make_path(base, &format!("/costs/monthly/{}", month)), period),
+        1,
+    );
+    let days = days_in_month(month);
+    let labels: Vec<String> = (1..=days).map(|d| d.to_string()).collect();
+    let mut lines = vec![
+        ChartLine {
+            label: month.to_string(),
+            values: cumulative(daily),
+        },
+        ChartLine {
+            label: "Previous month".to_string(),
+            values: cumulative(previous),
+        },
+    ];
+    if let Some(budget) = budget {
+        lines.push(ChartLine {
+            label: "Budget".to_string(),
+            values: vec![budget.to_f64(); days as usize],
+        });
+    }
+    let chart = (!daily.is_empty() || !previous.is_empty()).then(|| line_chart(&labels, &lines));
+    let mut info_rows = vec![
+        InfoRow::new("Month", month),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total_cost, currency)),
+    ];
+    if let Some(budget) = budget {
+        info_rows.push(InfoRow::new("Budget", &format!("{:.2} {}", budget, currency)));
+    }
+    let content = chart.map(|chart| {
+        view! {
+            <h2>"Cumulative Cost"</h2>
+            <div inner_html={chart}></div>
+        }
+    });
+
     Page {
         title: format!("Cost Explorer - {}", month),
         breadcrumbs: vec![
@@ -91,21 +222,27 @@ pub fn render_hub(
             ),
             Breadcrumb::current(month),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::new("Month", month),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total_cost, currency)),
-        ],
-        content: (),
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
         subpages: vec![
             Subpage::new(
                 "By User",
-                make_path(base, &format!("/costs/monthly/{}/users", month)),
+                nav.drill(
+                    &make_path(base, &format!("/costs/monthly/{}/users", month)),
+                    origin.as_deref(),
+                ),
                 user_count,
             ),
             Subpage::new(
                 "By Model",
-                make_path(base, &format!("/costs/monthly/{}/models", month)),
+                nav.drill(
+                    &make_path(base, &format!("/costs/monthly/{}/models", month)),
+                    origin.as_deref(),
+                ),
                 model_count,
             ),
         ],
@@ -113,25 +250,32 @@ pub fn render_hub(
     .render()
 }
 
+/// `can_share` adds a link for creating a share link to the page.
 pub fn render_users(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     month: &str,
     costs: &[CostByUser],
+    can_share: bool,
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
     let month_owned = month.to_string();
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(base, &format!("/costs/monthly/{}/users", month));
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/monthly/{}/users", month)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Cost by User"</h2>
@@ -149,7 +293,7 @@ pub fn render_users(
                     {page_items.iter().map(|c| {
                         let display = c.user_email.clone()
                             .unwrap_or_else(|| c.user_id.clone());
-                        let href = make_path(&base_owned, &format!("/costs/monthly/{}/users/{}", month_owned, c.user_id));
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/monthly/{}/users/{}", month_owned, c.user_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         view! {
                             <tr>
@@ -172,10 +316,23 @@ pub fn render_users(
                 "Monthly Cost",
                 with_period(&make_path(base, "/costs/monthly"), period),
             ),
-            Breadcrumb::link(month, make_path(base, &format!("/costs/monthly/{}", month))),
+            Breadcrumb::link(
+                month,
+                with_period(&make_path(base, &format!("/costs/monthly/{}", month)), period),
+            ),
             Breadcrumb::current("By User"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: if can_share {
+            vec![
+                nav.back(),
+                NavLink::new("Share", with_query(&make_path(base, "/share"), "path", &self_path)),
+            ]
+        } else {
+            vec![nav.back()]
+        },
         info_rows: vec![
             InfoRow::new("Month", month),
             InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
@@ -188,23 +345,28 @@ pub fn render_users(
 
 pub fn render_models(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     month: &str,
     costs: &[CostByModel],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
     let base_owned = base.to_string();
     let month_owned = month.to_string();
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(base, &format!("/costs/monthly/{}/models", month));
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/monthly/{}/models", month)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Cost by Model"</h2>
@@ -222,7 +384,7 @@ pub fn render_models(
                     {page_items.iter().map(|c| {
                         let display = c.model_name.clone()
                             .unwrap_or_else(|| c.model_id.clone());
-                        let href = make_path(&base_owned, &format!("/costs/monthly/{}/models/{}", month_owned, c.model_id));
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/monthly/{}/models/{}", month_owned, c.model_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         view! {
                             <tr>
@@ -245,10 +407,16 @@ pub fn render_models(
                 "Monthly Cost",
                 with_period(&make_path(base, "/costs/monthly"), period),
             ),
-            Breadcrumb::link(month, make_path(base, &format!("/costs/monthly/{}", month))),
+            Breadcrumb::link(
+                month,
+                with_period(&make_path(base, &format!("/costs/monthly/{}", month)), period),
+            ),
             Breadcrumb::current("By Model"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Month", month),
             InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
@@ -261,25 +429,26 @@ pub fn render_models(
 
 pub fn render_user_models(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     month: &str,
     user_email: &str,
     costs: &[CostByModel],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(
-        base,
-        &format!("/costs/monthly/{}/users/{}", month, user_email),
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/monthly/{}/users/{}", month, user_email)),
+        period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Models for "{user_email}</h2>
@@ -319,14 +488,23 @@ pub fn render_user_models(
                 "Monthly Cost",
                 with_period(&make_path(base, "/costs/monthly"), period),
             ),
-            Breadcrumb::link(month, make_path(base, &format!("/costs/monthly/{}", month))),
+            Breadcrumb::link(
+                month,
+                with_period(&make_path(base, &format!("/costs/monthly/{}", month)), period),
+            ),
             Breadcrumb::link(
                 "By User",
-                make_path(base, &format!("/costs/monthly/{}/users", month)),
+                with_period(
+                    &make_path(base, &format!("/costs/monthly/{}/users", month)),
+                    period,
+                ),
             ),
             Breadcrumb::current(user_email),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Month", month),
             InfoRow::new("User", user_email),
@@ -340,25 +518,26 @@ pub fn render_user_models(
 
 pub fn render_model_users(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     month: &str,
     model_name: &str,
     costs: &[CostByUser],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
-    let self_path = make_path(
-        base,
-        &format!("/costs/monthly/{}/models/{}", month, model_name),
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/costs/monthly/{}/models/{}", month, model_name)),
+        period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
 
     let content = view! {
         <h2>"Users for "{model_name}</h2>
@@ -398,14 +577,23 @@ pub fn render_model_users(
                 "Monthly Cost",
                 with_period(&make_path(base, "/costs/monthly"), period),
             ),
-            Breadcrumb::link(month, make_path(base, &format!("/costs/monthly/{}", month))),
+            Breadcrumb::link(
+                month,
+                with_period(&make_path(base, &format!("/costs/monthly/{}", month)), period),
+            ),
             Breadcrumb::link(
                 "By Model",
-                make_path(base, &format!("/costs/monthly/{}/models", month)),
+                with_period(
+                    &make_path(base, &format!("/costs/monthly/{}/models", month)),
+                    period,
+                ),
             ),
             Breadcrumb::current(model_name),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::new("Month", month),
             InfoRow::new("Model", model_name),
@@ -425,23 +613,23 @@ mod tests {
     fn render_contains_title() {
         let monthly = vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 820.50,
+            amount: Amount::from_f64(820.50),
             currency: "USD".to_string(),
         }];
-        let html = render("/", "30d", 1, &monthly);
+        let html = render("/", &"30d".into(), 1, &monthly, None, None);
         assert!(html.contains("<title>Cost Explorer - Monthly Cost</title>"));
     }
 
     #[test]
     fn render_contains_breadcrumbs() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], None, None);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
     }
 
     #[test]
     fn render_contains_period_links() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], None, None);
         assert!(html.contains("<b>Past 30 Days</b>"));
         assert!(html.contains("?period=7d"));
     }
@@ -450,10 +638,10 @@ mod tests {
     fn render_months_display_without_day() {
         let monthly = vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 820.50,
+            amount: Amount::from_f64(820.50),
             currency: "USD".to_string(),
         }];
-        let html = render("/", "30d", 1, &monthly);
+        let html = render("/", &"30d".into(), 1, &monthly, None, None);
         assert!(html.contains(">2024-01<"));
     }
 
@@ -461,35 +649,48 @@ mod tests {
     fn render_months_are_links() {
         let monthly = vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 820.50,
+            amount: Amount::from_f64(820.50),
             currency: "USD".to_string(),
         }];
-        let html = render("/", "30d", 1, &monthly);
+        let html = render("/", &"30d".into(), 1, &monthly, None, None);
         assert!(html.contains("/costs/monthly/2024-01"));
         assert!(html.contains("<a href=\"/costs/monthly/2024-01\">"));
     }
 
+    #[test]
+    fn render_shows_gross_and_credits() {
+        let monthly = vec![CostRecord {
+            date: "2024-01-01".to_string(),
+            amount: Amount::from_f64(820.50),
+            currency: "USD".to_string(),
+        }];
+        let html = render("/", &"30d".into(), 1, &monthly, None, Some(Amount::from_f64(-20.0)));
+        assert!(html.contains("840.50 USD"));
+        assert!(html.contains(r#"<a href="/costs/credits">-20.00 USD</a>"#));
+        assert!(html.contains("Net Cost"));
+    }
+
     #[test]
     fn render_empty_monthly_cost() {
-        let html = render("/", "30d", 1, &[]);
+        let html = render("/", &"30d".into(), 1, &[], None, None);
         assert!(html.contains("No cost data found for this period."));
     }
 
     #[test]
     fn render_uses_custom_base_path() {
-        let html = render("/_dashboard", "30d", 1, &[]);
+        let html = render("/_dashboard", &"30d".into(), 1, &[], None, None);
         assert!(html.contains("/_dashboard/costs/monthly"));
     }
 
     #[test]
     fn render_hub_contains_title() {
-        let html = render_hub("/", "30d", "2024-01", 820.50, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01", Amount::from_f64(820.50), "USD", 3, 2, &[], &[], None);
         assert!(html.contains("<title>Cost Explorer - 2024-01</title>"));
     }
 
     #[test]
     fn render_hub_contains_breadcrumbs() {
-        let html = render_hub("/", "30d", "2024-01", 820.50, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01", Amount::from_f64(820.50), "USD", 3, 2, &[], &[], None);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
         assert!(html.contains("2024-01"));
@@ -497,23 +698,65 @@ mod tests {
 
     #[test]
     fn render_hub_contains_subpage_links() {
-        let html = render_hub("/", "30d", "2024-01", 820.50, "USD", 3, 2);
+        let html = render_hub("/", &"30d".into(), "2024-01", Amount::from_f64(820.50), "USD", 3, 2, &[], &[], None);
         assert!(html.contains("By User"));
         assert!(html.contains("By Model"));
         assert!(html.contains("/costs/monthly/2024-01/users"));
         assert!(html.contains("/costs/monthly/2024-01/models"));
     }
 
+    fn record(date: &str, amount: f64) -> CostRecord {
+        CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn cumulative_runs_through_last_day_with_cost() {
+        let daily = vec![record("2024-02-01", 1.0), record("2024-02-03", 2.5)];
+        assert_eq!(cumulative(&daily), vec![1.0, 1.0, 3.5]);
+        assert!(cumulative(&[]).is_empty());
+        assert_eq!(days_in_month("2024-02"), 29);
+        assert_eq!(days_in_month("2024-04"), 30);
+    }
+
+    #[test]
+    fn render_hub_charts_month_against_previous_and_budget() {
+        let daily = vec![record("2024-02-01", 1.0), record("2024-02-02", 2.0)];
+        let previous = vec![record("2024-01-01", 4.0)];
+        let html = render_hub(
+            "/",
+            &"30d".into(),
+            "2024-02",
+            Amount::from_f64(3.0),
+            "USD",
+            1,
+            1,
+            &daily,
+            &previous,
+            Some(Amount::from_f64(50.0)),
+        );
+        assert!(html.contains("Cumulative Cost"));
+        assert_eq!(html.matches("<polyline").count(), 3);
+        assert!(html.contains("Previous month"));
+        assert!(html.contains("50.00 USD"));
+
+        let html = render_hub("/", &"30d".into(), "2024-02", Amount::ZERO, "USD", 0, 0, &[], &[], None);
+        assert!(!html.contains("Cumulative Cost"));
+    }
+
     #[test]
     fn render_hub_custom_base() {
-        let html = render_hub("/_dashboard", "30d", "2024-01", 50.0, "USD", 1, 1);
+        let html = render_hub("/_dashboard", &"30d".into(), "2024-01", Amount::from_f64(50.0), "USD", 1, 1, &[], &[], None);
         assert!(html.contains("/_dashboard/costs/monthly/2024-01/users"));
         assert!(html.contains("/_dashboard/costs/monthly/2024-01/models"));
     }
 
     #[test]
     fn render_users_empty() {
-        let html = render_users("/", "30d", 1, "2024-01", &[]);
+        let html = render_users("/", &"30d".into(), 1, "2024-01", &[], false);
         assert!(html.contains("No cost data found for this month."));
     }
 
@@ -522,10 +765,10 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "user-1".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 42.0,
+            amount: Amount::from_f64(42.0),
             currency: "USD".to_string(),
         }];
-        let html = render_users("/", "30d", 1, "2024-01", &costs);
+        let html = render_users("/", &"30d".into(), 1, "2024-01", &costs, false);
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("42.00 USD"));
         assert!(html.contains("/costs/monthly/2024-01/users/user-1"));
@@ -533,16 +776,24 @@ mod tests {
 
     #[test]
     fn render_users_breadcrumbs() {
-        let html = render_users("/", "30d", 1, "2024-01", &[]);
+        let html = render_users("/", &"30d".into(), 1, "2024-01", &[], false);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
         assert!(html.contains("2024-01"));
         assert!(html.contains("By User"));
     }
 
+    #[test]
+    fn render_users_share_link_only_when_allowed() {
+        let html = render_users("/", &"30d".into(), 1, "2024-01", &[], true);
+        assert!(html.contains("/share?path=/costs/monthly/2024-01/users%3Fperiod%3D30d"));
+        let html = render_users("/", &"30d".into(), 1, "2024-01", &[], false);
+        assert!(!html.contains("/share?path="));
+    }
+
     #[test]
     fn render_models_empty() {
-        let html = render_models("/", "30d", 1, "2024-01", &[]);
+        let html = render_models("/", &"30d".into(), 1, "2024-01", &[]);
         assert!(html.contains("No cost data found for this month."));
     }
 
@@ -551,10 +802,10 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 55.0,
+            amount: Amount::from_f64(55.0),
             currency: "USD".to_string(),
         }];
-        let html = render_models("/", "30d", 1, "2024-01", &costs);
+        let html = render_models("/", &"30d".into(), 1, "2024-01", &costs);
         assert!(html.contains("claude-3"));
         assert!(html.contains("55.00 USD"));
         assert!(html.contains("/costs/monthly/2024-01/models/model-1"));
@@ -562,7 +813,7 @@ mod tests {
 
     #[test]
     fn render_models_breadcrumbs() {
-        let html = render_models("/", "30d", 1, "2024-01", &[]);
+        let html = render_models("/", &"30d".into(), 1, "2024-01", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
         assert!(html.contains("2024-01"));
@@ -571,7 +822,7 @@ mod tests {
 
     #[test]
     fn render_user_models_empty() {
-        let html = render_user_models("/", "30d", 1, "2024-01", "alice@example.com", &[]);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01", "alice@example.com", &[]);
         assert!(html.contains("No cost data found."));
     }
 
@@ -580,10 +831,10 @@ mod tests {
         let costs = vec![CostByModel {
             model_id: "model-1".to_string(),
             model_name: Some("claude-3".to_string()),
-            amount: 30.0,
+            amount: Amount::from_f64(30.0),
             currency: "USD".to_string(),
         }];
-        let html = render_user_models("/", "30d", 1, "2024-01", "alice@example.com", &costs);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01", "alice@example.com", &costs);
         assert!(html.contains("claude-3"));
         assert!(html.contains("30.00 USD"));
         // Leaf page: model names are plain text, not links
@@ -592,7 +843,7 @@ mod tests {
 
     #[test]
     fn render_user_models_breadcrumbs() {
-        let html = render_user_models("/", "30d", 1, "2024-01", "alice@example.com", &[]);
+        let html = render_user_models("/", &"30d".into(), 1, "2024-01", "alice@example.com", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
         assert!(html.contains("2024-01"));
@@ -602,7 +853,7 @@ mod tests {
 
     #[test]
     fn render_model_users_empty() {
-        let html = render_model_users("/", "30d", 1, "2024-01", "claude-3", &[]);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01", "claude-3", &[]);
         assert!(html.contains("No cost data found."));
     }
 
@@ -611,10 +862,10 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "user-1".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 25.0,
+            amount: Amount::from_f64(25.0),
             currency: "USD".to_string(),
         }];
-        let html = render_model_users("/", "30d", 1, "2024-01", "claude-3", &costs);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01", "claude-3", &costs);
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("25.00 USD"));
         // Leaf page: user emails are plain text, not links
@@ -623,7 +874,7 @@ mod tests {
 
     #[test]
     fn render_model_users_breadcrumbs() {
-        let html = render_model_users("/", "30d", 1, "2024-01", "claude-3", &[]);
+        let html = render_model_users("/", &"30d".into(), 1, "2024-01", "claude-3", &[]);
         assert!(html.contains("Cost Explorer"));
         assert!(html.contains("Monthly Cost"));
         assert!(html.contains("2024-01"));
@@ -0,0 +1,98 @@
+use super::{make_path, PAGE_SIZE};
+use crate::preferences::{Preferences, PAGE_SIZES};
+use chrono_tz::TZ_VARIANTS;
+use leptos::prelude::*;
+use templates::{Breadcrumb, NavLink, Page, PERIODS};
+
+pub fn render(base: &str, prefs: &Preferences) -> String {
+    let action = make_path(base, "/preferences");
+    let period = prefs.period.clone().unwrap_or_default();
+    let page_size = prefs.page_size.map(|s| s.to_string()).unwrap_or_default();
+    let default_page_size = format!("Default ({})", PAGE_SIZE);
+    let timezone = prefs.timezone.clone().unwrap_or_default();
+
+    let content = view! {
+        <h2>"Preferences"</h2>
+        <form method="post" action={action}>
+            <p>
+                <label>"Default period "
+                    <select name="period">
+                        <option value="" selected={period.is_empty()}>"Default (Past 30 Days)"</option>
+                        {PERIODS.iter().map(|(key, label)| {
+                            let selected = period == *key;
+                            view! { <option value={*key} selected={selected}>{*label}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Rows per page "
+                    <select name="page_size">
+                        <option value="" selected={page_size.is_empty()}>{default_page_size}</option>
+                        {PAGE_SIZES.iter().map(|size| {
+                            let value = size.to_string();
+                            let selected = page_size == value;
+                            view! { <option value={value.clone()} selected={selected}>{value}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Time zone "
+                    <select name="timezone">
+                        <option value="" selected={timezone.is_empty()}>"Default"</option>
+                        {TZ_VARIANTS.iter().map(|tz| {
+                            let name = tz.name();
+                            let selected = timezone == name;
+                            view! { <option value={name} selected={selected}>{name}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <button type="submit">"Save"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Preferences".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Preferences"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_marks_saved_choices() {
+        let prefs = Preferences {
+            period: Some("7d".to_string()),
+            page_size: Some(100),
+            timezone: Some("Europe/Berlin".to_string()),
+        };
+        let html = render("/_dashboard", &prefs);
+        assert!(html.contains("<title>Cost Explorer - Preferences</title>"));
+        assert!(html.contains(r#"action="/_dashboard/preferences""#));
+        assert!(html.contains(r#"<option value="7d" selected"#));
+        assert!(html.contains(r#"<option value="100" selected"#));
+        assert!(html.contains(r#"<option value="Europe/Berlin" selected"#));
+    }
+
+    #[test]
+    fn render_defaults() {
+        let html = render("/", &Preferences::default());
+        assert!(html.contains(r#"<option value="" selected"#));
+        assert!(html.contains("Default (50)"));
+    }
+}
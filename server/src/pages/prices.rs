@@ -0,0 +1,305 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use super::{change_cells, make_path, with_period, NavContext};
+use common::{Amount, CostByModel, ModelPrice};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{html_escape, period_links, Breadcrumb, InfoRow, NavLink, Page};
+
+/// Admin page listing model prices, with forms to add and delete them.
+pub fn render(base: &str, prices: &[ModelPrice], models: &[(String, String)]) -> String {
+    let action = make_path(base, "/prices");
+    let delete_action = make_path(base, "/prices/delete");
+    let model_count = {
+        let mut ids: Vec<&str> = prices.iter().map(|p| p.model_id.as_str()).collect();
+        ids.dedup();
+        ids.len()
+    };
+    let rows: Vec<_> = prices
+        .iter()
+        .map(|price| {
+            let href = make_path(base, &format!("/models/{}", price.model_id));
+            let label = price
+                .model_name
+                .clone()
+                .unwrap_or_else(|| price.model_id.clone());
+            (
+                href,
+                label,
+                price.model_id.clone(),
+                price.effective_from.format("%Y-%m-%d").to_string(),
+                format!("{:.6}", price.input_per_1k),
+                format!("{:.6}", price.output_per_1k),
+            )
+        })
+        .collect();
+    let options: Vec<_> = models.to_vec();
+
+    let content = view! {
+        <h2>"Model Prices"</h2>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No model prices set."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="model_prices">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Effective From"</th>
+                        <th>"Input / 1K Tokens"</th>
+                        <th>"Output / 1K Tokens"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, model_id, from, input, output)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{from.clone()}</td>
+                            <td>{input}</td>
+                            <td>{output}</td>
+                            <td>
+                                <form method="post" action={delete_action.clone()}>
+                                    <input type="hidden" name="model_id" value={model_id}/>
+                                    <input type="hidden" name="effective_from" value={from}/>
+                                    <button type="submit">"Delete"</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Set Price"</h3>
+        <form method="post" action={action}>
+            <p>
+                <label>"Model "
+                    <select name="model_id">
+                        {options.into_iter().map(|(id, name)| view! {
+                            <option value={id}>{name}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Effective from "
+                    <input type="date" name="effective_from" required/>
+                </label>
+            </p>
+            <p>
+                <label>"Input per 1K tokens (USD) "
+                    <input type="number" name="input_per_1k" min="0" step="0.000001" required/>
+                </label>
+            </p>
+            <p>
+                <label>"Output per 1K tokens (USD) "
+                    <input type="number" name="output_per_1k" min="0" step="0.000001" required/>
+                </label>
+            </p>
+            <button type="submit">"Save"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Model Prices".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Model Prices"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![
+            InfoRow::new("Prices", &prices.len().to_string()),
+            InfoRow::new("Priced Models", &model_count.to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// Estimated cost from token usage next to the cost CE reports, per model.
+/// Models without a price show no estimate; a large variance on a priced
+/// model points at a wrong price or traffic tagged with the wrong model.
+/// `edit_prices` links to the admin price editor.
+pub fn render_variance(
+    base: &str,
+    nav: &NavContext,
+    actual: &[CostByModel],
+    estimates: &HashMap<String, Amount>,
+    edit_prices: bool,
+) -> String {
+    let period = nav.period.as_str();
+    let currency = actual
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let mut rows: Vec<(String, Option<String>, Amount, Option<Amount>)> = actual
+        .iter()
+        .map(|c| {
+            (
+                c.model_id.clone(),
+                c.model_name.clone(),
+                c.amount,
+                estimates.get(&c.model_id).copied(),
+            )
+        })
+        .collect();
+    for (model_id, estimate) in estimates {
+        if !actual.iter().any(|c| &c.model_id == model_id) {
+            rows.push((model_id.clone(), None, Amount::ZERO, Some(*estimate)));
+        }
+    }
+    rows.sort_by_key(|(_, _, amount, estimate)| {
+        Reverse(estimate.map(|e| (*amount - e).max(e - *amount)))
+    });
+    let total_actual: Amount = actual.iter().map(|c| c.amount).sum();
+    let total_estimated: Amount = estimates.values().copied().sum();
+    let unpriced = rows.iter().filter(|r| r.3.is_none()).count();
+    let empty = rows.is_empty();
+    let origin = nav.here(
+        &with_period(&make_path(base, "/costs/estimates"), period),
+        1,
+    );
+    let rows: Vec<_> = rows
+        .into_iter()
+        .map(|(model_id, model_name, amount, estimate)| {
+            let href = nav.drill(
+                &make_path(base, &format!("/models/{}", model_id)),
+                origin.as_deref(),
+            );
+            let label = model_name.unwrap_or(model_id);
+            let cells = match estimate {
+                Some(estimate) => change_cells(amount, estimate, &currency),
+                None => ["-".to_string(), "-".to_string(), "-".to_string()],
+            };
+            (href, label, format!("{:.2} {}", amount, currency), cells)
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Estimated vs Actual Cost"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_estimates">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Actual"</th>
+                        <th>"Estimated"</th>
+                        <th>"Variance"</th>
+                        <th>"Variance %"</th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, actual, [estimated, variance, percent])| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{actual}</td>
+                            <td>{estimated}</td>
+                            <td>{variance}</td>
+                            <td>{percent}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(&make_path(base, "/costs/estimates"), period),
+        ),
+        InfoRow::new("Actual Cost", &format!("{:.2} {}", total_actual, currency)),
+        InfoRow::new(
+            "Estimated Cost",
+            &format!("{:.2} {}", total_estimated, currency),
+        ),
+        InfoRow::new("Models Without Price", &unpriced.to_string()),
+    ];
+    if edit_prices {
+        info_rows.push(InfoRow::raw(
+            "Prices",
+            format!(
+                r#"<a href="{}">Edit model prices</a>"#,
+                html_escape(&make_path(base, "/prices"))
+            ),
+        ));
+    }
+
+    Page {
+        title: "Cost Explorer - Estimated vs Actual".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Estimated vs Actual"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn cost(model_id: &str, name: Option<&str>, amount: f64) -> CostByModel {
+        CostByModel {
+            model_id: model_id.to_string(),
+            model_name: name.map(str::to_string),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_lists_prices() {
+        let prices = vec![ModelPrice {
+            model_id: "m1".to_string(),
+            model_name: Some("claude".to_string()),
+            effective_from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+            input_per_1k: Amount::from_f64(0.003),
+            output_per_1k: Amount::from_f64(0.015),
+        }];
+        let models = vec![("m2".to_string(), "llama".to_string())];
+        let html = render("/_dashboard", &prices, &models);
+        assert!(html.contains("<title>Cost Explorer - Model Prices</title>"));
+        assert!(html.contains(r#"href="/_dashboard/models/m1""#));
+        assert!(html.contains("2025-01-01"));
+        assert!(html.contains("0.015000"));
+        assert!(html.contains(r#"action="/_dashboard/prices/delete""#));
+        assert!(html.contains(r#"<option value="m2">llama</option>"#));
+    }
+
+    #[test]
+    fn render_variance_orders_by_variance() {
+        let actual = vec![
+            cost("m1", Some("claude"), 10.0),
+            cost("m2", Some("llama"), 5.0),
+            cost("m3", None, 1.0),
+        ];
+        let estimates: HashMap<String, Amount> = [
+            ("m1".to_string(), Amount::from_f64(9.5)),
+            ("m2".to_string(), Amount::from_f64(2.5)),
+        ]
+        .into_iter()
+        .collect();
+        let html = render_variance("/", &"30d".into(), &actual, &estimates, true);
+        assert!(html.contains("<title>Cost Explorer - Estimated vs Actual</title>"));
+        assert!(html.find("llama").unwrap() < html.find("claude").unwrap());
+        assert!(html.contains("+2.50 USD"));
+        assert!(html.contains("+100.0%"));
+        assert!(html.contains("12.00 USD"));
+        assert!(html.contains(r#"href="/prices""#));
+    }
+}
@@ -0,0 +1,161 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use super::{make_path, share, with_period, NavContext};
+use common::{Amount, CostByRegion};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// Total per region, highest first.
+pub fn totals_by_region(costs: &[CostByRegion]) -> Vec<(String, Amount)> {
+    let mut totals: BTreeMap<&str, Amount> = BTreeMap::new();
+    for c in costs {
+        *totals.entry(c.region.as_str()).or_default() += c.amount;
+    }
+    let mut totals: Vec<_> = totals
+        .into_iter()
+        .map(|(region, amount)| (region.to_string(), amount))
+        .collect();
+    totals.sort_by_key(|(_, amount)| Reverse(*amount));
+    totals
+}
+
+pub fn render(base: &str, nav: &NavContext, costs: &[CostByRegion]) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let regions = totals_by_region(costs);
+    let empty = costs.is_empty();
+    let self_path = with_period(&make_path(base, "/costs/regions"), period);
+    let origin = nav.here(&self_path, 1);
+    let base_owned = base.to_string();
+    let region_currency = currency.clone();
+
+    let content = view! {
+        <h2>"Cost by Region"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="cost_by_region">
+                    <tr>
+                        <th>"Region"</th>
+                        <th>"Cost"</th>
+                        <th>"Share"</th>
+                    </tr>
+                    {regions.into_iter().map(|(region, amount)| {
+                        let cost_str = format!("{:.2} {}", amount, region_currency);
+                        let share_str = share(amount, total);
+                        view! {
+                            <tr>
+                                <td>{region}</td>
+                                <td>{cost_str}</td>
+                                <td>{share_str}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+                <h2>"By Model"</h2>
+                <table class="data-table" data-export-name="model_cost_by_region">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Region"</th>
+                        <th>"Cost"</th>
+                    </tr>
+                    {costs.iter().map(|c| {
+                        let display = c.model_name.clone()
+                            .unwrap_or_else(|| c.model_id.clone());
+                        let href = nav.drill(&make_path(&base_owned, &format!("/models/{}", c.model_id)), origin.as_deref());
+                        let region = c.region.clone();
+                        let cost_str = format!("{:.2} {}", c.amount, c.currency);
+                        view! {
+                            <tr>
+                                <td><a href={href}>{display}</a></td>
+                                <td>{region}</td>
+                                <td>{cost_str}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Regions".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Regions"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/regions"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cost(region: &str, model: &str, micros: i64) -> CostByRegion {
+        CostByRegion {
+            region: region.to_string(),
+            model_id: model.to_string(),
+            model_name: Some(format!("{} name", model)),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn totals_by_region_sums_models() {
+        let costs = vec![
+            cost("eu-central-1", "m1", 1_000_000),
+            cost("us-east-1", "m1", 1_500_000),
+            cost("eu-central-1", "m2", 1_000_000),
+        ];
+        let totals = totals_by_region(&costs);
+        assert_eq!(
+            totals[0],
+            ("eu-central-1".to_string(), Amount::from_micros(2_000_000))
+        );
+        assert_eq!(totals[1].0, "us-east-1");
+    }
+
+    #[test]
+    fn render_lists_regions_and_models() {
+        let costs = vec![
+            cost("us-east-1", "m1", 3_000_000),
+            cost("eu-central-1", "m1", 1_000_000),
+        ];
+        let html = render("/", &"30d".into(), &costs);
+        assert!(html.contains("<title>Cost Explorer - Regions</title>"));
+        assert!(html.contains("eu-central-1"));
+        assert!(html.contains("75.0%"));
+        assert!(html.contains("m1 name"));
+        assert!(html.contains("/models/m1?period=30d"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), &[]);
+        assert!(html.contains("No cost data found for this period."));
+    }
+}
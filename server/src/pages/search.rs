@@ -0,0 +1,96 @@
+use super::{make_path, with_period, NavContext};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, Page};
+
+/// `(id, label)` matches linking to `{base}{prefix}/{id}`.
+fn match_table(
+    nav: &NavContext,
+    base: &str,
+    prefix: &'static str,
+    heading: &'static str,
+    matches: &[(String, String)],
+) -> impl IntoView {
+    let rows = matches
+        .iter()
+        .map(|(id, label)| {
+            let href = nav.drill(&make_path(base, &format!("{}/{}", prefix, id)), None);
+            (href, label.clone(), id.clone())
+        })
+        .collect::<Vec<_>>();
+    view! {
+        <h2>{heading}</h2>
+        {if rows.is_empty() {
+            Either::Left(view! { <p>"No matches."</p> })
+        } else {
+            Either::Right(view! {
+                <table class="data-table">
+                    <tr>
+                        <th>"Name"</th>
+                        <th>"ID"</th>
+                    </tr>
+                    {rows.into_iter().map(|(href, label, id)| view! {
+                        <tr>
+                            <td><a href={href}>{label}</a></td>
+                            <td>{id}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    }
+}
+
+/// `users` is `None` when the viewer may not see other users.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    query: &str,
+    users: Option<&[(String, String)]>,
+    models: Option<&[(String, String)]>,
+) -> String {
+    let period = nav.period.as_str();
+    let content = view! {
+        {users.map(|users| match_table(nav, base, "/users", "Users", users))}
+        {models.map(|models| match_table(nav, base, "/models", "Models", models))}
+    };
+
+    Page {
+        title: "Cost Explorer - Search".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Search"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![InfoRow::new("Query", query)],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_links_matches_to_hubs() {
+        let users = vec![("u1".to_string(), "alice@example.com".to_string())];
+        let models = vec![("m1".to_string(), "claude-3-sonnet".to_string())];
+        let html = render("/", &"7d".into(), "a", Some(&users), Some(&models));
+        assert!(html.contains("<title>Cost Explorer - Search</title>"));
+        assert!(html.contains(r#"href="/users/u1?period=7d""#));
+        assert!(html.contains(r#"href="/models/m1?period=7d""#));
+        assert!(html.contains("alice@example.com"));
+    }
+
+    #[test]
+    fn render_hides_users_without_access() {
+        let html = render("/", &"30d".into(), "x", None, Some(&[]));
+        assert!(!html.contains("<h2>Users</h2>"));
+        assert!(html.contains("No matches."));
+    }
+}
@@ -0,0 +1,226 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use super::{make_path, share, with_period, NavContext};
+use common::{Amount, ServiceCostRow};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, Breadcrumb, InfoRow, Page};
+
+/// Total per service, highest first.
+fn totals_by_service(rows: &[ServiceCostRow]) -> Vec<(String, Amount)> {
+    let mut totals: BTreeMap<&str, Amount> = BTreeMap::new();
+    for r in rows {
+        *totals.entry(r.service.as_str()).or_default() += r.amount;
+    }
+    let mut totals: Vec<_> = totals
+        .into_iter()
+        .map(|(service, amount)| (service.to_string(), amount))
+        .collect();
+    totals.sort_by_key(|(_, amount)| Reverse(*amount));
+    totals
+}
+
+/// Total per day, in date order.
+fn totals_by_date(rows: &[ServiceCostRow]) -> Vec<(String, Amount)> {
+    let mut totals: BTreeMap<String, Amount> = BTreeMap::new();
+    for r in rows {
+        *totals
+            .entry(r.date.format("%Y-%m-%d").to_string())
+            .or_default() += r.amount;
+    }
+    totals.into_iter().collect()
+}
+
+fn service_table(
+    rows: &[ServiceCostRow],
+    currency: &str,
+    empty_message: &'static str,
+) -> impl IntoView {
+    let services = totals_by_service(rows);
+    let total: Amount = services.iter().map(|(_, amount)| *amount).sum();
+    let currency = currency.to_string();
+    if services.is_empty() {
+        Either::Left(view! {
+            <p>{empty_message}</p>
+        })
+    } else {
+        Either::Right(view! {
+            <table class="data-table" data-export-name="cost_by_service">
+                <tr>
+                    <th>"Service"</th>
+                    <th>"Cost"</th>
+                    <th>"Share"</th>
+                </tr>
+                {services.into_iter().map(|(service, amount)| {
+                    let cost_str = format!("{:.2} {}", amount, currency);
+                    let share_str = share(amount, total);
+                    view! {
+                        <tr>
+                            <td>{service}</td>
+                            <td>{cost_str}</td>
+                            <td>{share_str}</td>
+                        </tr>
+                    }
+                }).collect::<Vec<_>>()}
+            </table>
+        })
+    }
+}
+
+pub fn render_index(base: &str, nav: &NavContext, rows: &[ServiceCostRow]) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = rows.iter().map(|r| r.amount).sum();
+    let currency = rows
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let dates = totals_by_date(rows);
+    let self_path = with_period(&make_path(base, "/costs/services"), period);
+    let origin = nav.here(&self_path, 1);
+    let base_owned = base.to_string();
+    let date_currency = currency.clone();
+
+    let content = view! {
+        <h2>"Cost by Service"</h2>
+        {service_table(rows, &currency, "No cost data found for this period.")}
+        {(!dates.is_empty()).then(|| view! {
+            <h2>"By Date"</h2>
+            <table class="data-table" data-export-name="service_cost_by_date">
+                <tr>
+                    <th>"Date"</th>
+                    <th>"Cost"</th>
+                </tr>
+                {dates.into_iter().map(|(date, amount)| {
+                    let href = nav.drill(&make_path(&base_owned, &format!("/costs/services/{}", date)), origin.as_deref());
+                    let cost_str = format!("{:.2} {}", amount, date_currency);
+                    view! {
+                        <tr>
+                            <td><a href={href}>{date}</a></td>
+                            <td>{cost_str}</td>
+                        </tr>
+                    }
+                }).collect::<Vec<_>>()}
+            </table>
+        })}
+    };
+
+    Page {
+        title: "Cost Explorer - Services".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Services"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/services"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_date(base: &str, nav: &NavContext, date: &str, rows: &[ServiceCostRow]) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = rows.iter().map(|r| r.amount).sum();
+    let currency = rows
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+
+    let content = view! {
+        <h2>"Cost by Service"</h2>
+        {service_table(rows, &currency, "No cost data found for this date.")}
+    };
+
+    Page {
+        title: format!("Cost Explorer - Services - {}", date),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link(
+                "Services",
+                with_period(&make_path(base, "/costs/services"), period),
+            ),
+            Breadcrumb::current(date),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::new("Date", date),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn row(date: &str, service: &str, micros: i64) -> ServiceCostRow {
+        ServiceCostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            service: service.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn totals_by_service_orders_by_amount() {
+        let rows = vec![
+            row("2024-01-01", "Amazon S3", 1_000_000),
+            row("2024-01-01", "Amazon Bedrock", 2_000_000),
+            row("2024-01-02", "Amazon S3", 1_500_000),
+        ];
+        let totals = totals_by_service(&rows);
+        assert_eq!(
+            totals[0],
+            ("Amazon S3".to_string(), Amount::from_micros(2_500_000))
+        );
+        assert_eq!(totals[1].0, "Amazon Bedrock");
+    }
+
+    #[test]
+    fn render_index_lists_services_and_dates() {
+        let rows = vec![
+            row("2024-01-01", "Amazon Bedrock", 3_000_000),
+            row("2024-01-02", "AmazonCloudWatch", 1_000_000),
+        ];
+        let html = render_index("/", &"7d".into(), &rows);
+        assert!(html.contains("<title>Cost Explorer - Services</title>"));
+        assert!(html.contains("Amazon Bedrock"));
+        assert!(html.contains("75.0%"));
+        assert!(html.contains("/costs/services/2024-01-02?period=7d"));
+        assert!(html.contains("4.00 USD"));
+    }
+
+    #[test]
+    fn render_index_empty() {
+        let html = render_index("/", &"30d".into(), &[]);
+        assert!(html.contains("No cost data found for this period."));
+        assert!(!html.contains("By Date"));
+    }
+
+    #[test]
+    fn render_date_contains_breadcrumbs() {
+        let rows = vec![row("2024-01-01", "Amazon Bedrock", 3_000_000)];
+        let html = render_date("/", &"30d".into(), "2024-01-01", &rows);
+        assert!(html.contains("Cost Explorer - Services - 2024-01-01"));
+        assert!(html.contains("/costs/services?period=30d"));
+        assert!(html.contains("100.0%"));
+    }
+}
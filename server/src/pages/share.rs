@@ -0,0 +1,116 @@
+use super::{make_path, with_period, NavContext};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, Page};
+
+/// Days a new share link is valid for unless the admin picks otherwise.
+const DEFAULT_DAYS: i64 = 7;
+
+/// A created share link: its URL and when it expires.
+pub struct ShareLink {
+    pub url: String,
+    pub expires: String,
+}
+
+/// Admin page creating a link that opens the report at `path` without
+/// logging in, for up to `max_days` days. `enabled` is false without a
+/// `share_secret`; `link` is the one just created.
+pub fn render(
+    base: &str,
+    nav: &NavContext,
+    path: &str,
+    max_days: i64,
+    enabled: bool,
+    link: Option<ShareLink>,
+) -> String {
+    let period = nav.period.as_str();
+    let action = with_period(&make_path(base, "/share"), period);
+    let path = path.to_string();
+    let days = DEFAULT_DAYS.min(max_days).to_string();
+
+    let content = view! {
+        <h2>"Share a Report"</h2>
+        {link.map(|link| view! {
+            <p>"Anyone with this link can open the report, read-only, until " {link.expires} ":"</p>
+            <p><input type="text" size="100" value={link.url} readonly/></p>
+        })}
+        {if enabled {
+            Either::Left(view! {
+                <p>"Share links open one page, with the period and metric in its path, without logging in. Pages linked from it still ask for a login."</p>
+                <form method="post" action={action}>
+                    <p>
+                        <label>"Report path "
+                            <input type="text" name="path" size="80" value={path} required/>
+                        </label>
+                    </p>
+                    <p>
+                        <label>"Valid for "
+                            <input type="number" name="days" min="1" max={max_days.to_string()} value={days} required/>
+                            " days"
+                        </label>
+                    </p>
+                    <button type="submit">"Create Link"</button>
+                </form>
+            })
+        } else {
+            Either::Right(view! {
+                <p>"Set share_secret in the server config to create share links."</p>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Share".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Share"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_prefills_the_form() {
+        let html = render(
+            "/",
+            &"30d".into(),
+            "/costs/monthly/2025-02/users?period=30d",
+            30,
+            true,
+            None,
+        );
+        assert!(html.contains(r#"action="/share?period=30d""#));
+        assert!(html.contains(r#"value="/costs/monthly/2025-02/users?period=30d""#));
+        assert!(html.contains(r#"max="30""#));
+        assert!(!html.contains("Anyone with this link"));
+    }
+
+    #[test]
+    fn render_shows_created_link() {
+        let link = ShareLink {
+            url: "https://costs.example.com/costs/monthly/2025-02/users?share=1.abc".to_string(),
+            expires: "2025-03-08 12:00 UTC".to_string(),
+        };
+        let html = render("/", &"30d".into(), "", 30, true, Some(link));
+        assert!(html.contains("until 2025-03-08 12:00 UTC"));
+        assert!(html.contains("https://costs.example.com/costs/monthly/2025-02/users?share=1.abc"));
+    }
+
+    #[test]
+    fn render_explains_missing_secret() {
+        let html = render("/", &"30d".into(), "", 30, false, None);
+        assert!(html.contains("Set share_secret"));
+        assert!(!html.contains("Create Link"));
+    }
+}
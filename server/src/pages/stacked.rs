@@ -0,0 +1,201 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+
+use super::{make_path, with_period, NavContext};
+use common::{Amount, ModelCostRow};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{period_links, stacked_bar_chart, Breadcrumb, InfoRow, Page, StackedBar};
+
+/// Models charted on their own; the rest are summed into "Other".
+const TOP_MODELS: usize = 7;
+
+/// Date × model matrix, columns ordered by total cost.
+struct Pivot {
+    columns: Vec<String>,
+    /// Per date, one amount per column.
+    rows: Vec<(String, Vec<Amount>)>,
+}
+
+fn pivot(rows: &[ModelCostRow]) -> Pivot {
+    let mut totals: HashMap<&str, (Amount, &str)> = HashMap::new();
+    for r in rows {
+        let label = r.model_name.as_deref().unwrap_or(&r.model_id);
+        totals.entry(&r.model_id).or_insert((Amount::ZERO, label)).0 += r.amount;
+    }
+    let mut models: Vec<_> = totals.into_iter().collect();
+    models.sort_by_key(|(id, (amount, _))| (Reverse(*amount), *id));
+    let column: HashMap<&str, usize> = models
+        .iter()
+        .enumerate()
+        .map(|(i, (id, _))| (*id, i.min(TOP_MODELS)))
+        .collect();
+    let mut columns: Vec<String> = models
+        .iter()
+        .take(TOP_MODELS)
+        .map(|(_, (_, label))| label.to_string())
+        .collect();
+    if models.len() > TOP_MODELS {
+        columns.push("Other".to_string());
+    }
+    let mut by_date: BTreeMap<String, Vec<Amount>> = BTreeMap::new();
+    for r in rows {
+        let amounts = by_date
+            .entry(r.date.format("%Y-%m-%d").to_string())
+            .or_insert_with(|| vec![Amount::ZERO; columns.len()]);
+        amounts[column[r.model_id.as_str()]] += r.amount;
+    }
+    Pivot {
+        columns,
+        rows: by_date.into_iter().collect(),
+    }
+}
+
+pub fn render(base: &str, nav: &NavContext, rows: &[ModelCostRow]) -> String {
+    let period = nav.period.as_str();
+    let total: Amount = rows.iter().map(|r| r.amount).sum();
+    let currency = rows
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let pivot = pivot(rows);
+    let self_path = with_period(&make_path(base, "/costs/daily/stacked"), period);
+    let origin = nav.here(&self_path, 1);
+
+    let bars: Vec<StackedBar> = pivot
+        .rows
+        .iter()
+        .map(|(date, amounts)| StackedBar {
+            label: date.clone(),
+            href: nav.drill(
+                &make_path(base, &format!("/costs/daily/{}/models", date)),
+                origin.as_deref(),
+            ),
+            values: amounts.iter().map(|a| a.to_f64()).collect(),
+        })
+        .collect();
+    let chart = stacked_bar_chart(&pivot.columns, &bars);
+    let table_rows: Vec<_> = pivot
+        .rows
+        .iter()
+        .map(|(date, amounts)| {
+            let href = nav.drill(
+                &make_path(base, &format!("/costs/daily/{}", date)),
+                origin.as_deref(),
+            );
+            let cells: Vec<String> = amounts.iter().map(|a| format!("{:.2}", a)).collect();
+            let day_total: Amount = amounts.iter().sum();
+            (href, date.clone(), cells, format!("{:.2}", day_total))
+        })
+        .collect();
+    let columns = pivot.columns.clone();
+
+    let content = view! {
+        <h2>"Daily Cost by Model"</h2>
+        {if table_rows.is_empty() {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <div inner_html={chart}></div>
+                <table class="data-table" data-export-name="daily_cost_by_model">
+                    <tr>
+                        <th>"Date"</th>
+                        {columns.into_iter().map(|c| view! { <th>{c}</th> }).collect::<Vec<_>>()}
+                        <th>"Total"</th>
+                    </tr>
+                    {table_rows.into_iter().map(|(href, date, cells, day_total)| view! {
+                        <tr>
+                            <td><a href={href}>{date}</a></td>
+                            {cells.into_iter().map(|c| view! { <td>{c}</td> }).collect::<Vec<_>>()}
+                            <td>{day_total}</td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Daily Cost by Model".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link(
+                "Daily Cost",
+                with_period(&make_path(base, "/costs/daily"), period),
+            ),
+            Breadcrumb::current("By Model"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/daily/stacked"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn row(date: &str, model: &str, micros: i64) -> ModelCostRow {
+        ModelCostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            model_id: model.to_string(),
+            model_name: Some(format!("{}-name", model)),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn pivot_orders_columns_and_groups_other() {
+        let mut rows: Vec<ModelCostRow> = (0..9)
+            .map(|i| row("2024-01-01", &format!("m{}", i), (i + 1) * 1_000_000))
+            .collect();
+        rows.push(row("2024-01-02", "m8", 2_000_000));
+        let pivot = pivot(&rows);
+        assert_eq!(pivot.columns.len(), TOP_MODELS + 1);
+        assert_eq!(pivot.columns[0], "m8-name");
+        assert_eq!(pivot.columns[TOP_MODELS], "Other");
+        assert_eq!(pivot.rows.len(), 2);
+        let (date, amounts) = &pivot.rows[0];
+        assert_eq!(date, "2024-01-01");
+        // m0 and m1 fall outside the top seven.
+        assert_eq!(amounts[TOP_MODELS], Amount::from_micros(3_000_000));
+        assert_eq!(pivot.rows[1].1[0], Amount::from_micros(2_000_000));
+    }
+
+    #[test]
+    fn render_shows_chart_and_pivot() {
+        let rows = vec![
+            row("2024-01-01", "m1", 3_000_000),
+            row("2024-01-01", "m2", 1_000_000),
+            row("2024-01-02", "m2", 2_000_000),
+        ];
+        let html = render("/", &"7d".into(), &rows);
+        assert!(html.contains("<title>Cost Explorer - Daily Cost by Model</title>"));
+        assert!(html.contains("<svg class=\"chart\""));
+        assert!(html.contains("<th>m1-name</th>"));
+        assert!(html.contains("/costs/daily/2024-01-02/models?period=7d"));
+        assert!(html.contains("6.00 USD"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), &[]);
+        assert!(html.contains("No cost data found for this period."));
+        assert!(!html.contains("<svg"));
+    }
+}
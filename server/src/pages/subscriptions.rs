@@ -0,0 +1,141 @@
+use super::make_path;
+use common::{ReportKind, ReportSchedule, ReportSubscription};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{Breadcrumb, InfoRow, NavLink, Page};
+
+/// Admin page listing scheduled report subscriptions, with forms to add and
+/// delete them.
+pub fn render(base: &str, subscriptions: &[ReportSubscription]) -> String {
+    let action = make_path(base, "/subscriptions");
+    let delete_action = make_path(base, "/subscriptions/delete");
+    let rows: Vec<_> = subscriptions
+        .iter()
+        .map(|s| {
+            (
+                s.id.to_string(),
+                s.recipient.clone(),
+                s.kind.label(),
+                s.schedule.label(),
+                s.last_sent.map_or_else(
+                    || "Never".to_string(),
+                    |start| format!("Period from {}", start),
+                ),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>"Report Subscriptions"</h2>
+        <p>
+            "The batch daemon emails each report once its period is over, using the [mail] "
+            "config. Each send covers the last whole day, week or month of its schedule."
+        </p>
+        {if rows.is_empty() {
+            Either::Left(view! {
+                <p>"No report subscriptions set."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="report_subscriptions">
+                    <tr>
+                        <th>"Recipient"</th>
+                        <th>"Report"</th>
+                        <th>"Schedule"</th>
+                        <th>"Last Sent"</th>
+                        <th></th>
+                    </tr>
+                    {rows.into_iter().map(|(id, recipient, report, schedule, last_sent)| view! {
+                        <tr>
+                            <td>{recipient}</td>
+                            <td>{report}</td>
+                            <td>{schedule}</td>
+                            <td>{last_sent}</td>
+                            <td>
+                                <form method="post" action={delete_action.clone()}>
+                                    <input type="hidden" name="id" value={id}/>
+                                    <button type="submit">"Delete"</button>
+                                </form>
+                            </td>
+                        </tr>
+                    }).collect::<Vec<_>>()}
+                </table>
+            })
+        }}
+        <h3>"Add Subscription"</h3>
+        <form method="post" action={action}>
+            <p>
+                <label>"Recipient "
+                    <input type="email" name="recipient" placeholder="finance@example.com" required/>
+                </label>
+            </p>
+            <p>
+                <label>"Report "
+                    <select name="report">
+                        {ReportKind::ALL.into_iter().map(|kind| view! {
+                            <option value={kind.as_str()}>{kind.label()}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <p>
+                <label>"Schedule "
+                    <select name="schedule">
+                        {ReportSchedule::ALL.into_iter().map(|schedule| view! {
+                            <option value={schedule.as_str()}>{schedule.label()}</option>
+                        }).collect::<Vec<_>>()}
+                    </select>
+                </label>
+            </p>
+            <button type="submit">"Add"</button>
+        </form>
+    };
+
+    Page {
+        title: "Cost Explorer - Report Subscriptions".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", make_path(base, "")),
+            Breadcrumb::current("Report Subscriptions"),
+        ],
+        search: Some(make_path(base, "/search")),
+        source_filter: None,
+        error: None,
+        nav_links: vec![NavLink::back()],
+        info_rows: vec![InfoRow::new(
+            "Subscriptions",
+            &subscriptions.len().to_string(),
+        )],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn render_lists_subscriptions_and_choices() {
+        let subscriptions = vec![ReportSubscription {
+            id: 7,
+            recipient: "finance@example.com".to_string(),
+            kind: ReportKind::MonthlyStatement,
+            schedule: ReportSchedule::Monthly,
+            last_sent: NaiveDate::from_ymd_opt(2025, 2, 1),
+        }];
+        let html = render("/_dashboard", &subscriptions);
+        assert!(html.contains("<title>Cost Explorer - Report Subscriptions</title>"));
+        assert!(html.contains("<td>finance@example.com</td>"));
+        assert!(html.contains("<td>Monthly statement</td>"));
+        assert!(html.contains("Period from 2025-02-01"));
+        assert!(html.contains(r#"<input type="hidden" name="id" value="7""#));
+        assert!(html.contains(r#"action="/_dashboard/subscriptions/delete""#));
+        assert!(html.contains(r#"<option value="top_spenders">Weekly top spenders</option>"#));
+        assert!(html.contains(r#"<option value="weekly">Every Monday</option>"#));
+
+        let html = render("/", &[]);
+        assert!(html.contains("No report subscriptions set."));
+    }
+}
@@ -1,55 +1,81 @@
-use super::{make_path, paginate, with_period, PAGE_SIZE};
-use common::{CostByUser, CostRecord, UserInfo};
+use std::collections::HashMap;
+
+use super::{
+    change_cells, change_percent, charged_info_row, compare_info_rows, compare_links, filter_links,
+    make_path, paginate, share, with_compare, with_period, with_sort, IndexFilter, NavContext,
+};
+use common::{
+    Amount, ApiKeyInfo, CostByApiKey, CostByUser, CostRecord, InferenceProfileInfo, SpendLimit,
+    UserInfo,
+};
 use leptos::either::Either;
 use leptos::prelude::*;
-use templates::{pagination_nav, period_links, Breadcrumb, InfoRow, NavLink, Page, Subpage};
+use templates::{html_escape, pagination_nav, period_links, Breadcrumb, InfoRow, Page, Subpage};
 
-pub fn render_index(
-    base: &str,
-    period: &str,
-    page: usize,
+/// `?sort=` names for [`render_index`] columns.
+pub const INDEX_SORT: &[&str] = &[
+    "email", "cost", "api_keys", "profiles", "previous", "change", "change_pct", "charged",
+];
+
+/// A row of the users index.
+pub struct IndexRow {
+    pub user_id: String,
+    pub display: String,
+    pub cost: Amount,
+    pub previous: Amount,
+    pub currency: String,
+    /// `None` for cost entries without a matching account.
+    active: Option<bool>,
+    pub api_keys: String,
+    pub profiles: i64,
+    /// Cost with the markup added, when one is configured.
+    pub charged: Option<Amount>,
+}
+
+/// Every row of the users index that passes `filter`, in `sort` order.
+/// `charged` is the charged cost by user id, when a markup is configured.
+pub fn index_rows(
     users: &[UserInfo],
     costs: &[CostByUser],
+    previous: Option<&[CostByUser]>,
     sort: Option<usize>,
     order: &str,
-) -> String {
-    let users = users.to_vec();
-    let costs = costs.to_vec();
-    let empty = users.is_empty() && costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    filter: &IndexFilter,
+    charged: Option<&HashMap<String, Amount>>,
+) -> Vec<IndexRow> {
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let base_owned = base.to_string();
 
     // Build a cost lookup by user_id
-    let cost_map: std::collections::HashMap<String, &CostByUser> =
+    let cost_map: HashMap<String, &CostByUser> =
         costs.iter().map(|c| (c.user_id.clone(), c)).collect();
+    let previous_map: HashMap<String, Amount> = previous
+        .unwrap_or_default()
+        .iter()
+        .map(|c| (c.user_id.clone(), c.amount))
+        .collect();
+    let charged_for =
+        |user_id: &str| charged.map(|charged| charged.get(user_id).copied().unwrap_or_default());
 
     // Merge users with costs: show all users, lookup cost by user_id
-    struct Row {
-        user_id: String,
-        display: String,
-        cost: f64,
-        currency: String,
-        api_keys: String,
-        profiles: i64,
-    }
-
-    let mut rows: Vec<Row> = users
+    let mut rows: Vec<IndexRow> = users
         .iter()
         .map(|u| {
             let cost_entry = cost_map.get(&u.user_id);
-            Row {
+            IndexRow {
                 user_id: u.user_id.clone(),
                 display: u.user_email.clone(),
-                cost: cost_entry.map(|c| c.amount).unwrap_or(0.0),
+                cost: cost_entry.map(|c| c.amount).unwrap_or_default(),
+                previous: previous_map.get(&u.user_id).copied().unwrap_or_default(),
                 currency: cost_entry
                     .map(|c| c.currency.clone())
                     .unwrap_or_else(|| currency.clone()),
+                active: Some(u.active_api_key_count > 0),
                 api_keys: format!("{}/{}", u.active_api_key_count, u.api_key_count),
                 profiles: u.inference_profile_count,
+                charged: charged_for(&u.user_id),
             }
         })
         .collect();
@@ -57,43 +83,98 @@ pub fn render_index(
     // Also add any cost entries for users not in the enriched list
     let user_ids: std::collections::HashSet<String> =
         users.iter().map(|u| u.user_id.clone()).collect();
-    for c in &costs {
+    for c in costs {
         if !user_ids.contains(&c.user_id) {
-            rows.push(Row {
+            rows.push(IndexRow {
                 user_id: c.user_id.clone(),
                 display: c.user_email.clone().unwrap_or_else(|| c.user_id.clone()),
                 cost: c.amount,
+                previous: previous_map.get(&c.user_id).copied().unwrap_or_default(),
                 currency: c.currency.clone(),
+                active: None,
                 api_keys: "-".to_string(),
                 profiles: 0,
+                charged: charged_for(&c.user_id),
             });
         }
     }
 
-    let total_rows = rows.len();
-    // Sort rows before paginating
+    rows.retain(|r| filter.matches(r.cost, r.active));
     if let Some(col) = sort {
         let desc = order == "desc";
         rows.sort_by(|a, b| {
             let cmp = match col {
                 0 => a.display.cmp(&b.display),
-                1 => a.cost.partial_cmp(&b.cost).unwrap_or(std::cmp::Ordering::Equal),
+                1 => a.cost.cmp(&b.cost),
                 2 => a.api_keys.cmp(&b.api_keys),
                 3 => a.profiles.cmp(&b.profiles),
+                4 => a.previous.cmp(&b.previous),
+                5 => (a.cost - a.previous).cmp(&(b.cost - b.previous)),
+                6 => change_percent(a.cost, a.previous)
+                    .partial_cmp(&change_percent(b.cost, b.previous))
+                    .unwrap_or(std::cmp::Ordering::Equal),
+                7 => a.charged.cmp(&b.charged),
                 _ => std::cmp::Ordering::Equal,
             };
             if desc { cmp.reverse() } else { cmp }
         });
     }
+    rows
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_index(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    users: &[UserInfo],
+    costs: &[CostByUser],
+    previous: Option<&[CostByUser]>,
+    sort: Option<usize>,
+    order: &str,
+    filter: &IndexFilter,
+    charged: Option<&HashMap<String, Amount>>,
+) -> String {
+    let period = nav.period.as_str();
+    let empty = users.is_empty() && costs.is_empty();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
+    let currency = costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let base_owned = base.to_string();
+    let compare = previous.is_some();
+    let previous_total = previous.map(|p| p.iter().map(|c| c.amount).sum::<Amount>());
+
+    let charged_total = charged.map(|charged| {
+        costs
+            .iter()
+            .filter_map(|c| charged.get(&c.user_id).copied())
+            .sum::<Amount>()
+    });
+
+    let rows = index_rows(users, costs, previous, sort, order, filter, charged);
+    let total_rows = rows.len();
     let total_pages = if total_rows == 0 {
         1
     } else {
-        total_rows.div_ceil(PAGE_SIZE)
+        total_rows.div_ceil(nav.page_size)
     };
     let page = page.clamp(1, total_pages);
-    let skip = (page - 1) * PAGE_SIZE;
-    let self_path = with_period(&make_path(base, "/users"), period);
-    let pagination_html = pagination_nav(&self_path, page, total_rows, PAGE_SIZE);
+    let skip = (page - 1) * nav.page_size;
+    let period_path = with_period(&make_path(base, "/users"), period);
+    let unfiltered_path = with_compare(&period_path, compare);
+    let self_path = filter.apply_to(&unfiltered_path);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, total_rows, nav.page_size);
+    let export_path = with_sort(
+        &filter.apply_to(&with_compare(
+            &with_period(&make_path(base, "/users.csv"), period),
+            compare,
+        )),
+        sort,
+        order,
+    );
 
     let content = view! {
         <h2>"Users"</h2>
@@ -103,23 +184,43 @@ pub fn render_index(
             })
         } else {
             Either::Right(view! {
-                <table class="data-table" data-export-name="cost_by_user">
+                <table class="data-table" data-export-name="cost_by_user" data-export-href={export_path.clone()}>
                     <tr>
                         <th>"Email"</th>
-                        <th>"Cost"</th>
+                        {if charged.is_some() {
+                            Either::Left(view! {
+                                <th>"Raw Cost"</th>
+                                <th>"Charged Cost"</th>
+                            })
+                        } else {
+                            Either::Right(view! { <th>"Cost"</th> })
+                        }}
                         <th>"API Keys"</th>
                         <th>"Profiles"</th>
+                        {compare.then(|| view! {
+                            <th>"Previous"</th>
+                            <th>"Change"</th>
+                            <th>"Change %"</th>
+                        })}
                     </tr>
-                    {rows.into_iter().skip(skip).take(PAGE_SIZE).map(|r| {
-                        let href = with_period(&make_path(&base_owned, &format!("/users/{}", r.user_id)), period);
+                    {rows.into_iter().skip(skip).take(nav.page_size).map(|r| {
+                        let href = nav.drill(&make_path(&base_owned, &format!("/users/{}", r.user_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", r.cost, r.currency);
+                        let charged_str = r.charged.map(|c| format!("{:.2} {}", c, r.currency));
+                        let change = compare.then(|| change_cells(r.cost, r.previous, &r.currency));
                         let profiles_str = r.profiles.to_string();
                         view! {
                             <tr>
                                 <td><a href={href}>{r.display}</a></td>
                                 <td>{cost_str}</td>
+                                {charged_str.map(|c| view! { <td>{c}</td> })}
                                 <td>{r.api_keys}</td>
                                 <td>{profiles_str}</td>
+                                {change.map(|[previous, change, percent]| view! {
+                                    <td>{previous}</td>
+                                    <td>{change}</td>
+                                    <td>{percent}</td>
+                                })}
                             </tr>
                         }
                     }).collect::<Vec<_>>()}
@@ -129,24 +230,115 @@ pub fn render_index(
         }}
     };
 
+    let mut info_rows = vec![
+        InfoRow::raw(
+            "Period",
+            period_links(
+                &filter.apply_to(&with_compare(&make_path(base, "/users"), compare)),
+                period,
+            ),
+        ),
+        InfoRow::raw(
+            "Compare",
+            compare_links(&filter.apply_to(&period_path), compare),
+        ),
+        InfoRow::raw("Filter", filter_links(&unfiltered_path, filter)),
+        InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+    ];
+    info_rows.extend(charged_info_row(charged_total, &currency));
+    info_rows.push(InfoRow::raw(
+        "Export",
+        format!(r#"<a href="{}">CSV</a>"#, html_escape(&export_path)),
+    ));
+    info_rows.extend(compare_info_rows(total, previous_total, &currency));
+
     Page {
         title: "Cost Explorer - Users".to_string(),
         breadcrumbs: vec![
             Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
             Breadcrumb::current("Users"),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::raw("Period", period_links(&make_path(base, "/users"), period)),
-            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
-        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
         content,
         subpages: vec![],
     }
     .render()
 }
 
-pub fn render_hub(base: &str, period: &str, user: &UserInfo) -> String {
+/// `limit` is the user's spend limit, if any; `edit_limits` adds a link to
+/// the admin limits page. `keys` and `key_costs` fill the period's cost per
+/// API key.
+pub fn render_hub(
+    base: &str,
+    nav: &NavContext,
+    user: &UserInfo,
+    limit: Option<&SpendLimit>,
+    edit_limits: bool,
+    keys: &[ApiKeyInfo],
+    key_costs: &[CostByApiKey],
+) -> String {
+    let period = nav.period.as_str();
+    let key_rows = key_cost_rows(keys, key_costs);
+    let key_total: Amount = key_rows.iter().map(|r| r.cost).sum();
+    let key_currency = key_costs
+        .first()
+        .map(|c| c.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let content = (!key_rows.is_empty()).then(|| {
+        view! {
+            <h2>"Cost by API Key"</h2>
+            <table class="data-table" data-export-name="user_cost_by_api_key">
+                <tr>
+                    <th>"Key"</th>
+                    <th>"Status"</th>
+                    <th>"Cost"</th>
+                    <th>"Share"</th>
+                </tr>
+                {key_rows.into_iter().map(|r| {
+                    let cost_str = format!("{:.2} {}", r.cost, key_currency);
+                    let share_str = share(r.cost, key_total);
+                    view! {
+                        <tr>
+                            <td><code>{r.key}</code></td>
+                            <td>{r.status}</td>
+                            <td>{cost_str}</td>
+                            <td>{share_str}</td>
+                        </tr>
+                    }
+                }).collect::<Vec<_>>()}
+            </table>
+        }
+    });
+    let mut info_rows = vec![
+        InfoRow::new("User ID", &user.user_id),
+        InfoRow::new("Email", &user.user_email),
+        InfoRow::new("Created", &user.created_at),
+    ];
+    if limit.is_some() || edit_limits {
+        let mut value = match limit {
+            Some(limit) if limit.limit_exceeded => format!(
+                "{:.2} USD <strong>(exceeded, API access locked)</strong>",
+                limit.monthly_limit
+            ),
+            Some(limit) => format!("{:.2} USD", limit.monthly_limit),
+            None => "None".to_string(),
+        };
+        if edit_limits {
+            value.push_str(&format!(
+                r#" | <a href="{}">Edit</a>"#,
+                html_escape(&make_path(base, "/limits"))
+            ));
+        }
+        info_rows.push(InfoRow::raw("Monthly Limit", value));
+    }
+    let origin = nav.here(
+        &with_period(&make_path(base, &format!("/users/{}", user.user_id)), period),
+        1,
+    );
     Page {
         title: format!("Cost Explorer - {}", user.user_email),
         breadcrumbs: vec![
@@ -154,56 +346,115 @@ pub fn render_hub(base: &str, period: &str, user: &UserInfo) -> String {
             Breadcrumb::link("Users", with_period(&make_path(base, "/users"), period)),
             Breadcrumb::current(&user.user_email),
         ],
-        nav_links: vec![NavLink::back()],
-        info_rows: vec![
-            InfoRow::new("User ID", &user.user_id),
-            InfoRow::new("Email", &user.user_email),
-            InfoRow::new("Created", &user.created_at),
-        ],
-        content: (),
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows,
+        content,
         subpages: vec![
             Subpage::new(
                 "Daily Cost",
-                with_period(
+                nav.drill(
                     &make_path(base, &format!("/users/{}/daily", user.user_id)),
-                    period,
+                    origin.as_deref(),
                 ),
                 "-",
             ),
             Subpage::new(
                 "Monthly Cost",
-                with_period(
+                nav.drill(
                     &make_path(base, &format!("/users/{}/monthly", user.user_id)),
-                    period,
+                    origin.as_deref(),
                 ),
                 "-",
             ),
+            Subpage::new(
+                "API Keys",
+                nav.drill(
+                    &make_path(base, &format!("/users/{}/keys", user.user_id)),
+                    origin.as_deref(),
+                ),
+                format!("{}/{}", user.active_api_key_count, user.api_key_count),
+            ),
+            Subpage::new(
+                "Inference Profiles",
+                nav.drill(
+                    &make_path(base, &format!("/users/{}/profiles", user.user_id)),
+                    origin.as_deref(),
+                ),
+                user.inference_profile_count,
+            ),
         ],
     }
     .render()
 }
 
+struct KeyCostRow {
+    key: String,
+    status: &'static str,
+    cost: Amount,
+}
+
+/// Every key of the user, with cost or not, plus cost of keys that are gone
+/// and cost not tied to a key, highest cost first.
+fn key_cost_rows(keys: &[ApiKeyInfo], costs: &[CostByApiKey]) -> Vec<KeyCostRow> {
+    let cost_map: std::collections::HashMap<&str, Amount> = costs
+        .iter()
+        .map(|c| (c.api_key_id.as_str(), c.amount))
+        .collect();
+    let mut rows: Vec<KeyCostRow> = keys
+        .iter()
+        .map(|k| KeyCostRow {
+            key: format!("...{}", k.api_key_preview),
+            status: if k.is_disabled { "Disabled" } else { "Active" },
+            cost: cost_map.get(k.api_key_id.as_str()).copied().unwrap_or_default(),
+        })
+        .collect();
+    let key_ids: std::collections::HashSet<&str> =
+        keys.iter().map(|k| k.api_key_id.as_str()).collect();
+    for c in costs {
+        if c.api_key_id.is_empty() {
+            rows.push(KeyCostRow {
+                key: "(untagged)".to_string(),
+                status: "-",
+                cost: c.amount,
+            });
+        } else if !key_ids.contains(c.api_key_id.as_str()) {
+            rows.push(KeyCostRow {
+                key: c.api_key_id.clone(),
+                status: "Deleted",
+                cost: c.amount,
+            });
+        }
+    }
+    rows.sort_by_key(|r| std::cmp::Reverse(r.cost));
+    rows
+}
+
 pub fn render_daily_costs(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     user_id: &str,
     user_email: &str,
     costs: &[CostRecord],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
     let self_path = with_period(
         &make_path(base, &format!("/users/{}/daily", user_id)),
         period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
     let base_owned = base.to_string();
 
     let content = view! {
@@ -220,7 +471,7 @@ pub fn render_daily_costs(
                         <th>"Cost"</th>
                     </tr>
                     {page_items.iter().map(|c| {
-                        let href = with_period(&make_path(&base_owned, &format!("/costs/daily/{}/users/{}", c.date, user_id)), period);
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/daily/{}/users/{}", c.date, user_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         let date = c.date.clone();
                         view! {
@@ -247,7 +498,10 @@ pub fn render_daily_costs(
             ),
             Breadcrumb::current("Daily Cost"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::raw(
                 "Period",
@@ -266,25 +520,27 @@ pub fn render_daily_costs(
 
 pub fn render_monthly_costs(
     base: &str,
-    period: &str,
+    nav: &NavContext,
     page: usize,
     user_id: &str,
     user_email: &str,
     costs: &[CostRecord],
 ) -> String {
+    let period = nav.period.as_str();
     let costs = costs.to_vec();
     let empty = costs.is_empty();
-    let total: f64 = costs.iter().map(|c| c.amount).sum();
+    let total: Amount = costs.iter().map(|c| c.amount).sum();
     let currency = costs
         .first()
         .map(|c| c.currency.clone())
         .unwrap_or_else(|| "USD".to_string());
-    let (page_items, page) = paginate(&costs, page);
+    let (page_items, page) = paginate(&costs, page, nav.page_size);
     let self_path = with_period(
         &make_path(base, &format!("/users/{}/monthly", user_id)),
         period,
     );
-    let pagination_html = pagination_nav(&self_path, page, costs.len(), PAGE_SIZE);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, costs.len(), nav.page_size);
     let base_owned = base.to_string();
 
     let content = view! {
@@ -302,7 +558,7 @@ pub fn render_monthly_costs(
                     </tr>
                     {page_items.iter().map(|c| {
                         let month = if c.date.len() >= 7 { &c.date[..7] } else { &c.date };
-                        let href = with_period(&make_path(&base_owned, &format!("/costs/monthly/{}/users/{}", month, user_id)), period);
+                        let href = nav.drill(&make_path(&base_owned, &format!("/costs/monthly/{}/users/{}", month, user_id)), origin.as_deref());
                         let cost_str = format!("{:.2} {}", c.amount, c.currency);
                         let month_display = month.to_string();
                         view! {
@@ -329,7 +585,10 @@ pub fn render_monthly_costs(
             ),
             Breadcrumb::current("Monthly Cost"),
         ],
-        nav_links: vec![NavLink::back()],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
         info_rows: vec![
             InfoRow::raw(
                 "Period",
@@ -346,13 +605,182 @@ pub fn render_monthly_costs(
     .render()
 }
 
+pub fn render_keys(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    user_id: &str,
+    user_email: &str,
+    keys: &[ApiKeyInfo],
+) -> String {
+    let period = nav.period.as_str();
+    let keys = keys.to_vec();
+    let empty = keys.is_empty();
+    let active = keys.iter().filter(|k| !k.is_disabled).count();
+    let (page_items, page) = paginate(&keys, page, nav.page_size);
+    let self_path = with_period(&make_path(base, &format!("/users/{}/keys", user_id)), period);
+    let pagination_html = pagination_nav(&nav.with_from(&self_path), page, keys.len(), nav.page_size);
+
+    let content = view! {
+        <h2>"API Keys"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No API keys found for this user."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="api_keys">
+                    <tr>
+                        <th>"Key"</th>
+                        <th>"Status"</th>
+                        <th>"Created"</th>
+                    </tr>
+                    {page_items.iter().map(|k| {
+                        let preview = format!("...{}", k.api_key_preview);
+                        let status = if k.is_disabled { "Disabled" } else { "Active" };
+                        let created = k.created_at.clone();
+                        view! {
+                            <tr>
+                                <td><code>{preview}</code></td>
+                                <td>{status}</td>
+                                <td>{created}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+                <div inner_html={pagination_html}></div>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Cost Explorer - {} - API Keys", user_email),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link("Users", with_period(&make_path(base, "/users"), period)),
+            Breadcrumb::link(
+                user_email,
+                with_period(&make_path(base, &format!("/users/{}", user_id)), period),
+            ),
+            Breadcrumb::current("API Keys"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::new("Email", user_email),
+            InfoRow::new("Active Keys", &format!("{}/{}", active, keys.len())),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+pub fn render_profiles(
+    base: &str,
+    nav: &NavContext,
+    page: usize,
+    user_id: &str,
+    user_email: &str,
+    profiles: &[InferenceProfileInfo],
+) -> String {
+    let period = nav.period.as_str();
+    let profiles = profiles.to_vec();
+    let empty = profiles.is_empty();
+    let model_count = profiles
+        .iter()
+        .map(|p| p.model_id.as_str())
+        .collect::<std::collections::HashSet<_>>()
+        .len();
+    let (page_items, page) = paginate(&profiles, page, nav.page_size);
+    let self_path = with_period(
+        &make_path(base, &format!("/users/{}/profiles", user_id)),
+        period,
+    );
+    let origin = nav.here(&self_path, page);
+    let pagination_html =
+        pagination_nav(&nav.with_from(&self_path), page, profiles.len(), nav.page_size);
+    let base_owned = base.to_string();
+
+    let content = view! {
+        <h2>"Inference Profiles"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No inference profiles found for this user."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="inference_profiles">
+                    <tr>
+                        <th>"Model"</th>
+                        <th>"Profile ID"</th>
+                        <th>"Created"</th>
+                    </tr>
+                    {page_items.iter().map(|p| {
+                        let href = nav.drill(&make_path(&base_owned, &format!("/models/{}", p.model_id)), origin.as_deref());
+                        let display = p.model_name.clone().unwrap_or_else(|| p.model_id.clone());
+                        let profile_id = p.inference_profile_id.clone();
+                        let created = p.created_at.clone();
+                        view! {
+                            <tr>
+                                <td><a href={href}>{display}</a></td>
+                                <td>{profile_id}</td>
+                                <td>{created}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+                <div inner_html={pagination_html}></div>
+            })
+        }}
+    };
+
+    Page {
+        title: format!("Cost Explorer - {} - Inference Profiles", user_email),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link("Users", with_period(&make_path(base, "/users"), period)),
+            Breadcrumb::link(
+                user_email,
+                with_period(&make_path(base, &format!("/users/{}", user_id)), period),
+            ),
+            Breadcrumb::current("Inference Profiles"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::new("Email", user_email),
+            InfoRow::new("Profiles", &profiles.len().to_string()),
+            InfoRow::new("Models", &model_count.to_string()),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn render_index_empty() {
-        let html = render_index("/", "30d", 1, &[], &[], None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("No users found."));
         assert!(html.contains("Cost Explorer - Users"));
     }
@@ -370,19 +798,159 @@ mod tests {
         let costs = vec![CostByUser {
             user_id: "abc-123".to_string(),
             user_email: Some("alice@example.com".to_string()),
-            amount: 50.0,
+            amount: Amount::from_f64(50.0),
             currency: "USD".to_string(),
         }];
-        let html = render_index("/", "30d", 1, &users, &costs, None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &users,
+            &costs,
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("50.00 USD"));
         assert!(html.contains("2/3")); // active/total api keys
         assert!(html.contains("/users/abc-123"));
     }
 
+    #[test]
+    fn render_index_exports_with_filter_and_sort() {
+        let filter = IndexFilter::parse(Some("5"), Some("active"), None);
+        let html = render_index(
+            "/",
+            &"7d".into(),
+            2,
+            &[],
+            &[],
+            Some(&[]),
+            Some(1),
+            "desc",
+            &filter,
+            None,
+        );
+        assert!(html.contains(
+            "/users.csv?period=7d&amp;compare=prev&amp;min_cost=5.00&amp;status=active&amp;sort=1&amp;order=desc"
+        ));
+    }
+
+    #[test]
+    fn render_index_filters_before_paginating() {
+        let user = |id: &str, active: i64| UserInfo {
+            user_id: id.to_string(),
+            user_email: format!("{}@example.com", id),
+            created_at: "2024-01-01".to_string(),
+            api_key_count: 1,
+            active_api_key_count: active,
+            inference_profile_count: 0,
+        };
+        let users = vec![user("idle", 0), user("busy", 1), user("free", 1)];
+        let costs = vec![CostByUser {
+            user_id: "busy".to_string(),
+            user_email: Some("busy@example.com".to_string()),
+            amount: Amount::from_f64(5.0),
+            currency: "USD".to_string(),
+        }];
+        let filter = IndexFilter::parse(None, Some("active"), Some("true"));
+        let html = render_index("/", &"30d".into(), 1, &users, &costs, None, None, "asc", &filter, None);
+        assert!(html.contains("busy@example.com"));
+        assert!(!html.contains("idle@example.com"));
+        assert!(!html.contains("free@example.com"));
+        assert!(html.contains("<b>Active</b>"));
+        assert!(html.contains("/users?status=active&amp;has_cost=true&amp;compare=prev"));
+    }
+
+    #[test]
+    fn render_index_compare_with_previous() {
+        let costs = vec![CostByUser {
+            user_id: "abc-123".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            amount: Amount::from_f64(40.0),
+            currency: "USD".to_string(),
+        }];
+        let previous = vec![CostByUser {
+            amount: Amount::from_f64(50.0),
+            ..costs[0].clone()
+        }];
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &costs,
+            Some(&previous),
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
+        assert!(html.contains("-10.00 USD"));
+        assert!(html.contains("-20.0%"));
+        assert!(html.contains("<b>Previous Period</b>"));
+    }
+
+    #[test]
+    fn render_index_shows_charged_cost() {
+        let costs = vec![CostByUser {
+            user_id: "abc-123".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            amount: Amount::from_f64(40.0),
+            currency: "USD".to_string(),
+        }];
+        let charged = [("abc-123".to_string(), Amount::from_f64(44.0))]
+            .into_iter()
+            .collect();
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &costs,
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            Some(&charged),
+        );
+        assert!(html.contains("<th>Raw Cost</th>"));
+        assert!(html.contains("<th>Charged Cost</th>"));
+        assert!(html.contains("<td>44.00 USD</td>"));
+        assert!(html.contains("<td>Charged Cost</td>"));
+
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &costs,
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
+        assert!(!html.contains("Charged Cost"));
+    }
+
     #[test]
     fn render_index_period_links() {
-        let html = render_index("/", "30d", 1, &[], &[], None, "asc");
+        let html = render_index(
+            "/",
+            &"30d".into(),
+            1,
+            &[],
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("<b>Past 30 Days</b>"));
         assert!(html.contains("?period=7d"));
     }
@@ -397,10 +965,48 @@ mod tests {
             active_api_key_count: 1,
             inference_profile_count: 0,
         }];
-        let html = render_index("/_dashboard", "30d", 1, &users, &[], None, "asc");
+        let html = render_index(
+            "/_dashboard",
+            &"30d".into(),
+            1,
+            &users,
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
         assert!(html.contains("/_dashboard/users/abc-123"));
     }
 
+    #[test]
+    fn render_index_links_carry_from() {
+        let users = vec![UserInfo {
+            user_id: "abc-123".to_string(),
+            user_email: "alice@example.com".to_string(),
+            created_at: "2024-01-01".to_string(),
+            api_key_count: 1,
+            active_api_key_count: 1,
+            inference_profile_count: 0,
+        }];
+        let nav = NavContext::new("30d", Some("/"));
+        let html = render_index(
+            "/",
+            &nav,
+            1,
+            &users,
+            &[],
+            None,
+            None,
+            "asc",
+            &IndexFilter::default(),
+            None,
+        );
+        assert!(html.contains("/users/abc-123?from=/users%3Ffrom%3D/"));
+        assert!(!html.contains("history.back()"));
+    }
+
     #[test]
     fn render_hub_contains_info() {
         let user = UserInfo {
@@ -411,17 +1017,79 @@ mod tests {
             active_api_key_count: 2,
             inference_profile_count: 5,
         };
-        let html = render_hub("/", "30d", &user);
+        let html = render_hub("/", &"30d".into(), &user, None, false, &[], &[]);
         assert!(html.contains("alice@example.com"));
         assert!(html.contains("abc-123"));
         assert!(html.contains("2024-01-01"));
         assert!(html.contains("Daily Cost"));
         assert!(html.contains("Monthly Cost"));
+        assert!(html.contains("/users/abc-123/keys"));
+        assert!(html.contains("/users/abc-123/profiles"));
+        assert!(!html.contains("Monthly Limit"));
+    }
+
+    #[test]
+    fn render_hub_breaks_cost_down_by_key() {
+        let user = UserInfo {
+            user_id: "abc-123".to_string(),
+            user_email: "alice@example.com".to_string(),
+            created_at: String::new(),
+            api_key_count: 2,
+            active_api_key_count: 1,
+            inference_profile_count: 0,
+        };
+        let key = |id: &str, preview: &str, is_disabled| ApiKeyInfo {
+            api_key_id: id.to_string(),
+            api_key_preview: preview.to_string(),
+            is_disabled,
+            created_at: String::new(),
+        };
+        let keys = vec![key("k1", "abcd1234", false), key("k2", "wxyz9876", true)];
+        let cost = |id: &str, amount| CostByApiKey {
+            api_key_id: id.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        };
+        let costs = vec![cost("k1", 30.0), cost("gone", 10.0), cost("", 10.0)];
+        let html = render_hub("/", &"30d".into(), &user, None, false, &keys, &costs);
+        assert!(html.contains("Cost by API Key"));
+        assert!(html.contains("<td>30.00 USD</td>"));
+        assert!(html.contains("<td>60.0%</td>"));
+        assert!(html.contains("<code>...wxyz9876</code>"));
+        assert!(html.contains("<td>Deleted</td>"));
+        assert!(html.contains("(untagged)"));
+
+        let rows = key_cost_rows(&keys, &costs);
+        let order: Vec<&str> = rows.iter().map(|r| r.key.as_str()).collect();
+        assert_eq!(order[0], "...abcd1234");
+        assert_eq!(order[3], "...wxyz9876");
+    }
+
+    #[test]
+    fn render_hub_shows_locked_limit() {
+        let user = UserInfo {
+            user_id: "abc-123".to_string(),
+            user_email: "alice@example.com".to_string(),
+            created_at: String::new(),
+            api_key_count: 1,
+            active_api_key_count: 0,
+            inference_profile_count: 0,
+        };
+        let limit = SpendLimit {
+            user_id: "abc-123".to_string(),
+            user_email: None,
+            monthly_limit: Amount::from_f64(100.0),
+            limit_exceeded: true,
+        };
+        let html = render_hub("/_dashboard", &"30d".into(), &user, Some(&limit), true, &[], &[]);
+        assert!(html.contains("100.00 USD"));
+        assert!(html.contains("API access locked"));
+        assert!(html.contains(r#"href="/_dashboard/limits""#));
     }
 
     #[test]
     fn render_daily_costs_empty() {
-        let html = render_daily_costs("/", "30d", 1, "abc-123", "alice@example.com", &[]);
+        let html = render_daily_costs("/", &"30d".into(), 1, "abc-123", "alice@example.com", &[]);
         assert!(html.contains("No cost data found for this user"));
     }
 
@@ -429,10 +1097,10 @@ mod tests {
     fn render_daily_costs_with_data() {
         let costs = vec![CostRecord {
             date: "2024-01-15".to_string(),
-            amount: 42.0,
+            amount: Amount::from_f64(42.0),
             currency: "USD".to_string(),
         }];
-        let html = render_daily_costs("/", "30d", 1, "abc-123", "alice@example.com", &costs);
+        let html = render_daily_costs("/", &"30d".into(), 1, "abc-123", "alice@example.com", &costs);
         assert!(html.contains("2024-01-15"));
         assert!(html.contains("42.00 USD"));
         assert!(html.contains("/costs/daily/2024-01-15/users/abc-123"));
@@ -440,7 +1108,7 @@ mod tests {
 
     #[test]
     fn render_monthly_costs_empty() {
-        let html = render_monthly_costs("/", "30d", 1, "abc-123", "alice@example.com", &[]);
+        let html = render_monthly_costs("/", &"30d".into(), 1, "abc-123", "alice@example.com", &[]);
         assert!(html.contains("No cost data found for this user"));
     }
 
@@ -448,12 +1116,58 @@ mod tests {
     fn render_monthly_costs_with_data() {
         let costs = vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 500.0,
+            amount: Amount::from_f64(500.0),
             currency: "USD".to_string(),
         }];
-        let html = render_monthly_costs("/", "30d", 1, "abc-123", "alice@example.com", &costs);
+        let html = render_monthly_costs("/", &"30d".into(), 1, "abc-123", "alice@example.com", &costs);
         assert!(html.contains("2024-01"));
         assert!(html.contains("500.00 USD"));
         assert!(html.contains("/costs/monthly/2024-01/users/abc-123"));
     }
+
+    #[test]
+    fn render_keys_with_data() {
+        let keys = vec![
+            ApiKeyInfo {
+                api_key_id: "key-1".to_string(),
+                api_key_preview: "abcd1234".to_string(),
+                is_disabled: false,
+                created_at: "2024-02-01".to_string(),
+            },
+            ApiKeyInfo {
+                api_key_id: "key-2".to_string(),
+                api_key_preview: "wxyz9876".to_string(),
+                is_disabled: true,
+                created_at: "2024-01-01".to_string(),
+            },
+        ];
+        let html = render_keys("/", &"30d".into(), 1, "abc-123", "alice@example.com", &keys);
+        assert!(html.contains("...abcd1234"));
+        assert!(html.contains("Disabled"));
+        assert!(html.contains("2024-02-01"));
+        assert!(html.contains("1/2"));
+    }
+
+    #[test]
+    fn render_keys_empty() {
+        let html = render_keys("/", &"30d".into(), 1, "abc-123", "alice@example.com", &[]);
+        assert!(html.contains("No API keys found for this user."));
+    }
+
+    #[test]
+    fn render_profiles_links_models() {
+        let profiles = vec![InferenceProfileInfo {
+            inference_profile_id: "prof-1".to_string(),
+            model_id: "model-1".to_string(),
+            model_name: Some("claude-3-sonnet".to_string()),
+            user_id: "abc-123".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            created_at: "2024-03-01".to_string(),
+        }];
+        let html =
+            render_profiles("/", &"30d".into(), 1, "abc-123", "alice@example.com", &profiles);
+        assert!(html.contains("<a href=\"/models/model-1\">claude-3-sonnet</a>"));
+        assert!(html.contains("prof-1"));
+        assert!(html.contains("2024-03-01"));
+    }
 }
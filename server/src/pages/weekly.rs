@@ -0,0 +1,231 @@
+use super::{make_path, paginate, with_period, NavContext};
+use chrono::{Datelike, Duration, NaiveDate};
+use common::{Amount, CostRecord};
+use leptos::either::Either;
+use leptos::prelude::*;
+use templates::{pagination_nav, period_links, Breadcrumb, InfoRow, Page};
+
+/// ISO week label for the week starting on `monday`, e.g. `2025-W03`.
+pub fn week_label(monday: NaiveDate) -> String {
+    let week = monday.iso_week();
+    format!("{}-W{:02}", week.year(), week.week())
+}
+
+fn parse_day(date: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()
+}
+
+/// Weekly totals; each record is dated by the Monday of its week.
+pub fn render(base: &str, nav: &NavContext, page: usize, weekly_cost: &[CostRecord]) -> String {
+    let period = nav.period.as_str();
+    let weekly_cost = weekly_cost.to_vec();
+    let total: Amount = weekly_cost.iter().map(|r| r.amount).sum();
+    let currency = weekly_cost
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let empty = weekly_cost.is_empty();
+    let start_owned = weekly_cost
+        .first()
+        .map(|r| r.date.clone())
+        .unwrap_or_default();
+    let end_owned = weekly_cost
+        .last()
+        .map(|r| r.date.clone())
+        .unwrap_or_default();
+    let base_owned = base.to_string();
+    let (page_items, page) = paginate(&weekly_cost, page, nav.page_size);
+    let self_path = with_period(&make_path(base, "/costs/weekly"), period);
+    let origin = nav.here(&self_path, page);
+    let pagination_html = pagination_nav(
+        &nav.with_from(&self_path),
+        page,
+        weekly_cost.len(),
+        nav.page_size,
+    );
+
+    let content = view! {
+        <h2>"Weekly Cost Breakdown"</h2>
+        {if empty {
+            Either::Left(view! {
+                <p>"No cost data found for this period."</p>
+            })
+        } else {
+            Either::Right(view! {
+                <table class="data-table" data-export-name="weekly_cost" data-start={start_owned} data-end={end_owned}>
+                    <tr>
+                        <th>"Week"</th>
+                        <th>"Start"</th>
+                        <th>"End"</th>
+                        <th>"Cost"</th>
+                    </tr>
+                    {page_items.iter().map(|r| {
+                        let monday = parse_day(&r.date);
+                        let week = monday.map(week_label).unwrap_or_else(|| r.date.clone());
+                        let sunday = monday
+                            .map(|d| (d + Duration::days(6)).format("%Y-%m-%d").to_string())
+                            .unwrap_or_default();
+                        let week_href = nav.drill(&make_path(&base_owned, &format!("/costs/weekly/{}", week)), origin.as_deref());
+                        let cost_str = format!("{:.2} {}", r.amount, r.currency);
+                        let start = r.date.clone();
+                        view! {
+                            <tr>
+                                <td><a href={week_href}>{week}</a></td>
+                                <td>{start}</td>
+                                <td>{sunday}</td>
+                                <td>{cost_str}</td>
+                            </tr>
+                        }
+                    }).collect::<Vec<_>>()}
+                </table>
+                <div inner_html={pagination_html}></div>
+            })
+        }}
+    };
+
+    Page {
+        title: "Cost Explorer - Weekly Cost".to_string(),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::current("Weekly Cost"),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::raw(
+                "Period",
+                period_links(&make_path(base, "/costs/weekly"), period),
+            ),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+/// One ISO week, Monday to Sunday, with each day linking to its daily hub.
+pub fn render_week(
+    base: &str,
+    nav: &NavContext,
+    monday: NaiveDate,
+    daily_cost: &[CostRecord],
+) -> String {
+    let period = nav.period.as_str();
+    let week = week_label(monday);
+    let currency = daily_cost
+        .first()
+        .map(|r| r.currency.clone())
+        .unwrap_or_else(|| "USD".to_string());
+    let total: Amount = daily_cost.iter().map(|r| r.amount).sum();
+    let origin = nav.here(
+        &with_period(&make_path(base, &format!("/costs/weekly/{}", week)), period),
+        1,
+    );
+    let rows: Vec<_> = (0..7)
+        .map(|i| {
+            let day = monday + Duration::days(i);
+            let date = day.format("%Y-%m-%d").to_string();
+            let amount: Amount = daily_cost
+                .iter()
+                .filter(|r| r.date == date)
+                .map(|r| r.amount)
+                .sum();
+            let href = nav.drill(
+                &make_path(base, &format!("/costs/daily/{}", date)),
+                origin.as_deref(),
+            );
+            (
+                href,
+                date,
+                day.format("%a").to_string(),
+                format!("{:.2} {}", amount, currency),
+            )
+        })
+        .collect();
+
+    let content = view! {
+        <h2>{format!("Week {}", week)}</h2>
+        <table class="data-table" data-export-name={format!("week_{}", week)}>
+            <tr>
+                <th>"Date"</th>
+                <th>"Day"</th>
+                <th>"Cost"</th>
+            </tr>
+            {rows.into_iter().map(|(href, date, weekday, cost)| view! {
+                <tr>
+                    <td><a href={href}>{date}</a></td>
+                    <td>{weekday}</td>
+                    <td>{cost}</td>
+                </tr>
+            }).collect::<Vec<_>>()}
+        </table>
+    };
+
+    Page {
+        title: format!("Cost Explorer - {}", week),
+        breadcrumbs: vec![
+            Breadcrumb::link("Cost Explorer", with_period(&make_path(base, ""), period)),
+            Breadcrumb::link(
+                "Weekly Cost",
+                with_period(&make_path(base, "/costs/weekly"), period),
+            ),
+            Breadcrumb::current(&week),
+        ],
+        search: Some(nav.search(base)),
+        source_filter: nav.source_filter(base),
+        error: None,
+        nav_links: vec![nav.back()],
+        info_rows: vec![
+            InfoRow::new("Week", &week),
+            InfoRow::new("Total Cost", &format!("{:.2} {}", total, currency)),
+        ],
+        content,
+        subpages: vec![],
+    }
+    .render()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(date: &str, amount: f64) -> CostRecord {
+        CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn render_labels_iso_weeks() {
+        let weekly = vec![record("2024-12-30", 12.5), record("2025-01-06", 7.5)];
+        let html = render("/", &"30d".into(), 1, &weekly);
+        assert!(html.contains("<title>Cost Explorer - Weekly Cost</title>"));
+        assert!(html.contains(r#"href="/costs/weekly/2025-W01""#));
+        assert!(html.contains(">2025-W02<"));
+        assert!(html.contains("2025-01-05"));
+        assert!(html.contains("20.00 USD"));
+    }
+
+    #[test]
+    fn render_empty() {
+        let html = render("/", &"30d".into(), 1, &[]);
+        assert!(html.contains("No cost data found for this period."));
+    }
+
+    #[test]
+    fn render_week_lists_every_day() {
+        let monday = NaiveDate::from_ymd_opt(2025, 1, 13).unwrap();
+        let daily = vec![record("2025-01-14", 3.0), record("2025-01-19", 1.0)];
+        let html = render_week("/", &"30d".into(), monday, &daily);
+        assert!(html.contains("<title>Cost Explorer - 2025-W03</title>"));
+        assert!(html.contains(r#"href="/costs/daily/2025-01-13""#));
+        assert!(html.contains(r#"href="/costs/daily/2025-01-19""#));
+        assert!(html.contains("<td>0.00 USD</td>"));
+        assert!(html.contains("4.00 USD"));
+    }
+}
@@ -0,0 +1,112 @@
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use templates::PERIODS;
+use tower_sessions::Session;
+
+/// Rows-per-page values offered on the preferences page.
+pub const PAGE_SIZES: [usize; 4] = [25, 50, 100, 200];
+
+const SESSION_KEY: &str = "preferences";
+
+/// Per-user display defaults, kept in the session. `None` means the built-in
+/// default; an explicit `?period=` still wins over the preferred period.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Preferences {
+    pub period: Option<String>,
+    pub page_size: Option<usize>,
+    /// IANA time zone name overriding the configured `timezone`.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// The submitted preferences form; empty fields reset to the default.
+#[derive(Deserialize)]
+pub struct PreferencesForm {
+    #[serde(default)]
+    pub period: String,
+    #[serde(default)]
+    pub page_size: String,
+    #[serde(default)]
+    pub timezone: String,
+}
+
+impl Preferences {
+    pub async fn load(session: &Session) -> Self {
+        match session.get::<Preferences>(SESSION_KEY).await {
+            Ok(Some(prefs)) => prefs,
+            _ => Self::default(),
+        }
+    }
+
+    pub async fn save(&self, session: &Session) -> anyhow::Result<()> {
+        session.insert(SESSION_KEY, self).await?;
+        Ok(())
+    }
+
+    /// Values that are not on offer are dropped rather than rejected, so a
+    /// stale form never leaves the user stuck.
+    pub fn from_form(form: &PreferencesForm) -> Self {
+        let period = Some(form.period.as_str())
+            .filter(|p| PERIODS.iter().any(|(key, _)| key == p))
+            .map(str::to_string);
+        let page_size = form
+            .page_size
+            .parse()
+            .ok()
+            .filter(|size| PAGE_SIZES.contains(size));
+        let timezone = form
+            .timezone
+            .parse::<Tz>()
+            .ok()
+            .map(|tz| tz.name().to_string());
+        Self {
+            period,
+            page_size,
+            timezone,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn form(period: &str, page_size: &str) -> PreferencesForm {
+        PreferencesForm {
+            period: period.to_string(),
+            page_size: page_size.to_string(),
+            timezone: String::new(),
+        }
+    }
+
+    #[test]
+    fn from_form_accepts_offered_values() {
+        let prefs = Preferences::from_form(&form("7d", "100"));
+        assert_eq!(prefs.period.as_deref(), Some("7d"));
+        assert_eq!(prefs.page_size, Some(100));
+    }
+
+    #[test]
+    fn from_form_keeps_known_timezones() {
+        let mut f = form("", "");
+        f.timezone = "America/New_York".to_string();
+        assert_eq!(
+            Preferences::from_form(&f).timezone.as_deref(),
+            Some("America/New_York")
+        );
+        f.timezone = "Mars/Olympus".to_string();
+        assert_eq!(Preferences::from_form(&f).timezone, None);
+    }
+
+    #[test]
+    fn from_form_drops_unknown_values() {
+        assert_eq!(
+            Preferences::from_form(&form("", "")),
+            Preferences::default()
+        );
+        assert_eq!(
+            Preferences::from_form(&form("2y", "7")),
+            Preferences::default()
+        );
+    }
+}
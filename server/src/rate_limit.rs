@@ -0,0 +1,86 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use axum::http::{header, Request, StatusCode};
+use axum::response::{Html, IntoResponse, Response};
+use axum::Router;
+use tower_governor::governor::GovernorConfigBuilder;
+use tower_governor::key_extractor::KeyExtractor;
+use tower_governor::{GovernorError, GovernorLayer};
+use tower_sessions::Session;
+
+use crate::config::RateLimitConfig;
+use crate::forwarded::ClientInfo;
+use crate::handlers::AppState;
+use crate::pages;
+
+/// How often clients that have not been limited lately are forgotten.
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Keys requests by session, or by client address before one exists.
+#[derive(Clone)]
+struct SessionOrIp;
+
+impl KeyExtractor for SessionOrIp {
+    type Key = String;
+
+    fn extract<T>(&self, req: &Request<T>) -> Result<Self::Key, GovernorError> {
+        let session = req.extensions().get::<Session>().and_then(Session::id);
+        let ip = req.extensions().get::<ClientInfo>().and_then(|c| c.ip);
+        Ok(match (session, ip) {
+            (Some(id), _) => format!("session {id}"),
+            (None, Some(ip)) => format!("ip {ip}"),
+            (None, None) => "unknown".to_string(),
+        })
+    }
+}
+
+/// Limits each client's requests to `routes`, answering over-limit ones
+/// with a 429 page; `routes` is returned as is when the limit is off.
+pub fn limit(routes: Router<AppState>, config: &RateLimitConfig) -> Router<AppState> {
+    let Some(period) = config.period() else {
+        return routes;
+    };
+    let Some(governor) = GovernorConfigBuilder::default()
+        .period(period)
+        .burst_size(config.burst)
+        .key_extractor(SessionOrIp)
+        .finish()
+    else {
+        return routes;
+    };
+
+    let limiter = Arc::downgrade(governor.limiter());
+    tokio::spawn(async move {
+        loop {
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+            let Some(limiter) = limiter.upgrade() else {
+                break;
+            };
+            limiter.retain_recent();
+        }
+    });
+
+    routes.route_layer(GovernorLayer::new(governor).error_handler(too_many_requests))
+}
+
+fn too_many_requests(error: GovernorError) -> Response {
+    match error {
+        GovernorError::TooManyRequests { wait_time, .. } => {
+            let message = format!(
+                "Too many detailed cost pages requested in a short time. \
+                 Try again in {wait_time} seconds."
+            );
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, wait_time.to_string())],
+                Html(pages::error::render(&message)),
+            )
+                .into_response()
+        }
+        error => {
+            log::error!("Rate limiting failed: {error}");
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use arc_swap::ArcSwap;
+use chrono::{DateTime, Utc};
+
+use crate::config::{load_config, AppConfig};
+use crate::logging;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Extensions the `config` crate tries after a file name without one.
+const EXTENSIONS: [&str; 7] = ["toml", "json", "yaml", "yml", "ini", "ron", "json5"];
+
+/// The latest reload, for the diagnostics page.
+static LAST_RELOAD: Mutex<Option<Reload>> = Mutex::new(None);
+
+/// When the config was last reloaded, and why if it was kept instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reload {
+    pub at: DateTime<Utc>,
+    pub error: Option<String>,
+}
+
+pub fn last_reload() -> Option<Reload> {
+    LAST_RELOAD.lock().unwrap().clone()
+}
+
+/// Applies the settings that live outside `AppState`: the theme and the log
+/// filter.
+pub fn apply(config: &AppConfig) {
+    templates::set_theme(config.theme.clone().into());
+    logging::set_filter(config.log_level.as_deref());
+}
+
+/// Reloads `config_file` into `config` on SIGHUP and whenever the file
+/// changes. A config that fails to load is logged and the current one kept.
+pub fn spawn(config_file: String, config: Arc<ArcSwap<AppConfig>>) {
+    tokio::spawn(async move {
+        #[cfg(unix)]
+        let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+            .expect("failed to install SIGHUP handler");
+        let mut modified = modified(&config_file);
+        loop {
+            let hangup = async {
+                #[cfg(unix)]
+                hangup.recv().await;
+                #[cfg(not(unix))]
+                std::future::pending::<()>().await;
+            };
+            tokio::select! {
+                _ = hangup => log::info!("Reloading config on SIGHUP"),
+                _ = changed(&config_file, &mut modified) => {
+                    log::info!("Reloading changed config file")
+                }
+            }
+            reload(&config_file, &config).await;
+        }
+    });
+}
+
+async fn reload(config_file: &str, config: &ArcSwap<AppConfig>) {
+    let next = match load_config(config_file).await {
+        Ok(next) => next,
+        Err(e) => {
+            log::error!("Keeping the current config: {e:#}");
+            record(Some(format!("{e:#}")));
+            return;
+        }
+    };
+    let restart = config.load().restart_required(&next);
+    if !restart.is_empty() {
+        log::warn!(
+            "Changes to {} take effect after a restart",
+            restart.join(", ")
+        );
+    }
+    apply(&next);
+    config.store(Arc::new(next));
+    record(None);
+}
+
+fn record(error: Option<String>) {
+    *LAST_RELOAD.lock().unwrap() = Some(Reload {
+        at: Utc::now(),
+        error,
+    });
+}
+
+/// Waits until the file's modification time differs from `last`.
+async fn changed(config_file: &str, last: &mut Option<SystemTime>) {
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+        let now = modified(config_file);
+        if now != *last {
+            *last = now;
+            return;
+        }
+    }
+}
+
+fn modified(config_file: &str) -> Option<SystemTime> {
+    path(config_file)?.metadata().ok()?.modified().ok()
+}
+
+/// The file `config::File::with_name(config_file)` reads.
+fn path(config_file: &str) -> Option<PathBuf> {
+    let name = Path::new(config_file);
+    if name.is_file() {
+        return Some(name.to_path_buf());
+    }
+    EXTENSIONS
+        .iter()
+        .map(|ext| name.with_extension(ext))
+        .find(|path| path.is_file())
+}
@@ -0,0 +1,65 @@
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const HEADER: &str = "x-request-id";
+
+/// Longest incoming ID kept; longer ones are replaced.
+const MAX_LEN: usize = 64;
+
+/// The caller's ID when it is short and plain, so a load balancer's logs
+/// line up with ours; a new UUID otherwise.
+fn resolve(incoming: Option<&HeaderValue>) -> String {
+    incoming
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| {
+            !id.is_empty()
+                && id.len() <= MAX_LEN
+                && id
+                    .bytes()
+                    .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.'))
+        })
+        .map(str::to_string)
+        .unwrap_or_else(|| Uuid::new_v4().to_string())
+}
+
+/// Runs the request in a span carrying its ID, so every log line it writes
+/// has the ID, and returns the ID in the `X-Request-Id` response header.
+pub async fn request_id(req: Request, next: Next) -> Response {
+    let id = resolve(req.headers().get(HEADER));
+    let span = tracing::info_span!(
+        "request",
+        request_id = %id,
+        method = %req.method(),
+        path = %req.uri().path(),
+        email = tracing::field::Empty
+    );
+    let mut response = next.run(req).instrument(span).await;
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(HEADER, value);
+    }
+    response
+}
+
+/// Adds the signed-in user's email to the current request's span.
+pub fn record_email(email: &str) {
+    tracing::Span::current().record("email", email);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn keeps_plain_ids_and_replaces_others() {
+        let id = |s: &str| resolve(Some(&HeaderValue::from_str(s).unwrap()));
+        assert_eq!(id("req-123_a.b"), "req-123_a.b");
+        assert_ne!(id("has space"), "has space");
+        assert_ne!(id(&"a".repeat(65)), "a".repeat(65));
+        assert!(Uuid::parse_str(&id("")).is_ok());
+        assert!(Uuid::parse_str(&resolve(None)).is_ok());
+    }
+}
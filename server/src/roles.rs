@@ -0,0 +1,85 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Access level of a logged-in user. Loaded from the `roles` table at login
+/// and kept in the session; users without a row are `SelfOnly`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Everything.
+    Admin,
+    /// All cost data, including per-user breakdowns.
+    Finance,
+    /// Aggregate cost data across users, but only their own user pages.
+    Viewer,
+    /// Only their own costs.
+    #[default]
+    SelfOnly,
+}
+
+impl Role {
+    /// Whether totals and model breakdowns cover every user's costs.
+    pub fn sees_all_costs(self) -> bool {
+        !matches!(self, Role::SelfOnly)
+    }
+
+    /// Whether other users' individual costs are visible.
+    pub fn sees_all_users(self) -> bool {
+        matches!(self, Role::Admin | Role::Finance)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::Finance => "finance",
+            Role::Viewer => "viewer",
+            Role::SelfOnly => "self_only",
+        }
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "admin" => Ok(Role::Admin),
+            "finance" => Ok(Role::Finance),
+            "viewer" => Ok(Role::Viewer),
+            "self_only" | "self-only" => Ok(Role::SelfOnly),
+            other => Err(anyhow::anyhow!("unknown role: {other}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_roles() {
+        assert_eq!("admin".parse::<Role>().unwrap(), Role::Admin);
+        assert_eq!("Finance".parse::<Role>().unwrap(), Role::Finance);
+        assert_eq!("viewer".parse::<Role>().unwrap(), Role::Viewer);
+        assert_eq!("self-only".parse::<Role>().unwrap(), Role::SelfOnly);
+        assert!("root".parse::<Role>().is_err());
+    }
+
+    #[test]
+    fn as_str_round_trips() {
+        for role in [Role::Admin, Role::Finance, Role::Viewer, Role::SelfOnly] {
+            assert_eq!(role.as_str().parse::<Role>().unwrap(), role);
+        }
+    }
+
+    #[test]
+    fn visibility() {
+        assert!(Role::Admin.sees_all_users());
+        assert!(Role::Finance.sees_all_users());
+        assert!(!Role::Viewer.sees_all_users());
+        assert!(Role::Viewer.sees_all_costs());
+        assert!(!Role::SelfOnly.sees_all_costs());
+        assert!(!Role::SelfOnly.sees_all_users());
+    }
+}
@@ -1,80 +1,716 @@
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
 use async_trait::async_trait;
-use chrono::NaiveDate;
-use common::{CostByModel, CostByUser, CostRecord, ModelInfo, UserInfo};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use common::{
+    Amount, ApiKeyInfo, BatchRequest, CostAdjustment, CostAlert, CostByAccount, CostByApiKey, CostByModel, CostByRegion, CostByUser, CostMatrix, CostRecord, CostRow,
+    DataFreshness, InferenceProfileInfo, Label, Metric, ModelCostRow, ModelFamily, ModelInfo, ModelPrice, PoolStatus, ReportKind, ReportSchedule, ReportSubscription, ServiceCostRow,
+    SpendLimit, TableStats, TokenUsageRow, UserCostRow, UserInfo, UserTokenUsage, AWS_SOURCE, CREDIT_SOURCE,
+};
+use serde::Deserialize;
 use sqlx::PgPool;
 use uuid::Uuid;
 
+use crate::live_cache::{CacheStats, LiveCache, LiveKey, Lookup};
+
+/// Most results per kind returned by the header search.
+const SEARCH_LIMIT: i64 = 20;
+
+/// Days in the moving average shown alongside daily cost.
+pub const MOVING_AVERAGE_DAYS: i64 = 7;
+
+/// Cost and listing queries return the upstream error instead of an empty
+/// result, so pages can report an outage rather than "no cost data".
 #[async_trait]
 pub trait CostService: Send + Sync {
     async fn health_check(&self) -> Result<(), String>;
-    async fn get_daily_cost(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostRecord>;
-    async fn get_monthly_cost(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostRecord>;
-    async fn get_cost_by_user(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostByUser>;
-    async fn get_cost_by_model(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostByModel>;
+    /// Today in the configured time zone, the day periods end on.
+    fn today(&self) -> NaiveDate;
+    /// The gateway whose cost the service reads, by tenant name.
+    fn tenant(&self) -> &str;
+    /// The same service with its cost queries narrowed to `source`, or
+    /// across all sources when `None`.
+    fn with_source(&self, source: Option<&str>) -> Arc<dyn CostService>;
+    /// Sources with stored cost, for the source filter.
+    async fn list_sources(&self) -> Result<Vec<String>>;
+    async fn get_daily_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_monthly_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
+    /// One record per ISO week, dated by its Monday.
+    async fn get_weekly_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_cost_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByUser>>;
+    async fn get_cost_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByModel>>;
     async fn get_cost_by_model_for_user(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostByModel>;
+        metric: Metric,
+    ) -> Result<Vec<CostByModel>>;
     async fn get_cost_by_user_for_model(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostByUser>;
+        metric: Metric,
+    ) -> Result<Vec<CostByUser>>;
     async fn get_daily_cost_for_user(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
     async fn get_monthly_cost_for_user(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_weekly_cost_for_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
     async fn get_daily_cost_for_model(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
     async fn get_monthly_cost_for_model(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
     async fn get_daily_cost_for_user_and_model(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
         model_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
     async fn get_monthly_cost_for_user_and_model(
         &self,
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
         model_id: &str,
-    ) -> Vec<CostRecord>;
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>>;
+    async fn get_daily_cost_by_service(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<ServiceCostRow>>;
+    /// One row per day and model, by date then highest cost first.
+    async fn get_daily_cost_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<ModelCostRow>>;
+    /// One row per day and user, by date then highest cost first.
+    async fn get_daily_cost_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<UserCostRow>>;
+    async fn get_cost_matrix(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<CostMatrix>;
+    async fn get_cost_by_region(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByRegion>>;
+    async fn get_cost_by_account(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByAccount>>;
+    /// The user's cost per API key, read live from CE; empty when the data
+    /// source has no AWS cost.
+    async fn get_cost_by_api_key_for_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<CostByApiKey>>;
     async fn get_user_email(&self, user_id: &str) -> Option<String>;
     async fn get_model_name(&self, model_id: &str) -> Option<String>;
-    async fn list_users(&self) -> Vec<(String, String)>;
-    async fn list_models(&self) -> Vec<(String, String)>;
+    async fn list_users(&self) -> Result<Vec<(String, String)>>;
+    async fn list_models(&self) -> Result<Vec<(String, String)>>;
+    /// `(user_id, email)` pairs whose email or id contains `query`.
+    async fn search_users(&self, query: &str) -> Result<Vec<(String, String)>>;
+    /// `(model_id, name)` pairs whose name or id contains `query`.
+    async fn search_models(&self, query: &str) -> Result<Vec<(String, String)>>;
     async fn get_user_id_by_email(&self, email: &str) -> Option<String>;
-    async fn list_users_enriched(&self) -> Vec<UserInfo>;
+    async fn list_users_enriched(&self) -> Result<Vec<UserInfo>>;
     async fn get_user_info(&self, user_id: &str) -> Option<UserInfo>;
-    async fn list_models_enriched(&self) -> Vec<ModelInfo>;
+    async fn list_models_enriched(&self) -> Result<Vec<ModelInfo>>;
     async fn get_model_info(&self, model_id: &str) -> Option<ModelInfo>;
+    async fn list_api_keys_for_user(&self, user_id: &str) -> Result<Vec<ApiKeyInfo>>;
+    async fn list_profiles_for_user(&self, user_id: &str) -> Result<Vec<InferenceProfileInfo>>;
+    async fn list_profiles_for_model(&self, model_id: &str) -> Result<Vec<InferenceProfileInfo>>;
+    async fn get_role(&self, email: &str) -> Option<String>;
+    /// Every monthly spend limit, with user emails filled in.
+    async fn list_spend_limits(&self) -> Result<Vec<SpendLimit>>;
+    async fn get_spend_limit(&self, user_id: &str) -> Result<Option<SpendLimit>>;
+    /// Sets a user's monthly limit, or removes it when `None`.
+    async fn set_spend_limit(&self, user_id: &str, monthly_limit: Option<Amount>) -> Result<()>;
+    /// A user's own cost alert, if they set one.
+    async fn get_cost_alert(&self, user_id: &str) -> Result<Option<CostAlert>>;
+    /// Sets a user's alert thresholds; removes the alert when both are `None`.
+    async fn set_cost_alert(
+        &self,
+        user_id: &str,
+        daily: Option<Amount>,
+        monthly: Option<Amount>,
+    ) -> Result<()>;
+    /// Every model price, with model names filled in.
+    async fn list_model_prices(&self) -> Result<Vec<ModelPrice>>;
+    async fn set_model_price(&self, price: &ModelPrice) -> Result<()>;
+    async fn delete_model_price(&self, model_id: &str, effective_from: NaiveDate) -> Result<()>;
+    async fn list_model_families(&self) -> Result<Vec<ModelFamily>>;
+    async fn set_model_family(&self, rule: &ModelFamily) -> Result<()>;
+    async fn delete_model_family(&self, prefix: &str) -> Result<()>;
+    async fn list_labels(&self) -> Result<Vec<Label>>;
+    async fn add_label(&self, label: &Label) -> Result<()>;
+    async fn remove_label(&self, label: &Label) -> Result<()>;
+    async fn list_report_subscriptions(&self) -> Result<Vec<ReportSubscription>>;
+    async fn add_report_subscription(
+        &self,
+        recipient: &str,
+        kind: ReportKind,
+        schedule: ReportSchedule,
+    ) -> Result<()>;
+    async fn delete_report_subscription(&self, id: i64) -> Result<()>;
+    /// Daily token usage per model. Always from CE: the cost table has no
+    /// token counts.
+    async fn get_token_usage_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<TokenUsageRow>>;
+    /// Token usage per user over `[start, end)`, from CE like the per-model
+    /// usage.
+    async fn get_token_usage_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<UserTokenUsage>>;
+    /// Manual cost adjustments in `[start, end)`, with user emails and
+    /// model names filled in.
+    async fn list_adjustments(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<CostAdjustment>>;
+    async fn set_adjustment(&self, adjustment: &CostAdjustment) -> Result<()>;
+    async fn delete_adjustment(
+        &self,
+        date: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+    ) -> Result<()>;
+    /// Stores cost rows from a provider other than AWS.
+    async fn import_costs(&self, rows: &[CostRow]) -> Result<()>;
+    /// Rebuilds the whole monthly rollup from the cost table.
+    async fn refresh_monthly_rollup(&self) -> Result<()>;
+    /// When the batch last ran and the monthly rollup was last rebuilt.
+    async fn data_freshness(&self) -> Result<DataFreshness>;
+    /// Records a change made on the pages, so cached pages are rendered
+    /// again.
+    async fn record_edit(&self) -> Result<()>;
+    /// Queues an incremental batch run for this tenant's batch daemon.
+    async fn request_batch_run(&self, requested_by: &str) -> Result<()>;
+    /// The `limit` latest batch runs requested for this tenant, newest first.
+    async fn list_batch_requests(&self, limit: i64) -> Result<Vec<BatchRequest>>;
+    /// Fetches the last few days of `metric` from CE into the cost table
+    /// right away; returns the number of rows stored.
+    async fn refresh_costs(&self, metric: Metric) -> Result<usize>;
+    /// When the live CE part of `[start, end)` was fetched; `None` when the
+    /// range is read from the cost table only or was never fetched.
+    async fn data_as_of(&self, start: NaiveDate, end: NaiveDate, metric: Metric) -> Option<DateTime<Utc>>;
+    /// Storage, cache and CE usage figures for the admin diagnostics page.
+    async fn diagnostics(&self) -> Diagnostics;
+}
+
+/// What the admin diagnostics page shows about the service.
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics {
+    pub data_source: DataSource,
+    pub tables: Vec<TableStats>,
+    /// Why the table figures could not be read.
+    pub tables_error: Option<String>,
+    pub cost_pool: PoolStatus,
+    pub gateway_pool: PoolStatus,
+    pub live_cache: CacheStats,
+    pub ce_calls: ce::CallStats,
 }
 
+/// Where cost figures come from: the batch-populated `cost` table, live
+/// Cost Explorer queries, or the table for past days and CE for today.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataSource {
+    #[default]
+    Db,
+    Ce,
+    Hybrid,
+}
+
+/// Today's date as the pages' periods see it.
+pub type Clock = Arc<dyn Fn() -> NaiveDate + Send + Sync>;
+
+#[derive(Clone)]
 pub struct RealCostService {
+    /// Gateway DB of `tenant`.
     pub pool: PgPool,
-    pub cost_pool: PgPool,
+    /// The gateway whose cost rows the service reads and writes.
+    pub tenant: String,
+    /// Drops live CE rows of users and models `pool` does not know, as CE
+    /// bills every tenant's traffic to the same accounts.
+    pub filter_live: bool,
+    pub cost_db: Arc<dyn db::CostStore>,
+    pub data_source: DataSource,
+    /// Hours after a day ends before its stored rows are trusted in hybrid
+    /// mode; see [`RealCostService::split_range`].
+    pub settlement_hours: i64,
+    /// Live rows for the `Ce` and `Hybrid` sources, and the service
+    /// breakdown, which the cost table does not store. Summed over all
+    /// configured payer accounts.
+    pub ce_clients: ce::Clients,
+    /// Live rows, served stale while a background task refreshes them.
+    pub live_cache: Arc<LiveCache>,
+    /// Today, from which hybrid mode fetches live; see [`split`].
+    pub clock: Clock,
+    /// Narrows cost queries to one source; see [`CostService::with_source`].
+    pub source: Option<String>,
+}
+
+impl RealCostService {
+    /// Whether the service's source includes rows that CE reports, gross
+    /// cost or credits.
+    fn includes_aws(&self) -> bool {
+        self.source
+            .as_deref()
+            .is_none_or(|source| source == AWS_SOURCE || source == CREDIT_SOURCE)
+    }
+
+    /// Splits `[start, end)` into the part read from the cost table and the
+    /// part fetched live from CE. Hybrid mode also fetches every day from the
+    /// first one whose rows were stored before it settled, as CE restates
+    /// figures for a while after the fact. Other sources are only stored.
+    async fn split_range(&self, start: NaiveDate, end: NaiveDate) -> Result<Split> {
+        let today = (self.clock)();
+        if !self.includes_aws() {
+            return Ok(split(DataSource::Db, start, end, today, None));
+        }
+        let unsettled = match self.data_source {
+            DataSource::Hybrid => self
+                .cost_db
+                .first_unsettled_date(&self.tenant, start, end.min(today), self.settlement_hours)
+                .await
+                .context("Failed to query cost table freshness")?,
+            _ => None,
+        };
+        Ok(split(self.data_source, start, end, today, unsettled))
+    }
+
+    async fn live_rows(
+        &self,
+        range: Option<(NaiveDate, NaiveDate)>,
+        user_id: Option<&str>,
+        model_id: Option<&str>,
+        metric: Metric,
+    ) -> Result<Vec<CostRow>> {
+        let Some((start, end)) = range else {
+            return Ok(Vec::new());
+        };
+        // Only days never fetched are waited on; stale ones are served and
+        // refreshed in the background.
+        let Lookup {
+            mut rows,
+            missing,
+            stale,
+        } = self
+            .live_cache
+            .lookup(&(self.tenant.clone(), start, end, metric), Utc::now());
+        if !stale.is_empty() {
+            let clients = self.ce_clients.clone();
+            let pool = self.pool.clone();
+            let filter = self.filter_live;
+            let cache = self.live_cache.clone();
+            tokio::spawn(async move {
+                for key in stale {
+                    match fetch_live(&clients, &pool, filter, key.clone()).await {
+                        Ok(rows) => cache.store(key, rows, Utc::now()),
+                        Err(e) => {
+                            log::error!("Failed to refresh live cost: {e:#}");
+                            cache.refresh_failed(&key);
+                        }
+                    }
+                }
+            });
+        }
+        for key in missing {
+            let fetched = self
+                .live_cache
+                .fetch_once(key.clone(), || {
+                    fetch_live(&self.ce_clients, &self.pool, self.filter_live, key)
+                })
+                .await?;
+            rows.extend(fetched);
+        }
+        rows.retain(|r| {
+            user_id.is_none_or(|id| r.user_id == id)
+                && model_id.is_none_or(|id| r.model_id == id)
+                && self.source.as_deref().is_none_or(|source| r.source == source)
+        });
+        Ok(rows)
+    }
+
+    /// Resolves all emails with one gateway query instead of one per row.
+    async fn fill_user_emails(&self, costs: &mut [CostByUser]) {
+        let ids: Vec<Uuid> = costs
+            .iter()
+            .filter_map(|c| Uuid::parse_str(&c.user_id).ok())
+            .collect();
+        let emails = db::get_user_emails(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query user emails: {e}");
+                HashMap::new()
+            });
+        for cost in costs {
+            cost.user_email = Uuid::parse_str(&cost.user_id)
+                .ok()
+                .and_then(|id| emails.get(&id).cloned());
+        }
+    }
+
+    async fn fill_model_names(&self, costs: &mut [CostByModel]) {
+        let ids: Vec<Uuid> = costs
+            .iter()
+            .filter_map(|c| Uuid::parse_str(&c.model_id).ok())
+            .collect();
+        let names = db::get_model_names(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for cost in costs {
+            cost.model_name = Uuid::parse_str(&cost.model_id)
+                .ok()
+                .and_then(|id| names.get(&id).cloned());
+        }
+    }
+}
+
+/// The part of a range read from the cost table and the part fetched live.
+type Split = (
+    Option<(NaiveDate, NaiveDate)>,
+    Option<(NaiveDate, NaiveDate)>,
+);
+
+/// Splits `[start, end)` for reading from `source`. Every source covers the
+/// same days; hybrid mode fetches those from the first `unsettled` one, or
+/// from `today`, live.
+fn split(
+    source: DataSource,
+    start: NaiveDate,
+    end: NaiveDate,
+    today: NaiveDate,
+    unsettled: Option<NaiveDate>,
+) -> Split {
+    let non_empty = |s: NaiveDate, e: NaiveDate| (s < e).then_some((s, e));
+    match source {
+        DataSource::Db => (non_empty(start, end), None),
+        DataSource::Ce => (None, non_empty(start, end)),
+        DataSource::Hybrid => {
+            let boundary = unsettled.map_or(today, |date| date.min(today));
+            (
+                non_empty(start, end.min(boundary)),
+                non_empty(start.max(boundary), end),
+            )
+        }
+    }
+}
+
+/// Every user's and model's daily gross cost and credits in the key's
+/// range, from CE, as rows of the key's tenant. With `filter`, only those
+/// of users and models in the tenant's `gateway` DB.
+async fn fetch_live(
+    clients: &ce::Clients,
+    gateway: &PgPool,
+    filter: bool,
+    key: LiveKey,
+) -> Result<Vec<CostRow>> {
+    let (tenant, start, end, metric) = key;
+    let start = start.format("%Y-%m-%d").to_string();
+    let end = end.format("%Y-%m-%d").to_string();
+    let (mut rows, _calls) =
+        ce::get_daily_gross_cost_and_credits_counted(clients, &start, &end, &[metric])
+            .await
+            .context("Failed to fetch cost from CE")?;
+    if filter {
+        let known = ingest::KnownIds::load(gateway, &tenant)
+            .await
+            .context("Failed to query gateway users and models")?;
+        rows.retain(|r| known.users.contains(&r.user_id) && known.models.contains(&r.model_id));
+    }
+    for row in &mut rows {
+        row.tenant = tenant.clone();
+    }
+    Ok(rows)
+}
+
+fn records_by<F>(rows: &[CostRow], key: F) -> Vec<CostRecord>
+where
+    F: Fn(&CostRow) -> String,
+{
+    let mut totals: BTreeMap<String, (Amount, String)> = BTreeMap::new();
+    for row in rows {
+        let entry = totals
+            .entry(key(row))
+            .or_insert_with(|| (Amount::ZERO, row.currency.clone()));
+        entry.0 += row.amount;
+    }
+    totals
+        .into_iter()
+        .map(|(date, (amount, currency))| CostRecord {
+            date,
+            amount,
+            currency,
+        })
+        .collect()
+}
+
+fn daily_records(rows: &[CostRow]) -> Vec<CostRecord> {
+    records_by(rows, |r| r.date.format("%Y-%m-%d").to_string())
+}
+
+fn monthly_records(rows: &[CostRow]) -> Vec<CostRecord> {
+    records_by(rows, |r| format!("{:04}-{:02}-01", r.date.year(), r.date.month()))
+}
+
+fn weekly_records(rows: &[CostRow]) -> Vec<CostRecord> {
+    records_by(rows, |r| {
+        let monday = r.date - chrono::Duration::days(r.date.weekday().num_days_from_monday() as i64);
+        monday.format("%Y-%m-%d").to_string()
+    })
+}
+
+fn totals_by<F>(rows: &[CostRow], key: F) -> Vec<(String, Amount, String)>
+where
+    F: Fn(&CostRow) -> &str,
+{
+    let mut totals: HashMap<&str, (Amount, &str)> = HashMap::new();
+    for row in rows {
+        let entry = totals
+            .entry(key(row))
+            .or_insert((Amount::ZERO, row.currency.as_str()));
+        entry.0 += row.amount;
+    }
+    totals
+        .into_iter()
+        .map(|(id, (amount, currency))| (id.to_string(), amount, currency.to_string()))
+        .collect()
+}
+
+fn by_user(rows: &[CostRow]) -> Vec<CostByUser> {
+    totals_by(rows, |r| &r.user_id)
+        .into_iter()
+        .map(|(user_id, amount, currency)| CostByUser {
+            user_id,
+            user_email: None,
+            amount,
+            currency,
+        })
+        .collect()
+}
+
+fn by_model(rows: &[CostRow]) -> Vec<CostByModel> {
+    totals_by(rows, |r| &r.model_id)
+        .into_iter()
+        .map(|(model_id, amount, currency)| CostByModel {
+            model_id,
+            model_name: None,
+            amount,
+            currency,
+        })
+        .collect()
+}
+
+fn daily_by_model(rows: &[CostRow]) -> Vec<ModelCostRow> {
+    let mut totals: BTreeMap<(NaiveDate, &str), (Amount, &str)> = BTreeMap::new();
+    for row in rows {
+        let entry = totals
+            .entry((row.date, row.model_id.as_str()))
+            .or_insert((Amount::ZERO, row.currency.as_str()));
+        entry.0 += row.amount;
+    }
+    let mut costs: Vec<ModelCostRow> = totals
+        .into_iter()
+        .map(|((date, model_id), (amount, currency))| ModelCostRow {
+            date,
+            model_id: model_id.to_string(),
+            model_name: None,
+            amount,
+            currency: currency.to_string(),
+        })
+        .collect();
+    costs.sort_by_key(|c| (c.date, Reverse(c.amount)));
+    costs
+}
+
+fn daily_by_user(rows: &[CostRow]) -> Vec<UserCostRow> {
+    let mut totals: BTreeMap<(NaiveDate, &str), (Amount, &str)> = BTreeMap::new();
+    for row in rows {
+        let entry = totals
+            .entry((row.date, row.user_id.as_str()))
+            .or_insert((Amount::ZERO, row.currency.as_str()));
+        entry.0 += row.amount;
+    }
+    let mut costs: Vec<UserCostRow> = totals
+        .into_iter()
+        .map(|((date, user_id), (amount, currency))| UserCostRow {
+            date,
+            user_id: user_id.to_string(),
+            user_email: None,
+            amount,
+            currency: currency.to_string(),
+        })
+        .collect();
+    costs.sort_by_key(|c| (c.date, Reverse(c.amount)));
+    costs
+}
+
+/// Average cost of the `days` days ending on each record's date, for the
+/// records dated `from` or later. Days without a record count as zero, but
+/// the window never reaches back before the first record.
+pub fn moving_average(daily: &[CostRecord], from: NaiveDate, days: i64) -> Vec<CostRecord> {
+    let costs: BTreeMap<NaiveDate, Amount> = daily
+        .iter()
+        .filter_map(|r| Some((NaiveDate::parse_from_str(&r.date, "%Y-%m-%d").ok()?, r.amount)))
+        .collect();
+    let (Some(&first), Some(record)) = (costs.keys().next(), daily.first()) else {
+        return Vec::new();
+    };
+    costs
+        .range(from..)
+        .map(|(&date, _)| {
+            let window_start = (date - Duration::days(days - 1)).max(first);
+            let total: Amount = costs.range(window_start..=date).map(|(_, a)| *a).sum();
+            let span = (date - window_start).num_days() + 1;
+            CostRecord {
+                date: date.format("%Y-%m-%d").to_string(),
+                amount: Amount::from_micros(total.micros() / span),
+                currency: record.currency.clone(),
+            }
+        })
+        .collect()
+}
+
+/// Sums records sharing a date; the result is ordered by date like the DB
+/// queries.
+fn merge_records(stored: Vec<CostRecord>, live: Vec<CostRecord>) -> Vec<CostRecord> {
+    if live.is_empty() {
+        return stored;
+    }
+    let mut merged: BTreeMap<String, CostRecord> = BTreeMap::new();
+    for record in stored.into_iter().chain(live) {
+        match merged.get_mut(&record.date) {
+            Some(existing) => existing.amount += record.amount,
+            None => {
+                merged.insert(record.date.clone(), record);
+            }
+        }
+    }
+    merged.into_values().collect()
+}
+
+/// Sums entries sharing a user; the result is ordered by amount, highest
+/// first, like the DB queries.
+fn merge_by_user(stored: Vec<CostByUser>, live: Vec<CostByUser>) -> Vec<CostByUser> {
+    if live.is_empty() {
+        return stored;
+    }
+    let mut merged: Vec<CostByUser> = Vec::with_capacity(stored.len() + live.len());
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for cost in stored.into_iter().chain(live) {
+        match index.get(&cost.user_id) {
+            Some(&i) => merged[i].amount += cost.amount,
+            None => {
+                index.insert(cost.user_id.clone(), merged.len());
+                merged.push(cost);
+            }
+        }
+    }
+    merged.sort_by_key(|c| Reverse(c.amount));
+    merged
+}
+
+/// Model counterpart of [`merge_by_user`].
+fn merge_by_model(stored: Vec<CostByModel>, live: Vec<CostByModel>) -> Vec<CostByModel> {
+    if live.is_empty() {
+        return stored;
+    }
+    let mut merged: Vec<CostByModel> = Vec::with_capacity(stored.len() + live.len());
+    let mut index: HashMap<String, usize> = HashMap::new();
+    for cost in stored.into_iter().chain(live) {
+        match index.get(&cost.model_id) {
+            Some(&i) => merged[i].amount += cost.amount,
+            None => {
+                index.insert(cost.model_id.clone(), merged.len());
+                merged.push(cost);
+            }
+        }
+    }
+    merged.sort_by_key(|c| Reverse(c.amount));
+    merged
 }
 
 #[async_trait]
@@ -84,55 +720,121 @@ impl CostService for RealCostService {
             .fetch_one(&self.pool)
             .await
             .map_err(|e| format!("gateway db: {e}"))?;
-        sqlx::query_scalar::<_, i32>("SELECT 1")
-            .fetch_one(&self.cost_pool)
+        self.cost_db
+            .ping()
             .await
             .map_err(|e| format!("cost db: {e}"))?;
         Ok(())
     }
 
-    async fn get_daily_cost(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostRecord> {
-        db::get_daily_cost(&self.cost_pool, start, end)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query daily cost: {e}");
-                Vec::new()
-            })
+    fn tenant(&self) -> &str {
+        &self.tenant
     }
 
-    async fn get_monthly_cost(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostRecord> {
-        db::get_monthly_cost(&self.cost_pool, start, end)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query monthly cost: {e}");
-                Vec::new()
-            })
+    fn today(&self) -> NaiveDate {
+        (self.clock)()
     }
 
-    async fn get_cost_by_user(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostByUser> {
-        let mut costs = db::get_cost_by_user(&self.cost_pool, start, end)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query cost by user: {e}");
-                Vec::new()
-            });
-        for cost in &mut costs {
-            cost.user_email = self.get_user_email(&cost.user_id).await;
-        }
-        costs
+    fn with_source(&self, source: Option<&str>) -> Arc<dyn CostService> {
+        Arc::new(Self {
+            source: source.map(str::to_string),
+            ..self.clone()
+        })
     }
 
-    async fn get_cost_by_model(&self, start: NaiveDate, end: NaiveDate) -> Vec<CostByModel> {
-        let mut costs = db::get_cost_by_model(&self.cost_pool, start, end)
+    async fn list_sources(&self) -> Result<Vec<String>> {
+        self.cost_db.list_sources(&self.tenant)
             .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query cost by model: {e}");
-                Vec::new()
-            });
-        for cost in &mut costs {
-            cost.model_name = self.get_model_name(&cost.model_id).await;
-        }
-        costs
+            .context("Failed to query cost sources")
+    }
+
+    async fn get_daily_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        Ok(merge_records(stored, daily_records(&live)))
+    }
+
+    async fn get_monthly_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_monthly_cost(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query monthly cost")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        Ok(merge_records(stored, monthly_records(&live)))
+    }
+
+    async fn get_weekly_cost(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_weekly_cost(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query weekly cost")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        Ok(merge_records(stored, weekly_records(&live)))
+    }
+
+    async fn get_cost_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByUser>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_cost_by_user(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query cost by user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        let mut costs = merge_by_user(stored, by_user(&live));
+        self.fill_user_emails(&mut costs).await;
+        Ok(costs)
+    }
+
+    async fn get_cost_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByModel>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_cost_by_model(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query cost by model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        let mut costs = merge_by_model(stored, by_model(&live));
+        self.fill_model_names(&mut costs).await;
+        Ok(costs)
     }
 
     async fn get_cost_by_model_for_user(
@@ -140,17 +842,19 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostByModel> {
-        let mut costs = db::get_cost_by_model_for_user(&self.cost_pool, start, end, user_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query cost by model for user: {e}");
-                Vec::new()
-            });
-        for cost in &mut costs {
-            cost.model_name = self.get_model_name(&cost.model_id).await;
-        }
-        costs
+        metric: Metric,
+    ) -> Result<Vec<CostByModel>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_cost_by_model_for_user(&self.tenant, start, end, user_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query cost by model for user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), None, metric).await?;
+        let mut costs = merge_by_model(stored, by_model(&live));
+        self.fill_model_names(&mut costs).await;
+        Ok(costs)
     }
 
     async fn get_cost_by_user_for_model(
@@ -158,17 +862,19 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostByUser> {
-        let mut costs = db::get_cost_by_user_for_model(&self.cost_pool, start, end, model_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query cost by user for model: {e}");
-                Vec::new()
-            });
-        for cost in &mut costs {
-            cost.user_email = self.get_user_email(&cost.user_id).await;
-        }
-        costs
+        metric: Metric,
+    ) -> Result<Vec<CostByUser>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_cost_by_user_for_model(&self.tenant, start, end, model_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query cost by user for model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, Some(model_id), metric).await?;
+        let mut costs = merge_by_user(stored, by_user(&live));
+        self.fill_user_emails(&mut costs).await;
+        Ok(costs)
     }
 
     async fn get_daily_cost_for_user(
@@ -176,13 +882,17 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_daily_cost_for_user(&self.cost_pool, start, end, user_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query daily cost for user: {e}");
-                Vec::new()
-            })
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost_for_user(&self.tenant, start, end, user_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost for user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), None, metric).await?;
+        Ok(merge_records(stored, daily_records(&live)))
     }
 
     async fn get_monthly_cost_for_user(
@@ -190,13 +900,35 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         user_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_monthly_cost_for_user(&self.cost_pool, start, end, user_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query monthly cost for user: {e}");
-                Vec::new()
-            })
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_monthly_cost_for_user(&self.tenant, start, end, user_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query monthly cost for user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), None, metric).await?;
+        Ok(merge_records(stored, monthly_records(&live)))
+    }
+
+    async fn get_weekly_cost_for_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_weekly_cost_for_user(&self.tenant, start, end, user_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query weekly cost for user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), None, metric).await?;
+        Ok(merge_records(stored, weekly_records(&live)))
     }
 
     async fn get_daily_cost_for_model(
@@ -204,13 +936,17 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_daily_cost_for_model(&self.cost_pool, start, end, model_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query daily cost for model: {e}");
-                Vec::new()
-            })
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost_for_model(&self.tenant, start, end, model_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost for model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, Some(model_id), metric).await?;
+        Ok(merge_records(stored, daily_records(&live)))
     }
 
     async fn get_monthly_cost_for_model(
@@ -218,13 +954,17 @@ impl CostService for RealCostService {
         start: NaiveDate,
         end: NaiveDate,
         model_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_monthly_cost_for_model(&self.cost_pool, start, end, model_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query monthly cost for model: {e}");
-                Vec::new()
-            })
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_monthly_cost_for_model(&self.tenant, start, end, model_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query monthly cost for model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, Some(model_id), metric).await?;
+        Ok(merge_records(stored, monthly_records(&live)))
     }
 
     async fn get_daily_cost_for_user_and_model(
@@ -233,13 +973,17 @@ impl CostService for RealCostService {
         end: NaiveDate,
         user_id: &str,
         model_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_daily_cost_for_user_and_model(&self.cost_pool, start, end, user_id, model_id)
-            .await
-            .unwrap_or_else(|e| {
-                log::error!("Failed to query daily cost for user and model: {e}");
-                Vec::new()
-            })
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost_for_user_and_model(&self.tenant, start, end, user_id, model_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost for user and model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), Some(model_id), metric).await?;
+        Ok(merge_records(stored, daily_records(&live)))
     }
 
     async fn get_monthly_cost_for_user_and_model(
@@ -248,13 +992,220 @@ impl CostService for RealCostService {
         end: NaiveDate,
         user_id: &str,
         model_id: &str,
-    ) -> Vec<CostRecord> {
-        db::get_monthly_cost_for_user_and_model(&self.cost_pool, start, end, user_id, model_id)
+        metric: Metric,
+    ) -> Result<Vec<CostRecord>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_monthly_cost_for_user_and_model(&self.tenant, start, end, user_id, model_id, metric, self.source.as_deref())
+                .await
+                .context("Failed to query monthly cost for user and model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, Some(user_id), Some(model_id), metric).await?;
+        Ok(merge_records(stored, monthly_records(&live)))
+    }
+
+    async fn get_daily_cost_by_service(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<ServiceCostRow>> {
+        if !self.includes_aws() {
+            return Ok(Vec::new());
+        }
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        ce::get_daily_cost_by_service(&self.ce_clients, &start, &end, metric)
+            .await
+            .context("Failed to fetch cost by service from CE")
+    }
+
+    async fn get_cost_by_account(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByAccount>> {
+        if !self.includes_aws() {
+            return Ok(Vec::new());
+        }
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        ce::get_cost_by_linked_account(&self.ce_clients, &start, &end, metric)
+            .await
+            .context("Failed to fetch cost by account from CE")
+    }
+
+    async fn get_daily_cost_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<ModelCostRow>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let mut costs = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost_by_model(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost by model")?,
+            None => Vec::new(),
+        };
+        // The two ranges never share a day, so live rows only append.
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        costs.extend(daily_by_model(&live));
+        let ids: Vec<Uuid> = costs
+            .iter()
+            .filter_map(|c| Uuid::parse_str(&c.model_id).ok())
+            .collect();
+        let names = db::get_model_names(&self.pool, &ids)
             .await
             .unwrap_or_else(|e| {
-                log::error!("Failed to query monthly cost for user and model: {e}");
-                Vec::new()
-            })
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for cost in &mut costs {
+            cost.model_name = Uuid::parse_str(&cost.model_id)
+                .ok()
+                .and_then(|id| names.get(&id).cloned());
+        }
+        Ok(costs)
+    }
+
+    async fn get_daily_cost_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<UserCostRow>> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let mut costs = match stored_range {
+            Some((start, end)) => self.cost_db.get_daily_cost_by_user(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query daily cost by user")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        costs.extend(daily_by_user(&live));
+        let ids: Vec<Uuid> = costs
+            .iter()
+            .filter_map(|c| Uuid::parse_str(&c.user_id).ok())
+            .collect();
+        let emails = db::get_user_emails(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query user emails: {e}");
+                HashMap::new()
+            });
+        for cost in &mut costs {
+            cost.user_email = Uuid::parse_str(&cost.user_id)
+                .ok()
+                .and_then(|id| emails.get(&id).cloned());
+        }
+        Ok(costs)
+    }
+
+    async fn get_cost_matrix(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<CostMatrix> {
+        let (stored_range, live_range) = self.split_range(start, end).await?;
+        let stored = match stored_range {
+            Some((start, end)) => self.cost_db.get_cost_by_user_and_model(&self.tenant, start, end, metric, self.source.as_deref())
+                .await
+                .context("Failed to query cost by user and model")?,
+            None => Vec::new(),
+        };
+        let live = self.live_rows(live_range, None, None, metric).await?;
+        let mut matrix = CostMatrix::from_entries(
+            stored
+                .into_iter()
+                .chain(live.into_iter().map(|r| (r.user_id, r.model_id, r.amount, r.currency))),
+        );
+
+        let user_ids: Vec<Uuid> = matrix
+            .users
+            .iter()
+            .filter_map(|(id, _)| Uuid::parse_str(id).ok())
+            .collect();
+        let emails = db::get_user_emails(&self.pool, &user_ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query user emails: {e}");
+                HashMap::new()
+            });
+        for (id, email) in &mut matrix.users {
+            *email = Uuid::parse_str(id).ok().and_then(|id| emails.get(&id).cloned());
+        }
+        let model_ids: Vec<Uuid> = matrix
+            .models
+            .iter()
+            .filter_map(|(id, _)| Uuid::parse_str(id).ok())
+            .collect();
+        let names = db::get_model_names(&self.pool, &model_ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for (id, name) in &mut matrix.models {
+            *name = Uuid::parse_str(id).ok().and_then(|id| names.get(&id).cloned());
+        }
+        Ok(matrix)
+    }
+
+    async fn get_cost_by_region(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        metric: Metric,
+    ) -> Result<Vec<CostByRegion>> {
+        if !self.includes_aws() {
+            return Ok(Vec::new());
+        }
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        let mut costs = ce::get_cost_by_region(&self.ce_clients, &start, &end, metric)
+            .await
+            .context("Failed to fetch cost by region from CE")?;
+        let ids: Vec<Uuid> = costs
+            .iter()
+            .filter_map(|c| Uuid::parse_str(&c.model_id).ok())
+            .collect();
+        let names = db::get_model_names(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for cost in &mut costs {
+            cost.model_name = Uuid::parse_str(&cost.model_id)
+                .ok()
+                .and_then(|id| names.get(&id).cloned());
+        }
+        if self.filter_live {
+            // Models of the other tenants' gateways have no name here.
+            costs.retain(|c| c.model_name.is_some());
+        }
+        Ok(costs)
+    }
+
+    async fn get_cost_by_api_key_for_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+        user_id: &str,
+        metric: Metric,
+    ) -> Result<Vec<CostByApiKey>> {
+        if !self.includes_aws() {
+            return Ok(Vec::new());
+        }
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        ce::get_cost_by_api_key(&self.ce_clients, &start, &end, user_id, metric)
+            .await
+            .context("Failed to fetch cost by API key from CE")
     }
 
     async fn get_user_email(&self, user_id: &str) -> Option<String> {
@@ -267,22 +1218,40 @@ impl CostService for RealCostService {
         db::get_model_name(&self.pool, uuid).await
     }
 
-    async fn list_users(&self) -> Vec<(String, String)> {
-        db::list_users(&self.pool)
+    async fn list_users(&self) -> Result<Vec<(String, String)>> {
+        Ok(db::list_users(&self.pool)
             .await
-            .unwrap_or_default()
+            .context("Failed to query users")?
             .into_iter()
             .map(|(id, email)| (id.to_string(), email))
-            .collect()
+            .collect())
     }
 
-    async fn list_models(&self) -> Vec<(String, String)> {
-        db::list_models(&self.pool)
+    async fn list_models(&self) -> Result<Vec<(String, String)>> {
+        Ok(db::list_models(&self.pool)
             .await
-            .unwrap_or_default()
+            .context("Failed to query models")?
             .into_iter()
             .map(|(id, name)| (id.to_string(), name))
-            .collect()
+            .collect())
+    }
+
+    async fn search_users(&self, query: &str) -> Result<Vec<(String, String)>> {
+        Ok(db::search_users(&self.pool, query, SEARCH_LIMIT)
+            .await
+            .context("Failed to search users")?
+            .into_iter()
+            .map(|(id, email)| (id.to_string(), email))
+            .collect())
+    }
+
+    async fn search_models(&self, query: &str) -> Result<Vec<(String, String)>> {
+        Ok(db::search_models(&self.pool, query, SEARCH_LIMIT)
+            .await
+            .context("Failed to search models")?
+            .into_iter()
+            .map(|(id, name)| (id.to_string(), name))
+            .collect())
     }
 
     async fn get_user_id_by_email(&self, email: &str) -> Option<String> {
@@ -291,10 +1260,10 @@ impl CostService for RealCostService {
             .map(|uuid| uuid.to_string())
     }
 
-    async fn list_users_enriched(&self) -> Vec<UserInfo> {
+    async fn list_users_enriched(&self) -> Result<Vec<UserInfo>> {
         db::list_users_enriched(&self.pool)
             .await
-            .unwrap_or_default()
+            .context("Failed to query users")
     }
 
     async fn get_user_info(&self, user_id: &str) -> Option<UserInfo> {
@@ -302,14 +1271,519 @@ impl CostService for RealCostService {
         db::get_user_info(&self.pool, uuid).await
     }
 
-    async fn list_models_enriched(&self) -> Vec<ModelInfo> {
+    async fn list_models_enriched(&self) -> Result<Vec<ModelInfo>> {
         db::list_models_enriched(&self.pool)
             .await
-            .unwrap_or_default()
+            .context("Failed to query models")
     }
 
     async fn get_model_info(&self, model_id: &str) -> Option<ModelInfo> {
         let uuid = Uuid::parse_str(model_id).ok()?;
         db::get_model_info(&self.pool, uuid).await
     }
+
+    async fn list_api_keys_for_user(&self, user_id: &str) -> Result<Vec<ApiKeyInfo>> {
+        let Ok(uuid) = Uuid::parse_str(user_id) else {
+            return Ok(Vec::new());
+        };
+        db::list_api_keys_for_user(&self.pool, uuid)
+            .await
+            .context("Failed to query API keys")
+    }
+
+    async fn list_profiles_for_user(&self, user_id: &str) -> Result<Vec<InferenceProfileInfo>> {
+        let Ok(uuid) = Uuid::parse_str(user_id) else {
+            return Ok(Vec::new());
+        };
+        db::list_profiles_for_user(&self.pool, uuid)
+            .await
+            .context("Failed to query inference profiles")
+    }
+
+    async fn list_profiles_for_model(&self, model_id: &str) -> Result<Vec<InferenceProfileInfo>> {
+        let Ok(uuid) = Uuid::parse_str(model_id) else {
+            return Ok(Vec::new());
+        };
+        db::list_profiles_for_model(&self.pool, uuid)
+            .await
+            .context("Failed to query inference profiles")
+    }
+
+    async fn get_role(&self, email: &str) -> Option<String> {
+        self.cost_db.get_role(email)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query role: {e}");
+                None
+            })
+    }
+
+    async fn list_spend_limits(&self) -> Result<Vec<SpendLimit>> {
+        let mut limits = self.cost_db.list_spend_limits()
+            .await
+            .context("Failed to query spend limits")?;
+        let ids: Vec<Uuid> = limits
+            .iter()
+            .filter_map(|l| Uuid::parse_str(&l.user_id).ok())
+            .collect();
+        let emails = db::get_user_emails(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query user emails: {e}");
+                HashMap::new()
+            });
+        for limit in &mut limits {
+            limit.user_email = Uuid::parse_str(&limit.user_id)
+                .ok()
+                .and_then(|id| emails.get(&id).cloned());
+        }
+        limits.sort_by(|a, b| a.user_email.cmp(&b.user_email));
+        Ok(limits)
+    }
+
+    async fn get_spend_limit(&self, user_id: &str) -> Result<Option<SpendLimit>> {
+        self.cost_db.get_spend_limit(user_id)
+            .await
+            .context("Failed to query spend limit")
+    }
+
+    async fn set_spend_limit(&self, user_id: &str, monthly_limit: Option<Amount>) -> Result<()> {
+        match monthly_limit {
+            Some(limit) => self.cost_db.set_spend_limit(user_id, limit).await,
+            None => self.cost_db.delete_spend_limit(user_id).await,
+        }
+        .context("Failed to save spend limit")
+    }
+
+    async fn get_cost_alert(&self, user_id: &str) -> Result<Option<CostAlert>> {
+        self.cost_db.get_cost_alert(user_id)
+            .await
+            .context("Failed to query cost alert")
+    }
+
+    async fn set_cost_alert(
+        &self,
+        user_id: &str,
+        daily: Option<Amount>,
+        monthly: Option<Amount>,
+    ) -> Result<()> {
+        self.cost_db.set_cost_alert(user_id, daily, monthly)
+            .await
+            .context("Failed to save cost alert")
+    }
+
+    async fn list_model_prices(&self) -> Result<Vec<ModelPrice>> {
+        let mut prices = self.cost_db.list_model_prices()
+            .await
+            .context("Failed to query model prices")?;
+        let ids: Vec<Uuid> = prices
+            .iter()
+            .filter_map(|p| Uuid::parse_str(&p.model_id).ok())
+            .collect();
+        let names = db::get_model_names(&self.pool, &ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for price in &mut prices {
+            price.model_name = Uuid::parse_str(&price.model_id)
+                .ok()
+                .and_then(|id| names.get(&id).cloned());
+        }
+        Ok(prices)
+    }
+
+    async fn set_model_price(&self, price: &ModelPrice) -> Result<()> {
+        self.cost_db.set_model_price(price)
+            .await
+            .context("Failed to save model price")
+    }
+
+    async fn delete_model_price(&self, model_id: &str, effective_from: NaiveDate) -> Result<()> {
+        self.cost_db.delete_model_price(model_id, effective_from)
+            .await
+            .context("Failed to delete model price")
+    }
+
+    async fn list_model_families(&self) -> Result<Vec<ModelFamily>> {
+        self.cost_db.list_model_families()
+            .await
+            .context("Failed to query model families")
+    }
+
+    async fn set_model_family(&self, rule: &ModelFamily) -> Result<()> {
+        self.cost_db.set_model_family(rule)
+            .await
+            .context("Failed to save model family")
+    }
+
+    async fn delete_model_family(&self, prefix: &str) -> Result<()> {
+        self.cost_db.delete_model_family(prefix)
+            .await
+            .context("Failed to delete model family")
+    }
+
+    async fn list_labels(&self) -> Result<Vec<Label>> {
+        self.cost_db.list_labels()
+            .await
+            .context("Failed to query labels")
+    }
+
+    async fn add_label(&self, label: &Label) -> Result<()> {
+        self.cost_db.add_label(label)
+            .await
+            .context("Failed to add label")
+    }
+
+    async fn remove_label(&self, label: &Label) -> Result<()> {
+        self.cost_db.remove_label(label)
+            .await
+            .context("Failed to remove label")
+    }
+
+    async fn list_report_subscriptions(&self) -> Result<Vec<ReportSubscription>> {
+        self.cost_db.list_report_subscriptions()
+            .await
+            .context("Failed to query report subscriptions")
+    }
+
+    async fn add_report_subscription(
+        &self,
+        recipient: &str,
+        kind: ReportKind,
+        schedule: ReportSchedule,
+    ) -> Result<()> {
+        self.cost_db.add_report_subscription(recipient, kind, schedule)
+            .await
+            .context("Failed to add report subscription")
+    }
+
+    async fn delete_report_subscription(&self, id: i64) -> Result<()> {
+        self.cost_db.delete_report_subscription(id)
+            .await
+            .context("Failed to delete report subscription")
+    }
+
+    async fn get_token_usage_by_model(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<TokenUsageRow>> {
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        ce::get_daily_token_usage_by_model(&self.ce_clients, &start, &end)
+            .await
+            .context("Failed to fetch token usage from CE")
+    }
+
+    async fn get_token_usage_by_user(
+        &self,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<UserTokenUsage>> {
+        let start = start.format("%Y-%m-%d").to_string();
+        let end = end.format("%Y-%m-%d").to_string();
+        ce::get_token_usage_by_user(&self.ce_clients, &start, &end)
+            .await
+            .context("Failed to fetch token usage from CE")
+    }
+
+    async fn list_adjustments(&self, start: NaiveDate, end: NaiveDate) -> Result<Vec<CostAdjustment>> {
+        let mut adjustments = self.cost_db.list_adjustments(&self.tenant, start, end)
+            .await
+            .context("Failed to query cost adjustments")?;
+        let user_ids: Vec<Uuid> = adjustments
+            .iter()
+            .filter_map(|a| Uuid::parse_str(&a.user_id).ok())
+            .collect();
+        let model_ids: Vec<Uuid> = adjustments
+            .iter()
+            .filter_map(|a| Uuid::parse_str(&a.model_id).ok())
+            .collect();
+        let emails = db::get_user_emails(&self.pool, &user_ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query user emails: {e}");
+                HashMap::new()
+            });
+        let names = db::get_model_names(&self.pool, &model_ids)
+            .await
+            .unwrap_or_else(|e| {
+                log::error!("Failed to query model names: {e}");
+                HashMap::new()
+            });
+        for adjustment in &mut adjustments {
+            adjustment.user_email = Uuid::parse_str(&adjustment.user_id)
+                .ok()
+                .and_then(|id| emails.get(&id).cloned());
+            adjustment.model_name = Uuid::parse_str(&adjustment.model_id)
+                .ok()
+                .and_then(|id| names.get(&id).cloned());
+        }
+        Ok(adjustments)
+    }
+
+    async fn set_adjustment(&self, adjustment: &CostAdjustment) -> Result<()> {
+        self.cost_db.set_adjustment(&self.tenant, adjustment)
+            .await
+            .context("Failed to save cost adjustment")
+    }
+
+    async fn delete_adjustment(
+        &self,
+        date: NaiveDate,
+        user_id: &str,
+        model_id: &str,
+        metric: Metric,
+    ) -> Result<()> {
+        self.cost_db.delete_adjustment(&self.tenant, date, user_id, model_id, metric)
+            .await
+            .context("Failed to delete cost adjustment")
+    }
+
+    async fn import_costs(&self, rows: &[CostRow]) -> Result<()> {
+        self.cost_db.import_cost_rows(rows)
+            .await
+            .context("Failed to import costs")
+    }
+
+    async fn refresh_monthly_rollup(&self) -> Result<()> {
+        self.cost_db.refresh_monthly_rollup(None)
+            .await
+            .context("Failed to refresh monthly rollup")
+    }
+
+    async fn data_freshness(&self) -> Result<DataFreshness> {
+        self.cost_db.data_freshness()
+            .await
+            .context("Failed to read data freshness")
+    }
+
+    async fn record_edit(&self) -> Result<()> {
+        self.cost_db.record_edit()
+            .await
+            .context("Failed to record an edit")
+    }
+
+    async fn request_batch_run(&self, requested_by: &str) -> Result<()> {
+        self.cost_db.request_batch_run(&self.tenant, requested_by)
+            .await
+            .context("Failed to request a batch run")
+    }
+
+    async fn list_batch_requests(&self, limit: i64) -> Result<Vec<BatchRequest>> {
+        self.cost_db.list_batch_requests(&self.tenant, limit)
+            .await
+            .context("Failed to list batch requests")
+    }
+
+    async fn refresh_costs(&self, metric: Metric) -> Result<usize> {
+        let end = Utc::now().date_naive();
+        let start = end - Duration::days(ingest::DEFAULT_INCREMENTAL_DAYS);
+        let source = ingest::Source::load(&self.pool, &self.tenant, self.ce_clients.clone(), vec![metric])
+            .await
+            .context("Failed to read known users and models")?;
+        let ingested = source
+            .ingest(
+                self.cost_db.as_ref(),
+                start,
+                end,
+                ingest::DEFAULT_CHUNK_DAYS,
+                ingest::DEFAULT_FETCH_CONCURRENCY,
+            )
+            .await
+            .context("Failed to refresh costs from CE")?;
+        Ok(ingested.rows)
+    }
+
+    async fn data_as_of(&self, start: NaiveDate, end: NaiveDate, metric: Metric) -> Option<DateTime<Utc>> {
+        let (_, live_range) = self.split_range(start, end).await.ok()?;
+        let (start, end) = live_range?;
+        self.live_cache.fetched_at(&(self.tenant.clone(), start, end, metric))
+    }
+
+    async fn diagnostics(&self) -> Diagnostics {
+        let (tables, tables_error) = match self.cost_db.table_stats().await {
+            Ok(tables) => (tables, None),
+            Err(e) => (Vec::new(), Some(format!("{e:#}"))),
+        };
+        Diagnostics {
+            data_source: self.data_source,
+            tables,
+            tables_error,
+            cost_pool: self.cost_db.pool_status(),
+            gateway_pool: db::pool_status(&self.pool),
+            live_cache: self.live_cache.stats(),
+            ce_calls: ce::recent_calls(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(date: &str, user: &str, model: &str, micros: i64) -> CostRow {
+        CostRow {
+            date: NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap(),
+            user_id: user.to_string(),
+            model_id: model.to_string(),
+            amount: Amount::from_micros(micros),
+            currency: "USD".to_string(),
+            metric: Metric::Blended,
+            source: AWS_SOURCE.to_string(),
+            tenant: common::DEFAULT_TENANT.to_string(),
+        }
+    }
+
+    #[test]
+    fn every_source_covers_the_same_days() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let today = day(15);
+        let days = |(stored, live): Split| {
+            [stored, live]
+                .into_iter()
+                .flatten()
+                .flat_map(|(s, e)| s.iter_days().take_while(move |d| *d < e))
+                .collect::<Vec<_>>()
+        };
+        // "This month", a settled range, today's own page and an empty one.
+        let ranges = [
+            (day(1), today),
+            (day(1), day(10)),
+            (today, day(16)),
+            (today, today),
+        ];
+        for (start, end) in ranges {
+            let db = days(split(DataSource::Db, start, end, today, None));
+            for unsettled in [None, Some(day(13)), Some(day(5))] {
+                let ce = days(split(DataSource::Ce, start, end, today, unsettled));
+                let hybrid = days(split(DataSource::Hybrid, start, end, today, unsettled));
+                assert_eq!(ce, db, "{start}..{end}");
+                assert_eq!(hybrid, db, "{start}..{end} unsettled from {unsettled:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn hybrid_fetches_unsettled_days_live() {
+        let day = |d| NaiveDate::from_ymd_opt(2025, 3, d).unwrap();
+        let today = day(15);
+        let hybrid =
+            |start, end, unsettled| split(DataSource::Hybrid, start, end, today, unsettled);
+        assert_eq!(hybrid(day(1), today, None), (Some((day(1), today)), None));
+        assert_eq!(
+            hybrid(day(1), today, Some(day(13))),
+            (Some((day(1), day(13))), Some((day(13), today)))
+        );
+        // A page of today itself is read from CE.
+        assert_eq!(hybrid(today, day(16), None), (None, Some((today, day(16)))));
+        assert_eq!(
+            hybrid(day(1), day(10), Some(day(13))),
+            (Some((day(1), day(10))), None)
+        );
+    }
+
+    #[test]
+    fn daily_and_monthly_records_group_rows() {
+        let rows = vec![
+            row("2024-01-02", "u1", "m1", 1),
+            row("2024-01-01", "u2", "m1", 2),
+            row("2024-01-02", "u2", "m2", 3),
+        ];
+        let daily = daily_records(&rows);
+        assert_eq!(daily.len(), 2);
+        assert_eq!(daily[0].date, "2024-01-01");
+        assert_eq!(daily[1].amount, Amount::from_micros(4));
+        let monthly = monthly_records(&rows);
+        assert_eq!(monthly.len(), 1);
+        assert_eq!(monthly[0].date, "2024-01-01");
+        assert_eq!(monthly[0].amount, Amount::from_micros(6));
+    }
+
+    #[test]
+    fn weekly_records_group_by_monday() {
+        let rows = vec![
+            row("2024-01-03", "u1", "m1", 1),
+            row("2024-01-07", "u1", "m1", 2),
+            row("2024-01-08", "u1", "m1", 4),
+        ];
+        let weekly = weekly_records(&rows);
+        assert_eq!(weekly.len(), 2);
+        assert_eq!(weekly[0].date, "2024-01-01");
+        assert_eq!(weekly[0].amount, Amount::from_micros(3));
+        assert_eq!(weekly[1].date, "2024-01-08");
+    }
+
+    #[test]
+    fn merge_by_user_sums_and_orders() {
+        let stored = by_user(&[row("2024-01-01", "u1", "m1", 5), row("2024-01-01", "u2", "m1", 4)]);
+        let live = by_user(&[row("2024-01-02", "u2", "m1", 3)]);
+        let merged = merge_by_user(stored, live);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].user_id, "u2");
+        assert_eq!(merged[0].amount, Amount::from_micros(7));
+    }
+
+    #[test]
+    fn daily_by_model_groups_by_date_and_model() {
+        let rows = vec![
+            row("2024-01-02", "u1", "m1", 1),
+            row("2024-01-01", "u2", "m1", 2),
+            row("2024-01-02", "u2", "m2", 3),
+            row("2024-01-02", "u3", "m1", 1),
+        ];
+        let costs = daily_by_model(&rows);
+        assert_eq!(costs.len(), 3);
+        assert_eq!(costs[0].model_id, "m1");
+        assert_eq!(costs[1].model_id, "m2");
+        assert_eq!(costs[2].amount, Amount::from_micros(2));
+    }
+
+    #[test]
+    fn daily_by_user_groups_by_date_and_user() {
+        let rows = vec![
+            row("2024-01-02", "u1", "m1", 1),
+            row("2024-01-02", "u1", "m2", 3),
+            row("2024-01-01", "u2", "m1", 2),
+            row("2024-01-02", "u2", "m1", 5),
+        ];
+        let costs = daily_by_user(&rows);
+        assert_eq!(costs.len(), 3);
+        assert_eq!(costs[0].user_id, "u2");
+        assert_eq!(costs[1].user_id, "u2");
+        assert_eq!(costs[1].amount, Amount::from_micros(5));
+        assert_eq!(costs[2].amount, Amount::from_micros(4));
+    }
+
+    #[test]
+    fn moving_average_spans_trailing_days() {
+        let daily: Vec<CostRecord> = [
+            ("2024-01-01", 7.0),
+            ("2024-01-02", 14.0),
+            ("2024-01-09", 7.0),
+        ]
+        .into_iter()
+        .map(|(date, amount)| CostRecord {
+            date: date.to_string(),
+            amount: Amount::from_f64(amount),
+            currency: "USD".to_string(),
+        })
+        .collect();
+        let from = NaiveDate::from_ymd_opt(2024, 1, 2).unwrap();
+        let average = moving_average(&daily, from, 7);
+        assert_eq!(average.len(), 2);
+        assert_eq!(average[0].date, "2024-01-02");
+        assert_eq!(average[0].amount, Amount::from_f64(10.5));
+        assert_eq!(average[1].date, "2024-01-09");
+        assert_eq!(average[1].amount, Amount::from_f64(1.0));
+        assert!(moving_average(&[], from, 7).is_empty());
+    }
+
+    #[test]
+    fn merge_records_keeps_stored_without_live() {
+        let stored = daily_records(&[row("2024-01-01", "u1", "m1", 5)]);
+        assert_eq!(merge_records(stored, Vec::new()).len(), 1);
+    }
 }
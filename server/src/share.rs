@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use axum::extract::{Request, State};
+use axum::http::{Method, StatusCode, Uri};
+use axum::middleware::Next;
+use axum::response::{Html, IntoResponse, Response};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use tower_sessions::{MemoryStore, Session};
+
+use crate::handlers::AppState;
+use crate::pages::{self, with_query};
+use crate::roles::Role;
+
+/// Query parameter holding a share link's `{expires}.{signature}` token.
+pub const SHARE_PARAM: &str = "share";
+
+/// The user a shared page is rendered for.
+pub const SHARED_EMAIL: &str = "shared link";
+
+type HmacSha256 = Hmac<Sha256>;
+
+fn mac(secret: &str, target: &str, expires: i64) -> HmacSha256 {
+    let mut mac =
+        HmacSha256::new_from_slice(secret.as_bytes()).expect("HMAC takes keys of any length");
+    mac.update(format!("{expires}\n{target}").as_bytes());
+    mac
+}
+
+/// `target`, a path with its query, with a token that opens it without
+/// logging in until `expires`.
+pub fn sign(secret: &str, target: &str, expires: DateTime<Utc>) -> String {
+    let expires = expires.timestamp();
+    let signature = URL_SAFE_NO_PAD.encode(mac(secret, target, expires).finalize().into_bytes());
+    with_query(target, SHARE_PARAM, &format!("{expires}.{signature}"))
+}
+
+/// Checks the share token of `uri`, if it has one: it must be signed with
+/// `secret` for the rest of the URI and not have expired.
+pub fn verify(secret: &str, uri: &Uri, now: DateTime<Utc>) -> Option<bool> {
+    let query = uri.query()?;
+    let mut token = None;
+    let mut rest = Vec::new();
+    for pair in query.split('&') {
+        match pair
+            .strip_prefix(SHARE_PARAM)
+            .and_then(|p| p.strip_prefix('='))
+        {
+            Some(value) => token = Some(value),
+            None => rest.push(pair),
+        }
+    }
+    let token = token?;
+    let target = if rest.is_empty() {
+        uri.path().to_string()
+    } else {
+        format!("{}?{}", uri.path(), rest.join("&"))
+    };
+    Some(!secret.is_empty() && token_valid(secret, &target, token, now).unwrap_or(false))
+}
+
+fn token_valid(secret: &str, target: &str, token: &str, now: DateTime<Utc>) -> Option<bool> {
+    let (expires, signature) = token.split_once('.')?;
+    let expires: i64 = expires.parse().ok()?;
+    let signature = URL_SAFE_NO_PAD.decode(signature).ok()?;
+    let signed = mac(secret, target, expires)
+        .verify_slice(&signature)
+        .is_ok();
+    Some(signed && now.timestamp() < expires)
+}
+
+/// A throwaway session for a shared page's viewer.
+async fn shared_session() -> tower_sessions::session::Result<Session> {
+    let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+    session.insert("email", SHARED_EMAIL).await?;
+    session.insert("role", Role::Finance).await?;
+    Ok(session)
+}
+
+/// Opens pages requested with a valid share token as a read-only `Finance`
+/// user in a throwaway session; rejects requests with any other token.
+pub async fn shared_access(
+    State(state): State<AppState>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let secret = state.config.load().share_secret.clone();
+    match verify(&secret, req.uri(), Utc::now()) {
+        None => next.run(req).await,
+        Some(true) if req.method() == Method::GET => match shared_session().await {
+            Ok(session) => {
+                req.extensions_mut().insert(session);
+                next.run(req).await
+            }
+            Err(e) => {
+                log::error!("Failed to open shared page: {e}");
+                StatusCode::INTERNAL_SERVER_ERROR.into_response()
+            }
+        },
+        Some(_) => (
+            StatusCode::FORBIDDEN,
+            Html(pages::error::render(
+                "This share link is invalid or has expired.",
+            )),
+        )
+            .into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, TimeZone};
+
+    fn uri(s: &str) -> Uri {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn signed_links_open_until_they_expire() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let link = sign(
+            "secret",
+            "/costs/monthly/2025-02/users?period=30d&metric=UnblendedCost",
+            now + Duration::days(7),
+        );
+        assert!(link.starts_with(
+            "/costs/monthly/2025-02/users?period=30d&metric=UnblendedCost&share=1741435200."
+        ));
+        assert_eq!(verify("secret", &uri(&link), now), Some(true));
+        assert_eq!(
+            verify("secret", &uri(&link), now + Duration::days(8)),
+            Some(false)
+        );
+        assert_eq!(verify("other", &uri(&link), now), Some(false));
+        assert_eq!(verify("", &uri(&link), now), Some(false));
+    }
+
+    #[test]
+    fn altered_links_are_rejected() {
+        let now = Utc.with_ymd_and_hms(2025, 3, 1, 12, 0, 0).unwrap();
+        let link = sign(
+            "secret",
+            "/costs/monthly/2025-02/users",
+            now + Duration::days(1),
+        );
+        assert_eq!(verify("secret", &uri(&link), now), Some(true));
+        let other = link.replace("2025-02", "2025-01");
+        assert_eq!(verify("secret", &uri(&other), now), Some(false));
+        let extended = link.replacen("share=17", "share=18", 1);
+        assert_eq!(verify("secret", &uri(&extended), now), Some(false));
+        assert_eq!(
+            verify("secret", &uri("/costs/monthly?share=garbage"), now),
+            Some(false)
+        );
+        assert_eq!(
+            verify("secret", &uri("/costs/monthly?period=7d"), now),
+            None
+        );
+    }
+}
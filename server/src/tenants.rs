@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use common::DEFAULT_TENANT;
+use tower_sessions::Session;
+
+use crate::etag::is_html;
+use crate::handlers::{require_login, AppState};
+use crate::pages::make_path;
+use crate::roles::Role;
+
+/// Where the pages of `tenant` are served: the default tenant's at `base`,
+/// the others' under `{base}/t/{tenant}`.
+pub fn base_path(base: &str, tenant: &str) -> String {
+    if tenant == DEFAULT_TENANT {
+        base.to_string()
+    } else {
+        make_path(base, &format!("/t/{tenant}"))
+    }
+}
+
+/// Each tenant's name and home page, the default tenant first.
+pub fn links<'a>(base: &str, tenants: impl IntoIterator<Item = &'a str>) -> Vec<(String, String)> {
+    std::iter::once(DEFAULT_TENANT)
+        .chain(tenants)
+        .map(|tenant| (tenant.to_string(), base_path(base, tenant)))
+        .collect()
+}
+
+/// The state of [`switcher`]: the routes' own state and every tenant's link.
+#[derive(Clone)]
+pub struct Switcher {
+    pub state: AppState,
+    pub links: Arc<Vec<(String, String)>>,
+}
+
+/// Serves the pages of tenants other than the default one to admins only;
+/// others are sent to log in or refused.
+pub async fn admins_only(
+    State(state): State<AppState>,
+    session: Session,
+    req: Request,
+    next: Next,
+) -> Response {
+    match require_login(&session, &state).await {
+        Ok(user) if user.role == Role::Admin => next.run(req).await,
+        Ok(_) => StatusCode::FORBIDDEN.into_response(),
+        Err(redirect) => redirect,
+    }
+}
+
+/// Adds links to the other tenants' pages to the header of every page an
+/// admin is shown.
+pub async fn switcher(
+    State(switcher): State<Switcher>,
+    session: Session,
+    req: Request,
+    next: Next,
+) -> Response {
+    let response = next.run(req).await;
+    if response.status() != StatusCode::OK || !is_html(response.headers()) {
+        return response;
+    }
+    match require_login(&session, &switcher.state).await {
+        Ok(user) if user.role == Role::Admin => {}
+        _ => return response,
+    }
+
+    let (mut parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            log::error!("Failed to read a rendered page: {e}");
+            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+        }
+    };
+    let Ok(page) = std::str::from_utf8(&bytes) else {
+        return Response::from_parts(parts, Body::from(bytes));
+    };
+    let current = switcher.state.service.tenant();
+    let page = templates::add_tenant_switcher(page, &switcher.links, current);
+    parts.headers.remove(header::CONTENT_LENGTH);
+    Response::from_parts(parts, Body::from(page))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_start_with_the_default_tenant() {
+        assert_eq!(base_path("/", "default"), "/");
+        assert_eq!(base_path("/", "dev"), "/t/dev");
+        assert_eq!(
+            links("/_dashboard", ["dev", "staging"]),
+            vec![
+                ("default".to_string(), "/_dashboard".to_string()),
+                ("dev".to_string(), "/_dashboard/t/dev".to_string()),
+                ("staging".to_string(), "/_dashboard/t/staging".to_string()),
+            ]
+        );
+    }
+}
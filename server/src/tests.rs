@@ -1,15 +1,23 @@
+use arc_swap::ArcSwap;
 use async_trait::async_trait;
 use axum::body::Body;
-use chrono::NaiveDate;
-use common::{CostByModel, CostByUser, CostRecord, ModelInfo, UserInfo};
+use chrono::{DateTime, NaiveDate, Utc};
+use common::{
+    Amount, ApiKeyInfo, BatchRequest, CostAdjustment, CostAlert, CostByAccount, CostByApiKey, CostByModel, CostByRegion, CostByUser, CostMatrix, CostRecord, CostRow,
+    DataFreshness, InferenceProfileInfo, Label, Metric, ModelCostRow, ModelFamily, ModelInfo, ModelPrice,
+    ReportKind, ReportSchedule, ReportSubscription, ServiceCostRow, SpendLimit, TokenUsageRow, UserCostRow, UserInfo, UserTokenUsage,
+};
 use http_body_util::BodyExt;
+use myhandlers::{OidcProvider, GROUPS_KEY};
 use std::sync::Arc;
 use tower::ServiceExt;
-use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::{Expiry, MemoryStore, Session, SessionManagerLayer};
 
 use crate::build_router;
-use crate::handlers::AppState;
-use crate::service::CostService;
+use crate::config::{AppConfig, RateLimitConfig, SessionStoreKind, TenantConfig};
+use crate::handlers::{login_role, AppState};
+use crate::roles::Role;
+use crate::service::{CostService, Diagnostics};
 
 struct MockCostService {
     users: Vec<CostByUser>,
@@ -23,18 +31,18 @@ impl MockCostService {
             users: vec![CostByUser {
                 user_id: "aaaa-bbbb".to_string(),
                 user_email: Some("alice@example.com".to_string()),
-                amount: 100.0,
+                amount: Amount::from_f64(100.0),
                 currency: "USD".to_string(),
             }],
             models: vec![CostByModel {
                 model_id: "cccc-dddd".to_string(),
                 model_name: Some("claude-3-sonnet".to_string()),
-                amount: 80.0,
+                amount: Amount::from_f64(80.0),
                 currency: "USD".to_string(),
             }],
             daily: vec![CostRecord {
                 date: "2024-01-15".to_string(),
-                amount: 100.0,
+                amount: Amount::from_f64(100.0),
                 currency: "USD".to_string(),
             }],
         }
@@ -47,24 +55,73 @@ impl CostService for MockCostService {
         Ok(())
     }
 
-    async fn get_daily_cost(&self, _start: NaiveDate, _end: NaiveDate) -> Vec<CostRecord> {
-        self.daily.clone()
+    fn tenant(&self) -> &str {
+        common::DEFAULT_TENANT
     }
 
-    async fn get_monthly_cost(&self, _start: NaiveDate, _end: NaiveDate) -> Vec<CostRecord> {
-        vec![CostRecord {
+    fn today(&self) -> NaiveDate {
+        Utc::now().date_naive()
+    }
+
+    fn with_source(&self, _source: Option<&str>) -> Arc<dyn CostService> {
+        Arc::new(MockCostService::new())
+    }
+
+    async fn list_sources(&self) -> anyhow::Result<Vec<String>> {
+        Ok(vec!["aws".to_string()])
+    }
+
+    async fn get_daily_cost(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
+    }
+
+    async fn get_monthly_cost(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(vec![CostRecord {
             date: "2024-01-01".to_string(),
-            amount: 500.0,
+            amount: Amount::from_f64(500.0),
             currency: "USD".to_string(),
-        }]
+        }])
     }
 
-    async fn get_cost_by_user(&self, _start: NaiveDate, _end: NaiveDate) -> Vec<CostByUser> {
-        self.users.clone()
+    async fn get_weekly_cost(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(vec![CostRecord {
+            date: "2024-01-01".to_string(),
+            amount: Amount::from_f64(120.0),
+            currency: "USD".to_string(),
+        }])
     }
 
-    async fn get_cost_by_model(&self, _start: NaiveDate, _end: NaiveDate) -> Vec<CostByModel> {
-        self.models.clone()
+    async fn get_cost_by_user(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByUser>> {
+        Ok(self.users.clone())
+    }
+
+    async fn get_cost_by_model(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByModel>> {
+        Ok(self.models.clone())
     }
 
     async fn get_cost_by_model_for_user(
@@ -72,8 +129,9 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _user_id: &str,
-    ) -> Vec<CostByModel> {
-        self.models.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByModel>> {
+        Ok(self.models.clone())
     }
 
     async fn get_cost_by_user_for_model(
@@ -81,8 +139,9 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _model_id: &str,
-    ) -> Vec<CostByUser> {
-        self.users.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByUser>> {
+        Ok(self.users.clone())
     }
 
     async fn get_daily_cost_for_user(
@@ -90,8 +149,19 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _user_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
+    }
+
+    async fn get_weekly_cost_for_user(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _user_id: &str,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
     }
 
     async fn get_monthly_cost_for_user(
@@ -99,8 +169,9 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _user_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
     }
 
     async fn get_daily_cost_for_model(
@@ -108,8 +179,9 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _model_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
     }
 
     async fn get_monthly_cost_for_model(
@@ -117,8 +189,9 @@ impl CostService for MockCostService {
         _start: NaiveDate,
         _end: NaiveDate,
         _model_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
     }
 
     async fn get_daily_cost_for_user_and_model(
@@ -127,8 +200,9 @@ impl CostService for MockCostService {
         _end: NaiveDate,
         _user_id: &str,
         _model_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
     }
 
     async fn get_monthly_cost_for_user_and_model(
@@ -137,8 +211,73 @@ impl CostService for MockCostService {
         _end: NaiveDate,
         _user_id: &str,
         _model_id: &str,
-    ) -> Vec<CostRecord> {
-        self.daily.clone()
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostRecord>> {
+        Ok(self.daily.clone())
+    }
+
+    async fn get_daily_cost_by_service(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<ServiceCostRow>> {
+        Ok(vec![])
+    }
+
+    async fn get_daily_cost_by_model(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<ModelCostRow>> {
+        Ok(vec![])
+    }
+
+    async fn get_daily_cost_by_user(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<UserCostRow>> {
+        Ok(vec![])
+    }
+
+    async fn get_cost_matrix(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<CostMatrix> {
+        Ok(CostMatrix::default())
+    }
+
+    async fn get_cost_by_region(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByRegion>> {
+        Ok(vec![])
+    }
+
+    async fn get_cost_by_account(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByAccount>> {
+        Ok(vec![])
+    }
+
+    async fn get_cost_by_api_key_for_user(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _user_id: &str,
+        _metric: Metric,
+    ) -> anyhow::Result<Vec<CostByApiKey>> {
+        Ok(vec![])
     }
 
     async fn get_user_email(&self, _user_id: &str) -> Option<String> {
@@ -149,27 +288,49 @@ impl CostService for MockCostService {
         Some("claude-3-sonnet".to_string())
     }
 
-    async fn list_users(&self) -> Vec<(String, String)> {
-        vec![("aaaa-bbbb".to_string(), "alice@example.com".to_string())]
+    async fn list_users(&self) -> anyhow::Result<Vec<(String, String)>> {
+        Ok(vec![(
+            "aaaa-bbbb".to_string(),
+            "alice@example.com".to_string(),
+        )])
+    }
+
+    async fn list_models(&self) -> anyhow::Result<Vec<(String, String)>> {
+        Ok(vec![(
+            "cccc-dddd".to_string(),
+            "claude-3-sonnet".to_string(),
+        )])
     }
 
-    async fn list_models(&self) -> Vec<(String, String)> {
-        vec![("cccc-dddd".to_string(), "claude-3-sonnet".to_string())]
+    async fn search_users(&self, query: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let users = self.list_users().await?;
+        Ok(users
+            .into_iter()
+            .filter(|(id, email)| id.contains(query) || email.contains(query))
+            .collect())
+    }
+
+    async fn search_models(&self, query: &str) -> anyhow::Result<Vec<(String, String)>> {
+        let models = self.list_models().await?;
+        Ok(models
+            .into_iter()
+            .filter(|(id, name)| id.contains(query) || name.contains(query))
+            .collect())
     }
 
     async fn get_user_id_by_email(&self, _email: &str) -> Option<String> {
         Some("aaaa-bbbb".to_string())
     }
 
-    async fn list_users_enriched(&self) -> Vec<UserInfo> {
-        vec![UserInfo {
+    async fn list_users_enriched(&self) -> anyhow::Result<Vec<UserInfo>> {
+        Ok(vec![UserInfo {
             user_id: "aaaa-bbbb".to_string(),
             user_email: "alice@example.com".to_string(),
             created_at: "2024-01-01".to_string(),
             api_key_count: 2,
             active_api_key_count: 1,
             inference_profile_count: 3,
-        }]
+        }])
     }
 
     async fn get_user_info(&self, _user_id: &str) -> Option<UserInfo> {
@@ -183,14 +344,14 @@ impl CostService for MockCostService {
         })
     }
 
-    async fn list_models_enriched(&self) -> Vec<ModelInfo> {
-        vec![ModelInfo {
+    async fn list_models_enriched(&self) -> anyhow::Result<Vec<ModelInfo>> {
+        Ok(vec![ModelInfo {
             model_id: "cccc-dddd".to_string(),
             model_name: "claude-3-sonnet".to_string(),
             is_disabled: false,
             protected: false,
             user_count: 1,
-        }]
+        }])
     }
 
     async fn get_model_info(&self, _model_id: &str) -> Option<ModelInfo> {
@@ -202,21 +363,231 @@ impl CostService for MockCostService {
             user_count: 1,
         })
     }
+
+    async fn list_api_keys_for_user(&self, _user_id: &str) -> anyhow::Result<Vec<ApiKeyInfo>> {
+        Ok(vec![ApiKeyInfo {
+            api_key_id: "eeee-ffff".to_string(),
+            api_key_preview: "abcd1234".to_string(),
+            is_disabled: false,
+            created_at: "2024-01-01".to_string(),
+        }])
+    }
+
+    async fn list_profiles_for_user(
+        &self,
+        _user_id: &str,
+    ) -> anyhow::Result<Vec<InferenceProfileInfo>> {
+        Ok(vec![InferenceProfileInfo {
+            inference_profile_id: "gggg-hhhh".to_string(),
+            model_id: "cccc-dddd".to_string(),
+            model_name: Some("claude-3-sonnet".to_string()),
+            user_id: "aaaa-bbbb".to_string(),
+            user_email: Some("alice@example.com".to_string()),
+            created_at: "2024-01-01".to_string(),
+        }])
+    }
+
+    async fn list_profiles_for_model(
+        &self,
+        model_id: &str,
+    ) -> anyhow::Result<Vec<InferenceProfileInfo>> {
+        Ok(self
+            .list_profiles_for_user("aaaa-bbbb")
+            .await?
+            .into_iter()
+            .filter(|p| p.model_id == model_id)
+            .collect())
+    }
+
+    async fn get_role(&self, _email: &str) -> Option<String> {
+        None
+    }
+
+    async fn list_spend_limits(&self) -> anyhow::Result<Vec<SpendLimit>> {
+        Ok(vec![])
+    }
+
+    async fn get_spend_limit(&self, _user_id: &str) -> anyhow::Result<Option<SpendLimit>> {
+        Ok(None)
+    }
+
+    async fn set_spend_limit(
+        &self,
+        _user_id: &str,
+        _monthly_limit: Option<Amount>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_cost_alert(&self, _user_id: &str) -> anyhow::Result<Option<CostAlert>> {
+        Ok(None)
+    }
+
+    async fn set_cost_alert(
+        &self,
+        _user_id: &str,
+        _daily: Option<Amount>,
+        _monthly: Option<Amount>,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_model_prices(&self) -> anyhow::Result<Vec<ModelPrice>> {
+        Ok(vec![])
+    }
+
+    async fn set_model_price(&self, _price: &ModelPrice) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_model_price(
+        &self,
+        _model_id: &str,
+        _effective_from: NaiveDate,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_model_families(&self) -> anyhow::Result<Vec<ModelFamily>> {
+        Ok(vec![])
+    }
+
+    async fn set_model_family(&self, _rule: &ModelFamily) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_model_family(&self, _prefix: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_labels(&self) -> anyhow::Result<Vec<Label>> {
+        Ok(vec![])
+    }
+
+    async fn add_label(&self, _label: &Label) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn remove_label(&self, _label: &Label) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_report_subscriptions(&self) -> anyhow::Result<Vec<ReportSubscription>> {
+        Ok(vec![])
+    }
+
+    async fn add_report_subscription(
+        &self,
+        _recipient: &str,
+        _kind: ReportKind,
+        _schedule: ReportSchedule,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_report_subscription(&self, _id: i64) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn get_token_usage_by_model(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+    ) -> anyhow::Result<Vec<TokenUsageRow>> {
+        Ok(vec![])
+    }
+
+    async fn get_token_usage_by_user(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+    ) -> anyhow::Result<Vec<UserTokenUsage>> {
+        Ok(vec![])
+    }
+
+    async fn list_adjustments(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+    ) -> anyhow::Result<Vec<CostAdjustment>> {
+        Ok(vec![])
+    }
+
+    async fn set_adjustment(&self, _adjustment: &CostAdjustment) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn delete_adjustment(
+        &self,
+        _date: NaiveDate,
+        _user_id: &str,
+        _model_id: &str,
+        _metric: Metric,
+    ) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn import_costs(&self, _rows: &[CostRow]) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn refresh_monthly_rollup(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn data_freshness(&self) -> anyhow::Result<DataFreshness> {
+        Ok(DataFreshness::default())
+    }
+
+    async fn record_edit(&self) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn request_batch_run(&self, _requested_by: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn list_batch_requests(&self, _limit: i64) -> anyhow::Result<Vec<BatchRequest>> {
+        Ok(vec![])
+    }
+
+    async fn refresh_costs(&self, _metric: Metric) -> anyhow::Result<usize> {
+        Ok(0)
+    }
+
+    async fn data_as_of(
+        &self,
+        _start: NaiveDate,
+        _end: NaiveDate,
+        _metric: Metric,
+    ) -> Option<DateTime<Utc>> {
+        None
+    }
+
+    async fn diagnostics(&self) -> Diagnostics {
+        Diagnostics::default()
+    }
 }
 
 fn mock_state(base: &str) -> AppState {
     AppState {
         service: Arc::new(MockCostService::new()),
         base_path: base.to_string(),
-        cognito_client_id: String::new(),
-        cognito_client_secret: String::new(),
-        cognito_domain: String::new(),
-        cognito_redirect_uri: String::new(),
-        cognito_region: String::new(),
-        cognito_user_pool_id: String::new(),
+        auth_provider: Arc::new(OidcProvider::new("", "", "", "", Vec::new())),
+        config: Arc::new(ArcSwap::from_pointee(test_config())),
+        tenants: Default::default(),
     }
 }
 
+/// The config with every setting at its default.
+fn test_config() -> AppConfig {
+    config::Config::builder()
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap()
+}
+
 fn test_app() -> axum::Router {
     let session_store = MemoryStore::default();
     let session_layer = SessionManagerLayer::new(session_store)
@@ -253,22 +624,158 @@ async fn unauthenticated_home_redirects_to_login() {
     assert!(status == 303 || status == 302 || status == 307);
 }
 
+#[tokio::test]
+async fn unauthenticated_home_events_redirects_to_login() {
+    let (status, _) = get("/events/home").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
 #[tokio::test]
 async fn unauthenticated_daily_costs_redirects_to_login() {
     let (status, _) = get("/costs/daily").await;
     assert!(status == 303 || status == 302 || status == 307);
 }
 
+#[tokio::test]
+async fn unauthenticated_calendar_redirects_to_login() {
+    let (status, _) = get("/costs/calendar").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_daily_costs_by_model_redirects_to_login() {
+    let (status, _) = get("/costs/daily/stacked").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_distribution_redirects_to_login() {
+    let (status, _) = get("/costs/distribution?period=7d").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_cost_matrix_redirects_to_login() {
+    let (status, _) = get("/costs/matrix").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/costs/matrix.csv").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_fiscal_redirects_to_login() {
+    let (status, _) = get("/costs/fiscal").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_weekly_redirects_to_login() {
+    let (status, _) = get("/costs/weekly").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/costs/weekly/2025-W03").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_prices_redirects_to_login() {
+    let (status, _) = get("/prices").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/costs/estimates").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_amortization_redirects_to_login() {
+    let (status, _) = get("/costs/amortization").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_efficiency_redirects_to_login() {
+    let (status, _) = get("/efficiency").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_credits_redirects_to_login() {
+    let (status, _) = get("/costs/credits").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_families_redirects_to_login() {
+    let (status, _) = get("/families").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/costs/families").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_labels_redirects_to_login() {
+    let (status, _) = get("/labels").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/costs/labels").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_idle_report_redirects_to_login() {
+    let (status, _) = get("/reports/idle").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_subscriptions_redirects_to_login() {
+    let (status, _) = get("/subscriptions").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_adjustments_redirects_to_login() {
+    let (status, _) = get("/adjustments").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_diagnostics_redirects_to_login() {
+    let (status, _) = get("/admin/diagnostics").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_spend_limits_redirects_to_login() {
+    let (status, _) = get("/limits").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_my_costs_redirects_to_login() {
+    let (status, _) = get("/me?period=7d").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_alerts_redirects_to_login() {
+    let (status, _) = get("/me/alerts").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
 #[tokio::test]
 async fn unauthenticated_users_redirects_to_login() {
     let (status, _) = get("/users").await;
     assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/users.csv?sort=cost&order=desc").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/users.json?page=2&page_size=10").await;
+    assert!(status == 303 || status == 302 || status == 307);
 }
 
 #[tokio::test]
 async fn unauthenticated_models_redirects_to_login() {
     let (status, _) = get("/models").await;
     assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get("/models.csv?min_cost=1").await;
+    assert!(status == 303 || status == 302 || status == 307);
 }
 
 #[tokio::test]
@@ -307,6 +814,42 @@ async fn unauthenticated_model_monthly_costs_redirects_to_login() {
     assert!(status == 303 || status == 302 || status == 307);
 }
 
+#[tokio::test]
+async fn unauthenticated_user_keys_redirects_to_login() {
+    let (status, _) = get("/users/aaaa-bbbb/keys").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_user_profiles_redirects_to_login() {
+    let (status, _) = get("/users/aaaa-bbbb/profiles").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_model_users_redirects_to_login() {
+    let (status, _) = get("/models/cccc-dddd/users").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_export_xlsx_redirects_to_login() {
+    let (status, _) = get("/export.xlsx").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_search_redirects_to_login() {
+    let (status, _) = get("/search?q=alice").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_preferences_redirects_to_login() {
+    let (status, _) = get("/preferences").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
 // Daily cost drill-down redirects
 #[tokio::test]
 async fn unauthenticated_cost_date_detail_redirects_to_login() {
@@ -338,6 +881,31 @@ async fn unauthenticated_cost_date_model_users_redirects_to_login() {
     assert!(status == 303 || status == 302 || status == 307);
 }
 
+// Service breakdown redirects
+#[tokio::test]
+async fn unauthenticated_services_redirects_to_login() {
+    let (status, _) = get("/costs/services").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_service_date_redirects_to_login() {
+    let (status, _) = get("/costs/services/2024-01-15").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_accounts_redirects_to_login() {
+    let (status, _) = get("/costs/accounts").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
+#[tokio::test]
+async fn unauthenticated_regions_redirects_to_login() {
+    let (status, _) = get("/costs/regions").await;
+    assert!(status == 303 || status == 302 || status == 307);
+}
+
 // Monthly cost redirects
 #[tokio::test]
 async fn unauthenticated_monthly_costs_redirects_to_login() {
@@ -415,3 +983,399 @@ async fn nested_base_path_monthly_costs_redirect() {
     let (status, _) = get_from(app, "/_dashboard/costs/monthly").await;
     assert!(status == 303 || status == 302 || status == 307);
 }
+
+#[tokio::test]
+async fn admin_group_members_are_admins() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        admin_group: Some("cost-admins".to_string()),
+        ..test_config()
+    }));
+    let session = Session::new(None, Arc::new(MemoryStore::default()), None);
+    assert_eq!(login_role(&session, &state, "alice@example.com").await, Role::SelfOnly);
+    session.insert(GROUPS_KEY, vec!["cost-admins"]).await.unwrap();
+    assert_eq!(login_role(&session, &state, "alice@example.com").await, Role::Admin);
+}
+
+#[test]
+fn reload_reports_settings_needing_restart() {
+    let current = test_config();
+    let next = AppConfig {
+        port: 9090,
+        default_period: "7d".to_string(),
+        log_level: Some("server=debug".to_string()),
+        ..test_config()
+    };
+    assert_eq!(current.restart_required(&next), vec!["port"]);
+    assert!(current.restart_required(&test_config()).is_empty());
+}
+
+#[test]
+fn session_store_defaults_to_the_cost_database() {
+    assert_eq!(test_config().session_store, SessionStoreKind::Postgres);
+    let redis: AppConfig = config::Config::builder()
+        .set_override("session_store", "redis")
+        .unwrap()
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+    assert_eq!(redis.session_store, SessionStoreKind::Redis);
+    assert_eq!(redis.redis_url, "redis://localhost:6379");
+    assert_eq!(test_config().restart_required(&redis), vec!["session_store"]);
+}
+
+#[test]
+fn markup_is_overridden_per_model() {
+    assert!(test_config().markup().is_none());
+    let marked_up: AppConfig = config::Config::builder()
+        .set_override("markup_percent", 12.5)
+        .unwrap()
+        .set_override("model_markup_percent.m1", 5.0)
+        .unwrap()
+        .build()
+        .unwrap()
+        .try_deserialize()
+        .unwrap();
+    let markup = marked_up.markup();
+    assert_eq!(markup.percent_for("m1"), 5.0);
+    assert_eq!(markup.percent_for("m2"), 12.5);
+    assert!(test_config().restart_required(&marked_up).is_empty());
+}
+
+#[test]
+fn tenants_need_unique_valid_names() {
+    let tenant = |name: &str| TenantConfig {
+        name: name.to_string(),
+        database_url_gateway_ro: "postgres://localhost/gateway".to_string(),
+    };
+    let config = AppConfig {
+        tenants: vec![tenant("dev"), tenant("staging")],
+        ..test_config()
+    };
+    assert!(config.check_tenants().is_ok());
+    assert_eq!(config.tenant_names(), vec!["default", "dev", "staging"]);
+    assert_eq!(test_config().restart_required(&config), vec!["tenants"]);
+    for tenants in [
+        vec![tenant("dev"), tenant("dev")],
+        vec![tenant("default")],
+        vec![tenant("Dev")],
+    ] {
+        let config = AppConfig {
+            tenants,
+            ..test_config()
+        };
+        assert!(config.check_tenants().is_err());
+    }
+}
+
+#[tokio::test]
+async fn other_tenants_are_served_to_admins_only() {
+    let state = AppState {
+        tenants: Arc::new(
+            [(
+                "dev".to_string(),
+                Arc::new(MockCostService::new()) as Arc<dyn CostService>,
+            )]
+            .into_iter()
+            .collect(),
+        ),
+        ..mock_state("/")
+    };
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+
+    let (status, _) = get_from(app.clone(), "/t/dev/costs/daily").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, _) = get_from(app.clone(), "/t/prod/costs/daily").await;
+    assert_eq!(status, 404);
+
+    let expires = Utc::now() + chrono::Duration::days(1);
+    let path = "/t/dev/costs/monthly/2025-02/users?period=30d";
+    let link = crate::share::sign("secret", path, expires);
+    let (status, _) = get_from(app.clone(), &link).await;
+    assert_eq!(status, 403);
+    // Only admins get the switcher.
+    let path = "/costs/monthly/2025-02/users?period=30d";
+    let link = crate::share::sign("secret", path, expires);
+    let (status, body) = get_from(app, &link).await;
+    assert_eq!(status, 200);
+    assert!(!body.contains("tenant-switcher"));
+}
+
+#[tokio::test]
+async fn drill_downs_are_rate_limited() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        rate_limit: RateLimitConfig {
+            burst: 2,
+            per_minute: 1,
+        },
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+
+    for _ in 0..2 {
+        let (status, _) = get_from(app.clone(), "/costs/daily/2024-01-15").await;
+        assert_ne!(status, 429);
+    }
+    let (status, body) = get_from(app.clone(), "/costs/daily/2024-01-15/users").await;
+    assert_eq!(status, 429);
+    assert!(body.contains("Try again in"));
+    let (status, _) = get_from(app, "/costs/daily").await;
+    assert_ne!(status, 429);
+}
+
+#[tokio::test]
+async fn share_links_open_reports_without_login() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+    let path = "/costs/monthly/2025-02/users?period=30d";
+    let link = crate::share::sign("secret", path, Utc::now() + chrono::Duration::days(1));
+
+    let (status, body) = get_from(app.clone(), &link).await;
+    assert_eq!(status, 200);
+    assert!(body.contains("Cost by User"));
+    let (status, _) = get_from(app.clone(), path).await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let (status, body) = get_from(app.clone(), &link.replace("2025-02", "2025-01")).await;
+    assert_eq!(status, 403);
+    assert!(body.contains("invalid or has expired"));
+    let token = &link[link.find("share=").unwrap()..];
+    let (status, _) = get_from(app, &format!("/share?{token}")).await;
+    assert_eq!(status, 403);
+}
+
+#[tokio::test]
+async fn pages_show_data_freshness() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+    let path = "/costs/monthly/2025-02/users?period=30d";
+    let link = crate::share::sign("secret", path, Utc::now() + chrono::Duration::days(1));
+
+    let (status, body) = get_from(app, &link).await;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#"<footer class="freshness">Data updated: never</footer>"#));
+    assert!(body.contains("No batch run has stored cost data yet."));
+}
+
+#[tokio::test]
+async fn unauthenticated_batch_runs_redirects_to_login() {
+    let (status, _) = get("/admin/batch").await;
+    assert!(status == 303 || status == 302 || status == 307);
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/admin/batch/run")
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert!(resp.status().is_redirection());
+}
+
+#[tokio::test]
+async fn unauthenticated_refresh_redirects_to_login() {
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/admin/refresh")
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert!(resp.status().is_redirection());
+}
+
+#[tokio::test]
+async fn unauthenticated_import_redirects_to_login() {
+    let body = "--x\r\n\
+                Content-Disposition: form-data; name=\"source\"\r\n\r\n\
+                openai\r\n\
+                --x--\r\n";
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/import")
+        .header("content-type", "multipart/form-data; boundary=x")
+        .body(Body::from(body))
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert!(resp.status().is_redirection());
+}
+
+#[tokio::test]
+async fn unauthenticated_source_filter_redirects_to_login() {
+    let req = axum::http::Request::builder()
+        .uri("/source?source=openai")
+        .header("referer", "https://evil.example/")
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert!(resp.status().is_redirection());
+    assert_eq!(resp.headers()["location"], "/login");
+}
+
+#[tokio::test]
+async fn unauthenticated_graphql_is_unauthorized() {
+    let req = axum::http::Request::builder()
+        .method("POST")
+        .uri("/graphql")
+        .header("content-type", "application/json")
+        .body(Body::from(r#"{"query":"{ me { id } }"}"#))
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 401);
+}
+
+#[tokio::test]
+async fn share_links_answer_graphql_queries() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+    let path = "/graphql?query=%7Bcosts(start:%222024-01-01%22,end:%222024-02-01%22)%7Bdate%20amount%7D%7D";
+    let link = crate::share::sign("secret", path, Utc::now() + chrono::Duration::days(1));
+
+    let (status, body) = get_from(app, &link).await;
+    assert_eq!(status, 200);
+    assert!(body.contains(r#""date":"2024-01-15""#));
+}
+
+#[tokio::test]
+async fn responses_carry_a_request_id() {
+    let req = axum::http::Request::builder()
+        .uri("/")
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    let id = resp.headers()["x-request-id"].to_str().unwrap();
+    assert!(uuid::Uuid::parse_str(id).is_ok());
+
+    let req = axum::http::Request::builder()
+        .uri("/health")
+        .header("x-request-id", "lb-4f2a")
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert_eq!(resp.headers()["x-request-id"], "lb-4f2a");
+}
+
+#[tokio::test]
+async fn unchanged_pages_answer_not_modified() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        settlement_hours: 0,
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+    let link = crate::share::sign(
+        "secret",
+        "/costs/daily?period=last_month",
+        Utc::now() + chrono::Duration::days(1),
+    );
+
+    let req = axum::http::Request::builder()
+        .uri(&link)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["cache-control"], "private, no-cache");
+    let etag = resp.headers()["etag"].clone();
+
+    let req = axum::http::Request::builder()
+        .uri(&link)
+        .header("if-none-match", etag.clone())
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 304);
+    assert_eq!(resp.headers()["etag"], etag);
+    assert!(resp.headers().contains_key("last-modified"));
+    let body = resp.into_body().collect().await.unwrap().to_bytes();
+    assert!(body.is_empty());
+
+    let req = axum::http::Request::builder()
+        .uri(&link)
+        .header("if-none-match", "\"stale\"")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.oneshot(req).await.unwrap().status(), 200);
+}
+
+#[tokio::test]
+async fn pages_of_unsettled_days_are_not_tagged() {
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        share_secret: "secret".to_string(),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+    for path in ["/costs/daily?period=7d", "/users?period=last_month"] {
+        let link = crate::share::sign("secret", path, Utc::now() + chrono::Duration::days(1));
+        let req = axum::http::Request::builder()
+            .uri(&link)
+            .body(Body::empty())
+            .unwrap();
+        let resp = app.clone().oneshot(req).await.unwrap();
+        assert_eq!(resp.status(), 200, "{path}");
+        assert!(!resp.headers().contains_key("etag"), "{path}");
+    }
+}
+
+#[tokio::test]
+async fn assets_are_served_without_login() {
+    let page = templates::page_layout("Test", String::new());
+    let start = page.find("/assets/app.").unwrap();
+    let end = start + page[start..].find('"').unwrap();
+    let req = axum::http::Request::builder()
+        .uri(&page[start..end])
+        .body(Body::empty())
+        .unwrap();
+    let resp = test_app().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["content-type"], "text/css; charset=utf-8");
+    assert!(resp.headers()["cache-control"]
+        .to_str()
+        .unwrap()
+        .contains("immutable"));
+
+    let (status, _) = get("/assets/app.0000000000000000.css").await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn static_dir_is_served() {
+    let dir = std::env::temp_dir().join(format!("cost-static-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("site.css"), "body { margin: 0; }").unwrap();
+    let state = mock_state("/");
+    state.config.store(Arc::new(AppConfig {
+        static_dir: Some(dir.clone()),
+        ..test_config()
+    }));
+    let session_layer = SessionManagerLayer::new(MemoryStore::default());
+    let app = build_router(state).layer(session_layer);
+
+    let (status, body) = get_from(app, "/static/site.css").await;
+    assert_eq!(status, 200);
+    assert_eq!(body, "body { margin: 0; }");
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,97 @@
+(function(){
+  // Cycles auto -> light -> dark; the choice is kept in a cookie for a year.
+  var root=document.documentElement;
+  var btn=document.getElementById('theme-toggle');
+  function label(){btn.textContent='Theme: '+(root.getAttribute('data-theme')||'auto');}
+  label();
+  btn.addEventListener('click',function(){
+    var next={'':'light','light':'dark','dark':''}[root.getAttribute('data-theme')||''];
+    if(next){
+      root.setAttribute('data-theme',next);
+      document.cookie='theme='+next+'; path=/; max-age=31536000; SameSite=Lax';
+    }else{
+      root.removeAttribute('data-theme');
+      document.cookie='theme=; path=/; max-age=0; SameSite=Lax';
+    }
+    label();
+  });
+})();
+(function(){
+  var params=new URLSearchParams(window.location.search);
+  var curSort=params.get('sort');
+  var curOrder=params.get('dir')||params.get('order')||'asc';
+  // Mark sorted column header
+  document.querySelectorAll('table.data-table').forEach(function(table){
+    var ths=table.querySelectorAll('tr:first-child th');
+    if(curSort!==null){
+      var idx=parseInt(curSort,10);
+      if(ths[idx])ths[idx].classList.add(curOrder==='desc'?'sort-desc':'sort-asc');
+    }
+    // Click handler: navigate with sort params
+    ths.forEach(function(th,i){
+      th.addEventListener('click',function(){
+        var p=new URLSearchParams(window.location.search);
+        var newOrder=(p.get('sort')===String(i)&&curOrder!=='desc')?'desc':'asc';
+        p.delete('dir');p.set('sort',i);p.set('order',newOrder);p.set('page','1');
+        window.location.search=p.toString();
+      });
+    });
+  });
+  // Append sort params to pagination and period links
+  if(curSort!==null){
+    document.querySelectorAll('a[href]').forEach(function(a){
+      var h=a.getAttribute('href');
+      if(h&&h.indexOf('sort=')===-1&&h.indexOf('?')!==-1)a.setAttribute('href',h+'&sort='+curSort+'&order='+curOrder);
+    });
+  }
+})();
+(function(){
+  function exportCsv(table){
+    var name=table.getAttribute('data-export-name')||'cost_export';
+    var rows=Array.from(table.querySelectorAll('tr'));
+    var csv=rows.map(function(row){
+      return Array.from(row.querySelectorAll('th,td')).map(function(cell){
+        var text=(cell.textContent||'').replace(/"/g,'""');
+        return '"'+text+'"';
+      }).join(',');
+    }).join('\n');
+    var blob=new Blob([csv],{type:'text/csv;charset=utf-8;'});
+    var url=URL.createObjectURL(blob);
+    var a=document.createElement('a');
+    var ds=table.getAttribute('data-start')||'';
+    var de=table.getAttribute('data-end')||'';
+    var fname=name+(ds?'_'+ds:'')+(de?'_'+de:'')+'.csv';
+    a.href=url;a.download=fname;a.style.display='none';
+    document.body.appendChild(a);a.click();
+    document.body.removeChild(a);URL.revokeObjectURL(url);
+  }
+  document.querySelectorAll('table.data-table').forEach(function(table){
+    var btn=document.createElement('button');
+    btn.textContent='Export CSV';btn.className='export-csv-btn';
+    // Tables with a server-side export download every filtered row, not
+    // just the page shown.
+    var href=table.getAttribute('data-export-href');
+    btn.addEventListener('click',function(){
+      if(href)window.location.href=href;else exportCsv(table);
+    });
+    table.parentNode.insertBefore(btn,table);
+  });
+})();
+(function(){
+  // Elements with data-live take their data-live-field from the server-sent
+  // events at that URL.
+  var sources={};
+  document.querySelectorAll('[data-live]').forEach(function(el){
+    var src=el.getAttribute('data-live');
+    (sources[src]=sources[src]||[]).push(el);
+  });
+  Object.keys(sources).forEach(function(src){
+    new EventSource(src).onmessage=function(e){
+      var data=JSON.parse(e.data);
+      sources[src].forEach(function(el){
+        var value=data[el.getAttribute('data-live-field')];
+        if(value!=null)el.textContent=value;
+      });
+    };
+  });
+})();
@@ -0,0 +1,5 @@
+// Applies the theme picked with the toggle before the page is drawn.
+(function(){
+  var m=document.cookie.match(/(?:^|; )theme=(light|dark)/);
+  if(m)document.documentElement.setAttribute('data-theme',m[1]);
+})();
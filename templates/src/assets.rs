@@ -0,0 +1,103 @@
+//! The stylesheet and scripts every page loads. Each is served under a name
+//! carrying a hash of its content, so browsers can keep it for good and
+//! still pick up a new build's version at once.
+
+use std::sync::{LazyLock, OnceLock};
+
+pub struct Asset {
+    stem: &'static str,
+    extension: &'static str,
+    /// `{stem}.{hash}.{extension}`, e.g. `app.3f9c1d2a7b6e4f10.css`.
+    pub name: String,
+    pub content_type: &'static str,
+    pub body: &'static str,
+}
+
+impl Asset {
+    fn new(
+        stem: &'static str,
+        extension: &'static str,
+        content_type: &'static str,
+        body: &'static str,
+    ) -> Self {
+        Self {
+            stem,
+            extension,
+            name: format!("{stem}.{:016x}.{extension}", fnv1a(body)),
+            content_type,
+            body,
+        }
+    }
+}
+
+static ASSETS: LazyLock<[Asset; 3]> = LazyLock::new(|| {
+    [
+        Asset::new(
+            "app",
+            "css",
+            "text/css; charset=utf-8",
+            include_str!("../assets/app.css"),
+        ),
+        Asset::new(
+            "theme",
+            "js",
+            "text/javascript; charset=utf-8",
+            include_str!("../assets/theme.js"),
+        ),
+        Asset::new(
+            "app",
+            "js",
+            "text/javascript; charset=utf-8",
+            include_str!("../assets/app.js"),
+        ),
+    ]
+});
+
+static ASSETS_PATH: OnceLock<String> = OnceLock::new();
+
+/// Sets the path the assets are served under, e.g. `/_dashboard/assets`;
+/// pages link `/assets` until the first call. Later calls are ignored.
+pub fn set_assets_path(path: &str) {
+    let _ = ASSETS_PATH.set(path.trim_end_matches('/').to_string());
+}
+
+/// The asset served as `name`; names of other builds' versions are unknown.
+pub fn asset(name: &str) -> Option<&'static Asset> {
+    ASSETS.iter().find(|asset| asset.name == name)
+}
+
+/// Where pages load the asset with the given stem and extension from.
+pub(crate) fn url(stem: &str, extension: &str) -> String {
+    let asset = ASSETS
+        .iter()
+        .find(|asset| asset.stem == stem && asset.extension == extension)
+        .expect("every linked asset is built in");
+    let path = ASSETS_PATH.get().map_or("/assets", String::as_str);
+    format!("{path}/{}", asset.name)
+}
+
+/// A hash that stays the same across Rust releases, unlike `DefaultHasher`.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_carry_the_content_hash() {
+        let css = url("app", "css");
+        assert!(css.starts_with("/assets/app."));
+        assert!(css.ends_with(".css"));
+        let name = css.trim_start_matches("/assets/");
+        let asset = asset(name).unwrap();
+        assert_eq!(asset.content_type, "text/css; charset=utf-8");
+        assert!(asset.body.contains("prefers-color-scheme: dark"));
+        assert_ne!(url("app", "js"), url("theme", "js"));
+        assert!(asset("app.css").is_none());
+        assert_ne!(fnv1a("a {}"), fnv1a("b {}"));
+    }
+}
@@ -1,6 +1,12 @@
+use std::sync::RwLock;
+
 use leptos::either::Either;
 use leptos::prelude::*;
 
+mod assets;
+
+pub use assets::{asset, set_assets_path, Asset};
+
 pub fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
@@ -8,17 +14,19 @@ pub fn html_escape(s: &str) -> String {
         .replace('"', "&quot;")
 }
 
+/// Period keys accepted in `?period=`, with their labels.
+pub const PERIODS: [(&str, &str); 7] = [
+    ("7d", "Past 7 Days"),
+    ("30d", "Past 30 Days"),
+    ("month", "This Month"),
+    ("last_month", "Last Month"),
+    ("3m", "Last 3 Months"),
+    ("6m", "Last 6 Months"),
+    ("12m", "Last 12 Months"),
+];
+
 pub fn period_links(path: &str, active: &str) -> String {
-    let periods = [
-        ("7d", "Past 7 Days"),
-        ("30d", "Past 30 Days"),
-        ("month", "This Month"),
-        ("last_month", "Last Month"),
-        ("3m", "Last 3 Months"),
-        ("6m", "Last 6 Months"),
-        ("12m", "Last 12 Months"),
-    ];
-    let parts: Vec<String> = periods
+    let parts: Vec<String> = PERIODS
         .iter()
         .map(|(key, label)| {
             if *key == active {
@@ -92,106 +100,382 @@ pub fn collapsible_block(content: &str, css_class: &str) -> String {
     )
 }
 
+/// One day of a [`calendar_heatmap`].
+pub struct HeatmapCell {
+    /// Column, counted in weeks from the first cell.
+    pub week: usize,
+    /// Row, Monday = 0.
+    pub weekday: usize,
+    /// Spend relative to the busiest day, from 0.0 to 1.0.
+    pub intensity: f64,
+    /// Tooltip, e.g. date and amount.
+    pub title: String,
+    pub href: String,
+}
+
+const HEATMAP_CELL: usize = 14;
+const HEATMAP_GAP: usize = 3;
+const HEATMAP_LABEL_WIDTH: usize = 32;
+
+/// GitHub-style calendar as inline SVG: one column per week, one row per
+/// weekday, each day linked and shaded by its intensity.
+pub fn calendar_heatmap(cells: &[HeatmapCell]) -> String {
+    let step = HEATMAP_CELL + HEATMAP_GAP;
+    let weeks = cells.iter().map(|c| c.week + 1).max().unwrap_or(0);
+    let width = HEATMAP_LABEL_WIDTH + weeks * step;
+    let height = 7 * step;
+    let labels: String = [(0, "Mon"), (2, "Wed"), (4, "Fri")]
+        .iter()
+        .map(|(row, label)| {
+            format!(
+                r#"<text x="0" y="{}" class="heatmap-label">{}</text>"#,
+                row * step + HEATMAP_CELL - 3,
+                label
+            )
+        })
+        .collect();
+    let rects: String = cells
+        .iter()
+        .map(|c| {
+            let intensity = c.intensity.clamp(0.0, 1.0);
+            let fill = if intensity > 0.0 {
+                format!(
+                    r#"fill="var(--accent)" fill-opacity="{:.2}""#,
+                    0.15 + 0.85 * intensity
+                )
+            } else {
+                r#"fill="var(--border-light)""#.to_string()
+            };
+            format!(
+                r#"<a href="{}"><rect x="{}" y="{}" width="{size}" height="{size}" rx="2" {}><title>{}</title></rect></a>"#,
+                html_escape(&c.href),
+                HEATMAP_LABEL_WIDTH + c.week * step,
+                c.weekday * step,
+                fill,
+                html_escape(&c.title),
+                size = HEATMAP_CELL
+            )
+        })
+        .collect();
+    format!(
+        r#"<svg class="heatmap" width="{}" height="{}" viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">{}{}</svg>"#,
+        width, height, width, height, labels, rects
+    )
+}
+
+/// One bar of a [`stacked_bar_chart`].
+pub struct StackedBar {
+    /// Axis label, e.g. a date.
+    pub label: String,
+    pub href: String,
+    /// One value per series, in series order.
+    pub values: Vec<f64>,
+}
+
+/// Series colors, reused in order when there are more series.
+const CHART_PALETTE: [&str; 8] = [
+    "#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#9c755f",
+];
+const CHART_HEIGHT: usize = 160;
+const CHART_BAR_WIDTH: usize = 14;
+const CHART_GAP: usize = 4;
+
+/// A line of a [`line_chart`], or drawn over a [`stacked_bar_chart_with_line`]
+/// with one value per bar.
+pub struct ChartLine {
+    pub label: String,
+    pub values: Vec<f64>,
+}
+
+/// Inline SVG bar chart with one bar per entry, each split into its series
+/// values and scaled to the tallest bar, followed by a legend.
+pub fn stacked_bar_chart(series: &[String], bars: &[StackedBar]) -> String {
+    stacked_bar_chart_with_line(series, bars, None)
+}
+
+/// A [`stacked_bar_chart`] with `line`, if any, drawn through the bar
+/// centers on the same scale, e.g. a moving average.
+pub fn stacked_bar_chart_with_line(
+    series: &[String],
+    bars: &[StackedBar],
+    line: Option<&ChartLine>,
+) -> String {
+    let step = CHART_BAR_WIDTH + CHART_GAP;
+    let max = bars
+        .iter()
+        .map(|b| b.values.iter().sum::<f64>())
+        .chain(line.iter().flat_map(|l| l.values.iter().copied()))
+        .fold(0.0, f64::max);
+    let width = bars.len() * step;
+    let rects: String = bars
+        .iter()
+        .enumerate()
+        .map(|(i, bar)| {
+            let mut y = CHART_HEIGHT as f64;
+            let segments: String = bar
+                .values
+                .iter()
+                .enumerate()
+                .filter(|(_, v)| **v > 0.0)
+                .map(|(j, v)| {
+                    let height = if max > 0.0 {
+                        v / max * CHART_HEIGHT as f64
+                    } else {
+                        0.0
+                    };
+                    y -= height;
+                    format!(
+                        r#"<rect x="{}" y="{:.1}" width="{}" height="{:.1}" fill="{}"><title>{}: {:.2}</title></rect>"#,
+                        i * step,
+                        y,
+                        CHART_BAR_WIDTH,
+                        height,
+                        CHART_PALETTE[j % CHART_PALETTE.len()],
+                        html_escape(series.get(j).map(String::as_str).unwrap_or("")),
+                        v
+                    )
+                })
+                .collect();
+            format!(
+                r#"<a href="{}"><title>{}</title>{}</a>"#,
+                html_escape(&bar.href),
+                html_escape(&bar.label),
+                segments
+            )
+        })
+        .collect();
+    let legend: String = series
+        .iter()
+        .enumerate()
+        .map(|(j, name)| {
+            format!(
+                r#"<span class="chart-legend-item"><span class="chart-swatch" style="background: {}"></span>{}</span>"#,
+                CHART_PALETTE[j % CHART_PALETTE.len()],
+                html_escape(name)
+            )
+        })
+        .collect();
+    let (polyline, line_legend) = match line {
+        Some(line) => {
+            let points: Vec<String> = line
+                .values
+                .iter()
+                .enumerate()
+                .map(|(i, v)| {
+                    let y = if max > 0.0 {
+                        CHART_HEIGHT as f64 - v / max * CHART_HEIGHT as f64
+                    } else {
+                        CHART_HEIGHT as f64
+                    };
+                    format!("{},{:.1}", i * step + CHART_BAR_WIDTH / 2, y)
+                })
+                .collect();
+            (
+                format!(
+                    r#"<polyline class="chart-line" points="{}"><title>{}</title></polyline>"#,
+                    points.join(" "),
+                    html_escape(&line.label)
+                ),
+                format!(
+                    r#"<span class="chart-legend-item"><span class="chart-line-swatch"></span>{}</span>"#,
+                    html_escape(&line.label)
+                ),
+            )
+        }
+        None => (String::new(), String::new()),
+    };
+    format!(
+        r#"<svg class="chart" width="{}" height="{}" viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">{}{}</svg><div class="chart-legend">{}{}</div>"#,
+        width, CHART_HEIGHT, width, CHART_HEIGHT, rects, polyline, legend, line_legend
+    )
+}
+
+/// Inline SVG line chart with one point per label, one line per entry of
+/// `lines` scaled to the largest value, followed by a legend. A line may end
+/// before the last label, e.g. a month still in progress.
+pub fn line_chart(labels: &[String], lines: &[ChartLine]) -> String {
+    let step = CHART_BAR_WIDTH + CHART_GAP;
+    let max = lines
+        .iter()
+        .flat_map(|l| l.values.iter().take(labels.len()).copied())
+        .fold(0.0, f64::max);
+    let width = labels.len() * step;
+    let polylines: String = lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !line.values.is_empty())
+        .map(|(j, line)| {
+            let points: Vec<String> = line
+                .values
+                .iter()
+                .take(labels.len())
+                .enumerate()
+                .map(|(i, v)| {
+                    let y = if max > 0.0 {
+                        CHART_HEIGHT as f64 - v / max * CHART_HEIGHT as f64
+                    } else {
+                        CHART_HEIGHT as f64
+                    };
+                    format!("{},{:.1}", i * step + CHART_BAR_WIDTH / 2, y)
+                })
+                .collect();
+            format!(
+                r#"<polyline points="{}" fill="none" stroke="{}" stroke-width="2"><title>{}</title></polyline>"#,
+                points.join(" "),
+                CHART_PALETTE[j % CHART_PALETTE.len()],
+                html_escape(&line.label)
+            )
+        })
+        .collect();
+    let legend: String = lines
+        .iter()
+        .enumerate()
+        .map(|(j, line)| {
+            format!(
+                r#"<span class="chart-legend-item"><span class="chart-swatch" style="background: {}"></span>{}</span>"#,
+                CHART_PALETTE[j % CHART_PALETTE.len()],
+                html_escape(&line.label)
+            )
+        })
+        .collect();
+    format!(
+        r#"<svg class="chart" width="{}" height="{}" viewBox="0 0 {} {}" xmlns="http://www.w3.org/2000/svg">{}</svg><div class="chart-legend">{}</div>"#,
+        width, CHART_HEIGHT, width, CHART_HEIGHT, polylines, legend
+    )
+}
+
+/// Deployment branding applied to every page.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Theme {
+    /// CSS color for links and highlights, e.g. `#0a7d4f`.
+    pub accent_color: Option<String>,
+    /// Image shown above the breadcrumbs.
+    pub logo_url: Option<String>,
+    /// Stylesheet loaded after the built-in one, to override its rules.
+    pub custom_css: Option<String>,
+}
+
+static THEME: RwLock<Theme> = RwLock::new(Theme {
+    accent_color: None,
+    logo_url: None,
+    custom_css: None,
+});
+
+/// Sets the theme for all pages rendered afterwards; pages render with the
+/// default theme until the first call.
+pub fn set_theme(theme: Theme) {
+    *THEME.write().unwrap() = theme;
+}
+
+fn theme() -> Theme {
+    THEME.read().unwrap().clone()
+}
+
+/// Keeps a configured color from breaking out of its CSS declaration.
+fn css_color(value: &str) -> Option<&str> {
+    let safe = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || "#(),.% -".contains(c));
+    (safe && !value.trim().is_empty()).then_some(value)
+}
+
 pub fn page_layout(title: &str, body_html: String) -> String {
+    let theme = theme();
+    let accent = theme
+        .accent_color
+        .as_deref()
+        .and_then(css_color)
+        .map(|color| format!("<style>:root {{ --accent: {}; }}</style>\n", color))
+        .unwrap_or_default();
+    let custom_css = theme
+        .custom_css
+        .as_deref()
+        .map(|url| format!("<link rel=\"stylesheet\" href=\"{}\">\n", html_escape(url)))
+        .unwrap_or_default();
+    let logo = theme
+        .logo_url
+        .as_deref()
+        .map(|url| format!(r#"<img class="logo" src="{}" alt="">"#, html_escape(url)))
+        .unwrap_or_default();
     format!(
         r#"<!DOCTYPE html>
 <html>
 <head>
 <meta charset="utf-8">
 <title>{title}</title>
-<style>
-body {{ font-family: monospace; padding: 16px; }}
-table {{ width: 100%; border-collapse: collapse; }}
-th {{ text-align: left; padding: 6px 8px; border-bottom: 1px solid #ccc; }}
-table.data-table th {{ cursor: pointer; user-select: none; }}
-table.data-table th:after {{ content: ' \2195 '; color: #ccc; }}
-table.data-table th.sort-asc:after {{ content: ' \25B2 '; color: #333; }}
-table.data-table th.sort-desc:after {{ content: ' \25BC '; color: #333; }}
-td {{ padding: 6px 8px; border-bottom: 1px solid #eee; vertical-align: top; }}
-tr:last-child td {{ border-bottom: none; }}
-pre {{ white-space: pre-wrap; }}
-form {{ display: inline; }}
-details.collapsible {{ display: flex; flex-direction: column; }}
-details.collapsible > summary {{ cursor: pointer; list-style: none; order: 1; }}
-details.collapsible > summary::-webkit-details-marker {{ display: none; }}
-details.collapsible > summary .show-less {{ display: none; }}
-details.collapsible > .collapsible-full {{ white-space: pre-wrap; word-break: break-word; order: 0; }}
-details.collapsible[open] > summary .preview-text {{ display: none; }}
-details.collapsible[open] > summary .show-more {{ display: none; }}
-details.collapsible[open] > summary .show-less {{ display: inline; }}
-.hidden {{ display: none; }}
-.filtered-row {{ opacity: 0.45; }}
-.filtered-badge {{ color: #888; font-weight: bold; font-size: 0.85em; }}
-.export-csv-btn {{ margin-bottom: 8px; cursor: pointer; font-family: monospace; padding: 4px 12px; }}
-</style>
-</head>
+<script src="{theme_js}"></script>
+<link rel="stylesheet" href="{app_css}">
+{accent}{custom_css}</head>
 <body>
-{body_html}
-<script>
-(function(){{
-  var params=new URLSearchParams(window.location.search);
-  var curSort=params.get('sort');
-  var curOrder=params.get('order')||'asc';
-  // Mark sorted column header
-  document.querySelectorAll('table.data-table').forEach(function(table){{
-    var ths=table.querySelectorAll('tr:first-child th');
-    if(curSort!==null){{
-      var idx=parseInt(curSort,10);
-      if(ths[idx])ths[idx].classList.add(curOrder==='desc'?'sort-desc':'sort-asc');
-    }}
-    // Click handler: navigate with sort params
-    ths.forEach(function(th,i){{
-      th.addEventListener('click',function(){{
-        var p=new URLSearchParams(window.location.search);
-        var newOrder=(p.get('sort')===String(i)&&p.get('order')!=='desc')?'desc':'asc';
-        p.set('sort',i);p.set('order',newOrder);p.set('page','1');
-        window.location.search=p.toString();
-      }});
-    }});
-  }});
-  // Append sort params to pagination and period links
-  if(curSort!==null){{
-    document.querySelectorAll('a[href]').forEach(function(a){{
-      var h=a.getAttribute('href');
-      if(h&&h.indexOf('sort=')===-1&&h.indexOf('?')!==-1)a.setAttribute('href',h+'&sort='+curSort+'&order='+curOrder);
-    }});
-  }}
-}})();
-(function(){{
-  function exportCsv(table){{
-    var name=table.getAttribute('data-export-name')||'cost_export';
-    var rows=Array.from(table.querySelectorAll('tr'));
-    var csv=rows.map(function(row){{
-      return Array.from(row.querySelectorAll('th,td')).map(function(cell){{
-        var text=(cell.textContent||'').replace(/"/g,'""');
-        return '"'+text+'"';
-      }}).join(',');
-    }}).join('\n');
-    var blob=new Blob([csv],{{type:'text/csv;charset=utf-8;'}});
-    var url=URL.createObjectURL(blob);
-    var a=document.createElement('a');
-    var ds=table.getAttribute('data-start')||'';
-    var de=table.getAttribute('data-end')||'';
-    var fname=name+(ds?'_'+ds:'')+(de?'_'+de:'')+'.csv';
-    a.href=url;a.download=fname;a.style.display='none';
-    document.body.appendChild(a);a.click();
-    document.body.removeChild(a);URL.revokeObjectURL(url);
-  }}
-  document.querySelectorAll('table.data-table').forEach(function(table){{
-    var btn=document.createElement('button');
-    btn.textContent='Export CSV';btn.className='export-csv-btn';
-    btn.addEventListener('click',function(){{exportCsv(table);}});
-    table.parentNode.insertBefore(btn,table);
-  }});
-}})();
-</script>
+<button type="button" class="theme-toggle" id="theme-toggle">Theme</button>
+{logo}{body_html}
+<script src="{app_js}"></script>
 </body>
 </html>"#,
         title = html_escape(title),
+        theme_js = assets::url("theme", "js"),
+        app_css = assets::url("app", "css"),
+        app_js = assets::url("app", "js"),
+        accent = accent,
+        custom_css = custom_css,
+        logo = logo,
         body_html = body_html
     )
 }
 
+/// Adds the "Data updated" footer to a page rendered by [`page_layout`],
+/// and a warning banner at the top of its body when `stale` gives one.
+pub fn add_freshness(page: &str, updated: &str, stale: Option<&str>) -> String {
+    let mut page = page.to_string();
+    if let Some(end) = page.rfind("</body>") {
+        let footer = format!(
+            "<footer class=\"freshness\">Data updated: {}</footer>\n",
+            html_escape(updated)
+        );
+        page.insert_str(end, &footer);
+    }
+    if let (Some(warning), Some(start)) = (stale, page.find("<body>\n")) {
+        let banner = format!(
+            "<div class=\"stale-banner\" role=\"alert\">{}</div>\n",
+            html_escape(warning)
+        );
+        page.insert_str(start + "<body>\n".len(), &banner);
+    }
+    page
+}
+
+/// Adds links to the home page of every gateway the deployment serves, as
+/// `(tenant, href)` pairs, to the top of a page rendered by
+/// [`page_layout`]; the `current` one is shown in bold.
+pub fn add_tenant_switcher(page: &str, tenants: &[(String, String)], current: &str) -> String {
+    let Some(start) = page.find("<body>\n") else {
+        return page.to_string();
+    };
+    let links: Vec<String> = tenants
+        .iter()
+        .map(|(tenant, href)| {
+            if tenant == current {
+                format!("<b>{}</b>", html_escape(tenant))
+            } else {
+                format!(
+                    "<a href=\"{}\">{}</a>",
+                    html_escape(href),
+                    html_escape(tenant)
+                )
+            }
+        })
+        .collect();
+    let mut page = page.to_string();
+    page.insert_str(
+        start + "<body>\n".len(),
+        &format!(
+            "<nav class=\"tenant-switcher\">Gateway: {}</nav>\n",
+            links.join(" | ")
+        ),
+    );
+    page
+}
+
 pub struct Breadcrumb {
     pub label: String,
     pub href: Option<String>,
@@ -255,6 +539,15 @@ impl InfoRow {
     }
 }
 
+/// Where cost figures came from, e.g. `aws` or `manual`.
+pub struct SourceFilter {
+    /// Target of the form, which stores the choice and returns to the page.
+    pub action: String,
+    pub sources: Vec<String>,
+    /// `None` for all sources.
+    pub current: Option<String>,
+}
+
 pub struct Subpage {
     pub label: String,
     pub href: String,
@@ -271,9 +564,29 @@ impl Subpage {
     }
 }
 
+/// Splits a GET form's action into its path and query pairs: submitting
+/// the form replaces the action's query, so the pairs go in hidden fields.
+fn form_query(action: &str) -> (String, Vec<(String, String)>) {
+    let Some((path, query)) = action.split_once('?') else {
+        return (action.to_string(), Vec::new());
+    };
+    let fields = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    (path.to_string(), fields)
+}
+
 pub struct Page<C: IntoView = ()> {
     pub title: String,
     pub breadcrumbs: Vec<Breadcrumb>,
+    /// Action URL of the header search box; no box when `None`.
+    pub search: Option<String>,
+    /// Header control narrowing cost figures to one source.
+    pub source_filter: Option<SourceFilter>,
+    /// Shown as a banner under the breadcrumbs when an upstream call failed.
+    pub error: Option<String>,
     pub nav_links: Vec<NavLink>,
     pub info_rows: Vec<InfoRow>,
     pub content: C,
@@ -285,6 +598,9 @@ impl Default for Page {
         Page {
             title: String::new(),
             breadcrumbs: Vec::new(),
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: Vec::new(),
             info_rows: Vec::new(),
             content: (),
@@ -298,6 +614,9 @@ impl<C: IntoView> Page<C> {
         let Page {
             title,
             breadcrumbs,
+            search,
+            source_filter,
+            error,
             nav_links,
             info_rows,
             content,
@@ -305,6 +624,32 @@ impl<C: IntoView> Page<C> {
         } = self;
 
         let body = view! {
+            {search.map(|action| {
+                let (action, fields) = form_query(&action);
+                view! {
+                <form class="search" method="get" action={action}>
+                    {fields.into_iter().map(|(name, value)| view! {
+                        <input type="hidden" name={name} value={value}/>
+                    }).collect::<Vec<_>>()}
+                    <input type="search" name="q" placeholder="User or model"/>
+                    <button type="submit">"Search"</button>
+                </form>
+                }
+            })}
+
+            {source_filter.map(|SourceFilter { action, sources, current }| view! {
+                <form class="search" method="get" action={action}>
+                    <select name="source">
+                        <option value="" selected={current.is_none()}>"All sources"</option>
+                        {sources.into_iter().map(|source| {
+                            let selected = current.as_deref() == Some(source.as_str());
+                            view! { <option value={source.clone()} selected={selected}>{source}</option> }
+                        }).collect::<Vec<_>>()}
+                    </select>
+                    <button type="submit">"Filter"</button>
+                </form>
+            })}
+
             {if !breadcrumbs.is_empty() {
                 Either::Left(view! {
                     <h1>
@@ -325,6 +670,13 @@ impl<C: IntoView> Page<C> {
                 Either::Right(())
             }}
 
+            {error.map(|message| view! {
+                <div class="error-banner">
+                    <h2>"Error"</h2>
+                    <p>{message}</p>
+                </div>
+            })}
+
             {if !nav_links.is_empty() {
                 Either::Left(view! {
                     <h2>"Navigation"</h2>
@@ -422,6 +774,193 @@ line2</pre>"#
         assert!(result.starts_with("<!DOCTYPE html>"));
     }
 
+    #[test]
+    fn calendar_heatmap_places_and_shades_cells() {
+        let cells = vec![
+            HeatmapCell {
+                week: 0,
+                weekday: 2,
+                intensity: 0.0,
+                title: "2024-01-03: 0.00 USD".to_string(),
+                href: "/costs/daily/2024-01-03".to_string(),
+            },
+            HeatmapCell {
+                week: 1,
+                weekday: 0,
+                intensity: 1.0,
+                title: "2024-01-08: 12.00 USD".to_string(),
+                href: "/costs/daily/2024-01-08?period=7d&from=/x".to_string(),
+            },
+        ];
+        let svg = calendar_heatmap(&cells);
+        assert!(svg.starts_with("<svg class=\"heatmap\""));
+        assert!(svg.contains(r#"width="66""#));
+        assert!(svg.contains(r#"<rect x="32" y="34""#));
+        assert!(svg.contains(r#"fill="var(--border-light)""#));
+        assert!(svg.contains(r#"fill-opacity="1.00""#));
+        assert!(svg.contains(r#"href="/costs/daily/2024-01-08?period=7d&amp;from=/x""#));
+        assert!(svg.contains("<title>2024-01-08: 12.00 USD</title>"));
+    }
+
+    #[test]
+    fn stacked_bar_chart_stacks_series() {
+        let series = vec!["sonnet".to_string(), "<haiku>".to_string()];
+        let bars = vec![
+            StackedBar {
+                label: "2024-01-01".to_string(),
+                href: "/costs/daily/2024-01-01".to_string(),
+                values: vec![1.0, 1.0],
+            },
+            StackedBar {
+                label: "2024-01-02".to_string(),
+                href: "/costs/daily/2024-01-02".to_string(),
+                values: vec![4.0, 0.0],
+            },
+        ];
+        let html = stacked_bar_chart(&series, &bars);
+        assert!(html.starts_with("<svg class=\"chart\" width=\"36\""));
+        assert!(html.contains(r##"<rect x="0" y="120.0" width="14" height="40.0" fill="#4e79a7">"##));
+        assert!(html.contains(r##"<rect x="0" y="80.0" width="14" height="40.0" fill="#f28e2b">"##));
+        assert!(html.contains(r##"<rect x="18" y="0.0" width="14" height="160.0" fill="#4e79a7">"##));
+        assert_eq!(html.matches("<rect").count(), 3);
+        assert!(html.contains("&lt;haiku&gt;"));
+    }
+
+    #[test]
+    fn stacked_bar_chart_with_line_overlays_line() {
+        let bars = vec![
+            StackedBar {
+                label: "2024-01-01".to_string(),
+                href: "/costs/daily/2024-01-01".to_string(),
+                values: vec![2.0],
+            },
+            StackedBar {
+                label: "2024-01-02".to_string(),
+                href: "/costs/daily/2024-01-02".to_string(),
+                values: vec![4.0],
+            },
+        ];
+        let line = ChartLine {
+            label: "7-day average".to_string(),
+            values: vec![2.0, 3.0],
+        };
+        let html = stacked_bar_chart_with_line(&["Cost".to_string()], &bars, Some(&line));
+        assert!(html.contains(r#"<polyline class="chart-line" points="7,80.0 25,40.0">"#));
+        assert!(html.contains("chart-line-swatch"));
+        assert!(html.contains("7-day average"));
+        assert!(!stacked_bar_chart(&["Cost".to_string()], &bars).contains("polyline"));
+    }
+
+    #[test]
+    fn line_chart_draws_one_polyline_per_line() {
+        let labels: Vec<String> = (1..=3).map(|d| d.to_string()).collect();
+        let lines = vec![
+            ChartLine {
+                label: "This month".to_string(),
+                values: vec![1.0, 2.0],
+            },
+            ChartLine {
+                label: "<Last> month".to_string(),
+                values: vec![1.0, 3.0, 4.0, 5.0],
+            },
+            ChartLine {
+                label: "Empty".to_string(),
+                values: vec![],
+            },
+        ];
+        let html = line_chart(&labels, &lines);
+        assert!(html.starts_with("<svg class=\"chart\" width=\"54\""));
+        assert!(html.contains(r##"<polyline points="7,120.0 25,80.0" fill="none" stroke="#4e79a7""##));
+        assert!(html.contains(r##"points="7,120.0 25,40.0 43,0.0" fill="none" stroke="#f28e2b""##));
+        assert_eq!(html.matches("<polyline").count(), 2);
+        assert!(html.contains("&lt;Last&gt; month"));
+    }
+
+    /// The body of the asset `page` links at `url`.
+    fn linked_asset(page: &str, url: &str) -> &'static str {
+        assert!(page.contains(&format!(r#""{url}""#)));
+        asset(url.trim_start_matches("/assets/")).unwrap().body
+    }
+
+    #[test]
+    fn page_layout_has_theme_toggle() {
+        let result = page_layout("Test", String::new());
+        let css = linked_asset(&result, &assets::url("app", "css"));
+        assert!(css.contains("prefers-color-scheme: dark"));
+        let script = linked_asset(&result, &assets::url("theme", "js"));
+        assert!(script.contains("data-theme"));
+        assert!(result.contains(r#"id="theme-toggle""#));
+    }
+
+    #[test]
+    fn page_layout_follows_live_values() {
+        let result = page_layout("Test", String::new());
+        let script = linked_asset(&result, &assets::url("app", "js"));
+        assert!(script.contains("querySelectorAll('[data-live]')"));
+        assert!(script.contains("new EventSource(src)"));
+    }
+
+    #[test]
+    fn page_layout_has_no_inline_scripts() {
+        let result = page_layout("Test", String::new());
+        assert_eq!(result.matches("<script").count(), 2);
+        assert_eq!(result.matches("<script src=").count(), 2);
+    }
+
+    #[test]
+    fn page_layout_applies_configured_theme() {
+        set_theme(Theme {
+            accent_color: Some("#0a7d4f".to_string()),
+            logo_url: Some("/static/logo.svg".to_string()),
+            custom_css: Some("/static/site.css".to_string()),
+        });
+        let result = page_layout("Test", String::new());
+        assert!(result.contains(":root { --accent: #0a7d4f; }"));
+        assert!(result.contains(r#"<img class="logo" src="/static/logo.svg" alt="">"#));
+        let custom = result.find(r#"<link rel="stylesheet" href="/static/site.css">"#);
+        let built_in = result.find(&assets::url("app", "css"));
+        assert!(custom.unwrap() > built_in.unwrap());
+    }
+
+    #[test]
+    fn css_color_rejects_injection() {
+        assert_eq!(css_color("rgb(10, 125, 79)"), Some("rgb(10, 125, 79)"));
+        assert_eq!(css_color("red; } body { display: none"), None);
+        assert_eq!(css_color("</style>"), None);
+    }
+
+    #[test]
+    fn add_freshness_adds_footer_and_banner() {
+        let page = page_layout("Test", "<p>body</p>".to_string());
+        let html = add_freshness(&page, "2025-03-11 06:00 UTC", None);
+        assert!(html.contains(
+            "<footer class=\"freshness\">Data updated: 2025-03-11 06:00 UTC</footer>\n</body>"
+        ));
+        assert!(!html.contains("stale-banner"));
+
+        let html = add_freshness(&page, "never", Some("Cost data is <old>."));
+        let banner = html
+            .find("<div class=\"stale-banner\" role=\"alert\">Cost data is &lt;old&gt;.</div>")
+            .unwrap();
+        assert!(banner < html.find("<p>body</p>").unwrap());
+    }
+
+    #[test]
+    fn add_tenant_switcher_links_other_tenants() {
+        let page = page_layout("Test", "<p>body</p>".to_string());
+        let tenants = [
+            ("default".to_string(), "/".to_string()),
+            ("staging".to_string(), "/t/staging/".to_string()),
+        ];
+        let html = add_tenant_switcher(&page, &tenants, "default");
+        let nav = html
+            .find(r#"<nav class="tenant-switcher">Gateway: <b>default</b> | <a href="/t/staging/">staging</a></nav>"#)
+            .unwrap();
+        assert!(nav < html.find("<p>body</p>").unwrap());
+        let html = add_tenant_switcher(&page, &tenants, "staging");
+        assert!(html.contains(r#"<a href="/">default</a> | <b>staging</b>"#));
+    }
+
     #[test]
     fn page_layout_escapes_title() {
         let result = page_layout("<script>", "".to_string());
@@ -458,6 +997,9 @@ line2</pre>"#
                 Breadcrumb::link("Home", "/"),
                 Breadcrumb::current("Current"),
             ],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![],
             content: (),
@@ -477,6 +1019,9 @@ line2</pre>"#
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![NavLink::new("Edit", "/edit"), NavLink::back()],
             info_rows: vec![],
             content: (),
@@ -495,6 +1040,9 @@ line2</pre>"#
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![InfoRow::new("Key", "<value>")],
             content: (),
@@ -512,6 +1060,9 @@ line2</pre>"#
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![InfoRow::raw("Key", "<b>bold</b>")],
             content: (),
@@ -526,6 +1077,9 @@ line2</pre>"#
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![],
             content: view! { <form><input type="text" name="x"/></form> },
@@ -541,6 +1095,9 @@ line2</pre>"#
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![],
             content: (),
@@ -555,11 +1112,86 @@ line2</pre>"#
         assert!(html.contains("42"));
     }
 
+    #[test]
+    fn page_render_source_filter() {
+        let html = Page {
+            source_filter: Some(SourceFilter {
+                action: "/source".to_string(),
+                sources: vec!["aws".to_string(), "openai".to_string()],
+                current: Some("openai".to_string()),
+            }),
+            ..Page::default()
+        }
+        .render();
+        assert!(html.contains(r#"action="/source""#));
+        assert!(html.contains(r#"<select name="source">"#));
+        assert!(html.contains(r#"<option value="aws">aws</option>"#));
+        assert!(html.contains(r#"<option value="openai" selected>openai</option>"#));
+        assert!(html.contains("All sources"));
+    }
+
+    #[test]
+    fn page_render_error_banner() {
+        let html = Page {
+            title: "Test".to_string(),
+            breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: Some("cost database: <timeout>".to_string()),
+            nav_links: vec![],
+            info_rows: vec![],
+            content: (),
+            subpages: vec![],
+        }
+        .render();
+        assert!(html.contains(r#"<div class="error-banner">"#));
+        assert!(html.contains("cost database: &lt;timeout&gt;"));
+    }
+
+    #[test]
+    fn page_render_search_box() {
+        let html = Page {
+            title: "Test".to_string(),
+            breadcrumbs: vec![],
+            search: Some("/_dashboard/search".to_string()),
+            source_filter: None,
+            error: None,
+            nav_links: vec![],
+            info_rows: vec![],
+            content: (),
+            subpages: vec![],
+        }
+        .render();
+        assert!(html.contains(r#"action="/_dashboard/search""#));
+        assert!(html.contains(r#"name="q""#));
+    }
+
+    #[test]
+    fn page_render_search_box_keeps_query() {
+        let html = Page {
+            title: "Test".to_string(),
+            breadcrumbs: vec![],
+            search: Some("/search?period=7d".to_string()),
+            source_filter: None,
+            error: None,
+            nav_links: vec![],
+            info_rows: vec![],
+            content: (),
+            subpages: vec![],
+        }
+        .render();
+        assert!(html.contains(r#"action="/search""#));
+        assert!(html.contains(r#"type="hidden" name="period" value="7d""#));
+    }
+
     #[test]
     fn page_render_empty_sections_omitted() {
         let html = Page {
             title: "Test".to_string(),
             breadcrumbs: vec![],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![],
             info_rows: vec![],
             content: (),
@@ -570,6 +1202,7 @@ line2</pre>"#
         assert!(!html.contains("Navigation"));
         assert!(!html.contains("Info"));
         assert!(!html.contains("Subpages"));
+        assert!(!html.contains("error-banner"));
     }
 
     #[test]
@@ -577,6 +1210,9 @@ line2</pre>"#
         let html = Page {
             title: "Full Page".to_string(),
             breadcrumbs: vec![Breadcrumb::link("Home", "/"), Breadcrumb::current("Detail")],
+            search: None,
+            source_filter: None,
+            error: None,
             nav_links: vec![NavLink::back()],
             info_rows: vec![InfoRow::new("Name", "test")],
             content: view! { <p>"content"</p> },
@@ -0,0 +1,213 @@
+//! Dashboard state and key handling, apart from drawing.
+
+use chrono::{Duration, NaiveDate};
+use common::{CostByModel, CostByUser, CostRecord, Metric};
+use ratatui::crossterm::event::KeyCode;
+use ratatui::widgets::TableState;
+
+/// Lengths in days that `[` and `]` step through.
+pub const PERIODS: [i64; 5] = [7, 14, 30, 90, 365];
+
+/// What the dashboard shows for one period and metric.
+#[derive(Debug, Clone, Default)]
+pub struct Dashboard {
+    pub daily: Vec<CostRecord>,
+    /// Highest first, with emails filled in where known.
+    pub users: Vec<CostByUser>,
+    /// Highest first, with names filled in where known.
+    pub models: Vec<CostByModel>,
+}
+
+/// The table that takes the arrow keys.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pane {
+    Users,
+    Models,
+}
+
+/// What the event loop does after a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    None,
+    Reload,
+    Quit,
+}
+
+pub struct App {
+    /// Days shown, ending today.
+    pub days: i64,
+    pub metric: Metric,
+    pub focus: Pane,
+    pub users: TableState,
+    pub models: TableState,
+    pub dashboard: Dashboard,
+    /// Why the last load failed; the previous figures stay on screen.
+    pub error: Option<String>,
+    pub loading: bool,
+}
+
+impl App {
+    pub fn new(days: i64, metric: Metric) -> Self {
+        Self {
+            days: days.max(1),
+            metric,
+            focus: Pane::Users,
+            users: TableState::default(),
+            models: TableState::default(),
+            dashboard: Dashboard::default(),
+            error: None,
+            loading: false,
+        }
+    }
+
+    /// `[start, end)` of the last `days` days up to and including `today`.
+    pub fn range(&self, today: NaiveDate) -> (NaiveDate, NaiveDate) {
+        let end = today + Duration::days(1);
+        (end - Duration::days(self.days), end)
+    }
+
+    /// Shows `dashboard`, keeping the selections where they still fit.
+    pub fn set_dashboard(&mut self, dashboard: Dashboard) {
+        clamp(&mut self.users, dashboard.users.len());
+        clamp(&mut self.models, dashboard.models.len());
+        self.dashboard = dashboard;
+        self.error = None;
+    }
+
+    pub fn handle_key(&mut self, code: KeyCode) -> Action {
+        match code {
+            KeyCode::Char('q') | KeyCode::Esc => return Action::Quit,
+            KeyCode::Char('r') => return Action::Reload,
+            KeyCode::Tab | KeyCode::BackTab | KeyCode::Left | KeyCode::Right => {
+                self.focus = match self.focus {
+                    Pane::Users => Pane::Models,
+                    Pane::Models => Pane::Users,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => self.step(1),
+            KeyCode::Up | KeyCode::Char('k') => self.step(-1),
+            KeyCode::Home | KeyCode::Char('g') => self.step(isize::MIN),
+            KeyCode::End | KeyCode::Char('G') => self.step(isize::MAX),
+            KeyCode::Char('[') => {
+                return self.set_days(PERIODS.iter().rev().find(|&&d| d < self.days))
+            }
+            KeyCode::Char(']') => return self.set_days(PERIODS.iter().find(|&&d| d > self.days)),
+            KeyCode::Char('m') => {
+                let next = Metric::ALL
+                    .iter()
+                    .position(|&m| m == self.metric)
+                    .map_or(0, |i| (i + 1) % Metric::ALL.len());
+                self.metric = Metric::ALL[next];
+                return Action::Reload;
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn set_days(&mut self, days: Option<&i64>) -> Action {
+        match days {
+            Some(&days) => {
+                self.days = days;
+                Action::Reload
+            }
+            None => Action::None,
+        }
+    }
+
+    /// Moves the focused selection by `by` rows, stopping at either end.
+    fn step(&mut self, by: isize) {
+        let (state, len) = match self.focus {
+            Pane::Users => (&mut self.users, self.dashboard.users.len()),
+            Pane::Models => (&mut self.models, self.dashboard.models.len()),
+        };
+        if len == 0 {
+            return;
+        }
+        let selected = match state.selected() {
+            Some(i) => i.saturating_add_signed(by).min(len - 1),
+            None if by < 0 => len - 1,
+            None => 0,
+        };
+        state.select(Some(selected));
+    }
+}
+
+/// Drops a selection past the last of `len` rows back onto it.
+fn clamp(state: &mut TableState, len: usize) {
+    match (state.selected(), len) {
+        (Some(_), 0) => state.select(None),
+        (Some(i), len) if i >= len => state.select(Some(len - 1)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::Amount;
+
+    fn user(id: &str) -> CostByUser {
+        CostByUser {
+            user_id: id.to_string(),
+            user_email: None,
+            amount: Amount::from_micros(1),
+            currency: "USD".to_string(),
+        }
+    }
+
+    #[test]
+    fn range_ends_after_today() {
+        let app = App::new(7, Metric::Blended);
+        let today = NaiveDate::from_ymd_opt(2025, 3, 10).unwrap();
+        assert_eq!(
+            app.range(today),
+            (
+                NaiveDate::from_ymd_opt(2025, 3, 4).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 3, 11).unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn arrows_move_the_focused_selection() {
+        let mut app = App::new(30, Metric::Blended);
+        app.set_dashboard(Dashboard {
+            users: vec![user("a"), user("b"), user("c")],
+            ..Dashboard::default()
+        });
+        assert_eq!(app.handle_key(KeyCode::Down), Action::None);
+        assert_eq!(app.users.selected(), Some(0));
+        app.handle_key(KeyCode::Char('G'));
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.users.selected(), Some(2));
+        app.handle_key(KeyCode::Tab);
+        app.handle_key(KeyCode::Down);
+        assert_eq!(app.focus, Pane::Models);
+        assert_eq!(app.models.selected(), None);
+        app.handle_key(KeyCode::Tab);
+        app.handle_key(KeyCode::Char('g'));
+        assert_eq!(app.users.selected(), Some(0));
+
+        app.handle_key(KeyCode::End);
+        app.set_dashboard(Dashboard {
+            users: vec![user("a")],
+            ..Dashboard::default()
+        });
+        assert_eq!(app.users.selected(), Some(0));
+    }
+
+    #[test]
+    fn period_and_metric_keys_reload() {
+        let mut app = App::new(30, Metric::NetUnblended);
+        assert_eq!(app.handle_key(KeyCode::Char(']')), Action::Reload);
+        assert_eq!(app.days, 90);
+        assert_eq!(app.handle_key(KeyCode::Char('[')), Action::Reload);
+        assert_eq!(app.days, 30);
+        app.days = 7;
+        assert_eq!(app.handle_key(KeyCode::Char('[')), Action::None);
+        assert_eq!(app.handle_key(KeyCode::Char('m')), Action::Reload);
+        assert_eq!(app.metric, Metric::Blended);
+        assert_eq!(app.handle_key(KeyCode::Char('q')), Action::Quit);
+    }
+}
@@ -0,0 +1,197 @@
+//! Draws the dashboard: the daily trend over the top users and top models.
+
+use common::Amount;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Cell, Row, Sparkline, Table, TableState};
+use ratatui::Frame;
+
+use crate::app::{App, Pane};
+
+const HELP: &str = "q quit  tab switch table  ↑↓ select  [ ] period  m metric  r reload";
+
+pub fn draw(frame: &mut Frame, app: &mut App) {
+    let [header, trend, tables, footer] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(10),
+        Constraint::Min(5),
+        Constraint::Length(1),
+    ])
+    .areas(frame.area());
+    let [users, models] =
+        Layout::horizontal([Constraint::Percentage(50), Constraint::Percentage(50)]).areas(tables);
+
+    frame.render_widget(header_line(app), header);
+    draw_trend(frame, app, trend);
+    let rows: Vec<_> = app
+        .dashboard
+        .users
+        .iter()
+        .map(|c| (c.user_email.as_deref().unwrap_or(&c.user_id), c.amount))
+        .collect();
+    draw_table(
+        frame,
+        "Top Users",
+        &rows,
+        app.focus == Pane::Users,
+        &mut app.users,
+        users,
+    );
+    let rows: Vec<_> = app
+        .dashboard
+        .models
+        .iter()
+        .map(|c| (c.model_name.as_deref().unwrap_or(&c.model_id), c.amount))
+        .collect();
+    draw_table(
+        frame,
+        "Top Models",
+        &rows,
+        app.focus == Pane::Models,
+        &mut app.models,
+        models,
+    );
+    frame.render_widget(Line::from(HELP).dim(), footer);
+}
+
+fn header_line(app: &App) -> Line<'static> {
+    let total: Amount = app.dashboard.daily.iter().map(|c| c.amount).sum();
+    let mut line = Line::from(format!(
+        "Cost Explorer - last {} days, {} - total {:.2} {}",
+        app.days,
+        app.metric.label(),
+        total,
+        currency(app)
+    ))
+    .bold();
+    if app.loading {
+        line.push_span(" (loading)");
+    }
+    if let Some(error) = &app.error {
+        line.push_span(format!("  {error}").red());
+    }
+    line
+}
+
+fn currency(app: &App) -> &str {
+    app.dashboard
+        .daily
+        .first()
+        .map_or("USD", |c| c.currency.as_str())
+}
+
+fn draw_trend(frame: &mut Frame, app: &App, area: Rect) {
+    let daily = &app.dashboard.daily;
+    let cents: Vec<u64> = daily
+        .iter()
+        .map(|c| (c.amount.micros() / 10_000).max(0) as u64)
+        .collect();
+    let title = match daily.iter().max_by_key(|c| c.amount) {
+        Some(peak) => format!(" Daily Trend - peak {:.2} on {} ", peak.amount, peak.date),
+        None => " Daily Trend - no costs ".to_string(),
+    };
+    let sparkline = Sparkline::default()
+        .block(Block::bordered().title(title))
+        .data(&cents)
+        .cyan();
+    frame.render_widget(sparkline, area);
+}
+
+fn draw_table(
+    frame: &mut Frame,
+    title: &str,
+    rows: &[(&str, Amount)],
+    focused: bool,
+    state: &mut TableState,
+    area: Rect,
+) {
+    let rows = rows.iter().map(|(name, amount)| {
+        Row::new([
+            Cell::from(name.to_string()),
+            Cell::from(Line::from(format!("{amount:.2}")).right_aligned()),
+        ])
+    });
+    let border = if focused {
+        Style::new().cyan()
+    } else {
+        Style::new()
+    };
+    let table = Table::new(rows, [Constraint::Min(10), Constraint::Length(12)])
+        .header(
+            Row::new([
+                Cell::from("Name"),
+                Cell::from(Line::from("Amount").right_aligned()),
+            ])
+            .bold(),
+        )
+        .block(
+            Block::bordered()
+                .title(format!(" {title} "))
+                .border_style(border),
+        )
+        .row_highlight_style(Style::new().reversed());
+    frame.render_stateful_widget(table, area, state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::Dashboard;
+    use common::{CostByUser, CostRecord, Metric};
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn screen(app: &mut App) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(80, 20)).unwrap();
+        terminal.draw(|frame| draw(frame, app)).unwrap();
+        let buffer = terminal.backend().buffer();
+        buffer
+            .content()
+            .chunks(buffer.area.width as usize)
+            .map(|line| line.iter().map(|cell| cell.symbol()).collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn draws_totals_and_tables() {
+        let mut app = App::new(30, Metric::Unblended);
+        app.set_dashboard(Dashboard {
+            daily: vec![
+                CostRecord {
+                    date: "2025-03-01".to_string(),
+                    amount: Amount::from_micros(1_500_000),
+                    currency: "USD".to_string(),
+                },
+                CostRecord {
+                    date: "2025-03-02".to_string(),
+                    amount: Amount::from_micros(2_250_000),
+                    currency: "USD".to_string(),
+                },
+            ],
+            users: vec![CostByUser {
+                user_id: "u1".to_string(),
+                user_email: Some("alice@example.com".to_string()),
+                amount: Amount::from_micros(3_750_000),
+                currency: "USD".to_string(),
+            }],
+            models: vec![],
+        });
+        let screen = screen(&mut app);
+        assert!(screen.contains("last 30 days, Unblended - total 3.75 USD"));
+        assert!(screen.contains("peak 2.25 on 2025-03-02"));
+        assert!(screen.contains("alice@example.com"));
+        assert!(screen.contains("3.75│"));
+        assert!(screen.contains("Top Models"));
+    }
+
+    #[test]
+    fn shows_load_errors() {
+        let mut app = App::new(7, Metric::Blended);
+        app.error = Some("connection refused".to_string());
+        let screen = screen(&mut app);
+        assert!(screen.contains("no costs"));
+        assert!(screen.contains("connection refused"));
+    }
+}